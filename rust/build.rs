@@ -0,0 +1,24 @@
+//! Generates the C header for the `capi` feature into `OUT_DIR`. The copy in
+//! `include/` is only rewritten by `make header`; a build warns when it is
+//! out of date.
+
+fn main() {
+    #[cfg(feature = "capi")]
+    {
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed=include/livelybot_motor_control.h");
+        let config = cbindgen::Config::from_file("cbindgen.toml").expect("cbindgen.toml");
+        let out = std::path::Path::new(&std::env::var("OUT_DIR").expect("OUT_DIR")).join("livelybot_motor_control.h");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src("src/capi.rs")
+            .generate()
+            .expect("generating the C header")
+            .write_to_file(&out);
+        let generated = std::fs::read(&out).expect("reading the generated header");
+        if std::fs::read("include/livelybot_motor_control.h").ok() != Some(generated) {
+            println!("cargo:warning=include/livelybot_motor_control.h is out of date, run `make header`");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Bus selection shared by the examples
+//!
+//! Every example runs against simulated motors ([`MockTransport`]) unless it
+//! is given `--hardware CHANNEL`, in which case it drives the real motors on
+//! that CAN interface.
+
+// Each example uses a different part of this module
+#![allow(dead_code)]
+
+use anyhow::Result;
+use livelybot_motor_control::{LivelyMotorController, MockTransport, SimMotor};
+
+pub struct Bus {
+    pub controller: LivelyMotorController,
+    /// The simulated motors, `None` on hardware
+    pub sim: Option<MockTransport>,
+}
+
+/// CAN channel of `--hardware CHANNEL` (or `--hardware=CHANNEL`), if given
+fn hardware_channel() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(channel) = arg.strip_prefix("--hardware=") {
+            return Some(channel.to_string());
+        }
+        if arg == "--hardware" {
+            return Some(args.next().unwrap_or_else(|| "can0".to_string()));
+        }
+    }
+    None
+}
+
+/// Open the hardware bus, or a simulated one with a motor on each of `motor_ids`
+pub fn open(motor_ids: &[u8]) -> Result<Bus> {
+    match hardware_channel() {
+        Some(channel) => {
+            println!("hardware bus {}, motors {:?}", channel, motor_ids);
+            Ok(Bus {
+                controller: LivelyMotorController::new(&channel, 1_000_000)?,
+                sim: None,
+            })
+        }
+        None => {
+            println!("simulated motors {:?} (pass --hardware CHANNEL for a real bus)", motor_ids);
+            let sim = motor_ids
+                .iter()
+                .fold(MockTransport::new(), |sim, &id| sim.with_motor(id, SimMotor::default()));
+            Ok(Bus {
+                controller: LivelyMotorController::with_transport("sim", sim.clone()),
+                sim: Some(sim),
+            })
+        }
+    }
+}
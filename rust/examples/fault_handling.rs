@@ -0,0 +1,81 @@
+//! React to motor faults with a fault policy
+//!
+//! Streams a slow sine to motors 1 and 2 while polling their fault
+//! registers. A fault is classified ([`FaultClass::from_code`]) and answered
+//! by a [`SafetyMonitor`] following a [`FaultPolicy`]: over-temperature only
+//! warns, everything else brings the motors to rest over 50 ms and disables
+//! them. In simulation, motor 2 reports a limit fault (code 39) after one
+//! second; on hardware the loop runs until a real fault or Ctrl+C.
+//!
+//! ```bash
+//! cargo run --example fault_handling
+//! cargo run --example fault_handling -- --hardware can0
+//! ```
+
+mod common;
+
+use anyhow::{Result, ensure};
+use livelybot_motor_control::cli::StopFlags;
+use livelybot_motor_control::prelude::*;
+use livelybot_motor_control::{ControlLoop, FaultAction, FaultClass, FaultPolicy, SafetyMonitor};
+use std::time::Duration;
+
+const MOTOR_IDS: [u8; 2] = [1, 2];
+const POLICY: &str = r#"{
+    "default": { "over_temperature": "warn" },
+    "stop_mode": { "mode": "ramped", "ramp_ms": 50 }
+}"#;
+const INJECT_AT: Duration = Duration::from_secs(1);
+
+fn main() -> Result<()> {
+    let stop = StopFlags::install()?;
+    let bus = common::open(&MOTOR_IDS)?;
+    let controller = &bus.controller;
+    let policy: FaultPolicy = serde_json::from_str(POLICY)?;
+    let monitor = SafetyMonitor::new().with_fault_policy(policy);
+
+    for id in MOTOR_IDS {
+        controller.enable_motor(id)?;
+    }
+    let mut handled = None;
+    let result = ControlLoop::new(100.0).run(&stop.running, |info| {
+        if let Some(sim) = &bus.sim {
+            if info.elapsed >= INJECT_AT && handled.is_none() {
+                sim.set_fault(2, 39);
+            }
+        }
+
+        let target_deg = 20.0 * info.elapsed.as_secs_f64().sin();
+        for id in MOTOR_IDS {
+            let angle = degrees_to_position(target_deg);
+            controller.send_angle_command_to(id, angle, rps_to_velocity(1.0), nm_to_torque(2.0))?;
+        }
+
+        // Fault registers are read at 10 Hz; the stream runs at 100 Hz
+        if info.cycle % 10 != 0 {
+            return Ok(true);
+        }
+        for id in MOTOR_IDS {
+            let code = controller.read_fault(id)?;
+            let Some(class) = FaultClass::from_code(code) else {
+                continue;
+            };
+            let action = monitor.respond_to_fault(controller, id, class, Some(code))?;
+            println!("motor {} fault {} ({}): {}", id, code, class, action);
+            handled = Some(action);
+            if action == FaultAction::Stop {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    });
+    for id in MOTOR_IDS {
+        controller.disable_motor(id)?;
+    }
+    result?;
+
+    if bus.sim.is_some() {
+        ensure!(handled == Some(FaultAction::Stop), "simulated fault was not stopped on");
+    }
+    Ok(())
+}
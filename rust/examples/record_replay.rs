@@ -0,0 +1,98 @@
+//! Record a motion, then replay it from the recording
+//!
+//! Sweeps motors 1 and 2 for two seconds while a [`Recorder`] logs targets
+//! and feedback, writes the recording as CSV, reads it back and replays its
+//! target columns as a [`Trajectory`] — the same path `livelybot replay`
+//! takes for a recorded session.
+//!
+//! ```bash
+//! cargo run --example record_replay
+//! cargo run --example record_replay -- --hardware can0
+//! ```
+
+mod common;
+
+use anyhow::{Result, anyhow, ensure};
+use livelybot_motor_control::cli::StopFlags;
+use livelybot_motor_control::prelude::*;
+use livelybot_motor_control::{ControlLoop, JointSample, Recorder};
+use std::f64::consts::TAU;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+const MOTOR_IDS: [u8; 2] = [1, 2];
+const DURATION: Duration = Duration::from_secs(2);
+
+fn main() -> Result<()> {
+    let stop = StopFlags::install()?;
+    let bus = common::open(&MOTOR_IDS)?;
+    let controller = &bus.controller;
+    let path = std::env::temp_dir().join("livelybot_record_replay.csv");
+
+    for id in MOTOR_IDS {
+        controller.enable_motor(id)?;
+    }
+    let result = record(controller, &stop.running).and_then(|recorder| {
+        recorder.write_csv(&path)?;
+        println!("recorded {} rows to {}", recorder.len(), path.display());
+
+        let trajectory = recorded_targets(&Recorder::read_csv(&path)?)?;
+        ensure!(trajectory.waypoints.len() == recorder.len(), "recording lost rows");
+        let seconds = trajectory.duration().as_secs_f64();
+        println!("replaying {} waypoints over {:.1}s", trajectory.waypoints.len(), seconds);
+        trajectory.play(controller, 100.0, 2.0, 3.0, &stop.running)
+    });
+    for id in MOTOR_IDS {
+        controller.disable_motor(id)?;
+    }
+    result
+}
+
+/// Sweep the motors at 100 Hz, recording target and feedback every cycle
+fn record(controller: &LivelyMotorController, running: &AtomicBool) -> Result<Recorder> {
+    let mut recorder = Recorder::new(&MOTOR_IDS);
+    ControlLoop::new(100.0).run(running, |info| {
+        let target_deg = 45.0 * (TAU * info.elapsed.as_secs_f64() / DURATION.as_secs_f64()).sin();
+        let mut samples = Vec::with_capacity(MOTOR_IDS.len());
+        for (i, &id) in MOTOR_IDS.iter().enumerate() {
+            let target = if i % 2 == 0 { target_deg } else { -target_deg };
+            controller.send_angle_command_to(id, degrees_to_position(target), rps_to_velocity(2.0), nm_to_torque(3.0))?;
+            samples.push(JointSample {
+                motor_id: id,
+                target_deg: target,
+                actual: controller.read_motor_state(id).ok(),
+            });
+        }
+        recorder.record(&samples);
+        Ok(info.elapsed < DURATION)
+    })?;
+    Ok(recorder)
+}
+
+/// The `m<id>.target_deg` columns of a recording as a trajectory
+fn recorded_targets(recording: &Recorder) -> Result<Trajectory> {
+    let channels = recording.channels();
+    let columns = MOTOR_IDS
+        .iter()
+        .map(|id| {
+            let name = format!("m{}.target_deg", id);
+            channels
+                .iter()
+                .position(|(channel, _)| *channel == name)
+                .ok_or_else(|| anyhow!("recording has no column {}", name))
+        })
+        .collect::<Result<Vec<usize>>>()?;
+
+    let waypoints = recording
+        .rows()
+        .iter()
+        .map(|row| Waypoint {
+            time: Duration::from_secs_f64(row.time_s.max(0.0)),
+            positions_deg: columns.iter().map(|&c| Real::from_f64(row.values[c])).collect(),
+        })
+        .collect();
+    Ok(Trajectory {
+        motor_ids: MOTOR_IDS.to_vec(),
+        waypoints,
+    })
+}
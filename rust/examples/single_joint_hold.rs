@@ -0,0 +1,58 @@
+//! Hold one joint at a fixed angle
+//!
+//! Enables motor 1, streams a 30° setpoint at 500 Hz for three seconds,
+//! prints the tracking error every second and the loop timing at the end.
+//!
+//! ```bash
+//! cargo run --example single_joint_hold                    # simulated motor
+//! cargo run --example single_joint_hold -- --hardware can0 # real motor
+//! ```
+
+mod common;
+
+use anyhow::{Result, ensure};
+use livelybot_motor_control::cli::StopFlags;
+use livelybot_motor_control::prelude::*;
+use livelybot_motor_control::ControlLoop;
+use std::time::Duration;
+
+const MOTOR_ID: u8 = 1;
+const TARGET_DEG: f64 = 30.0;
+const HOLD: Duration = Duration::from_secs(3);
+
+fn main() -> Result<()> {
+    let stop = StopFlags::install()?;
+    let bus = common::open(&[MOTOR_ID])?;
+    let controller = &bus.controller;
+
+    controller.enable_motor(MOTOR_ID)?;
+    let mut control = ControlLoop::new(500.0);
+    let result = control.run(&stop.running, |info| {
+        controller.send_angle_command_to(
+            MOTOR_ID,
+            degrees_to_position(TARGET_DEG),
+            rps_to_velocity(2.0),
+            nm_to_torque(3.0),
+        )?;
+        if info.cycle % 500 == 0 {
+            let state = controller.read_motor_state(MOTOR_ID)?;
+            println!(
+                "t={:.1}s position {:.2}° error {:.2}°",
+                info.elapsed.as_secs_f64(),
+                state.position_deg,
+                TARGET_DEG - state.position_deg
+            );
+        }
+        Ok(info.elapsed < HOLD)
+    });
+    let state = controller.read_motor_state(MOTOR_ID);
+    controller.disable_motor(MOTOR_ID)?;
+    result?;
+
+    let state = state?;
+    println!("final position {:.2}°, {}", state.position_deg, control.jitter());
+    if bus.sim.is_some() {
+        ensure!((state.position_deg - TARGET_DEG).abs() < 1.0, "simulated joint did not settle at {}°", TARGET_DEG);
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#ifndef LIVELYBOT_MOTOR_CONTROL_H
+#define LIVELYBOT_MOTOR_CONTROL_H
+
+/* Generated by cbindgen from src/capi.rs by `make header`; do not edit. */
+
+#include <stdint.h>
+/* Opaque controller handle, created by lmc_new and released by lmc_free */
+typedef struct LivelyMotorController LivelyMotorController;
+
+// Motor feedback, filled by [`lmc_read_state`]
+typedef struct LmcMotorState {
+  uint8_t motor_id;
+  // Position in degrees
+  double position_deg;
+  // Velocity in r/s
+  double velocity_rps;
+  // Torque in Nm
+  double torque_nm;
+} LmcMotorState;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Message of the last failed call on this thread, empty if none failed
+//
+// The string stays valid until the next failing call on the same thread.
+const char *lmc_last_error(void);
+
+// Open the CAN interface `channel`, null on failure
+//
+// # Safety
+//
+// `channel` must be null or a NUL-terminated string.
+LivelyMotorController *lmc_new(const char *channel, uint32_t bitrate);
+
+// Enable `motor_id` in position mode with the default gains
+//
+// # Safety
+//
+// `controller` must be null or a pointer returned by [`lmc_new`] and not freed.
+int lmc_enable(const LivelyMotorController *controller, uint8_t motor_id);
+
+// Disable `motor_id`
+//
+// # Safety
+//
+// `controller` must be null or a pointer returned by [`lmc_new`] and not freed.
+int lmc_disable(const LivelyMotorController *controller, uint8_t motor_id);
+
+// Move `motor_id` to `angle_deg` within the velocity and torque limits
+//
+// Software limits loaded into the controller apply as for Rust callers.
+//
+// # Safety
+//
+// `controller` must be null or a pointer returned by [`lmc_new`] and not freed.
+int lmc_send_angle(const LivelyMotorController *controller,
+                   uint8_t motor_id,
+                   double angle_deg,
+                   double max_vel_rps,
+                   double max_torque_nm);
+
+// Read the position, velocity and torque of `motor_id` into `state`
+//
+// # Safety
+//
+// `controller` must be null or a pointer returned by [`lmc_new`] and not
+// freed; `state` must be null or point to writable memory.
+int lmc_read_state(const LivelyMotorController *controller,
+                   uint8_t motor_id,
+                   struct LmcMotorState *state);
+
+// Close the controller; null is ignored
+//
+// # Safety
+//
+// `controller` must be null or a pointer returned by [`lmc_new`] that is
+// not used again.
+void lmc_free(LivelyMotorController *controller);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LIVELYBOT_MOTOR_CONTROL_H */
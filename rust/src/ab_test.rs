@@ -0,0 +1,200 @@
+//! A/B comparison of actuators
+//!
+//! Runs the same excitation [`Primitive`] on two motors and compares tracking
+//! error, temperature rise and current draw, e.g. to qualify a replacement
+//! actuator against the original. Reports serialize to JSON.
+
+use crate::{EnableConfig, LivelyMotorController, Primitive, PrimitiveRunner, Register};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Result of one motor running the excitation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbReport {
+    pub channel: String,
+    pub motor_id: u8,
+    /// Cycles with a position reading
+    pub samples: usize,
+    /// Whether the excitation ran to the end instead of being interrupted
+    pub completed: bool,
+    pub rms_error_deg: f64,
+    pub max_error_deg: f64,
+    pub temperature_start_c: Option<f64>,
+    pub temperature_end_c: Option<f64>,
+    pub mean_current_a: Option<f64>,
+    pub peak_current_a: Option<f64>,
+}
+
+impl AbReport {
+    pub fn temperature_rise_c(&self) -> Option<f64> {
+        Some(self.temperature_end_c? - self.temperature_start_c?)
+    }
+}
+
+/// One compared metric
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AbMetric {
+    pub name: &'static str,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+}
+
+impl AbMetric {
+    /// B − A
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.b? - self.a?)
+    }
+}
+
+/// Reports of both motors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbComparison {
+    pub a: AbReport,
+    pub b: AbReport,
+}
+
+impl AbComparison {
+    /// Compared metrics; a value is `None` where it could not be read
+    pub fn metrics(&self) -> Vec<AbMetric> {
+        let (a, b) = (&self.a, &self.b);
+        [
+            ("rms_error_deg", Some(a.rms_error_deg), Some(b.rms_error_deg)),
+            ("max_error_deg", Some(a.max_error_deg), Some(b.max_error_deg)),
+            ("temperature_rise_c", a.temperature_rise_c(), b.temperature_rise_c()),
+            ("mean_current_a", a.mean_current_a, b.mean_current_a),
+            ("peak_current_a", a.peak_current_a, b.peak_current_a),
+        ]
+        .into_iter()
+        .map(|(name, a, b)| AbMetric { name, a, b })
+        .collect()
+    }
+}
+
+/// Runs an excitation on one motor while measuring it
+#[derive(Debug, Clone)]
+pub struct AbTest {
+    excitation: Primitive,
+    rate_hz: f64,
+    max_vel_rps: f64,
+    max_torque_nm: f64,
+    enable: EnableConfig,
+}
+
+impl AbTest {
+    /// Test at 100 Hz with 2.0 r/s and 3.0 Nm limits, enabled with
+    /// [`EnableConfig::position`]
+    pub fn new(excitation: Primitive) -> Self {
+        Self {
+            excitation,
+            rate_hz: 100.0,
+            max_vel_rps: 2.0,
+            max_torque_nm: 3.0,
+            enable: EnableConfig::position(),
+        }
+    }
+
+    pub fn with_rate(mut self, rate_hz: f64) -> Self {
+        self.rate_hz = rate_hz;
+        self
+    }
+
+    pub fn with_limits(mut self, max_vel_rps: f64, max_torque_nm: f64) -> Self {
+        self.max_vel_rps = max_vel_rps;
+        self.max_torque_nm = max_torque_nm;
+        self
+    }
+
+    /// Gains and torque limit the motor is enabled with for the run
+    pub fn with_enable(mut self, enable: EnableConfig) -> Self {
+        self.enable = enable;
+        self
+    }
+
+    /// Enable `motor_id`, run the excitation and disable it again
+    ///
+    /// Angle setpoints are broadcast, so only the motor under test may be
+    /// enabled on the bus while it runs.
+    pub fn run(&self, controller: &LivelyMotorController, motor_id: u8, running: &AtomicBool) -> Result<AbReport> {
+        let temperature = || controller.read_register_float(motor_id, Register::Temperature).ok().map(f64::from);
+        let temperature_start_c = temperature();
+
+        let mut squared_error = 0.0;
+        let mut max_error_deg: f64 = 0.0;
+        let mut samples = 0;
+        let mut currents = Vec::new();
+
+        controller.enable_with(motor_id, &self.enable)?;
+        let result = PrimitiveRunner::new(controller, motor_id)
+            .with_rate(self.rate_hz)
+            .with_limits(self.max_vel_rps, self.max_torque_nm)
+            .run(&self.excitation, running, |status| {
+                let Some(target_deg) = status.target_deg else {
+                    return Ok(());
+                };
+                if let Ok(state) = controller.read_motor_state(motor_id) {
+                    let error = state.position_deg - target_deg;
+                    squared_error += error * error;
+                    max_error_deg = max_error_deg.max(error.abs());
+                    samples += 1;
+                }
+                if let Ok(current) = controller.read_register_float(motor_id, Register::QCurrent) {
+                    currents.push(f64::from(current).abs());
+                }
+                Ok(())
+            });
+        controller.disable_motor(motor_id)?;
+        result?;
+
+        let mean = |values: &[f64]| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+        Ok(AbReport {
+            channel: controller.channel().to_string(),
+            motor_id,
+            samples,
+            completed: running.load(Ordering::SeqCst),
+            rms_error_deg: if samples > 0 { (squared_error / samples as f64).sqrt() } else { 0.0 },
+            max_error_deg,
+            temperature_start_c,
+            temperature_end_c: temperature(),
+            mean_current_a: mean(&currents),
+            peak_current_a: currents.iter().copied().reduce(f64::max),
+        })
+    }
+
+    /// Run motor A, then after `cooldown` motor B, on the same bus
+    ///
+    /// B is disabled while A runs, since setpoints are broadcast.
+    pub fn compare(
+        &self,
+        controller: &LivelyMotorController,
+        motor_a: u8,
+        motor_b: u8,
+        cooldown: Duration,
+        running: &AtomicBool,
+    ) -> Result<AbComparison> {
+        controller.disable_motor(motor_b)?;
+        let a = self.run(controller, motor_a, running)?;
+        if !cooldown.is_zero() && running.load(Ordering::SeqCst) {
+            thread::sleep(cooldown);
+        }
+        let b = self.run(controller, motor_b, running)?;
+        Ok(AbComparison { a, b })
+    }
+
+    /// Run motor A and motor B at the same time, each on its own bus
+    pub fn compare_parallel(
+        &self,
+        (controller_a, motor_a): (&LivelyMotorController, u8),
+        (controller_b, motor_b): (&LivelyMotorController, u8),
+        running: &AtomicBool,
+    ) -> Result<AbComparison> {
+        let (a, b) = thread::scope(|s| {
+            let b = s.spawn(|| self.run(controller_b, motor_b, running));
+            let a = self.run(controller_a, motor_a, running);
+            (a, b.join().unwrap_or_else(|_| Err(anyhow!("motor B test panicked"))))
+        });
+        Ok(AbComparison { a: a?, b: b? })
+    }
+}
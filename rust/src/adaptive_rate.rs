@@ -0,0 +1,175 @@
+//! Backing off on a sick bus
+//!
+//! Noise or marginal wiring shows up as rising bus error counters
+//! ([`BusErrorStats`]) long before the motors stop answering. Each
+//! [`AdaptiveRate::check`] compares the counters with the previous check; a
+//! check with too many errors raises the degradation level by one, and a run
+//! of clean checks lowers it again. Every level spaces motor queries further
+//! apart and halves the rate telemetry pollers run at
+//! ([`CanBus::telemetry_period`]), so the bus carries fewer frames while
+//! setpoint streams and writes are sent exactly as before. Each level change
+//! is published as [`EventKind::BusDegradation`].
+
+use crate::bus::BusErrorStats;
+use crate::events::{EventBus, EventKind};
+use crate::CanBus;
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// When the bus counts as degraded and how far to back off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveRateConfig {
+    /// Errors between two checks that raise the level
+    pub degrade_errors: u64,
+    /// Consecutive checks below `degrade_errors` before the level drops again
+    pub recover_checks: u32,
+    /// Highest level
+    pub max_level: u8,
+    /// Query spacing added per level
+    pub spacing_per_level: Duration,
+}
+
+impl Default for AdaptiveRateConfig {
+    /// Degrade on 10 errors per check, recover after 5 clean checks, at most
+    /// 3 levels of 2 ms query spacing each
+    fn default() -> Self {
+        Self {
+            degrade_errors: 10,
+            recover_checks: 5,
+            max_level: 3,
+            spacing_per_level: Duration::from_millis(2),
+        }
+    }
+}
+
+impl AdaptiveRateConfig {
+    pub fn with_degrade_errors(mut self, degrade_errors: u64) -> Self {
+        self.degrade_errors = degrade_errors;
+        self
+    }
+
+    pub fn with_recover_checks(mut self, recover_checks: u32) -> Self {
+        self.recover_checks = recover_checks;
+        self
+    }
+
+    pub fn with_max_level(mut self, max_level: u8) -> Self {
+        self.max_level = max_level;
+        self
+    }
+
+    pub fn with_spacing_per_level(mut self, spacing: Duration) -> Self {
+        self.spacing_per_level = spacing;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.degrade_errors == 0 {
+            return Err(anyhow!("degrade threshold must be at least one error"));
+        }
+        if self.recover_checks == 0 {
+            return Err(anyhow!("recovery needs at least one clean check"));
+        }
+        // The telemetry scale halves per level
+        if !(1..=16).contains(&self.max_level) {
+            return Err(anyhow!("max degradation level must be in 1..=16, got {}", self.max_level));
+        }
+        Ok(())
+    }
+
+    /// Fraction of their rate telemetry pollers run at on `level`
+    pub fn telemetry_scale(&self, level: u8) -> f64 {
+        0.5f64.powi(level as i32)
+    }
+
+    /// Query spacing on `level`
+    pub fn query_spacing(&self, level: u8) -> Duration {
+        self.spacing_per_level * level as u32
+    }
+}
+
+/// Throttles a bus's queries and telemetry while its error counters rise
+pub struct AdaptiveRate {
+    bus: Arc<CanBus>,
+    config: AdaptiveRateConfig,
+    events: Option<Arc<EventBus>>,
+    last: BusErrorStats,
+    level: u8,
+    clean_checks: u32,
+}
+
+impl AdaptiveRate {
+    /// Watch `bus`, starting undegraded; counts from before are ignored
+    pub fn new(bus: &Arc<CanBus>, config: AdaptiveRateConfig, events: Option<Arc<EventBus>>) -> Result<Self> {
+        config.validate()?;
+        bus.throttle_queries(Duration::ZERO, 1.0)?;
+        Ok(Self {
+            bus: Arc::clone(bus),
+            config,
+            events,
+            last: bus.error_stats(),
+            level: 0,
+            clean_checks: 0,
+        })
+    }
+
+    /// Current level, 0 when the bus is healthy
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn config(&self) -> &AdaptiveRateConfig {
+        &self.config
+    }
+
+    /// Compare the error counters with the previous check and adjust the level;
+    /// returns the errors since then
+    pub fn check(&mut self) -> Result<BusErrorStats> {
+        let now = self.bus.error_stats();
+        let errors = now.since(&self.last);
+        self.last = now;
+
+        let level = if errors.total() >= self.config.degrade_errors {
+            self.clean_checks = 0;
+            (self.level + 1).min(self.config.max_level)
+        } else if self.level > 0 {
+            self.clean_checks += 1;
+            if self.clean_checks < self.config.recover_checks {
+                return Ok(errors);
+            }
+            self.clean_checks = 0;
+            self.level - 1
+        } else {
+            return Ok(errors);
+        };
+        if level != self.level {
+            self.set_level(level, errors)?;
+        }
+        Ok(errors)
+    }
+
+    fn set_level(&mut self, level: u8, errors: BusErrorStats) -> Result<()> {
+        let query_spacing = self.config.query_spacing(level);
+        let telemetry_scale = self.config.telemetry_scale(level);
+        self.bus.throttle_queries(query_spacing, telemetry_scale)?;
+        self.level = level;
+        if let Some(events) = &self.events {
+            events.publish(EventKind::BusDegradation {
+                channel: self.bus.channel().to_string(),
+                level,
+                errors,
+                telemetry_scale,
+                query_spacing,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AdaptiveRate {
+    /// Lift the throttle
+    fn drop(&mut self) {
+        let _ = self.bus.throttle_queries(Duration::ZERO, 1.0);
+    }
+}
@@ -0,0 +1,737 @@
+//! Async controller on the tokio runtime (`tokio` feature)
+//!
+//! [`AsyncLivelyMotorController`] mirrors the blocking [`LivelyMotorController`]
+//! API with `async` methods: waits use `tokio::time::sleep` and replies are
+//! awaited instead of polled, so a supervisor running many tasks on one
+//! executor is not stalled by a ping or an enable sequence.
+//!
+//! It owns its own socket rather than a [`CanBus`](crate::CanBus): a reader
+//! task hands a copy of every received frame to each waiting request, but the
+//! bandwidth reservation and the monitors of a `CanBus` do not apply to it.
+//!
+//! [`LivelyMotorController`]: crate::LivelyMotorController
+
+use crate::bus::SUBSCRIBER_QUEUE_LEN;
+use crate::calibration::{self, Calibration};
+use crate::capabilities::{Capabilities, Feature, Probe};
+use crate::enable::{self, ControlMode, EnableConfig};
+use crate::error::{self, FaultCode, MotorError, Result};
+use crate::limits::{self, Limits};
+use crate::protocol::{self, EncodingPolicy, Register, RegisterReply, TruncatedFrame, ValueType};
+use crate::query::{self, ErrorLogEntry, InfoQuery, TemperatureSnapshot};
+use crate::{
+    BusLock, MitCommand, MitRanges, MotorInfo, MotorState, Scaling, StateCache, VelocityFilter, DEFAULT_FEEDBACK_WINDOW,
+};
+use anyhow::anyhow;
+use socketcan::tokio::CanSocket;
+use socketcan::{CanFrame, CanId, EmbeddedFrame};
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant};
+
+/// How long a request waits for its reply
+const REPLY_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// LivelyBot motor controller for async code
+pub struct AsyncLivelyMotorController {
+    socket: Arc<CanSocket>,
+    channel: String,
+    bitrate: u32,
+    bus_lock: Option<BusLock>,
+    encoding: EncodingPolicy,
+    /// Every received frame, fed by `reader`
+    frames: broadcast::Sender<CanFrame>,
+    reader: JoinHandle<()>,
+    /// Receiver backing `read_frame_with_timeout`, created on first use
+    rx: tokio::sync::Mutex<Option<broadcast::Receiver<CanFrame>>>,
+    states: Arc<StateCache>,
+    enabled: Mutex<BTreeSet<u8>>,
+    filters: Mutex<BTreeMap<u8, VelocityFilter>>,
+    feedback_window: Duration,
+    mit_ranges: MitRanges,
+    malformed: AtomicU64,
+    capabilities: Mutex<BTreeMap<u8, Capabilities>>,
+    limits: Mutex<BTreeMap<u8, Limits>>,
+    calibrations: Mutex<BTreeMap<u8, Calibration>>,
+}
+
+impl AsyncLivelyMotorController {
+    /// Open `channel`, taking exclusive ownership of it; must be called from
+    /// within a tokio runtime
+    pub fn new(channel: &str, bitrate: u32) -> Result<Self> {
+        Self::open(channel, bitrate, false)
+    }
+
+    /// Create a controller even if another process owns the channel
+    pub fn new_forced(channel: &str, bitrate: u32) -> Result<Self> {
+        Self::open(channel, bitrate, true)
+    }
+
+    fn open(channel: &str, bitrate: u32, force: bool) -> Result<Self> {
+        let bus_lock = BusLock::acquire_or_force(channel, force)?;
+        let socket = Arc::new(
+            CanSocket::open(channel).map_err(|e| error::can_io(e, format!("cannot open CAN interface {}", channel)))?,
+        );
+        let (frames, _) = broadcast::channel(SUBSCRIBER_QUEUE_LEN);
+
+        let reader = tokio::spawn({
+            let socket = Arc::clone(&socket);
+            let frames = frames.clone();
+            async move {
+                loop {
+                    match socket.read_frame().await {
+                        // No receiver is waiting: nobody needs the frame
+                        Ok(frame) => drop(frames.send(frame)),
+                        // Interface down or similar; retry without spinning
+                        Err(_) => time::sleep(Duration::from_millis(10)).await,
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            socket,
+            channel: channel.to_string(),
+            bitrate,
+            bus_lock,
+            encoding: EncodingPolicy::default(),
+            frames,
+            reader,
+            rx: tokio::sync::Mutex::new(None),
+            states: Arc::new(StateCache::new()),
+            enabled: Mutex::new(BTreeSet::new()),
+            filters: Mutex::new(BTreeMap::new()),
+            feedback_window: DEFAULT_FEEDBACK_WINDOW,
+            mit_ranges: MitRanges::default(),
+            malformed: AtomicU64::new(0),
+            capabilities: Mutex::new(BTreeMap::new()),
+            limits: Mutex::new(BTreeMap::new()),
+            calibrations: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Padding byte and strictness used for all outgoing payloads
+    pub fn encoding(&self) -> &EncodingPolicy {
+        &self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: EncodingPolicy) {
+        self.encoding = encoding;
+    }
+
+    /// Window over which velocity feedback is fitted to estimate acceleration
+    pub fn feedback_window(&self) -> Duration {
+        self.feedback_window
+    }
+
+    pub fn set_feedback_window(&mut self, window: Duration) {
+        self.feedback_window = window;
+        self.filters.get_mut().unwrap().clear();
+    }
+
+    /// Ranges the MIT command fields are mapped onto
+    pub fn mit_ranges(&self) -> &MitRanges {
+        &self.mit_ranges
+    }
+
+    pub fn set_mit_ranges(&mut self, ranges: MitRanges) {
+        self.mit_ranges = ranges;
+    }
+
+    /// Lock-free cache of the latest state read from each motor
+    pub fn state_cache(&self) -> &Arc<StateCache> {
+        &self.states
+    }
+
+    /// Motors currently enabled through this controller
+    pub fn enabled_motors(&self) -> Vec<u8> {
+        self.enabled.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Whether this controller holds the channel's ownership lock
+    pub fn owns_bus(&self) -> bool {
+        self.bus_lock.is_some()
+    }
+
+    /// CAN interface name
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Configured CAN bitrate
+    pub fn bitrate(&self) -> u32 {
+        self.bitrate
+    }
+
+    /// Received frames rejected by a parser as too short
+    pub fn malformed_frames(&self) -> u64 {
+        self.malformed.load(Ordering::Relaxed)
+    }
+
+    /// Send a CAN frame
+    pub async fn send_frame(&self, id: u32, data: &[u8]) -> Result<()> {
+        if self.encoding.strict {
+            self.encoding.validate(id, data)?;
+        }
+        let can_id = CanId::extended(id).ok_or(anyhow!("Invalid CAN ID 0x{:X}", id))?;
+        let frame = CanFrame::new(can_id, data).ok_or(anyhow!("Failed to create CAN frame 0x{:X}", id))?;
+        self.socket
+            .write_frame(frame)
+            .await
+            .map_err(|e| error::can_io(e, format!("failed to send frame 0x{:X} on {}", id, self.channel)))
+    }
+
+    /// Read a CAN frame with timeout
+    pub async fn read_frame_with_timeout(&self, timeout_ms: u64) -> Result<Option<CanFrame>> {
+        let mut rx = self.rx.lock().await;
+        let rx = rx.get_or_insert_with(|| self.frames.subscribe());
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        loop {
+            match time::timeout_at(deadline, rx.recv()).await {
+                Ok(Ok(frame)) => return Ok(Some(frame)),
+                Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) | Err(_) => return Ok(None),
+            }
+        }
+    }
+
+    /// Ping a motor to check if it's online
+    pub async fn ping_motor(&self, motor_id: u8) -> Result<MotorInfo> {
+        self.during("ping", motor_id, async {
+            let start_time = Instant::now();
+            let mut info = MotorInfo {
+                motor_id,
+                ..Default::default()
+            };
+
+            let mut rx = self.frames.subscribe();
+            self.send_frame(protocol::REPLY_FLAG | motor_id as u32, &self.encoding.query(InfoQuery::Identity, 0))
+                .await?;
+
+            let reply = wait_for_reply(&mut rx, motor_id, |data| crate::answers_ping(&self.encoding, data)).await;
+            if let Some(frame) = reply {
+                info.response_time_ms = start_time.elapsed().as_millis() as u64;
+                info.is_online = true;
+                if let Ok(identity) = self.count_malformed(motor_id, query::Identity::parse(frame.data())) {
+                    info.name = identity.name;
+                    if !identity.hardware_version.is_empty() {
+                        info.hardware_version = identity.hardware_version;
+                    }
+                }
+            }
+            Ok(info)
+        })
+        .await
+    }
+
+    /// Send an info query and return the matching reply payload
+    pub async fn query(&self, motor_id: u8, query: InfoQuery, index: u8) -> Result<Vec<u8>> {
+        self.during(&format!("{} query of", query.name()), motor_id, async {
+            let mut rx = self.frames.subscribe();
+            self.send_frame(protocol::REPLY_FLAG | motor_id as u32, &self.encoding.query(query, index))
+                .await?;
+            wait_for_reply(&mut rx, motor_id, |data| query.matches(data, index))
+                .await
+                .map(|frame| frame.data().to_vec())
+                // No reply; the firmware may not support this query
+                .ok_or_else(|| error::timeout(motor_id))
+        })
+        .await
+    }
+
+    /// Serial number stored in the motor
+    pub async fn read_serial_number(&self, motor_id: u8) -> Result<u32> {
+        self.require(motor_id, Feature::SerialNumber)?;
+        self.count_malformed(motor_id, query::parse_serial_number(&self.query(motor_id, InfoQuery::SerialNumber, 0).await?))
+    }
+
+    /// Time since the motor powered up
+    pub async fn read_uptime(&self, motor_id: u8) -> Result<Duration> {
+        self.require(motor_id, Feature::Uptime)?;
+        self.count_malformed(motor_id, query::parse_uptime(&self.query(motor_id, InfoQuery::Uptime, 0).await?))
+    }
+
+    /// Motor, driver and MCU temperatures sampled together
+    pub async fn read_temperatures(&self, motor_id: u8) -> Result<TemperatureSnapshot> {
+        self.require(motor_id, Feature::Temperatures)?;
+        self.count_malformed(motor_id, TemperatureSnapshot::parse(&self.query(motor_id, InfoQuery::Temperatures, 0).await?))
+    }
+
+    /// Probe which firmware-gated features `motor_id` has and remember them
+    pub async fn detect_capabilities(&self, motor_id: u8) -> Result<Capabilities> {
+        let info = self.ping_motor(motor_id).await?;
+        if !info.is_online {
+            return Err(error::timeout(motor_id));
+        }
+        let mut features = BTreeSet::new();
+        for feature in Feature::ALL {
+            let probe = match feature.probe() {
+                Probe::Query(query) => self.query(motor_id, query, 0).await.map(drop),
+                Probe::Register(reg, ty) => self.read_registers(motor_id, reg, ty, 1).await.map(drop),
+            };
+            match probe {
+                Ok(()) => {
+                    features.insert(feature);
+                }
+                Err(MotorError::Timeout { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let capabilities = Capabilities {
+            motor_id,
+            firmware: info.hardware_version,
+            features,
+        };
+        self.capabilities.lock().unwrap().insert(motor_id, capabilities.clone());
+        Ok(capabilities)
+    }
+
+    /// Compare the scaling `motor_id` reports with the crate's `FACTOR_*`
+    /// constants; `None` if the firmware does not report one
+    pub async fn check_scaling(&self, motor_id: u8) -> Result<Option<Scaling>> {
+        let mut reported = [0.0; 3];
+        let registers = [Register::ScalePosition, Register::ScaleVelocity, Register::ScaleTorque];
+        for (value, register) in reported.iter_mut().zip(registers) {
+            let reply = match self.read_registers(motor_id, register, ValueType::Float, 1).await {
+                Ok(reply) => reply,
+                Err(MotorError::Timeout { .. }) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            *value = reply
+                .float(0)
+                .ok_or_else(|| error::invalid_response(motor_id, "scaling reply truncated"))? as f64;
+        }
+        let scaling = Scaling {
+            position: reported[0],
+            velocity: reported[1],
+            torque: reported[2],
+        };
+        let mismatches = scaling.mismatches(&Scaling::EXPECTED);
+        if !mismatches.is_empty() {
+            return Err(error::scaling_mismatch(motor_id, mismatches.join(", ")));
+        }
+        Ok(Some(scaling))
+    }
+
+    /// Capabilities detected for `motor_id`, if any
+    pub fn capabilities(&self, motor_id: u8) -> Option<Capabilities> {
+        self.capabilities.lock().unwrap().get(&motor_id).cloned()
+    }
+
+    /// Whether the firmware of `motor_id` has `feature`, detecting on first use
+    pub async fn supports(&self, feature: Feature, motor_id: u8) -> bool {
+        match self.capabilities(motor_id) {
+            Some(capabilities) => capabilities.supports(feature),
+            None => self.detect_capabilities(motor_id).await.is_ok_and(|c| c.supports(feature)),
+        }
+    }
+
+    fn require(&self, motor_id: u8, feature: Feature) -> Result<()> {
+        match self.capabilities.lock().unwrap().get(&motor_id) {
+            Some(capabilities) if !capabilities.supports(feature) => Err(error::unsupported(motor_id, feature)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Error log entries, most recent first
+    pub async fn read_error_log(&self, motor_id: u8) -> Result<Vec<ErrorLogEntry>> {
+        self.require(motor_id, Feature::ErrorLog)?;
+        let mut entries = Vec::new();
+        for index in 0..query::ERROR_LOG_LEN {
+            let reply = self.query(motor_id, InfoQuery::ErrorLog, index).await?;
+            match self.count_malformed(motor_id, ErrorLogEntry::parse(&reply))? {
+                Some(entry) => entries.push(entry),
+                None => break,
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Read `count` registers of type `ty` starting at `reg`
+    pub async fn read_registers(
+        &self,
+        motor_id: u8,
+        reg: Register,
+        ty: ValueType,
+        count: u8,
+    ) -> Result<RegisterReply> {
+        let mut rx = self.frames.subscribe();
+        self.send_frame(protocol::REPLY_FLAG | motor_id as u32, &self.encoding.read(reg, ty, count))
+            .await
+            .map_err(|e| e.context(format!("requesting register 0x{:02X} from motor {}", reg.addr(), motor_id)))?;
+
+        let mut malformed = None;
+        let reply = wait_for_reply(&mut rx, motor_id, |data| {
+            match self.count_malformed(motor_id, self.encoding.parse_reply(data)) {
+                Ok(reply) => reply.register == reg.addr(),
+                Err(e) => {
+                    if let MotorError::InvalidResponse { .. } = e {
+                        malformed = Some(e);
+                    }
+                    false
+                }
+            }
+        })
+        .await;
+        if let Some(frame) = reply {
+            return Ok(self.encoding.parse_reply(frame.data())?);
+        }
+        // A malformed answer explains the missing reply better than the timeout
+        Err(malformed.unwrap_or(error::timeout(motor_id)))
+    }
+
+    /// Read a single float register
+    pub async fn read_register_float(&self, motor_id: u8, reg: Register) -> Result<f32> {
+        let reply = self.read_registers(motor_id, reg, ValueType::Float, 1).await?;
+        reply
+            .float(0)
+            .ok_or_else(|| error::invalid_response(motor_id, &format!("empty reply for register 0x{:02X}", reg.addr())))
+    }
+
+    /// Read the active fault code (0 = no fault)
+    pub async fn read_fault(&self, motor_id: u8) -> Result<u8> {
+        self.during("read fault of", motor_id, async {
+            let reply = self.read_registers(motor_id, Register::Fault, ValueType::Int8, 1).await?;
+            Ok(reply.int(0).ok_or_else(|| error::invalid_response(motor_id, "empty fault reply"))? as u8)
+        })
+        .await
+    }
+
+    /// Fail with [`MotorError::MotorFault`] if the motor reports a fault
+    pub async fn check_fault(&self, motor_id: u8) -> Result<()> {
+        match self.read_fault(motor_id).await? {
+            0 => Ok(()),
+            code => Err(error::motor_fault(motor_id, FaultCode(code))),
+        }
+    }
+
+    /// Write an int8 register
+    pub async fn write_register_int8(&self, motor_id: u8, reg: Register, value: i8) -> Result<()> {
+        self.send_frame(motor_id as u32, &self.encoding.write_int8(reg, value))
+            .await
+            .map_err(|e| e.context(format!("writing register 0x{:02X} of motor {}", reg.addr(), motor_id)))
+    }
+
+    /// Write an int16 register
+    pub async fn write_register_int16(&self, motor_id: u8, reg: Register, value: i16) -> Result<()> {
+        self.send_frame(motor_id as u32, &self.encoding.write_int16(reg, value))
+            .await
+            .map_err(|e| e.context(format!("writing register 0x{:02X} of motor {}", reg.addr(), motor_id)))
+    }
+
+    /// Write a float register
+    pub async fn write_register_float(&self, motor_id: u8, reg: Register, value: f32) -> Result<()> {
+        self.send_frame(motor_id as u32, &self.encoding.write_float(reg, value))
+            .await
+            .map_err(|e| e.context(format!("writing register 0x{:02X} of motor {}", reg.addr(), motor_id)))
+    }
+
+    /// Read measured position, velocity and torque
+    pub async fn read_motor_state(&self, motor_id: u8) -> Result<MotorState> {
+        let reply = self
+            .read_registers(motor_id, Register::Position, ValueType::Int16, 3)
+            .await
+            .map_err(|e| e.context(format!("reading feedback of motor {} on {}", motor_id, self.channel)))?;
+        let calibration = self.calibration(motor_id).unwrap_or_default();
+        let state = crate::decode_state(motor_id, &reply, calibration, &self.filters, self.feedback_window)?;
+        self.states.publish(&state);
+        Ok(state)
+    }
+
+    /// Set the current position as the motor's zero
+    pub async fn set_zero(&self, motor_id: u8) -> Result<()> {
+        self.during("set zero of", motor_id, self.write_register_int8(motor_id, Register::SetZero, 1))
+            .await
+    }
+
+    /// Erase the fault history returned by [`read_error_log`](Self::read_error_log)
+    pub async fn clear_error_log(&self, motor_id: u8) -> Result<()> {
+        self.require(motor_id, Feature::ErrorLog)?;
+        self.during("clear error log of", motor_id, self.write_register_int8(motor_id, Register::ClearErrorLog, 1))
+            .await
+    }
+
+    /// Store ID, zero, gains and limits in flash so they survive a power cycle
+    pub async fn save_config(&self, motor_id: u8) -> Result<()> {
+        self.during("save config of", motor_id, async {
+            self.write_register_int8(motor_id, Register::SaveConfig, 1).await?;
+            time::sleep(Duration::from_millis(100)).await;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Change a motor's CAN ID and verify it answers on the new ID
+    pub async fn set_motor_id(&self, motor_id: u8, new_id: u8) -> Result<()> {
+        if new_id == 0 || new_id > 127 {
+            return Err(anyhow!("Invalid motor ID {} (valid: 1-127)", new_id).into());
+        }
+        self.during("change ID of", motor_id, async {
+            self.write_register_int8(motor_id, Register::MotorId, new_id as i8).await?;
+            time::sleep(Duration::from_millis(50)).await;
+
+            if !self.ping_motor(new_id).await?.is_online {
+                // The motor did not respond on its new ID
+                return Err(error::timeout(new_id));
+            }
+            let mut capabilities = self.capabilities.lock().unwrap();
+            if let Some(mut moved) = capabilities.remove(&motor_id) {
+                moved.motor_id = new_id;
+                capabilities.insert(new_id, moved);
+            }
+            let mut limits = self.limits.lock().unwrap();
+            if let Some(moved) = limits.remove(&motor_id) {
+                limits.insert(new_id, moved);
+            }
+            let mut calibrations = self.calibrations.lock().unwrap();
+            if let Some(moved) = calibrations.remove(&motor_id) {
+                calibrations.insert(new_id, moved);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Scan a range of motor IDs
+    pub async fn scan_range(&self, start_id: u8, end_id: u8) -> Result<Vec<MotorInfo>> {
+        let mut motors = Vec::new();
+        for motor_id in start_id..=end_id {
+            motors.push(self.ping_motor(motor_id).await?);
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(motors)
+    }
+
+    /// Enable motor (position mode, [`EnableConfig::position`])
+    pub async fn enable_motor(&self, motor_id: u8) -> Result<()> {
+        self.enable_with(motor_id, &EnableConfig::position()).await
+    }
+
+    /// Enable motor with the mode, gains, torque limit and ramp of `config`
+    pub async fn enable_with(&self, motor_id: u8, config: &EnableConfig) -> Result<()> {
+        config.validate()?;
+        let config = &self.limited_enable(motor_id, config)?;
+        let operation = match config.mode {
+            ControlMode::Position => "enable",
+            ControlMode::Velocity => "enable velocity mode of",
+        };
+        self.during(operation, motor_id, async {
+            if config.ramp.is_some() {
+                self.write_register_float(motor_id, Register::Kp, 0.0).await?;
+                self.write_register_float(motor_id, Register::Kd, 0.0).await?;
+            }
+
+            self.write_register_int8(motor_id, Register::Mode, 0x0A).await?;
+            time::sleep(Duration::from_millis(50)).await;
+
+            if let Some(limit) = config.torque_limit_nm {
+                self.write_register_float(motor_id, Register::TorqueLimit, limit).await?;
+                time::sleep(Duration::from_millis(20)).await;
+            }
+
+            for (i, (kp, kd)) in config.gain_steps().into_iter().enumerate() {
+                if i > 0 {
+                    time::sleep(enable::RAMP_STEP).await;
+                }
+                self.write_register_float(motor_id, Register::Kp, kp).await?;
+                self.write_register_float(motor_id, Register::Kd, kd).await?;
+            }
+            self.enabled.lock().unwrap().insert(motor_id);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Enable motor for velocity control ([`EnableConfig::velocity`])
+    pub async fn enable_velocity_mode(&self, motor_id: u8) -> Result<()> {
+        self.enable_with(motor_id, &EnableConfig::velocity()).await
+    }
+
+    /// Disable motor
+    pub async fn disable_motor(&self, motor_id: u8) -> Result<()> {
+        self.during("disable", motor_id, async {
+            self.write_register_int8(motor_id, Register::Mode, 0x00).await?;
+            self.enabled.lock().unwrap().remove(&motor_id);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Send velocity control command (0xAD) to every motor on the bus
+    pub async fn send_velocity_command(&self, position: i16, velocity: i16, acceleration: i16) -> Result<()> {
+        self.send_velocity_command_to(0, position, velocity, acceleration).await
+    }
+
+    /// Send angle stream control command (0x90) to every motor on the bus
+    pub async fn send_angle_command(&self, angle: i16, max_vel: i16, max_tqe: i16) -> Result<()> {
+        self.send_angle_command_to(0, angle, max_vel, max_tqe).await
+    }
+
+    /// Send velocity control command (0xAD) to `motor_id` only
+    pub async fn send_velocity_command_to(
+        &self,
+        motor_id: u8,
+        position: i16,
+        velocity: i16,
+        acceleration: i16,
+    ) -> Result<()> {
+        let (position, velocity) = match self.limits_for(motor_id) {
+            Some(limits) => {
+                let position_now = self.states.get(motor_id).map(|cached| cached.state.position_deg);
+                limits.velocity_command(motor_id, position, velocity, position_now)?
+            }
+            None => (position, velocity),
+        };
+        let calibration = self.calibration_for(motor_id)?;
+        let data = self.encoding.velocity_stream(
+            calibration.position_to_motor(position),
+            calibration.velocity_to_motor(velocity),
+            calibration.acceleration_to_motor(acceleration),
+        );
+        self.send_frame(protocol::stream_id(protocol::VELOCITY_STREAM_ID, motor_id), &data).await
+    }
+
+    /// Send angle stream control command (0x90) to `motor_id` only
+    pub async fn send_angle_command_to(&self, motor_id: u8, angle: i16, max_vel: i16, max_tqe: i16) -> Result<()> {
+        let (angle, max_vel, max_tqe) = match self.limits_for(motor_id) {
+            Some(limits) => limits.angle_command(motor_id, angle, max_vel, max_tqe)?,
+            None => (angle, max_vel, max_tqe),
+        };
+        let calibration = self.calibration_for(motor_id)?;
+        let data = self.encoding.angle_stream(
+            calibration.position_to_motor(angle),
+            calibration.max_velocity_to_motor(max_vel),
+            calibration.max_torque_to_motor(max_tqe),
+        );
+        self.send_frame(protocol::stream_id(protocol::ANGLE_STREAM_ID, motor_id), &data).await
+    }
+
+    /// Send an MIT-style impedance command to `motor_id`
+    pub async fn send_mit_command(
+        &self,
+        motor_id: u8,
+        position_deg: f64,
+        velocity_rps: f64,
+        kp: f64,
+        kd: f64,
+        ff_torque_nm: f64,
+    ) -> Result<()> {
+        let (position_deg, velocity_rps, ff_torque_nm) = match self.limits_for(motor_id) {
+            Some(limits) => (
+                limits.position_deg(motor_id, position_deg)?,
+                limits.velocity_rps(motor_id, velocity_rps)?,
+                limits.torque_nm(motor_id, ff_torque_nm)?,
+            ),
+            None => (position_deg, velocity_rps, ff_torque_nm),
+        };
+        let calibration = self.calibration_for(motor_id)?;
+        let command = MitCommand {
+            position_deg: calibration.to_motor_deg(position_deg),
+            velocity_rps: calibration.to_motor_velocity(velocity_rps),
+            kp: calibration.to_motor_gain(kp),
+            kd: calibration.to_motor_gain(kd),
+            torque_nm: calibration.to_motor_torque(ff_torque_nm),
+        };
+        let data = command.encode(&self.mit_ranges);
+        self.send_frame(protocol::stream_id(protocol::MIT_STREAM_ID, motor_id), &data).await
+    }
+
+    /// Enforce `limits` on every command to `motor_id` from now on
+    pub fn set_limits(&self, motor_id: u8, limits: Limits) -> Result<()> {
+        if motor_id == 0 {
+            return Err(anyhow!("limits are set per motor, not for the broadcast ID 0").into());
+        }
+        limits.validate()?;
+        self.limits.lock().unwrap().insert(motor_id, limits);
+        Ok(())
+    }
+
+    pub fn clear_limits(&self, motor_id: u8) {
+        self.limits.lock().unwrap().remove(&motor_id);
+    }
+
+    pub fn limits(&self, motor_id: u8) -> Option<Limits> {
+        self.limits.lock().unwrap().get(&motor_id).copied()
+    }
+
+    /// Apply `calibration` to every command to `motor_id` and to its parsed feedback
+    pub fn set_calibration(&self, motor_id: u8, calibration: Calibration) -> Result<()> {
+        if motor_id == 0 {
+            return Err(anyhow!("calibrations are set per motor, not for the broadcast ID 0").into());
+        }
+        calibration.validate()?;
+        self.calibrations.lock().unwrap().insert(motor_id, calibration);
+        Ok(())
+    }
+
+    pub fn clear_calibration(&self, motor_id: u8) {
+        self.calibrations.lock().unwrap().remove(&motor_id);
+    }
+
+    pub fn calibration(&self, motor_id: u8) -> Option<Calibration> {
+        self.calibrations.lock().unwrap().get(&motor_id).copied()
+    }
+
+    fn calibration_for(&self, motor_id: u8) -> Result<Calibration> {
+        Ok(calibration::for_command(&self.calibrations.lock().unwrap(), motor_id)?)
+    }
+
+    fn limits_for(&self, motor_id: u8) -> Option<Limits> {
+        limits::effective(&self.limits.lock().unwrap(), motor_id)
+    }
+
+    fn limited_enable(&self, motor_id: u8, config: &EnableConfig) -> Result<EnableConfig> {
+        let mut config = *config;
+        if let Some(limits) = self.limits_for(motor_id) {
+            config.torque_limit_nm = match config.torque_limit_nm {
+                Some(torque) => Some(limits.torque_nm(motor_id, torque as f64)? as f32),
+                None => limits.max_torque_nm.map(|max| max as f32),
+            };
+        }
+        Ok(config)
+    }
+
+    /// Await `f`, naming the operation, motor and channel in any error it returns
+    async fn during<T>(&self, operation: &str, motor_id: u8, f: impl Future<Output = Result<T>>) -> Result<T> {
+        f.await.map_err(|e| e.context(format!("{} motor {} on {}", operation, motor_id, self.channel)))
+    }
+
+    /// Count `result` if it failed on a [`TruncatedFrame`], which is an
+    /// invalid response of `motor_id`
+    fn count_malformed<T>(&self, motor_id: u8, result: anyhow::Result<T>) -> Result<T> {
+        result.map_err(|e| match e.downcast::<TruncatedFrame>() {
+            Ok(truncated) => {
+                self.malformed.fetch_add(1, Ordering::Relaxed);
+                error::invalid_response(motor_id, &truncated.to_string())
+            }
+            Err(e) => e.into(),
+        })
+    }
+}
+
+impl Drop for AsyncLivelyMotorController {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// First frame from `motor_id` whose payload `accept`s, within [`REPLY_TIMEOUT`]
+async fn wait_for_reply(
+    rx: &mut broadcast::Receiver<CanFrame>,
+    motor_id: u8,
+    mut accept: impl FnMut(&[u8]) -> bool,
+) -> Option<CanFrame> {
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    loop {
+        match time::timeout_at(deadline, rx.recv()).await {
+            Ok(Ok(frame)) if crate::is_reply_from(&frame, motor_id) && accept(frame.data()) => return Some(frame),
+            Ok(Ok(_)) | Ok(Err(RecvError::Lagged(_))) => continue,
+            Ok(Err(RecvError::Closed)) | Err(_) => return None,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Fleet compliance auditing
+//!
+//! Collects, per motor, the firmware version, a hash of the tuning parameters,
+//! the fault state and the firmware's fault history, and checks them against an expected baseline. Reports
+//! serialize to JSON so results from many robots can be aggregated.
+
+use crate::{ErrorLogEntry, LivelyMotorController, MotorInfo, Register};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tuning parameters read back from a motor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AuditParameters {
+    pub kp: f32,
+    pub kd: f32,
+    pub torque_limit: f32,
+}
+
+impl AuditParameters {
+    /// Stable hash of the parameter values (FNV-1a 64, hex)
+    pub fn hash(&self) -> String {
+        let mut bytes = Vec::with_capacity(12);
+        for value in [self.kp, self.kd, self.torque_limit] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        format!("{:016x}", fnv1a64(&bytes))
+    }
+}
+
+/// Audit result of one motor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MotorAudit {
+    pub channel: String,
+    pub motor_id: u8,
+    pub name: String,
+    pub firmware: String,
+    pub response_time_ms: u64,
+    pub parameters: Option<AuditParameters>,
+    pub parameter_hash: Option<String>,
+    /// Active fault code, `None` if it could not be read
+    pub fault_code: Option<u8>,
+    /// Fault history kept by the firmware, `None` if it does not answer the query
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_log: Option<Vec<ErrorLogEntry>>,
+    /// Compliance problems, empty when compliant
+    pub issues: Vec<String>,
+}
+
+impl MotorAudit {
+    pub fn is_compliant(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Audit report of one host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FleetReport {
+    pub host: String,
+    /// Unix time in seconds
+    pub generated_at: u64,
+    pub interfaces: Vec<String>,
+    pub motors: Vec<MotorAudit>,
+    /// Interfaces or hosts that could not be audited
+    pub errors: Vec<String>,
+}
+
+impl FleetReport {
+    pub fn new(host: &str) -> Self {
+        Self {
+            host: host.to_string(),
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            interfaces: Vec::new(),
+            motors: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn compliant_count(&self) -> usize {
+        self.motors.iter().filter(|m| m.is_compliant()).count()
+    }
+}
+
+/// Expected baseline; unset fields are not checked
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditPolicy {
+    pub expected_firmware: Option<String>,
+    pub expected_parameter_hash: Option<String>,
+}
+
+/// Audit one online motor
+pub fn audit_motor(
+    controller: &LivelyMotorController,
+    info: &MotorInfo,
+    policy: &AuditPolicy,
+) -> MotorAudit {
+    let motor_id = info.motor_id;
+    let read = |reg| controller.read_register_float(motor_id, reg);
+    let parameters = match (read(Register::Kp), read(Register::Kd), read(Register::TorqueLimit)) {
+        (Ok(kp), Ok(kd), Ok(torque_limit)) => Some(AuditParameters { kp, kd, torque_limit }),
+        _ => None,
+    };
+    let parameter_hash = parameters.map(|p| p.hash());
+    let fault_code = controller.read_fault(motor_id).ok();
+    let error_log = controller.read_error_log(motor_id).ok();
+
+    let mut issues = Vec::new();
+    if let Some(expected) = &policy.expected_firmware {
+        if &info.hardware_version != expected {
+            issues.push(format!("firmware {} != expected {}", info.hardware_version, expected));
+        }
+    }
+    match (&parameter_hash, &policy.expected_parameter_hash) {
+        (None, _) => issues.push("parameters could not be read".to_string()),
+        (Some(hash), Some(expected)) if hash != expected => {
+            issues.push(format!("parameter hash {} != expected {}", hash, expected));
+        }
+        _ => {}
+    }
+    match fault_code {
+        Some(0) => {}
+        Some(code) => issues.push(format!("active fault 0x{:02X}", code)),
+        None => issues.push("fault state could not be read".to_string()),
+    }
+
+    MotorAudit {
+        channel: controller.channel().to_string(),
+        motor_id,
+        name: info.name.clone(),
+        firmware: info.hardware_version.clone(),
+        response_time_ms: info.response_time_ms,
+        parameters,
+        parameter_hash,
+        fault_code,
+        error_log,
+        issues,
+    }
+}
+
+/// Scan `start_id..=end_id` on a controller and audit every motor found
+pub fn audit_bus(
+    controller: &LivelyMotorController,
+    start_id: u8,
+    end_id: u8,
+    policy: &AuditPolicy,
+) -> anyhow::Result<Vec<MotorAudit>> {
+    Ok(controller
+        .scan_range(start_id, end_id)?
+        .iter()
+        .filter(|info| info.is_online)
+        .map(|info| audit_motor(controller, info, policy))
+        .collect())
+}
+
+pub(crate) fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
@@ -0,0 +1,121 @@
+//! Command authority of bridge clients
+//!
+//! Every request to a [`BridgeAgent`](crate::BridgeAgent) needs an
+//! [`Authority`] level; a client gets its level from the token it sends, or
+//! the anonymous level without one:
+//!
+//! - observer: read-only (ping, motor states)
+//! - operator: also moves the robot (routines, stop)
+//! - engineer: also writes motor parameters (gains, torque limit)
+//!
+//! so a telemetry dashboard given an observer token cannot rewrite gains. The
+//! tokens are shared secrets in the agent's config, sent in clear over UDP:
+//! they keep honest clients apart, not attackers on the robot's network.
+
+use crate::BridgeMessage;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// What a client may do, each level including the ones below
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Authority {
+    Observer,
+    Operator,
+    Engineer,
+}
+
+impl Authority {
+    /// Level needed to send `message` to an agent
+    pub fn required_for(message: &BridgeMessage) -> Self {
+        match message {
+            BridgeMessage::Ping { .. }
+            | BridgeMessage::Pong { .. }
+            | BridgeMessage::Ack { .. }
+            | BridgeMessage::ReadState { .. }
+            | BridgeMessage::State { .. } => Authority::Observer,
+            BridgeMessage::Run { .. } | BridgeMessage::Stop => Authority::Operator,
+            BridgeMessage::SetParam { .. } => Authority::Engineer,
+        }
+    }
+}
+
+impl fmt::Display for Authority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Authority::Observer => "observer",
+            Authority::Operator => "operator",
+            Authority::Engineer => "engineer",
+        })
+    }
+}
+
+/// Token → authority table of an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessPolicy {
+    /// Level of requests without a token, `None` to reject them
+    #[serde(default = "default_anonymous")]
+    pub anonymous: Option<Authority>,
+    #[serde(default)]
+    pub tokens: BTreeMap<String, Authority>,
+}
+
+fn default_anonymous() -> Option<Authority> {
+    Some(Authority::Observer)
+}
+
+impl Default for AccessPolicy {
+    /// Observers without a token, no tokens
+    fn default() -> Self {
+        Self {
+            anonymous: default_anonymous(),
+            tokens: BTreeMap::new(),
+        }
+    }
+}
+
+impl AccessPolicy {
+    /// Everything allowed without a token, as before authority levels existed
+    pub fn open() -> Self {
+        Self {
+            anonymous: Some(Authority::Engineer),
+            tokens: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_token(mut self, token: &str, authority: Authority) -> Self {
+        self.tokens.insert(token.to_string(), authority);
+        self
+    }
+
+    /// Level granted to `token`; an unknown token is rejected rather than
+    /// treated as anonymous
+    pub fn authority(&self, token: Option<&str>) -> Result<Authority> {
+        match token {
+            Some(token) => self
+                .tokens
+                .iter()
+                .find(|(known, _)| constant_time_eq(known.as_bytes(), token.as_bytes()))
+                .map(|(_, &authority)| authority)
+                .ok_or(anyhow!("unknown token")),
+            None => self.anonymous.ok_or(anyhow!("a token is required")),
+        }
+    }
+
+    /// Check that `token` may send `message`
+    pub fn authorize(&self, token: Option<&str>, message: &BridgeMessage) -> Result<Authority> {
+        let granted = self.authority(token)?;
+        let required = Authority::required_for(message);
+        if granted < required {
+            return Err(anyhow!("permission denied: {} access required, client is {}", required, granted));
+        }
+        Ok(granted)
+    }
+}
+
+/// Compare without returning early on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
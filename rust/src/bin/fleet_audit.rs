@@ -0,0 +1,170 @@
+//! LivelyBot Fleet Audit
+//!
+//! Scans all motors on the given interfaces (locally and/or on remote robots via
+//! ssh) and emits a JSON compliance report: firmware versions, parameter hashes
+//! and fault state per motor.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use livelybot_motor_control::audit::{self, AuditPolicy, FleetReport};
+use livelybot_motor_control::cli::GenerateArgs;
+use livelybot_motor_control::LivelyMotorController;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// LivelyBot Fleet Audit
+#[derive(Parser)]
+#[command(name = "fleet_audit", author, version, about, long_about = None)]
+struct Args {
+    /// CAN interfaces to audit locally (repeatable, default: can0)
+    #[arg(short, long)]
+    interface: Vec<String>,
+
+    /// CAN bitrate (default: 1000000)
+    #[arg(short, long, default_value = "1000000")]
+    bitrate: u32,
+
+    /// Starting motor ID (default: 1)
+    #[arg(short, long, default_value = "1")]
+    start_id: u8,
+
+    /// Ending motor ID (default: 14)
+    #[arg(short, long, default_value = "14")]
+    end_id: u8,
+
+    /// Remote robots to audit over ssh (repeatable, e.g. user@robot1)
+    #[arg(long)]
+    host: Vec<String>,
+
+    /// Only audit the remote hosts, not this machine
+    #[arg(long)]
+    no_local: bool,
+
+    /// Expected firmware version
+    #[arg(long)]
+    expect_firmware: Option<String>,
+
+    /// Expected parameter hash (as printed in a previous report)
+    #[arg(long)]
+    expect_params_hash: Option<String>,
+
+    /// Write the report to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Use the CAN channel even if another program holds its lock
+    #[arg(long)]
+    force: bool,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+
+    let policy = AuditPolicy {
+        expected_firmware: args.expect_firmware.clone(),
+        expected_parameter_hash: args.expect_params_hash.clone(),
+    };
+
+    let mut reports = Vec::new();
+    if !args.no_local {
+        reports.push(audit_local(&args, &policy));
+    }
+    for host in &args.host {
+        match audit_remote(host, &args) {
+            Ok(remote) => reports.extend(remote),
+            Err(e) => {
+                let mut failed = FleetReport::new(host);
+                failed.errors.push(format!("{}: {:#}", host, e));
+                reports.push(failed);
+            }
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&reports)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, json + "\n")?,
+        None => println!("{}", json),
+    }
+
+    let total: usize = reports.iter().map(|r| r.motors.len()).sum();
+    let compliant: usize = reports.iter().map(|r| r.compliant_count()).sum();
+    eprintln!("审计完成: {} 台电机, {} 台合规", total, compliant);
+
+    Ok(())
+}
+
+fn audit_local(args: &Args, policy: &AuditPolicy) -> FleetReport {
+    let mut report = FleetReport::new(&hostname());
+    report.interfaces = if args.interface.is_empty() {
+        vec!["can0".to_string()]
+    } else {
+        args.interface.clone()
+    };
+
+    for interface in report.interfaces.clone() {
+        let controller = if args.force {
+            LivelyMotorController::new_forced(&interface, args.bitrate)
+        } else {
+            LivelyMotorController::new(&interface, args.bitrate)
+        };
+
+        let result = controller
+            .map_err(Into::into)
+            .and_then(|c| audit::audit_bus(&c, args.start_id, args.end_id, policy));
+        match result {
+            Ok(motors) => report.motors.extend(motors),
+            Err(e) => report.errors.push(format!("{}: {:#}", interface, e)),
+        }
+    }
+
+    report
+}
+
+/// Run `fleet_audit` on a remote robot over ssh and parse its report
+fn audit_remote(host: &str, args: &Args) -> Result<Vec<FleetReport>> {
+    let mut remote_args = vec![
+        "fleet_audit".to_string(),
+        format!("--bitrate={}", args.bitrate),
+        format!("--start-id={}", args.start_id),
+        format!("--end-id={}", args.end_id),
+    ];
+    for interface in &args.interface {
+        remote_args.push(format!("--interface={}", interface));
+    }
+    if let Some(firmware) = &args.expect_firmware {
+        remote_args.push(format!("--expect-firmware={}", firmware));
+    }
+    if let Some(hash) = &args.expect_params_hash {
+        remote_args.push(format!("--expect-params-hash={}", hash));
+    }
+    if args.force {
+        remote_args.push("--force".to_string());
+    }
+
+    let output = Command::new("ssh")
+        .arg("-o")
+        .arg("BatchMode=yes")
+        .arg(host)
+        .args(&remote_args)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "remote audit failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
@@ -0,0 +1,606 @@
+//! `livelybot angle`: angle control with MIT-style impedance control.
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+    terminal::Clear,
+    terminal::ClearType,
+    cursor::MoveTo,
+};
+use livelybot_motor_control::cli::{BusArgs, StopFlags, ToolConfig};
+use livelybot_motor_control::{
+    AbComparison, AbTest, Console, ConsoleCommand, ControlLoop, JointSample, LivelyMotorController, OscillateParams, ParkConfig, ParkRunner, Primitive,
+    PrimitiveRunner, Recorder, Register, SessionMetadata, ConfigFingerprint, Oscillator, OscillatorConfig,
+};
+use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Angle control options
+#[derive(clap::Args)]
+pub struct AngleArgs {
+    /// Motor ID (default: 1)
+    #[arg(short, long, default_value = "1")]
+    motor_id: u8,
+
+    #[command(subcommand)]
+    mode: Option<Mode>,
+
+    /// Record target vs actual in sine mode (.csv or .mf4 for ASAM MDF4)
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// Robot name stored in the recording
+    #[arg(long)]
+    robot_name: Option<String>,
+
+    /// Operator stored in the recording
+    #[arg(long)]
+    operator: Option<String>,
+
+    /// Free-text notes stored in the recording
+    #[arg(long)]
+    notes: Option<String>,
+
+    /// Park pose configuration (JSON); the park pose is entered on exit
+    #[arg(long, value_name = "FILE")]
+    park_config: Option<PathBuf>,
+
+    /// Park pose entered on exit or by the park mode
+    #[arg(long, default_value = "park")]
+    park_pose: String,
+}
+
+#[derive(Subcommand)]
+enum Mode {
+    /// Interactive angle control
+    Interactive,
+    /// Sine wave control
+    Sine {
+        /// Amplitude in degrees
+        #[arg(long, default_value = "90.0")]
+        amplitude: f64,
+        /// Frequency in Hz
+        #[arg(long, default_value = "0.2")]
+        frequency: f64,
+        /// Duration in seconds
+        #[arg(long, default_value = "10.0")]
+        duration: f64,
+    },
+    /// Step control
+    Step {
+        /// Comma-separated angles
+        #[arg(long)]
+        angles: String,
+        /// Time per step in seconds
+        #[arg(long, default_value = "3.0")]
+        step_time: f64,
+    },
+    /// Multi-position test
+    Test {
+        /// Comma-separated positions
+        #[arg(long, default_value = "0,30,60,90,60,30,0")]
+        positions: String,
+    },
+    /// Phase-synchronized sine on several joints, e.g. a multi-joint shake-down
+    Oscillate {
+        /// Oscillator configuration (JSON): per-joint amplitude, frequency and phase
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+    },
+    /// Move into the park pose (requires --park-config) and exit
+    Park,
+    /// Run the same sine excitation on --motor-id (A) and a second motor (B) and compare them
+    Compare {
+        /// Motor ID of B
+        #[arg(long)]
+        motor_b: u8,
+        /// CAN interface of B; when set, A and B run at the same time on their own buses
+        #[arg(long)]
+        interface_b: Option<String>,
+        /// Amplitude in degrees
+        #[arg(long, default_value = "30.0")]
+        amplitude: f64,
+        /// Frequency in Hz
+        #[arg(long, default_value = "0.5")]
+        frequency: f64,
+        /// Duration in seconds
+        #[arg(long, default_value = "10.0")]
+        duration: f64,
+        /// Pause between A and B when they share a bus, in seconds
+        #[arg(long, default_value = "0.0")]
+        cooldown: f64,
+        /// Write the comparison as JSON
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+}
+
+pub fn run(bus: &BusArgs, mut args: AngleArgs) -> Result<()> {
+    // The first Ctrl+C stops the mode, a second one aborts parking
+    let stop = StopFlags::install()?;
+    let (running, parking) = (&stop.running, &stop.shutdown);
+
+    let park = args.park_config.as_ref().map(ParkConfig::load).transpose()?;
+    if matches!(args.mode, Some(Mode::Park)) && park.is_none() {
+        return Err(anyhow!("park 模式需要 --park-config"));
+    }
+
+    // Initialize controller
+    let config = bus.config()?;
+    let controller = bus.open(&config)?;
+
+    execute!(
+        stdout(),
+        Print("✅ ".green()),
+        Print(format!("控制器初始化成功 (电机 ID: {})\n", args.motor_id))
+    )?;
+
+    // Enable motor
+    controller.enable_motor(args.motor_id)?;
+    execute!(
+        stdout(),
+        Print("✅ ".green()),
+        Print("电机已激活，准备发送流控制指令\n")
+    )?;
+
+    // Run the specified mode
+    let mode = args.mode.take().unwrap_or(Mode::Interactive);
+    match mode {
+        Mode::Interactive => run_interactive_mode(&controller, args.motor_id, running)?,
+        Mode::Sine { amplitude, frequency, duration } => {
+            let mut recorder = args
+                .record
+                .as_ref()
+                .map(|_| -> Result<Recorder> {
+                    let fingerprint = run_config(&args, &config, park.as_ref(), (amplitude, frequency, duration))?;
+                    Ok(Recorder::new(&[args.motor_id]).with_metadata(session_metadata(&args, &fingerprint)))
+                })
+                .transpose()?;
+            run_sine_wave(&controller, args.motor_id, running, amplitude, frequency, duration, recorder.as_mut())?;
+            if let Some(recorder) = recorder.as_mut().filter(|_| !running.load(Ordering::SeqCst)) {
+                recorder.annotate("interrupted (Ctrl+C)");
+            }
+            if let (Some(recorder), Some(path)) = (recorder, &args.record) {
+                save_recording(&recorder, path)?;
+            }
+        }
+        Mode::Step { angles, step_time } => {
+            let angle_list = parse_double_list(&angles)?;
+            run_step_control(&controller, args.motor_id, running, &angle_list, step_time)?
+        }
+        Mode::Test { positions } => {
+            let position_list = parse_double_list(&positions)?;
+            test_positions(&controller, args.motor_id, running, &position_list)?
+        }
+        Mode::Oscillate { config } => {
+            let oscillator = Oscillator::new(OscillatorConfig::load(&config)?)?;
+            run_oscillator(&controller, args.motor_id, &oscillator, running)?;
+        }
+        Mode::Park => {}
+        Mode::Compare { motor_b, interface_b, amplitude, frequency, duration, cooldown, report } => {
+            let excitation = Primitive::oscillate(OscillateParams {
+                center_deg: 0.0,
+                amplitude_deg: amplitude,
+                frequency_hz: frequency,
+                duration: Duration::from_secs_f64(duration),
+            });
+            let test = AbTest::new(excitation);
+            let comparison = match interface_b {
+                Some(interface_b) => {
+                    let controller_b = bus.open_on(&config, &interface_b)?;
+                    execute!(
+                        stdout(),
+                        Print("🆎 ".cyan()),
+                        Print(format!(
+                            "电机 {} ({}) 与电机 {} ({}) 同时运行激励...\n",
+                            args.motor_id,
+                            controller.channel(),
+                            motor_b,
+                            controller_b.channel()
+                        ))
+                    )?;
+                    test.compare_parallel((&controller, args.motor_id), (&controller_b, motor_b), running)?
+                }
+                None => {
+                    execute!(
+                        stdout(),
+                        Print("🆎 ".cyan()),
+                        Print(format!(
+                            "电机 {} 与电机 {} 依次运行激励, 中间冷却 {}s...\n",
+                            args.motor_id, motor_b, cooldown
+                        ))
+                    )?;
+                    test.compare(&controller, args.motor_id, motor_b, Duration::from_secs_f64(cooldown), running)?
+                }
+            };
+            print_comparison(&comparison)?;
+            if let Some(path) = report {
+                std::fs::write(&path, serde_json::to_string_pretty(&comparison)? + "\n")?;
+                execute!(stdout(), Print("💾 ".green()), Print(format!("对比报告已保存到 {}\n", path.display())))?;
+            }
+        }
+    }
+
+    if let Some(park_config) = park {
+        run_park(&controller, park_config, &args.park_pose, parking)?;
+    }
+
+    // Cleanup
+    controller.disable_motor(args.motor_id)?;
+    execute!(stdout(), Print("🛑 ".yellow()), Print("电机已禁用\n"))?;
+
+    Ok(())
+}
+
+fn run_park(controller: &LivelyMotorController, config: ParkConfig, pose: &str, running: &AtomicBool) -> Result<()> {
+    execute!(stdout(), Print("🅿️  ".cyan()), Print(format!("进入停放姿态: {}\n", pose)))?;
+    ParkRunner::new(controller, config).park(pose, running, |stage| {
+        let _ = execute!(stdout(), Print(format!("   -> 阶段: {}\n", stage.name)));
+    })?;
+    execute!(stdout(), Print("✅ ".green()), Print("已停放\n"))?;
+    Ok(())
+}
+
+fn print_header() {
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50).cyan()),
+        Print("\n"),
+        Print("🚀 0x90 流控制模式 (复刻 SDK)\n".blue().bold()),
+        Print("输入角度 (如 90) 回车。\n"),
+        Print("默认参数: 限速 2.0 r/s, 限矩 3.0 Nm\n"),
+        Print("kp [数值] / kd [数值] 调整增益, p 暂停, r 恢复\n"),
+        Print("输入 q 退出\n"),
+        Print("=".repeat(50)),
+        Print("\n")
+    ).unwrap();
+}
+
+fn run_interactive_mode(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    running: &AtomicBool,
+) -> Result<()> {
+    print_header();
+    let console = Console::stdin("(Stream 0x90) > ");
+    let mut target: Option<f64> = None;
+    let mut hold: Option<f64> = None;
+
+    // Setpoints are streamed every cycle; typing never stalls the stream
+    let mut control_loop = ControlLoop::new(100.0);
+    control_loop.run(running, |_| {
+        let inputs = console.poll();
+        let answered = !inputs.is_empty();
+        for input in inputs {
+            match input {
+                Ok(ConsoleCommand::Quit) => return Ok(false),
+                Ok(ConsoleCommand::Target(angle)) => {
+                    target = Some(angle);
+                    let note = if hold.is_some() { " (暂停中, 恢复后执行)" } else { "" };
+                    execute!(stdout(), Print(format!("   -> 目标角度: {} 度{}\n", angle, note)))?;
+                }
+                Ok(ConsoleCommand::Set { name, value }) => {
+                    let register = match name.as_str() {
+                        "kp" => Register::Kp,
+                        "kd" => Register::Kd,
+                        _ => {
+                            execute!(stdout(), Print(format!("   -> 未知参数: {}\n", name).red()))?;
+                            continue;
+                        }
+                    };
+                    controller.write_register_float(motor_id, register, value as f32)?;
+                    execute!(stdout(), Print(format!("   -> {} = {}\n", name, value)))?;
+                }
+                Ok(ConsoleCommand::Pause) => {
+                    hold = Some(controller.read_motor_state(motor_id)?.position_deg);
+                    execute!(stdout(), Print("   -> ⏸️  已暂停, 保持当前位置\n".yellow()))?;
+                }
+                Ok(ConsoleCommand::Resume) => {
+                    hold = None;
+                    execute!(stdout(), Print("   -> ▶️  已恢复\n".green()))?;
+                }
+                Err(e) => execute!(stdout(), Print(format!("   -> 输入错误: {}\n", e).red()))?,
+            }
+        }
+        if answered {
+            console.prompt();
+        }
+
+        if let Some(angle) = hold.or(target) {
+            controller.send_angle_command(
+                livelybot_motor_control::degrees_to_position(angle),
+                livelybot_motor_control::rps_to_velocity(2.0),
+                livelybot_motor_control::nm_to_torque(3.0),
+            )?;
+        }
+        Ok(true)
+    })?;
+
+    execute!(stdout(), Print(format!("\n⏱️  {}\n", control_loop.jitter())))?;
+    Ok(())
+}
+
+fn run_sine_wave(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    running: &AtomicBool,
+    amplitude_deg: f64,
+    frequency_hz: f64,
+    duration_sec: f64,
+    mut recorder: Option<&mut Recorder>,
+) -> Result<()> {
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50)),
+        Print("\n"),
+        Print("🌊 正弦波角度控制\n".blue()),
+        Print(format!("幅值: {}°, 频率: {} Hz, 时长: {}s\n", amplitude_deg, frequency_hz, duration_sec)),
+        Print("=".repeat(50)),
+        Print("\n")
+    )?;
+
+    let primitive = Primitive::oscillate(OscillateParams {
+        center_deg: 0.0,
+        amplitude_deg,
+        frequency_hz,
+        duration: Duration::from_secs_f64(duration_sec),
+    });
+
+    PrimitiveRunner::new(controller, motor_id).run(&primitive, running, |status| {
+        let target_deg = status.target_deg.unwrap_or(0.0);
+        let mut line = format!("目标: {:.1}°", target_deg);
+        if let Some(recorder) = recorder.as_deref_mut() {
+            let actual = controller.read_motor_state(motor_id).ok();
+            recorder.record(&[JointSample { motor_id, target_deg, actual }]);
+            if let Some(state) = actual {
+                line.push_str(&format!("  实际: {:.1}°", state.position_deg));
+            }
+        }
+
+        execute!(
+            stdout(),
+            MoveTo(0, 15),
+            Clear(ClearType::CurrentLine),
+            Print(line)
+        )?;
+        stdout().flush()?;
+        Ok(())
+    })
+}
+
+fn run_oscillator(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    oscillator: &Oscillator,
+    running: &AtomicBool,
+) -> Result<()> {
+    let config = oscillator.config();
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50)),
+        Print("\n"),
+        Print("🌊 多关节同步正弦\n".blue()),
+        Print(format!("频率: {} Hz, 时长: {}s, 渐入渐出: {}s\n", config.rate_hz, config.duration_s, config.ramp_s)),
+    )?;
+    for joint in &config.joints {
+        execute!(
+            stdout(),
+            Print(format!(
+                "  电机 {}: 中心 {}°, 幅值 {}°, 频率 {} Hz, 相位 {}°\n",
+                joint.motor_id, joint.center_deg, joint.amplitude_deg, joint.frequency_hz, joint.phase_deg
+            ))
+        )?;
+    }
+    execute!(stdout(), Print("=".repeat(50)), Print("\n"))?;
+
+    // --motor-id is already enabled (and disabled on exit) by main
+    let others: Vec<u8> = config.motor_ids().into_iter().filter(|&id| id != motor_id).collect();
+    for &id in &others {
+        controller.enable_motor(id)?;
+    }
+    let result = oscillator.run(controller, running, |info, targets| {
+        let line: Vec<String> = targets.iter().map(|(id, angle)| format!("{}: {:.1}°", id, angle)).collect();
+        execute!(
+            stdout(),
+            MoveTo(0, 15),
+            Clear(ClearType::CurrentLine),
+            Print(format!("{:.1}s  {}", info.elapsed.as_secs_f64(), line.join("  ")))
+        )?;
+        stdout().flush()?;
+        Ok(())
+    });
+    for &id in &others {
+        controller.disable_motor(id)?;
+    }
+    result
+}
+
+fn run_step_control(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    running: &AtomicBool,
+    angles: &[f64],
+    step_duration_sec: f64,
+) -> Result<()> {
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50)),
+        Print("\n"),
+        Print("📈 阶梯角度控制\n".blue()),
+        Print("角度序列: "),
+    )?;
+
+    for (i, &angle) in angles.iter().enumerate() {
+        if i > 0 {
+            execute!(stdout(), Print(", "))?;
+        }
+        execute!(stdout(), Print(angle))?;
+    }
+    execute!(
+        stdout(),
+        Print("°\n"),
+        Print(format!("每步时长: {}s\n", step_duration_sec)),
+        Print("=".repeat(50)),
+        Print("\n")
+    )?;
+
+    let step_time = Duration::from_secs_f64(step_duration_sec);
+    let primitive = Primitive::sequence(angles.iter().map(|&a| Primitive::move_to(a, step_time)).collect());
+
+    PrimitiveRunner::new(controller, motor_id).run(&primitive, running, |status| {
+        if status.step_started {
+            execute!(
+                stdout(),
+                Print(format!("\n--- 步骤 {}/{}: {}° ---\n", status.step + 1, status.steps, angles[status.step]))
+            )?;
+        }
+        if status.cycle.cycle % 10 == 0 {
+            let remaining = status.step_duration.saturating_sub(status.step_elapsed);
+            execute!(
+                stdout(),
+                MoveTo(0, 20),
+                Clear(ClearType::CurrentLine),
+                Print(format!("剩余时间: {:.1}s", remaining.as_secs_f64()))
+            )?;
+            stdout().flush()?;
+        }
+        Ok(())
+    })
+}
+
+fn test_positions(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    running: &AtomicBool,
+    positions: &[f64],
+) -> Result<()> {
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50)),
+        Print("\n"),
+        Print("🧪 多位置测试\n".blue()),
+        Print("测试位置: "),
+    )?;
+
+    for (i, &pos) in positions.iter().enumerate() {
+        if i > 0 {
+            execute!(stdout(), Print(", "))?;
+        }
+        execute!(stdout(), Print(pos))?;
+    }
+    execute!(
+        stdout(),
+        Print("°\n"),
+        Print("=".repeat(50)),
+        Print("\n")
+    )?;
+
+    let settle = Duration::from_secs(2);
+    let primitive = Primitive::sequence(positions.iter().map(|&p| Primitive::move_to(p, settle)).collect());
+
+    PrimitiveRunner::new(controller, motor_id).run(&primitive, running, |status| {
+        if status.step_started {
+            execute!(
+                stdout(),
+                Print(format!("\n--- 测试位置 {}/{}: {}° ---\n", status.step + 1, status.steps, positions[status.step])),
+                Print("等待2秒稳定...")
+            )?;
+            stdout().flush()?;
+        }
+        Ok(())
+    })
+}
+
+fn print_comparison(comparison: &AbComparison) -> Result<()> {
+    let cell = |v: Option<f64>| v.map_or_else(|| "--".to_string(), |v| format!("{:.3}", v));
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50)),
+        Print("\n"),
+        Print("📊 A/B 对比结果\n".blue()),
+        Print(format!(
+            "{:<20} {:>9} {:>9} {:>9}\n",
+            "指标",
+            format!("A (ID {})", comparison.a.motor_id),
+            format!("B (ID {})", comparison.b.motor_id),
+            "B - A"
+        ))
+    )?;
+    for metric in comparison.metrics() {
+        execute!(
+            stdout(),
+            Print(format!(
+                "{:<20} {:>9} {:>9} {:>9}\n",
+                metric.name,
+                cell(metric.a),
+                cell(metric.b),
+                cell(metric.delta())
+            ))
+        )?;
+    }
+    for report in [&comparison.a, &comparison.b] {
+        if !report.completed {
+            execute!(stdout(), Print("⚠️  ".yellow()), Print(format!("电机 {} 的激励被中断, 结果不完整\n", report.motor_id)))?;
+        }
+    }
+    execute!(stdout(), Print("=".repeat(50)), Print("\n"))?;
+    Ok(())
+}
+
+/// Effective configuration of a sine run, hashed into the recording
+fn run_config(
+    args: &AngleArgs,
+    tool: &ToolConfig,
+    park: Option<&ParkConfig>,
+    sine: (f64, f64, f64),
+) -> Result<ConfigFingerprint> {
+    let (amplitude, frequency, duration) = sine;
+    let mut config = ConfigFingerprint::new()
+        .with("bus", &(&tool.interface, tool.bitrate, tool.reserve_bandwidth))?
+        .with("motor_id", &args.motor_id)?
+        .with("sine", &serde_json::json!({ "amplitude": amplitude, "frequency": frequency, "duration": duration }))?;
+    if let Some(park) = park {
+        config.add("park", &(park, &args.park_pose))?;
+    }
+    if !tool.limits.is_empty() {
+        config.add("limits", &tool.limits)?;
+    }
+    Ok(config)
+}
+
+fn session_metadata(args: &AngleArgs, config: &ConfigFingerprint) -> SessionMetadata {
+    let mut metadata = SessionMetadata::default().with_git_commit_of(".").with_config(config);
+    metadata.robot = args.robot_name.clone();
+    metadata.operator = args.operator.clone().or_else(|| std::env::var("USER").ok());
+    metadata.notes = args.notes.clone();
+    metadata.with_extra("mode", "sine")
+}
+
+fn save_recording(recorder: &Recorder, path: &Path) -> Result<()> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("mf4") | Some("mdf") => recorder.write_mdf4(path)?,
+        _ => recorder.write_csv(path)?,
+    }
+    execute!(
+        stdout(),
+        Print("\n💾 ".green()),
+        Print(format!("已保存 {} 条记录到 {}\n", recorder.len(), path.display()))
+    )?;
+    Ok(())
+}
+
+fn parse_double_list(s: &str) -> Result<Vec<f64>> {
+    s.split(',')
+        .map(|s| s.trim().parse::<f64>().map_err(Into::into))
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,82 @@
+//! `livelybot flash`: writes gains and limits from a profile, optionally sets
+//! the zero, and stores the configuration in motor flash.
+
+use anyhow::{anyhow, Result};
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::BusArgs;
+use livelybot_motor_control::{ProfileSet, Register};
+use std::io::{stdin, stdout, Write};
+use std::path::PathBuf;
+
+/// Flash options
+#[derive(clap::Args)]
+pub struct FlashArgs {
+    /// Motors whose configuration is stored (comma-separated)
+    #[arg(short, long, value_delimiter = ',', required = true)]
+    motor_ids: Vec<u8>,
+
+    /// Gain / limit profiles (CSV or JSON) written before saving
+    #[arg(long, value_name = "FILE")]
+    profiles: Option<PathBuf>,
+
+    /// Profile written to the motors
+    #[arg(long, default_value = "normal")]
+    profile: String,
+
+    /// Set the current position as zero before saving
+    #[arg(long)]
+    zero: bool,
+
+    /// Do not ask for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+pub fn run(bus: &BusArgs, args: FlashArgs) -> Result<()> {
+    let profiles = args.profiles.as_ref().map(ProfileSet::import).transpose()?;
+    if let Some(profiles) = &profiles {
+        profiles.profile(&args.profile)?;
+    }
+
+    if !args.yes {
+        execute!(
+            stdout(),
+            Print("⚠️  ".yellow()),
+            Print(format!("将写入电机 {:?} 的 flash{}, 继续? [y/N] ", args.motor_ids, if args.zero { " (含零点)" } else { "" }))
+        )?;
+        stdout().flush()?;
+        let mut answer = String::new();
+        stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            return Err(anyhow!("已取消"));
+        }
+    }
+
+    let controller = bus.open(&bus.config()?)?;
+
+    if let Some(profiles) = &profiles {
+        let applied = profiles.apply(&controller, &args.profile, &args.motor_ids)?;
+        execute!(stdout(), Print("✅ ".green()), Print(format!("配置 {} 已写入电机 {:?}\n", args.profile, applied)))?;
+    }
+
+    for &motor_id in &args.motor_ids {
+        if args.zero {
+            controller.set_zero(motor_id)?;
+        }
+        controller.save_config(motor_id)?;
+
+        // Read back what was stored
+        let kp = controller.read_register_float(motor_id, Register::Kp)?;
+        let kd = controller.read_register_float(motor_id, Register::Kd)?;
+        let torque_limit = controller.read_register_float(motor_id, Register::TorqueLimit)?;
+        execute!(
+            stdout(),
+            Print("💾 ".green()),
+            Print(format!("电机 {}: kp {}, kd {}, 限矩 {} Nm, 已保存\n", motor_id, kp, kd, torque_limit))
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! `livelybot inventory`: records which motor (by serial number) drives each
+//! joint, and checks for swapped or replaced motors after maintenance.
+
+use anyhow::{anyhow, Result};
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::BusArgs;
+use livelybot_motor_control::{JointInventory, JointMapping, JointStatus};
+use std::io::{stdin, stdout, Write};
+use std::path::PathBuf;
+
+/// Inventory options
+#[derive(clap::Args)]
+pub struct InventoryArgs {
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(clap::Subcommand)]
+enum Action {
+    /// Read the serial number of every joint of a joint mapping
+    Record {
+        /// Joint mapping (JSON, as used by replay)
+        #[arg(long)]
+        mapping: PathBuf,
+        /// Inventory file written
+        #[arg(short, long, default_value = "inventory.json")]
+        output: PathBuf,
+    },
+    /// Compare the motors on the bus with the inventory
+    Check {
+        /// Inventory file (default: the one in --config)
+        inventory: Option<PathBuf>,
+        /// Also look for moved motors on IDs 1..=N
+        #[arg(long, value_name = "N")]
+        scan: Option<u8>,
+        /// Rewrite this joint mapping (and the inventory) to where the motors are now
+        #[arg(long, value_name = "MAPPING")]
+        remap: Option<PathBuf>,
+        /// Do not ask for confirmation before remapping
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+pub fn run(bus: &BusArgs, args: InventoryArgs) -> Result<()> {
+    let config = bus.config()?;
+    let controller = bus.open_unchecked(&config)?;
+
+    match args.action {
+        Action::Record { mapping, output } => {
+            let inventory = JointInventory::record(&controller, &JointMapping::load(&mapping)?)?;
+            for joint in &inventory.joints {
+                let line = format!("{:<16} ID {:<3} SN {}\n", joint.name, joint.motor_id, joint.serial_number);
+                execute!(stdout(), Print(line))?;
+            }
+            inventory.save(&output)?;
+            let saved = format!("已记录 {} 个关节到 {}\n", inventory.joints.len(), output.display());
+            execute!(stdout(), Print("💾 ".green()), Print(saved))?;
+        }
+        Action::Check { inventory, scan, remap, yes } => {
+            let path = inventory
+                .or(config.inventory.clone())
+                .ok_or_else(|| anyhow!("no inventory file given and none in --config"))?;
+            let inventory = JointInventory::load(&path)?.on_channel(controller.channel());
+            let report = match scan {
+                Some(end_id) => inventory.check_scanning(&[&controller], 1, end_id)?,
+                None => inventory.check(&[&controller])?,
+            };
+            for check in &report.joints {
+                let icon = if check.status == JointStatus::Ok { "✅ ".green() } else { "❌ ".red() };
+                execute!(stdout(), Print(icon), Print(format!("{}\n", check)))?;
+            }
+            if report.is_ok() {
+                execute!(stdout(), Print("🔎 ".green()), Print("所有关节与记录一致\n"))?;
+                return Ok(());
+            }
+
+            let Some(mapping_path) = remap else {
+                return report.ensure_ok();
+            };
+            let remapped = report.remap(&JointMapping::load(&mapping_path)?)?;
+            if !yes {
+                execute!(
+                    stdout(),
+                    Print("⚠️  ".yellow()),
+                    Print(format!(
+                        "将按电机当前 ID 改写 {} 与 {}, 继续? [y/N] ",
+                        mapping_path.display(),
+                        path.display()
+                    ))
+                )?;
+                stdout().flush()?;
+                let mut answer = String::new();
+                stdin().read_line(&mut answer)?;
+                if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                    return Err(anyhow!("已取消"));
+                }
+            }
+            std::fs::write(&mapping_path, serde_json::to_string_pretty(&remapped)? + "\n")?;
+            // Joints on other buses stay as recorded
+            let mut updated = JointInventory::load(&path)?;
+            updated.joints.retain(|j| j.channel != controller.channel());
+            updated.joints.extend(report.remapped_inventory().joints);
+            updated.save(&path)?;
+            execute!(stdout(), Print("💾 ".green()), Print("关节映射与记录已更新\n"))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! LivelyBot command line tool
+//!
+//! One binary for the everyday motor tools. Every subcommand shares the bus
+//! flags and the `--config` file, so per-motor software limits configured
+//! there apply to every command the tool sends.
+
+use anyhow::Result;
+use clap::{CommandFactory, Parser, Subcommand};
+use livelybot_motor_control::cli::{BusArgs, GenerateArgs};
+
+mod angle;
+mod flash;
+mod inventory;
+mod params;
+mod record;
+mod replay;
+mod scan;
+mod sweep;
+mod vel;
+
+/// LivelyBot motor tool
+#[derive(Parser)]
+#[command(name = "livelybot", author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    bus: BusArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scan the bus for motors and show their information
+    Scan(scan::ScanArgs),
+    /// Angle control: interactive, sine, step, oscillate, park, compare
+    Angle(angle::AngleArgs),
+    /// Interactive velocity control with emergency stop
+    Vel(vel::VelArgs),
+    /// Read or write motor registers by name
+    Params(params::ParamsArgs),
+    /// Write a gain profile and store the configuration in motor flash
+    Flash(flash::FlashArgs),
+    /// Log motor states and sent commands to CSV or MCAP
+    Record(record::RecordArgs),
+    /// Play back a recording or a joint trajectory
+    Replay(replay::ReplayArgs),
+    /// Run an excitation for each value of Kp, Kd, torque limit or command rate
+    Sweep(sweep::SweepArgs),
+    /// Record motor serial numbers per joint; detect swapped motors
+    Inventory(inventory::InventoryArgs),
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+
+    match args.command {
+        Some(Command::Scan(scan)) => scan::run(&args.bus, scan),
+        Some(Command::Angle(angle)) => angle::run(&args.bus, angle),
+        Some(Command::Vel(vel)) => vel::run(&args.bus, vel),
+        Some(Command::Params(params)) => params::run(&args.bus, params),
+        Some(Command::Flash(flash)) => flash::run(&args.bus, flash),
+        Some(Command::Record(record)) => record::run(&args.bus, record),
+        Some(Command::Replay(replay)) => replay::run(&args.bus, replay),
+        Some(Command::Sweep(sweep)) => sweep::run(&args.bus, sweep),
+        Some(Command::Inventory(inventory)) => inventory::run(&args.bus, inventory),
+        None => {
+            Args::command().print_help()?;
+            Ok(())
+        }
+    }
+}
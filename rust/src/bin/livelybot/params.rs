@@ -0,0 +1,112 @@
+//! `livelybot params`: reads and writes motor registers by name.
+
+use anyhow::{anyhow, Result};
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::BusArgs;
+use livelybot_motor_control::protocol::{Access, ValueType};
+use livelybot_motor_control::{LivelyMotorController, Register};
+use std::io::stdout;
+
+/// Register options
+#[derive(clap::Args)]
+pub struct ParamsArgs {
+    /// Motor ID (default: 1)
+    #[arg(short, long, default_value = "1")]
+    motor_id: u8,
+
+    /// Write a register before listing them, e.g. --set kp=5.0 (repeatable)
+    #[arg(long, value_name = "NAME=VALUE")]
+    set: Vec<String>,
+
+    /// Print the register values as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(bus: &BusArgs, args: ParamsArgs) -> Result<()> {
+    // Parse every assignment before writing any of them
+    let assignments: Vec<(Register, f64)> = args.set.iter().map(|s| parse_assignment(s)).collect::<Result<_>>()?;
+    let controller = bus.open_unchecked(&bus.config()?)?;
+
+    let mut motor_id = args.motor_id;
+    for (register, value) in assignments {
+        motor_id = write(&controller, motor_id, register, value)?;
+        if !args.json {
+            execute!(stdout(), Print("✅ ".green()), Print(format!("{} = {}\n", register.info().name, value)))?;
+        }
+    }
+
+    let readable = Register::ALL.iter().filter(|r| r.info().access != Access::Write);
+    if args.json {
+        let values: serde_json::Map<String, serde_json::Value> = readable
+            .map(|&r| (r.info().name.to_string(), read(&controller, motor_id, r).ok().into()))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&values)?);
+        return Ok(());
+    }
+
+    execute!(stdout(), Print(format!("电机 {} 寄存器:\n", motor_id).cyan()))?;
+    for &register in readable {
+        let info = register.info();
+        let value = match read(&controller, motor_id, register) {
+            Ok(value) if value_type(register) == ValueType::Float => format!("{:.4}", value),
+            Ok(value) => format!("{}", value),
+            Err(_) => "--".to_string(),
+        };
+        execute!(stdout(), Print(format!("  0x{:02X} {:<16} {:>12} {}\n", info.address, info.name, value, info.unit)))?;
+    }
+    Ok(())
+}
+
+/// `name=value` with a writable register name
+fn parse_assignment(text: &str) -> Result<(Register, f64)> {
+    let (name, value) = text.split_once('=').ok_or_else(|| anyhow!("expected NAME=VALUE, got {:?}", text))?;
+    let register = Register::ALL
+        .into_iter()
+        .find(|r| r.info().name == name.trim())
+        .ok_or_else(|| anyhow!("unknown register {:?}", name.trim()))?;
+    if register.info().access == Access::Read {
+        return Err(anyhow!("register {} is read-only", name.trim()));
+    }
+    let value: f64 = value.trim().parse().map_err(|e| anyhow!("{}: {}", text, e))?;
+    Ok((register, value))
+}
+
+fn value_type(register: Register) -> ValueType {
+    match register.info().value_type {
+        "float" => ValueType::Float,
+        "int16" => ValueType::Int16,
+        _ => ValueType::Int8,
+    }
+}
+
+fn read(controller: &LivelyMotorController, motor_id: u8, register: Register) -> Result<f64> {
+    let reply = controller.read_registers(motor_id, register, value_type(register), 1)?;
+    reply.float(0).map(f64::from).ok_or_else(|| anyhow!("empty reply for {}", register.info().name))
+}
+
+/// Write `value`, returns the motor's ID afterwards
+fn write(controller: &LivelyMotorController, motor_id: u8, register: Register, value: f64) -> Result<u8> {
+    let integer = |min: f64, max: f64| {
+        if value.fract() != 0.0 || value < min || value > max {
+            return Err(anyhow!("{} must be an integer in {}..={}", register.info().name, min, max));
+        }
+        Ok(value)
+    };
+    match (register, value_type(register)) {
+        (Register::MotorId, _) => {
+            let new_id = integer(1.0, 127.0)? as u8;
+            controller.set_motor_id(motor_id, new_id)?;
+            return Ok(new_id);
+        }
+        (_, ValueType::Float) => controller.write_register_float(motor_id, register, value as f32)?,
+        (_, ValueType::Int16) => {
+            controller.write_register_int16(motor_id, register, integer(-32768.0, 32767.0)? as i16)?
+        }
+        (_, _) => controller.write_register_int8(motor_id, register, integer(-128.0, 127.0)? as i8)?,
+    }
+    Ok(motor_id)
+}
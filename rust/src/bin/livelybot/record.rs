@@ -0,0 +1,58 @@
+//! `livelybot record`: logs motor states and sent commands to CSV or MCAP
+//! until Ctrl+C.
+
+use anyhow::Result;
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::{BusArgs, StopFlags};
+use livelybot_motor_control::{ControlLoop, TelemetryRecorder};
+use std::io::stdout;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Recording options
+#[derive(clap::Args)]
+pub struct RecordArgs {
+    /// Motors whose states are recorded (comma-separated)
+    #[arg(short, long, value_delimiter = ',', default_value = "1")]
+    motor_ids: Vec<u8>,
+
+    /// Output file (.csv or .mcap)
+    #[arg(short, long, default_value = "telemetry.mcap")]
+    output: PathBuf,
+
+    /// Stop after SECONDS instead of at Ctrl+C
+    #[arg(long, value_name = "SECONDS")]
+    duration: Option<f64>,
+}
+
+pub fn run(bus: &BusArgs, args: RecordArgs) -> Result<()> {
+    let stop = StopFlags::install()?;
+    let controller = bus.open_unchecked(&bus.config()?)?;
+
+    let mut recorder = TelemetryRecorder::start(&controller, &args.output, &args.motor_ids)?;
+    execute!(
+        stdout(),
+        Print("⏺️  ".red()),
+        Print(format!("记录电机 {:?} 到 {} (Ctrl+C 停止)\n", args.motor_ids, args.output.display()))
+    )?;
+
+    let deadline = args.duration.map(|s| Instant::now() + Duration::from_secs_f64(s));
+    ControlLoop::with_period(Duration::from_millis(50))
+        .run(&stop.running, |_| Ok(deadline.is_none_or(|d| Instant::now() < d)))?;
+
+    recorder.stop()?;
+    execute!(
+        stdout(),
+        Print("💾 ".green()),
+        Print(format!(
+            "已记录 {} 条状态, {} 条指令到 {}\n",
+            recorder.states_logged(),
+            recorder.commands_logged(),
+            args.output.display()
+        ))
+    )?;
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! `livelybot replay`: plays back the targets of a recording, or an imported
+//! joint trajectory, as angle commands.
+
+use anyhow::{anyhow, Result};
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::{BusArgs, StopFlags};
+use livelybot_motor_control::{Float, JointMapping, Real, Recorder, Trajectory, Waypoint};
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Replay options
+#[derive(clap::Args)]
+pub struct ReplayArgs {
+    /// Recording written with `angle --record` (CSV), or a trajectory export with --mapping
+    file: PathBuf,
+
+    /// Joint name → motor mapping (JSON) for JointTrajectory exports
+    #[arg(long, value_name = "FILE")]
+    mapping: Option<PathBuf>,
+
+    /// Command rate in Hz
+    #[arg(long, default_value = "100.0")]
+    rate: f64,
+
+    /// Velocity limit in r/s
+    #[arg(long, default_value = "2.0")]
+    max_vel: f64,
+
+    /// Torque limit in Nm
+    #[arg(long, default_value = "3.0")]
+    max_torque: f64,
+}
+
+pub fn run(bus: &BusArgs, args: ReplayArgs) -> Result<()> {
+    let stop = StopFlags::install()?;
+    let trajectory = match &args.mapping {
+        Some(mapping) => Trajectory::import(&args.file, &JointMapping::load(mapping)?)?,
+        None => recorded_targets(&args.file)?,
+    };
+    let controller = bus.open(&bus.config()?)?;
+
+    execute!(
+        stdout(),
+        Print("▶️  ".cyan()),
+        Print(format!(
+            "回放 {}: 电机 {:?}, {} 个点, {:.1}s\n",
+            args.file.display(),
+            trajectory.motor_ids,
+            trajectory.waypoints.len(),
+            trajectory.duration().as_secs_f64()
+        ))
+    )?;
+
+    for &id in &trajectory.motor_ids {
+        controller.enable_motor(id)?;
+    }
+    let result = trajectory.play(&controller, args.rate, args.max_vel, args.max_torque, &stop.running);
+    for &id in &trajectory.motor_ids {
+        controller.disable_motor(id)?;
+    }
+    result?;
+
+    execute!(stdout(), Print("🛑 ".yellow()), Print("回放结束, 电机已禁用\n"))?;
+    Ok(())
+}
+
+/// The `m<id>.target_deg` columns of a recording as a trajectory; rows
+/// without a target for every motor are skipped
+fn recorded_targets(path: &Path) -> Result<Trajectory> {
+    let recording = Recorder::read_csv(path)?;
+    let (columns, motor_ids): (Vec<usize>, Vec<u8>) = recording
+        .channels()
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (name, _))| {
+            let motor_id = name.strip_prefix('m')?.strip_suffix(".target_deg")?.parse::<u8>().ok()?;
+            Some((i, motor_id))
+        })
+        .unzip();
+    if motor_ids.is_empty() {
+        return Err(anyhow!("recording {} has no target columns", path.display()));
+    }
+
+    let waypoints = recording
+        .rows()
+        .iter()
+        .filter_map(|row| {
+            let positions_deg: Vec<f64> = columns.iter().map(|&c| row.values[c]).collect();
+            positions_deg.iter().all(|p| p.is_finite()).then(|| Waypoint {
+                time: Duration::from_secs_f64(row.time_s.max(0.0)),
+                positions_deg: positions_deg.into_iter().map(Real::from_f64).collect(),
+            })
+        })
+        .collect();
+    Ok(Trajectory { motor_ids, waypoints })
+}
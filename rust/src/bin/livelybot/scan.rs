@@ -0,0 +1,506 @@
+//! `livelybot scan`: scans the CAN bus for connected LivelyBot motors and
+//! displays their information.
+
+use anyhow::{anyhow, Result};
+use crossterm::{
+    cursor::MoveToColumn,
+    event::{
+        self, Event, KeyCode, KeyEventKind, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute,
+    style::{Print, Stylize},
+    terminal::{disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, Clear, ClearType},
+};
+use livelybot_motor_control::cli::BusArgs;
+use livelybot_motor_control::{plugins, protocol};
+use livelybot_motor_control::{BusMonitor, Feature, FieldCommand, JogDirection, JogSession, JogStatus, LivelyMotorController, MotorInfo, TorqueEnvelope};
+use socketcan::{EmbeddedFrame, Id};
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use std::thread;
+
+/// Scan options
+#[derive(clap::Args)]
+pub struct ScanArgs {
+    /// Starting motor ID (default: 1)
+    #[arg(short, long, default_value = "1")]
+    start_id: u8,
+
+    /// Ending motor ID (default: 14)
+    #[arg(short, long, default_value = "14")]
+    end_id: u8,
+
+    /// Skip the interactive action menu after scanning
+    #[arg(long)]
+    no_menu: bool,
+
+    /// Allow the menu's set-ID and set-zero actions, which write registers
+    /// (0x5F, 0x5E) that no firmware documentation confirms
+    #[arg(long)]
+    allow_unverified_writes: bool,
+
+    /// After scanning, listen for SECONDS and report motor vs foreign bus load
+    #[arg(long, value_name = "SECONDS")]
+    bus_report: Option<f64>,
+
+    /// After scanning, print every frame seen for SECONDS with a decoded description
+    #[arg(long, value_name = "SECONDS")]
+    sniff: Option<f64>,
+
+    /// After scanning, print the fault history stored in each motor
+    #[arg(long)]
+    error_log: bool,
+
+    /// Clear the fault history of each motor after printing it (implies --error-log)
+    #[arg(long)]
+    clear_error_log: bool,
+
+    /// Custom command definitions (JSON list) used to decode sniffed frames
+    #[arg(long, value_name = "FILE")]
+    plugins: Option<PathBuf>,
+}
+
+pub fn run(bus: &BusArgs, args: ScanArgs) -> Result<()> {
+    if let Some(path) = &args.plugins {
+        for command in FieldCommand::load_all(path)? {
+            plugins::register(command)?;
+        }
+    }
+
+    // Print header
+    print_header();
+
+    // Initialize controller
+    let config = bus.config()?;
+    let controller = bus.open_unchecked(&config)?;
+
+    execute!(
+        stdout(),
+        Print("✅ ".green()),
+        Print(format!("扫描器初始化成功 (接口: {}, 波特率: {})\n", config.interface, config.bitrate))
+    )?;
+
+    // Scan motors
+    let motors = scan_motors(&controller, args.start_id, args.end_id)?;
+
+    // Print summary
+    print_summary(&motors)?;
+
+    if let Some(seconds) = args.bus_report {
+        print_bus_report(&controller, Duration::from_secs_f64(seconds))?;
+    }
+
+    if let Some(seconds) = args.sniff {
+        sniff(&controller, Duration::from_secs_f64(seconds))?;
+    }
+
+    // Quick actions on discovered motors
+    let online: Vec<u8> = motors.iter().filter(|m| m.is_online).map(|m| m.motor_id).collect();
+    if args.error_log || args.clear_error_log {
+        print_error_logs(&controller, &online, args.clear_error_log)?;
+    }
+    if !args.no_menu && !online.is_empty() && stdin().is_terminal() {
+        run_action_menu(&controller, &online, args.allow_unverified_writes)?;
+    }
+
+    Ok(())
+}
+
+fn print_header() {
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50).cyan()),
+        Print("\n"),
+        Print("🚀 LivelyBot 高扭矩电机扫描器\n".blue().bold()),
+        Print("开始扫描电机 ID (范围: "),
+    ).unwrap();
+}
+
+fn scan_motors(controller: &LivelyMotorController, start_id: u8, end_id: u8) -> Result<Vec<MotorInfo>> {
+    execute!(
+        stdout(),
+        Print(format!("{}-{}...", start_id, end_id)),
+        Print("\n"),
+        Print("超时时间: 50ms/电机\n"),
+        Print("按 Ctrl+C 可随时停止\n"),
+        Print("=".repeat(50)),
+        Print("\n")
+    )?;
+
+    let mut motors = Vec::new();
+
+    for motor_id in start_id..=end_id {
+        execute!(
+            stdout(),
+            Print(format!("扫描 ID {:2}... ", motor_id))
+        )?;
+
+        stdout().flush()?;
+
+        match controller.ping_motor(motor_id) {
+            Ok(info) => {
+                if info.is_online {
+                    execute!(
+                        stdout(),
+                        Print("✅ ".green()),
+                        Print(format!("[响应] 发现电机 ID: {} (CAN ID: 0x{:X})\n",
+                                   info.motor_id, info.motor_id))
+                    )?;
+                } else {
+                    execute!(stdout(), Print("无响应\n"))?;
+                }
+                motors.push(info);
+            }
+            Err(e) => {
+                execute!(
+                    stdout(),
+                    Print(format!("❌ 错误: {:#}\n", e))
+                )?;
+                motors.push(MotorInfo {
+                    motor_id,
+                    ..Default::default()
+                });
+            }
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    Ok(motors)
+}
+
+fn print_summary(motors: &[MotorInfo]) -> Result<()> {
+    let online_count = motors.iter().filter(|m| m.is_online).count();
+
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50)),
+        Print("\n"),
+        Print(format!("扫描完成！发现 {} 台电机在线\n", online_count))
+    )?;
+
+    if online_count > 0 {
+        execute!(stdout(), Print("\n在线电机详情:\n"))?;
+
+        for motor in motors {
+            if motor.is_online {
+                execute!(
+                    stdout(),
+                    Print("  ID ".cyan()),
+                    Print(format!("{}", motor.motor_id)),
+                    Print(" - ".cyan()),
+                    Print(&motor.name),
+                    Print(format!(" (响应时间: {}ms)", motor.response_time_ms))
+                )?;
+                if let Some(env) = TorqueEnvelope::for_model(&motor.name) {
+                    execute!(
+                        stdout(),
+                        Print(format!(" 力矩: 持续 {} Nm / 峰值 {} Nm ({:.1}s)",
+                                      env.continuous_nm, env.peak_nm, env.peak_duration.as_secs_f64()))
+                    )?;
+                }
+                execute!(stdout(), Print("\n"))?;
+            }
+        }
+    }
+
+    execute!(
+        stdout(),
+        Print("=".repeat(50)),
+        Print("\n")
+    )?;
+
+    Ok(())
+}
+fn print_error_logs(controller: &LivelyMotorController, motor_ids: &[u8], clear: bool) -> Result<()> {
+    execute!(stdout(), Print("\n📜 故障记录:\n"))?;
+    for &motor_id in motor_ids {
+        if !controller.supports(Feature::ErrorLog, motor_id) {
+            execute!(stdout(), Print(format!("  ID {}: 固件不支持故障记录\n", motor_id).yellow()))?;
+            continue;
+        }
+        let entries = match controller.read_error_log(motor_id) {
+            Ok(entries) => entries,
+            Err(e) => {
+                execute!(stdout(), Print(format!("  ID {}: 无法读取 ({:#})\n", motor_id, e).yellow()))?;
+                continue;
+            }
+        };
+        if entries.is_empty() {
+            execute!(stdout(), Print(format!("  ID {}: 无记录\n", motor_id)))?;
+        }
+        let uptime = controller.read_uptime(motor_id).ok();
+        for entry in &entries {
+            let when = match uptime.map(|u| entry.age(u)) {
+                Some(Some(age)) => format!("{} s 前", age.as_secs()),
+                Some(None) => format!("上电后 {} s (此前的上电周期)", entry.uptime_s),
+                None => format!("上电后 {} s", entry.uptime_s),
+            };
+            execute!(
+                stdout(),
+                Print(format!("  ID {}: ", motor_id).cyan()),
+                Print(format!("故障 0x{:02X}, {}\n", entry.code, when))
+            )?;
+        }
+        if clear && !entries.is_empty() {
+            controller.clear_error_log(motor_id)?;
+            execute!(stdout(), Print(format!("  ID {}: 已清除\n", motor_id).green()))?;
+        }
+    }
+    Ok(())
+}
+
+fn print_bus_report(controller: &LivelyMotorController, duration: Duration) -> Result<()> {
+    execute!(stdout(), Print(format!("\n📊 监听总线 {:.1}s...\n", duration.as_secs_f64())))?;
+
+    let mut monitor = BusMonitor::new(controller.bus());
+    let deadline = Instant::now() + duration;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        monitor.poll(remaining.min(Duration::from_millis(100)))?;
+    }
+
+    let report = monitor.load_report();
+    execute!(
+        stdout(),
+        Print(format!("电机流量: {:.1}%\n", report.motor_load * 100.0)),
+        Print(format!("其他节点流量: {:.1}% (预留 {:.1}%)\n", report.foreign_load * 100.0, report.reserved * 100.0))
+    )?;
+
+    let mut rates: Vec<_> = report.foreign_rates.iter().collect();
+    rates.sort_by_key(|(&id, _)| id);
+    for (id, rate) in rates {
+        execute!(stdout(), Print(format!("  ID 0x{:X}: {:.1} 帧/s\n", id, rate)))?;
+    }
+
+    let drops = monitor.rx_drops();
+    if drops.total() > 0 {
+        execute!(
+            stdout(),
+            Print("⚠️  ".yellow()),
+            Print(format!(
+                "接收丢帧 {} 帧 (内核 {}, 控制器溢出 {}, 队列 {}), 电机反馈可能丢失\n",
+                drops.total(),
+                drops.kernel_dropped,
+                drops.overruns,
+                drops.subscriber_dropped
+            ))
+        )?;
+    }
+    let malformed = controller.bus().malformed_frames();
+    if malformed > 0 {
+        execute!(
+            stdout(),
+            Print("⚠️  ".yellow()),
+            Print(format!("收到 {} 个长度不足的帧 (DLC < 8 或应答被截断), 已丢弃\n", malformed))
+        )?;
+    }
+
+    if !report.foreign_fits() {
+        execute!(
+            stdout(),
+            Print("⚠️  ".yellow()),
+            Print("其他节点流量超过预留带宽, 请增大 --reserve-bandwidth\n")
+        )?;
+    }
+    Ok(())
+}
+
+fn sniff(controller: &LivelyMotorController, duration: Duration) -> Result<()> {
+    execute!(stdout(), Print(format!("\n🔍 抓取总线帧 {:.1}s...\n", duration.as_secs_f64())))?;
+    for (command, name) in plugins::registered() {
+        execute!(stdout(), Print(format!("  插件 0x{:02X}: {}\n", command, name)))?;
+    }
+
+    let start = Instant::now();
+    let deadline = start + duration;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        let Some(frame) = controller.read_frame_with_timeout(remaining.as_millis().min(100) as u64)? else {
+            continue;
+        };
+        let id = match frame.id() {
+            Id::Standard(id) => id.as_raw() as u32,
+            Id::Extended(id) => id.as_raw(),
+        };
+        let data: Vec<String> = frame.data().iter().map(|b| format!("{:02X}", b)).collect();
+        execute!(
+            stdout(),
+            Print(format!(
+                "{:8.3}  0x{:08X}  {:<23}  {}\n",
+                start.elapsed().as_secs_f64(),
+                id,
+                data.join(" "),
+                protocol::describe_frame(id, frame.data())
+            ))
+        )?;
+    }
+    Ok(())
+}
+
+fn prompt(text: &str) -> Result<String> {
+    execute!(stdout(), Print(text))?;
+    stdout().flush()?;
+
+    let mut input = String::new();
+    stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+fn run_action_menu(controller: &LivelyMotorController, online: &[u8], allow_unverified_writes: bool) -> Result<()> {
+    let gated = if allow_unverified_writes { "" } else { " (需 --allow-unverified-writes)" };
+    loop {
+        let input = prompt(&format!("\n选择电机 ID 进行操作 {:?} (回车退出): ", online))?;
+        if input.is_empty() || input.eq_ignore_ascii_case("q") {
+            break;
+        }
+
+        let motor_id = match input.parse::<u8>() {
+            Ok(id) if online.contains(&id) => id,
+            _ => {
+                execute!(stdout(), Print("无效的电机 ID\n".red()))?;
+                continue;
+            }
+        };
+
+        execute!(
+            stdout(),
+            Print(format!("\n电机 {} 操作:\n", motor_id).cyan()),
+            Print("  1) 识别 (闪烁)\n"),
+            Print("  2) 读取状态\n"),
+            Print(format!("  3) 设置 ID{}\n", gated)),
+            Print(format!("  4) 设置零点{}\n", gated)),
+            Print("  5) 低力矩点动\n"),
+            Print("  b) 返回\n")
+        )?;
+
+        let result: Result<String> = match prompt("操作: ")?.as_str() {
+            "1" => controller.identify(motor_id).map(|_| "识别完成".to_string()).map_err(Into::into),
+            "2" => controller.read_motor_state(motor_id).map(|state| {
+                format!(
+                    "位置: {:.2}°, 速度: {:.3} r/s, 力矩: {:.3} Nm",
+                    state.position_deg, state.velocity_rps, state.torque_nm
+                )
+            }).map_err(Into::into),
+            "3" | "4" if !allow_unverified_writes => Err(anyhow!(
+                "设置 ID / 零点写入的寄存器 (0x5F / 0x5E) 未经固件文档确认, 确认适用于当前固件后加 --allow-unverified-writes"
+            )),
+            "3" => match prompt("新 ID (1-127): ")?.parse::<u8>() {
+                Ok(new_id) => controller
+                    .set_motor_id(motor_id, new_id)
+                    .map(|_| format!("ID 已修改: {} -> {} (重新扫描以刷新列表)", motor_id, new_id))
+                    .map_err(Into::into),
+                Err(_) => Ok("已取消".to_string()),
+            },
+            "4" => {
+                if prompt("确认将当前位置设为零点? (y/N): ")?.eq_ignore_ascii_case("y") {
+                    controller.set_zero(motor_id).map(|_| "零点已设置".to_string()).map_err(Into::into)
+                } else {
+                    Ok("已取消".to_string())
+                }
+            }
+            "5" => run_jog(controller, motor_id).map(|_| "点动结束, 电机已禁用".to_string()),
+            _ => continue,
+        };
+
+        match result {
+            Ok(message) => execute!(stdout(), Print("✅ ".green()), Print(format!("{}\n", message)))?,
+            Err(e) => execute!(stdout(), Print(format!("❌ 错误: {:#}\n", e).red()))?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Low-torque jog: hold ←/→ to move, release to stop, q/Esc to leave
+fn run_jog(controller: &LivelyMotorController, motor_id: u8) -> Result<()> {
+    const JOG_SPEED: f64 = 0.3;
+    const JOG_TORQUE_CAP: f64 = 0.5;
+
+    execute!(
+        stdout(),
+        Print(format!("点动模式 (限矩 {} Nm, 速度 {} r/s): 按住 ←/→ 点动, 松开停止, q 退出\n",
+                      JOG_TORQUE_CAP, JOG_SPEED))
+    )?;
+
+    // Report key release events where the terminal supports it, otherwise the
+    // jog stops once key repeats stop arriving (deadman timeout)
+    enable_raw_mode()?;
+    let release_events = supports_keyboard_enhancement().unwrap_or(false);
+    if release_events {
+        execute!(stdout(), PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+    }
+    let result = jog_loop(controller, motor_id, JOG_SPEED, JOG_TORQUE_CAP);
+    if release_events {
+        execute!(stdout(), PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    execute!(stdout(), Print("\n"))?;
+    result
+}
+
+fn jog_loop(controller: &LivelyMotorController, motor_id: u8, speed: f64, torque_cap: f64) -> Result<()> {
+    let mut session: Option<JogSession> = None;
+
+    loop {
+        if event::poll(Duration::from_millis(20))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    if let Some(mut active) = session.take() {
+                        active.stop()?;
+                    }
+                    continue;
+                }
+
+                let direction = match key.code {
+                    KeyCode::Left => JogDirection::Negative,
+                    KeyCode::Right => JogDirection::Positive,
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    _ => continue,
+                };
+
+                match session.as_mut() {
+                    Some(active) if !active.is_stopped() => {
+                        active.set_direction(direction);
+                        active.hold();
+                    }
+                    _ => {
+                        let mut active = controller.jog(motor_id, direction, speed, torque_cap)?;
+                        active.set_deadman(Duration::from_millis(600));
+                        session = Some(active);
+                    }
+                }
+            }
+        }
+
+        if let Some(active) = session.as_mut() {
+            match active.step()? {
+                JogStatus::Moving { torque_nm } => {
+                    execute!(
+                        stdout(),
+                        MoveToColumn(0),
+                        Clear(ClearType::CurrentLine),
+                        Print(format!("点动中... 力矩: {:.3} Nm", torque_nm.unwrap_or(0.0)))
+                    )?;
+                }
+                JogStatus::Overload { torque_nm } => {
+                    execute!(
+                        stdout(),
+                        MoveToColumn(0),
+                        Clear(ClearType::CurrentLine),
+                        Print(format!("⚠️  力矩超限 ({:.3} Nm), 已停止", torque_nm).yellow())
+                    )?;
+                    session = None;
+                }
+                JogStatus::Released | JogStatus::Stopped => session = None,
+            }
+        }
+    }
+
+    if let Some(mut active) = session {
+        active.stop()?;
+    }
+    Ok(())
+}
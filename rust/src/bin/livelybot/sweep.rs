@@ -0,0 +1,104 @@
+//! `livelybot sweep`: runs a sine excitation once per value of a parameter
+//! and prints a comparison table.
+
+use anyhow::Result;
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::{BusArgs, StopFlags};
+use livelybot_motor_control::{OscillateParams, Primitive, Sweep, SweepParam};
+use std::io::stdout;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Sweep options
+#[derive(clap::Args)]
+pub struct SweepArgs {
+    /// Motor under test; the only motor enabled while the sweep runs
+    #[arg(short, long, default_value = "1")]
+    motor_id: u8,
+
+    /// Swept parameter: kp, kd, torque_limit or command_rate
+    #[arg(short, long)]
+    param: String,
+
+    /// Values to run (comma-separated)
+    #[arg(long, value_delimiter = ',', required = true)]
+    values: Vec<f64>,
+
+    /// Sine amplitude in degrees
+    #[arg(long, default_value = "30.0")]
+    amplitude: f64,
+
+    /// Sine frequency in Hz
+    #[arg(long, default_value = "0.5")]
+    frequency: f64,
+
+    /// Length of each run in seconds
+    #[arg(long, default_value = "10.0")]
+    duration: f64,
+
+    /// Command rate in Hz when it is not the swept parameter
+    #[arg(long, default_value = "100.0")]
+    rate: f64,
+
+    /// Seconds the motor rests disabled between runs
+    #[arg(long, default_value = "5.0")]
+    rest: f64,
+
+    /// Save the full report as JSON
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// Save the table as CSV
+    #[arg(long)]
+    csv: Option<PathBuf>,
+}
+
+pub fn run(bus: &BusArgs, args: SweepArgs) -> Result<()> {
+    let stop = StopFlags::install()?;
+    let controller = bus.open(&bus.config()?)?;
+
+    let param = SweepParam::from_name(&args.param)?;
+    let excitation = Primitive::oscillate(OscillateParams {
+        center_deg: 0.0,
+        amplitude_deg: args.amplitude,
+        frequency_hz: args.frequency,
+        duration: Duration::from_secs_f64(args.duration),
+    });
+    let sweep = Sweep::new(param, &args.values, excitation)
+        .with_rate(args.rate)
+        .with_rest(Duration::from_secs_f64(args.rest));
+    sweep.validate()?;
+
+    execute!(
+        stdout(),
+        Print("🔬 ".cyan()),
+        Print(format!("电机 {} 扫描 {}: {:?} (Ctrl+C 停止)\n", args.motor_id, param.name(), args.values))
+    )?;
+    let report = sweep.run(&controller, args.motor_id, &stop.running, |point| {
+        let _ = execute!(
+            stdout(),
+            Print("✅ ".green()),
+            Print(format!(
+                "{} = {}: RMS 误差 {:.3}°, 最大误差 {:.3}°\n",
+                param.name(),
+                point.value,
+                point.report.rms_error_deg,
+                point.report.max_error_deg
+            ))
+        );
+    })?;
+
+    execute!(stdout(), Print("\n📊 扫描结果 (* 为 RMS 误差最小)\n".blue()), Print(report.table()))?;
+    if let Some(path) = &args.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)? + "\n")?;
+        execute!(stdout(), Print("💾 ".green()), Print(format!("报告已保存到 {}\n", path.display())))?;
+    }
+    if let Some(path) = &args.csv {
+        std::fs::write(path, report.to_csv())?;
+        execute!(stdout(), Print("💾 ".green()), Print(format!("表格已保存到 {}\n", path.display())))?;
+    }
+    Ok(())
+}
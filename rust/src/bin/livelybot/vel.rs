@@ -0,0 +1,130 @@
+//! `livelybot vel`: velocity control with intelligent emergency stop.
+
+use anyhow::Result;
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::{BusArgs, StopFlags};
+use livelybot_motor_control::{Console, ConsoleCommand, ControlLoop, LivelyMotorController, MAGIC_POS};
+use std::io::stdout;
+use std::sync::atomic::AtomicBool;
+
+/// Velocity control options
+#[derive(clap::Args)]
+pub struct VelArgs {
+    /// Motor ID (default: 1)
+    #[arg(short, long, default_value = "1")]
+    motor_id: u8,
+
+    /// Default acceleration (default: 15.0)
+    #[arg(short, long, default_value = "15.0")]
+    acceleration: f64,
+
+    /// Maximum brake acceleration (default: 30.0)
+    #[arg(long, default_value = "30.0")]
+    brake_acceleration: f64,
+}
+
+pub fn run(bus: &BusArgs, args: VelArgs) -> Result<()> {
+    let stop = StopFlags::install()?;
+
+    // Print header
+    print_header();
+
+    // Initialize controller
+    let controller = bus.open(&bus.config()?)?;
+
+    execute!(
+        stdout(),
+        Print("✅ ".green()),
+        Print(format!("控制器初始化成功 (电机 ID: {})\n", args.motor_id))
+    )?;
+
+    // Enable motor
+    controller.enable_velocity_mode(args.motor_id)?;
+    execute!(
+        stdout(),
+        Print("✅ ".green()),
+        Print("电机已激活，准备开始控制\n")
+    )?;
+
+    // Interactive input
+    run_interactive_mode(&controller, args.motor_id, &stop.running, args.acceleration, args.brake_acceleration)?;
+
+    // Cleanup
+    controller.disable_motor(args.motor_id)?;
+    execute!(stdout(), Print("🛑 ".yellow()), Print("电机已禁用\n"))?;
+
+    Ok(())
+}
+
+fn print_header() {
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50).cyan()),
+        Print("\n"),
+        Print("🏎️  速度 + 加速度模式 (智能紧急制动)\n".blue().bold()),
+        Print("命令:\n"),
+        Print("  [速度值]       -> 设置目标速度 (例如: 5.0, -2.0)\n"),
+        Print("  acc [数值]    -> 设置行驶加速度 (例如: acc 10.0)\n"),
+        Print("  0             -> 触发紧急停止\n"),
+        Print("  q             -> 退出\n"),
+        Print("=".repeat(50)),
+        Print("\n")
+    ).unwrap();
+}
+
+fn run_interactive_mode(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    running: &AtomicBool,
+    default_acc: f64,
+    brake_acc: f64,
+) -> Result<()> {
+    let console = Console::stdin("命令: ");
+    let mut target_velocity = 0.0;
+    let mut target_acceleration = default_acc.abs();
+
+    let mut control_loop = ControlLoop::new(100.0);
+    control_loop.run(running, |_| {
+        let inputs = console.poll();
+        let answered = !inputs.is_empty();
+        for input in inputs {
+            match input {
+                Ok(ConsoleCommand::Quit) => return Ok(false),
+                Ok(ConsoleCommand::Target(vel)) => {
+                    target_velocity = vel;
+                    if vel == 0.0 {
+                        execute!(stdout(), Print("   -> 🛑 紧急制动\n".yellow()))?;
+                    } else {
+                        execute!(stdout(), Print(format!("   -> 目标速度: {} rad/s\n", vel)))?;
+                    }
+
+                    // Brake at full deceleration, drive at the configured one
+                    let effective_acc = if vel == 0.0 { brake_acc } else { target_acceleration };
+                    controller.send_velocity_command_to(
+                        motor_id,
+                        MAGIC_POS,
+                        livelybot_motor_control::rps_to_velocity(target_velocity),
+                        livelybot_motor_control::rps2_to_acceleration(effective_acc),
+                    )?;
+                }
+                Ok(ConsoleCommand::Set { name, value }) if name == "acc" => {
+                    target_acceleration = value.abs();
+                    execute!(stdout(), Print(format!("   -> 行驶加速度设为: {} rad/s²\n", value)))?;
+                }
+                Ok(_) => execute!(stdout(), Print("   -> 不支持的命令\n".red()))?,
+                Err(e) => execute!(stdout(), Print(format!("   -> 输入错误: {}\n", e).red()))?,
+            }
+        }
+        if answered {
+            console.prompt();
+        }
+        Ok(true)
+    })?;
+
+    execute!(stdout(), Print(format!("\n⏱️  {}\n", control_loop.jitter())))?;
+    Ok(())
+}
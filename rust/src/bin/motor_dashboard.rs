@@ -0,0 +1,278 @@
+//! LivelyBot Motor Dashboard
+//!
+//! Live view of several joints with keyboard tuning: select a joint and a
+//! parameter, nudge it in bounded steps and see the value read back from the
+//! motor immediately.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    style::{Print, Stylize},
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+};
+use livelybot_motor_control::cli::{BusArgs, GenerateArgs};
+use livelybot_motor_control::tuning::{self, TunableParam, TuneBounds};
+use livelybot_motor_control::{AdaptiveRate, AdaptiveRateConfig, LivelyMotorController, MotorState};
+use std::collections::HashMap;
+use std::io::{stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// LivelyBot Motor Dashboard
+#[derive(Parser)]
+#[command(name = "motor_dashboard", author, version, about, long_about = None)]
+struct Args {
+    /// Comma-separated motor IDs (default: the joints of a --config robot description, else 1)
+    #[arg(short, long)]
+    motor_ids: Option<String>,
+
+    #[command(flatten)]
+    bus: BusArgs,
+
+    /// Highest torque limit the tuning keys can set (Nm)
+    #[arg(long, default_value = "6.0")]
+    max_torque: f32,
+
+    /// Refresh period in milliseconds
+    #[arg(long, default_value = "100")]
+    refresh_ms: u64,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+/// What the dashboard shows for one joint
+struct JointView {
+    motor_id: u8,
+    state: Option<MotorState>,
+    params: HashMap<TunableParam, f32>,
+}
+
+/// Row of the first joint
+const TABLE_ROW: u16 = 3;
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+
+    let config = args.bus.config()?;
+    let motor_ids = match (&args.motor_ids, &config.robot) {
+        (Some(ids), _) => ids
+            .split(',')
+            .map(|s| s.trim().parse::<u8>().map_err(|_| anyhow!("无效的电机 ID: {}", s)))
+            .collect::<Result<Vec<u8>>>()?,
+        (None, Some(robot)) => robot.motor_ids_on(&config.interface),
+        (None, None) => vec![1],
+    };
+    if motor_ids.is_empty() {
+        return Err(anyhow!("{} 上没有配置关节", config.interface));
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let controller = args.bus.open_unchecked(&config)?;
+
+    let mut joints: Vec<JointView> = motor_ids
+        .iter()
+        .map(|&motor_id| JointView {
+            motor_id,
+            state: None,
+            params: HashMap::new(),
+        })
+        .collect();
+    for joint in &mut joints {
+        read_params(&controller, joint);
+    }
+
+    enable_raw_mode()?;
+    execute!(stdout(), Hide, Clear(ClearType::All))?;
+    let result = run_dashboard(&controller, &mut joints, &args, &running);
+    execute!(stdout(), Show, MoveTo(0, TABLE_ROW + joints.len() as u16 + 3), Print("\n"))?;
+    disable_raw_mode()?;
+    result
+}
+
+fn read_params(controller: &LivelyMotorController, joint: &mut JointView) {
+    for param in TunableParam::ALL {
+        if let Ok(value) = tuning::read_param(controller, joint.motor_id, param) {
+            joint.params.insert(param, value);
+        }
+    }
+}
+
+fn run_dashboard(
+    controller: &LivelyMotorController,
+    joints: &mut [JointView],
+    args: &Args,
+    running: &AtomicBool,
+) -> Result<()> {
+    let mut selected = 0;
+    let mut param_index = 0;
+    let mut status = String::from("就绪");
+    // Refresh less often while the bus shows errors
+    let mut adaptive = AdaptiveRate::new(controller.bus(), AdaptiveRateConfig::default(), None)?;
+
+    while running.load(Ordering::SeqCst) {
+        for joint in joints.iter_mut() {
+            joint.state = controller.read_motor_state(joint.motor_id).ok();
+        }
+        let level = adaptive.level();
+        let errors = adaptive.check()?;
+        let refresh = controller.bus().telemetry_period(Duration::from_millis(args.refresh_ms));
+        if adaptive.level() > level {
+            status = format!(
+                "⚠️  总线错误 {} 次, 降级到 {} 级: 刷新周期 {} ms",
+                errors.total(),
+                adaptive.level(),
+                refresh.as_millis()
+            );
+        } else if adaptive.level() < level {
+            status = format!("总线恢复到 {} 级: 刷新周期 {} ms", adaptive.level(), refresh.as_millis());
+        }
+        draw(joints, selected, TunableParam::ALL[param_index], &status)?;
+
+        if !event::poll(refresh)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+
+        let steps = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            KeyCode::Up => {
+                selected = (selected + joints.len() - 1) % joints.len();
+                continue;
+            }
+            KeyCode::Down => {
+                selected = (selected + 1) % joints.len();
+                continue;
+            }
+            KeyCode::Left => {
+                param_index = (param_index + TunableParam::ALL.len() - 1) % TunableParam::ALL.len();
+                continue;
+            }
+            KeyCode::Right => {
+                param_index = (param_index + 1) % TunableParam::ALL.len();
+                continue;
+            }
+            KeyCode::Char('r') => {
+                read_params(controller, &mut joints[selected]);
+                status = format!("已重新读取电机 {} 的参数", joints[selected].motor_id);
+                continue;
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => 1,
+            KeyCode::Char('-') => -1,
+            KeyCode::PageUp => 10,
+            KeyCode::PageDown => -10,
+            _ => continue,
+        };
+
+        let param = TunableParam::ALL[param_index];
+        let joint = &mut joints[selected];
+        let Some(&current) = joint.params.get(&param) else {
+            status = format!("❌ 电机 {} 的 {} 未读取, 按 r 重试", joint.motor_id, param.name());
+            continue;
+        };
+
+        let target = TuneBounds::default_for(param, args.max_torque).nudge(current, steps);
+        status = match tuning::write_verified(controller, joint.motor_id, param, target) {
+            Ok(result) => {
+                joint.params.insert(param, result.read_back);
+                if result.verified() {
+                    format!("✅ 电机 {} {} = {:.3} (回读一致)", joint.motor_id, param.name(), result.read_back)
+                } else {
+                    format!(
+                        "⚠️  电机 {} {}: 写入 {:.3}, 回读 {:.3}",
+                        joint.motor_id,
+                        param.name(),
+                        result.requested,
+                        result.read_back
+                    )
+                }
+            }
+            Err(e) => format!("❌ {:#}", e),
+        };
+    }
+
+    Ok(())
+}
+
+fn draw(joints: &[JointView], selected: usize, param: TunableParam, status: &str) -> Result<()> {
+    let mut out = stdout();
+    execute!(
+        out,
+        MoveTo(0, 0),
+        Clear(ClearType::CurrentLine),
+        Print("📊 LivelyBot 电机仪表盘".blue().bold()),
+        MoveTo(0, 1),
+        Clear(ClearType::CurrentLine),
+        Print("↑/↓ 选择关节  ←/→ 选择参数  +/- 微调 (PgUp/PgDn 十步)  r 重新读取  q 退出"),
+        MoveTo(0, 2),
+        Clear(ClearType::CurrentLine),
+        Print(format!(
+            "  {:>3}  {:>9}  {:>9}  {:>8}  {:>8}  {:>8}  {:>8}",
+            "ID", "位置(°)", "速度(r/s)", "力矩(Nm)", "Kp", "Kd", "限矩(Nm)"
+        ))
+    )?;
+
+    for (row, joint) in joints.iter().enumerate() {
+        let (position, velocity, torque) = match joint.state {
+            Some(s) => (
+                format!("{:9.2}", s.position_deg),
+                format!("{:9.3}", s.velocity_rps),
+                format!("{:8.3}", s.torque_nm),
+            ),
+            None => (format!("{:>9}", "--"), format!("{:>9}", "--"), format!("{:>8}", "--")),
+        };
+
+        let mut line = format!(
+            "{} {:>3}  {}  {}  {}",
+            if row == selected { ">" } else { " " },
+            joint.motor_id,
+            position,
+            velocity,
+            torque
+        );
+        for p in TunableParam::ALL {
+            let value = match joint.params.get(&p) {
+                Some(v) => format!("{:.3}", v),
+                None => "--".to_string(),
+            };
+            if row == selected && p == param {
+                line.push_str(&format!("  {:>8}", format!("[{}]", value)));
+            } else {
+                line.push_str(&format!("  {:>8}", value));
+            }
+        }
+
+        execute!(out, MoveTo(0, TABLE_ROW + row as u16), Clear(ClearType::CurrentLine))?;
+        if row == selected {
+            execute!(out, Print(line.cyan()))?;
+        } else {
+            execute!(out, Print(line))?;
+        }
+    }
+
+    execute!(
+        out,
+        MoveTo(0, TABLE_ROW + joints.len() as u16 + 1),
+        Clear(ClearType::CurrentLine),
+        Print(format!("{}: {}", param.name(), status))
+    )?;
+    out.flush()?;
+    Ok(())
+}
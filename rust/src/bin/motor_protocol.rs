@@ -0,0 +1,78 @@
+//! LivelyBot Motor Protocol
+//!
+//! Prints the frames and registers implemented by this crate, either as a table
+//! or (with `--dump`) as JSON for external tools.
+
+use anyhow::Result;
+use clap::Parser;
+use livelybot_motor_control::cli::GenerateArgs;
+use livelybot_motor_control::protocol;
+use std::path::PathBuf;
+
+/// LivelyBot Motor Protocol
+#[derive(Parser)]
+#[command(name = "motor_protocol", author, version, about, long_about = None)]
+struct Args {
+    /// Print the machine-readable JSON description
+    #[arg(long)]
+    dump: bool,
+
+    /// Write the JSON description to a file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+
+    let description = protocol::describe();
+
+    if let Some(path) = &args.output {
+        std::fs::write(path, serde_json::to_string_pretty(&description)? + "\n")?;
+        return Ok(());
+    }
+    if args.dump {
+        println!("{}", serde_json::to_string_pretty(&description)?);
+        return Ok(());
+    }
+
+    println!("LivelyBot 电机协议 v{}", description.version);
+    println!("填充字节: 0x{:02X}, 回复标志: 0x{:04X}", description.padding, description.reply_flag);
+
+    println!("\n帧:");
+    for frame in &description.frames {
+        let id = if frame.addressed {
+            format!("0x{:04X} | id", frame.arbitration_id)
+        } else {
+            format!("0x{:04X}", frame.arbitration_id)
+        };
+        println!("  {:<16} {:<14} {}", frame.name, id, frame.description);
+        for field in &frame.fields {
+            println!(
+                "    [{}] {:<14} {:<6} {} / {}",
+                field.offset, field.name, field.value_type, field.scale, field.unit
+            );
+        }
+    }
+
+    println!("\n寄存器:");
+    for reg in &description.registers {
+        println!(
+            "  0x{:02X} {:<13} {:<6} {:<11} {}{}",
+            reg.address,
+            reg.name,
+            reg.value_type,
+            format!("{:?}", reg.access),
+            reg.description,
+            if reg.verified { "" } else { " (未验证)" }
+        );
+    }
+
+    Ok(())
+}
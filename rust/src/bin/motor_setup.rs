@@ -0,0 +1,250 @@
+//! LivelyBot Motor Setup Wizard
+//!
+//! Commissions a new robot in one pass: scan the bus, identify each motor,
+//! assign joint names and IDs, zero the joints, write gains and limits from a
+//! profile, save everything to motor flash and emit the robot TOML.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::GenerateArgs;
+use livelybot_motor_control::{LivelyMotorController, MotorSettings, ProfileSet};
+use std::fmt::Write as _;
+use std::io::{stdin, stdout, Write};
+use std::path::PathBuf;
+
+/// LivelyBot Motor Setup Wizard
+#[derive(Parser)]
+#[command(name = "motor_setup", author, version, about, long_about = None)]
+struct Args {
+    /// Starting motor ID (default: 1)
+    #[arg(short, long, default_value = "1")]
+    start_id: u8,
+
+    /// Ending motor ID (default: 14)
+    #[arg(short, long, default_value = "14")]
+    end_id: u8,
+
+    /// CAN interface (default: can0)
+    #[arg(short, long, default_value = "can0")]
+    interface: String,
+
+    /// CAN bitrate (default: 1000000)
+    #[arg(short, long, default_value = "1000000")]
+    bitrate: u32,
+
+    /// Gain / limit profiles (CSV or JSON); defaults are used without it
+    #[arg(long, value_name = "FILE")]
+    profiles: Option<PathBuf>,
+
+    /// Profile written to the motors
+    #[arg(long, default_value = "normal")]
+    profile: String,
+
+    /// Robot configuration written at the end
+    #[arg(short, long, default_value = "robot.toml")]
+    output: PathBuf,
+
+    /// Do not store the configuration in motor flash
+    #[arg(long)]
+    no_save: bool,
+
+    /// Use the CAN channel even if another program holds its lock
+    #[arg(long)]
+    force: bool,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+/// A commissioned joint
+struct JointSetup {
+    name: String,
+    motor_id: u8,
+    model: String,
+    settings: MotorSettings,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+
+    let profiles = args.profiles.as_ref().map(ProfileSet::import).transpose()?;
+    if let Some(profiles) = &profiles {
+        profiles.profile(&args.profile)?;
+    }
+
+    print_header();
+
+    let controller = if args.force {
+        LivelyMotorController::new_forced(&args.interface, args.bitrate)?
+    } else {
+        LivelyMotorController::new(&args.interface, args.bitrate)?
+    };
+    if !controller.owns_bus() {
+        execute!(stdout(), Print("⚠️  ".yellow()), Print("--force: 未持有总线锁, 其他程序可能同时控制电机\n"))?;
+    }
+
+    // 1. Scan
+    execute!(stdout(), Print("🔍 ".cyan()), Print(format!("扫描 ID {}-{}...\n", args.start_id, args.end_id)))?;
+    let found: Vec<_> = controller
+        .scan_range(args.start_id, args.end_id)?
+        .into_iter()
+        .filter(|m| m.is_online)
+        .collect();
+    if found.is_empty() {
+        return Err(anyhow!("未发现电机"));
+    }
+    execute!(stdout(), Print("✅ ".green()), Print(format!("发现 {} 个电机\n", found.len())))?;
+
+    // IDs currently answering on the bus, updated as motors are renumbered
+    let mut occupied: Vec<u8> = found.iter().map(|m| m.motor_id).collect();
+    let mut joints = Vec::new();
+
+    for motor in &found {
+        execute!(
+            stdout(),
+            Print("\n"),
+            Print(format!("--- 电机 {} ({}) ---\n", motor.motor_id, motor.name).cyan())
+        )?;
+
+        // 2. Identify and name
+        controller.identify(motor.motor_id)?;
+        let name = prompt("LED 正在闪烁, 输入关节名称 (回车跳过该电机): ")?;
+        if name.is_empty() {
+            continue;
+        }
+        if joints.iter().any(|j: &JointSetup| j.name == name) {
+            execute!(stdout(), Print(format!("关节名称 {} 已使用, 跳过\n", name).red()))?;
+            continue;
+        }
+
+        // 3. Assign ID
+        let motor_id = assign_id(&controller, motor.motor_id, &mut occupied)?;
+
+        // 4. Zero
+        prompt("将关节移动到零位后按回车: ")?;
+        controller.set_zero(motor_id)?;
+
+        // 5. Gains and limits
+        let settings = profiles
+            .as_ref()
+            .and_then(|p| p.profile(&args.profile).ok()?.get(&motor_id).copied())
+            .unwrap_or_default();
+        settings.write(&controller, motor_id)?;
+
+        // 6. Save
+        if !args.no_save {
+            controller.save_config(motor_id)?;
+        }
+
+        execute!(
+            stdout(),
+            Print("✅ ".green()),
+            Print(format!(
+                "{}: ID {}, kp {}, kd {}, 限矩 {} Nm{}\n",
+                name,
+                motor_id,
+                settings.kp,
+                settings.kd,
+                settings.torque_limit,
+                if args.no_save { "" } else { ", 已保存" }
+            ))
+        )?;
+        joints.push(JointSetup {
+            name,
+            motor_id,
+            model: motor.name.clone(),
+            settings,
+        });
+    }
+
+    // 7. Robot configuration
+    let toml = robot_toml(&args, &joints);
+    std::fs::write(&args.output, toml)
+        .map_err(|e| anyhow!("Cannot write robot config {}: {}", args.output.display(), e))?;
+    execute!(
+        stdout(),
+        Print("\n📄 ".cyan()),
+        Print(format!("已写入 {} ({} 个关节)\n", args.output.display(), joints.len()))
+    )?;
+
+    Ok(())
+}
+
+fn print_header() {
+    execute!(
+        stdout(),
+        Print("\n"),
+        Print("=".repeat(50).cyan()),
+        Print("\n"),
+        Print("🧰 电机调试向导\n".blue().bold()),
+        Print("扫描 → 识别 → 分配 ID → 零点 → 增益/限幅 → 保存 → robot.toml\n"),
+        Print("=".repeat(50)),
+        Print("\n")
+    ).unwrap();
+}
+
+fn prompt(text: &str) -> Result<String> {
+    execute!(stdout(), Print(text))?;
+    stdout().flush()?;
+
+    let mut input = String::new();
+    stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Ask for the joint's ID and renumber the motor if it changes
+fn assign_id(controller: &LivelyMotorController, current: u8, occupied: &mut Vec<u8>) -> Result<u8> {
+    loop {
+        let input = prompt(&format!("新 ID (1-127, 回车保持 {}): ", current))?;
+        if input.is_empty() {
+            return Ok(current);
+        }
+
+        match input.parse::<u8>() {
+            Ok(new_id) if new_id == current => return Ok(current),
+            Ok(new_id) if occupied.contains(&new_id) => {
+                execute!(stdout(), Print(format!("ID {} 已被占用\n", new_id).red()))?;
+            }
+            Ok(new_id) => match controller.set_motor_id(current, new_id) {
+                Ok(()) => {
+                    occupied.retain(|&id| id != current);
+                    occupied.push(new_id);
+                    return Ok(new_id);
+                }
+                Err(e) => execute!(stdout(), Print(format!("❌ 错误: {:#}\n", e).red()))?,
+            },
+            Err(_) => execute!(stdout(), Print("无效的 ID\n".red()))?,
+        }
+    }
+}
+
+fn robot_toml(args: &Args, joints: &[JointSetup]) -> String {
+    let mut toml = String::new();
+    let _ = writeln!(toml, "# Generated by motor_setup");
+    let _ = writeln!(toml);
+    let _ = writeln!(toml, "[bus]");
+    let _ = writeln!(toml, "interface = {:?}", args.interface);
+    let _ = writeln!(toml, "bitrate = {}", args.bitrate);
+    if args.profiles.is_some() {
+        let _ = writeln!(toml, "profile = {:?}", args.profile);
+    }
+
+    for joint in joints {
+        let _ = writeln!(toml);
+        let _ = writeln!(toml, "[[joints]]");
+        let _ = writeln!(toml, "name = {:?}", joint.name);
+        let _ = writeln!(toml, "motor_id = {}", joint.motor_id);
+        let _ = writeln!(toml, "model = {:?}", joint.model);
+        let _ = writeln!(toml, "kp = {:?}", joint.settings.kp);
+        let _ = writeln!(toml, "kd = {:?}", joint.settings.kd);
+        let _ = writeln!(toml, "torque_limit = {:?}", joint.settings.torque_limit);
+    }
+    toml
+}
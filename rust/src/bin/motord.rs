@@ -0,0 +1,166 @@
+//! LivelyBot Motor Daemon
+//!
+//! Runs the motor layer as a headless service on the robot: owns the CAN bus,
+//! serves the UDP bridge interface (the same protocol as
+//! `robot_coordinator agent`, with per-client authority levels), pings every configured motor as a watchdog and
+//! disables all motors when one falls silent. SIGTERM or SIGINT parks the
+//! robot (if a park config is given) and disables the motors before exiting.
+//! Under systemd (`Type=notify`) it reports readiness and status and sends
+//! watchdog keep-alives while the bridge is serving. With `mirror` set, the
+//! motors' bus traffic is forwarded over UDP for logging on another machine.
+
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use livelybot_motor_control::cli::GenerateArgs;
+use livelybot_motor_control::service::{ServiceConfig, SystemdNotifier};
+use livelybot_motor_control::{
+    BridgeAgent, ControlLoop, LivelyMotorController, MotorGroup, ParkConfig, ParkRunner, TelemetryRecorder,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+/// LivelyBot Motor Daemon
+#[derive(Parser)]
+#[command(name = "motord", author, version, about, long_about = None)]
+struct Args {
+    /// Service config or robot description (JSON, TOML or YAML)
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Use the CAN channel even if another program holds its lock
+    #[arg(long)]
+    force: bool,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+    let config = ServiceConfig::load(args.config.ok_or(anyhow!("--config is required"))?)?;
+    let notifier = SystemdNotifier::from_env()?;
+
+    // SIGTERM as well as SIGINT (ctrlc's `termination` feature)
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let controller = if args.force {
+        LivelyMotorController::new_forced(&config.interface, config.bitrate)?
+    } else {
+        LivelyMotorController::new(&config.interface, config.bitrate)?
+    };
+    // Feedback keeps flowing to subscribers between watchdog rounds
+    controller.start_receiver();
+    let group = match &config.robot {
+        Some(robot) => robot.group(&controller)?,
+        None => MotorGroup::new(&controller, &config.motor_ids)?,
+    };
+    let offline = group.offline();
+    if !offline.is_empty() {
+        eprintln!("motord: motors {:?} do not answer", offline);
+    }
+
+    // Kept alive until exit; logging happens on the receiving machine
+    let _mirror = match &config.mirror {
+        Some(target) => {
+            println!("motord: mirroring motor traffic to {}", target);
+            Some(TelemetryRecorder::mirror_udp(&controller, target.as_str(), &config.motor_ids)?)
+        }
+        None => None,
+    };
+
+    let agent = BridgeAgent::bind(&controller, &config.listen)?.with_policy(config.access.clone());
+    let status = format!("serving {} motors on {} at {}", group.len(), config.interface, agent.local_addr()?);
+    println!("motord: {}", status);
+    notifier.ready(&status)?;
+
+    let served = thread::scope(|scope| {
+        let bridge = scope.spawn(|| agent.serve(&running));
+        let tripped = watch(&config, &group, &notifier, &running, || bridge.is_finished());
+        running.store(false, Ordering::SeqCst);
+        let served = bridge.join().map_err(|_| anyhow!("bridge thread panicked"))?;
+        served.and(tripped)
+    });
+
+    notifier.stopping("shutting down")?;
+    println!("motord: shutting down");
+    // A tripped watchdog left the motors disabled; parking would enable them again
+    let parked = match (&config.park_config, &served) {
+        (Some(path), Ok(false)) => park(&controller, path, &config.park_pose),
+        _ => Ok(()),
+    };
+    let disabled = group.disable_all();
+    served.and(parked).and(disabled)
+}
+
+/// Ping the motors every watchdog period until `running` is cleared or the
+/// bridge stops; keeps the systemd watchdog fed meanwhile. Returns whether the
+/// motor watchdog is tripped at the end.
+fn watch<F>(
+    config: &ServiceConfig,
+    group: &MotorGroup,
+    notifier: &SystemdNotifier,
+    running: &AtomicBool,
+    bridge_stopped: F,
+) -> Result<bool>
+where
+    F: Fn() -> bool,
+{
+    let period = notifier
+        .watchdog_timeout()
+        .map_or(config.watchdog_period(), |timeout| config.watchdog_period().min(timeout / 2));
+    let mut last_seen: BTreeMap<u8, Instant> = group.motor_ids().iter().map(|&id| (id, Instant::now())).collect();
+    let mut tripped = false;
+
+    ControlLoop::with_period(period).run(running, |_| {
+        if bridge_stopped() {
+            return Ok(false);
+        }
+        let offline = group.offline();
+        for (id, seen) in last_seen.iter_mut() {
+            if !offline.contains(id) {
+                *seen = Instant::now();
+            }
+        }
+
+        let silent: Vec<u8> = last_seen
+            .iter()
+            .filter(|(_, seen)| seen.elapsed() > config.motor_timeout())
+            .map(|(&id, _)| id)
+            .collect();
+        if !silent.is_empty() && !tripped {
+            tripped = true;
+            let status = format!("watchdog: motors {:?} silent, all motors disabled", silent);
+            eprintln!("motord: {}", status);
+            notifier.status(&status)?;
+            if let Err(e) = group.disable_all() {
+                eprintln!("motord: {:#}", e);
+            }
+        } else if silent.is_empty() && tripped {
+            tripped = false;
+            println!("motord: all motors answer again");
+            notifier.status("all motors answer again; re-enable to resume")?;
+        }
+
+        notifier.watchdog()?;
+        Ok(true)
+    })?;
+    Ok(tripped)
+}
+
+fn park(controller: &LivelyMotorController, path: &Path, pose: &str) -> Result<()> {
+    let config = ParkConfig::load(path)?;
+    ParkRunner::new(controller, config).park(pose, &AtomicBool::new(true), |stage| {
+        println!("motord: park stage {}", stage.name);
+    })
+}
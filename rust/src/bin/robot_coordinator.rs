@@ -0,0 +1,190 @@
+//! LivelyBot Robot Coordinator
+//!
+//! Runs choreographed routines across several robots. Each robot runs
+//! `robot_coordinator agent`, which owns its CAN bus; a single
+//! `robot_coordinator run` process synchronizes the clocks of all agents and
+//! starts one trajectory per robot at the same instant.
+
+use anyhow::{Result, anyhow};
+use clap::{Parser, Subcommand};
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::GenerateArgs;
+use livelybot_motor_control::remote::DEFAULT_BRIDGE_PORT;
+use livelybot_motor_control::{BridgeAgent, ControlLoop, Coordinator, JointMapping, LivelyMotorController, Trajectory};
+use std::io::stdout;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// LivelyBot Robot Coordinator
+#[derive(Parser)]
+#[command(name = "robot_coordinator", author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Mode>,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+#[derive(Subcommand)]
+enum Mode {
+    /// Accept routines from a coordinator and play them on the local bus
+    Agent {
+        /// UDP address to listen on
+        #[arg(short, long, default_value_t = format!("0.0.0.0:{}", DEFAULT_BRIDGE_PORT))]
+        listen: String,
+
+        /// CAN interface (default: can0)
+        #[arg(short, long, default_value = "can0")]
+        interface: String,
+
+        /// CAN bitrate (default: 1000000)
+        #[arg(short, long, default_value = "1000000")]
+        bitrate: u32,
+
+        /// Use the CAN channel even if another program holds its lock
+        #[arg(long)]
+        force: bool,
+    },
+    /// Start a routine on several agents at the same time
+    Run {
+        /// One robot's part as HOST[:PORT]=TRAJECTORY,MAPPING (repeatable)
+        #[arg(short, long, required = true)]
+        part: Vec<String>,
+
+        /// Routine name reported by the agents
+        #[arg(long, default_value = "routine")]
+        name: String,
+
+        /// Delay between dispatch and the synchronized start in ms
+        #[arg(long, default_value = "500")]
+        lead_ms: u64,
+
+        /// Streaming rate on the agents in Hz
+        #[arg(long, default_value = "100")]
+        rate: f64,
+
+        /// Maximum velocity in r/s
+        #[arg(long, default_value = "2.0")]
+        max_velocity: f64,
+
+        /// Maximum torque in Nm
+        #[arg(long, default_value = "3.0")]
+        max_torque: f64,
+
+        /// Access token for agents that require one (e.g. motord)
+        #[arg(long)]
+        token: Option<String>,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    match args.command {
+        Some(Mode::Agent { listen, interface, bitrate, force }) => {
+            let controller = if force {
+                LivelyMotorController::new_forced(&interface, bitrate)?
+            } else {
+                LivelyMotorController::new(&interface, bitrate)?
+            };
+            let agent = BridgeAgent::bind(&controller, &listen)?;
+            let local_addr = agent.local_addr()?;
+            execute!(
+                stdout(),
+                Print("📡 ".green()),
+                Print(format!("协调代理已启动: {} ({})\n", local_addr, interface))
+            )?;
+            agent.serve(&running)?;
+        }
+        Some(Mode::Run { part, name, lead_ms, rate, max_velocity, max_torque, token }) => {
+            let parts = part.iter().map(|p| parse_part(p)).collect::<Result<Vec<_>>>()?;
+            let agents: Vec<SocketAddr> = parts.iter().map(|(a, _)| *a).collect();
+            let mut coordinator = Coordinator::new(&agents)?;
+            if let Some(token) = &token {
+                coordinator = coordinator.with_token(token);
+            }
+
+            for clock in coordinator.sync_clocks(8)? {
+                execute!(
+                    stdout(),
+                    Print("⏱️  ".cyan()),
+                    Print(format!(
+                        "{}: 时钟偏差 {:+.3} ms, 往返 {:.3} ms\n",
+                        clock.agent,
+                        clock.offset_us as f64 / 1000.0,
+                        clock.rtt.as_secs_f64() * 1000.0
+                    ))
+                )?;
+            }
+
+            let duration = parts.iter().map(|(_, t)| t.duration()).max().unwrap_or_default();
+            coordinator.run_synchronized(
+                &name,
+                &parts,
+                Duration::from_millis(lead_ms),
+                rate,
+                max_velocity,
+                max_torque,
+            )?;
+            execute!(stdout(), Print("🚀 ".green()), Print(format!("已启动 {} 台机器人\n", parts.len())))?;
+
+            // Forward Ctrl+C to the agents while waiting for them to finish
+            let finished = AtomicBool::new(false);
+            let result = std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    let _ = ControlLoop::with_period(Duration::from_millis(20)).run(&AtomicBool::new(true), |_| {
+                        if !running.load(Ordering::SeqCst) {
+                            let _ = coordinator.stop();
+                            return Ok(false);
+                        }
+                        Ok(!finished.load(Ordering::SeqCst))
+                    });
+                });
+                let result = coordinator.wait_finished(duration + Duration::from_secs(5));
+                finished.store(true, Ordering::SeqCst);
+                result
+            });
+            result?;
+            execute!(stdout(), Print("✅ ".green()), Print("动作完成\n"))?;
+        }
+        None => return Err(anyhow!("请指定子命令: agent 或 run")),
+    }
+
+    Ok(())
+}
+
+/// Parse `HOST[:PORT]=TRAJECTORY,MAPPING`
+fn parse_part(part: &str) -> Result<(SocketAddr, Trajectory)> {
+    let (host, files) = part
+        .split_once('=')
+        .ok_or(anyhow!("invalid part '{}', expected HOST[:PORT]=TRAJECTORY,MAPPING", part))?;
+    let host = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, DEFAULT_BRIDGE_PORT)
+    };
+    let addr = host
+        .to_socket_addrs()?
+        .next()
+        .ok_or(anyhow!("cannot resolve {}", host))?;
+
+    let (trajectory, mapping) = files
+        .split_once(',')
+        .ok_or(anyhow!("invalid part '{}', missing joint mapping file", part))?;
+    Ok((addr, Trajectory::import(trajectory, &JointMapping::load(mapping)?)?))
+}
@@ -0,0 +1,107 @@
+//! LivelyBot ROS 2 Bridge
+//!
+//! Exposes the joints of a joint mapping as ROS 2 topics and a service through
+//! rosbridge_server, see [`Ros2Bridge`]. The motors are disabled on exit.
+//! Requires the `ros2` feature.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use livelybot_motor_control::cli::{BusArgs, GenerateArgs, StopFlags};
+use livelybot_motor_control::ros2::DEFAULT_ROSBRIDGE_URL;
+use livelybot_motor_control::{JointMapping, Ros2Bridge};
+use std::io::stdout;
+use std::path::PathBuf;
+
+/// LivelyBot ROS 2 Bridge
+#[derive(Parser)]
+#[command(name = "ros2_bridge", author, version, about, long_about = None)]
+struct Args {
+    /// Joint name → motor mapping (JSON)
+    #[arg(long, value_name = "FILE")]
+    mapping: Option<PathBuf>,
+
+    #[command(flatten)]
+    bus: BusArgs,
+
+    /// rosbridge_server WebSocket URL
+    #[arg(long, default_value = DEFAULT_ROSBRIDGE_URL)]
+    url: String,
+
+    /// Prefix of the topic and service names
+    #[arg(long, default_value = "/livelybot")]
+    namespace: String,
+
+    /// joint_states rate in Hz
+    #[arg(long, default_value = "50.0")]
+    rate: f64,
+
+    /// Velocity limit in r/s of commands without velocity
+    #[arg(long, default_value = "2.0")]
+    max_vel: f64,
+
+    /// Torque limit in Nm of commands without effort
+    #[arg(long, default_value = "3.0")]
+    max_torque: f64,
+
+    /// Enable the motors at start instead of waiting for the enable service
+    #[arg(long)]
+    enable: bool,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.generate.run::<Args>()? {
+        return Ok(());
+    }
+    if !(args.rate > 0.0 && args.rate.is_finite()) {
+        return Err(anyhow!("--rate must be positive"));
+    }
+    let mapping = JointMapping::load(args.mapping.as_ref().ok_or(anyhow!("--mapping is required"))?)?;
+    let stop = StopFlags::install()?;
+    let controller = args.bus.open(&args.bus.config()?)?;
+
+    let mut bridge = Ros2Bridge::new(&controller, mapping.clone())
+        .with_namespace(&args.namespace)
+        .with_rate(args.rate)
+        .with_limits(args.max_vel, args.max_torque);
+    if args.enable {
+        bridge.set_enabled(true)?;
+    }
+
+    execute!(
+        stdout(),
+        Print("🤖 ".cyan()),
+        Print(format!(
+            "{} 个关节 → {} ({}/joint_states, {}/joint_commands, {}/enable)\n",
+            mapping.joints.len(),
+            args.url,
+            args.namespace,
+            args.namespace,
+            args.namespace
+        ))
+    )?;
+
+    let result = bridge.serve(&args.url, &stop.running);
+    for motor_id in mapping.motor_ids() {
+        controller.disable_motor(motor_id).ok();
+    }
+    if bridge.rejected_commands() > 0 {
+        execute!(
+            stdout(),
+            Print("⚠️  ".yellow()),
+            Print(format!(
+                "{} 条指令被拒绝, 最后一次: {}\n",
+                bridge.rejected_commands(),
+                bridge.last_error().unwrap_or_default()
+            ))
+        )?;
+    }
+    result
+}
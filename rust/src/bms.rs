@@ -0,0 +1,145 @@
+//! Battery BMS pass-through decoding
+//!
+//! Many platforms put the battery management system on the motor bus. A
+//! [`BmsMonitor`] picks the BMS frames out of the shared bus and decodes pack
+//! voltage, current and state of charge into a [`BatteryState`], which the
+//! [`SafetyMonitor`](crate::SafetyMonitor) can use to lower torque limits when
+//! the battery sags.
+
+use crate::{BatteryState, BusSubscription, CanBus, Endianness, ScaledField};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use socketcan::{CanFrame, CanId, EmbeddedFrame};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Where a BMS reports pack voltage, current and state of charge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BmsFormat {
+    pub name: String,
+    /// Arbitration ID of the status frame
+    pub id: u32,
+    /// Frame sent to request a status frame, for BMSs that only answer polls
+    #[serde(default)]
+    pub request_id: Option<u32>,
+    #[serde(default)]
+    pub endianness: Endianness,
+    /// Pack voltage in V
+    pub voltage: ScaledField,
+    /// Pack current in A (positive = discharge)
+    pub current: ScaledField,
+    /// State of charge in %
+    #[serde(default)]
+    pub soc: Option<ScaledField>,
+}
+
+impl BmsFormat {
+    /// Daly BMS status frame 0x90 (polled, BMS address 0x01, host 0x40)
+    pub fn daly() -> Self {
+        let field = |offset, bias| ScaledField {
+            offset,
+            size: 2,
+            signed: false,
+            scale: 0.1,
+            bias,
+        };
+        Self {
+            name: "daly".to_string(),
+            id: 0x1890_4001,
+            request_id: Some(0x1890_0140),
+            endianness: Endianness::Big,
+            voltage: field(0, 0.0),
+            current: field(4, -30000.0),
+            soc: Some(field(6, 0.0)),
+        }
+    }
+
+    /// Built-in format called `name`
+    pub fn known(name: &str) -> Result<Self> {
+        match name {
+            "daly" => Ok(Self::daly()),
+            _ => Err(anyhow!("unknown BMS format '{}' (known: daly)", name)),
+        }
+    }
+
+    /// Load a custom format from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read BMS format {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Decode `frame` if it is this BMS's status frame; `None` also if it is too short for the fields
+    pub fn decode(&self, frame: &CanFrame) -> Option<BatteryState> {
+        if crate::raw_id(frame) != self.id {
+            return None;
+        }
+        let data = frame.data();
+        Some(BatteryState {
+            timestamp: Instant::now(),
+            voltage_v: self.voltage.decode(data, self.endianness)?,
+            current_a: self.current.decode(data, self.endianness)?,
+            soc_pct: match &self.soc {
+                Some(field) => Some(field.decode(data, self.endianness)?),
+                None => None,
+            },
+        })
+    }
+}
+
+/// Collects BMS status frames from the shared bus
+pub struct BmsMonitor {
+    subscription: BusSubscription,
+    format: BmsFormat,
+    latest: Option<BatteryState>,
+}
+
+impl BmsMonitor {
+    pub fn new(bus: &Arc<CanBus>, format: BmsFormat) -> Self {
+        Self {
+            subscription: bus.subscribe(),
+            format,
+            latest: None,
+        }
+    }
+
+    pub fn format(&self) -> &BmsFormat {
+        &self.format
+    }
+
+    /// Ask a polled BMS for a status frame; no-op for broadcasting ones
+    pub fn request(&self) -> Result<()> {
+        let Some(id) = self.format.request_id else {
+            return Ok(());
+        };
+        let can_id = CanId::extended(id).ok_or(anyhow!("Invalid CAN ID 0x{:X}", id))?;
+        let frame = CanFrame::new(can_id, &[0; 8]).ok_or(anyhow!("Failed to create CAN frame 0x{:X}", id))?;
+        Ok(self.subscription.bus().send(&frame)?)
+    }
+
+    /// Read frames for up to `timeout`, returning the first battery state decoded
+    pub fn poll(&mut self, timeout: Duration) -> Result<Option<BatteryState>> {
+        let deadline = Instant::now() + timeout;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let Some(frame) = self.subscription.recv_timeout(remaining)? else {
+                break;
+            };
+            if let Some(state) = self.format.decode(&frame) {
+                self.latest = Some(state);
+                return Ok(Some(state));
+            }
+            if crate::raw_id(&frame) == self.format.id {
+                // Our status frame, but too short for the configured fields
+                self.subscription.bus().note_malformed();
+            }
+        }
+        Ok(None)
+    }
+
+    /// Last decoded battery state
+    pub fn latest(&self) -> Option<BatteryState> {
+        self.latest
+    }
+}
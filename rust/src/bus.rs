@@ -0,0 +1,672 @@
+//! Shared CAN bus handle
+//!
+//! One [`CanBus`] owns the socket of an interface and is shared (via `Arc`) by
+//! every controller, scanner and [`BusMonitor`] in the process. Whichever
+//! component is waiting reads the next frame off the socket and hands a copy to
+//! every subscriber, so a ping waiting for its reply no longer swallows the
+//! feedback frames another component is waiting for.
+//!
+//! Frames are only read while someone waits, so feedback arriving while every
+//! component sleeps piles up in the socket buffer and is lost once it fills.
+//! [`CanBus::start_receiver`] instead reads the socket continuously on a
+//! background thread; subscribers then just wait on their queues.
+//!
+//! The socket does not see its own transmissions; [`CanBus::subscribe_sent`]
+//! gets a copy of every frame this process sends instead.
+//!
+//! Frames move through a [`CanTransport`]: a socket, or for tests and desk
+//! debugging a replayed candump log ([`CanBus::replay`], logged frames arrive
+//! with their original spacing and sent frames go nowhere) or simulated
+//! motors ([`MockTransport`](crate::MockTransport)).
+
+use crate::candump::{CandumpLog, Replay};
+use crate::events::{EventBus, EventKind};
+use crate::shaping::{self, BusLoadReport, LoadCounter, LoadShaper, QueryThrottle};
+use crate::error::{self, Result};
+use crate::transport::{CanTransport, RawFrame, SocketTransport};
+use crate::BusLock;
+use anyhow::anyhow;
+use socketcan::CanFrame;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError, TrySendError};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Frames buffered per subscriber before new frames are dropped for it
+pub const SUBSCRIBER_QUEUE_LEN: usize = 256;
+
+/// Arbitration IDs a [`BusMonitor`] counts separately; frames of further IDs
+/// are only counted in the totals, so a node sending random IDs can't grow
+/// the tables without bound
+pub const MAX_TRACKED_IDS: usize = 4096;
+
+/// Longest single socket read while pumping, so other readers get a turn
+const PUMP_SLICE: Duration = Duration::from_millis(10);
+
+/// One CAN interface shared by all components of a process
+///
+/// The transport is a trait object rather than a type parameter. It is picked
+/// at run time (`--candump` in the CLI, [`reopen`](Self::reopen) hands back a
+/// new one), and a parameter would have to be carried by every type holding a
+/// controller, so code tested on a [`MockTransport`](crate::MockTransport)
+/// would no longer be the same type that runs on the socket. One virtual call
+/// per frame is nothing next to the syscall behind it.
+pub struct CanBus {
+    transport: Box<dyn CanTransport>,
+    /// Frames come from a candump log
+    replay: bool,
+    channel: String,
+    bitrate: u32,
+    /// Behind a mutex so [`reopen`](Self::reopen) can hand it to the new socket
+    bus_lock: Mutex<Option<BusLock>>,
+    /// Held by the thread currently reading the socket
+    reader: Mutex<()>,
+    subscribers: Mutex<Vec<(u64, SyncSender<CanFrame>)>>,
+    /// Subscribers to the frames sent through this bus
+    sent_subscribers: Mutex<Vec<(u64, SyncSender<CanFrame>)>>,
+    next_subscriber: AtomicU64,
+    /// Held while sending, so frames are spaced in transmit order
+    shaper: Mutex<LoadShaper>,
+    /// Spacing of motor queries while the bus is degraded
+    throttle: Mutex<QueryThrottle>,
+    /// Frames the transport failed to send
+    send_failures: AtomicU64,
+    /// Frames skipped for subscribers whose queue was full
+    subscriber_drops: AtomicU64,
+    /// Loss counters at the last [`CanBus::new_rx_drops`]
+    rx_drops_seen: Mutex<RxDropStats>,
+    /// Received frames rejected by a parser as too short
+    malformed: AtomicU64,
+    /// Set while the background receiver runs
+    receiving: AtomicBool,
+    receiver: Mutex<Option<JoinHandle<()>>>,
+    /// Failed socket reads of the background receiver
+    receive_errors: AtomicU64,
+}
+
+/// Receive-side frame loss counters
+///
+/// Lost feedback otherwise looks exactly like a motor that stopped answering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RxDropStats {
+    /// Frames the kernel dropped (`rx_dropped`), e.g. because our reader fell behind
+    pub kernel_dropped: u64,
+    /// Controller receive overruns (`rx_over_errors` + `rx_fifo_errors`)
+    pub overruns: u64,
+    /// Frames not handed to a subscriber because its queue was full
+    pub subscriber_dropped: u64,
+}
+
+impl RxDropStats {
+    pub fn total(&self) -> u64 {
+        self.kernel_dropped + self.overruns + self.subscriber_dropped
+    }
+
+    /// Losses since an `earlier` reading
+    pub fn since(&self, earlier: &RxDropStats) -> RxDropStats {
+        RxDropStats {
+            kernel_dropped: self.kernel_dropped.saturating_sub(earlier.kernel_dropped),
+            overruns: self.overruns.saturating_sub(earlier.overruns),
+            subscriber_dropped: self.subscriber_dropped.saturating_sub(earlier.subscriber_dropped),
+        }
+    }
+}
+
+/// Error counters of a bus
+///
+/// Rising counters mean noise or marginal wiring; see
+/// [`AdaptiveRate`](crate::AdaptiveRate) for backing off while they rise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BusErrorStats {
+    /// Receive errors of the interface (`rx_errors`): CRC, stuff and form errors
+    pub rx_errors: u64,
+    /// Transmit errors of the interface (`tx_errors`), e.g. frames nobody acknowledged
+    pub tx_errors: u64,
+    /// Frames the kernel or the controller dropped on receive, see [`RxDropStats`]
+    pub rx_dropped: u64,
+    /// Frames this process failed to send
+    pub send_failures: u64,
+    /// Received frames too short for their parser, see [`CanBus::malformed_frames`]
+    pub malformed: u64,
+}
+
+impl BusErrorStats {
+    pub fn total(&self) -> u64 {
+        self.rx_errors + self.tx_errors + self.rx_dropped + self.send_failures + self.malformed
+    }
+
+    /// Errors since an `earlier` reading
+    pub fn since(&self, earlier: &BusErrorStats) -> BusErrorStats {
+        BusErrorStats {
+            rx_errors: self.rx_errors.saturating_sub(earlier.rx_errors),
+            tx_errors: self.tx_errors.saturating_sub(earlier.tx_errors),
+            rx_dropped: self.rx_dropped.saturating_sub(earlier.rx_dropped),
+            send_failures: self.send_failures.saturating_sub(earlier.send_failures),
+            malformed: self.malformed.saturating_sub(earlier.malformed),
+        }
+    }
+}
+
+impl CanBus {
+    /// Open `channel`, taking its ownership lock unless `force` is set
+    pub fn open(channel: &str, bitrate: u32, force: bool) -> Result<Arc<Self>> {
+        let bus_lock = BusLock::acquire_or_force(channel, force)?;
+        let socket = SocketTransport::open(channel)
+            .map_err(|e| error::can_io(e, format!("cannot open CAN interface {}", channel)))?;
+
+        Ok(Arc::new(Self::with_link(Box::new(socket), false, channel, bitrate, bus_lock)))
+    }
+
+    /// Move frames through `transport` instead of a socket, e.g. a
+    /// [`MockTransport`](crate::MockTransport); no ownership lock is taken
+    pub fn with_transport<T: CanTransport + 'static>(transport: T, channel: &str, bitrate: u32) -> Arc<Self> {
+        Arc::new(Self::with_link(Box::new(transport), false, channel, bitrate, None))
+    }
+
+    /// Play back a candump log instead of opening an interface
+    ///
+    /// Logged frames are received with their original spacing, starting now;
+    /// afterwards the bus stays silent. Sent frames are only handed to
+    /// [`subscribe_sent`](Self::subscribe_sent) subscribers. The channel is
+    /// named after the first logged interface; no ownership lock is taken.
+    pub fn replay<P: AsRef<Path>>(path: P, bitrate: u32) -> Result<Arc<Self>> {
+        Ok(Self::replay_log(CandumpLog::load(path)?, bitrate))
+    }
+
+    /// Like [`replay`](Self::replay), from an already loaded log
+    pub fn replay_log(log: CandumpLog, bitrate: u32) -> Arc<Self> {
+        let channel = log.interface().unwrap_or("replay").to_string();
+        Arc::new(Self::with_link(Box::new(Replay::new(log)), true, &channel, bitrate, None))
+    }
+
+    fn with_link(
+        transport: Box<dyn CanTransport>,
+        replay: bool,
+        channel: &str,
+        bitrate: u32,
+        bus_lock: Option<BusLock>,
+    ) -> Self {
+        let mut bus = Self {
+            transport,
+            replay,
+            channel: channel.to_string(),
+            bitrate,
+            bus_lock: Mutex::new(bus_lock),
+            reader: Mutex::new(()),
+            subscribers: Mutex::new(Vec::new()),
+            sent_subscribers: Mutex::new(Vec::new()),
+            next_subscriber: AtomicU64::new(0),
+            shaper: Mutex::new(LoadShaper::new()),
+            throttle: Mutex::new(QueryThrottle::new()),
+            send_failures: AtomicU64::new(0),
+            subscriber_drops: AtomicU64::new(0),
+            rx_drops_seen: Mutex::new(RxDropStats::default()),
+            malformed: AtomicU64::new(0),
+            receiving: AtomicBool::new(false),
+            receiver: Mutex::new(None),
+            receive_errors: AtomicU64::new(0),
+        };
+        *bus.rx_drops_seen.get_mut().unwrap() = bus.rx_drop_stats();
+        bus
+    }
+
+    /// CAN interface name
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Configured CAN bitrate
+    pub fn bitrate(&self) -> u32 {
+        self.bitrate
+    }
+
+    /// Whether this process holds the channel's ownership lock
+    pub fn owns_bus(&self) -> bool {
+        self.bus_lock.lock().unwrap().is_some()
+    }
+
+    /// Open a fresh socket on the same channel, moving the ownership lock to it
+    ///
+    /// The bandwidth reservation and query throttle are kept; subscribers and
+    /// the background receiver of this bus are not carried over.
+    pub fn reopen(&self, bitrate: u32) -> Result<Arc<Self>> {
+        let transport = self
+            .transport
+            .reopen()
+            .map_err(|e| error::can_io(e, format!("cannot reopen CAN interface {}", self.channel)))?;
+
+        let bus_lock = self.bus_lock.lock().unwrap().take();
+        let bus = Arc::new(Self::with_link(transport, self.replay, &self.channel, bitrate, bus_lock));
+        bus.reserve_bandwidth(self.reserved_bandwidth())?;
+        bus.throttle_queries(self.query_spacing(), self.telemetry_scale())?;
+        Ok(bus)
+    }
+
+    /// Whether frames come from a candump log, see [`replay`](Self::replay)
+    pub fn is_replay(&self) -> bool {
+        self.replay
+    }
+
+    /// Whether the transport will receive no more frames, e.g. a replayed
+    /// log played to its end; always false for a socket
+    pub fn replay_finished(&self) -> bool {
+        self.transport.finished()
+    }
+
+    /// Leave `fraction` of the bitrate to other nodes by spacing transmitted frames
+    ///
+    /// `send` blocks until the frame's slot; 0 disables shaping.
+    pub fn reserve_bandwidth(&self, fraction: f64) -> Result<()> {
+        Ok(self.shaper.lock().unwrap().set_reserved(fraction)?)
+    }
+
+    /// Fraction of the bitrate reserved for other nodes
+    pub fn reserved_bandwidth(&self) -> f64 {
+        self.shaper.lock().unwrap().reserved()
+    }
+
+    /// Space motor queries (state and register reads, pings) at least
+    /// `spacing` apart and ask telemetry pollers to run at `telemetry_scale`
+    /// (0..=1) of their rate; setpoint streams and writes are never delayed
+    ///
+    /// `Duration::ZERO` and 1.0 lift the throttle.
+    pub fn throttle_queries(&self, spacing: Duration, telemetry_scale: f64) -> Result<()> {
+        Ok(self.throttle.lock().unwrap().set(spacing, telemetry_scale)?)
+    }
+
+    /// Smallest time between motor queries, zero unless throttled
+    pub fn query_spacing(&self) -> Duration {
+        self.throttle.lock().unwrap().spacing()
+    }
+
+    /// Fraction of their rate telemetry pollers should run at, 1.0 unless throttled
+    pub fn telemetry_scale(&self) -> f64 {
+        self.throttle.lock().unwrap().telemetry_scale()
+    }
+
+    /// A telemetry poller's `nominal` period stretched by the [`telemetry_scale`](Self::telemetry_scale)
+    pub fn telemetry_period(&self, nominal: Duration) -> Duration {
+        nominal.div_f64(self.telemetry_scale())
+    }
+
+    /// Transmit a frame
+    pub fn send(&self, frame: &CanFrame) -> Result<()> {
+        // Queries wait outside the shaper so setpoints are not held up behind them
+        if shaping::is_query(frame) {
+            let delay = self.throttle.lock().unwrap().delay(Instant::now());
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+        }
+        let mut shaper = self.shaper.lock().unwrap();
+        let delay = shaper.delay(frame, self.bitrate, Instant::now());
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+        self.transport.send(&RawFrame::from_frame(frame)).map_err(|e| {
+            self.send_failures.fetch_add(1, Ordering::Relaxed);
+            error::can_io(e, format!("failed to send frame 0x{:X} on {}", crate::raw_id(frame), self.channel))
+        })?;
+        drop(shaper);
+        deliver(&self.sent_subscribers, frame, &self.subscriber_drops);
+        Ok(())
+    }
+
+    /// Start receiving a copy of every frame read from now on
+    pub fn subscribe(self: &Arc<Self>) -> BusSubscription {
+        self.add_subscriber(false)
+    }
+
+    /// Start receiving a copy of every frame sent through this bus from now on
+    pub fn subscribe_sent(self: &Arc<Self>) -> BusSubscription {
+        self.add_subscriber(true)
+    }
+
+    fn add_subscriber(self: &Arc<Self>, sent: bool) -> BusSubscription {
+        let (tx, rx) = mpsc::sync_channel(SUBSCRIBER_QUEUE_LEN);
+        let id = self.next_subscriber.fetch_add(1, Ordering::Relaxed);
+        let subscribers = if sent { &self.sent_subscribers } else { &self.subscribers };
+        subscribers.lock().unwrap().push((id, tx));
+
+        BusSubscription {
+            bus: Arc::clone(self),
+            id,
+            sent,
+            rx,
+        }
+    }
+
+    /// Number of live subscriptions
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+
+    /// Interface statistic from sysfs, 0 if unavailable (e.g. a mock or replay)
+    fn sysfs_stat(&self, name: &str) -> u64 {
+        std::fs::read_to_string(format!("/sys/class/net/{}/statistics/{}", self.channel, name))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(0)
+    }
+
+    /// Frame loss counters of the interface (from sysfs) and of this process
+    pub fn rx_drop_stats(&self) -> RxDropStats {
+        RxDropStats {
+            kernel_dropped: self.sysfs_stat("rx_dropped"),
+            overruns: self.sysfs_stat("rx_over_errors") + self.sysfs_stat("rx_fifo_errors"),
+            subscriber_dropped: self.subscriber_drops.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Frames lost on receive since the previous call (or since the bus was
+    /// opened), to explain a reply that never came
+    ///
+    /// The counters are read only when this is called, so requests that get
+    /// their reply don't pay for the sysfs reads.
+    pub fn new_rx_drops(&self) -> RxDropStats {
+        let now = self.rx_drop_stats();
+        let mut seen = self.rx_drops_seen.lock().unwrap();
+        let lost = now.since(&seen);
+        *seen = now;
+        lost
+    }
+
+    /// Error counters of the interface (from sysfs) and of this process
+    pub fn error_stats(&self) -> BusErrorStats {
+        let drops = self.rx_drop_stats();
+        BusErrorStats {
+            rx_errors: self.sysfs_stat("rx_errors"),
+            tx_errors: self.sysfs_stat("tx_errors"),
+            rx_dropped: drops.kernel_dropped + drops.overruns,
+            send_failures: self.send_failures.load(Ordering::Relaxed),
+            malformed: self.malformed_frames(),
+        }
+    }
+
+    /// Received frames that were addressed to a parser but too short for it
+    /// (truncated replies, BMS frames with a short DLC)
+    pub fn malformed_frames(&self) -> u64 {
+        self.malformed.load(Ordering::Relaxed)
+    }
+
+    /// Count a malformed frame, see [`malformed_frames`](Self::malformed_frames)
+    pub fn note_malformed(&self) {
+        self.malformed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Read the socket continuously on a background thread
+    ///
+    /// Every frame is handed to the subscribers as it arrives, whether or not
+    /// one of them is waiting. Does nothing if the receiver already runs; it
+    /// stops with [`stop_receiver`](Self::stop_receiver) or when the bus is dropped.
+    pub fn start_receiver(self: &Arc<Self>) {
+        let mut receiver = self.receiver.lock().unwrap();
+        if self.receiving.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // Joined by a previous stop unless it was still finishing its last read
+        if let Some(previous) = receiver.take() {
+            let _ = previous.join();
+        }
+        let bus = Arc::downgrade(self);
+        *receiver = Some(thread::spawn(move || receive(&bus)));
+    }
+
+    /// Stop the background receiver; waiting subscribers read the socket again
+    pub fn stop_receiver(&self) {
+        self.receiving.store(false, Ordering::SeqCst);
+        let handle = self.receiver.lock().unwrap().take();
+        if let Some(handle) = handle {
+            if handle.thread().id() != thread::current().id() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    /// Whether the background receiver runs
+    pub fn is_receiving(&self) -> bool {
+        self.receiving.load(Ordering::SeqCst)
+    }
+
+    /// Socket reads the background receiver failed, e.g. while the interface was down
+    pub fn receive_errors(&self) -> u64 {
+        self.receive_errors.load(Ordering::Relaxed)
+    }
+
+    /// Read at most one frame and dispatch it to all subscribers
+    fn pump(&self, timeout: Duration) -> Result<()> {
+        let _reader = self.reader.lock().unwrap();
+
+        let frame = match self.transport.recv(timeout) {
+            Ok(Some(frame)) => frame
+                .to_frame()
+                .map_err(|e| e.context(format!("invalid frame received from {}", self.channel)))?,
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(error::can_io(e, format!("failed to read from {}", self.channel))),
+        };
+
+        deliver(&self.subscribers, &frame, &self.subscriber_drops);
+        Ok(())
+    }
+
+    fn unsubscribe(&self, id: u64, sent: bool) {
+        let subscribers = if sent { &self.sent_subscribers } else { &self.subscribers };
+        subscribers.lock().unwrap().retain(|(sub, _)| *sub != id);
+    }
+}
+
+/// Hand a copy of `frame` to every subscriber
+fn deliver(subscribers: &Mutex<Vec<(u64, SyncSender<CanFrame>)>>, frame: &CanFrame, drops: &AtomicU64) {
+    // A full queue means that subscriber is not reading; skip it rather than block
+    for (_, tx) in subscribers.lock().unwrap().iter() {
+        if let Err(TrySendError::Full(_)) = tx.try_send(*frame) {
+            drops.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Background receiver loop; holds the bus only while reading so dropping
+/// the last handle ends it
+fn receive(bus: &Weak<CanBus>) {
+    while let Some(bus) = bus.upgrade() {
+        if !bus.is_receiving() {
+            break;
+        }
+        if bus.pump(PUMP_SLICE).is_err() {
+            bus.receive_errors.fetch_add(1, Ordering::Relaxed);
+            thread::sleep(PUMP_SLICE);
+        }
+    }
+}
+
+/// A subscriber's view of the bus, unsubscribed on drop
+pub struct BusSubscription {
+    bus: Arc<CanBus>,
+    id: u64,
+    /// Subscribed to sent rather than received frames
+    sent: bool,
+    rx: Receiver<CanFrame>,
+}
+
+impl BusSubscription {
+    /// The bus this subscription belongs to
+    pub fn bus(&self) -> &Arc<CanBus> {
+        &self.bus
+    }
+
+    /// Next frame seen on the bus, or `None` after `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<CanFrame>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.rx.try_recv() {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(TryRecvError::Disconnected) => return Err(anyhow!("CAN bus subscription on {} closed", self.bus.channel).into()),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            let slice = (deadline - now).min(PUMP_SLICE);
+            if !self.bus.is_receiving() {
+                self.bus.pump(slice)?;
+                continue;
+            }
+            // The background receiver fills the queue; waiting in slices
+            // notices when it stops
+            match self.rx.recv_timeout(slice) {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(anyhow!("CAN bus subscription on {} closed", self.bus.channel).into())
+                }
+            }
+        }
+    }
+
+    /// Next frame already queued for this subscriber, without waiting or reading the socket
+    pub fn try_recv(&self) -> Option<CanFrame> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Discard frames already queued for this subscriber
+    pub fn drain(&self) {
+        while self.rx.try_recv().is_ok() {}
+    }
+}
+
+impl Drop for BusSubscription {
+    fn drop(&mut self) {
+        self.bus.unsubscribe(self.id, self.sent);
+    }
+}
+
+/// Passive bus observer counting traffic per arbitration ID
+pub struct BusMonitor {
+    subscription: BusSubscription,
+    started: Instant,
+    total: u64,
+    per_id: HashMap<u32, u64>,
+    /// Frames of IDs beyond [`MAX_TRACKED_IDS`]
+    untracked: u64,
+    last_frame: Option<Instant>,
+    load: LoadCounter,
+    drops_at_start: RxDropStats,
+}
+
+impl BusMonitor {
+    pub fn new(bus: &Arc<CanBus>) -> Self {
+        Self {
+            subscription: bus.subscribe(),
+            started: Instant::now(),
+            total: 0,
+            per_id: HashMap::new(),
+            untracked: 0,
+            last_frame: None,
+            load: LoadCounter::default(),
+            drops_at_start: bus.rx_drop_stats(),
+        }
+    }
+
+    /// Wait up to `timeout` for the next frame and account for it
+    pub fn poll(&mut self, timeout: Duration) -> Result<Option<CanFrame>> {
+        let frame = self.subscription.recv_timeout(timeout)?;
+        if let Some(frame) = &frame {
+            self.total += 1;
+            if !count_id(&mut self.per_id, crate::raw_id(frame)) {
+                self.untracked += 1;
+            }
+            self.last_frame = Some(Instant::now());
+            self.load.record(frame);
+        }
+        Ok(frame)
+    }
+
+    /// Frames seen since the monitor was created
+    pub fn total_frames(&self) -> u64 {
+        self.total
+    }
+
+    /// Frames seen per arbitration ID, for the first [`MAX_TRACKED_IDS`] IDs seen
+    pub fn frames_by_id(&self) -> &HashMap<u32, u64> {
+        &self.per_id
+    }
+
+    /// Frames not in [`frames_by_id`](Self::frames_by_id) because the table was full
+    pub fn untracked_frames(&self) -> u64 {
+        self.untracked
+    }
+
+    /// Average frame rate since the monitor was created
+    pub fn frames_per_second(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.total as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Bus load of motor and foreign traffic since the monitor was created
+    pub fn load_report(&self) -> BusLoadReport {
+        let bus = self.subscription.bus();
+        self.load.report(self.started.elapsed(), bus.bitrate(), bus.reserved_bandwidth())
+    }
+
+    /// Frames lost on receive since the monitor was created
+    pub fn rx_drops(&self) -> RxDropStats {
+        self.subscription.bus().rx_drop_stats().since(&self.drops_at_start)
+    }
+
+    /// Time since the last frame, `None` if none seen yet
+    pub fn idle_for(&self) -> Option<Duration> {
+        self.last_frame.map(|t| t.elapsed())
+    }
+}
+
+/// Count a frame of `id`, unless that would track more than [`MAX_TRACKED_IDS`] IDs
+pub(crate) fn count_id(counts: &mut HashMap<u32, u64>, id: u32) -> bool {
+    if let Some(count) = counts.get_mut(&id) {
+        *count += 1;
+    } else if counts.len() < MAX_TRACKED_IDS {
+        counts.insert(id, 1);
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Publishes [`EventKind::RxFramesDropped`] whenever frames were lost since the last check
+pub struct RxDropWatch {
+    bus: Arc<CanBus>,
+    events: Arc<EventBus>,
+    last: RxDropStats,
+}
+
+impl RxDropWatch {
+    pub fn new(bus: &Arc<CanBus>, events: Arc<EventBus>) -> Self {
+        Self {
+            bus: Arc::clone(bus),
+            events,
+            last: bus.rx_drop_stats(),
+        }
+    }
+
+    /// Losses since the previous check; an event is published if there were any
+    pub fn check(&mut self) -> RxDropStats {
+        let now = self.bus.rx_drop_stats();
+        let lost = now.since(&self.last);
+        self.last = now;
+        if lost.total() > 0 {
+            self.events.publish(EventKind::RxFramesDropped {
+                channel: self.bus.channel().to_string(),
+                lost,
+            });
+        }
+        lost
+    }
+}
@@ -0,0 +1,118 @@
+//! Advisory per-channel bus ownership lock
+//!
+//! Each process commanding motors on a CAN channel holds an exclusive `flock` on
+//! `<lock dir>/livelybot-<channel>.lock` containing its PID, so a second control
+//! program fails with a clear message instead of silently fighting over the bus.
+
+use anyhow::{Result, anyhow};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the lock directory
+pub const LOCK_DIR_ENV: &str = "LIVELYBOT_LOCK_DIR";
+
+/// Exclusive ownership of a CAN channel, released on drop
+#[derive(Debug)]
+pub struct BusLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl BusLock {
+    /// Lock file path used for `channel`
+    pub fn path_for(channel: &str) -> PathBuf {
+        let dir = std::env::var_os(LOCK_DIR_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                let run_lock = Path::new("/run/lock");
+                if is_writable_dir(run_lock) {
+                    run_lock.to_path_buf()
+                } else {
+                    std::env::temp_dir()
+                }
+            });
+        dir.join(format!("livelybot-{}.lock", channel))
+    }
+
+    /// Acquire the lock for `channel`, failing with the owner's PID if it is held
+    pub fn acquire(channel: &str) -> Result<Self> {
+        let path = Self::path_for(channel);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| anyhow!("Cannot open bus lock {}: {}", path.display(), e))?;
+
+        // SAFETY: flock on a valid, owned file descriptor
+        let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+        if ret != 0 {
+            let owner = read_owner(&mut file)
+                .map(|pid| format!("PID {}", pid))
+                .unwrap_or_else(|| "another process".to_string());
+            return Err(anyhow!(
+                "CAN channel {} is already in use by {} (lock: {}); use --force to override",
+                channel,
+                owner,
+                path.display()
+            ));
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+
+        Ok(Self { file, path })
+    }
+
+    /// Acquire the lock, or with `force` continue without it when it is held elsewhere
+    pub fn acquire_or_force(channel: &str, force: bool) -> Result<Option<Self>> {
+        match Self::acquire(channel) {
+            Ok(lock) => Ok(Some(lock)),
+            Err(_) if force => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// PID of the process currently holding the lock for `channel`, if any
+    pub fn owner(channel: &str) -> Option<u32> {
+        let mut file = File::open(Self::path_for(channel)).ok()?;
+        // SAFETY: flock on a valid, owned file descriptor
+        let free = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) } == 0;
+        if free {
+            return None;
+        }
+        read_owner(&mut file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for BusLock {
+    fn drop(&mut self) {
+        // The flock itself is released when the file is closed
+        let _ = self.file.set_len(0);
+    }
+}
+
+fn read_owner(file: &mut File) -> Option<u32> {
+    let mut content = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut content).ok()?;
+    content.trim().parse().ok()
+}
+
+fn is_writable_dir(path: &Path) -> bool {
+    let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: access() only reads the NUL-terminated path
+    path.is_dir() && unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } == 0
+}
@@ -0,0 +1,174 @@
+//! Per-motor joint calibration
+//!
+//! Joints are rarely mounted with the motor's zero at the joint's zero,
+//! mirrored joints turn the motor the other way, and geared joints turn
+//! slower than the rotor with more torque. A [`Calibration`] installed
+//! with [`LivelyMotorController::set_calibration`](crate::LivelyMotorController::set_calibration)
+//! is applied to every angle, velocity and MIT command sent to the motor and
+//! to every state parsed from its feedback, so the application works in
+//! output-shaft units throughout; the `FACTOR_*` scaling of the raw values
+//! stays rotor-side and is applied after the conversion. Software
+//! [`Limits`](crate::Limits) are in joint units too.
+
+use crate::{MotorState, Report};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Zero offset, direction and gear ratio of one motor's joint
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Motor angle at joint zero
+    #[serde(default)]
+    pub zero_offset_deg: f64,
+    /// Motor turns opposite to the joint's positive direction
+    #[serde(default)]
+    pub inverted: bool,
+    /// Motor turns per joint (output shaft) turn
+    #[serde(default = "default_gear_ratio")]
+    pub gear_ratio: f64,
+}
+
+fn default_gear_ratio() -> f64 {
+    1.0
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self::new(0.0, false)
+    }
+}
+
+impl Calibration {
+    pub fn new(zero_offset_deg: f64, inverted: bool) -> Self {
+        Self {
+            zero_offset_deg,
+            inverted,
+            gear_ratio: default_gear_ratio(),
+        }
+    }
+
+    /// Command and read the joint at the output of a `gear_ratio`:1 reduction
+    pub fn with_gear_ratio(mut self, gear_ratio: f64) -> Self {
+        self.gear_ratio = gear_ratio;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !self.zero_offset_deg.is_finite() {
+            return Err(anyhow!("invalid zero offset {}", self.zero_offset_deg));
+        }
+        if !self.gear_ratio.is_finite() || self.gear_ratio <= 0.0 {
+            return Err(anyhow!("invalid gear ratio {}, must be positive", self.gear_ratio));
+        }
+        Ok(())
+    }
+
+    /// Whether joint and motor units are the same
+    pub fn is_identity(&self) -> bool {
+        self.zero_offset_deg == 0.0 && !self.inverted && self.gear_ratio == 1.0
+    }
+
+    fn sign(&self) -> f64 {
+        if self.inverted { -1.0 } else { 1.0 }
+    }
+
+    /// Motor angle for a joint angle
+    pub fn to_motor_deg(&self, joint_deg: f64) -> f64 {
+        self.sign() * joint_deg * self.gear_ratio + self.zero_offset_deg
+    }
+
+    /// Joint angle for a motor angle
+    pub fn to_joint_deg(&self, motor_deg: f64) -> f64 {
+        self.sign() * (motor_deg - self.zero_offset_deg) / self.gear_ratio
+    }
+
+    /// Motor velocity or acceleration for a joint one
+    pub fn to_motor_velocity(&self, joint: f64) -> f64 {
+        self.sign() * joint * self.gear_ratio
+    }
+
+    /// Joint velocity or acceleration for a motor one
+    pub fn to_joint_velocity(&self, motor: f64) -> f64 {
+        self.sign() * motor / self.gear_ratio
+    }
+
+    /// Motor torque for a joint torque; the reduction multiplies torque
+    pub fn to_motor_torque(&self, joint_nm: f64) -> f64 {
+        self.sign() * joint_nm / self.gear_ratio
+    }
+
+    /// Joint torque for a motor torque
+    pub fn to_joint_torque(&self, motor_nm: f64) -> f64 {
+        self.sign() * motor_nm * self.gear_ratio
+    }
+
+    /// Motor position or velocity gain for a joint one: a joint gain is
+    /// `gear_ratio²` times stiffer seen from the motor side
+    pub fn to_motor_gain(&self, joint_gain: f64) -> f64 {
+        joint_gain / (self.gear_ratio * self.gear_ratio)
+    }
+
+    /// Raw stream position of the motor for a raw joint position;
+    /// [`MAGIC_POS`](crate::MAGIC_POS) passes unchanged
+    pub(crate) fn position_to_motor(&self, position: i16) -> i16 {
+        if self.is_identity() || position == crate::MAGIC_POS {
+            return position;
+        }
+        crate::degrees_to_position(self.to_motor_deg(crate::position_to_degrees(position)))
+    }
+
+    /// Raw stream velocity of the motor for a raw joint velocity
+    pub(crate) fn velocity_to_motor(&self, velocity: i16) -> i16 {
+        crate::rps_to_velocity(self.to_motor_velocity(crate::velocity_to_rps(velocity)))
+    }
+
+    /// Raw stream acceleration of the motor for a raw joint acceleration
+    pub(crate) fn acceleration_to_motor(&self, acceleration: i16) -> i16 {
+        crate::rps2_to_acceleration(self.to_motor_velocity(crate::acceleration_to_rps2(acceleration)))
+    }
+
+    /// Raw stream speed limit of the motor for a raw joint one; a limit has
+    /// no direction
+    pub(crate) fn max_velocity_to_motor(&self, max_vel: i16) -> i16 {
+        crate::rps_to_velocity(crate::velocity_to_rps(max_vel) * self.gear_ratio)
+    }
+
+    /// Raw stream torque limit of the motor for a raw joint one
+    pub(crate) fn max_torque_to_motor(&self, max_tqe: i16) -> i16 {
+        crate::nm_to_torque(crate::torque_to_nm(max_tqe) / self.gear_ratio)
+    }
+
+    /// A state parsed from the motor's feedback, in joint terms
+    pub fn to_joint_state(&self, state: &MotorState) -> MotorState {
+        MotorState {
+            position_deg: self.to_joint_deg(state.position_deg),
+            velocity_rps: self.to_joint_velocity(state.velocity_rps),
+            torque_nm: self.to_joint_torque(state.torque_nm),
+            acceleration_rps2: state.acceleration_rps2.map(|a| self.to_joint_velocity(a)),
+            ..*state
+        }
+    }
+
+    /// A report parsed from the motor's feedback, in joint terms
+    pub fn to_joint_report(&self, report: &Report) -> Report {
+        Report {
+            position_deg: report.position_deg.map(|p| self.to_joint_deg(p)),
+            velocity_rps: report.velocity_rps.map(|v| self.to_joint_velocity(v)),
+            torque_nm: report.torque_nm.map(|t| self.to_joint_torque(t)),
+            ..*report
+        }
+    }
+}
+
+/// Calibration a command to `motor_id` is sent with; ID 0 addresses every
+/// motor, which is refused once any of them is calibrated
+pub(crate) fn for_command(calibrations: &BTreeMap<u8, Calibration>, motor_id: u8) -> Result<Calibration> {
+    if motor_id != 0 {
+        return Ok(calibrations.get(&motor_id).copied().unwrap_or_default());
+    }
+    if calibrations.values().any(|c| !c.is_identity()) {
+        return Err(anyhow!("calibrated motors must be commanded by ID, not through the broadcast ID 0"));
+    }
+    Ok(Calibration::default())
+}
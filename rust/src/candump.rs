@@ -0,0 +1,227 @@
+//! candump log files
+//!
+//! Reads the log format written by `candump -l` / `candump -L`
+//! (`(1436509052.249713) can0 123#DEADBEEF`) and the screen format with
+//! absolute timestamps of `candump -ta` (`(1436509052.249713)  can0  123   [4]  DE AD BE EF`).
+//! IDs written with 8 hex digits are extended, shorter ones standard. CAN FD
+//! frames (`##`) are rejected.
+//!
+//! [`CanBus::replay`](crate::CanBus::replay) plays a log back as if it were
+//! received live, so field failures can be reproduced and regression tested
+//! without the robot.
+
+use crate::transport::{CanTransport, RawFrame};
+use anyhow::{Result, anyhow};
+use socketcan::{CanFrame, CanId, EmbeddedFrame};
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One logged frame
+#[derive(Debug, Clone)]
+pub struct CandumpRecord {
+    /// Time since the Unix epoch
+    pub timestamp: Duration,
+    pub interface: String,
+    pub frame: CanFrame,
+}
+
+impl CandumpRecord {
+    /// The record in `candump -l` format, without a newline
+    pub fn to_line(&self) -> String {
+        let mut line = format!(
+            "({}.{:06}) {} ",
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros(),
+            self.interface
+        );
+        let id = crate::raw_id(&self.frame);
+        let _ = if self.frame.is_extended() { write!(line, "{:08X}#", id) } else { write!(line, "{:03X}#", id) };
+        if self.frame.is_remote_frame() {
+            let _ = write!(line, "R{}", self.frame.dlc());
+        } else {
+            for byte in self.frame.data() {
+                let _ = write!(line, "{:02X}", byte);
+            }
+        }
+        line
+    }
+}
+
+/// The frames of a candump log, in file order
+#[derive(Debug, Clone, Default)]
+pub struct CandumpLog {
+    pub records: Vec<CandumpRecord>,
+}
+
+impl CandumpLog {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read candump log {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| e.context(format!("in candump log {}", path.display())))
+    }
+
+    /// Parse a log; blank lines and `#` comments are skipped
+    pub fn parse(text: &str) -> Result<Self> {
+        let records = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| parse_line(line).map_err(|e| anyhow!("line {}: {}", i + 1, e)))
+            .collect::<Result<_>>()?;
+        Ok(Self { records })
+    }
+
+    /// Only the frames logged on `interface`
+    pub fn on_interface(mut self, interface: &str) -> Self {
+        self.records.retain(|r| r.interface == interface);
+        self
+    }
+
+    /// Interface of the first frame
+    pub fn interface(&self) -> Option<&str> {
+        self.records.first().map(|r| r.interface.as_str())
+    }
+
+    /// Time between the first and the last frame
+    pub fn duration(&self) -> Duration {
+        match (self.records.first(), self.records.last()) {
+            (Some(first), Some(last)) => last.timestamp.saturating_sub(first.timestamp),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// The whole log in `candump -l` format
+    pub fn to_text(&self) -> String {
+        self.records.iter().map(|r| r.to_line() + "\n").collect()
+    }
+}
+
+fn parse_line(line: &str) -> Result<CandumpRecord> {
+    let mut tokens = line.split_whitespace();
+    let timestamp = tokens
+        .next()
+        .and_then(|t| t.strip_prefix('(')?.strip_suffix(')'))
+        .ok_or_else(|| anyhow!("expected a (seconds.micros) timestamp"))?;
+    let timestamp = parse_timestamp(timestamp)?;
+    let interface = tokens.next().ok_or_else(|| anyhow!("missing interface"))?.to_string();
+    let id = tokens.next().ok_or_else(|| anyhow!("missing frame"))?;
+
+    let frame = match id.split_once('#') {
+        Some((_, rest)) if rest.starts_with('#') => return Err(anyhow!("CAN FD frames are not supported")),
+        Some((id, data)) => match data.strip_prefix('R') {
+            Some(dlc) => remote_frame(id, dlc)?,
+            None => data_frame(id, &hex_bytes(data)?)?,
+        },
+        // Screen format: ID, [len], bytes
+        None => {
+            let len = tokens
+                .next()
+                .and_then(|t| t.strip_prefix('[')?.strip_suffix(']')?.parse::<usize>().ok())
+                .ok_or_else(|| anyhow!("expected ID#DATA or ID [LEN] BYTES"))?;
+            let rest: Vec<&str> = tokens.collect();
+            if rest.first() == Some(&"remote") {
+                remote_frame(id, &len.to_string())?
+            } else {
+                let data = hex_bytes(&rest.concat())?;
+                if data.len() != len {
+                    return Err(anyhow!("[{}] with {} data bytes", len, data.len()));
+                }
+                data_frame(id, &data)?
+            }
+        }
+    };
+    Ok(CandumpRecord { timestamp, interface, frame })
+}
+
+fn parse_timestamp(text: &str) -> Result<Duration> {
+    let (secs, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let secs: u64 = secs.parse().map_err(|_| anyhow!("invalid timestamp {}", text))?;
+    let digits = fraction.len().min(9);
+    let nanos = match digits {
+        0 => 0,
+        _ => {
+            let value: u32 = fraction[..digits].parse().map_err(|_| anyhow!("invalid timestamp {}", text))?;
+            value * 10u32.pow(9 - digits as u32)
+        }
+    };
+    Ok(Duration::new(secs, nanos))
+}
+
+fn can_id(text: &str) -> Result<CanId> {
+    let raw = u32::from_str_radix(text, 16).map_err(|_| anyhow!("invalid CAN ID {}", text))?;
+    let id = if text.len() == 8 { CanId::extended(raw) } else { u16::try_from(raw).ok().and_then(CanId::standard) };
+    id.ok_or_else(|| anyhow!("CAN ID {} out of range", text))
+}
+
+fn data_frame(id: &str, data: &[u8]) -> Result<CanFrame> {
+    CanFrame::new(can_id(id)?, data).ok_or_else(|| anyhow!("{} data bytes do not fit a CAN frame", data.len()))
+}
+
+fn remote_frame(id: &str, dlc: &str) -> Result<CanFrame> {
+    let dlc = if dlc.is_empty() { 0 } else { dlc.parse().map_err(|_| anyhow!("invalid remote DLC {}", dlc))? };
+    CanFrame::new_remote(can_id(id)?, dlc).ok_or_else(|| anyhow!("invalid remote DLC {}", dlc))
+}
+
+fn hex_bytes(text: &str) -> Result<Vec<u8>> {
+    // candump -L may separate bytes with dots
+    let digits: String = text.chars().filter(|&c| c != '.').collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(anyhow!("odd number of hex digits in {}", text));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| anyhow!("invalid data {}", text)))
+        .collect()
+}
+
+/// Hands out the frames of a log at their original spacing, starting when
+/// created; sent frames go nowhere
+pub(crate) struct Replay {
+    log: CandumpLog,
+    /// Index of the next frame to hand out
+    next: Mutex<usize>,
+    start: Instant,
+}
+
+impl Replay {
+    pub(crate) fn new(log: CandumpLog) -> Self {
+        Self { log, next: Mutex::new(0), start: Instant::now() }
+    }
+}
+
+impl CanTransport for Replay {
+    fn send(&self, _frame: &RawFrame) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Frames due while nobody read are handed out back to back, like a
+    /// socket buffer catching up
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        let mut next = self.next.lock().unwrap();
+        let Some(record) = self.log.records.get(*next) else {
+            thread::sleep(timeout);
+            return Ok(None);
+        };
+        let first = self.log.records[0].timestamp;
+        let due = self.start + record.timestamp.saturating_sub(first);
+        let now = Instant::now();
+        if due > now {
+            if due - now > timeout {
+                thread::sleep(timeout);
+                return Ok(None);
+            }
+            thread::sleep(due - now);
+        }
+        *next += 1;
+        Ok(Some(RawFrame::from_frame(&record.frame)))
+    }
+
+    fn finished(&self) -> bool {
+        *self.next.lock().unwrap() >= self.log.records.len()
+    }
+}
@@ -0,0 +1,102 @@
+//! Firmware feature detection
+//!
+//! Older firmware lacks some info sub-queries and registers, and simply does
+//! not answer them, so calling into one waits for the reply timeout every
+//! time. [`LivelyMotorController::detect_capabilities`](crate::LivelyMotorController::detect_capabilities)
+//! probes every [`Feature`] of a motor once and remembers the result; from then
+//! on [`supports`](crate::LivelyMotorController::supports) answers without bus
+//! traffic and calls needing a missing feature fail at once with
+//! [`MotorError::Unsupported`](crate::MotorError::Unsupported).
+
+use crate::protocol::{Register, ValueType};
+use crate::query::InfoQuery;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A feature not every firmware has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    SerialNumber,
+    /// Error log query and clearing it
+    ErrorLog,
+    Uptime,
+    Temperatures,
+    /// Automatic state feedback ([`PushFeedback`](crate::PushFeedback))
+    FeedbackPeriod,
+    /// Auxiliary digital inputs
+    Gpio,
+    /// Selectable content of automatic replies ([`ReportMask`](crate::ReportMask))
+    ReportContent,
+}
+
+/// How a feature is probed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Probe {
+    Query(InfoQuery),
+    Register(Register, ValueType),
+}
+
+impl Feature {
+    pub const ALL: [Feature; 7] = [
+        Feature::SerialNumber,
+        Feature::ErrorLog,
+        Feature::Uptime,
+        Feature::Temperatures,
+        Feature::FeedbackPeriod,
+        Feature::Gpio,
+        Feature::ReportContent,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::SerialNumber => "serial_number",
+            Feature::ErrorLog => "error_log",
+            Feature::Uptime => "uptime",
+            Feature::Temperatures => "temperatures",
+            Feature::FeedbackPeriod => "feedback_period",
+            Feature::Gpio => "gpio",
+            Feature::ReportContent => "report_content",
+        }
+    }
+
+    /// A read that firmware without the feature leaves unanswered
+    pub(crate) fn probe(self) -> Probe {
+        match self {
+            Feature::SerialNumber => Probe::Query(InfoQuery::SerialNumber),
+            Feature::ErrorLog => Probe::Query(InfoQuery::ErrorLog),
+            Feature::Uptime => Probe::Query(InfoQuery::Uptime),
+            Feature::Temperatures => Probe::Query(InfoQuery::Temperatures),
+            Feature::FeedbackPeriod => Probe::Register(Register::FeedbackPeriod, ValueType::Int16),
+            Feature::Gpio => Probe::Register(Register::GpioInput, ValueType::Int8),
+            Feature::ReportContent => Probe::Register(Register::ReportContent, ValueType::Int8),
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Features a motor's firmware answered when probed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub motor_id: u8,
+    /// Hardware version reported by the ping
+    pub firmware: String,
+    pub features: BTreeSet<Feature>,
+}
+
+impl Capabilities {
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Features the firmware did not answer, in [`Feature::ALL`] order
+    pub fn missing(&self) -> Vec<Feature> {
+        Feature::ALL.into_iter().filter(|f| !self.supports(*f)).collect()
+    }
+}
@@ -0,0 +1,177 @@
+//! C API (feature `capi`)
+//!
+//! `extern "C"` functions for linking C and C++ controllers against the
+//! cdylib. The header `include/livelybot_motor_control.h` is generated by
+//! cbindgen (see `cbindgen.toml`) with `make header`.
+//!
+//! Functions returning `int` return 0 on success and -1 on failure;
+//! [`lmc_last_error`] then describes the failure. Angles are in degrees,
+//! velocities in r/s and torques in Nm. A controller may be used from
+//! several threads, but [`lmc_free`] must be the last call on it.
+//!
+//! Build with the `release-capi` profile: it unwinds on panic, so a panic
+//! inside a call returns -1 instead of aborting the host process like the
+//! `release` profile does.
+
+use crate::LivelyMotorController;
+use anyhow::{Result, anyhow};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(e: &anyhow::Error) {
+    let message = CString::new(format!("{:#}", e).replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Run `f`, turning errors and panics into -1 and the thread's last error.
+/// Panics are only caught when unwinding, i.e. not under `release`'s
+/// `panic = "abort"`
+fn call(f: impl FnOnce() -> Result<()>) -> c_int {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| Err(anyhow!("panic in the C API")));
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            set_last_error(&e);
+            -1
+        }
+    }
+}
+
+/// # Safety
+///
+/// `controller` must be null or a pointer returned by [`lmc_new`] and not freed.
+unsafe fn controller<'a>(controller: *const LivelyMotorController) -> Result<&'a LivelyMotorController> {
+    controller.as_ref().ok_or_else(|| anyhow!("controller is null"))
+}
+
+/// Motor feedback, filled by [`lmc_read_state`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LmcMotorState {
+    pub motor_id: u8,
+    /// Position in degrees
+    pub position_deg: f64,
+    /// Velocity in r/s
+    pub velocity_rps: f64,
+    /// Torque in Nm
+    pub torque_nm: f64,
+}
+
+/// Message of the last failed call on this thread, empty if none failed
+///
+/// The string stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn lmc_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Open the CAN interface `channel`, null on failure
+///
+/// # Safety
+///
+/// `channel` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lmc_new(channel: *const c_char, bitrate: u32) -> *mut LivelyMotorController {
+    let mut opened = None;
+    let status = call(|| {
+        if channel.is_null() {
+            return Err(anyhow!("channel is null"));
+        }
+        let channel = CStr::from_ptr(channel).to_str().map_err(|_| anyhow!("channel is not UTF-8"))?;
+        opened = Some(LivelyMotorController::new(channel, bitrate)?);
+        Ok(())
+    });
+    match opened {
+        Some(controller) if status == 0 => Box::into_raw(Box::new(controller)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Enable `motor_id` in position mode with the default gains
+///
+/// # Safety
+///
+/// `controller` must be null or a pointer returned by [`lmc_new`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn lmc_enable(controller: *const LivelyMotorController, motor_id: u8) -> c_int {
+    call(|| Ok(self::controller(controller)?.enable_motor(motor_id)?))
+}
+
+/// Disable `motor_id`
+///
+/// # Safety
+///
+/// `controller` must be null or a pointer returned by [`lmc_new`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn lmc_disable(controller: *const LivelyMotorController, motor_id: u8) -> c_int {
+    call(|| Ok(self::controller(controller)?.disable_motor(motor_id)?))
+}
+
+/// Move `motor_id` to `angle_deg` within the velocity and torque limits
+///
+/// Software limits loaded into the controller apply as for Rust callers.
+///
+/// # Safety
+///
+/// `controller` must be null or a pointer returned by [`lmc_new`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn lmc_send_angle(
+    controller: *const LivelyMotorController,
+    motor_id: u8,
+    angle_deg: f64,
+    max_vel_rps: f64,
+    max_torque_nm: f64,
+) -> c_int {
+    call(|| {
+        Ok(self::controller(controller)?.send_angle_command_to(
+            motor_id,
+            crate::degrees_to_position(angle_deg),
+            crate::rps_to_velocity(max_vel_rps),
+            crate::nm_to_torque(max_torque_nm),
+        )?)
+    })
+}
+
+/// Read the position, velocity and torque of `motor_id` into `state`
+///
+/// # Safety
+///
+/// `controller` must be null or a pointer returned by [`lmc_new`] and not
+/// freed; `state` must be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn lmc_read_state(
+    controller: *const LivelyMotorController,
+    motor_id: u8,
+    state: *mut LmcMotorState,
+) -> c_int {
+    call(|| {
+        let state = state.as_mut().ok_or_else(|| anyhow!("state is null"))?;
+        let read = self::controller(controller)?.read_motor_state(motor_id)?;
+        *state = LmcMotorState {
+            motor_id: read.motor_id,
+            position_deg: read.position_deg,
+            velocity_rps: read.velocity_rps,
+            torque_nm: read.torque_nm,
+        };
+        Ok(())
+    })
+}
+
+/// Close the controller; null is ignored
+///
+/// # Safety
+///
+/// `controller` must be null or a pointer returned by [`lmc_new`] that is
+/// not used again.
+#[no_mangle]
+pub unsafe extern "C" fn lmc_free(controller: *mut LivelyMotorController) {
+    if !controller.is_null() {
+        drop(Box::from_raw(controller));
+    }
+}
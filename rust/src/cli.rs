@@ -0,0 +1,274 @@
+//! Shared command line helpers for the binaries
+//!
+//! Every tool flattens [`GenerateArgs`] into its arguments so shell completions
+//! and man pages can be produced from the same clap definitions. Tools driving
+//! motors also flatten [`BusArgs`], which opens the controller from the
+//! command line and an optional [`ToolConfig`] file, and installs the
+//! configured software limits before the first command is sent.
+
+use crate::config::{self, RobotConfig};
+use crate::{Calibration, CanBus, JointInventory, LivelyMotorController, Limits};
+use anyhow::{anyhow, Context, Result};
+use clap::CommandFactory;
+use clap_complete::Shell;
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Completion / man page generation flags
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct GenerateArgs {
+    /// Print a shell completion script (bash, zsh, fish, elvish, powershell) and exit
+    #[arg(long, value_name = "SHELL")]
+    pub completions: Option<Shell>,
+
+    /// Print a roff man page and exit
+    #[arg(long)]
+    pub man: bool,
+}
+
+impl GenerateArgs {
+    /// Emit the requested artifact for `C` to stdout, returns true if the caller should exit
+    pub fn run<C: CommandFactory>(&self) -> Result<bool> {
+        let mut cmd = C::command();
+        let name = cmd.get_name().to_string();
+
+        if let Some(shell) = self.completions {
+            clap_complete::generate(shell, &mut cmd, name, &mut stdout());
+            return Ok(true);
+        }
+
+        if self.man {
+            let mut buffer = Vec::new();
+            clap_mangen::Man::new(cmd).render(&mut buffer)?;
+            stdout().write_all(&buffer)?;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+/// Settings shared by the tools, loaded with `--config` from JSON, TOML or YAML
+///
+/// ```json
+/// { "interface": "can1", "limits": { "1": { "min_pos_deg": -90, "max_pos_deg": 90, "max_vel_rps": 2.0 } },
+///   "calibration": { "1": { "zero_offset_deg": 12.5, "inverted": true } } }
+/// ```
+///
+/// A robot description ([`RobotConfig`]) is read as well: the bus section
+/// and the limits and calibrations of the joints on the opened interface apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolConfig {
+    pub interface: String,
+    pub bitrate: u32,
+    /// Fraction of the bus bandwidth left free for other nodes (IMU, BMS)
+    pub reserve_bandwidth: f64,
+    /// Software limits per motor, enforced on every command
+    pub limits: BTreeMap<u8, Limits>,
+    /// Joint zero offset and direction per motor, applied to commands and feedback
+    pub calibration: BTreeMap<u8, Calibration>,
+    /// Joint inventory (see [`JointInventory`]) checked before motors are commanded
+    pub inventory: Option<PathBuf>,
+    /// Robot description the config was loaded from
+    #[serde(skip)]
+    pub robot: Option<RobotConfig>,
+}
+
+impl Default for ToolConfig {
+    fn default() -> Self {
+        Self {
+            interface: "can0".to_string(),
+            bitrate: 1_000_000,
+            reserve_bandwidth: 0.0,
+            limits: BTreeMap::new(),
+            calibration: BTreeMap::new(),
+            inventory: None,
+            robot: None,
+        }
+    }
+}
+
+impl ToolConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let value = config::load_value(path)?;
+        if config::is_robot_description(&value) {
+            return Ok(Self::from_robot(RobotConfig::load(path)?));
+        }
+        serde_json::from_value(value).map_err(|e| anyhow!("Invalid tool config {}: {}", path.display(), e))
+    }
+
+    /// The bus section and joint limits of a robot description
+    pub fn from_robot(robot: RobotConfig) -> Self {
+        Self {
+            interface: robot.bus.interface.clone(),
+            bitrate: robot.bus.bitrate,
+            reserve_bandwidth: robot.bus.reserve_bandwidth,
+            limits: robot.limits_on(&robot.bus.interface),
+            calibration: robot.calibrations_on(&robot.bus.interface),
+            inventory: robot.inventory.clone(),
+            robot: Some(robot),
+        }
+    }
+
+    /// Calibrations of the motors on `interface`
+    pub fn calibration_on(&self, interface: &str) -> BTreeMap<u8, Calibration> {
+        match &self.robot {
+            Some(robot) => robot.calibrations_on(interface),
+            None => self.calibration.clone(),
+        }
+    }
+
+    /// Software limits of the motors on `interface`
+    pub fn limits_on(&self, interface: &str) -> BTreeMap<u8, Limits> {
+        match &self.robot {
+            Some(robot) => robot.limits_on(interface),
+            None => self.limits.clone(),
+        }
+    }
+
+    /// Motors the config knows on `interface`: the robot's joints, or those
+    /// with limits or a calibration
+    pub fn motor_ids_on(&self, interface: &str) -> BTreeSet<u8> {
+        match &self.robot {
+            Some(robot) => robot.motor_ids_on(interface).into_iter().collect(),
+            None => self.limits.keys().chain(self.calibration.keys()).copied().collect(),
+        }
+    }
+}
+
+/// Bus selection flags; they override the `--config` file
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct BusArgs {
+    /// CAN interface (default: can0)
+    #[arg(short, long, global = true)]
+    pub interface: Option<String>,
+
+    /// CAN bitrate (default: 1000000)
+    #[arg(short, long, global = true)]
+    pub bitrate: Option<u32>,
+
+    /// Fraction of the bus bandwidth left free for other nodes (IMU, BMS)
+    #[arg(long, value_name = "FRACTION", global = true)]
+    pub reserve_bandwidth: Option<f64>,
+
+    /// Tool configuration or robot description (JSON, TOML or YAML): bus settings and per-motor limits
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Use the CAN channel even if another program holds its lock
+    #[arg(long, global = true)]
+    pub force: bool,
+
+    /// Replay a candump log (candump -l) instead of opening the interface
+    #[arg(long, value_name = "FILE", global = true)]
+    pub candump: Option<PathBuf>,
+}
+
+impl BusArgs {
+    /// The `--config` file (or the defaults) with the flags applied
+    pub fn config(&self) -> Result<ToolConfig> {
+        let mut config = self.config.as_ref().map(ToolConfig::load).transpose()?.unwrap_or_default();
+        if let Some(interface) = &self.interface {
+            config.interface = interface.clone();
+            config.limits = config.limits_on(interface);
+            config.calibration = config.calibration_on(interface);
+        }
+        if let Some(bitrate) = self.bitrate {
+            config.bitrate = bitrate;
+        }
+        if let Some(fraction) = self.reserve_bandwidth {
+            config.reserve_bandwidth = fraction;
+        }
+        Ok(config)
+    }
+
+    /// Open the configured interface, see [`open_on`](Self::open_on)
+    pub fn open(&self, config: &ToolConfig) -> Result<LivelyMotorController> {
+        self.open_on(config, &config.interface)
+    }
+
+    /// Open `interface` with the configured bitrate, bandwidth reservation and
+    /// limits, and refuse to continue if a joint of the configured inventory
+    /// on it is not driven by its recorded motor, or if a configured motor
+    /// reports a scaling other than the `FACTOR_*` constants
+    pub fn open_on(&self, config: &ToolConfig, interface: &str) -> Result<LivelyMotorController> {
+        let controller = self.open_unchecked_on(config, interface)?;
+        if !controller.bus().is_replay() {
+            for motor_id in config.motor_ids_on(interface) {
+                controller
+                    .check_scaling(motor_id)
+                    .context("refusing to command a motor that scales its values differently")?;
+            }
+        }
+        if let Some(path) = config.inventory.as_ref().filter(|_| !controller.bus().is_replay()) {
+            let inventory = JointInventory::load(path)?.on_channel(controller.channel());
+            inventory
+                .check(&[&controller])?
+                .ensure_ok()
+                .map_err(|e| e.context("run `livelybot inventory check` to find and remap the motors"))?;
+            execute!(stdout(), Print("🔎 ".green()), Print(format!("{} 个关节与电机序列号一致\n", inventory.joints.len())))?;
+        }
+        Ok(controller)
+    }
+
+    /// Open the configured interface without the inventory check, for tools
+    /// that only look at the bus (scan, register access, recording)
+    pub fn open_unchecked(&self, config: &ToolConfig) -> Result<LivelyMotorController> {
+        self.open_unchecked_on(config, &config.interface)
+    }
+
+    fn open_unchecked_on(&self, config: &ToolConfig, interface: &str) -> Result<LivelyMotorController> {
+        let controller = if let Some(path) = &self.candump {
+            execute!(stdout(), Print("⏯️  ".cyan()), Print(format!("回放 candump 日志 {}\n", path.display())))?;
+            LivelyMotorController::with_bus(CanBus::replay(path, config.bitrate)?)
+        } else if self.force {
+            LivelyMotorController::new_forced(interface, config.bitrate)?
+        } else {
+            LivelyMotorController::new(interface, config.bitrate)?
+        };
+        if !controller.owns_bus() && !controller.bus().is_replay() {
+            execute!(stdout(), Print("⚠️  ".yellow()), Print("--force: 未持有总线锁, 其他程序可能同时控制电机\n"))?;
+        }
+        controller.bus().reserve_bandwidth(config.reserve_bandwidth)?;
+        for (&motor_id, &limits) in &config.limits_on(interface) {
+            controller.set_limits(motor_id, limits)?;
+        }
+        for (&motor_id, &calibration) in &config.calibration_on(interface) {
+            controller.set_calibration(motor_id, calibration)?;
+        }
+        Ok(controller)
+    }
+}
+
+/// Flags cleared by Ctrl+C
+pub struct StopFlags {
+    /// Cleared by the first Ctrl+C: stop the running mode
+    pub running: Arc<AtomicBool>,
+    /// Cleared by a second Ctrl+C: abort the shutdown sequence as well (e.g. parking)
+    pub shutdown: Arc<AtomicBool>,
+}
+
+impl StopFlags {
+    /// Install the Ctrl+C handler; only one can be installed per process
+    pub fn install() -> Result<Self> {
+        let running = Arc::new(AtomicBool::new(true));
+        let shutdown = Arc::new(AtomicBool::new(true));
+        let (r, s) = (running.clone(), shutdown.clone());
+        ctrlc::set_handler(move || {
+            if !r.swap(false, Ordering::SeqCst) {
+                s.store(false, Ordering::SeqCst);
+            }
+        })?;
+        Ok(Self { running, shutdown })
+    }
+}
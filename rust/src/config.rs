@@ -0,0 +1,274 @@
+//! Robot description files
+//!
+//! A [`RobotConfig`] lists the CAN channels of a robot and its joints: motor
+//! ID, name, gear ratio, direction, zero offset, gains and software limits.
+//! It is read from TOML, YAML or JSON, picked by the file extension, and
+//! builds a configured [`MotorGroup`] per channel, whose commands and
+//! feedback are in joint angles ([`Calibration`]):
+//!
+//! ```yaml
+//! bus:
+//!   interface: can0
+//!   bitrate: 1000000
+//! joints:
+//!   - name: left_knee
+//!     motor_id: 1
+//!     gear_ratio: 1.5
+//!     reversed: true
+//!     zero_offset_deg: 12.5
+//!     kp: 2.0
+//!     kd: 0.2
+//!     limits: { min_pos_deg: -90, max_pos_deg: 90 }
+//!   - name: right_knee
+//!     motor_id: 1
+//!     interface: can1
+//! ```
+//!
+//! The `robot.toml` written by `motor_setup` is a valid description. The
+//! tools read the same file with `--config` ([`ToolConfig`](crate::cli::ToolConfig)).
+
+use crate::{Calibration, EnableConfig, LivelyMotorController, Limits, MotorGroup};
+use anyhow::{Result, anyhow};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Read a TOML (`.toml`), YAML (`.yaml`, `.yml`) or JSON (any other extension) file
+pub fn load_value<P: AsRef<Path>>(path: P) -> Result<Value> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+    let value = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&text).map_err(|e| anyhow!("Invalid TOML in {}: {}", path.display(), e))?,
+        Some("yaml" | "yml") => {
+            serde_yaml::from_str(&text).map_err(|e| anyhow!("Invalid YAML in {}: {}", path.display(), e))?
+        }
+        _ => serde_json::from_str(&text).map_err(|e| anyhow!("Invalid JSON in {}: {}", path.display(), e))?,
+    };
+    Ok(value)
+}
+
+/// Read a TOML, YAML or JSON file (see [`load_value`]) as `T`
+pub fn load_file<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    let path = path.as_ref();
+    serde_json::from_value(load_value(path)?).map_err(|e| anyhow!("Invalid {}: {}", path.display(), e))
+}
+
+/// Whether a loaded document is a robot description rather than a plain config
+pub fn is_robot_description(value: &Value) -> bool {
+    value.get("joints").is_some_and(Value::is_array)
+}
+
+/// Default bus of the robot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BusSection {
+    pub interface: String,
+    pub bitrate: u32,
+    /// Fraction of the bus bandwidth left free for other nodes (IMU, BMS)
+    pub reserve_bandwidth: f64,
+    /// Gain profile applied after enabling, as written by `motor_setup`
+    pub profile: Option<String>,
+}
+
+impl Default for BusSection {
+    fn default() -> Self {
+        Self {
+            interface: "can0".to_string(),
+            bitrate: 1_000_000,
+            reserve_bandwidth: 0.0,
+            profile: None,
+        }
+    }
+}
+
+fn default_gear_ratio() -> f64 {
+    1.0
+}
+
+/// One joint of the robot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JointConfig {
+    pub name: String,
+    pub motor_id: u8,
+    /// CAN interface of the motor; the bus interface if not given
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// Motor model, informational
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Motor turns per joint turn
+    #[serde(default = "default_gear_ratio")]
+    pub gear_ratio: f64,
+    /// Motor turns opposite to the joint's positive direction (also read as `inverted`)
+    #[serde(default, alias = "inverted")]
+    pub reversed: bool,
+    /// Motor angle at joint zero
+    #[serde(default)]
+    pub zero_offset_deg: f64,
+    /// Position gains written when the joint is enabled; the
+    /// [`EnableConfig::position`] defaults if not given
+    #[serde(default)]
+    pub kp: Option<f32>,
+    #[serde(default)]
+    pub kd: Option<f32>,
+    /// Torque limit written when the joint is enabled (Nm)
+    #[serde(default)]
+    pub torque_limit: Option<f32>,
+    /// Software limits of the joint, in joint degrees
+    #[serde(default)]
+    pub limits: Option<Limits>,
+}
+
+impl JointConfig {
+    /// Zero offset, direction and gear ratio, installed in the controller by
+    /// [`RobotConfig::group`]
+    pub fn calibration(&self) -> Calibration {
+        Calibration::new(self.zero_offset_deg, self.reversed).with_gear_ratio(self.gear_ratio)
+    }
+
+    /// Motor angle for a joint angle
+    pub fn to_motor_deg(&self, joint_deg: f64) -> f64 {
+        self.calibration().to_motor_deg(joint_deg)
+    }
+
+    /// Joint angle for a motor angle
+    pub fn to_joint_deg(&self, motor_deg: f64) -> f64 {
+        self.calibration().to_joint_deg(motor_deg)
+    }
+
+    /// Joint velocity for a motor velocity
+    pub fn to_joint_rps(&self, motor_rps: f64) -> f64 {
+        self.calibration().to_joint_velocity(motor_rps)
+    }
+
+    /// What enabling the joint writes: position mode with its gains and torque limit
+    pub fn enable_config(&self) -> EnableConfig {
+        let defaults = EnableConfig::position();
+        let mut config = defaults.with_gains(self.kp.unwrap_or(defaults.kp), self.kd.unwrap_or(defaults.kd));
+        config.torque_limit_nm = self.torque_limit;
+        config
+    }
+}
+
+/// Channels and joints of a robot
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RobotConfig {
+    #[serde(default)]
+    pub bus: BusSection,
+    pub joints: Vec<JointConfig>,
+    /// Joint inventory (see [`JointInventory`](crate::JointInventory)) checked before motors are commanded
+    #[serde(default)]
+    pub inventory: Option<PathBuf>,
+}
+
+impl RobotConfig {
+    /// Load and validate a description; a relative `inventory` is resolved
+    /// against the file's directory
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut config: Self = load_file(path)?;
+        if let (Some(inventory), Some(dir)) = (&config.inventory, path.parent()) {
+            if inventory.is_relative() {
+                config.inventory = Some(dir.join(inventory));
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (i, joint) in self.joints.iter().enumerate() {
+            if !(1..=127).contains(&joint.motor_id) {
+                return Err(anyhow!("joint {}: motor ID {} is outside 1..=127", joint.name, joint.motor_id));
+            }
+            joint.calibration().validate().map_err(|e| e.context(format!("joint {}", joint.name)))?;
+            joint.enable_config().validate().map_err(|e| e.context(format!("joint {}", joint.name)))?;
+            for other in &self.joints[..i] {
+                if other.name == joint.name {
+                    return Err(anyhow!("joint {} is listed twice", joint.name));
+                }
+                if other.motor_id == joint.motor_id && self.interface_of(other) == self.interface_of(joint) {
+                    return Err(anyhow!(
+                        "joints {} and {} both use motor {} on {}",
+                        other.name,
+                        joint.name,
+                        joint.motor_id,
+                        self.interface_of(joint)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// CAN interface of a joint
+    pub fn interface_of<'a>(&'a self, joint: &'a JointConfig) -> &'a str {
+        joint.interface.as_deref().unwrap_or(&self.bus.interface)
+    }
+
+    /// Interfaces with joints on them, the bus interface first
+    pub fn channels(&self) -> Vec<String> {
+        let mut channels = vec![self.bus.interface.clone()];
+        for joint in &self.joints {
+            let interface = self.interface_of(joint);
+            if !channels.iter().any(|c| c == interface) {
+                channels.push(interface.to_string());
+            }
+        }
+        channels
+    }
+
+    pub fn joint(&self, name: &str) -> Option<&JointConfig> {
+        self.joints.iter().find(|j| j.name == name)
+    }
+
+    /// Joints on `interface`, in file order
+    pub fn joints_on<'a>(&'a self, interface: &'a str) -> impl Iterator<Item = &'a JointConfig> + 'a {
+        self.joints.iter().filter(move |j| self.interface_of(j) == interface)
+    }
+
+    /// Motor IDs of the joints on `interface`, in file order
+    pub fn motor_ids_on(&self, interface: &str) -> Vec<u8> {
+        self.joints_on(interface).map(|j| j.motor_id).collect()
+    }
+
+    /// Calibrations of the joints on `interface`, by motor ID
+    pub fn calibrations_on(&self, interface: &str) -> BTreeMap<u8, Calibration> {
+        self.joints_on(interface).map(|j| (j.motor_id, j.calibration())).filter(|(_, c)| !c.is_identity()).collect()
+    }
+
+    /// Software limits of the joints on `interface`, by motor ID
+    pub fn limits_on(&self, interface: &str) -> BTreeMap<u8, Limits> {
+        self.joints_on(interface).filter_map(|j| Some((j.motor_id, j.limits?))).collect()
+    }
+
+    /// Open `interface` with the bus bitrate and bandwidth reservation
+    pub fn open(&self, interface: &str) -> Result<LivelyMotorController> {
+        let controller = LivelyMotorController::new(interface, self.bus.bitrate)?;
+        controller.bus().reserve_bandwidth(self.bus.reserve_bandwidth)?;
+        Ok(controller)
+    }
+
+    /// Group of the joints on `controller`'s channel, with their calibrations
+    /// and limits installed in the controller and their gains used by
+    /// [`MotorGroup::enable_all`]
+    pub fn group<'a>(&self, controller: &'a LivelyMotorController) -> Result<MotorGroup<'a>> {
+        let channel = controller.channel();
+        let joints: Vec<&JointConfig> = self.joints_on(channel).collect();
+        if joints.is_empty() {
+            return Err(anyhow!("robot description has no joints on {}", channel));
+        }
+        let motor_ids: Vec<u8> = joints.iter().map(|j| j.motor_id).collect();
+        let mut group = MotorGroup::new(controller, &motor_ids)?;
+        for joint in joints {
+            controller.set_calibration(joint.motor_id, joint.calibration())?;
+            if let Some(limits) = joint.limits {
+                controller.set_limits(joint.motor_id, limits)?;
+            }
+            group = group.with_enable_config(joint.motor_id, joint.enable_config());
+        }
+        Ok(group)
+    }
+}
@@ -0,0 +1,52 @@
+//! Effective configuration fingerprint
+//!
+//! Hashes the configuration a run actually used (gains read back from the
+//! motors, limits, joint mapping, command line) into a short ID stored with
+//! recordings, so a recorded behavior can always be traced to the exact
+//! configuration that produced it. Parts are serialized to JSON with sorted
+//! keys, so the hash does not depend on field or insertion order.
+
+use crate::audit::fnv1a64;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Named configuration parts and their combined hash
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFingerprint {
+    parts: BTreeMap<String, Value>,
+}
+
+impl ConfigFingerprint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a part, e.g. `("streamer", &streamer_config)`; a part of the same name is replaced
+    pub fn with<T: Serialize>(mut self, name: &str, part: &T) -> Result<Self> {
+        self.add(name, part)?;
+        Ok(self)
+    }
+
+    pub fn add<T: Serialize>(&mut self, name: &str, part: &T) -> Result<()> {
+        self.parts.insert(name.to_string(), serde_json::to_value(part)?);
+        Ok(())
+    }
+
+    /// Names of the parts included
+    pub fn parts(&self) -> Vec<&str> {
+        self.parts.keys().map(String::as_str).collect()
+    }
+
+    /// Canonical JSON of all parts, the input of the hash
+    pub fn canonical_json(&self) -> String {
+        // serde_json objects keep their keys sorted
+        serde_json::to_string(&self.parts).unwrap_or_default()
+    }
+
+    /// FNV-1a 64 of the canonical JSON, hex
+    pub fn hash(&self) -> String {
+        format!("{:016x}", fnv1a64(self.canonical_json().as_bytes()))
+    }
+}
@@ -0,0 +1,138 @@
+//! Interactive console alongside a running control loop
+//!
+//! Reading stdin blocks, so a [`Console`] reads lines on its own thread and
+//! passes parsed [`ConsoleCommand`]s through a channel. The control loop drains
+//! them once per cycle with [`Console::poll`] and keeps its timing.
+
+use anyhow::{Result, anyhow};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+/// A command typed at the console
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    /// New setpoint (a bare number, e.g. `90` or `-2.5`)
+    Target(f64),
+    /// Named parameter, e.g. `kp 2.0`, `kd 0.1`, `acc 10`
+    Set { name: String, value: f64 },
+    /// Stop following new setpoints (`pause` / `p`)
+    Pause,
+    /// Follow setpoints again (`resume` / `r`)
+    Resume,
+    /// Leave the loop (`q`, `quit`, `exit` or end of input)
+    Quit,
+}
+
+impl ConsoleCommand {
+    /// Parse one console line, `Ok(None)` for an empty line
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let mut words = line.split_whitespace();
+        let Some(first) = words.next() else {
+            return Ok(None);
+        };
+        let first = first.to_lowercase();
+
+        let command = match (first.as_str(), words.next()) {
+            ("q" | "quit" | "exit", None) => Self::Quit,
+            ("p" | "pause", None) => Self::Pause,
+            ("r" | "resume", None) => Self::Resume,
+            (word, None) => Self::Target(word.parse().map_err(|_| anyhow!("unknown command '{}'", word))?),
+            (name, Some(value)) => Self::Set {
+                name: name.to_string(),
+                value: value.parse().map_err(|_| anyhow!("'{}' is not a number", value))?,
+            },
+        };
+
+        match words.next() {
+            Some(extra) => Err(anyhow!("unexpected '{}'", extra)),
+            None => Ok(Some(command)),
+        }
+    }
+}
+
+/// Input read from the console: a command, or a line that did not parse
+pub type ConsoleInput = std::result::Result<ConsoleCommand, String>;
+
+/// Non-blocking command channel into a control loop
+pub struct Console {
+    rx: Receiver<ConsoleInput>,
+    prompt: String,
+}
+
+impl Console {
+    /// Read stdin on a background thread
+    ///
+    /// `prompt` is printed once here and again by [`prompt`](Self::prompt),
+    /// which the loop calls after answering a command. End of input is
+    /// reported as [`ConsoleCommand::Quit`].
+    pub fn stdin(prompt: &str) -> Self {
+        let mut console = Self::reader(BufReader::new(std::io::stdin()));
+        console.prompt = prompt.to_string();
+        console.prompt();
+        console
+    }
+
+    /// Read lines from `reader` on a background thread, e.g. a script or a
+    /// socket; end of input is reported as [`ConsoleCommand::Quit`]
+    pub fn reader<R: BufRead + Send + 'static>(mut reader: R) -> Self {
+        let (tx, console) = Self::channel();
+        thread::spawn(move || {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let input = match reader.read_line(&mut line) {
+                    Ok(0) | Err(_) => Ok(ConsoleCommand::Quit),
+                    Ok(_) => match ConsoleCommand::parse(&line) {
+                        Ok(Some(command)) => Ok(command),
+                        Ok(None) => continue,
+                        Err(e) => Err(e.to_string()),
+                    },
+                };
+                let quit = input == Ok(ConsoleCommand::Quit);
+                if tx.send(input).is_err() || quit {
+                    break;
+                }
+            }
+        });
+        console
+    }
+
+    /// Console fed by a sender, e.g. from a network handler or a test
+    pub fn channel() -> (Sender<ConsoleInput>, Self) {
+        let (tx, rx) = mpsc::channel();
+        (
+            tx,
+            Self {
+                rx,
+                prompt: String::new(),
+            },
+        )
+    }
+
+    /// Print the prompt for the next line
+    pub fn prompt(&self) {
+        if !self.prompt.is_empty() {
+            print!("{}", self.prompt);
+            let _ = std::io::stdout().flush();
+        }
+    }
+
+    /// Inputs received since the last poll, without blocking
+    ///
+    /// Once the sender is gone, a closed channel reads as [`ConsoleCommand::Quit`].
+    pub fn poll(&self) -> Vec<ConsoleInput> {
+        let mut inputs = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(input) => inputs.push(input),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    inputs.push(Ok(ConsoleCommand::Quit));
+                    break;
+                }
+            }
+        }
+        inputs
+    }
+}
@@ -0,0 +1,442 @@
+//! Fixed-rate control loop runtime
+//!
+//! Runs a user step closure at a fixed frequency and dispatches periodic tasks
+//! (e.g. temperature polling every 1000 cycles, parameter verification every 10 s)
+//! from a built-in scheduler, so callers don't need hand-rolled modulo counters.
+//! External sensors (load cells, force plates) registered on the loop are
+//! sampled at the start of every cycle, so their values line up with the
+//! commands sent in that cycle.
+//!
+//! With a TX deadline ([`ControlLoop::with_tx_deadline`]) latency is bounded
+//! instead: a cycle that starts past its deadline is skipped, and a step
+//! still running at the deadline is counted as an overrun, in the loop's
+//! [`DeadlineStats`]. Steps check [`CycleInfo::missed_deadline`] before sending,
+//! so the motors hold the previous setpoint rather than get a late one.
+//!
+//! The loop sleeps with an absolute `clock_nanosleep` on the monotonic clock,
+//! so it wakes at the cycle's scheduled instant instead of one relative
+//! sleep after the time spent computing it. The achieved period, its jitter
+//! and the wake-up latency are measured in [`JitterStats`].
+//!
+//! For 1 kHz control the loop can also run its thread under `SCHED_FIFO` and
+//! pin it to a CPU ([`ControlLoop::with_realtime_priority`],
+//! [`ControlLoop::with_core`]). Without the privileges for that it runs under
+//! the default scheduler and keeps the reason ([`ControlLoop::realtime_errors`]).
+
+use crate::rt;
+use crate::stats::{DeadlineStats, JitterStats};
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Information about the current loop cycle
+#[derive(Debug, Clone, Copy)]
+pub struct CycleInfo {
+    /// Cycle counter, starting at 0
+    pub cycle: u64,
+    /// Time since the loop started
+    pub elapsed: Duration,
+    /// Scheduled start instant of this cycle
+    pub deadline: Instant,
+    /// Instant by which the cycle's frames must be sent, in deadline mode
+    pub tx_deadline: Option<Instant>,
+}
+
+impl CycleInfo {
+    /// Whether the TX deadline has passed; always false without one
+    pub fn missed_deadline(&self) -> bool {
+        self.tx_deadline.is_some_and(|tx| Instant::now() > tx)
+    }
+}
+
+/// External sensor values sampled at the start of a cycle
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SensorFrame {
+    /// `(name, value)` in registration order, `None` if the sensor had no reading
+    values: Vec<(String, Option<f64>)>,
+}
+
+impl SensorFrame {
+    /// Value of a sensor by name
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values.iter().find(|(n, _)| n == name).and_then(|(_, v)| *v)
+    }
+
+    /// Sensor names in registration order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.iter().map(|(n, _)| n.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<f64>)> {
+        self.values.iter().map(|(n, v)| (n.as_str(), *v))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+type SensorFn<'a> = Box<dyn FnMut() -> Option<f64> + 'a>;
+
+/// Handle identifying a scheduled task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+type TaskFn<'a> = Box<dyn FnMut(&CycleInfo) -> Result<()> + 'a>;
+
+enum Trigger {
+    Cycles { every: u64, next: u64 },
+    Interval { every: Duration, next: Instant },
+}
+
+struct Task<'a> {
+    id: TaskId,
+    trigger: Trigger,
+    run: TaskFn<'a>,
+}
+
+/// Periodic task scheduler driven by the control loop
+///
+/// Due times are advanced by whole periods from the previous due time, so
+/// tasks don't drift when a cycle runs late.
+#[derive(Default)]
+pub struct Scheduler<'a> {
+    tasks: Vec<Task<'a>>,
+    next_id: u64,
+}
+
+impl<'a> Scheduler<'a> {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Run `task` every `cycles` loop cycles (first run on cycle `cycles`)
+    pub fn every_cycles<F>(&mut self, cycles: u64, task: F) -> TaskId
+    where
+        F: FnMut(&CycleInfo) -> Result<()> + 'a,
+    {
+        let every = cycles.max(1);
+        self.push(Trigger::Cycles { every, next: every }, Box::new(task))
+    }
+
+    /// Run `task` every `interval` of wall time (first run after one interval)
+    ///
+    /// The scheduler can't run a task more often than it is ticked: a zero
+    /// interval runs it every cycle, like `every_cycles(1, ..)`.
+    pub fn every<F>(&mut self, interval: Duration, task: F) -> TaskId
+    where
+        F: FnMut(&CycleInfo) -> Result<()> + 'a,
+    {
+        if interval.is_zero() {
+            return self.every_cycles(1, task);
+        }
+        let next = Instant::now() + interval;
+        self.push(Trigger::Interval { every: interval, next }, Box::new(task))
+    }
+
+    /// Remove a scheduled task, returns false if it was not found
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        let before = self.tasks.len();
+        self.tasks.retain(|t| t.id != id);
+        self.tasks.len() != before
+    }
+
+    /// Number of scheduled tasks
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Run all tasks that are due for this cycle
+    pub fn tick(&mut self, info: &CycleInfo) -> Result<()> {
+        let now = Instant::now();
+        for task in &mut self.tasks {
+            let due = match &mut task.trigger {
+                Trigger::Cycles { every, next } => {
+                    if info.cycle >= *next {
+                        while *next <= info.cycle {
+                            *next += *every;
+                        }
+                        true
+                    } else {
+                        false
+                    }
+                }
+                Trigger::Interval { every, next } => {
+                    if now >= *next {
+                        // Skip the due times that passed, without a loop per missed interval
+                        let missed = (now - *next).as_nanos() / every.as_nanos() + 1;
+                        *next += Duration::from_nanos((every.as_nanos() * missed) as u64);
+                        true
+                    } else {
+                        false
+                    }
+                }
+            };
+
+            if due {
+                (task.run)(info)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, trigger: Trigger, run: TaskFn<'a>) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        self.tasks.push(Task { id, trigger, run });
+        id
+    }
+}
+
+/// Fixed-rate loop runner
+///
+/// Cycle `n` is due at `start + n * period`, computed from the start instant
+/// rather than by adding up periods, so long runs stay on the wall clock
+/// timeline: a 10 minute trajectory ends 10 minutes after it started.
+pub struct ControlLoop<'a> {
+    period: Duration,
+    start: Option<Instant>,
+    tx_deadline: Option<Duration>,
+    stats: DeadlineStats,
+    jitter: JitterStats,
+    realtime_priority: Option<i32>,
+    core: Option<usize>,
+    realtime: bool,
+    /// Why the requested priority or CPU could not be applied in the last run
+    realtime_errors: Vec<anyhow::Error>,
+    scheduler: Scheduler<'a>,
+    sensors: Vec<(String, SensorFn<'a>)>,
+}
+
+impl<'a> ControlLoop<'a> {
+    /// Create a loop running at `frequency_hz`
+    pub fn new(frequency_hz: f64) -> Self {
+        Self::with_period(Duration::from_secs_f64(1.0 / frequency_hz.max(1e-3)))
+    }
+
+    /// Create a loop with an explicit period
+    pub fn with_period(period: Duration) -> Self {
+        Self {
+            period,
+            start: None,
+            tx_deadline: None,
+            stats: DeadlineStats::default(),
+            jitter: JitterStats::new(period),
+            realtime_priority: None,
+            core: None,
+            realtime: false,
+            realtime_errors: Vec::new(),
+            scheduler: Scheduler::new(),
+            sensors: Vec::new(),
+        }
+    }
+
+    /// Anchor the timeline at `start` instead of the moment `run` is called
+    ///
+    /// Loops on several machines given the same (synchronized) start instant
+    /// tick on the same timeline. A start in the past skips the cycles that
+    /// are already over.
+    pub fn with_start(mut self, start: Instant) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Require every cycle to send within `budget` of its scheduled start
+    ///
+    /// Cycles starting later are skipped and steps finishing later counted as
+    /// overruns; a budget above the period is cut to the period.
+    pub fn with_tx_deadline(mut self, budget: Duration) -> Self {
+        self.tx_deadline = Some(budget);
+        self
+    }
+
+    /// Run the loop's thread under `SCHED_FIFO` at `prio` (1–99)
+    ///
+    /// Applied to the calling thread when `run` starts and kept afterwards.
+    /// Without the privileges the loop runs at normal priority; see
+    /// [`realtime_errors`](Self::realtime_errors).
+    pub fn with_realtime_priority(mut self, prio: i32) -> Self {
+        self.realtime_priority = Some(prio);
+        self
+    }
+
+    /// Pin the loop's thread to CPU `core`, falling back like
+    /// [`with_realtime_priority`](Self::with_realtime_priority)
+    pub fn with_core(mut self, core: usize) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    /// Whether the last run got the requested priority and CPU; false when
+    /// none were requested
+    pub fn is_realtime(&self) -> bool {
+        self.realtime
+    }
+
+    /// Why the last run could not get the requested priority or CPU, empty
+    /// when it got them or none were requested
+    pub fn realtime_errors(&self) -> &[anyhow::Error] {
+        &self.realtime_errors
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Deadline accounting of the runs so far
+    pub fn stats(&self) -> DeadlineStats {
+        self.stats
+    }
+
+    /// Achieved period, jitter and wake-up latency of the runs so far
+    pub fn jitter(&self) -> JitterStats {
+        self.jitter
+    }
+
+    /// Access the periodic task scheduler
+    pub fn scheduler(&mut self) -> &mut Scheduler<'a> {
+        &mut self.scheduler
+    }
+
+    /// Register an external sensor read at the start of every cycle
+    ///
+    /// `read` returns `None` when no reading is available (e.g. a load cell
+    /// sampling slower than the loop). A sensor of the same name is replaced.
+    pub fn add_sensor<F>(&mut self, name: &str, read: F)
+    where
+        F: FnMut() -> Option<f64> + 'a,
+    {
+        self.sensors.retain(|(n, _)| n != name);
+        self.sensors.push((name.to_string(), Box::new(read)));
+    }
+
+    /// Run `step` every period until `running` is cleared or `step` returns false
+    pub fn run<F>(&mut self, running: &AtomicBool, mut step: F) -> Result<()>
+    where
+        F: FnMut(&CycleInfo) -> Result<bool>,
+    {
+        self.run_with_sensors(running, |info, _| step(info))
+    }
+
+    /// Like [`run`](Self::run), passing the sensor values sampled for the cycle
+    pub fn run_with_sensors<F>(&mut self, running: &AtomicBool, mut step: F) -> Result<()>
+    where
+        F: FnMut(&CycleInfo, &SensorFrame) -> Result<bool>,
+    {
+        self.apply_realtime();
+        let start = self.start.unwrap_or_else(Instant::now);
+        let period_ns = self.period.as_nanos().max(1);
+        let budget = self.tx_deadline.map(|budget| budget.min(self.period));
+        let mut slot = 0u64;
+        let mut cycle = 0u64;
+        let mut previous: Option<(u64, Instant)> = None;
+        let mut frame = SensorFrame {
+            values: self.sensors.iter().map(|(name, _)| (name.clone(), None)).collect(),
+        };
+
+        while running.load(Ordering::SeqCst) {
+            let mut deadline = start + slot_offset(period_ns, slot);
+            let now = Instant::now();
+            if deadline > now {
+                sleep_until(deadline);
+            } else if now - deadline > self.period {
+                // Fell more than one period behind: skip the missed cycles
+                // instead of bursting, without shifting the timeline
+                let current = ((now - start).as_nanos() / period_ns) as u64;
+                if budget.is_some() {
+                    self.stats.record_skipped(current - slot, cycle);
+                }
+                slot = current;
+                deadline = start + slot_offset(period_ns, slot);
+            }
+
+            let tx_deadline = budget.map(|budget| deadline + budget);
+            if tx_deadline.is_some_and(|tx| Instant::now() > tx) {
+                self.stats.record_skipped(1, cycle);
+                slot += 1;
+                continue;
+            }
+
+            let woke = Instant::now();
+            self.jitter.record_latency(woke.saturating_duration_since(deadline));
+            if let Some((previous_slot, previous_start)) = previous {
+                if previous_slot + 1 == slot {
+                    self.jitter.record_period(woke - previous_start);
+                }
+            }
+            previous = Some((slot, woke));
+
+            let info = CycleInfo {
+                cycle,
+                elapsed: deadline - start,
+                deadline,
+                tx_deadline,
+            };
+
+            for ((_, read), (_, value)) in self.sensors.iter_mut().zip(&mut frame.values) {
+                *value = read();
+            }
+
+            let keep_running = step(&info, &frame)?;
+            let overrun = tx_deadline.map_or(Duration::ZERO, |tx| Instant::now().saturating_duration_since(tx));
+            self.stats.record_cycle(cycle, overrun);
+            if !keep_running {
+                break;
+            }
+            self.scheduler.tick(&info)?;
+
+            cycle += 1;
+            slot += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Apply the requested priority and CPU to the calling thread, keeping
+    /// the errors instead of failing without the privileges
+    fn apply_realtime(&mut self) {
+        let priority = self.realtime_priority.map(rt::set_realtime_priority);
+        let core = self.core.map(rt::pin_to_core);
+        let requested = priority.is_some() || core.is_some();
+        self.realtime_errors = [priority, core].into_iter().flatten().filter_map(Result::err).collect();
+        self.realtime = requested && self.realtime_errors.is_empty();
+    }
+}
+
+/// Sleep until `deadline` with an absolute `clock_nanosleep`, resuming after
+/// signals without drifting
+pub(crate) fn sleep_until(deadline: Instant) {
+    let Some(wait) = deadline.checked_duration_since(Instant::now()) else {
+        return;
+    };
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `now` is a valid timespec for the clock to fill in
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    let nanos = now.tv_nsec as u64 + u64::from(wait.subsec_nanos());
+    let target = libc::timespec {
+        tv_sec: now.tv_sec + (wait.as_secs() + nanos / 1_000_000_000) as libc::time_t,
+        tv_nsec: (nanos % 1_000_000_000) as libc::c_long,
+    };
+    loop {
+        // SAFETY: `target` is a valid absolute time; no remainder is requested
+        let ret = unsafe {
+            libc::clock_nanosleep(libc::CLOCK_MONOTONIC, libc::TIMER_ABSTIME, &target, std::ptr::null_mut())
+        };
+        if ret != libc::EINTR {
+            break;
+        }
+    }
+}
+
+/// Offset of timeline slot `slot`, exact to the nanosecond
+fn slot_offset(period_ns: u128, slot: u64) -> Duration {
+    let ns = period_ns * u128::from(slot);
+    Duration::new((ns / 1_000_000_000) as u64, (ns % 1_000_000_000) as u32)
+}
@@ -0,0 +1,48 @@
+//! Unit conversions of the protocol's int16 values, generic over [`Float`]
+//!
+//! The crate-level functions ([`degrees_to_position`](crate::degrees_to_position)
+//! and friends) take and return `f64` and run these in [`Real`](crate::Real);
+//! use these directly to stay in `f32` on small targets.
+
+use crate::float::Float;
+use crate::{FACTOR_ACC, FACTOR_POS, FACTOR_TQE, FACTOR_VEL};
+
+/// Convert degrees to position integer
+pub fn degrees_to_position<T: Float>(angle_deg: T) -> i16 {
+    (angle_deg / T::from_f64(360.0) * T::from_f64(FACTOR_POS)).to_i16()
+}
+
+/// Convert r/s to velocity integer
+pub fn rps_to_velocity<T: Float>(velocity_rps: T) -> i16 {
+    (velocity_rps * T::from_f64(FACTOR_VEL)).to_i16()
+}
+
+/// Convert r/s² to acceleration integer
+pub fn rps2_to_acceleration<T: Float>(acceleration_rps2: T) -> i16 {
+    (acceleration_rps2 * T::from_f64(FACTOR_ACC)).to_i16()
+}
+
+/// Convert Nm to torque integer
+pub fn nm_to_torque<T: Float>(torque_nm: T) -> i16 {
+    (torque_nm * T::from_f64(FACTOR_TQE)).to_i16()
+}
+
+/// Convert position integer to degrees
+pub fn position_to_degrees<T: Float>(position: i16) -> T {
+    T::from_i16(position) / T::from_f64(FACTOR_POS) * T::from_f64(360.0)
+}
+
+/// Convert velocity integer to r/s
+pub fn velocity_to_rps<T: Float>(velocity: i16) -> T {
+    T::from_i16(velocity) / T::from_f64(FACTOR_VEL)
+}
+
+/// Convert acceleration integer to r/s²
+pub fn acceleration_to_rps2<T: Float>(acceleration: i16) -> T {
+    T::from_i16(acceleration) / T::from_f64(FACTOR_ACC)
+}
+
+/// Convert torque integer to Nm
+pub fn torque_to_nm<T: Float>(torque: i16) -> T {
+    T::from_i16(torque) / T::from_f64(FACTOR_TQE)
+}
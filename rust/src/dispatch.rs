@@ -0,0 +1,136 @@
+//! Per-motor frame dispatch
+//!
+//! A [`FrameDispatcher`] starts the bus's background receiver and routes every
+//! received frame by the motor that sent it, to per-motor channels and
+//! callbacks, so feedback of each joint arrives on its own queue however the
+//! main loop is timed. Frames without a sending motor (stream commands of
+//! other hosts) go to the [`on_other`](FrameDispatcher::on_other) callbacks.
+
+use crate::bus::SUBSCRIBER_QUEUE_LEN;
+use crate::CanBus;
+use socketcan::CanFrame;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Longest wait for a frame before the dispatch thread checks for a stop
+const DISPATCH_SLICE: Duration = Duration::from_millis(10);
+
+type Callback = Box<dyn FnMut(&CanFrame) + Send>;
+
+#[derive(Default)]
+struct Routes {
+    channels: BTreeMap<u8, Vec<SyncSender<CanFrame>>>,
+    callbacks: BTreeMap<u8, Vec<Callback>>,
+    other: Vec<Callback>,
+}
+
+/// Routes received frames to per-motor channels and callbacks on a background thread
+pub struct FrameDispatcher {
+    routes: Arc<Mutex<Routes>>,
+    running: Arc<AtomicBool>,
+    dropped: Arc<AtomicU64>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl FrameDispatcher {
+    /// Start dispatching frames of `bus`, starting its background receiver
+    pub fn start(bus: &Arc<CanBus>) -> Self {
+        bus.start_receiver();
+        let subscription = bus.subscribe();
+        let routes = Arc::new(Mutex::new(Routes::default()));
+        let running = Arc::new(AtomicBool::new(true));
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        let worker = {
+            let (routes, running, dropped) = (Arc::clone(&routes), Arc::clone(&running), Arc::clone(&dropped));
+            thread::spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    match subscription.recv_timeout(DISPATCH_SLICE) {
+                        Ok(Some(frame)) => route(&mut routes.lock().unwrap(), &frame, &dropped),
+                        Ok(None) => {}
+                        Err(_) => break,
+                    }
+                }
+            })
+        };
+
+        Self {
+            routes,
+            running,
+            dropped,
+            worker: Some(worker),
+        }
+    }
+
+    /// Queue of the frames `motor_id` sends from now on
+    ///
+    /// Holds up to [`SUBSCRIBER_QUEUE_LEN`] frames; newer frames are dropped
+    /// while it is full. Dropping the receiver removes the channel.
+    pub fn motor_channel(&self, motor_id: u8) -> Receiver<CanFrame> {
+        let (tx, rx) = mpsc::sync_channel(SUBSCRIBER_QUEUE_LEN);
+        self.routes.lock().unwrap().channels.entry(motor_id).or_default().push(tx);
+        rx
+    }
+
+    /// Call `callback` with every frame `motor_id` sends
+    ///
+    /// Callbacks run on the dispatch thread and must return quickly; they
+    /// must not register further routes on this dispatcher.
+    pub fn on_motor<F>(&self, motor_id: u8, callback: F)
+    where
+        F: FnMut(&CanFrame) + Send + 'static,
+    {
+        self.routes.lock().unwrap().callbacks.entry(motor_id).or_default().push(Box::new(callback));
+    }
+
+    /// Call `callback` with every frame not sent by a motor
+    pub fn on_other<F>(&self, callback: F)
+    where
+        F: FnMut(&CanFrame) + Send + 'static,
+    {
+        self.routes.lock().unwrap().other.push(Box::new(callback));
+    }
+
+    /// Frames dropped because a motor channel was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stop dispatching; the bus receiver keeps running for other subscribers
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for FrameDispatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn route(routes: &mut Routes, frame: &CanFrame, dropped: &AtomicU64) {
+    let Some(motor_id) = crate::reply_source(frame) else {
+        routes.other.iter_mut().for_each(|callback| callback(frame));
+        return;
+    };
+    if let Some(channels) = routes.channels.get_mut(&motor_id) {
+        channels.retain(|tx| match tx.try_send(*frame) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+    if let Some(callbacks) = routes.callbacks.get_mut(&motor_id) {
+        callbacks.iter_mut().for_each(|callback| callback(frame));
+    }
+}
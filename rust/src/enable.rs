@@ -0,0 +1,115 @@
+//! Gains and limits written when a motor is enabled
+//!
+//! [`LivelyMotorController::enable_with`](crate::LivelyMotorController::enable_with)
+//! writes an [`EnableConfig`]; `enable_motor` and `enable_velocity_mode` use
+//! [`EnableConfig::position`] and [`EnableConfig::velocity`], the values they
+//! always wrote. With a ramp the gains start at zero and rise to their targets,
+//! so a joint far from its setpoint does not snap to it on enable.
+
+use anyhow::{Result, anyhow};
+use std::time::Duration;
+
+/// Time between the gain writes of a ramp
+pub const RAMP_STEP: Duration = Duration::from_millis(20);
+
+/// Control mode a motor is enabled in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlMode {
+    Position,
+    Velocity,
+}
+
+/// What enabling a motor writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnableConfig {
+    pub mode: ControlMode,
+    pub kp: f32,
+    pub kd: f32,
+    /// Written before the gains; `None` keeps the motor's current limit
+    pub torque_limit_nm: Option<f32>,
+    /// Raise the gains from zero over this time instead of at once
+    pub ramp: Option<Duration>,
+}
+
+impl Default for EnableConfig {
+    fn default() -> Self {
+        Self::position()
+    }
+}
+
+impl EnableConfig {
+    /// Position mode with kp 1.0, kd 0.1, torque limit unchanged
+    pub fn position() -> Self {
+        Self {
+            mode: ControlMode::Position,
+            kp: 1.0,
+            kd: 0.1,
+            torque_limit_nm: None,
+            ramp: None,
+        }
+    }
+
+    /// Velocity mode with kp 2.0, kd 0.2 and a 3 Nm torque limit
+    pub fn velocity() -> Self {
+        Self {
+            mode: ControlMode::Velocity,
+            kp: 2.0,
+            kd: 0.2,
+            torque_limit_nm: Some(3.0),
+            ramp: None,
+        }
+    }
+
+    /// Position mode with kp 0.0 and kd 0.3: damping only, the joint moves
+    /// freely by hand but resists fast motion; torque limit unchanged
+    pub fn compliant() -> Self {
+        Self {
+            mode: ControlMode::Position,
+            kp: 0.0,
+            kd: 0.3,
+            torque_limit_nm: None,
+            ramp: None,
+        }
+    }
+
+    pub fn with_gains(mut self, kp: f32, kd: f32) -> Self {
+        self.kp = kp;
+        self.kd = kd;
+        self
+    }
+
+    pub fn with_torque_limit(mut self, torque_limit_nm: f32) -> Self {
+        self.torque_limit_nm = Some(torque_limit_nm);
+        self
+    }
+
+    pub fn with_ramp(mut self, ramp: Duration) -> Self {
+        self.ramp = Some(ramp);
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (name, gain) in [("kp", self.kp), ("kd", self.kd)] {
+            if !gain.is_finite() || gain < 0.0 {
+                return Err(anyhow!("{} must be a non-negative number, got {}", name, gain));
+            }
+        }
+        if let Some(limit) = self.torque_limit_nm {
+            if !limit.is_finite() || limit <= 0.0 {
+                return Err(anyhow!("torque limit must be positive, got {} Nm", limit));
+            }
+        }
+        Ok(())
+    }
+
+    /// `(kp, kd)` to write in turn, [`RAMP_STEP`] apart; the last pair is the target
+    pub fn gain_steps(&self) -> Vec<(f32, f32)> {
+        let steps = self.ramp.map_or(1, |ramp| (ramp.as_millis() / RAMP_STEP.as_millis()).max(1) as u32);
+        (1..=steps)
+            .map(|i| {
+                let fraction = i as f32 / steps as f32;
+                (self.kp * fraction, self.kd * fraction)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,245 @@
+//! Failure kinds a caller can match on
+//!
+//! [`LivelyMotorController`](crate::LivelyMotorController), the async
+//! controller and [`CanBus`](crate::CanBus) return `Result<T, MotorError>`,
+//! so callers that need to react differently (retry a timeout, reopen a bus
+//! that went down, stop on a fault, skip what the firmware can't do) match on
+//! the error directly:
+//!
+//! ```no_run
+//! # use livelybot_motor_control::{LivelyMotorController, MotorError};
+//! # let controller = LivelyMotorController::new("can0", 1_000_000)?;
+//! match controller.read_motor_state(1) {
+//!     Ok(state) => println!("{:.1}°", state.position_deg),
+//!     Err(MotorError::Timeout { motor_id, .. }) => eprintln!("motor {} is silent", motor_id),
+//!     Err(e @ MotorError::CanIo(_)) => eprintln!("bus down: {}", e),
+//!     Err(e) => return Err(e.into()),
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Each kind keeps what was being done when it happened in its
+//! [`ErrorContext`], so a timeout reads "enabling motor 7 on can1: reading
+//! feedback of motor 7 on can1: motor 7 did not answer in time".
+//!
+//! Any other failure (an invalid argument, an unreadable file, a read-back
+//! that does not match) is [`MotorError::Other`] with its context chain.
+//!
+//! Only those three return `MotorError`. Everything built on top of them
+//! ([`MotorGroup`](crate::MotorGroup), [`Watchdog`](crate::Watchdog),
+//! [`ControlLoop`](crate::ControlLoop), [`FallCatcher`](crate::FallCatcher),
+//! [`Limits`](crate::Limits), the ROS 2 bridge and the other modules) returns
+//! `anyhow::Result`, because their failures also come from files, user
+//! callbacks and other sources that have no kind. A controller failure
+//! inside them keeps its kind: [`MotorError::of`] finds it below their
+//! context.
+
+use crate::capabilities::Feature;
+use crate::FaultClass;
+use std::fmt;
+use std::io;
+
+/// `Result` of the controller and bus API
+pub type Result<T, E = MotorError> = std::result::Result<T, E>;
+
+/// Fault code read from a motor's fault register (never 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaultCode(pub u8);
+
+impl FaultCode {
+    pub fn class(self) -> FaultClass {
+        FaultClass::from_code(self.0).unwrap_or(FaultClass::Other)
+    }
+}
+
+impl fmt::Display for FaultCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.0, self.class())
+    }
+}
+
+/// What was being done when a failure happened, outermost first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext(Vec<String>);
+
+impl ErrorContext {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    /// Each entry followed by `": "`, so it prefixes the failure's own message
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|context| write!(f, "{}: ", context))
+    }
+}
+
+/// Why a motor operation failed
+#[derive(Debug, thiserror::Error)]
+pub enum MotorError {
+    /// The CAN socket failed: interface missing or down, transmit buffer full, ...
+    /// (socketcan reports its failures as `io::Error`)
+    #[error("CAN I/O error: {0}")]
+    CanIo(#[from] io::Error),
+    /// The motor did not answer in time
+    #[error("{context}motor {motor_id} did not answer in time")]
+    Timeout { motor_id: u8, context: ErrorContext },
+    /// The motor answered with something that is not the expected reply
+    #[error("{context}invalid response from motor {motor_id}: {reason}")]
+    InvalidResponse { motor_id: u8, reason: String, context: ErrorContext },
+    /// The motor reports an active fault
+    #[error("{context}motor {motor_id} reports fault {code}")]
+    MotorFault { motor_id: u8, code: FaultCode, context: ErrorContext },
+    /// The motor's firmware lacks the feature, as found by capability detection
+    #[error("{context}the firmware of motor {motor_id} does not support {feature}")]
+    Unsupported { motor_id: u8, feature: Feature, context: ErrorContext },
+    /// A command was not sent because it is outside the motor's [`Limits`](crate::Limits)
+    /// or not a finite number
+    #[error("{context}motor {motor_id} limit exceeded: {reason}")]
+    LimitExceeded { motor_id: u8, reason: String, context: ErrorContext },
+    /// The motor scales its values differently from the crate's `FACTOR_*` constants
+    #[error("{context}motor {motor_id} scaling mismatch: {reason}")]
+    ScalingMismatch { motor_id: u8, reason: String, context: ErrorContext },
+    /// Any other failure, with its context chain
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl MotorError {
+    /// The failure kind anywhere in the chain of `error`, `None` if there is
+    /// none besides [`MotorError::Other`]
+    pub fn of(error: &anyhow::Error) -> Option<&MotorError> {
+        error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<MotorError>())
+            .find(|e| !matches!(e, MotorError::Other(_)))
+    }
+
+    /// Add `context` around the failure, keeping its kind
+    pub(crate) fn context(self, context: String) -> Self {
+        match self {
+            MotorError::Other(e) => MotorError::Other(e.context(context)),
+            MotorError::CanIo(e) => can_io(e, context),
+            mut e => {
+                if let Some(ErrorContext(outer)) = e.error_context_mut() {
+                    outer.insert(0, context);
+                }
+                e
+            }
+        }
+    }
+
+    /// What was being done, empty for [`MotorError::CanIo`] (whose message
+    /// carries it) and [`MotorError::Other`] (whose chain does)
+    pub fn error_context(&self) -> Option<&ErrorContext> {
+        match self {
+            MotorError::CanIo(_) | MotorError::Other(_) => None,
+            MotorError::Timeout { context, .. }
+            | MotorError::InvalidResponse { context, .. }
+            | MotorError::MotorFault { context, .. }
+            | MotorError::Unsupported { context, .. }
+            | MotorError::LimitExceeded { context, .. }
+            | MotorError::ScalingMismatch { context, .. } => Some(context),
+        }
+    }
+
+    fn error_context_mut(&mut self) -> Option<&mut ErrorContext> {
+        match self {
+            MotorError::CanIo(_) | MotorError::Other(_) => None,
+            MotorError::Timeout { context, .. }
+            | MotorError::InvalidResponse { context, .. }
+            | MotorError::MotorFault { context, .. }
+            | MotorError::Unsupported { context, .. }
+            | MotorError::LimitExceeded { context, .. }
+            | MotorError::ScalingMismatch { context, .. } => Some(context),
+        }
+    }
+
+    /// Motor the failure concerns, `None` for bus and other failures
+    pub fn motor_id(&self) -> Option<u8> {
+        match self {
+            MotorError::CanIo(_) | MotorError::Other(_) => None,
+            MotorError::Timeout { motor_id, .. }
+            | MotorError::InvalidResponse { motor_id, .. }
+            | MotorError::MotorFault { motor_id, .. }
+            | MotorError::Unsupported { motor_id, .. }
+            | MotorError::LimitExceeded { motor_id, .. }
+            | MotorError::ScalingMismatch { motor_id, .. } => Some(*motor_id),
+        }
+    }
+}
+
+impl From<anyhow::Error> for MotorError {
+    /// Take out a failure kind the error was created from, moving the
+    /// context around it into the kind; anything else becomes
+    /// [`MotorError::Other`]
+    fn from(error: anyhow::Error) -> Self {
+        if matches!(error.downcast_ref::<MotorError>(), None | Some(MotorError::Other(_))) {
+            return MotorError::Other(error);
+        }
+        let outer: Vec<String> = error
+            .chain()
+            .take_while(|cause| !cause.is::<MotorError>())
+            .map(|cause| cause.to_string())
+            .collect();
+        let kind = error.downcast().unwrap_or_else(MotorError::Other);
+        outer.into_iter().rev().fold(kind, MotorError::context)
+    }
+}
+
+/// A socket failure with what was being done, keeping its `ErrorKind`
+pub(crate) fn can_io(error: io::Error, context: String) -> MotorError {
+    MotorError::CanIo(io::Error::new(error.kind(), format!("{}: {}", context, error)))
+}
+
+pub(crate) fn timeout(motor_id: u8) -> MotorError {
+    MotorError::Timeout {
+        motor_id,
+        context: ErrorContext::default(),
+    }
+}
+
+pub(crate) fn invalid_response(motor_id: u8, reason: &str) -> MotorError {
+    MotorError::InvalidResponse {
+        motor_id,
+        reason: reason.to_string(),
+        context: ErrorContext::default(),
+    }
+}
+
+pub(crate) fn unsupported(motor_id: u8, feature: Feature) -> MotorError {
+    MotorError::Unsupported {
+        motor_id,
+        feature,
+        context: ErrorContext::default(),
+    }
+}
+
+pub(crate) fn motor_fault(motor_id: u8, code: FaultCode) -> MotorError {
+    MotorError::MotorFault {
+        motor_id,
+        code,
+        context: ErrorContext::default(),
+    }
+}
+
+pub(crate) fn limit_exceeded(motor_id: u8, reason: String) -> MotorError {
+    MotorError::LimitExceeded {
+        motor_id,
+        reason,
+        context: ErrorContext::default(),
+    }
+}
+
+pub(crate) fn scaling_mismatch(motor_id: u8, reason: String) -> MotorError {
+    MotorError::ScalingMismatch {
+        motor_id,
+        reason,
+        context: ErrorContext::default(),
+    }
+}
@@ -0,0 +1,90 @@
+//! Emergency stop behaviour
+//!
+//! Disabling a motor drops its torque at once. That is the safest stop for a
+//! light joint, but a swinging 3 kg leg keeps going, or falls, and hits its
+//! end stop at full speed. A [`StopMode`] chooses how
+//! [`LivelyMotorController::emergency_stop`](crate::LivelyMotorController::emergency_stop),
+//! the [`Watchdog`](crate::Watchdog) and the `stop` action of a
+//! [`FaultPolicy`](crate::FaultPolicy) bring motors to rest:
+//!
+//! - `zero_torque`: disable at once (the default)
+//! - `max_braking`: zero velocity at the highest deceleration the velocity
+//!   stream encodes, then disable
+//! - `ramped`: zero velocity over `ramp_ms`, decelerating evenly from the last
+//!   known velocity, then disable
+//!
+//! The braking modes use the motor's own velocity loop, so they only help
+//! while the motor is enabled and its bus works. The velocity comes from the
+//! controller's state cache; without a recent state the fastest encodable
+//! velocity is assumed, so the motor is at rest by the end of the ramp.
+//!
+//! ```json
+//! { "stop_mode": { "mode": "ramped", "ramp_ms": 50 } }
+//! ```
+
+use crate::{Calibration, StateCache, FACTOR_ACC, FACTOR_VEL};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Highest deceleration the velocity stream encodes
+pub const MAX_BRAKE_ACCELERATION_RPS2: f64 = i16::MAX as f64 / FACTOR_ACC;
+
+/// Speed assumed for a motor without a recent state
+const MAX_VELOCITY_RPS: f64 = i16::MAX as f64 / FACTOR_VEL;
+
+/// Lowest deceleration sent; 0 would mean "no limit" to the motor
+const MIN_BRAKE_ACCELERATION_RPS2: f64 = 1.0 / FACTOR_ACC;
+
+/// Cached states older than this are not trusted for the braking velocity
+const STATE_MAX_AGE: Duration = Duration::from_millis(100);
+
+/// How an emergency stop brings a motor to rest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "mode")]
+pub enum StopMode {
+    /// Disable at once
+    #[default]
+    ZeroTorque,
+    /// Brake to zero velocity as hard as the velocity stream allows, then disable
+    MaxBraking,
+    /// Brake to zero velocity over `ramp_ms`, then disable
+    Ramped { ramp_ms: u64 },
+}
+
+/// Zero-velocity command of a braking stop and how long it takes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Braking {
+    pub acceleration_rps2: f64,
+    /// Time until the motor is at rest and can be disabled
+    pub duration: Duration,
+}
+
+impl StopMode {
+    /// Braking of a motor turning at `velocity_rps` (motor side, `None` if
+    /// unknown); `None` for [`ZeroTorque`](StopMode::ZeroTorque)
+    pub fn braking(&self, velocity_rps: Option<f64>) -> Option<Braking> {
+        let speed = velocity_rps.map_or(MAX_VELOCITY_RPS, f64::abs);
+        let acceleration_rps2 = match *self {
+            StopMode::ZeroTorque => return None,
+            StopMode::MaxBraking => MAX_BRAKE_ACCELERATION_RPS2,
+            StopMode::Ramped { ramp_ms } => speed / (ramp_ms.max(1) as f64 / 1000.0),
+        }
+        .clamp(MIN_BRAKE_ACCELERATION_RPS2, MAX_BRAKE_ACCELERATION_RPS2);
+        Some(Braking {
+            acceleration_rps2,
+            duration: Duration::from_secs_f64(speed / acceleration_rps2),
+        })
+    }
+}
+
+/// Motor-side velocity of `motor_id` from a recent cached state
+pub(crate) fn motor_velocity(
+    states: &StateCache,
+    calibrations: &BTreeMap<u8, Calibration>,
+    motor_id: u8,
+) -> Option<f64> {
+    let cached = states.get(motor_id).filter(|cached| cached.age() < STATE_MAX_AGE)?;
+    let calibration = calibrations.get(&motor_id).copied().unwrap_or_default();
+    Some(calibration.to_motor_velocity(cached.state.velocity_rps))
+}
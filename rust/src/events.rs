@@ -0,0 +1,141 @@
+//! Event bus
+//!
+//! Library components publish notable conditions (safety limits, bus problems)
+//! as [`Event`]s; any number of subscribers receive a copy over a channel.
+//! Each subscriber's channel holds at most [`EVENT_QUEUE_LEN`] events, so a
+//! subscriber that stops reading loses new events (counted by
+//! [`EventBus::dropped`]) instead of growing without bound.
+
+use crate::{BusErrorStats, DecodedSample, FaultAction, FaultClass, RxDropStats};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Events buffered per subscriber before new events are dropped for it
+pub const EVENT_QUEUE_LEN: usize = 1024;
+
+/// Torque duty envelope conditions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeEventKind {
+    /// Peak budget is getting used up (above the warning fraction)
+    Approaching,
+    /// Peak budget exhausted, commands clamped to the continuous torque
+    Limited,
+    /// Budget recovered, peak torque allowed again
+    Recovered,
+    /// A command above the peak torque was clamped
+    PeakClamped,
+}
+
+/// Event kinds published on the bus
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+    /// Torque duty envelope state change for a motor
+    TorqueEnvelope {
+        motor_id: u8,
+        kind: EnvelopeEventKind,
+        /// Fraction of the peak-duration budget used (0..=1)
+        budget_used: f64,
+    },
+    /// Torque limits scaled because of battery voltage (1.0 = no derating)
+    BatteryDerating { voltage_v: f64, torque_scale: f64 },
+    /// Sample decoded from a third-party node (IMU etc.)
+    Decoded(DecodedSample),
+    /// Received frames were lost since the previous check
+    RxFramesDropped { channel: String, lost: RxDropStats },
+    /// An [`AdaptiveRate`](crate::AdaptiveRate) changed the degradation level
+    /// of a bus (0 = recovered) after `errors` since its previous check
+    BusDegradation {
+        channel: String,
+        level: u8,
+        errors: BusErrorStats,
+        /// Fraction of their rate telemetry pollers now run at
+        telemetry_scale: f64,
+        /// Smallest time between motor queries now
+        query_spacing: Duration,
+    },
+    /// A motor fault was handled by the fault policy (`code` is `None` on loss of communication)
+    MotorFault {
+        motor_id: u8,
+        code: Option<u8>,
+        class: FaultClass,
+        action: FaultAction,
+    },
+    /// A [`Watchdog`](crate::Watchdog) was not fed in time and stopped these motors
+    WatchdogTripped { motor_ids: Vec<u8> },
+    /// A [`FallCatcher`](crate::FallCatcher) enabled a disabled motor back-driving at `velocity_rps`
+    FallCaught { motor_id: u8, velocity_rps: f64 },
+    /// A [`FallCatcher`](crate::FallCatcher) could not enable a falling motor
+    FallCatchFailed { motor_id: u8, velocity_rps: f64, reason: String },
+}
+
+impl EventKind {
+    /// Whether the event means a joint was limited, stopped or faulted, the
+    /// conditions a [`FlightRecorder`](crate::FlightRecorder) keeps an incident of
+    pub fn is_safety_event(&self) -> bool {
+        match self {
+            EventKind::TorqueEnvelope { kind, .. } => {
+                matches!(kind, EnvelopeEventKind::Limited | EnvelopeEventKind::PeakClamped)
+            }
+            EventKind::MotorFault { action, .. } => *action != FaultAction::Ignore,
+            EventKind::WatchdogTripped { .. } | EventKind::FallCaught { .. } | EventKind::FallCatchFailed { .. } => {
+                true
+            }
+            EventKind::BatteryDerating { .. }
+            | EventKind::Decoded(_)
+            | EventKind::RxFramesDropped { .. }
+            | EventKind::BusDegradation { .. } => false,
+        }
+    }
+}
+
+/// A timestamped event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub timestamp: Instant,
+    pub kind: EventKind,
+}
+
+/// Fan-out publisher of [`Event`]s
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<SyncSender<Event>>>,
+    /// Events skipped for subscribers whose queue was full
+    dropped: AtomicU64,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive all events published from now on
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (tx, rx) = sync_channel(EVENT_QUEUE_LEN);
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Publish an event to all live subscribers
+    pub fn publish(&self, kind: EventKind) {
+        let event = Event {
+            timestamp: Instant::now(),
+            kind,
+        };
+        // Drop subscribers whose receiver is gone
+        self.subscribers.lock().unwrap().retain(|tx| match tx.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    /// Events not delivered to a subscriber because its queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
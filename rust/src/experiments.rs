@@ -0,0 +1,266 @@
+//! Parameter sweeps
+//!
+//! A [`Sweep`] runs the same excitation once for every value of one parameter
+//! (Kp, Kd, torque limit or command rate) and collects the tracking error,
+//! temperature rise and current draw of each run ([`AbReport`]) into a
+//! [`SweepReport`]: a comparison table instead of an evening of editing
+//! configs and copying numbers by hand. Registers changed by the sweep are
+//! restored afterwards.
+
+use crate::{AbReport, AbTest, EnableConfig, LivelyMotorController, Primitive, Register};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The swept parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepParam {
+    Kp,
+    Kd,
+    /// Torque limit register, Nm
+    TorqueLimit,
+    /// Rate the excitation setpoints are sent at, Hz
+    CommandRate,
+}
+
+impl SweepParam {
+    pub const ALL: [SweepParam; 4] = [SweepParam::Kp, SweepParam::Kd, SweepParam::TorqueLimit, SweepParam::CommandRate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SweepParam::Kp => "kp",
+            SweepParam::Kd => "kd",
+            SweepParam::TorqueLimit => "torque_limit",
+            SweepParam::CommandRate => "command_rate",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name() == name)
+            .ok_or_else(|| anyhow!("unknown sweep parameter {} (kp, kd, torque_limit, command_rate)", name))
+    }
+
+    /// Register holding the parameter, `None` for the command rate
+    pub fn register(self) -> Option<Register> {
+        match self {
+            SweepParam::Kp => Some(Register::Kp),
+            SweepParam::Kd => Some(Register::Kd),
+            SweepParam::TorqueLimit => Some(Register::TorqueLimit),
+            SweepParam::CommandRate => None,
+        }
+    }
+}
+
+/// Result of the excitation at one parameter value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepPoint {
+    pub value: f64,
+    pub report: AbReport,
+}
+
+/// Results of a sweep, in the order the values ran
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepReport {
+    pub param: SweepParam,
+    pub motor_id: u8,
+    pub points: Vec<SweepPoint>,
+}
+
+impl SweepReport {
+    /// Completed point with the lowest RMS tracking error
+    pub fn best(&self) -> Option<&SweepPoint> {
+        self.points
+            .iter()
+            .filter(|p| p.report.completed && p.report.samples > 0)
+            .min_by(|a, b| a.report.rms_error_deg.total_cmp(&b.report.rms_error_deg))
+    }
+
+    /// Header and one row per point; missing readings are empty
+    fn rows(&self) -> Vec<[String; 7]> {
+        let cell = |v: Option<f64>| v.map_or_else(String::new, |v| format!("{:.3}", v));
+        let header = [
+            self.param.name(),
+            "rms_error_deg",
+            "max_error_deg",
+            "temperature_rise_c",
+            "mean_current_a",
+            "peak_current_a",
+            "completed",
+        ]
+        .map(str::to_string);
+        let rows = self.points.iter().map(|p| {
+            let r = &p.report;
+            [
+                format!("{}", p.value),
+                cell(Some(r.rms_error_deg)),
+                cell(Some(r.max_error_deg)),
+                cell(r.temperature_rise_c()),
+                cell(r.mean_current_a),
+                cell(r.peak_current_a),
+                r.completed.to_string(),
+            ]
+        });
+        std::iter::once(header).chain(rows).collect()
+    }
+
+    /// Aligned text table, the best point marked with `*`
+    pub fn table(&self) -> String {
+        let rows = self.rows();
+        let widths: Vec<usize> =
+            (0..7).map(|i| rows.iter().map(|r| r[i].len()).max().unwrap_or(0).max(2)).collect();
+        let best = self.best().map(|b| b.value);
+        let mut out = String::new();
+        for (i, row) in rows.iter().enumerate() {
+            let marker = if i > 0 && best == Some(self.points[i - 1].value) { "*" } else { " " };
+            let _ = write!(out, "{} {:<w$}", marker, row[0], w = widths[0]);
+            for (cell, width) in row.iter().zip(&widths).skip(1) {
+                let _ = write!(out, "  {:>w$}", if cell.is_empty() { "--" } else { cell }, w = width);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        self.rows().iter().map(|row| row.join(",") + "\n").collect()
+    }
+}
+
+/// Runs an excitation on one motor for each value of a parameter
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    param: SweepParam,
+    values: Vec<f64>,
+    excitation: Primitive,
+    rate_hz: f64,
+    max_vel_rps: f64,
+    max_torque_nm: f64,
+    enable: EnableConfig,
+    rest: Duration,
+}
+
+impl Sweep {
+    /// Sweep at 100 Hz (unless the rate is swept) with 2.0 r/s and 3.0 Nm
+    /// limits, [`EnableConfig::position`] gains and 2 s rest between runs
+    pub fn new(param: SweepParam, values: &[f64], excitation: Primitive) -> Self {
+        Self {
+            param,
+            values: values.to_vec(),
+            excitation,
+            rate_hz: 100.0,
+            max_vel_rps: 2.0,
+            max_torque_nm: 3.0,
+            enable: EnableConfig::position(),
+            rest: Duration::from_secs(2),
+        }
+    }
+
+    pub fn with_rate(mut self, rate_hz: f64) -> Self {
+        self.rate_hz = rate_hz;
+        self
+    }
+
+    pub fn with_limits(mut self, max_vel_rps: f64, max_torque_nm: f64) -> Self {
+        self.max_vel_rps = max_vel_rps;
+        self.max_torque_nm = max_torque_nm;
+        self
+    }
+
+    /// Gains and torque limit of every run, except the swept one
+    pub fn with_enable(mut self, enable: EnableConfig) -> Self {
+        self.enable = enable;
+        self
+    }
+
+    /// Time the motor rests disabled between runs, e.g. to cool down
+    pub fn with_rest(mut self, rest: Duration) -> Self {
+        self.rest = rest;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.values.is_empty() {
+            return Err(anyhow!("a sweep needs at least one value"));
+        }
+        if let Some(value) = self.values.iter().find(|v| !v.is_finite() || **v < 0.0) {
+            return Err(anyhow!("{} value {} must be finite and not negative", self.param.name(), value));
+        }
+        if self.param == SweepParam::CommandRate && self.values.contains(&0.0) {
+            return Err(anyhow!("command rate must be positive"));
+        }
+        Ok(())
+    }
+
+    /// The test of one run
+    fn test(&self, value: f64) -> Result<AbTest> {
+        let mut enable = self.enable;
+        let mut rate_hz = self.rate_hz;
+        match self.param {
+            SweepParam::Kp => enable.kp = value as f32,
+            SweepParam::Kd => enable.kd = value as f32,
+            SweepParam::TorqueLimit => enable.torque_limit_nm = Some(value as f32),
+            SweepParam::CommandRate => rate_hz = value,
+        }
+        enable.validate()?;
+        Ok(AbTest::new(self.excitation.clone())
+            .with_rate(rate_hz)
+            .with_limits(self.max_vel_rps, self.max_torque_nm)
+            .with_enable(enable))
+    }
+
+    /// Run every value on `motor_id`, calling `on_point` after each run
+    ///
+    /// Stops early when `running` is cleared; the interrupted run is kept
+    /// with `completed` false. The swept register is written back to its
+    /// value from before the sweep, also when a run fails.
+    pub fn run(
+        &self,
+        controller: &LivelyMotorController,
+        motor_id: u8,
+        running: &AtomicBool,
+        mut on_point: impl FnMut(&SweepPoint),
+    ) -> Result<SweepReport> {
+        self.validate()?;
+        let tests = self.values.iter().map(|&v| self.test(v)).collect::<Result<Vec<_>>>()?;
+        let original = match self.param.register() {
+            Some(register) => Some((register, controller.read_register_float(motor_id, register)?)),
+            None => None,
+        };
+
+        let mut points = Vec::new();
+        let result: Result<()> = (|| {
+            for (i, (&value, test)) in self.values.iter().zip(&tests).enumerate() {
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+                if i > 0 {
+                    rest(self.rest, running);
+                }
+                let point = SweepPoint { value, report: test.run(controller, motor_id, running)? };
+                on_point(&point);
+                points.push(point);
+            }
+            Ok(())
+        })();
+
+        if let Some((register, value)) = original {
+            controller.write_register_float(motor_id, register, value)?;
+        }
+        result?;
+        Ok(SweepReport { param: self.param, motor_id, points })
+    }
+}
+
+/// Sleep for `duration` or until `running` is cleared
+fn rest(duration: Duration, running: &AtomicBool) {
+    let end = Instant::now() + duration;
+    while running.load(Ordering::SeqCst) && Instant::now() < end {
+        thread::sleep((end - Instant::now()).min(Duration::from_millis(50)));
+    }
+}
@@ -0,0 +1,273 @@
+//! Catching gravity-loaded joints while they are disabled
+//!
+//! A disabled motor carries no torque, so a leg or arm left disabled under
+//! load falls as soon as friction lets go. A [`FallCatcher`] has the watched
+//! motors push their state at a high rate ([`PushFeedback`]) and, when one
+//! that is not enabled through the controller back-drives faster than the
+//! threshold, enables it with a damping-only profile
+//! ([`EnableConfig::compliant`]): nothing pulls the joint anywhere, it just
+//! stops falling fast. A caught joint stays enabled until
+//! [`FallCatcher::release`] disables it again.
+
+use crate::events::{EventBus, EventKind};
+use crate::{EnableConfig, LivelyMotorController, PushFeedback};
+use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long the worker waits for pushed states before checking for a stop
+const POLL: Duration = Duration::from_millis(20);
+
+/// When a disabled joint counts as falling, and how it is caught
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FallCatchConfig {
+    /// Back-driving speed that counts as falling, r/s
+    pub velocity_threshold_rps: f64,
+    /// Consecutive pushed states above the threshold before catching
+    pub confirm_samples: u32,
+    /// Rate the watched motors push their state at, Hz
+    pub feedback_rate_hz: f64,
+    /// What a falling joint is enabled with
+    pub catch: EnableConfig,
+}
+
+impl FallCatchConfig {
+    /// Catch after 2 states above `velocity_threshold_rps`, pushed at 500 Hz,
+    /// with [`EnableConfig::compliant`]
+    pub fn new(velocity_threshold_rps: f64) -> Self {
+        Self {
+            velocity_threshold_rps,
+            confirm_samples: 2,
+            feedback_rate_hz: 500.0,
+            catch: EnableConfig::compliant(),
+        }
+    }
+
+    pub fn with_confirm_samples(mut self, confirm_samples: u32) -> Self {
+        self.confirm_samples = confirm_samples;
+        self
+    }
+
+    pub fn with_feedback_rate(mut self, feedback_rate_hz: f64) -> Self {
+        self.feedback_rate_hz = feedback_rate_hz;
+        self
+    }
+
+    pub fn with_catch(mut self, catch: EnableConfig) -> Self {
+        self.catch = catch;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !self.velocity_threshold_rps.is_finite() || self.velocity_threshold_rps <= 0.0 {
+            return Err(anyhow!("fall velocity threshold must be positive, got {} r/s", self.velocity_threshold_rps));
+        }
+        if self.confirm_samples == 0 {
+            return Err(anyhow!("fall catcher needs at least one confirming sample"));
+        }
+        if !self.feedback_rate_hz.is_finite() || self.feedback_rate_hz <= 0.0 {
+            return Err(anyhow!("feedback rate must be positive, got {} Hz", self.feedback_rate_hz));
+        }
+        self.catch.validate()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Motors caught and not released yet
+    caught: BTreeSet<u8>,
+    catches: u64,
+    /// Catches that failed to enable the motor
+    errors: u64,
+    /// Pushed states that could not be received
+    poll_errors: u64,
+    last_error: Option<String>,
+}
+
+struct Shared {
+    controller: Arc<LivelyMotorController>,
+    config: FallCatchConfig,
+    events: Option<Arc<EventBus>>,
+    state: Mutex<State>,
+    running: AtomicBool,
+}
+
+/// Enables disabled joints that start falling
+pub struct FallCatcher {
+    shared: Arc<Shared>,
+    motor_ids: Vec<u8>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl FallCatcher {
+    /// Watch `motor_ids`; fails if a motor cannot push its state
+    pub fn start(
+        controller: Arc<LivelyMotorController>,
+        motor_ids: &[u8],
+        config: FallCatchConfig,
+    ) -> Result<Self> {
+        Self::start_with_events(controller, motor_ids, config, None)
+    }
+
+    /// Like [`start`](Self::start), also publishing [`EventKind::FallCaught`]
+    /// and [`EventKind::FallCatchFailed`] on `events`
+    pub fn start_with_events(
+        controller: Arc<LivelyMotorController>,
+        motor_ids: &[u8],
+        config: FallCatchConfig,
+        events: Option<Arc<EventBus>>,
+    ) -> Result<Self> {
+        config.validate()?;
+        if motor_ids.is_empty() {
+            return Err(anyhow!("fall catcher needs at least one motor"));
+        }
+        let shared = Arc::new(Shared {
+            controller,
+            config,
+            events,
+            state: Mutex::new(State::default()),
+            running: AtomicBool::new(true),
+        });
+
+        // The feedback borrows the controller, so it is set up on the worker
+        let (started_tx, started_rx) = mpsc::channel();
+        let worker = {
+            let shared = Arc::clone(&shared);
+            let motor_ids = motor_ids.to_vec();
+            thread::spawn(move || {
+                let mut feedback = PushFeedback::new(&shared.controller);
+                let started = motor_ids
+                    .iter()
+                    .try_for_each(|&id| feedback.enable(id, shared.config.feedback_rate_hz).map(|_| ()));
+                let ok = started.is_ok();
+                let _ = started_tx.send(started);
+                if ok {
+                    watch(&shared, &mut feedback);
+                }
+            })
+        };
+        let started = started_rx.recv().unwrap_or_else(|_| Err(anyhow!("fall catcher thread panicked")));
+        let catcher = Self {
+            shared,
+            motor_ids: motor_ids.to_vec(),
+            worker: Some(worker),
+        };
+        started?;
+        Ok(catcher)
+    }
+
+    /// Watched motors, in the order given
+    pub fn motor_ids(&self) -> &[u8] {
+        &self.motor_ids
+    }
+
+    /// Motors caught and not released yet, in ID order
+    pub fn caught(&self) -> Vec<u8> {
+        self.shared.state.lock().unwrap().caught.iter().copied().collect()
+    }
+
+    /// Disable a caught motor and watch it again; the limb should be supported
+    pub fn release(&self, motor_id: u8) -> Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.caught.contains(&motor_id) {
+            return Err(anyhow!("motor {} was not caught", motor_id));
+        }
+        self.shared.controller.disable_motor(motor_id)?;
+        state.caught.remove(&motor_id);
+        Ok(())
+    }
+
+    /// Joints caught since the catcher started
+    pub fn catches(&self) -> u64 {
+        self.shared.state.lock().unwrap().catches
+    }
+
+    /// Catches that failed because the motor could not be enabled
+    pub fn errors(&self) -> u64 {
+        self.shared.state.lock().unwrap().errors
+    }
+
+    /// Times the pushed states could not be received
+    pub fn poll_errors(&self) -> u64 {
+        self.shared.state.lock().unwrap().poll_errors
+    }
+
+    /// Why the last catch or receive failed
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.state.lock().unwrap().last_error.clone()
+    }
+}
+
+impl Drop for FallCatcher {
+    /// Stop watching; caught joints stay enabled
+    fn drop(&mut self) {
+        self.shared.running.store(false, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn watch(shared: &Shared, feedback: &mut PushFeedback<'_>) {
+    let config = &shared.config;
+    // Consecutive states above the threshold per motor
+    let mut streaks: BTreeMap<u8, u32> = BTreeMap::new();
+    while shared.running.load(Ordering::SeqCst) {
+        let states = match feedback.poll(POLL) {
+            Ok(states) => states,
+            Err(e) => {
+                let mut guard = shared.state.lock().unwrap();
+                guard.poll_errors += 1;
+                guard.last_error = Some(format!("{:#}", e));
+                drop(guard);
+                thread::sleep(POLL);
+                continue;
+            }
+        };
+        for state in states {
+            let motor_id = state.motor_id;
+            // Joints the application drives, or already caught, are left alone
+            let disabled = shared.controller.enable_mode(motor_id).is_none();
+            if !disabled || state.velocity_rps.abs() < config.velocity_threshold_rps {
+                streaks.remove(&motor_id);
+                continue;
+            }
+            let streak = streaks.entry(motor_id).or_insert(0);
+            *streak += 1;
+            if *streak < config.confirm_samples {
+                continue;
+            }
+            streaks.remove(&motor_id);
+
+            let caught = shared.controller.enable_with(motor_id, &config.catch);
+            let mut guard = shared.state.lock().unwrap();
+            match caught {
+                Ok(()) => {
+                    guard.caught.insert(motor_id);
+                    guard.catches += 1;
+                    if let Some(events) = &shared.events {
+                        events.publish(EventKind::FallCaught {
+                            motor_id,
+                            velocity_rps: state.velocity_rps,
+                        });
+                    }
+                }
+                Err(e) => {
+                    let reason = format!("{:#}", e);
+                    guard.errors += 1;
+                    guard.last_error = Some(format!("cannot catch motor {}: {}", motor_id, reason));
+                    if let Some(events) = &shared.events {
+                        events.publish(EventKind::FallCatchFailed {
+                            motor_id,
+                            velocity_rps: state.velocity_rps,
+                            reason,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Configurable fault responses
+//!
+//! A [`FaultPolicy`] declares per fault class, and optionally per joint, what
+//! the [`SafetyMonitor`](crate::SafetyMonitor) does when a motor reports a
+//! fault. Classes without an entry fall back to a robot-wide stop.
+//!
+//! ```json
+//! {
+//!   "default": { "over_temperature": "brake", "under_voltage": "warn" },
+//!   "joints": { "3": { "encoder": "hold", "limit": "ignore" } },
+//!   "stop_mode": { "mode": "ramped", "ramp_ms": 50 }
+//! }
+//! ```
+
+use crate::StopMode;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Group of fault codes calling for the same response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultClass {
+    /// Gate driver fault or PWM cycle overrun
+    Driver,
+    /// Encoder fault or invalid position/angle
+    Encoder,
+    OverVoltage,
+    UnderVoltage,
+    OverTemperature,
+    /// Started outside or ran into the position limits
+    Limit,
+    /// Not calibrated, not configured or configuration changed
+    Configuration,
+    /// Host side: the motor stopped answering
+    CommLoss,
+    /// Any other non-zero fault code
+    Other,
+}
+
+impl FaultClass {
+    /// Class of a fault code read from the fault register, `None` for 0
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            0 => return None,
+            33 | 37 => FaultClass::Driver,
+            35 | 42 | 43 => FaultClass::Encoder,
+            34 => FaultClass::OverVoltage,
+            40 => FaultClass::UnderVoltage,
+            38 => FaultClass::OverTemperature,
+            39 => FaultClass::Limit,
+            32 | 36 | 41 => FaultClass::Configuration,
+            _ => FaultClass::Other,
+        })
+    }
+}
+
+impl fmt::Display for FaultClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FaultClass::Driver => "driver",
+            FaultClass::Encoder => "encoder",
+            FaultClass::OverVoltage => "over_voltage",
+            FaultClass::UnderVoltage => "under_voltage",
+            FaultClass::OverTemperature => "over_temperature",
+            FaultClass::Limit => "limit",
+            FaultClass::Configuration => "configuration",
+            FaultClass::CommLoss => "comm_loss",
+            FaultClass::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Response to a fault, from mildest to most drastic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultAction {
+    /// Do nothing
+    Ignore,
+    /// Publish the fault as an event, keep running
+    Warn,
+    /// Keep the joint enabled at its position, ignore new setpoints
+    Hold,
+    /// Drop stiffness and damp the joint, ignore new setpoints
+    Brake,
+    /// Disable every enabled motor on the bus, braking them first as the
+    /// policy's [`StopMode`] says
+    Stop,
+}
+
+impl fmt::Display for FaultAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FaultAction::Ignore => "ignore",
+            FaultAction::Warn => "warn",
+            FaultAction::Hold => "hold",
+            FaultAction::Brake => "brake",
+            FaultAction::Stop => "stop",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Fault responses per class, with per-joint overrides
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaultPolicy {
+    /// Responses for all joints
+    #[serde(default)]
+    pub default: BTreeMap<FaultClass, FaultAction>,
+    /// Overrides by motor ID
+    #[serde(default)]
+    pub joints: BTreeMap<u8, BTreeMap<FaultClass, FaultAction>>,
+    /// Damping used by `brake`
+    #[serde(default = "default_brake_kd")]
+    pub brake_kd: f32,
+    /// How `stop` brings the motors to rest
+    #[serde(default)]
+    pub stop_mode: StopMode,
+}
+
+fn default_brake_kd() -> f32 {
+    1.0
+}
+
+impl Default for FaultPolicy {
+    /// Stop on every fault
+    fn default() -> Self {
+        Self {
+            default: BTreeMap::new(),
+            joints: BTreeMap::new(),
+            brake_kd: default_brake_kd(),
+            stop_mode: StopMode::default(),
+        }
+    }
+}
+
+impl FaultPolicy {
+    /// Response used for classes without an entry
+    pub const FALLBACK: FaultAction = FaultAction::Stop;
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read fault policy {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Response of `motor_id` to a fault of `class`
+    pub fn action(&self, motor_id: u8, class: FaultClass) -> FaultAction {
+        self.joints
+            .get(&motor_id)
+            .and_then(|overrides| overrides.get(&class))
+            .or_else(|| self.default.get(&class))
+            .copied()
+            .unwrap_or(Self::FALLBACK)
+    }
+}
@@ -0,0 +1,216 @@
+//! Acceleration estimation from velocity feedback
+//!
+//! The velocity register is quantized to 1/4000 r/s and noisy, so differencing
+//! two consecutive readings amplifies the noise by the sample rate. A
+//! [`VelocityFilter`] instead keeps the readings of a short sliding window and
+//! fits a line through them: the slope is the acceleration, the value of the
+//! line at the latest reading the smoothed velocity. A longer window trades lag
+//! for less noise.
+//!
+//! Motors can also send their state by themselves at a configured rate
+//! ([`LivelyMotorController::set_feedback_period`]); [`PushFeedback`] sets the
+//! rate per joint and turns the pushed replies into states, so slow joints can
+//! be given a low rate to leave bus bandwidth to the fast ones. Replies are
+//! decoded with each motor's [`ReportMask`](crate::ReportMask).
+
+use crate::protocol::TruncatedFrame;
+use crate::{BusSubscription, LivelyMotorController, MotorState, Report};
+use anyhow::Result;
+use socketcan::EmbeddedFrame;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Default length of the smoothing window
+pub const DEFAULT_FEEDBACK_WINDOW: Duration = Duration::from_millis(20);
+
+/// Readings needed before an acceleration is reported
+const MIN_SAMPLES: usize = 3;
+
+/// Sliding-window least-squares fit of velocity over time
+#[derive(Debug, Clone)]
+pub struct VelocityFilter {
+    window: Duration,
+    samples: VecDeque<(Instant, f64)>,
+}
+
+impl VelocityFilter {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Feed a velocity reading, returns the estimated acceleration in r/s²
+    ///
+    /// `None` until the window holds enough readings spread over time.
+    pub fn update(&mut self, velocity_rps: f64, at: Instant) -> Option<f64> {
+        // Readings out of order (e.g. from another thread) restart the window
+        if self.samples.back().is_some_and(|&(last, _)| at < last) {
+            self.samples.clear();
+        }
+        self.samples.push_back((at, velocity_rps));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(t, _)| at.duration_since(t) > self.window)
+        {
+            self.samples.pop_front();
+        }
+        self.acceleration_rps2()
+    }
+
+    /// Slope of the fit, r/s²
+    pub fn acceleration_rps2(&self) -> Option<f64> {
+        self.fit().map(|(_, slope)| slope)
+    }
+
+    /// Fitted velocity at the latest reading, r/s
+    pub fn smoothed_velocity_rps(&self) -> Option<f64> {
+        self.fit().map(|(at_latest, _)| at_latest)
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// `(value at the latest reading, slope)` of the least-squares line
+    fn fit(&self) -> Option<(f64, f64)> {
+        if self.samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let &(latest, _) = self.samples.back()?;
+        // Time relative to the latest reading keeps the sums well conditioned
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .map(|&(t, v)| (-latest.duration_since(t).as_secs_f64(), v))
+            .collect();
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_v = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let (mut stt, mut stv) = (0.0, 0.0);
+        for (t, v) in &points {
+            stt += (t - mean_t) * (t - mean_t);
+            stv += (t - mean_t) * (v - mean_v);
+        }
+        if stt <= f64::EPSILON {
+            return None;
+        }
+        let slope = stv / stt;
+        Some((mean_v - slope * mean_t, slope))
+    }
+}
+
+impl Default for VelocityFilter {
+    fn default() -> Self {
+        Self::new(DEFAULT_FEEDBACK_WINDOW)
+    }
+}
+
+/// Bits of an 8-byte extended frame with worst-case stuffing (see [`crate::shaping::frame_bits`])
+const STATE_FRAME_BITS: f64 = 160.0;
+
+/// Receives states the motors push at their configured feedback rate
+pub struct PushFeedback<'a> {
+    controller: &'a LivelyMotorController,
+    subscription: BusSubscription,
+    /// Applied rate per motor
+    rates: BTreeMap<u8, f64>,
+}
+
+impl<'a> PushFeedback<'a> {
+    pub fn new(controller: &'a LivelyMotorController) -> Self {
+        Self {
+            controller,
+            subscription: controller.bus().subscribe(),
+            rates: BTreeMap::new(),
+        }
+    }
+
+    /// Make `motor_id` push its state at `rate_hz`; returns the applied rate
+    pub fn enable(&mut self, motor_id: u8, rate_hz: f64) -> Result<f64> {
+        let applied = self.controller.set_feedback_period(motor_id, rate_hz)?;
+        if applied > 0.0 {
+            self.rates.insert(motor_id, applied);
+        } else {
+            self.rates.remove(&motor_id);
+        }
+        Ok(applied)
+    }
+
+    /// Return `motor_id` to answering requests only
+    pub fn disable(&mut self, motor_id: u8) -> Result<()> {
+        self.controller.set_feedback_period(motor_id, 0.0)?;
+        self.rates.remove(&motor_id);
+        Ok(())
+    }
+
+    /// Applied rate of every pushing motor
+    pub fn rates(&self) -> &BTreeMap<u8, f64> {
+        &self.rates
+    }
+
+    /// Fraction of the bitrate taken by the pushed states
+    pub fn load_fraction(&self) -> f64 {
+        let frames_per_s: f64 = self.rates.values().sum();
+        frames_per_s * STATE_FRAME_BITS / self.controller.bitrate() as f64
+    }
+
+    /// Collect pushed states for up to `timeout`
+    ///
+    /// Each state also updates the controller's state cache and acceleration
+    /// estimate, as if it had been read with `read_motor_state`. Fields a
+    /// motor does not report keep their cached values.
+    pub fn poll(&mut self, timeout: Duration) -> Result<Vec<MotorState>> {
+        Ok(self.receive(timeout)?.into_iter().map(|(_, state)| state).collect())
+    }
+
+    /// Like [`poll`](Self::poll), returning the reported fields only, including temperature
+    pub fn poll_reports(&mut self, timeout: Duration) -> Result<Vec<Report>> {
+        Ok(self.receive(timeout)?.into_iter().map(|(report, _)| report).collect())
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Vec<(Report, MotorState)>> {
+        let deadline = Instant::now() + timeout;
+        let mut received = Vec::new();
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let Some(frame) = self.subscription.recv_timeout(remaining)? else {
+                break;
+            };
+            let Some(motor_id) = crate::reply_source(&frame).filter(|id| self.rates.contains_key(id)) else {
+                continue;
+            };
+            let reply = match self.controller.encoding().parse_reply(frame.data()) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    if e.is::<TruncatedFrame>() {
+                        self.controller.bus().note_malformed();
+                    }
+                    continue;
+                }
+            };
+            received.extend(self.controller.ingest_report(motor_id, &reply)?);
+        }
+        Ok(received)
+    }
+
+    /// Stop every motor enabled here from pushing
+    pub fn stop(&mut self) -> Result<()> {
+        let motor_ids: Vec<u8> = self.rates.keys().copied().collect();
+        for motor_id in motor_ids {
+            self.disable(motor_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PushFeedback<'_> {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
@@ -0,0 +1,120 @@
+//! End-effector force estimation from joint torques
+//!
+//! Reads the joints of a configured planar chain, maps the measured torques
+//! through the transposed Jacobian and reports the resulting end-effector force
+//! as [`ChainTelemetry`], flagging contact when it exceeds a threshold. Useful
+//! for contact detection on arms without a force sensor; friction and gravity
+//! torques are not compensated, so calibrate `bias` at rest.
+
+use crate::kinematics::{Planar2Link, Planar3Link};
+use crate::{ChainTelemetry, EndEffectorForce, LivelyMotorController};
+use anyhow::{Result, anyhow};
+use std::time::Instant;
+
+/// Kinematic chain used for the estimate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Chain {
+    TwoLink(Planar2Link),
+    ThreeLink(Planar3Link),
+}
+
+impl Chain {
+    pub fn joint_count(&self) -> usize {
+        match self {
+            Chain::TwoLink(_) => 2,
+            Chain::ThreeLink(_) => 3,
+        }
+    }
+
+    /// Force for joint angles and torques in chain order
+    pub fn estimate(&self, joints_deg: &[f64], torques_nm: &[f64]) -> Result<EndEffectorForce> {
+        if joints_deg.len() != self.joint_count() || torques_nm.len() != self.joint_count() {
+            return Err(anyhow!("expected {} joints", self.joint_count()));
+        }
+
+        Ok(match self {
+            Chain::TwoLink(arm) => {
+                let [fx_n, fy_n] =
+                    arm.estimate_force([joints_deg[0], joints_deg[1]], [torques_nm[0], torques_nm[1]])?;
+                EndEffectorForce { fx_n, fy_n, mz_nm: None }
+            }
+            Chain::ThreeLink(arm) => {
+                let [fx_n, fy_n, mz_nm] = arm.estimate_force(
+                    [joints_deg[0], joints_deg[1], joints_deg[2]],
+                    [torques_nm[0], torques_nm[1], torques_nm[2]],
+                )?;
+                EndEffectorForce { fx_n, fy_n, mz_nm: Some(mz_nm) }
+            }
+        })
+    }
+}
+
+/// Estimates end-effector force of a chain of motors
+pub struct ForceEstimator<'a> {
+    controller: &'a LivelyMotorController,
+    chain: Chain,
+    motor_ids: Vec<u8>,
+    /// Torque offsets subtracted before the estimate
+    bias_nm: Vec<f64>,
+    contact_threshold_n: f64,
+}
+
+impl<'a> ForceEstimator<'a> {
+    /// `motor_ids` are the chain's joints from base to tip
+    pub fn new(controller: &'a LivelyMotorController, chain: Chain, motor_ids: &[u8]) -> Result<Self> {
+        if motor_ids.len() != chain.joint_count() {
+            return Err(anyhow!(
+                "chain has {} joints but {} motors were given",
+                chain.joint_count(),
+                motor_ids.len()
+            ));
+        }
+        Ok(Self {
+            controller,
+            chain,
+            motor_ids: motor_ids.to_vec(),
+            bias_nm: vec![0.0; motor_ids.len()],
+            contact_threshold_n: 5.0,
+        })
+    }
+
+    /// Force magnitude above which `contact` is reported
+    pub fn with_contact_threshold(mut self, threshold_n: f64) -> Self {
+        self.contact_threshold_n = threshold_n;
+        self
+    }
+
+    /// Record the current torques as the no-load bias
+    pub fn calibrate_bias(&mut self) -> Result<()> {
+        self.bias_nm = self
+            .motor_ids
+            .iter()
+            .map(|&id| self.controller.read_motor_state(id).map(|s| s.torque_nm))
+            .collect::<crate::error::Result<_>>()?;
+        Ok(())
+    }
+
+    /// Read all joints and estimate the current end-effector force
+    pub fn sample(&self) -> Result<ChainTelemetry> {
+        let joints = self
+            .motor_ids
+            .iter()
+            .map(|&id| self.controller.read_motor_state(id))
+            .collect::<crate::error::Result<Vec<_>>>()?;
+
+        let angles: Vec<f64> = joints.iter().map(|s| s.position_deg).collect();
+        let torques: Vec<f64> = joints
+            .iter()
+            .zip(&self.bias_nm)
+            .map(|(s, bias)| s.torque_nm - bias)
+            .collect();
+
+        let force = self.chain.estimate(&angles, &torques).ok();
+        Ok(ChainTelemetry {
+            timestamp: Instant::now(),
+            contact: force.is_some_and(|f| f.magnitude() > self.contact_threshold_n),
+            joints,
+            force,
+        })
+    }
+}
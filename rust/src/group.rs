@@ -0,0 +1,439 @@
+//! Commanding a set of motors on one bus
+//!
+//! Stream commands sent to the plain 0x90 / 0xAD IDs reach every motor on the
+//! bus, so with several motors they all follow the last setpoint sent. A
+//! [`MotorGroup`] tracks the motor IDs of a robot and sends each setpoint to
+//! the stream ID addressed to its motor ([`protocol::stream_id`]).
+//!
+//! The group remembers the last setpoint of each member and the profile last
+//! applied, so its state can be saved as a [`GroupSnapshot`] (optionally on
+//! every change, [`MotorGroup::with_autosave`]) and restored after a restart.
+//!
+//! Guarded by a [`Watchdog`] ([`MotorGroup::with_watchdog`]), every setpoint
+//! sent through the group feeds it, and the group refuses to enable members or
+//! send setpoints once it has tripped.
+//!
+//! The protocol has no frame carrying more than one target, so commanding n
+//! members takes n frames, about 130 µs each at 1 Mbit/s.
+//! [`MotorGroup::send_group_positions`] queues them back to back, and for a
+//! group that is every motor on the bus ([`MotorGroup::with_broadcast`])
+//! sends a common target as one frame to the unaddressed ID.
+
+use crate::protocol::{self, ANGLE_STREAM_ID, VELOCITY_STREAM_ID};
+use crate::snapshot::{EnabledMode, GroupSetpoint, GroupSnapshot, JointSnapshot};
+use crate::{ControlMode, EnableConfig, LivelyMotorController, MotorState, ProfileSet, Watchdog};
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Where and how often a group saves its snapshot
+struct Autosave {
+    path: PathBuf,
+    /// Shortest time between saves caused by setpoints
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+/// Motors of one robot, commanded by ID
+pub struct MotorGroup<'a> {
+    controller: &'a LivelyMotorController,
+    motor_ids: Vec<u8>,
+    setpoints: Mutex<BTreeMap<u8, GroupSetpoint>>,
+    profile: Mutex<Option<String>>,
+    autosave: Option<Autosave>,
+    watchdog: Option<Arc<Watchdog>>,
+    /// What [`enable_all`](MotorGroup::enable_all) writes per member
+    enable_configs: BTreeMap<u8, EnableConfig>,
+    /// The members are every motor on the bus
+    broadcast: bool,
+}
+
+impl<'a> MotorGroup<'a> {
+    /// Group `motor_ids` (1..=127, each once); commands taking one value per
+    /// motor use this order
+    pub fn new(controller: &'a LivelyMotorController, motor_ids: &[u8]) -> Result<Self> {
+        for (i, &motor_id) in motor_ids.iter().enumerate() {
+            if !(1..=127).contains(&motor_id) {
+                return Err(anyhow!("motor ID {} is outside 1..=127", motor_id));
+            }
+            if motor_ids[..i].contains(&motor_id) {
+                return Err(anyhow!("motor {} is listed twice", motor_id));
+            }
+        }
+        Ok(Self {
+            controller,
+            motor_ids: motor_ids.to_vec(),
+            setpoints: Mutex::new(BTreeMap::new()),
+            profile: Mutex::new(None),
+            autosave: None,
+            watchdog: None,
+            enable_configs: BTreeMap::new(),
+            broadcast: false,
+        })
+    }
+
+    /// Save a snapshot to `path` whenever members are enabled, disabled or
+    /// given a profile, and after setpoints at most every `interval`
+    pub fn with_autosave<P: AsRef<Path>>(mut self, path: P, interval: Duration) -> Self {
+        self.autosave = Some(Autosave {
+            path: path.as_ref().to_path_buf(),
+            interval,
+            last: Mutex::new(None),
+        });
+        self
+    }
+
+    /// Register every member with `watchdog` and feed it with each setpoint
+    ///
+    /// A loop that holds still without sending setpoints must feed the
+    /// watchdog itself.
+    pub fn with_watchdog(mut self, watchdog: Arc<Watchdog>) -> Self {
+        self.motor_ids.iter().for_each(|&id| watchdog.register(id));
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Enable `motor_id` with `config` in [`enable_all`](Self::enable_all)
+    /// instead of [`EnableConfig::position`]
+    pub fn with_enable_config(mut self, motor_id: u8, config: EnableConfig) -> Self {
+        self.enable_configs.insert(motor_id, config);
+        self
+    }
+
+    /// Declare the members to be every motor on the bus, so
+    /// [`send_group_positions`](Self::send_group_positions) may send a target
+    /// common to all of them as one frame to the unaddressed ID
+    pub fn with_broadcast(mut self) -> Self {
+        self.broadcast = true;
+        self
+    }
+
+    pub fn watchdog(&self) -> Option<&Arc<Watchdog>> {
+        self.watchdog.as_ref()
+    }
+
+    pub fn motor_ids(&self) -> &[u8] {
+        &self.motor_ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.motor_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.motor_ids.is_empty()
+    }
+
+    pub fn contains(&self, motor_id: u8) -> bool {
+        self.motor_ids.contains(&motor_id)
+    }
+
+    /// `(angle stream, velocity stream)` arbitration IDs of a member
+    pub fn stream_ids(&self, motor_id: u8) -> Result<(u32, u32)> {
+        self.check(motor_id)?;
+        Ok((
+            protocol::stream_id(ANGLE_STREAM_ID, motor_id),
+            protocol::stream_id(VELOCITY_STREAM_ID, motor_id),
+        ))
+    }
+
+    /// Members that do not answer a ping
+    pub fn offline(&self) -> Vec<u8> {
+        self.motor_ids
+            .iter()
+            .copied()
+            .filter(|&id| !self.controller.ping_motor(id).is_ok_and(|info| info.is_online))
+            .collect()
+    }
+
+    /// Enable every member in position mode, with its own config if it has one
+    pub fn enable_all(&self) -> Result<()> {
+        self.check_watchdog()?;
+        self.motor_ids.iter().try_for_each(|&id| {
+            let config = self.enable_configs.get(&id).copied().unwrap_or_else(EnableConfig::position);
+            self.controller.enable_with(id, &config)
+        })?;
+        self.autosave(true)
+    }
+
+    /// Enable every member in velocity mode
+    pub fn enable_velocity_all(&self) -> Result<()> {
+        self.enable_all_with(&EnableConfig::velocity())
+    }
+
+    /// Enable every member with the same gains and limits
+    pub fn enable_all_with(&self, config: &EnableConfig) -> Result<()> {
+        self.check_watchdog()?;
+        self.motor_ids.iter().try_for_each(|&id| self.controller.enable_with(id, config))?;
+        self.autosave(true)
+    }
+
+    /// Disable every member; a motor that fails does not keep the others enabled
+    pub fn disable_all(&self) -> Result<()> {
+        let failed: Vec<String> = self
+            .motor_ids
+            .iter()
+            .filter_map(|&id| self.controller.disable_motor(id).err().map(|e| format!("{:#}", e)))
+            .collect();
+        if !failed.is_empty() {
+            return Err(anyhow!("could not disable {}", failed.join("; ")));
+        }
+        self.autosave(true)
+    }
+
+    /// Write profile `name` to the members it covers and remember it
+    pub fn apply_profile(&self, profiles: &ProfileSet, name: &str) -> Result<Vec<u8>> {
+        let applied = profiles.apply(self.controller, name, &self.motor_ids)?;
+        *self.profile.lock().unwrap() = Some(name.to_string());
+        self.autosave(true)?;
+        Ok(applied)
+    }
+
+    /// Profile last applied through the group
+    pub fn profile(&self) -> Option<String> {
+        self.profile.lock().unwrap().clone()
+    }
+
+    /// Last setpoint sent to a member through the group
+    pub fn setpoint(&self, motor_id: u8) -> Option<GroupSetpoint> {
+        self.setpoints.lock().unwrap().get(&motor_id).copied()
+    }
+
+    /// Send an angle setpoint to one member
+    pub fn set_angle(&self, motor_id: u8, angle_deg: f64, max_vel_rps: f64, max_tqe_nm: f64) -> Result<()> {
+        self.check(motor_id)?;
+        self.check_watchdog()?;
+        self.controller.send_angle_command_to(
+            motor_id,
+            crate::degrees_to_position(angle_deg),
+            crate::rps_to_velocity(max_vel_rps),
+            crate::nm_to_torque(max_tqe_nm),
+        )?;
+        self.feed_watchdog();
+        let setpoint = GroupSetpoint::Angle { angle_deg, max_vel_rps, max_tqe_nm };
+        self.setpoints.lock().unwrap().insert(motor_id, setpoint);
+        self.autosave(false)
+    }
+
+    /// Send a velocity setpoint to one member
+    pub fn set_velocity(&self, motor_id: u8, velocity_rps: f64, acceleration_rps2: f64) -> Result<()> {
+        self.check(motor_id)?;
+        self.check_watchdog()?;
+        self.controller.send_velocity_command_to(
+            motor_id,
+            crate::MAGIC_POS,
+            crate::rps_to_velocity(velocity_rps),
+            crate::rps2_to_acceleration(acceleration_rps2),
+        )?;
+        self.feed_watchdog();
+        let setpoint = GroupSetpoint::Velocity { velocity_rps, acceleration_rps2 };
+        self.setpoints.lock().unwrap().insert(motor_id, setpoint);
+        self.autosave(false)
+    }
+
+    /// Send one angle per member, in group order
+    pub fn set_angles(&self, angles_deg: &[f64], max_vel_rps: f64, max_tqe_nm: f64) -> Result<()> {
+        self.check_len(angles_deg.len())?;
+        for (&motor_id, &angle_deg) in self.motor_ids.iter().zip(angles_deg) {
+            self.set_angle(motor_id, angle_deg, max_vel_rps, max_tqe_nm)?;
+        }
+        Ok(())
+    }
+
+    /// Send angle targets to several members at once
+    ///
+    /// All targets are checked before the first frame goes out, and the
+    /// frames are queued back to back, so the last motor starts about 130 µs
+    /// per target after the first at 1 Mbit/s instead of after the caller's
+    /// per-motor overhead. When the group is [`with_broadcast`](Self::with_broadcast),
+    /// every member gets the same angle and none has a [`Calibration`](crate::Calibration),
+    /// one frame to the unaddressed ID replaces them all. Returns the
+    /// number of frames sent.
+    pub fn send_group_positions(&self, targets: &[(u8, f64)], max_vel_rps: f64, max_tqe_nm: f64) -> Result<usize> {
+        for (i, &(motor_id, _)) in targets.iter().enumerate() {
+            self.check(motor_id)?;
+            if targets[..i].iter().any(|&(id, _)| id == motor_id) {
+                return Err(anyhow!("motor {} has two targets", motor_id));
+            }
+        }
+        self.check_watchdog()?;
+        let (max_vel, max_tqe) = (crate::rps_to_velocity(max_vel_rps), crate::nm_to_torque(max_tqe_nm));
+        let frames = if self.broadcasts(targets) {
+            self.controller.send_angle_command(crate::degrees_to_position(targets[0].1), max_vel, max_tqe)?;
+            1
+        } else {
+            for &(motor_id, angle_deg) in targets {
+                self.controller
+                    .send_angle_command_to(motor_id, crate::degrees_to_position(angle_deg), max_vel, max_tqe)?;
+            }
+            targets.len()
+        };
+        self.feed_watchdog();
+        let mut setpoints = self.setpoints.lock().unwrap();
+        for &(motor_id, angle_deg) in targets {
+            setpoints.insert(motor_id, GroupSetpoint::Angle { angle_deg, max_vel_rps, max_tqe_nm });
+        }
+        drop(setpoints);
+        self.autosave(false)?;
+        Ok(frames)
+    }
+
+    /// Whether `targets` can go out as one broadcast frame
+    fn broadcasts(&self, targets: &[(u8, f64)]) -> bool {
+        self.broadcast
+            && targets.len() == self.motor_ids.len()
+            && targets.len() > 1
+            && targets.iter().all(|&(_, angle_deg)| angle_deg == targets[0].1)
+            && self.motor_ids.iter().all(|&id| self.controller.calibration(id).is_none_or(|c| c.is_identity()))
+    }
+
+    /// Send one velocity per member, in group order
+    pub fn set_velocities(&self, velocities_rps: &[f64], acceleration_rps2: f64) -> Result<()> {
+        self.check_len(velocities_rps.len())?;
+        for (&motor_id, &velocity_rps) in self.motor_ids.iter().zip(velocities_rps) {
+            self.set_velocity(motor_id, velocity_rps, acceleration_rps2)?;
+        }
+        Ok(())
+    }
+
+    /// State of every member, in group order
+    pub fn read_states(&self) -> Result<Vec<MotorState>> {
+        Ok(self.motor_ids.iter().map(|&id| self.controller.read_motor_state(id)).collect::<crate::error::Result<_>>()?)
+    }
+
+    /// Current state of the group, without bus traffic (angles come from the
+    /// state cache)
+    ///
+    /// After a watchdog trip every member is recorded as disabled.
+    pub fn snapshot(&self) -> GroupSnapshot {
+        let setpoints = self.setpoints.lock().unwrap();
+        let tripped = self.watchdog.as_ref().is_some_and(|w| w.is_tripped());
+        let joints = self
+            .motor_ids
+            .iter()
+            .map(|&motor_id| JointSnapshot {
+                motor_id,
+                enabled: self.controller.enable_mode(motor_id).filter(|_| !tripped).map(|mode| match mode {
+                    ControlMode::Position => EnabledMode::Position,
+                    ControlMode::Velocity => EnabledMode::Velocity,
+                }),
+                setpoint: setpoints.get(&motor_id).copied(),
+                position_deg: self.controller.state_cache().get(motor_id).map(|c| c.state.position_deg),
+            })
+            .collect();
+        GroupSnapshot {
+            saved_us: crate::remote::unix_micros(),
+            profile: self.profile(),
+            joints,
+        }
+    }
+
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.snapshot().save(path)
+    }
+
+    /// Resume the state of `snapshot` after a restart
+    ///
+    /// Every joint is read first; nothing is enabled unless all answer and each
+    /// joint that was holding an angle is within `tolerance_deg` of it. Then
+    /// the members are enabled in their snapshot modes, the snapshot's profile
+    /// is applied (it must be in `profiles`) and each angle setpoint is sent
+    /// again. Velocity joints are resumed at zero velocity, not at their
+    /// last speed.
+    pub fn restore(&self, snapshot: &GroupSnapshot, profiles: Option<&ProfileSet>, tolerance_deg: f64) -> Result<()> {
+        self.check_watchdog()?;
+        let mut problems = Vec::new();
+        for joint in &snapshot.joints {
+            self.check(joint.motor_id)?;
+            let measured = match self.controller.read_motor_state(joint.motor_id) {
+                Ok(state) => state.position_deg,
+                Err(e) => {
+                    problems.push(format!("motor {}: {:#}", joint.motor_id, e));
+                    continue;
+                }
+            };
+            if let (Some(EnabledMode::Position), Some(GroupSetpoint::Angle { angle_deg, .. })) =
+                (joint.enabled, joint.setpoint)
+            {
+                if (measured - angle_deg).abs() > tolerance_deg {
+                    problems.push(format!("motor {} is at {:.1}°, not at {:.1}°", joint.motor_id, measured, angle_deg));
+                }
+            }
+        }
+        let profile = match (&snapshot.profile, profiles) {
+            (Some(name), Some(profiles)) => Some((profiles, name)),
+            (Some(name), None) => return Err(anyhow!("snapshot uses profile '{}' but no profiles were given", name)),
+            (None, _) => None,
+        };
+        if let Some((profiles, name)) = profile {
+            profiles.profile(name)?;
+        }
+        if !problems.is_empty() {
+            return Err(anyhow!("cannot restore snapshot: {}", problems.join("; ")));
+        }
+
+        // Enabling writes default gains, so the profile goes after it
+        for joint in &snapshot.joints {
+            match joint.enabled {
+                Some(EnabledMode::Position) => self.controller.enable_motor(joint.motor_id)?,
+                Some(EnabledMode::Velocity) => self.controller.enable_velocity_mode(joint.motor_id)?,
+                None => {}
+            }
+        }
+        if let Some((profiles, name)) = profile {
+            self.apply_profile(profiles, name)?;
+        }
+        for joint in &snapshot.joints {
+            match (joint.enabled, joint.setpoint) {
+                (Some(EnabledMode::Position), Some(GroupSetpoint::Angle { angle_deg, max_vel_rps, max_tqe_nm })) => {
+                    self.set_angle(joint.motor_id, angle_deg, max_vel_rps, max_tqe_nm)?
+                }
+                (Some(EnabledMode::Velocity), Some(GroupSetpoint::Velocity { acceleration_rps2, .. })) => {
+                    self.set_velocity(joint.motor_id, 0.0, acceleration_rps2)?
+                }
+                _ => {}
+            }
+        }
+        self.autosave(true)
+    }
+
+    /// Save the snapshot if autosave is on; setpoint changes (`force` unset)
+    /// are throttled to the autosave interval
+    fn autosave(&self, force: bool) -> Result<()> {
+        let Some(autosave) = &self.autosave else {
+            return Ok(());
+        };
+        let mut last = autosave.last.lock().unwrap();
+        if !force && last.is_some_and(|t| t.elapsed() < autosave.interval) {
+            return Ok(());
+        }
+        *last = Some(Instant::now());
+        self.save_snapshot(&autosave.path)
+    }
+
+    fn check_watchdog(&self) -> Result<()> {
+        self.watchdog.as_ref().map_or(Ok(()), |w| w.check())
+    }
+
+    fn feed_watchdog(&self) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.feed();
+        }
+    }
+
+    fn check(&self, motor_id: u8) -> Result<()> {
+        if !self.contains(motor_id) {
+            return Err(anyhow!("motor {} is not in the group", motor_id));
+        }
+        Ok(())
+    }
+
+    fn check_len(&self, len: usize) -> Result<()> {
+        if len != self.motor_ids.len() {
+            return Err(anyhow!("got {} setpoints for {} motors", len, self.motor_ids.len()));
+        }
+        Ok(())
+    }
+}
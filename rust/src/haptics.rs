@@ -0,0 +1,164 @@
+//! Virtual walls and haptic boundaries
+//!
+//! A [`VirtualWall`] shapes angle setpoints near a configured angle: targets
+//! beyond the wall are clamped, and once the joint enters the ramp zone in front
+//! of the wall the torque limit rises linearly towards `max_torque_nm`, so a
+//! compliant joint (teach mode) feels a spring pushing it back. A
+//! [`HapticBoundary`] combines a lower and an upper wall into a safe envelope.
+
+use crate::LivelyMotorController;
+use anyhow::Result;
+
+/// Which side of the wall is forbidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallSide {
+    /// Angles above the wall are forbidden
+    Upper,
+    /// Angles below the wall are forbidden
+    Lower,
+}
+
+/// Angle setpoint and torque limit after shaping
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallCommand {
+    pub angle_deg: f64,
+    pub max_torque_nm: f64,
+    /// How far into the ramp zone the joint is, 0.0 (free) to 1.0 (at the wall)
+    pub engagement: f64,
+}
+
+/// A one-sided virtual wall
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualWall {
+    pub angle_deg: f64,
+    pub side: WallSide,
+    /// Width of the zone in front of the wall where stiffness ramps up
+    pub ramp_deg: f64,
+    /// Torque limit reached at the wall
+    pub max_torque_nm: f64,
+}
+
+impl VirtualWall {
+    pub fn upper(angle_deg: f64, ramp_deg: f64, max_torque_nm: f64) -> Self {
+        Self {
+            angle_deg,
+            side: WallSide::Upper,
+            ramp_deg: ramp_deg.max(1e-3),
+            max_torque_nm,
+        }
+    }
+
+    pub fn lower(angle_deg: f64, ramp_deg: f64, max_torque_nm: f64) -> Self {
+        Self {
+            angle_deg,
+            side: WallSide::Lower,
+            ramp_deg: ramp_deg.max(1e-3),
+            max_torque_nm,
+        }
+    }
+
+    /// Distance into the forbidden direction, positive beyond the wall
+    fn penetration(&self, angle_deg: f64) -> f64 {
+        match self.side {
+            WallSide::Upper => angle_deg - self.angle_deg,
+            WallSide::Lower => self.angle_deg - angle_deg,
+        }
+    }
+
+    /// Move `angle_deg` back by `distance` away from the wall
+    fn back_off(&self, distance: f64) -> f64 {
+        match self.side {
+            WallSide::Upper => self.angle_deg - distance,
+            WallSide::Lower => self.angle_deg + distance,
+        }
+    }
+
+    /// Ramp engagement at `measured_deg`, 0.0 outside the zone, 1.0 at or beyond the wall
+    pub fn engagement(&self, measured_deg: f64) -> f64 {
+        ((self.penetration(measured_deg) + self.ramp_deg) / self.ramp_deg).clamp(0.0, 1.0)
+    }
+
+    /// Shape a setpoint given the measured joint angle
+    pub fn shape(&self, target_deg: f64, measured_deg: f64, torque_nm: f64) -> WallCommand {
+        let engagement = self.engagement(measured_deg);
+
+        let angle_deg = if engagement > 0.0 {
+            // Inside the zone: pull back to the zone edge unless the target is already clear
+            if self.penetration(target_deg) > -self.ramp_deg {
+                self.back_off(self.ramp_deg)
+            } else {
+                target_deg
+            }
+        } else if self.penetration(target_deg) > 0.0 {
+            self.angle_deg
+        } else {
+            target_deg
+        };
+
+        let max_torque_nm = torque_nm + engagement * (self.max_torque_nm - torque_nm).max(0.0);
+
+        WallCommand {
+            angle_deg,
+            max_torque_nm,
+            engagement,
+        }
+    }
+}
+
+/// Lower and upper walls forming a safe operating envelope
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HapticBoundary {
+    pub lower: Option<VirtualWall>,
+    pub upper: Option<VirtualWall>,
+}
+
+impl HapticBoundary {
+    /// Envelope `[min_deg, max_deg]` with the same ramp and stiffness on both sides
+    pub fn new(min_deg: f64, max_deg: f64, ramp_deg: f64, max_torque_nm: f64) -> Self {
+        Self {
+            lower: Some(VirtualWall::lower(min_deg, ramp_deg, max_torque_nm)),
+            upper: Some(VirtualWall::upper(max_deg, ramp_deg, max_torque_nm)),
+        }
+    }
+
+    /// Shape a setpoint through both walls; the more engaged wall wins
+    pub fn shape(&self, target_deg: f64, measured_deg: f64, torque_nm: f64) -> WallCommand {
+        let mut command = WallCommand {
+            angle_deg: target_deg,
+            max_torque_nm: torque_nm,
+            engagement: 0.0,
+        };
+
+        for wall in [self.lower, self.upper].into_iter().flatten() {
+            let shaped = wall.shape(command.angle_deg, measured_deg, torque_nm);
+            command.angle_deg = shaped.angle_deg;
+            if shaped.engagement >= command.engagement {
+                command.max_torque_nm = shaped.max_torque_nm;
+                command.engagement = shaped.engagement;
+            }
+        }
+
+        command
+    }
+
+    /// Read the joint angle, shape the setpoint and send it as a 0x90 command
+    pub fn send_angle(
+        &self,
+        controller: &LivelyMotorController,
+        motor_id: u8,
+        target_deg: f64,
+        max_vel_rps: f64,
+        torque_nm: f64,
+    ) -> Result<WallCommand> {
+        let measured_deg = controller.read_motor_state(motor_id)?.position_deg;
+        let command = self.shape(target_deg, measured_deg, torque_nm);
+
+        controller.send_angle_command_to(
+            motor_id,
+            crate::degrees_to_position(command.angle_deg),
+            crate::rps_to_velocity(max_vel_rps),
+            crate::nm_to_torque(command.max_torque_nm),
+        )?;
+        Ok(command)
+    }
+}
@@ -0,0 +1,179 @@
+//! Position / torque crossfade
+//!
+//! [`HybridBlend`] moves a joint between position control and torque control
+//! over a configurable duration, for catch and release maneuvers. Torque
+//! control runs on the same 0x90 stream as position control: the target is
+//! placed ahead of the joint in the direction of the torque so the position
+//! loop saturates, and the stream's torque limit sets the output torque. During
+//! a crossfade the target, torque limit and damping are interpolated every
+//! cycle, so there is no step in mode or gains.
+
+use crate::{LivelyMotorController, Register};
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+/// How far ahead of the joint the target is placed in torque control
+pub const TORQUE_MODE_LEAD_DEG: f64 = 90.0;
+
+/// Control mode a blend is heading to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HybridTarget {
+    Position { angle_deg: f64, max_torque_nm: f64 },
+    Torque { torque_nm: f64 },
+}
+
+/// Gains used at either end of the blend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HybridGains {
+    pub kp: f32,
+    pub kd_position: f32,
+    /// Damping in torque control, usually lower to let the joint comply
+    pub kd_torque: f32,
+}
+
+impl Default for HybridGains {
+    fn default() -> Self {
+        Self {
+            kp: 1.0,
+            kd_position: 0.1,
+            kd_torque: 0.02,
+        }
+    }
+}
+
+/// Command sent in one cycle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HybridCommand {
+    /// 0.0 = pure position control, 1.0 = pure torque control
+    pub torque_weight: f64,
+    pub angle_deg: f64,
+    pub max_torque_nm: f64,
+    pub kd: f32,
+}
+
+/// Crossfades one joint between position and torque control
+pub struct HybridBlend<'a> {
+    controller: &'a LivelyMotorController,
+    motor_id: u8,
+    gains: HybridGains,
+    max_vel_rps: f64,
+    position: (f64, f64),
+    torque_nm: f64,
+    /// Torque weight at the start of the current fade and its goal
+    from_weight: f64,
+    to_weight: f64,
+    fade_start: Instant,
+    fade_duration: Duration,
+    written_kd: Option<f32>,
+}
+
+impl<'a> HybridBlend<'a> {
+    /// Start in position control holding `angle_deg`; the motor must be enabled
+    pub fn new(controller: &'a LivelyMotorController, motor_id: u8, angle_deg: f64, max_torque_nm: f64) -> Self {
+        Self {
+            controller,
+            motor_id,
+            gains: HybridGains::default(),
+            max_vel_rps: 2.0,
+            position: (angle_deg, max_torque_nm),
+            torque_nm: 0.0,
+            from_weight: 0.0,
+            to_weight: 0.0,
+            fade_start: Instant::now(),
+            fade_duration: Duration::ZERO,
+            written_kd: None,
+        }
+    }
+
+    pub fn with_gains(mut self, gains: HybridGains) -> Self {
+        self.gains = gains;
+        self
+    }
+
+    pub fn with_max_velocity(mut self, max_vel_rps: f64) -> Self {
+        self.max_vel_rps = max_vel_rps;
+        self
+    }
+
+    /// Current torque weight (0.0 position … 1.0 torque)
+    pub fn torque_weight(&self) -> f64 {
+        if self.fade_duration.is_zero() {
+            return self.to_weight;
+        }
+        let s = (self.fade_start.elapsed().as_secs_f64() / self.fade_duration.as_secs_f64()).min(1.0);
+        self.from_weight + (self.to_weight - self.from_weight) * s
+    }
+
+    /// Whether the last crossfade has completed
+    pub fn is_settled(&self) -> bool {
+        self.torque_weight() == self.to_weight
+    }
+
+    /// Fade towards `target` over `duration`, starting from the current blend
+    pub fn blend_to(&mut self, target: HybridTarget, duration: Duration) {
+        self.from_weight = self.torque_weight();
+        match target {
+            HybridTarget::Position { angle_deg, max_torque_nm } => {
+                self.position = (angle_deg, max_torque_nm);
+                self.to_weight = 0.0;
+            }
+            HybridTarget::Torque { torque_nm } => {
+                self.torque_nm = torque_nm;
+                self.to_weight = 1.0;
+            }
+        }
+        self.fade_start = Instant::now();
+        self.fade_duration = duration;
+    }
+
+    /// Update the position setpoint without changing the blend
+    pub fn set_position(&mut self, angle_deg: f64, max_torque_nm: f64) {
+        self.position = (angle_deg, max_torque_nm);
+    }
+
+    /// Update the torque setpoint without changing the blend
+    pub fn set_torque(&mut self, torque_nm: f64) {
+        self.torque_nm = torque_nm;
+    }
+
+    /// Blend for a measured joint angle, without sending anything
+    pub fn command(&self, measured_deg: f64) -> HybridCommand {
+        let w = self.torque_weight();
+        let (position_deg, position_torque) = self.position;
+        let torque_target = measured_deg + self.torque_nm.signum() * TORQUE_MODE_LEAD_DEG;
+        let kd = self.gains.kd_position + (self.gains.kd_torque - self.gains.kd_position) * w as f32;
+
+        HybridCommand {
+            torque_weight: w,
+            angle_deg: position_deg + (torque_target - position_deg) * w,
+            max_torque_nm: position_torque + (self.torque_nm.abs() - position_torque) * w,
+            kd,
+        }
+    }
+
+    /// Read the joint, compute this cycle's blend and send it
+    pub fn step(&mut self) -> Result<HybridCommand> {
+        let measured_deg = self.controller.read_motor_state(self.motor_id)?.position_deg;
+        let command = self.command(measured_deg);
+
+        // Damping changes are written only when they moved noticeably
+        let kd_changed = self
+            .written_kd
+            .is_none_or(|kd| (kd - command.kd).abs() > 0.01 * self.gains.kd_position.max(1e-3));
+        if kd_changed {
+            if self.written_kd.is_none() {
+                self.controller.write_register_float(self.motor_id, Register::Kp, self.gains.kp)?;
+            }
+            self.controller.write_register_float(self.motor_id, Register::Kd, command.kd)?;
+            self.written_kd = Some(command.kd);
+        }
+
+        self.controller.send_angle_command_to(
+            self.motor_id,
+            crate::degrees_to_position(command.angle_deg),
+            crate::rps_to_velocity(self.max_vel_rps),
+            crate::nm_to_torque(command.max_torque_nm),
+        )?;
+        Ok(command)
+    }
+}
@@ -0,0 +1,292 @@
+//! Bus-wide flight recorder for safety events
+//!
+//! A fall rarely starts at the joint that faults: another joint saturated or
+//! lost feedback a moment before. A [`FlightRecorder`] samples the latest
+//! state of every joint in a [`StateCache`] into a ring of the last few
+//! seconds. When a safety event ([`EventKind::is_safety_event`]) is published
+//! it keeps recording for a while longer and then writes the seconds before
+//! and after the trigger, for all joints, together with every event in
+//! between, to one JSON incident file.
+//!
+//! The cache only holds what something read or received, so the samples are
+//! as fresh as the control loop (or [`PushFeedback`](crate::PushFeedback))
+//! keeps it; each sample carries the age of its state.
+
+use crate::events::{Event, EventBus};
+use crate::StateCache;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How much a [`FlightRecorder`] keeps around a trigger, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightRecorderConfig {
+    /// Directory the incident files are written to
+    pub dir: PathBuf,
+    /// Recorded time before the trigger
+    pub before: Duration,
+    /// Recorded time after the trigger
+    pub after: Duration,
+    /// Time between samples of the state cache
+    pub sample_period: Duration,
+}
+
+impl FlightRecorderConfig {
+    /// 5 s before and 2 s after each trigger, sampled every 10 ms
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            before: Duration::from_secs(5),
+            after: Duration::from_secs(2),
+            sample_period: Duration::from_millis(10),
+        }
+    }
+
+    pub fn with_window(mut self, before: Duration, after: Duration) -> Self {
+        self.before = before;
+        self.after = after;
+        self
+    }
+
+    pub fn with_sample_period(mut self, sample_period: Duration) -> Self {
+        self.sample_period = sample_period;
+        self
+    }
+}
+
+/// State of one joint in an incident sample
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IncidentJoint {
+    pub motor_id: u8,
+    pub position_deg: f64,
+    pub velocity_rps: f64,
+    pub torque_nm: f64,
+    /// Age of the cached state when it was sampled
+    pub age_ms: f64,
+}
+
+/// All joints at one instant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncidentSample {
+    /// Time relative to the trigger (negative before it)
+    pub time_s: f64,
+    pub joints: Vec<IncidentJoint>,
+}
+
+/// An event published during the incident window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncidentEvent {
+    /// Time relative to the trigger
+    pub time_s: f64,
+    pub safety: bool,
+    pub description: String,
+}
+
+/// Contents of an incident file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    /// Wall clock of the trigger, microseconds since the Unix epoch
+    pub trigger_unix_us: u64,
+    /// The safety event that started the incident
+    pub trigger: String,
+    pub events: Vec<IncidentEvent>,
+    pub samples: Vec<IncidentSample>,
+}
+
+impl Incident {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read incident {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+/// An incident still collecting its samples after the trigger
+struct Pending {
+    trigger: Event,
+    trigger_unix_us: u64,
+    events: Vec<Event>,
+}
+
+#[derive(Default)]
+struct Output {
+    written: Vec<PathBuf>,
+    last_error: Option<String>,
+}
+
+/// Samples the state cache on a background thread and writes an incident
+/// file around every safety event
+pub struct FlightRecorder {
+    running: Arc<AtomicBool>,
+    output: Arc<Mutex<Output>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl FlightRecorder {
+    /// Start sampling `cache` and watching `events`
+    pub fn start(cache: Arc<StateCache>, events: &EventBus, config: FlightRecorderConfig) -> Result<Self> {
+        if config.sample_period.is_zero() {
+            return Err(anyhow!("flight recorder sample period must be positive"));
+        }
+        std::fs::create_dir_all(&config.dir)
+            .map_err(|e| anyhow!("Cannot create incident directory {}: {}", config.dir.display(), e))?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let output = Arc::new(Mutex::new(Output::default()));
+        let subscription = events.subscribe();
+        let worker = {
+            let (running, output) = (Arc::clone(&running), Arc::clone(&output));
+            thread::spawn(move || record(&cache, &subscription, &config, &running, &output))
+        };
+        Ok(Self {
+            running,
+            output,
+            worker: Some(worker),
+        })
+    }
+
+    /// Incident files written so far, oldest first
+    pub fn incidents(&self) -> Vec<PathBuf> {
+        self.output.lock().unwrap().written.clone()
+    }
+
+    /// Why the last incident file could not be written, if it could not
+    pub fn last_error(&self) -> Option<String> {
+        self.output.lock().unwrap().last_error.clone()
+    }
+
+    /// Stop recording; an incident still collecting is written as far as it got
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for FlightRecorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn record(
+    cache: &StateCache,
+    subscription: &Receiver<Event>,
+    config: &FlightRecorderConfig,
+    running: &AtomicBool,
+    output: &Mutex<Output>,
+) {
+    let mut ring: VecDeque<(Instant, Vec<IncidentJoint>)> = VecDeque::new();
+    let mut pending: Option<Pending> = None;
+    let mut next = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        ring.push_back((now, sample(cache)));
+
+        for event in subscription.try_iter() {
+            match &mut pending {
+                Some(incident) => incident.events.push(event),
+                None if event.kind.is_safety_event() => {
+                    pending = Some(Pending {
+                        trigger_unix_us: crate::remote::unix_micros()
+                            .saturating_sub(event.timestamp.elapsed().as_micros() as u64),
+                        trigger: event.clone(),
+                        events: vec![event],
+                    })
+                }
+                None => {}
+            }
+        }
+
+        // Keep the pre-trigger window of the pending incident, or of a future one
+        let oldest = pending.as_ref().map_or(now, |p| p.trigger.timestamp.min(now));
+        while ring.front().is_some_and(|(t, _)| oldest.duration_since(*t) > config.before) {
+            ring.pop_front();
+        }
+
+        if pending.as_ref().is_some_and(|p| now.duration_since(p.trigger.timestamp) >= config.after) {
+            finish(pending.take().unwrap(), &ring, config, output);
+        }
+
+        next += config.sample_period;
+        match next.checked_duration_since(Instant::now()) {
+            Some(wait) => thread::sleep(wait),
+            // Fell behind; don't try to catch up with a burst of samples
+            None => next = Instant::now(),
+        }
+    }
+    if let Some(incident) = pending {
+        finish(incident, &ring, config, output);
+    }
+}
+
+fn sample(cache: &StateCache) -> Vec<IncidentJoint> {
+    cache
+        .snapshot()
+        .into_iter()
+        .map(|cached| IncidentJoint {
+            motor_id: cached.state.motor_id,
+            position_deg: cached.state.position_deg,
+            velocity_rps: cached.state.velocity_rps,
+            torque_nm: cached.state.torque_nm,
+            age_ms: cached.age().as_secs_f64() * 1000.0,
+        })
+        .collect()
+}
+
+/// Seconds from `trigger` to `t`, negative before it
+fn relative(t: Instant, trigger: Instant) -> f64 {
+    match t.checked_duration_since(trigger) {
+        Some(after) => after.as_secs_f64(),
+        None => -trigger.duration_since(t).as_secs_f64(),
+    }
+}
+
+fn finish(
+    pending: Pending,
+    ring: &VecDeque<(Instant, Vec<IncidentJoint>)>,
+    config: &FlightRecorderConfig,
+    output: &Mutex<Output>,
+) {
+    let trigger = pending.trigger.timestamp;
+    let incident = Incident {
+        trigger_unix_us: pending.trigger_unix_us,
+        trigger: format!("{:?}", pending.trigger.kind),
+        events: pending
+            .events
+            .iter()
+            .map(|event| IncidentEvent {
+                time_s: relative(event.timestamp, trigger),
+                safety: event.kind.is_safety_event(),
+                description: format!("{:?}", event.kind),
+            })
+            .collect(),
+        samples: ring
+            .iter()
+            .map(|(t, joints)| IncidentSample {
+                time_s: relative(*t, trigger),
+                joints: joints.clone(),
+            })
+            .filter(|s| s.time_s >= -config.before.as_secs_f64())
+            .collect(),
+    };
+
+    let path = config.dir.join(format!("incident-{}.json", incident.trigger_unix_us));
+    let written = serde_json::to_string_pretty(&incident)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| std::fs::write(&path, json).map_err(anyhow::Error::from));
+    let mut output = output.lock().unwrap();
+    match written {
+        Ok(()) => output.written.push(path),
+        Err(e) => output.last_error = Some(format!("Cannot write incident {}: {}", path.display(), e)),
+    }
+}
@@ -0,0 +1,264 @@
+//! Joint inventory: which motor, by serial number, drives which joint
+//!
+//! After maintenance two motors are easily plugged back in swapped, and the
+//! left knee's commands then drive the right knee. A [`JointInventory`]
+//! records the serial number found at each joint's bus and ID; at startup
+//! [`check`](JointInventory::check) reads the serials again and reports
+//! every joint whose motor moved, was replaced or is missing. Callers refuse
+//! to run ([`InventoryReport::ensure_ok`]) or, when every joint's motor was
+//! found, remap the joints to where their motors are now
+//! ([`InventoryReport::remap`]).
+
+use crate::{JointMapping, LivelyMotorController};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Where a joint's motor was seen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JointRecord {
+    pub name: String,
+    /// CAN interface of the motor
+    pub channel: String,
+    pub motor_id: u8,
+    pub serial_number: u32,
+}
+
+/// Recorded serial numbers of all joints
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JointInventory {
+    pub joints: Vec<JointRecord>,
+}
+
+impl JointInventory {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read joint inventory {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// Read the serial numbers of all joints of `mapping` on `controller`'s bus
+    pub fn record(controller: &LivelyMotorController, mapping: &JointMapping) -> Result<Self> {
+        let mut inventory = Self::default();
+        for joint in &mapping.joints {
+            inventory.add(controller, &joint.name, joint.motor_id)?;
+        }
+        Ok(inventory)
+    }
+
+    /// Record the motor on `motor_id` as joint `name`, replacing an earlier record of the joint
+    pub fn add(&mut self, controller: &LivelyMotorController, name: &str, motor_id: u8) -> Result<()> {
+        let serial_number = probe(controller, motor_id)?.ok_or_else(|| {
+            anyhow!("joint {}: no motor answers on ID {} of {}", name, motor_id, controller.channel())
+        })?;
+        self.joints.retain(|j| j.name != name);
+        self.joints.push(JointRecord {
+            name: name.to_string(),
+            channel: controller.channel().to_string(),
+            motor_id,
+            serial_number,
+        });
+        Ok(())
+    }
+
+    /// Only the joints on `channel`
+    pub fn on_channel(&self, channel: &str) -> Self {
+        Self {
+            joints: self.joints.iter().filter(|j| j.channel == channel).cloned().collect(),
+        }
+    }
+
+    /// Read the serial number on every recorded joint's bus and ID
+    ///
+    /// Finds swaps among the recorded IDs, also across buses. A motor moved
+    /// to an ID no joint uses is only found by
+    /// [`check_scanning`](Self::check_scanning).
+    pub fn check(&self, controllers: &[&LivelyMotorController]) -> Result<InventoryReport> {
+        self.check_ids(controllers, &[])
+    }
+
+    /// [`check`](Self::check), also reading the serials of motors answering
+    /// on IDs `start_id..=end_id` of every bus
+    pub fn check_scanning(
+        &self,
+        controllers: &[&LivelyMotorController],
+        start_id: u8,
+        end_id: u8,
+    ) -> Result<InventoryReport> {
+        self.check_ids(controllers, &(start_id..=end_id).collect::<Vec<_>>())
+    }
+
+    fn check_ids(&self, controllers: &[&LivelyMotorController], extra_ids: &[u8]) -> Result<InventoryReport> {
+        let controller = |channel: &str| {
+            controllers
+                .iter()
+                .find(|c| c.channel() == channel)
+                .ok_or_else(|| anyhow!("no controller for {} in the joint inventory", channel))
+        };
+
+        // Serial answering at each location, `None` for silence
+        let mut seen: BTreeMap<(String, u8), Option<u32>> = BTreeMap::new();
+        for joint in &self.joints {
+            seen.insert((joint.channel.clone(), joint.motor_id), None);
+        }
+        for c in controllers {
+            for &id in extra_ids {
+                seen.insert((c.channel().to_string(), id), None);
+            }
+        }
+        for ((channel, motor_id), serial) in seen.iter_mut() {
+            *serial = probe(controller(channel)?, *motor_id)?;
+        }
+
+        let located = |serial_number: u32| {
+            seen.iter()
+                .find(|(_, &serial)| serial == Some(serial_number))
+                .map(|((channel, motor_id), _)| (channel.clone(), *motor_id))
+        };
+        let joints = self
+            .joints
+            .iter()
+            .map(|joint| {
+                let serial = seen[&(joint.channel.clone(), joint.motor_id)];
+                let owner = serial.and_then(|serial| self.joints.iter().find(|j| j.serial_number == serial));
+                let status = match (serial, located(joint.serial_number)) {
+                    (Some(serial), _) if serial == joint.serial_number => JointStatus::Ok,
+                    (_, Some((channel, motor_id))) => JointStatus::Moved { channel, motor_id },
+                    (Some(serial_number), None) if owner.is_none() => JointStatus::Replaced { serial_number },
+                    (_, None) => JointStatus::Missing,
+                };
+                JointCheck {
+                    joint: joint.clone(),
+                    status,
+                    occupant: owner.filter(|owner| owner.name != joint.name).map(|owner| owner.name.clone()),
+                }
+            })
+            .collect();
+        Ok(InventoryReport { joints })
+    }
+}
+
+/// Serial number of the motor on `motor_id`, `None` if nothing answers
+fn probe(controller: &LivelyMotorController, motor_id: u8) -> Result<Option<u32>> {
+    if !controller.ping_motor(motor_id)?.is_online {
+        return Ok(None);
+    }
+    Ok(Some(controller.read_serial_number(motor_id)?))
+}
+
+/// What was found for one joint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JointStatus {
+    /// The recorded motor answers on the recorded ID
+    Ok,
+    /// The recorded motor answers elsewhere, e.g. swapped with another joint's
+    Moved { channel: String, motor_id: u8 },
+    /// An unknown motor answers on the joint's ID, e.g. a repaired actuator
+    Replaced { serial_number: u32 },
+    /// The joint's motor was not found; its ID is silent or taken by another joint's motor
+    Missing,
+}
+
+/// Check result of one joint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JointCheck {
+    pub joint: JointRecord,
+    pub status: JointStatus,
+    /// Another joint whose recorded motor answers on this joint's ID
+    pub occupant: Option<String>,
+}
+
+/// Check results of all joints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryReport {
+    pub joints: Vec<JointCheck>,
+}
+
+impl InventoryReport {
+    pub fn is_ok(&self) -> bool {
+        self.joints.iter().all(|j| j.status == JointStatus::Ok)
+    }
+
+    /// Joints whose motor is not where it was recorded
+    pub fn problems(&self) -> Vec<&JointCheck> {
+        self.joints.iter().filter(|j| j.status != JointStatus::Ok).collect()
+    }
+
+    /// Fail, naming every problem, unless all joints are where they were recorded
+    pub fn ensure_ok(&self) -> Result<()> {
+        if self.is_ok() {
+            return Ok(());
+        }
+        Err(anyhow!("joint inventory mismatch, refusing to run:\n{}", self))
+    }
+
+    /// `mapping` with every moved joint pointing at its motor's new ID
+    ///
+    /// Only possible when every joint's motor was found on its recorded bus;
+    /// a replaced or missing motor, or one on another bus, needs a person.
+    pub fn remap(&self, mapping: &JointMapping) -> Result<JointMapping> {
+        let mut remapped = mapping.clone();
+        for check in &self.joints {
+            let motor_id = match &check.status {
+                JointStatus::Ok => continue,
+                JointStatus::Moved { channel, motor_id } if *channel == check.joint.channel => *motor_id,
+                _ => return Err(anyhow!("cannot remap joint {}: {}", check.joint.name, check)),
+            };
+            for joint in remapped.joints.iter_mut().filter(|j| j.name == check.joint.name) {
+                joint.motor_id = motor_id;
+            }
+        }
+        Ok(remapped)
+    }
+
+    /// The inventory with moved joints at their new locations, to save after a remap
+    pub fn remapped_inventory(&self) -> JointInventory {
+        let joints = self
+            .joints
+            .iter()
+            .map(|check| match &check.status {
+                JointStatus::Moved { channel, motor_id } => JointRecord {
+                    channel: channel.clone(),
+                    motor_id: *motor_id,
+                    ..check.joint.clone()
+                },
+                _ => check.joint.clone(),
+            })
+            .collect();
+        JointInventory { joints }
+    }
+}
+
+impl fmt::Display for JointCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let joint = &self.joint;
+        write!(f, "{} (SN {}, {} ID {}): ", joint.name, joint.serial_number, joint.channel, joint.motor_id)?;
+        match &self.status {
+            JointStatus::Ok => write!(f, "ok")?,
+            JointStatus::Moved { channel, motor_id } => write!(f, "motor found on {} ID {}", channel, motor_id)?,
+            JointStatus::Replaced { serial_number } => write!(f, "unknown motor SN {} on its ID", serial_number)?,
+            JointStatus::Missing => write!(f, "motor missing")?,
+        }
+        if let Some(occupant) = &self.occupant {
+            write!(f, "; {}'s motor answers on its ID", occupant)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for InventoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in self.problems() {
+            writeln!(f, "  {}", check)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+//! Torque-limited jog for maintenance
+//!
+//! Moves a joint slowly with a very low torque cap — the usual way to free a
+//! jammed limb. The caller calls [`JogSession::hold`] while the jog key is held
+//! and [`JogSession::step`] at a fixed rate; the session stops as soon as holds
+//! stop arriving (key released) or the measured torque exceeds the cap.
+
+use crate::{LivelyMotorController, Register};
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+/// Maximum jog speed in r/s, regardless of what the caller asks for
+pub const JOG_MAX_SPEED: f64 = 1.0;
+
+/// Maximum jog torque cap in Nm
+pub const JOG_MAX_TORQUE: f64 = 2.0;
+
+/// Jog direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JogDirection {
+    Positive,
+    Negative,
+}
+
+impl JogDirection {
+    fn sign(self) -> f64 {
+        match self {
+            JogDirection::Positive => 1.0,
+            JogDirection::Negative => -1.0,
+        }
+    }
+}
+
+/// Result of one jog step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JogStatus {
+    /// Still moving, with the last measured torque (if read back)
+    Moving { torque_nm: Option<f64> },
+    /// Stopped because the measured torque exceeded the cap
+    Overload { torque_nm: f64 },
+    /// Stopped because no hold arrived within the deadman timeout
+    Released,
+    /// Already stopped
+    Stopped,
+}
+
+/// An active jog; stops the motor when dropped
+pub struct JogSession<'a> {
+    controller: &'a LivelyMotorController,
+    motor_id: u8,
+    velocity_rps: f64,
+    torque_cap_nm: f64,
+    deadman: Duration,
+    last_hold: Instant,
+    stopped: bool,
+}
+
+impl<'a> JogSession<'a> {
+    /// Time without `hold` after which the jog stops by itself
+    pub fn set_deadman(&mut self, timeout: Duration) {
+        self.deadman = timeout;
+    }
+
+    pub fn motor_id(&self) -> u8 {
+        self.motor_id
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Change direction without stopping
+    pub fn set_direction(&mut self, direction: JogDirection) {
+        self.velocity_rps = self.velocity_rps.abs() * direction.sign();
+    }
+
+    /// Signal that the jog key is still held
+    pub fn hold(&mut self) {
+        self.last_hold = Instant::now();
+    }
+
+    /// Send one velocity frame and check the torque; call at a fixed rate (e.g. 50 Hz)
+    pub fn step(&mut self) -> Result<JogStatus> {
+        if self.stopped {
+            return Ok(JogStatus::Stopped);
+        }
+        if self.is_released() {
+            self.stop()?;
+            return Ok(JogStatus::Released);
+        }
+
+        self.controller.send_velocity_command_to(
+            self.motor_id,
+            crate::MAGIC_POS,
+            crate::rps_to_velocity(self.velocity_rps),
+            crate::rps2_to_acceleration(5.0),
+        )?;
+
+        let torque_nm = self.controller.read_motor_state(self.motor_id).ok().map(|s| s.torque_nm);
+        if let Some(torque) = torque_nm {
+            if torque.abs() > self.torque_cap_nm {
+                self.stop()?;
+                return Ok(JogStatus::Overload { torque_nm: torque });
+            }
+        }
+
+        Ok(JogStatus::Moving { torque_nm })
+    }
+
+    /// Whether the deadman timeout has elapsed since the last hold
+    pub fn is_released(&self) -> bool {
+        self.last_hold.elapsed() > self.deadman
+    }
+
+    /// Stop immediately and disable the motor
+    pub fn stop(&mut self) -> Result<()> {
+        if self.stopped {
+            return Ok(());
+        }
+        self.stopped = true;
+        self.controller.send_velocity_command_to(
+            self.motor_id,
+            crate::MAGIC_POS,
+            0,
+            crate::rps2_to_acceleration(30.0),
+        )?;
+        Ok(self.controller.disable_motor(self.motor_id)?)
+    }
+}
+
+impl Drop for JogSession<'_> {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+impl LivelyMotorController {
+    /// Start a slow, torque-capped jog on `motor_id`
+    ///
+    /// `speed_rps` is clamped to [`JOG_MAX_SPEED`] and `torque_cap_nm` to
+    /// [`JOG_MAX_TORQUE`]. The cap is written to the motor's torque limit and
+    /// also checked host-side against the measured torque on every step.
+    pub fn jog(
+        &self,
+        motor_id: u8,
+        direction: JogDirection,
+        speed_rps: f64,
+        torque_cap_nm: f64,
+    ) -> Result<JogSession<'_>> {
+        let speed = speed_rps.abs().min(JOG_MAX_SPEED);
+        let torque_cap = torque_cap_nm.abs().min(JOG_MAX_TORQUE);
+
+        self.enable_velocity_mode(motor_id)?;
+        self.write_register_float(motor_id, Register::TorqueLimit, torque_cap as f32)?;
+
+        Ok(JogSession {
+            controller: self,
+            motor_id,
+            velocity_rps: speed * direction.sign(),
+            torque_cap_nm: torque_cap,
+            deadman: Duration::from_millis(200),
+            last_hold: Instant::now(),
+            stopped: false,
+        })
+    }
+}
@@ -0,0 +1,287 @@
+//! Planar serial-chain kinematics
+//!
+//! Forward and inverse kinematics for planar 2-link and 3-link arms, with joint
+//! limit checking. Joint angles are in degrees (as sent to the motors), link
+//! lengths and positions in any consistent unit. Angles are measured
+//! counter-clockwise, each joint relative to the previous link.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// End-effector position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// End-effector position and orientation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose2 {
+    pub x: f64,
+    pub y: f64,
+    /// Orientation of the last link in degrees
+    pub phi_deg: f64,
+}
+
+/// Allowed range of a joint
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct JointLimits {
+    pub min_deg: f64,
+    pub max_deg: f64,
+}
+
+impl JointLimits {
+    pub fn new(min_deg: f64, max_deg: f64) -> Self {
+        Self { min_deg, max_deg }
+    }
+
+    pub fn contains(&self, angle_deg: f64) -> bool {
+        angle_deg >= self.min_deg && angle_deg <= self.max_deg
+    }
+}
+
+impl Default for JointLimits {
+    fn default() -> Self {
+        Self::new(-180.0, 180.0)
+    }
+}
+
+/// Which of the two inverse solutions to pick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Elbow {
+    /// Positive elbow angle
+    Up,
+    /// Negative elbow angle
+    Down,
+}
+
+/// Planar 2-link arm
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Planar2Link {
+    pub l1: f64,
+    pub l2: f64,
+    pub limits: [JointLimits; 2],
+}
+
+impl Planar2Link {
+    pub fn new(l1: f64, l2: f64) -> Self {
+        Self {
+            l1,
+            l2,
+            limits: [JointLimits::default(); 2],
+        }
+    }
+
+    pub fn with_limits(mut self, limits: [JointLimits; 2]) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// End-effector position for joint angles `[q1, q2]`
+    pub fn forward(&self, joints_deg: [f64; 2]) -> Point2 {
+        let q1 = joints_deg[0].to_radians();
+        let q12 = q1 + joints_deg[1].to_radians();
+        Point2 {
+            x: self.l1 * q1.cos() + self.l2 * q12.cos(),
+            y: self.l1 * q1.sin() + self.l2 * q12.sin(),
+        }
+    }
+
+    /// Joint angles reaching `target` with the requested elbow configuration
+    pub fn inverse(&self, target: Point2, elbow: Elbow) -> Result<[f64; 2]> {
+        let joints = solve_2link(self.l1, self.l2, target, elbow)?;
+        check_limits(&self.limits, &joints)?;
+        Ok(joints)
+    }
+
+    /// Like [`inverse`](Self::inverse), falling back to the other elbow if the
+    /// preferred solution violates a joint limit
+    pub fn inverse_any(&self, target: Point2, preferred: Elbow) -> Result<[f64; 2]> {
+        self.inverse(target, preferred)
+            .or_else(|_| self.inverse(target, other(preferred)))
+    }
+
+    /// Jacobian d(x, y)/d(q1, q2), per radian
+    pub fn jacobian(&self, joints_deg: [f64; 2]) -> [[f64; 2]; 2] {
+        let q1 = joints_deg[0].to_radians();
+        let q12 = q1 + joints_deg[1].to_radians();
+        [
+            [-self.l1 * q1.sin() - self.l2 * q12.sin(), -self.l2 * q12.sin()],
+            [self.l1 * q1.cos() + self.l2 * q12.cos(), self.l2 * q12.cos()],
+        ]
+    }
+
+    /// End-effector force `[fx, fy]` balancing joint torques (τ = Jᵀ F)
+    pub fn estimate_force(&self, joints_deg: [f64; 2], torques_nm: [f64; 2]) -> Result<[f64; 2]> {
+        let j = self.jacobian(joints_deg);
+        let jt = [[j[0][0], j[1][0]], [j[0][1], j[1][1]]];
+        let det = jt[0][0] * jt[1][1] - jt[0][1] * jt[1][0];
+        if det.abs() < SINGULAR_EPS {
+            return Err(anyhow!("arm is at a singular configuration"));
+        }
+        Ok([
+            (jt[1][1] * torques_nm[0] - jt[0][1] * torques_nm[1]) / det,
+            (jt[0][0] * torques_nm[1] - jt[1][0] * torques_nm[0]) / det,
+        ])
+    }
+}
+
+/// Planar 3-link arm (2-link arm plus a wrist)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Planar3Link {
+    pub l1: f64,
+    pub l2: f64,
+    pub l3: f64,
+    pub limits: [JointLimits; 3],
+}
+
+impl Planar3Link {
+    pub fn new(l1: f64, l2: f64, l3: f64) -> Self {
+        Self {
+            l1,
+            l2,
+            l3,
+            limits: [JointLimits::default(); 3],
+        }
+    }
+
+    pub fn with_limits(mut self, limits: [JointLimits; 3]) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// End-effector pose for joint angles `[q1, q2, q3]`
+    pub fn forward(&self, joints_deg: [f64; 3]) -> Pose2 {
+        let q1 = joints_deg[0].to_radians();
+        let q12 = q1 + joints_deg[1].to_radians();
+        let q123 = q12 + joints_deg[2].to_radians();
+        Pose2 {
+            x: self.l1 * q1.cos() + self.l2 * q12.cos() + self.l3 * q123.cos(),
+            y: self.l1 * q1.sin() + self.l2 * q12.sin() + self.l3 * q123.sin(),
+            phi_deg: wrap_deg(q123.to_degrees()),
+        }
+    }
+
+    /// Joint angles reaching `target` with the requested elbow configuration
+    pub fn inverse(&self, target: Pose2, elbow: Elbow) -> Result<[f64; 3]> {
+        let phi = target.phi_deg.to_radians();
+        let wrist = Point2 {
+            x: target.x - self.l3 * phi.cos(),
+            y: target.y - self.l3 * phi.sin(),
+        };
+        let [q1, q2] = solve_2link(self.l1, self.l2, wrist, elbow)?;
+        let joints = [q1, q2, wrap_deg(target.phi_deg - q1 - q2)];
+        check_limits(&self.limits, &joints)?;
+        Ok(joints)
+    }
+
+    /// Like [`inverse`](Self::inverse), falling back to the other elbow if the
+    /// preferred solution violates a joint limit
+    pub fn inverse_any(&self, target: Pose2, preferred: Elbow) -> Result<[f64; 3]> {
+        self.inverse(target, preferred)
+            .or_else(|_| self.inverse(target, other(preferred)))
+    }
+
+    /// Jacobian d(x, y, phi)/d(q1, q2, q3), per radian
+    pub fn jacobian(&self, joints_deg: [f64; 3]) -> [[f64; 3]; 3] {
+        let q1 = joints_deg[0].to_radians();
+        let q12 = q1 + joints_deg[1].to_radians();
+        let q123 = q12 + joints_deg[2].to_radians();
+        let (s1, s12, s123) = (self.l1 * q1.sin(), self.l2 * q12.sin(), self.l3 * q123.sin());
+        let (c1, c12, c123) = (self.l1 * q1.cos(), self.l2 * q12.cos(), self.l3 * q123.cos());
+        [
+            [-(s1 + s12 + s123), -(s12 + s123), -s123],
+            [c1 + c12 + c123, c12 + c123, c123],
+            [1.0, 1.0, 1.0],
+        ]
+    }
+
+    /// End-effector wrench `[fx, fy, mz]` balancing joint torques (τ = Jᵀ W)
+    pub fn estimate_force(&self, joints_deg: [f64; 3], torques_nm: [f64; 3]) -> Result<[f64; 3]> {
+        let j = self.jacobian(joints_deg);
+        let jt = [
+            [j[0][0], j[1][0], j[2][0]],
+            [j[0][1], j[1][1], j[2][1]],
+            [j[0][2], j[1][2], j[2][2]],
+        ];
+        solve3(jt, torques_nm).ok_or(anyhow!("arm is at a singular configuration"))
+    }
+}
+
+/// Determinant below which a Jacobian is treated as singular
+const SINGULAR_EPS: f64 = 1e-9;
+
+/// Solve `a x = b` by Cramer's rule
+fn solve3(a: [[f64; 3]; 3], b: [f64; 3]) -> Option<[f64; 3]> {
+    let det3 = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let det = det3(a);
+    if det.abs() < SINGULAR_EPS {
+        return None;
+    }
+
+    let mut x = [0.0; 3];
+    for (col, value) in x.iter_mut().enumerate() {
+        let mut m = a;
+        for row in 0..3 {
+            m[row][col] = b[row];
+        }
+        *value = det3(m) / det;
+    }
+    Some(x)
+}
+
+fn solve_2link(l1: f64, l2: f64, target: Point2, elbow: Elbow) -> Result<[f64; 2]> {
+    let r2 = target.x * target.x + target.y * target.y;
+    let cos_q2 = (r2 - l1 * l1 - l2 * l2) / (2.0 * l1 * l2);
+    if !(-1.0 - 1e-9..=1.0 + 1e-9).contains(&cos_q2) {
+        return Err(anyhow!(
+            "target ({:.3}, {:.3}) is out of reach (reach {:.3}..{:.3})",
+            target.x,
+            target.y,
+            (l1 - l2).abs(),
+            l1 + l2
+        ));
+    }
+
+    let sin_q2 = match elbow {
+        Elbow::Up => (1.0 - cos_q2 * cos_q2).max(0.0).sqrt(),
+        Elbow::Down => -(1.0 - cos_q2 * cos_q2).max(0.0).sqrt(),
+    };
+    let q2 = sin_q2.atan2(cos_q2.clamp(-1.0, 1.0));
+    let q1 = target.y.atan2(target.x) - (l2 * sin_q2).atan2(l1 + l2 * cos_q2);
+
+    Ok([wrap_deg(q1.to_degrees()), wrap_deg(q2.to_degrees())])
+}
+
+fn check_limits(limits: &[JointLimits], joints: &[f64]) -> Result<()> {
+    for (i, (limit, &angle)) in limits.iter().zip(joints).enumerate() {
+        if !limit.contains(angle) {
+            return Err(anyhow!(
+                "joint {} angle {:.2}° outside limits [{:.2}°, {:.2}°]",
+                i + 1,
+                angle,
+                limit.min_deg,
+                limit.max_deg
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn other(elbow: Elbow) -> Elbow {
+    match elbow {
+        Elbow::Up => Elbow::Down,
+        Elbow::Down => Elbow::Up,
+    }
+}
+
+/// Wrap an angle to (-180, 180]
+fn wrap_deg(angle_deg: f64) -> f64 {
+    let wrapped = (angle_deg + 180.0).rem_euclid(360.0) - 180.0;
+    if wrapped == -180.0 { 180.0 } else { wrapped }
+}
@@ -0,0 +1,323 @@
+//! Declarative frame layouts
+//!
+//! Byte offsets, value types and byte order of every payload the crate encodes
+//! or decodes are described by a [`ProtocolLayout`] instead of being hard-coded
+//! in the encoder. The default matches current firmware; a firmware revision
+//! that moves fields or switches byte order is supported by loading a different
+//! layout from JSON (see [`EncodingPolicy::with_layout`](crate::EncodingPolicy::with_layout)).
+
+use crate::ValueType;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// CAN payloads are at most 8 bytes
+pub const FRAME_LEN: usize = 8;
+
+/// Byte order of multi-byte fields
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// One field of a frame
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub offset: usize,
+    pub value_type: ValueType,
+}
+
+impl FieldLayout {
+    pub fn new(name: &str, offset: usize, value_type: ValueType) -> Self {
+        Self {
+            name: name.to_string(),
+            offset,
+            value_type,
+        }
+    }
+
+    /// One past the last byte of the field
+    pub fn end(&self) -> usize {
+        self.offset + self.value_type.size()
+    }
+}
+
+/// Fields of one frame, in the order values are passed to [`encode`](Self::encode)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameLayout {
+    pub name: String,
+    pub fields: Vec<FieldLayout>,
+}
+
+impl FrameLayout {
+    pub fn new(name: &str, fields: Vec<FieldLayout>) -> Self {
+        Self {
+            name: name.to_string(),
+            fields,
+        }
+    }
+
+    /// Field called `name`
+    pub fn field(&self, name: &str) -> Result<&FieldLayout> {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .ok_or(anyhow!("frame '{}' has no field '{}'", self.name, name))
+    }
+
+    /// Bytes covered by fields, counted from the start of the payload
+    pub fn used_len(&self) -> usize {
+        self.fields.iter().map(FieldLayout::end).max().unwrap_or(0)
+    }
+
+    /// Check that every field fits the payload and no two fields overlap
+    pub fn validate(&self) -> Result<()> {
+        for (i, field) in self.fields.iter().enumerate() {
+            if field.end() > FRAME_LEN {
+                return Err(anyhow!(
+                    "frame '{}': field '{}' ends at byte {}, past the {}-byte payload",
+                    self.name,
+                    field.name,
+                    field.end(),
+                    FRAME_LEN
+                ));
+            }
+            for other in &self.fields[..i] {
+                if other.name == field.name {
+                    return Err(anyhow!("frame '{}': duplicate field '{}'", self.name, field.name));
+                }
+                if field.offset < other.end() && other.offset < field.end() {
+                    return Err(anyhow!(
+                        "frame '{}': fields '{}' and '{}' overlap",
+                        self.name,
+                        other.name,
+                        field.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Payload with `values` (in field order) written over `padding`
+    pub fn encode(&self, endianness: Endianness, padding: u8, values: &[f64]) -> [u8; FRAME_LEN] {
+        let mut data = [padding; FRAME_LEN];
+        for (field, &value) in self.fields.iter().zip(values) {
+            put(&mut data[field.offset..field.end()], field.value_type, value, endianness);
+        }
+        data
+    }
+
+    /// Values of all fields, `None` if `data` is too short
+    pub fn decode(&self, endianness: Endianness, data: &[u8]) -> Option<Vec<f64>> {
+        self.fields
+            .iter()
+            .map(|f| read_value(data.get(f.offset..f.end())?, f.value_type, endianness))
+            .collect()
+    }
+}
+
+/// Layouts of all frames the crate builds and parses
+///
+/// Register frames consist of the `register_header` fields followed by the
+/// register values, packed back to back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolLayout {
+    #[serde(default)]
+    pub endianness: Endianness,
+    /// `opcode` and `register` of register writes, reads and replies
+    pub register_header: FrameLayout,
+    /// `position`, `max_velocity`, `max_torque` of the 0x90 stream
+    pub angle_stream: FrameLayout,
+    /// `position`, `velocity`, `acceleration` of the 0xAD stream
+    pub velocity_stream: FrameLayout,
+}
+
+impl Default for ProtocolLayout {
+    fn default() -> Self {
+        Self {
+            endianness: Endianness::Little,
+            register_header: FrameLayout::new(
+                "register_header",
+                vec![
+                    FieldLayout::new("opcode", 0, ValueType::Int8),
+                    FieldLayout::new("register", 1, ValueType::Int8),
+                ],
+            ),
+            angle_stream: FrameLayout::new(
+                "angle_stream",
+                vec![
+                    FieldLayout::new("position", 0, ValueType::Int16),
+                    FieldLayout::new("max_velocity", 2, ValueType::Int16),
+                    FieldLayout::new("max_torque", 4, ValueType::Int16),
+                ],
+            ),
+            velocity_stream: FrameLayout::new(
+                "velocity_stream",
+                vec![
+                    FieldLayout::new("position", 0, ValueType::Int16),
+                    FieldLayout::new("velocity", 2, ValueType::Int16),
+                    FieldLayout::new("acceleration", 4, ValueType::Int16),
+                ],
+            ),
+        }
+    }
+}
+
+impl ProtocolLayout {
+    /// Load and validate a layout from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read frame layout {}: {}", path.display(), e))?;
+        Self::from_json(&text)
+    }
+
+    /// Parse and validate a layout
+    pub fn from_json(text: &str) -> Result<Self> {
+        let layout: Self = serde_json::from_str(text)?;
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// Check every frame, including the fields the encoder relies on
+    pub fn validate(&self) -> Result<()> {
+        let required: [(&FrameLayout, &[&str]); 3] = [
+            (&self.register_header, &["opcode", "register"]),
+            (&self.angle_stream, &["position", "max_velocity", "max_torque"]),
+            (&self.velocity_stream, &["position", "velocity", "acceleration"]),
+        ];
+
+        for (frame, names) in required {
+            frame.validate()?;
+            if frame.fields.len() != names.len() {
+                return Err(anyhow!("frame '{}' must have exactly the fields {:?}", frame.name, names));
+            }
+            for (field, name) in frame.fields.iter().zip(names) {
+                if field.name != *name {
+                    return Err(anyhow!(
+                        "frame '{}': expected field '{}', found '{}'",
+                        frame.name,
+                        name,
+                        field.name
+                    ));
+                }
+            }
+        }
+
+        for field in &self.register_header.fields {
+            if field.value_type != ValueType::Int8 {
+                return Err(anyhow!("register header field '{}' must be int8", field.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Offset of the first register value
+    pub fn register_values_offset(&self) -> usize {
+        self.register_header.used_len()
+    }
+
+    /// Offset of the register header field `name`
+    pub(crate) fn header_offset(&self, name: &str) -> usize {
+        self.register_header.field(name).map(|f| f.offset).unwrap_or(0)
+    }
+}
+
+/// A scaled value in a third-party frame (BMS, IMU): `(raw + bias) * scale`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScaledField {
+    /// Byte offset in the payload
+    pub offset: usize,
+    /// Size in bytes (1, 2 or 4)
+    pub size: usize,
+    #[serde(default)]
+    pub signed: bool,
+    pub scale: f64,
+    #[serde(default)]
+    pub bias: f64,
+}
+
+impl ScaledField {
+    /// Decode the field from `data`, `None` if the payload is too short
+    pub fn decode(&self, data: &[u8], endianness: Endianness) -> Option<f64> {
+        let bytes = data.get(self.offset..self.offset + self.size)?;
+        let mut raw: u64 = 0;
+        match endianness {
+            Endianness::Little => bytes.iter().rev().for_each(|&b| raw = raw << 8 | b as u64),
+            Endianness::Big => bytes.iter().for_each(|&b| raw = raw << 8 | b as u64),
+        }
+
+        let value = if self.signed {
+            let shift = 64 - 8 * self.size as u32;
+            ((raw << shift) as i64 >> shift) as f64
+        } else {
+            raw as f64
+        };
+        Some((value + self.bias) * self.scale)
+    }
+
+    /// Write `value` into `data`, the inverse of [`decode`](Self::decode);
+    /// the raw value saturates at the field's range
+    pub fn encode(&self, value: f64, data: &mut [u8], endianness: Endianness) -> Result<()> {
+        let out = data
+            .get_mut(self.offset..self.offset + self.size)
+            .ok_or(anyhow!("field at {}..{} exceeds the payload", self.offset, self.offset + self.size))?;
+        let bits = 8 * self.size as u32;
+        let (min, max) = if self.signed {
+            (-(1i64 << (bits - 1)) as f64, ((1i64 << (bits - 1)) - 1) as f64)
+        } else {
+            (0.0, ((1u64 << bits) - 1) as f64)
+        };
+        let raw = (value / self.scale - self.bias).round().clamp(min, max) as i64 as u64;
+        for (i, byte) in out.iter_mut().enumerate() {
+            let shift = match endianness {
+                Endianness::Little => 8 * i,
+                Endianness::Big => 8 * (self.size - 1 - i),
+            };
+            *byte = (raw >> shift) as u8;
+        }
+        Ok(())
+    }
+}
+
+/// Write `value` into `out` (exactly the field's size)
+pub(crate) fn put(out: &mut [u8], value_type: ValueType, value: f64, endianness: Endianness) {
+    macro_rules! bytes {
+        ($v:expr) => {
+            match endianness {
+                Endianness::Little => $v.to_le_bytes(),
+                Endianness::Big => $v.to_be_bytes(),
+            }
+        };
+    }
+    match value_type {
+        ValueType::Int8 => out.copy_from_slice(&bytes!(value as i64 as u8)),
+        ValueType::Int16 => out.copy_from_slice(&bytes!(value as i16)),
+        ValueType::Int32 => out.copy_from_slice(&bytes!(value as i32)),
+        ValueType::Float => out.copy_from_slice(&bytes!(value as f32)),
+    }
+}
+
+/// Read one value of `value_type` from `bytes` (exactly the field's size)
+pub(crate) fn read_value(bytes: &[u8], value_type: ValueType, endianness: Endianness) -> Option<f64> {
+    macro_rules! value {
+        ($t:ty) => {{
+            let bytes = bytes.try_into().ok()?;
+            match endianness {
+                Endianness::Little => <$t>::from_le_bytes(bytes),
+                Endianness::Big => <$t>::from_be_bytes(bytes),
+            }
+        }};
+    }
+    Some(match value_type {
+        ValueType::Int8 => value!(i8) as f64,
+        ValueType::Int16 => value!(i16) as f64,
+        ValueType::Int32 => value!(i32) as f64,
+        ValueType::Float => value!(f32) as f64,
+    })
+}
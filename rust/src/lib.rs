@@ -2,12 +2,179 @@
 //!
 //! High-performance Rust implementation for controlling LivelyBot motors via CAN bus.
 //! Supports motor scanning, velocity control, and angle stream control.
+//!
+//! Robot code should import from [`prelude`], the semver-stable part of the API.
+//!
+//! The controller and bus return [`MotorError`] so callers can match on the
+//! failure kind; the higher-level modules return `anyhow::Result`, see
+//! [`error`] for how to find the kind in those.
 
-use anyhow::{Result, anyhow};
-use socketcan::{CanSocket, CanFrame, CanId, Socket, EmbeddedFrame};
-use std::time::Duration;
+use anyhow::anyhow;
+use error::Result;
+use socketcan::{CanFrame, CanId, EmbeddedFrame};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::thread;
 
+pub mod ab_test;
+pub mod adaptive_rate;
+#[cfg(feature = "tokio")]
+pub mod async_controller;
+pub mod audit;
+pub mod authority;
+pub mod bms;
+pub mod bus;
+pub mod bus_lock;
+pub mod calibration;
+pub mod candump;
+pub mod capabilities;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod cli;
+pub mod config;
+pub mod config_hash;
+pub mod console;
+pub mod control_loop;
+pub mod conversions;
+pub mod dispatch;
+pub mod enable;
+pub mod error;
+pub mod estop;
+pub mod events;
+pub mod experiments;
+pub mod fall_catch;
+pub mod fault_policy;
+pub mod feedback;
+pub mod float;
+pub mod force;
+pub mod group;
+pub mod haptics;
+pub mod hybrid;
+pub mod incident;
+pub mod inventory;
+pub mod jog;
+pub mod kinematics;
+pub mod layout;
+pub mod lifecycle;
+pub mod limits;
+pub mod load_share;
+pub mod mcap;
+pub mod mock;
+pub mod mdf4;
+pub mod mirror;
+pub mod mit;
+pub mod motion;
+pub mod odometer;
+pub mod oscillator;
+pub mod park;
+pub mod passthrough;
+pub mod plugins;
+pub mod prelude;
+pub mod primitives;
+pub mod profiles;
+pub mod protocol;
+pub mod query;
+#[cfg(feature = "python")]
+mod python;
+pub mod recorder;
+pub mod remote;
+pub mod report;
+pub mod robot;
+#[cfg(feature = "ros2")]
+pub mod ros2;
+pub mod rt;
+pub mod safety;
+pub mod scaling;
+pub mod sdk_compat;
+pub mod service;
+pub mod shaping;
+pub mod snapshot;
+pub mod streamer;
+pub mod state_cache;
+pub mod stats;
+pub mod sync;
+pub mod telemetry;
+pub mod telemetry_recorder;
+pub mod trajectory;
+pub mod tuning;
+pub mod transport;
+pub mod tx_scheduler;
+pub mod watchdog;
+pub mod wheel;
+
+pub use ab_test::{AbComparison, AbMetric, AbReport, AbTest};
+pub use adaptive_rate::{AdaptiveRate, AdaptiveRateConfig};
+#[cfg(feature = "tokio")]
+pub use async_controller::AsyncLivelyMotorController;
+pub use authority::{AccessPolicy, Authority};
+pub use bms::{BmsFormat, BmsMonitor};
+pub use bus::{BusErrorStats, BusMonitor, BusSubscription, CanBus, RxDropStats, RxDropWatch};
+pub use bus_lock::BusLock;
+pub use calibration::Calibration;
+pub use candump::{CandumpLog, CandumpRecord};
+pub use capabilities::{Capabilities, Feature};
+pub use config::{JointConfig, RobotConfig};
+pub use config_hash::ConfigFingerprint;
+pub use dispatch::FrameDispatcher;
+pub use enable::{ControlMode, EnableConfig};
+pub use error::{ErrorContext, FaultCode, MotorError};
+pub use estop::{Braking, StopMode};
+pub use console::{Console, ConsoleCommand, ConsoleInput};
+pub use control_loop::{ControlLoop, CycleInfo, Scheduler, SensorFrame};
+pub use events::{Event, EventBus, EventKind};
+pub use experiments::{Sweep, SweepParam, SweepPoint, SweepReport};
+pub use fall_catch::{FallCatchConfig, FallCatcher};
+pub use fault_policy::{FaultAction, FaultClass, FaultPolicy};
+pub use feedback::{PushFeedback, VelocityFilter, DEFAULT_FEEDBACK_WINDOW};
+pub use float::{Float, Real};
+pub use force::{Chain, ForceEstimator};
+pub use group::MotorGroup;
+pub use haptics::{HapticBoundary, VirtualWall, WallCommand, WallSide};
+pub use hybrid::{HybridBlend, HybridCommand, HybridGains, HybridTarget};
+pub use incident::{FlightRecorder, FlightRecorderConfig, Incident, IncidentEvent, IncidentJoint, IncidentSample};
+pub use inventory::{InventoryReport, JointCheck, JointInventory, JointRecord, JointStatus};
+pub use jog::{JogDirection, JogSession, JogStatus};
+pub use kinematics::{Elbow, JointLimits, Planar2Link, Planar3Link, Point2, Pose2};
+pub use layout::{Endianness, FieldLayout, FrameLayout, ProtocolLayout, ScaledField};
+pub use lifecycle::{ManagedMotor, MotorLifecycle, MotorSettings};
+pub use limits::{LimitMode, Limits};
+pub use load_share::{LoadShare, ShareCommand, ThermalDerating, TorqueSplit};
+pub use mirror::{Mirror, MirrorLink, MirrorStats};
+pub use mock::{MockTransport, SimMotor};
+pub use mit::{MitCommand, MitRanges};
+pub use motion::{MotionHandle, MotionOutcome, MotionSignals};
+pub use odometer::{MotorUsage, Odometer};
+pub use oscillator::{JointOscillation, Oscillator, OscillatorConfig};
+pub use park::{ParkConfig, ParkPose, ParkRunner, ParkStage, RangeRule};
+pub use passthrough::{AlignedSample, DecodedSample, FieldDecoder, FrameDecoder, NamedField, PassThrough};
+pub use plugins::{CommandCodec, DecodedCommand, FieldCommand};
+pub use primitives::{OscillateParams, Primitive, PrimitiveRunner, PrimitiveStatus};
+pub use recorder::{Annotation, JointSample, Recorder, SessionMetadata};
+pub use remote::{BridgeAgent, BridgeMessage, BridgeRequest, ClockSync, Coordinator};
+pub use report::{Report, ReportField, ReportMask};
+#[cfg(feature = "ros2")]
+pub use ros2::Ros2Bridge;
+pub use safety::{BatteryDerating, SafetyMonitor, TorqueEnvelope};
+pub use scaling::Scaling;
+pub use shaping::BusLoadReport;
+pub use snapshot::{EnabledMode, GroupSetpoint, GroupSnapshot, JointSnapshot};
+pub use streamer::{CommandFrame, GroupStreamer, JointStreamConfig, Setpoint, StreamerConfig};
+pub use state_cache::{CachedState, StateCache};
+pub use stats::{DeadlineStats, JitterStats};
+pub use sync::{LatchedSample, SyncLatch, SyncSource};
+pub use profiles::{Profile, ProfileSet};
+pub use protocol::{EncodingPolicy, Register, TruncatedFrame, ValueType};
+pub use query::{ErrorLogEntry, Identity, InfoQuery, TemperatureSnapshot};
+pub use telemetry::{BatteryState, ChainTelemetry, EndEffectorForce, GpioState, MotorState, MotorTelemetry};
+pub use telemetry_recorder::{SentCommand, TelemetryFormat, TelemetryRecorder};
+pub use trajectory::{JointMap, JointMapping, Trajectory, Waypoint};
+pub use transport::{CanTransport, RawFrame, SocketTransport};
+pub use tuning::{TunableParam, TuneBounds, TuneResult};
+pub use tx_scheduler::{ScheduledFrameId, TxScheduler};
+pub use watchdog::Watchdog;
+pub use wheel::{AngleUnwrapper, BasePose, DifferentialDrive, Wheel, WheelOdometry};
+
 // Protocol coefficients
 pub const FACTOR_POS: f64 = 10000.0;    // 1圈 = 10000
 pub const FACTOR_VEL: f64 = 4000.0;     // 1r/s = 4000
@@ -38,110 +205,719 @@ impl Default for MotorInfo {
 
 /// LivelyBot motor controller using CAN interface
 pub struct LivelyMotorController {
-    socket: CanSocket,
-    channel: String,
-    bitrate: u32,
+    bus: Arc<CanBus>,
+    encoding: EncodingPolicy,
+    /// Subscription backing `read_frame_with_timeout`, created on first use
+    rx: Mutex<Option<BusSubscription>>,
+    /// Latest state of every motor read through this controller
+    states: Arc<StateCache>,
+    /// Motors enabled through this controller and how, restored by `reconnect`
+    enabled: Mutex<BTreeMap<u8, EnableConfig>>,
+    /// Per-motor velocity history for the acceleration estimate
+    filters: Mutex<BTreeMap<u8, VelocityFilter>>,
+    feedback_window: Duration,
+    mit_ranges: MitRanges,
+    /// Detected firmware features per motor
+    capabilities: Mutex<BTreeMap<u8, Capabilities>>,
+    /// Software limits enforced on outgoing commands
+    limits: Mutex<BTreeMap<u8, Limits>>,
+    /// Joint calibrations applied to commands and parsed feedback
+    calibrations: Arc<Mutex<BTreeMap<u8, Calibration>>>,
+    /// Content of each motor's pushed replies, if not the default
+    report_masks: Arc<Mutex<BTreeMap<u8, ReportMask>>>,
 }
 
 impl LivelyMotorController {
-    /// Create a new motor controller
+    /// Create a new motor controller, taking exclusive ownership of the channel
     pub fn new(channel: &str, bitrate: u32) -> Result<Self> {
-        let socket = CanSocket::open(channel)?;
+        Ok(Self::with_bus(CanBus::open(channel, bitrate, false)?))
+    }
 
-        Ok(Self {
-            socket,
-            channel: channel.to_string(),
-            bitrate,
-        })
+    /// Create a controller even if another process owns the channel
+    pub fn new_forced(channel: &str, bitrate: u32) -> Result<Self> {
+        Ok(Self::with_bus(CanBus::open(channel, bitrate, true)?))
+    }
+
+    /// Create a controller reading a candump log instead of an interface,
+    /// see [`CanBus::replay`]
+    pub fn replay<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Ok(Self::with_bus(CanBus::replay(path, 1_000_000)?))
+    }
+
+    /// Create a controller on another transport, e.g. simulated motors
+    /// ([`MockTransport`]), see [`CanBus::with_transport`]
+    pub fn with_transport<T: CanTransport + 'static>(channel: &str, transport: T) -> Self {
+        Self::with_bus(CanBus::with_transport(transport, channel, 1_000_000))
+    }
+
+    /// Create a controller on a bus already opened by this process
+    pub fn with_bus(bus: Arc<CanBus>) -> Self {
+        Self {
+            bus,
+            encoding: EncodingPolicy::default(),
+            rx: Mutex::new(None),
+            states: Arc::new(StateCache::new()),
+            enabled: Mutex::new(BTreeMap::new()),
+            filters: Mutex::new(BTreeMap::new()),
+            feedback_window: DEFAULT_FEEDBACK_WINDOW,
+            mit_ranges: MitRanges::default(),
+            capabilities: Mutex::new(BTreeMap::new()),
+            limits: Mutex::new(BTreeMap::new()),
+            calibrations: Arc::new(Mutex::new(BTreeMap::new())),
+            report_masks: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Padding byte and strictness used for all outgoing payloads
+    pub fn encoding(&self) -> &EncodingPolicy {
+        &self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: EncodingPolicy) {
+        self.encoding = encoding;
+    }
+
+    /// Window over which velocity feedback is fitted to estimate acceleration
+    pub fn feedback_window(&self) -> Duration {
+        self.feedback_window
+    }
+
+    /// Longer windows give a smoother but more delayed acceleration estimate
+    pub fn set_feedback_window(&mut self, window: Duration) {
+        self.feedback_window = window;
+        self.filters.get_mut().unwrap().clear();
+    }
+
+    /// Ranges the MIT command fields are mapped onto
+    pub fn mit_ranges(&self) -> &MitRanges {
+        &self.mit_ranges
+    }
+
+    /// Must match the firmware's ranges, or every MIT command is rescaled
+    pub fn set_mit_ranges(&mut self, ranges: MitRanges) {
+        self.mit_ranges = ranges;
+    }
+
+    /// The shared bus, for attaching monitors or further controllers
+    pub fn bus(&self) -> &Arc<CanBus> {
+        &self.bus
+    }
+
+    /// Read the bus continuously in the background, so feedback is not lost
+    /// while nothing waits for it (see [`CanBus::start_receiver`])
+    pub fn start_receiver(&self) {
+        self.bus.start_receiver();
+    }
+
+    pub fn stop_receiver(&self) {
+        self.bus.stop_receiver();
+    }
+
+    /// Lock-free cache of the latest state read from each motor
+    pub fn state_cache(&self) -> &Arc<StateCache> {
+        &self.states
+    }
+
+    /// Switch to another interface or bitrate, e.g. from a vcan bench to can0
+    ///
+    /// Encoding policy, state cache, the background receiver and the set of
+    /// enabled motors are kept: every motor enabled through this controller is
+    /// enabled again in the same mode and must answer a ping on the new bus.
+    /// Monitors or controllers sharing the old [`CanBus`] keep using it.
+    pub fn reconnect(&mut self, channel: &str, bitrate: u32) -> Result<()> {
+        let bus = if channel == self.channel() {
+            self.bus.reopen(bitrate)?
+        } else {
+            CanBus::open(channel, bitrate, !self.owns_bus())?
+        };
+        if self.bus.is_receiving() {
+            bus.start_receiver();
+        }
+        *self.rx.get_mut().unwrap() = None;
+        self.bus = bus;
+
+        let enabled: Vec<(u8, EnableConfig)> = self
+            .enabled
+            .get_mut()
+            .unwrap()
+            .iter()
+            .map(|(&id, &config)| (id, config))
+            .collect();
+        let mut missing = Vec::new();
+        for (motor_id, config) in enabled {
+            self.enable_with(motor_id, &config)?;
+            if !self.ping_motor(motor_id)?.is_online {
+                missing.push(motor_id);
+            }
+        }
+        if !missing.is_empty() {
+            return Err(anyhow!("motors {:?} did not answer on {} after reconnecting", missing, channel).into());
+        }
+        Ok(())
+    }
+
+    /// Motors currently enabled through this controller
+    pub fn enabled_motors(&self) -> Vec<u8> {
+        self.enabled.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Whether `motor_id` is enabled through this controller, and in which mode
+    fn enable_mode(&self, motor_id: u8) -> Option<ControlMode> {
+        self.enabled.lock().unwrap().get(&motor_id).map(|config| config.mode)
+    }
+
+    /// Whether this controller holds the channel's ownership lock
+    pub fn owns_bus(&self) -> bool {
+        self.bus.owns_bus()
+    }
+
+    /// CAN interface name
+    pub fn channel(&self) -> &str {
+        self.bus.channel()
+    }
+
+    /// Configured CAN bitrate
+    pub fn bitrate(&self) -> u32 {
+        self.bus.bitrate()
     }
 
     /// Send a CAN frame
     pub fn send_frame(&self, id: u32, data: &[u8]) -> Result<()> {
-        let can_id = CanId::extended(id).ok_or(anyhow!("Invalid CAN ID"))?;
-        let frame = CanFrame::new(can_id, data).ok_or(anyhow!("Failed to create CAN frame"))?;
-        self.socket.write_frame(&frame)?;
-        Ok(())
+        if self.encoding.strict {
+            self.encoding.validate(id, data)?;
+        }
+        let can_id = CanId::extended(id).ok_or(anyhow!("Invalid CAN ID 0x{:X}", id))?;
+        let frame = CanFrame::new(can_id, data).ok_or(anyhow!("Failed to create CAN frame 0x{:X}", id))?;
+        self.bus.send(&frame)
+    }
+
+    /// Send a custom command registered with [`plugins::register`]
+    pub fn send_command(&self, motor_id: u8, command: u8, values: &[f64]) -> Result<()> {
+        let codec = plugins::lookup(command).ok_or(anyhow!("no plugin registered for command 0x{:02X}", command))?;
+        let context = || format!("sending {} to motor {}", codec.name(), motor_id);
+        let data = codec.encode(values).map_err(|e| e.context(context()))?;
+        self.send_frame(motor_id as u32, &data).map_err(|e| e.context(context()))
+    }
+
+    /// Run `f`, naming the operation, motor and channel in any error it returns
+    fn during<T>(&self, operation: &str, motor_id: u8, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        f().map_err(|e| e.context(format!("{} motor {} on {}", operation, motor_id, self.channel())))
     }
 
     /// Read a CAN frame with timeout
+    ///
+    /// Frames are copies from the shared bus; replies consumed by pings or
+    /// register reads of other components still show up here.
     pub fn read_frame_with_timeout(&self, timeout_ms: u64) -> Result<Option<CanFrame>> {
-        self.socket.set_read_timeout(Duration::from_millis(timeout_ms))?;
-        match self.socket.read_frame() {
-            Ok(frame) => Ok(Some(frame)),
-            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => Ok(None),
-            Err(e) => Err(e.into()),
-        }
+        let mut rx = self.rx.lock().unwrap();
+        rx.get_or_insert_with(|| self.bus.subscribe())
+            .recv_timeout(Duration::from_millis(timeout_ms))
+    }
+
+    /// [`read_frame_with_timeout`](Self::read_frame_with_timeout) as a
+    /// [`RawFrame`], independent of the socketcan version
+    pub fn read_raw_frame(&self, timeout_ms: u64) -> Result<Option<RawFrame>> {
+        Ok(self.read_frame_with_timeout(timeout_ms)?.as_ref().map(RawFrame::from_frame))
     }
 
     /// Ping a motor to check if it's online
+    ///
+    /// Safe while another thread streams commands to or reads registers of the
+    /// same motor: the reply is picked from a private copy of the RX stream, and
+    /// register replies meant for the other thread are skipped, not taken as
+    /// the ping's answer.
     pub fn ping_motor(&self, motor_id: u8) -> Result<MotorInfo> {
-        let start_time = std::time::Instant::now();
+        self.during("ping", motor_id, || self.ping_motor_inner(motor_id))
+    }
+
+    fn ping_motor_inner(&self, motor_id: u8) -> Result<MotorInfo> {
+        let start_time = Instant::now();
         let mut info = MotorInfo {
             motor_id,
             ..Default::default()
         };
 
         // Send ping command: 0x8000 | motor_id with CAN_EFF_FLAG
-        let ping_id = 0x8000u32 | motor_id as u32;
-        let ping_data = [0x11, 0x00, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50];
+        let ping_id = protocol::REPLY_FLAG | motor_id as u32;
+        let ping_data = self.encoding.query(InfoQuery::Identity, 0);
 
+        let rx = self.bus.subscribe();
         self.send_frame(ping_id, &ping_data)?;
         thread::sleep(Duration::from_millis(10));
 
         // Wait for response
-        let timeout_start = std::time::Instant::now();
+        let timeout_start = Instant::now();
         while timeout_start.elapsed().as_millis() < 50 {
-            if let Some(frame) = self.read_frame_with_timeout(10)? {
-                let can_id = frame.id();
-
-                // Parse response (same logic as Python/C++ versions)
-                let (source_id, direct_id) = match can_id {
-                    socketcan::Id::Standard(id) => {
-                        let id_raw = id.as_raw();
-                        (((id_raw >> 8) & 0x7F) as u8, (id_raw & 0xFF) as u8)
-                    }
-                    socketcan::Id::Extended(id) => {
-                        let id_raw = id.as_raw();
-                        (((id_raw >> 8) & 0x7F) as u8, (id_raw & 0xFF) as u8)
-                    }
-                };
+            let Some(frame) = wait_for_reply(&rx, motor_id, 10)? else {
+                continue;
+            };
+            if !answers_ping(&self.encoding, frame.data()) {
+                continue;
+            }
+            info.response_time_ms = start_time.elapsed().as_millis() as u64;
+            info.is_online = true;
 
-                let detected_id = if source_id > 0 && source_id < 128 {
-                    source_id
-                } else if direct_id == motor_id {
-                    direct_id
-                } else {
+            // Parse motor info from response
+            if let Ok(identity) = self.count_malformed(motor_id, query::Identity::parse(frame.data())) {
+                info.name = identity.name;
+                if !identity.hardware_version.is_empty() {
+                    info.hardware_version = identity.hardware_version;
+                }
+            }
+            break;
+        }
+
+        Ok(info)
+    }
+
+    /// Send an info query and return the matching reply payload
+    pub fn query(&self, motor_id: u8, query: InfoQuery, index: u8) -> Result<Vec<u8>> {
+        self.during(&format!("{} query of", query.name()), motor_id, || {
+            let rx = self.bus.subscribe();
+            self.send_frame(protocol::REPLY_FLAG | motor_id as u32, &self.encoding.query(query, index))?;
+
+            let timeout_start = Instant::now();
+            while timeout_start.elapsed().as_millis() < 50 {
+                let Some(frame) = wait_for_reply(&rx, motor_id, 10)? else {
                     continue;
                 };
+                if query.matches(frame.data(), index) {
+                    return Ok(frame.data().to_vec());
+                }
+            }
+            // No reply; the firmware may not support this query
+            Err(error::timeout(motor_id))
+        })
+    }
+
+    /// Serial number stored in the motor
+    pub fn read_serial_number(&self, motor_id: u8) -> Result<u32> {
+        self.require(motor_id, Feature::SerialNumber)?;
+        self.count_malformed(motor_id, query::parse_serial_number(&self.query(motor_id, InfoQuery::SerialNumber, 0)?))
+    }
+
+    /// Time since the motor powered up
+    pub fn read_uptime(&self, motor_id: u8) -> Result<Duration> {
+        self.require(motor_id, Feature::Uptime)?;
+        self.count_malformed(motor_id, query::parse_uptime(&self.query(motor_id, InfoQuery::Uptime, 0)?))
+    }
+
+    /// Motor, driver and MCU temperatures sampled together
+    pub fn read_temperatures(&self, motor_id: u8) -> Result<TemperatureSnapshot> {
+        self.require(motor_id, Feature::Temperatures)?;
+        self.count_malformed(motor_id, TemperatureSnapshot::parse(&self.query(motor_id, InfoQuery::Temperatures, 0)?))
+    }
 
-                if detected_id == motor_id {
-                    info.response_time_ms = start_time.elapsed().as_millis() as u64;
-                    info.is_online = true;
-
-                    // Parse motor info from response
-                    let data = frame.data();
-                    if data.len() >= 4 && data[0] == 0x51 {
-                        let mut name_bytes = [0u8; 3];
-                        name_bytes.copy_from_slice(&data[1..4]);
-                        if let Ok(name) = std::str::from_utf8(&name_bytes) {
-                            info.name = name.trim_end_matches('\0').to_string();
-                        }
-                    }
-
-                    if data.len() >= 8 {
-                        let mut version_bytes = [0u8; 4];
-                        version_bytes.copy_from_slice(&data[4..8]);
-                        if let Ok(version) = std::str::from_utf8(&version_bytes) {
-                            info.hardware_version = version.trim_end_matches('\0').to_string();
-                        }
-                    }
-
-                    break;
+    /// Error log entries, most recent first
+    pub fn read_error_log(&self, motor_id: u8) -> Result<Vec<ErrorLogEntry>> {
+        self.require(motor_id, Feature::ErrorLog)?;
+        let mut entries = Vec::new();
+        for index in 0..query::ERROR_LOG_LEN {
+            let reply = self.query(motor_id, InfoQuery::ErrorLog, index)?;
+            match self.count_malformed(motor_id, ErrorLogEntry::parse(&reply))? {
+                Some(entry) => entries.push(entry),
+                None => break,
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Probe which firmware-gated features `motor_id` has and remember them
+    ///
+    /// Takes up to one reply timeout per missing feature. A motor that does
+    /// not answer the ping is an error, not a motor without features.
+    pub fn detect_capabilities(&self, motor_id: u8) -> Result<Capabilities> {
+        let info = self.ping_motor(motor_id)?;
+        if !info.is_online {
+            return Err(error::timeout(motor_id));
+        }
+        let mut features = BTreeSet::new();
+        for feature in Feature::ALL {
+            let probe = match feature.probe() {
+                capabilities::Probe::Query(query) => self.query(motor_id, query, 0).map(drop),
+                capabilities::Probe::Register(reg, ty) => self.read_registers(motor_id, reg, ty, 1).map(drop),
+            };
+            match probe {
+                Ok(()) => {
+                    features.insert(feature);
                 }
+                Err(MotorError::Timeout { .. }) => {}
+                Err(e) => return Err(e),
             }
         }
+        let capabilities = Capabilities {
+            motor_id,
+            firmware: info.hardware_version,
+            features,
+        };
+        self.capabilities.lock().unwrap().insert(motor_id, capabilities.clone());
+        Ok(capabilities)
+    }
+
+    /// Compare the scaling `motor_id` reports with the crate's `FACTOR_*`
+    /// constants, see [`scaling`](crate::scaling)
+    ///
+    /// Returns the reported scaling, `None` if the firmware does not report
+    /// one, and fails with [`MotorError::ScalingMismatch`] if it differs.
+    pub fn check_scaling(&self, motor_id: u8) -> Result<Option<Scaling>> {
+        let mut reported = [0.0; 3];
+        let registers = [Register::ScalePosition, Register::ScaleVelocity, Register::ScaleTorque];
+        for (value, register) in reported.iter_mut().zip(registers) {
+            let reply = match self.read_registers(motor_id, register, ValueType::Float, 1) {
+                Ok(reply) => reply,
+                Err(MotorError::Timeout { .. }) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            *value = reply
+                .float(0)
+                .ok_or_else(|| error::invalid_response(motor_id, "scaling reply truncated"))? as f64;
+        }
+        let scaling = Scaling {
+            position: reported[0],
+            velocity: reported[1],
+            torque: reported[2],
+        };
+        let mismatches = scaling.mismatches(&Scaling::EXPECTED);
+        if !mismatches.is_empty() {
+            return Err(error::scaling_mismatch(motor_id, mismatches.join(", ")));
+        }
+        Ok(Some(scaling))
+    }
 
-        Ok(info)
+    /// Capabilities detected for `motor_id`, if any
+    pub fn capabilities(&self, motor_id: u8) -> Option<Capabilities> {
+        self.capabilities.lock().unwrap().get(&motor_id).cloned()
+    }
+
+    /// Whether the firmware of `motor_id` has `feature`
+    ///
+    /// Detects the motor's capabilities on first use; `false` if that fails.
+    pub fn supports(&self, feature: Feature, motor_id: u8) -> bool {
+        match self.capabilities(motor_id) {
+            Some(capabilities) => capabilities.supports(feature),
+            None => self.detect_capabilities(motor_id).is_ok_and(|c| c.supports(feature)),
+        }
+    }
+
+    /// Fail with [`MotorError::Unsupported`] if detection found `feature` missing;
+    /// motors not yet probed are tried anyway
+    fn require(&self, motor_id: u8, feature: Feature) -> Result<()> {
+        match self.capabilities.lock().unwrap().get(&motor_id) {
+            Some(capabilities) if !capabilities.supports(feature) => Err(error::unsupported(motor_id, feature)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count `result` on the bus if it failed on a [`TruncatedFrame`], which
+    /// is an invalid response of `motor_id`
+    fn count_malformed<T>(&self, motor_id: u8, result: anyhow::Result<T>) -> Result<T> {
+        result.map_err(|e| match e.downcast::<TruncatedFrame>() {
+            Ok(truncated) => {
+                self.bus.note_malformed();
+                error::invalid_response(motor_id, &truncated.to_string())
+            }
+            Err(e) => e.into(),
+        })
+    }
+
+    /// Read `count` registers of type `ty` starting at `reg`
+    pub fn read_registers(
+        &self,
+        motor_id: u8,
+        reg: Register,
+        ty: ValueType,
+        count: u8,
+    ) -> Result<protocol::RegisterReply> {
+        let data = self.encoding.read(reg, ty, count);
+        let rx = self.bus.subscribe();
+        self.send_frame(protocol::REPLY_FLAG | motor_id as u32, &data)
+            .map_err(|e| e.context(format!("requesting register 0x{:02X} from motor {}", reg.addr(), motor_id)))?;
+
+        let timeout_start = Instant::now();
+        let mut malformed = None;
+        while timeout_start.elapsed().as_millis() < 50 {
+            let Some(frame) = wait_for_reply(&rx, motor_id, 10)? else {
+                continue;
+            };
+            match self.count_malformed(motor_id, self.encoding.parse_reply(frame.data())) {
+                Ok(reply) if reply.register == reg.addr() => return Ok(reply),
+                Err(e @ MotorError::InvalidResponse { .. }) => malformed = Some(e),
+                _ => {}
+            }
+        }
+
+        // A malformed answer explains the missing reply better than the timeout
+        if let Some(e) = malformed {
+            return Err(e);
+        }
+        let lost = self.bus.new_rx_drops();
+        if lost.total() > 0 {
+            return Err(error::timeout(motor_id).context(format!(
+                "reading register 0x{:02X} ({}) on {}: {} received frames were dropped since the last timeout \
+                 (kernel {}, overrun {}, queue {}); the reply may have been lost",
+                reg.addr(),
+                reg.info().name,
+                self.channel(),
+                lost.total(),
+                lost.kernel_dropped,
+                lost.overruns,
+                lost.subscriber_dropped
+            )));
+        }
+        Err(error::timeout(motor_id))
+    }
+
+    /// Read a single float register
+    pub fn read_register_float(&self, motor_id: u8, reg: Register) -> Result<f32> {
+        let reply = self.read_registers(motor_id, reg, ValueType::Float, 1)?;
+        reply
+            .float(0)
+            .ok_or_else(|| error::invalid_response(motor_id, &format!("empty reply for register 0x{:02X}", reg.addr())))
+    }
+
+    /// Read the active fault code (0 = no fault)
+    pub fn read_fault(&self, motor_id: u8) -> Result<u8> {
+        self.during("read fault of", motor_id, || {
+            let reply = self.read_registers(motor_id, Register::Fault, ValueType::Int8, 1)?;
+            Ok(reply.int(0).ok_or_else(|| error::invalid_response(motor_id, "empty fault reply"))? as u8)
+        })
+    }
+
+    /// Fail with [`MotorError::MotorFault`] if the motor reports a fault
+    pub fn check_fault(&self, motor_id: u8) -> Result<()> {
+        match self.read_fault(motor_id)? {
+            0 => Ok(()),
+            code => Err(error::motor_fault(motor_id, FaultCode(code))),
+        }
+    }
+
+    /// Write an int8 register
+    pub fn write_register_int8(&self, motor_id: u8, reg: Register, value: i8) -> Result<()> {
+        self.send_frame(motor_id as u32, &self.encoding.write_int8(reg, value))
+            .map_err(|e| e.context(format!("writing register 0x{:02X} of motor {}", reg.addr(), motor_id)))
+    }
+
+    /// Write an int16 register
+    pub fn write_register_int16(&self, motor_id: u8, reg: Register, value: i16) -> Result<()> {
+        self.send_frame(motor_id as u32, &self.encoding.write_int16(reg, value))
+            .map_err(|e| e.context(format!("writing register 0x{:02X} of motor {}", reg.addr(), motor_id)))
+    }
+
+    /// Write a float register
+    pub fn write_register_float(&self, motor_id: u8, reg: Register, value: f32) -> Result<()> {
+        self.send_frame(motor_id as u32, &self.encoding.write_float(reg, value))
+            .map_err(|e| e.context(format!("writing register 0x{:02X} of motor {}", reg.addr(), motor_id)))
+    }
+
+    /// Read measured position, velocity and torque
+    pub fn read_motor_state(&self, motor_id: u8) -> Result<MotorState> {
+        let reply = self
+            .read_registers(motor_id, Register::Position, ValueType::Int16, 3)
+            .map_err(|e| e.context(format!("reading feedback of motor {} on {}", motor_id, self.channel())))?;
+        self.ingest_state(motor_id, &reply)
+    }
+
+    /// Convert a position/velocity/torque reply (requested or pushed) into a
+    /// state, updating the acceleration estimate and the state cache
+    pub(crate) fn ingest_state(&self, motor_id: u8, reply: &protocol::RegisterReply) -> Result<MotorState> {
+        let calibration = self.calibration(motor_id).unwrap_or_default();
+        let state = decode_state(motor_id, reply, calibration, &self.filters, self.feedback_window)?;
+        self.states.publish(&state);
+        Ok(state)
+    }
+
+    /// Make the motor send its state by itself at `rate_hz` (0 = only on request)
+    ///
+    /// The period is rounded to the register's 0.1 ms resolution and read back;
+    /// returns the rate the motor actually uses. Pushed states are picked up by
+    /// [`PushFeedback`].
+    pub fn set_feedback_period(&self, motor_id: u8, rate_hz: f64) -> Result<f64> {
+        self.require(motor_id, Feature::FeedbackPeriod)?;
+        self.during("set feedback period of", motor_id, || {
+            let period = if rate_hz > 0.0 {
+                (10_000.0 / rate_hz).round().clamp(1.0, i16::MAX as f64) as i16
+            } else {
+                0
+            };
+            self.write_register_int16(motor_id, Register::FeedbackPeriod, period)?;
+            let applied = self.read_feedback_period(motor_id)?;
+            if applied != period {
+                return Err(anyhow!("feedback period reads back as {} instead of {} (x 0.1 ms)", applied, period).into());
+            }
+            Ok(period_to_rate(applied))
+        })
+    }
+
+    /// Rate of automatic state feedback, 0 if the motor only answers requests
+    pub fn feedback_rate(&self, motor_id: u8) -> Result<f64> {
+        Ok(period_to_rate(self.read_feedback_period(motor_id)?))
+    }
+
+    /// Select the fields the motor pushes; read back like the feedback period
+    pub fn set_report_content(&self, motor_id: u8, mask: ReportMask) -> Result<()> {
+        mask.validate()?;
+        self.require(motor_id, Feature::ReportContent)?;
+        self.during("set report content of", motor_id, || {
+            self.write_register_int8(motor_id, Register::ReportContent, mask.bits() as i8)?;
+            let applied = self.read_report_content(motor_id)?;
+            if applied != mask {
+                return Err(anyhow!("report content reads back as {} instead of {}", applied, mask).into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Read the fields the motor pushes and decode its replies with them from now on
+    pub fn read_report_content(&self, motor_id: u8) -> Result<ReportMask> {
+        self.require(motor_id, Feature::ReportContent)?;
+        let reply = self.read_registers(motor_id, Register::ReportContent, ValueType::Int8, 1)?;
+        let bits = reply.int(0).ok_or_else(|| error::invalid_response(motor_id, "empty report content reply"))? as u8;
+        let mask = ReportMask::from_bits(bits).map_err(|e| error::invalid_response(motor_id, &e.to_string()))?;
+        self.report_masks.lock().unwrap().insert(motor_id, mask);
+        Ok(mask)
+    }
+
+    /// Content the motor's pushed replies are decoded with, [`ReportMask::STATE`]
+    /// unless set or read otherwise
+    pub fn report_content(&self, motor_id: u8) -> ReportMask {
+        self.report_masks.lock().unwrap().get(&motor_id).copied().unwrap_or_default()
+    }
+
+    /// Report contents shared with components decoding pushed replies off the bus
+    pub(crate) fn report_masks(&self) -> Arc<Mutex<BTreeMap<u8, ReportMask>>> {
+        Arc::clone(&self.report_masks)
+    }
+
+    /// Decode a pushed reply with the motor's report content, `None` if it is
+    /// not a report; missing fields keep their cached values
+    pub(crate) fn ingest_report(
+        &self,
+        motor_id: u8,
+        reply: &protocol::RegisterReply,
+    ) -> Result<Option<(Report, MotorState)>> {
+        let mask = self.report_content(motor_id);
+        if !mask.matches(reply) {
+            return Ok(None);
+        }
+        let report = self.calibration(motor_id).unwrap_or_default().to_joint_report(&mask.decode(motor_id, reply)?);
+        if mask == ReportMask::STATE {
+            return Ok(Some((report, self.ingest_state(motor_id, reply)?)));
+        }
+        let mut state = report.merge_into(self.states.get(motor_id).map(|cached| cached.state).as_ref());
+        if let Some(velocity_rps) = report.velocity_rps {
+            state.acceleration_rps2 = self
+                .filters
+                .lock()
+                .unwrap()
+                .entry(motor_id)
+                .or_insert_with(|| VelocityFilter::new(self.feedback_window))
+                .update(velocity_rps, Instant::now());
+        }
+        self.states.publish(&state);
+        Ok(Some((report, state)))
+    }
+
+    fn read_feedback_period(&self, motor_id: u8) -> Result<i16> {
+        self.require(motor_id, Feature::FeedbackPeriod)?;
+        let reply = self.read_registers(motor_id, Register::FeedbackPeriod, ValueType::Int16, 1)?;
+        Ok(reply.int(0).ok_or_else(|| error::invalid_response(motor_id, "empty feedback period reply"))? as i16)
+    }
+
+    /// Make a motor identify itself by toggling its enable state (status LED blinks)
+    ///
+    /// Refused while the motor is enabled through this controller, since the
+    /// toggling would fight the running control; use [`ping_motor`](Self::ping_motor)
+    /// to check a motor under control.
+    pub fn identify(&self, motor_id: u8) -> Result<()> {
+        self.during("identify", motor_id, || {
+            if self.enabled.lock().unwrap().contains_key(&motor_id) {
+                return Err(anyhow!("motor is under control; disable it first").into());
+            }
+            for _ in 0..3 {
+                self.write_register_int8(motor_id, Register::Mode, 0x0A)?;
+                thread::sleep(Duration::from_millis(200));
+                self.disable_motor(motor_id)?;
+                thread::sleep(Duration::from_millis(200));
+            }
+            Ok(())
+        })
+    }
+
+    /// Set the current position as the motor's zero
+    pub fn set_zero(&self, motor_id: u8) -> Result<()> {
+        self.during("set zero of", motor_id, || self.write_register_int8(motor_id, Register::SetZero, 1))
+    }
+
+    /// Erase the fault history returned by [`read_error_log`](Self::read_error_log)
+    pub fn clear_error_log(&self, motor_id: u8) -> Result<()> {
+        self.require(motor_id, Feature::ErrorLog)?;
+        self.during("clear error log of", motor_id, || {
+            self.write_register_int8(motor_id, Register::ClearErrorLog, 1)
+        })
+    }
+
+    /// Store ID, zero, gains and limits in flash so they survive a power cycle
+    pub fn save_config(&self, motor_id: u8) -> Result<()> {
+        self.during("save config of", motor_id, || {
+            self.write_register_int8(motor_id, Register::SaveConfig, 1)?;
+            thread::sleep(Duration::from_millis(100));
+            Ok(())
+        })
+    }
+
+    /// Change a motor's CAN ID and verify it answers on the new ID
+    pub fn set_motor_id(&self, motor_id: u8, new_id: u8) -> Result<()> {
+        if new_id == 0 || new_id > 127 {
+            return Err(anyhow!("Invalid motor ID {} (valid: 1-127)", new_id).into());
+        }
+        self.during("change ID of", motor_id, || {
+            self.write_register_int8(motor_id, Register::MotorId, new_id as i8)?;
+            thread::sleep(Duration::from_millis(50));
+
+            if !self.ping_motor(new_id)?.is_online {
+                // The motor did not respond on its new ID
+                return Err(error::timeout(new_id));
+            }
+            // The firmware and the joint limits moved with the motor
+            let mut capabilities = self.capabilities.lock().unwrap();
+            if let Some(mut moved) = capabilities.remove(&motor_id) {
+                moved.motor_id = new_id;
+                capabilities.insert(new_id, moved);
+            }
+            let mut limits = self.limits.lock().unwrap();
+            if let Some(moved) = limits.remove(&motor_id) {
+                limits.insert(new_id, moved);
+            }
+            let mut calibrations = self.calibrations.lock().unwrap();
+            if let Some(moved) = calibrations.remove(&motor_id) {
+                calibrations.insert(new_id, moved);
+            }
+            let mut report_masks = self.report_masks.lock().unwrap();
+            if let Some(moved) = report_masks.remove(&motor_id) {
+                report_masks.insert(new_id, moved);
+            }
+            Ok(())
+        })
+    }
+
+    /// Read the actuator's auxiliary digital inputs (limit switches etc.)
+    pub fn read_gpio(&self, motor_id: u8) -> Result<GpioState> {
+        self.require(motor_id, Feature::Gpio)?;
+        self.during("read GPIO of", motor_id, || {
+            let reply = self.read_registers(motor_id, Register::GpioInput, ValueType::Int8, 1)?;
+            let bits = reply.int(0).ok_or_else(|| error::invalid_response(motor_id, "empty GPIO reply"))? as u8;
+            Ok(GpioState { bits })
+        })
+    }
+
+    /// Collect a telemetry sample for a motor
+    pub fn read_telemetry(&self, motor_id: u8) -> Result<MotorTelemetry> {
+        Ok(MotorTelemetry {
+            motor_id,
+            timestamp: Instant::now(),
+            state: self.read_motor_state(motor_id).ok(),
+            gpio: self.read_gpio(motor_id).ok(),
+        })
     }
 
     /// Scan a range of motor IDs
@@ -157,147 +933,432 @@ impl LivelyMotorController {
         Ok(motors)
     }
 
-    /// Enable motor (position mode)
+    /// Enable motor (position mode, [`EnableConfig::position`])
     pub fn enable_motor(&self, motor_id: u8) -> Result<()> {
-        let motor_id = motor_id as u32;
-
-        // Set mode to 0x0A (Position Mode)
-        let mode_data = [0x01, 0x00, 0x0A, 0x50, 0x50, 0x50, 0x50, 0x50];
-        self.send_frame(motor_id, &mode_data)?;
-        thread::sleep(Duration::from_millis(50));
-
-        // Set PID parameters
-        let kp_data = {
-            let mut data = [0x0D, 0x23, 0x00, 0x00, 0x00, 0x00, 0x50, 0x50];
-            let kp = 1.0f32;
-            data[2..6].copy_from_slice(&kp.to_le_bytes());
-            data
-        };
-        self.send_frame(motor_id, &kp_data)?;
-        thread::sleep(Duration::from_millis(20));
-
-        let kd_data = {
-            let mut data = [0x0D, 0x24, 0x00, 0x00, 0x00, 0x00, 0x50, 0x50];
-            let kd = 0.1f32;
-            data[2..6].copy_from_slice(&kd.to_le_bytes());
-            data
+        self.enable_with(motor_id, &EnableConfig::position())
+    }
+
+    /// Enable motor with the mode, gains, torque limit and ramp of `config`
+    ///
+    /// The torque limit is held to the motor's [`Limits`] like any command.
+    pub fn enable_with(&self, motor_id: u8, config: &EnableConfig) -> Result<()> {
+        config.validate()?;
+        let config = &self.limited_enable(motor_id, config)?;
+        let operation = match config.mode {
+            ControlMode::Position => "enable",
+            ControlMode::Velocity => "enable velocity mode of",
         };
-        self.send_frame(motor_id, &kd_data)?;
+        self.during(operation, motor_id, || {
+            if config.ramp.is_some() {
+                // Start without stiffness so the joint doesn't jump to its target
+                self.write_register_float(motor_id, Register::Kp, 0.0)?;
+                self.write_register_float(motor_id, Register::Kd, 0.0)?;
+            }
 
-        Ok(())
+            // Set mode to 0x0A (Position Mode); velocity control runs on top of it
+            self.write_register_int8(motor_id, Register::Mode, 0x0A)?;
+            thread::sleep(Duration::from_millis(50));
+
+            if let Some(limit) = config.torque_limit_nm {
+                self.write_register_float(motor_id, Register::TorqueLimit, limit)?;
+                thread::sleep(Duration::from_millis(20));
+            }
+
+            // Set PID parameters
+            let steps = config.gain_steps();
+            for (i, &(kp, kd)) in steps.iter().enumerate() {
+                if i > 0 {
+                    thread::sleep(enable::RAMP_STEP);
+                }
+                self.write_register_float(motor_id, Register::Kp, kp)?;
+                self.write_register_float(motor_id, Register::Kd, kd)?;
+            }
+            self.enabled.lock().unwrap().insert(motor_id, *config);
+            Ok(())
+        })
     }
 
     /// Disable motor
     pub fn disable_motor(&self, motor_id: u8) -> Result<()> {
-        let data = [0x01, 0x00, 0x00, 0x50, 0x50, 0x50, 0x50, 0x50];
-        self.send_frame(motor_id as u32, &data)
+        self.during("disable", motor_id, || {
+            self.write_register_int8(motor_id, Register::Mode, 0x00)?;
+            self.enabled.lock().unwrap().remove(&motor_id);
+            Ok(())
+        })
     }
 
-    /// Send velocity control command (0xAD)
-    pub fn send_velocity_command(&self, position: i16, velocity: i16, acceleration: i16) -> Result<()> {
-        let mut data = [0u8; 8];
-        data[0..2].copy_from_slice(&position.to_le_bytes());
-        data[2..4].copy_from_slice(&velocity.to_le_bytes());
-        data[4..6].copy_from_slice(&acceleration.to_le_bytes());
-        data[6] = 0x50;
-        data[7] = 0x50;
+    /// Bring `motor_ids` to rest as `mode` says and disable them, returning
+    /// once all are disabled; see [`estop`](crate::estop)
+    ///
+    /// The braking command bypasses software limits. Every motor is disabled
+    /// even if another one fails.
+    pub fn emergency_stop(&self, motor_ids: &[u8], mode: StopMode) -> Result<()> {
+        let mut result = Ok(());
+        let mut longest = Duration::ZERO;
+        for &motor_id in motor_ids {
+            let velocity = estop::motor_velocity(&self.states, &self.calibrations.lock().unwrap(), motor_id);
+            let Some(braking) = mode.braking(velocity) else {
+                continue;
+            };
+            let acceleration = rps2_to_acceleration(braking.acceleration_rps2);
+            let data = self.encoding.velocity_stream(MAGIC_POS, 0, acceleration);
+            match self.send_frame(protocol::stream_id(protocol::VELOCITY_STREAM_ID, motor_id), &data) {
+                Ok(()) => longest = longest.max(braking.duration),
+                Err(e) => result = result.and(Err(e)),
+            }
+        }
+        thread::sleep(longest);
+        for &motor_id in motor_ids {
+            if let Err(e) = self.disable_motor(motor_id) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
 
-        self.send_frame(0x00AD, &data)
+    /// Joint calibrations, shared with the [`Watchdog`] for its braking stops
+    pub(crate) fn calibrations(&self) -> &Arc<Mutex<BTreeMap<u8, Calibration>>> {
+        &self.calibrations
     }
 
-    /// Send angle stream control command (0x90)
-    pub fn send_angle_command(&self, angle: i16, max_vel: i16, max_tqe: i16) -> Result<()> {
-        let mut data = [0u8; 8];
-        data[0..2].copy_from_slice(&angle.to_le_bytes());
-        data[2..4].copy_from_slice(&max_vel.to_le_bytes());
-        data[4..6].copy_from_slice(&max_tqe.to_le_bytes());
-        data[6] = 0x50;
-        data[7] = 0x50;
+    /// Send velocity control command (0xAD) to every motor on the bus
+    pub fn send_velocity_command(&self, position: i16, velocity: i16, acceleration: i16) -> Result<()> {
+        self.send_velocity_command_to(0, position, velocity, acceleration)
+    }
 
-        self.send_frame(0x0090, &data)
+    /// Send angle stream control command (0x90) to every motor on the bus
+    pub fn send_angle_command(&self, angle: i16, max_vel: i16, max_tqe: i16) -> Result<()> {
+        self.send_angle_command_to(0, angle, max_vel, max_tqe)
     }
 
-    /// Enable motor for velocity control
-    pub fn enable_velocity_mode(&self, motor_id: u8) -> Result<()> {
-        let motor_id = motor_id as u32;
-
-        // Set mode to 0x0A (Position Mode)
-        let mode_data = [0x01, 0x00, 0x0A, 0x50, 0x50, 0x50, 0x50, 0x50];
-        self.send_frame(motor_id, &mode_data)?;
-        thread::sleep(Duration::from_millis(50));
-
-        // Set torque limit (register 0x22)
-        let torque_data = {
-            let mut data = [0x0D, 0x22, 0x00, 0x00, 0x00, 0x00, 0x50, 0x50];
-            let torque_limit = 3.0f32;
-            data[2..6].copy_from_slice(&torque_limit.to_le_bytes());
-            data
+    /// Send velocity control command (0xAD) to `motor_id` only
+    pub fn send_velocity_command_to(
+        &self,
+        motor_id: u8,
+        position: i16,
+        velocity: i16,
+        acceleration: i16,
+    ) -> Result<()> {
+        let (position, velocity) = match self.limits_for(motor_id) {
+            Some(limits) => {
+                let position_now = self.states.get(motor_id).map(|cached| cached.state.position_deg);
+                limits.velocity_command(motor_id, position, velocity, position_now)?
+            }
+            None => (position, velocity),
         };
-        self.send_frame(motor_id, &torque_data)?;
-        thread::sleep(Duration::from_millis(20));
-
-        // Set PID parameters for velocity control
-        let kp_data = {
-            let mut data = [0x0D, 0x23, 0x00, 0x00, 0x00, 0x00, 0x50, 0x50];
-            let kp = 2.0f32;
-            data[2..6].copy_from_slice(&kp.to_le_bytes());
-            data
+        let calibration = self.calibration_for(motor_id)?;
+        let data = self.encoding.velocity_stream(
+            calibration.position_to_motor(position),
+            calibration.velocity_to_motor(velocity),
+            calibration.acceleration_to_motor(acceleration),
+        );
+        self.send_frame(protocol::stream_id(protocol::VELOCITY_STREAM_ID, motor_id), &data)
+    }
+
+    /// Send angle stream control command (0x90) to `motor_id` only
+    pub fn send_angle_command_to(&self, motor_id: u8, angle: i16, max_vel: i16, max_tqe: i16) -> Result<()> {
+        let (angle, max_vel, max_tqe) = match self.limits_for(motor_id) {
+            Some(limits) => limits.angle_command(motor_id, angle, max_vel, max_tqe)?,
+            None => (angle, max_vel, max_tqe),
         };
-        self.send_frame(motor_id, &kp_data)?;
+        let calibration = self.calibration_for(motor_id)?;
+        let data = self.encoding.angle_stream(
+            calibration.position_to_motor(angle),
+            calibration.max_velocity_to_motor(max_vel),
+            calibration.max_torque_to_motor(max_tqe),
+        );
+        self.send_frame(protocol::stream_id(protocol::ANGLE_STREAM_ID, motor_id), &data)
+    }
 
-        let kd_data = {
-            let mut data = [0x0D, 0x24, 0x00, 0x00, 0x00, 0x00, 0x50, 0x50];
-            let kd = 0.2f32;
-            data[2..6].copy_from_slice(&kd.to_le_bytes());
-            data
+    /// Send an MIT-style impedance command to `motor_id`: position and velocity
+    /// targets, gains and feed-forward torque in one frame
+    ///
+    /// Values outside [`mit_ranges`](Self::mit_ranges) are clamped; position,
+    /// velocity and feed-forward torque are held to the motor's [`Limits`].
+    /// A value that is not finite fails with [`MotorError::LimitExceeded`]
+    /// and nothing is sent: clamping would turn NaN into the field minimum.
+    /// With a [`Calibration`] all five values are in joint terms, gains
+    /// included.
+    pub fn send_mit_command(
+        &self,
+        motor_id: u8,
+        position_deg: f64,
+        velocity_rps: f64,
+        kp: f64,
+        kd: f64,
+        ff_torque_nm: f64,
+    ) -> Result<()> {
+        let values = [
+            ("position", position_deg),
+            ("velocity", velocity_rps),
+            ("kp", kp),
+            ("kd", kd),
+            ("feed-forward torque", ff_torque_nm),
+        ];
+        if let Some((name, value)) = values.into_iter().find(|(_, value)| !value.is_finite()) {
+            return Err(error::limit_exceeded(motor_id, format!("MIT {} is {}", name, value)));
+        }
+        let (position_deg, velocity_rps, ff_torque_nm) = match self.limits_for(motor_id) {
+            Some(limits) => (
+                limits.position_deg(motor_id, position_deg)?,
+                limits.velocity_rps(motor_id, velocity_rps)?,
+                limits.torque_nm(motor_id, ff_torque_nm)?,
+            ),
+            None => (position_deg, velocity_rps, ff_torque_nm),
+        };
+        let calibration = self.calibration_for(motor_id)?;
+        let command = MitCommand {
+            position_deg: calibration.to_motor_deg(position_deg),
+            velocity_rps: calibration.to_motor_velocity(velocity_rps),
+            kp: calibration.to_motor_gain(kp),
+            kd: calibration.to_motor_gain(kd),
+            torque_nm: calibration.to_motor_torque(ff_torque_nm),
         };
-        self.send_frame(motor_id, &kd_data)?;
+        self.send_frame(protocol::stream_id(protocol::MIT_STREAM_ID, motor_id), &command.encode(&self.mit_ranges))
+    }
+
+    /// Enable motor for velocity control ([`EnableConfig::velocity`])
+    pub fn enable_velocity_mode(&self, motor_id: u8) -> Result<()> {
+        self.enable_with(motor_id, &EnableConfig::velocity())
+    }
+
+    /// Enforce `limits` on every command to `motor_id` from now on
+    pub fn set_limits(&self, motor_id: u8, limits: Limits) -> Result<()> {
+        if motor_id == 0 {
+            return Err(anyhow!("limits are set per motor, not for the broadcast ID 0").into());
+        }
+        limits.validate()?;
+        self.limits.lock().unwrap().insert(motor_id, limits);
+        Ok(())
+    }
+
+    pub fn clear_limits(&self, motor_id: u8) {
+        self.limits.lock().unwrap().remove(&motor_id);
+    }
+
+    pub fn limits(&self, motor_id: u8) -> Option<Limits> {
+        self.limits.lock().unwrap().get(&motor_id).copied()
+    }
 
+    /// Apply `calibration` to every command to `motor_id` and to its parsed
+    /// feedback from now on, see [`calibration`](crate::calibration)
+    pub fn set_calibration(&self, motor_id: u8, calibration: Calibration) -> Result<()> {
+        if motor_id == 0 {
+            return Err(anyhow!("calibrations are set per motor, not for the broadcast ID 0").into());
+        }
+        calibration.validate()?;
+        self.calibrations.lock().unwrap().insert(motor_id, calibration);
         Ok(())
     }
 
+    pub fn clear_calibration(&self, motor_id: u8) {
+        self.calibrations.lock().unwrap().remove(&motor_id);
+    }
+
+    pub fn calibration(&self, motor_id: u8) -> Option<Calibration> {
+        self.calibrations.lock().unwrap().get(&motor_id).copied()
+    }
+
+    fn calibration_for(&self, motor_id: u8) -> Result<Calibration> {
+        Ok(calibration::for_command(&self.calibrations.lock().unwrap(), motor_id)?)
+    }
+
+    /// Limits a command to `motor_id` (or to all motors, for ID 0) must respect
+    fn limits_for(&self, motor_id: u8) -> Option<Limits> {
+        limits::effective(&self.limits.lock().unwrap(), motor_id)
+    }
+
+    /// `config` with its torque limit held to the motor's limits, or set to
+    /// the maximum torque where `config` would keep the motor's own
+    fn limited_enable(&self, motor_id: u8, config: &EnableConfig) -> Result<EnableConfig> {
+        let mut config = *config;
+        if let Some(limits) = self.limits_for(motor_id) {
+            config.torque_limit_nm = match config.torque_limit_nm {
+                Some(torque) => Some(limits.torque_nm(motor_id, torque as f64)? as f32),
+                None => limits.max_torque_nm.map(|max| max as f32),
+            };
+        }
+        Ok(config)
+    }
+
     /// Convert degrees to position integer
     pub fn degrees_to_position(angle_deg: f64) -> i16 {
-        let pos = (angle_deg / 360.0) * FACTOR_POS;
-        pos.max(-32768.0).min(32767.0) as i16
+        degrees_to_position(angle_deg)
     }
 
     /// Convert rad/s to velocity integer
     pub fn rps_to_velocity(velocity_rps: f64) -> i16 {
-        let vel = velocity_rps * FACTOR_VEL;
-        vel.max(-32768.0).min(32767.0) as i16
+        rps_to_velocity(velocity_rps)
     }
 
     /// Convert rad/s² to acceleration integer
     pub fn rps2_to_acceleration(acceleration_rps2: f64) -> i16 {
-        let acc = acceleration_rps2 * FACTOR_ACC;
-        acc.max(-32768.0).min(32767.0) as i16
+        rps2_to_acceleration(acceleration_rps2)
     }
 
     /// Convert Nm to torque integer
     pub fn nm_to_torque(torque_nm: f64) -> i16 {
-        let tqe = torque_nm * FACTOR_TQE;
-        tqe.max(-32768.0).min(32767.0) as i16
+        nm_to_torque(torque_nm)
+    }
+
+    /// Convert position integer to degrees
+    pub fn position_to_degrees(position: i16) -> f64 {
+        position_to_degrees(position)
+    }
+
+    /// Convert velocity integer to r/s
+    pub fn velocity_to_rps(velocity: i16) -> f64 {
+        velocity_to_rps(velocity)
+    }
+
+    /// Convert acceleration integer to r/s²
+    pub fn acceleration_to_rps2(acceleration: i16) -> f64 {
+        acceleration_to_rps2(acceleration)
+    }
+
+    /// Convert torque integer to Nm
+    pub fn torque_to_nm(torque: i16) -> f64 {
+        torque_to_nm(torque)
     }
 }
 
-// Public conversion functions for binary compatibility
+// Public conversion functions for binary compatibility, computed in `Real`
 pub fn degrees_to_position(angle_deg: f64) -> i16 {
-    let pos = (angle_deg / 360.0) * FACTOR_POS;
-    pos.max(-32768.0).min(32767.0) as i16
+    conversions::degrees_to_position(Real::from_f64(angle_deg))
 }
 
 pub fn rps_to_velocity(velocity_rps: f64) -> i16 {
-    let vel = velocity_rps * FACTOR_VEL;
-    vel.max(-32768.0).min(32767.0) as i16
+    conversions::rps_to_velocity(Real::from_f64(velocity_rps))
 }
 
 pub fn rps2_to_acceleration(acceleration_rps2: f64) -> i16 {
-    let acc = acceleration_rps2 * FACTOR_ACC;
-    acc.max(-32768.0).min(32767.0) as i16
+    conversions::rps2_to_acceleration(Real::from_f64(acceleration_rps2))
 }
 
 pub fn nm_to_torque(torque_nm: f64) -> i16 {
-    let tqe = torque_nm * FACTOR_TQE;
-    tqe.max(-32768.0).min(32767.0) as i16
+    conversions::nm_to_torque(Real::from_f64(torque_nm))
+}
+
+// Inverse conversions used by the feedback parser
+
+pub fn position_to_degrees(position: i16) -> f64 {
+    conversions::position_to_degrees::<Real>(position).to_f64()
+}
+
+pub fn velocity_to_rps(velocity: i16) -> f64 {
+    conversions::velocity_to_rps::<Real>(velocity).to_f64()
+}
+
+pub fn acceleration_to_rps2(acceleration: i16) -> f64 {
+    conversions::acceleration_to_rps2::<Real>(acceleration).to_f64()
+}
+
+pub fn torque_to_nm(torque: i16) -> f64 {
+    conversions::torque_to_nm::<Real>(torque).to_f64()
+}
+
+/// Raw arbitration ID of a frame (standard or extended)
+pub(crate) fn raw_id(frame: &CanFrame) -> u32 {
+    match frame.id() {
+        socketcan::Id::Standard(id) => id.as_raw() as u32,
+        socketcan::Id::Extended(id) => id.as_raw(),
+    }
+}
+
+/// Wait for a reply frame originating from `motor_id`
+fn wait_for_reply(rx: &BusSubscription, motor_id: u8, timeout_ms: u64) -> Result<Option<CanFrame>> {
+    let timeout_start = Instant::now();
+    while timeout_start.elapsed().as_millis() < timeout_ms as u128 {
+        if let Some(frame) = rx.recv_timeout(Duration::from_millis(10))? {
+            if is_reply_from(&frame, motor_id) {
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether `frame` was sent by `motor_id`
+fn is_reply_from(frame: &CanFrame, motor_id: u8) -> bool {
+    reply_source(frame) == Some(motor_id)
+}
+
+/// Motor that sent `frame`, `None` for stream commands
+fn reply_source(frame: &CanFrame) -> Option<u8> {
+    // Stream commands of other hosts carry a motor ID in the same byte
+    if protocol::stream_target(raw_id(frame)).is_some() {
+        return None;
+    }
+    // Parse response (same logic as Python/C++ versions)
+    let (source_id, direct_id) = reply_ids(frame);
+    if source_id > 0 && source_id < 128 {
+        Some(source_id)
+    } else {
+        Some(direct_id)
+    }
+}
+
+/// Whether a frame from the pinged motor answers the ping, rather than a
+/// register read or another query issued concurrently
+fn answers_ping(encoding: &EncodingPolicy, data: &[u8]) -> bool {
+    if data.first() == Some(&query::QUERY_REPLY) {
+        return InfoQuery::Identity.matches(data, 0);
+    }
+    // Requests of other nodes (e.g. another ping) echoed on the bus are no answer either
+    let opcode = data.get(encoding.layout().header_offset("opcode"));
+    if opcode.is_some_and(|op| matches!(op & 0xF0, protocol::OP_READ | protocol::OP_WRITE)) {
+        return false;
+    }
+    match encoding.parse_reply(data) {
+        Ok(_) => false,
+        Err(e) => !e.is::<TruncatedFrame>(),
+    }
+}
+
+/// Convert a position/velocity/torque reply into a joint state, feeding the
+/// motor's acceleration estimate
+fn decode_state(
+    motor_id: u8,
+    reply: &protocol::RegisterReply,
+    calibration: Calibration,
+    filters: &Mutex<BTreeMap<u8, VelocityFilter>>,
+    window: Duration,
+) -> Result<MotorState> {
+    let value = |i| {
+        reply
+            .int(i)
+            .map(|v| v as i16)
+            .ok_or_else(|| error::invalid_response(motor_id, "state reply truncated"))
+    };
+
+    let velocity_rps = calibration.to_joint_velocity(velocity_to_rps(value(1)?));
+    let acceleration_rps2 = filters
+        .lock()
+        .unwrap()
+        .entry(motor_id)
+        .or_insert_with(|| VelocityFilter::new(window))
+        .update(velocity_rps, Instant::now());
+    Ok(MotorState {
+        motor_id,
+        position_deg: calibration.to_joint_deg(position_to_degrees(value(0)?)),
+        velocity_rps,
+        torque_nm: calibration.to_joint_torque(torque_to_nm(value(2)?)),
+        acceleration_rps2,
+    })
+}
+
+/// Feedback period register value (0.1 ms) to Hz, 0 when disabled
+fn period_to_rate(period: i16) -> f64 {
+    if period > 0 {
+        10_000.0 / period as f64
+    } else {
+        0.0
+    }
+}
+
+/// Extract (source ID, direct ID) from a reply arbitration ID
+fn reply_ids(frame: &CanFrame) -> (u8, u8) {
+    let id_raw = raw_id(frame);
+    (((id_raw >> 8) & 0x7F) as u8, (id_raw & 0xFF) as u8)
 }
\ No newline at end of file
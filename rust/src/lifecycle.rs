@@ -0,0 +1,327 @@
+//! Motor lifecycle state machine
+//!
+//! Each motor moves through `Offline → Discovered → Configured → Enabled`, with
+//! `Faulted` reachable from anywhere and `Offline` from every state but
+//! `Faulted` once the motor stops answering. [`ManagedMotor`] enforces the
+//! allowed transitions so setpoints can't be sent to an unconfigured motor by
+//! accident.
+
+use crate::{FaultAction, FaultClass, FaultPolicy, LivelyMotorController, Register, SafetyMonitor};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+/// Lifecycle state of a motor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MotorLifecycle {
+    /// Not answering on the bus
+    Offline,
+    /// Answered a ping, not configured yet
+    Discovered,
+    /// Gains and limits written, output disabled
+    Configured,
+    /// Accepting setpoints
+    Enabled,
+    /// Stopped after a fault, must be reset and rediscovered
+    Faulted,
+}
+
+impl MotorLifecycle {
+    /// Whether moving from `self` to `next` is allowed
+    pub fn can_transition_to(self, next: MotorLifecycle) -> bool {
+        use MotorLifecycle::*;
+        matches!(
+            (self, next),
+            (_, Faulted)
+                | (Offline, Discovered)
+                | (Discovered, Offline)
+                | (Discovered, Configured)
+                | (Configured, Configured)
+                | (Configured, Enabled)
+                | (Configured, Offline)
+                | (Enabled, Configured)
+                | (Enabled, Offline)
+                | (Faulted, Offline)
+        )
+    }
+}
+
+impl fmt::Display for MotorLifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MotorLifecycle::Offline => "Offline",
+            MotorLifecycle::Discovered => "Discovered",
+            MotorLifecycle::Configured => "Configured",
+            MotorLifecycle::Enabled => "Enabled",
+            MotorLifecycle::Faulted => "Faulted",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Gains and limits applied in the `Configured` state
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MotorSettings {
+    pub kp: f32,
+    pub kd: f32,
+    pub torque_limit: f32,
+}
+
+impl Default for MotorSettings {
+    fn default() -> Self {
+        Self {
+            kp: 1.0,
+            kd: 0.1,
+            torque_limit: 3.0,
+        }
+    }
+}
+
+impl MotorSettings {
+    /// Write torque limit and gains to a motor
+    pub fn write(&self, controller: &LivelyMotorController, motor_id: u8) -> Result<()> {
+        controller.write_register_float(motor_id, Register::TorqueLimit, self.torque_limit)?;
+        thread::sleep(Duration::from_millis(20));
+        controller.write_register_float(motor_id, Register::Kp, self.kp)?;
+        thread::sleep(Duration::from_millis(20));
+        Ok(controller.write_register_float(motor_id, Register::Kd, self.kd)?)
+    }
+}
+
+/// A motor whose commands are gated by its lifecycle state
+pub struct ManagedMotor<'a> {
+    controller: &'a LivelyMotorController,
+    motor_id: u8,
+    state: MotorLifecycle,
+    settings: Option<MotorSettings>,
+    fault_reason: Option<String>,
+    safety: Option<&'a SafetyMonitor>,
+    /// `Hold` or `Brake` response in effect, with the held angle
+    restriction: Option<(FaultAction, Option<f64>)>,
+}
+
+impl<'a> ManagedMotor<'a> {
+    pub fn new(controller: &'a LivelyMotorController, motor_id: u8) -> Self {
+        Self {
+            controller,
+            motor_id,
+            state: MotorLifecycle::Offline,
+            settings: None,
+            fault_reason: None,
+            safety: None,
+            restriction: None,
+        }
+    }
+
+    /// Pass torque setpoints through a safety monitor's duty envelope and
+    /// answer faults with its fault policy
+    pub fn with_safety(mut self, safety: &'a SafetyMonitor) -> Self {
+        self.safety = Some(safety);
+        self
+    }
+
+    pub fn motor_id(&self) -> u8 {
+        self.motor_id
+    }
+
+    /// Current lifecycle state
+    pub fn state(&self) -> MotorLifecycle {
+        self.state
+    }
+
+    /// Reason of the last fault, if faulted
+    pub fn fault_reason(&self) -> Option<&str> {
+        self.fault_reason.as_deref()
+    }
+
+    /// Settings applied by the last `configure`
+    pub fn settings(&self) -> Option<MotorSettings> {
+        self.settings
+    }
+
+    /// Ping the motor: `Offline → Discovered`, or `Offline` if it is silent
+    ///
+    /// A configured or enabled motor that still answers keeps its state; one
+    /// that dropped off the bus goes `Offline` and must be configured again.
+    /// A faulted motor stays `Faulted` until [`reset`](Self::reset).
+    pub fn discover(&mut self) -> Result<MotorLifecycle> {
+        let online = self.controller.ping_motor(self.motor_id)?.is_online;
+        let next = match (self.state, online) {
+            (MotorLifecycle::Faulted, _) => MotorLifecycle::Faulted,
+            (_, false) => MotorLifecycle::Offline,
+            (MotorLifecycle::Offline, true) => MotorLifecycle::Discovered,
+            (state, true) => state,
+        };
+
+        if next != self.state {
+            self.transition(next)?;
+        }
+        Ok(self.state)
+    }
+
+    /// Write gains and limits: `Discovered/Configured → Configured`
+    pub fn configure(&mut self, settings: MotorSettings) -> Result<()> {
+        self.check(MotorLifecycle::Configured)?;
+        self.write_settings(&settings)?;
+        self.settings = Some(settings);
+        self.transition(MotorLifecycle::Configured)
+    }
+
+    /// Switch to position mode: `Configured → Enabled`
+    pub fn enable(&mut self) -> Result<()> {
+        self.check(MotorLifecycle::Enabled)?;
+        let settings = self.settings.ok_or(anyhow!("motor {} has no settings", self.motor_id))?;
+
+        self.controller.write_register_int8(self.motor_id, Register::Mode, 0x0A)?;
+        thread::sleep(Duration::from_millis(50));
+        self.write_settings(&settings)?;
+        self.transition(MotorLifecycle::Enabled)
+    }
+
+    /// Disable output: `Enabled → Configured`
+    pub fn disable(&mut self) -> Result<()> {
+        self.check(MotorLifecycle::Configured)?;
+        self.controller.disable_motor(self.motor_id)?;
+        self.transition(MotorLifecycle::Configured)
+    }
+
+    /// Stop the motor and enter `Faulted`
+    pub fn fault(&mut self, reason: &str) -> Result<()> {
+        self.fault_reason = Some(reason.to_string());
+        self.transition(MotorLifecycle::Faulted)?;
+        Ok(self.controller.disable_motor(self.motor_id)?)
+    }
+
+    /// Clear a fault: `Faulted → Offline`, the motor must be rediscovered
+    pub fn reset(&mut self) -> Result<()> {
+        self.transition(MotorLifecycle::Offline)?;
+        self.fault_reason = None;
+        self.settings = None;
+        self.restriction = None;
+        Ok(())
+    }
+
+    /// Read the fault register and respond as the safety monitor's policy says
+    ///
+    /// A motor that does not answer counts as [`FaultClass::CommLoss`].
+    /// Without a safety monitor every fault stops the robot. After `Hold`,
+    /// setpoints are replaced by the position at the time of the fault; after
+    /// `Brake` they are rejected, until [`resume`](Self::resume).
+    pub fn check_fault(&mut self) -> Result<FaultAction> {
+        let (class, code) = match self.controller.read_fault(self.motor_id) {
+            Ok(0) => return Ok(FaultAction::Ignore),
+            Ok(code) => (FaultClass::from_code(code).unwrap_or(FaultClass::Other), Some(code)),
+            Err(_) => (FaultClass::CommLoss, None),
+        };
+        let action = match self.safety {
+            Some(safety) => safety.respond_to_fault(self.controller, self.motor_id, class, code)?,
+            None => FaultPolicy::FALLBACK,
+        };
+
+        match action {
+            FaultAction::Ignore | FaultAction::Warn => {}
+            FaultAction::Hold => {
+                let held = self.controller.read_motor_state(self.motor_id).ok().map(|s| s.position_deg);
+                self.restriction = Some((action, held));
+            }
+            FaultAction::Brake => self.restriction = Some((action, None)),
+            FaultAction::Stop => {
+                let reason = match code {
+                    Some(code) => format!("{} fault 0x{:02X}", class, code),
+                    None => class.to_string(),
+                };
+                self.fault(&reason)?;
+            }
+        }
+        Ok(action)
+    }
+
+    /// `Hold` or `Brake` response currently in effect
+    pub fn fault_response(&self) -> Option<FaultAction> {
+        self.restriction.map(|(action, _)| action)
+    }
+
+    /// Accept setpoints again after a `Hold` or `Brake` response
+    pub fn resume(&mut self) -> Result<()> {
+        if let (Some((FaultAction::Brake, _)), Some(settings)) = (self.restriction, self.settings) {
+            self.write_settings(&settings)?;
+        }
+        self.restriction = None;
+        Ok(())
+    }
+
+    /// Send an angle setpoint (requires `Enabled`)
+    pub fn set_angle(&self, angle_deg: f64, max_vel_rps: f64, max_tqe_nm: f64) -> Result<()> {
+        self.require_enabled()?;
+        let angle_deg = match self.restriction {
+            Some((FaultAction::Hold, Some(held_deg))) => held_deg,
+            Some((action, _)) => return Err(self.restricted(action)),
+            None => angle_deg,
+        };
+        let max_tqe_nm = match self.safety {
+            Some(safety) => safety.limit_torque(self.motor_id, max_tqe_nm),
+            None => max_tqe_nm,
+        };
+        Ok(self.controller.send_angle_command_to(
+            self.motor_id,
+            crate::degrees_to_position(angle_deg),
+            crate::rps_to_velocity(max_vel_rps),
+            crate::nm_to_torque(max_tqe_nm),
+        )?)
+    }
+
+    /// Send a velocity setpoint (requires `Enabled`)
+    pub fn set_velocity(&self, velocity_rps: f64, acceleration_rps2: f64) -> Result<()> {
+        self.require_enabled()?;
+        let velocity_rps = match self.restriction {
+            Some((FaultAction::Hold, _)) => 0.0,
+            Some((action, _)) => return Err(self.restricted(action)),
+            None => velocity_rps,
+        };
+        Ok(self.controller.send_velocity_command_to(
+            self.motor_id,
+            crate::MAGIC_POS,
+            crate::rps_to_velocity(velocity_rps),
+            crate::rps2_to_acceleration(acceleration_rps2),
+        )?)
+    }
+
+    fn write_settings(&self, settings: &MotorSettings) -> Result<()> {
+        settings.write(self.controller, self.motor_id)
+    }
+
+    fn restricted(&self, action: FaultAction) -> anyhow::Error {
+        anyhow!("motor {}: setpoints suspended by fault response '{}'", self.motor_id, action)
+    }
+
+    fn require_enabled(&self) -> Result<()> {
+        if self.state != MotorLifecycle::Enabled {
+            return Err(anyhow!(
+                "motor {} is {}, setpoints require Enabled",
+                self.motor_id,
+                self.state
+            ));
+        }
+        Ok(())
+    }
+
+    fn check(&self, next: MotorLifecycle) -> Result<()> {
+        if !self.state.can_transition_to(next) {
+            return Err(anyhow!(
+                "motor {}: transition {} -> {} not allowed",
+                self.motor_id,
+                self.state,
+                next
+            ));
+        }
+        Ok(())
+    }
+
+    fn transition(&mut self, next: MotorLifecycle) -> Result<()> {
+        self.check(next)?;
+        self.state = next;
+        Ok(())
+    }
+}
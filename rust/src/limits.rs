@@ -0,0 +1,240 @@
+//! Software joint limits
+//!
+//! A motor mounted in a joint with mechanical end stops must never be told to
+//! go past them. [`Limits`] set on a controller with
+//! [`set_limits`](crate::LivelyMotorController::set_limits) are checked before
+//! every stream or MIT command to that motor leaves the host: targets out of
+//! range are clamped, or rejected with [`MotorError::LimitExceeded`](crate::MotorError::LimitExceeded), per
+//! [`LimitMode`]. Targets that are not finite numbers are rejected in either mode.
+//!
+//! What can be checked depends on the command: angle commands have their
+//! angle, speed cap and torque cap limited; velocity commands their speed, and
+//! a velocity pushing further past an end stop (judged by the last cached
+//! position) is stopped; MIT commands their position, velocity and
+//! feed-forward torque, while the torque the gains produce is up to the motor.
+//! Commands to every motor at once must respect the limits of all of them.
+
+use crate::error;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What happens to a target outside the limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitMode {
+    /// Send the nearest allowed value instead
+    #[default]
+    Clamp,
+    /// Send nothing and fail
+    Reject,
+}
+
+/// Allowed range of one motor's commands; `None` leaves a quantity unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Limits {
+    #[serde(default)]
+    pub min_pos_deg: Option<f64>,
+    #[serde(default)]
+    pub max_pos_deg: Option<f64>,
+    /// Largest speed in either direction (r/s)
+    #[serde(default)]
+    pub max_vel_rps: Option<f64>,
+    /// Largest torque in either direction (Nm)
+    #[serde(default)]
+    pub max_torque_nm: Option<f64>,
+    #[serde(default)]
+    pub mode: LimitMode,
+}
+
+impl Limits {
+    /// Position range only
+    pub fn position(min_pos_deg: f64, max_pos_deg: f64) -> Self {
+        Self {
+            min_pos_deg: Some(min_pos_deg),
+            max_pos_deg: Some(max_pos_deg),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_velocity(mut self, max_vel_rps: f64) -> Self {
+        self.max_vel_rps = Some(max_vel_rps);
+        self
+    }
+
+    pub fn with_max_torque(mut self, max_torque_nm: f64) -> Self {
+        self.max_torque_nm = Some(max_torque_nm);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: LimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let values = [self.min_pos_deg, self.max_pos_deg, self.max_vel_rps, self.max_torque_nm];
+        if values.iter().flatten().any(|v| !v.is_finite()) {
+            return Err(anyhow!("limits must be finite numbers"));
+        }
+        if let (Some(min), Some(max)) = (self.min_pos_deg, self.max_pos_deg) {
+            if min > max {
+                return Err(anyhow!("minimum position {}° is above the maximum {}°", min, max));
+            }
+        }
+        for (name, value) in [("velocity", self.max_vel_rps), ("torque", self.max_torque_nm)] {
+            if value.is_some_and(|v| v < 0.0) {
+                return Err(anyhow!("maximum {} must not be negative", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Limits that satisfy both `self` and `other`; rejecting wins over clamping
+    pub fn intersect(&self, other: &Limits) -> Limits {
+        let tighter = |a: Option<f64>, b: Option<f64>, pick: fn(f64, f64) -> f64| match (a, b) {
+            (Some(a), Some(b)) => Some(pick(a, b)),
+            (a, b) => a.or(b),
+        };
+        Limits {
+            min_pos_deg: tighter(self.min_pos_deg, other.min_pos_deg, f64::max),
+            max_pos_deg: tighter(self.max_pos_deg, other.max_pos_deg, f64::min),
+            max_vel_rps: tighter(self.max_vel_rps, other.max_vel_rps, f64::min),
+            max_torque_nm: tighter(self.max_torque_nm, other.max_torque_nm, f64::min),
+            mode: if self.mode == LimitMode::Reject { self.mode } else { other.mode },
+        }
+    }
+
+    pub fn position_deg(&self, motor_id: u8, position_deg: f64) -> Result<f64> {
+        self.apply(motor_id, "position", "°", position_deg, self.min_pos_deg, self.max_pos_deg)
+    }
+
+    pub fn velocity_rps(&self, motor_id: u8, velocity_rps: f64) -> Result<f64> {
+        let max = self.max_vel_rps;
+        self.apply(motor_id, "velocity", " r/s", velocity_rps, max.map(|m| -m), max)
+    }
+
+    pub fn torque_nm(&self, motor_id: u8, torque_nm: f64) -> Result<f64> {
+        let max = self.max_torque_nm;
+        self.apply(motor_id, "torque", " Nm", torque_nm, max.map(|m| -m), max)
+    }
+
+    /// Velocity limit, plus no motion further past an end stop the joint is
+    /// already at or beyond (by `position_deg`, if known)
+    pub fn velocity_at(&self, motor_id: u8, velocity_rps: f64, position_deg: Option<f64>) -> Result<f64> {
+        let velocity = self.velocity_rps(motor_id, velocity_rps)?;
+        let Some(position) = position_deg else {
+            return Ok(velocity);
+        };
+        let at_max = self.max_pos_deg.is_some_and(|max| position >= max) && velocity > 0.0;
+        let at_min = self.min_pos_deg.is_some_and(|min| position <= min) && velocity < 0.0;
+        if !(at_max || at_min) {
+            return Ok(velocity);
+        }
+        match self.mode {
+            LimitMode::Clamp => Ok(0.0),
+            LimitMode::Reject => Err(error::limit_exceeded(
+                motor_id,
+                format!("velocity {} r/s drives past the end stop at {:.1}°", velocity, position),
+            )
+            .into()),
+        }
+    }
+
+    fn apply(
+        &self,
+        motor_id: u8,
+        quantity: &str,
+        unit: &str,
+        value: f64,
+        lo: Option<f64>,
+        hi: Option<f64>,
+    ) -> Result<f64> {
+        // NaN compares false with both bounds, so it would pass as in range
+        if !value.is_finite() {
+            let reason = format!("{} {}{} is not a finite number", quantity, value, unit);
+            return Err(error::limit_exceeded(motor_id, reason).into());
+        }
+        let below = lo.filter(|&lo| value < lo);
+        let above = hi.filter(|&hi| value > hi);
+        let Some(bound) = below.or(above) else {
+            return Ok(value);
+        };
+        match self.mode {
+            LimitMode::Clamp => Ok(bound),
+            LimitMode::Reject => {
+                let lo = lo.map_or("-∞".to_string(), |v| v.to_string());
+                let hi = hi.map_or("∞".to_string(), |v| v.to_string());
+                Err(error::limit_exceeded(
+                    motor_id,
+                    format!("{} {}{} is outside [{}, {}]{}", quantity, value, unit, lo, hi, unit),
+                )
+                .into())
+            }
+        }
+    }
+
+    /// Raw angle stream fields within the limits; the speed and torque caps by magnitude
+    ///
+    /// Fields within the limits are passed on as they are, only a limited one is re-encoded.
+    pub(crate) fn angle_command(
+        &self,
+        motor_id: u8,
+        angle: i16,
+        max_vel: i16,
+        max_tqe: i16,
+    ) -> Result<(i16, i16, i16)> {
+        let angle = limit_raw(angle, crate::position_to_degrees, crate::degrees_to_position, |deg| {
+            self.position_deg(motor_id, deg)
+        })?;
+        let max_vel = limit_raw(max_vel, crate::velocity_to_rps, crate::rps_to_velocity, |rps| {
+            self.velocity_rps(motor_id, rps)
+        })?;
+        let max_tqe = limit_raw(max_tqe, crate::torque_to_nm, crate::nm_to_torque, |nm| self.torque_nm(motor_id, nm))?;
+        Ok((angle, max_vel, max_tqe))
+    }
+
+    /// Raw velocity stream position and velocity within the limits;
+    /// [`MAGIC_POS`](crate::MAGIC_POS) and fields within the limits pass unchanged
+    pub(crate) fn velocity_command(
+        &self,
+        motor_id: u8,
+        position: i16,
+        velocity: i16,
+        position_now_deg: Option<f64>,
+    ) -> Result<(i16, i16)> {
+        let position = if position == crate::MAGIC_POS {
+            position
+        } else {
+            limit_raw(position, crate::position_to_degrees, crate::degrees_to_position, |deg| {
+                self.position_deg(motor_id, deg)
+            })?
+        };
+        let velocity = limit_raw(velocity, crate::velocity_to_rps, crate::rps_to_velocity, |rps| {
+            self.velocity_at(motor_id, rps, position_now_deg)
+        })?;
+        Ok((position, velocity))
+    }
+}
+
+/// `raw` as it is if `limit` keeps its decoded value, else the limited value encoded
+fn limit_raw(
+    raw: i16,
+    decode: fn(i16) -> f64,
+    encode: fn(f64) -> i16,
+    limit: impl FnOnce(f64) -> Result<f64>,
+) -> Result<i16> {
+    let value = decode(raw);
+    let limited = limit(value)?;
+    Ok(if limited == value { raw } else { encode(limited) })
+}
+
+/// Limits a command to `motor_id` must respect; ID 0 addresses every motor,
+/// so it gets the intersection of all of them
+pub(crate) fn effective(limits: &BTreeMap<u8, Limits>, motor_id: u8) -> Option<Limits> {
+    if motor_id == 0 {
+        limits.values().copied().reduce(|a, b| a.intersect(&b))
+    } else {
+        limits.get(&motor_id).copied()
+    }
+}
@@ -0,0 +1,163 @@
+//! Torque distribution for motors sharing one load
+//!
+//! Joints driven by several motors (e.g. the two hip motors of a parallel
+//! mechanism) are overconstrained: any split of the commanded effort moves the
+//! joint. [`LoadShare`] splits it in proportion to what each motor can still
+//! deliver, its torque envelope budget and thermal headroom, so a motor that
+//! is running hot or has used up its peak budget hands load to its partners
+//! before it saturates. Weights are recomputed on every [`distribute`](LoadShare::distribute).
+
+use crate::safety::{DutyTracker, TorqueEnvelope};
+use std::time::Instant;
+
+/// Torque derating over motor temperature
+///
+/// Full torque up to `derate_start_c`, falling linearly to zero at `max_c`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalDerating {
+    pub derate_start_c: f64,
+    pub max_c: f64,
+}
+
+impl ThermalDerating {
+    pub fn new(derate_start_c: f64, max_c: f64) -> Self {
+        Self {
+            derate_start_c: derate_start_c.min(max_c),
+            max_c,
+        }
+    }
+
+    /// Torque scale at `temperature_c`
+    pub fn scale(&self, temperature_c: f64) -> f64 {
+        let span = self.max_c - self.derate_start_c;
+        if span <= 0.0 {
+            return if temperature_c < self.max_c { 1.0 } else { 0.0 };
+        }
+        (1.0 - (temperature_c - self.derate_start_c) / span).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for ThermalDerating {
+    /// Derate from 60 °C, no torque at 85 °C
+    fn default() -> Self {
+        Self::new(60.0, 85.0)
+    }
+}
+
+/// Torque command of one motor in a split
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShareCommand {
+    pub motor_id: u8,
+    /// Share of the effort this cycle (0..=1)
+    pub weight: f64,
+    pub torque_nm: f64,
+}
+
+/// Result of splitting one effort command
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorqueSplit {
+    pub commands: Vec<ShareCommand>,
+    /// Sum of the motor torques (Nm)
+    pub delivered_nm: f64,
+    /// Whether the group could not deliver the requested effort
+    pub saturated: bool,
+}
+
+struct Member {
+    motor_id: u8,
+    tracker: DutyTracker,
+    temperature_c: Option<f64>,
+}
+
+/// Splits a joint effort across motors sharing the load
+pub struct LoadShare {
+    members: Vec<Member>,
+    derating: ThermalDerating,
+}
+
+impl LoadShare {
+    /// Share the load between `motors` with their torque envelopes
+    pub fn new(motors: &[(u8, TorqueEnvelope)]) -> Self {
+        Self {
+            members: motors
+                .iter()
+                .map(|&(motor_id, envelope)| Member {
+                    motor_id,
+                    tracker: DutyTracker::new(envelope),
+                    temperature_c: None,
+                })
+                .collect(),
+            derating: ThermalDerating::default(),
+        }
+    }
+
+    pub fn with_thermal_derating(mut self, derating: ThermalDerating) -> Self {
+        self.derating = derating;
+        self
+    }
+
+    /// Feed a temperature reading; returns false for an unknown motor
+    pub fn set_temperature(&mut self, motor_id: u8, temperature_c: f64) -> bool {
+        match self.members.iter_mut().find(|m| m.motor_id == motor_id) {
+            Some(member) => {
+                member.temperature_c = Some(temperature_c);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Torque each motor can deliver now (Nm)
+    ///
+    /// Between the continuous and the peak torque in proportion to the peak
+    /// budget left (only the continuous torque once it is used up), scaled by
+    /// thermal derating.
+    pub fn capacities(&self) -> Vec<(u8, f64)> {
+        self.members
+            .iter()
+            .map(|m| (m.motor_id, self.capacity(m)))
+            .collect()
+    }
+
+    /// Split `torque_nm` across the motors and account for it in their budgets
+    ///
+    /// Motor torques are proportional to their capacities, so every motor
+    /// reaches its limit at the same time and none saturates while another
+    /// still has headroom.
+    pub fn distribute(&mut self, torque_nm: f64, now: Instant) -> TorqueSplit {
+        let capacities: Vec<f64> = self.members.iter().map(|m| self.capacity(m)).collect();
+        let total: f64 = capacities.iter().sum();
+        let delivered_abs = torque_nm.abs().min(total);
+
+        let mut commands = Vec::with_capacity(self.members.len());
+        for (member, capacity) in self.members.iter_mut().zip(capacities) {
+            let weight = if total > 0.0 { capacity / total } else { 0.0 };
+            let (torque_nm, _) = member
+                .tracker
+                .update((delivered_abs * weight).copysign(torque_nm), now);
+            commands.push(ShareCommand {
+                motor_id: member.motor_id,
+                weight,
+                torque_nm,
+            });
+        }
+
+        TorqueSplit {
+            delivered_nm: commands.iter().map(|c| c.torque_nm).sum(),
+            saturated: torque_nm.abs() > total,
+            commands,
+        }
+    }
+
+    fn capacity(&self, member: &Member) -> f64 {
+        let envelope = member.tracker.envelope();
+        let torque = if member.tracker.is_limited() {
+            envelope.continuous_nm
+        } else {
+            let headroom = envelope.peak_nm - envelope.continuous_nm;
+            envelope.continuous_nm + headroom * (1.0 - member.tracker.budget_used())
+        };
+        let thermal = member.temperature_c.map_or(1.0, |t| self.derating.scale(t));
+        torque * thermal
+    }
+}
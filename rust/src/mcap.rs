@@ -0,0 +1,118 @@
+//! Minimal MCAP writer
+//!
+//! Streams schema, channel and message records to an unchunked, unindexed
+//! MCAP file as read by Foxglove and PlotJuggler. Messages are written as
+//! they come, so a long recording does not need to fit in memory; readers
+//! scan the data section instead of using a summary. No CRCs are computed
+//! (a zero CRC means "not set").
+
+use anyhow::{Result, anyhow};
+use std::io::Write;
+
+const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_DATA_END: u8 = 0x0F;
+
+/// Writes MCAP records to `W`; call [`finish`](Self::finish) to end the file
+pub struct McapWriter<W: Write> {
+    out: W,
+    next_schema: u16,
+    next_channel: u16,
+    /// Sequence number of the next message, per channel
+    sequences: Vec<u32>,
+}
+
+impl<W: Write> McapWriter<W> {
+    /// Start a file with the given profile (e.g. `""` or `"ros2"`)
+    pub fn new(mut out: W, profile: &str) -> Result<Self> {
+        out.write_all(MAGIC)?;
+        let mut header = Vec::new();
+        put_str(&mut header, profile);
+        put_str(&mut header, "livelybot-motor-control");
+        write_record(&mut out, OP_HEADER, &header)?;
+        Ok(Self {
+            out,
+            next_schema: 1,
+            next_channel: 0,
+            sequences: Vec::new(),
+        })
+    }
+
+    /// Add a schema (e.g. encoding `"jsonschema"`), returns its ID
+    pub fn add_schema(&mut self, name: &str, encoding: &str, data: &[u8]) -> Result<u16> {
+        let id = self.next_schema;
+        self.next_schema += 1;
+        let mut record = id.to_le_bytes().to_vec();
+        put_str(&mut record, name);
+        put_str(&mut record, encoding);
+        put_bytes(&mut record, data);
+        write_record(&mut self.out, OP_SCHEMA, &record)?;
+        Ok(id)
+    }
+
+    /// Add a channel with messages in `message_encoding` (e.g. `"json"`), returns its ID
+    pub fn add_channel(&mut self, schema_id: u16, topic: &str, message_encoding: &str) -> Result<u16> {
+        let id = self.next_channel;
+        self.next_channel += 1;
+        let mut record = id.to_le_bytes().to_vec();
+        record.extend_from_slice(&schema_id.to_le_bytes());
+        put_str(&mut record, topic);
+        put_str(&mut record, message_encoding);
+        // Empty metadata map
+        record.extend_from_slice(&0u32.to_le_bytes());
+        write_record(&mut self.out, OP_CHANNEL, &record)?;
+        self.sequences.push(0);
+        Ok(id)
+    }
+
+    /// Write a message on `channel_id`, logged at `log_time_ns` since the Unix epoch
+    pub fn write_message(&mut self, channel_id: u16, log_time_ns: u64, data: &[u8]) -> Result<()> {
+        let sequence = self
+            .sequences
+            .get_mut(channel_id as usize)
+            .ok_or_else(|| anyhow!("unknown MCAP channel {}", channel_id))?;
+        let mut record = Vec::with_capacity(22 + data.len());
+        record.extend_from_slice(&channel_id.to_le_bytes());
+        record.extend_from_slice(&sequence.to_le_bytes());
+        record.extend_from_slice(&log_time_ns.to_le_bytes());
+        record.extend_from_slice(&log_time_ns.to_le_bytes());
+        record.extend_from_slice(data);
+        *sequence = sequence.wrapping_add(1);
+        write_record(&mut self.out, OP_MESSAGE, &record)
+    }
+
+    /// End the data section and write the footer; returns the output
+    pub fn finish(mut self) -> Result<W> {
+        write_record(&mut self.out, OP_DATA_END, &0u32.to_le_bytes())?;
+        // No summary section
+        let mut footer = Vec::with_capacity(20);
+        footer.extend_from_slice(&0u64.to_le_bytes());
+        footer.extend_from_slice(&0u64.to_le_bytes());
+        footer.extend_from_slice(&0u32.to_le_bytes());
+        write_record(&mut self.out, OP_FOOTER, &footer)?;
+        self.out.write_all(MAGIC)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn write_record<W: Write>(out: &mut W, opcode: u8, content: &[u8]) -> Result<()> {
+    out.write_all(&[opcode])?;
+    out.write_all(&(content.len() as u64).to_le_bytes())?;
+    out.write_all(content)?;
+    Ok(())
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    put_bytes(buf, s.as_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
@@ -0,0 +1,183 @@
+//! Minimal ASAM MDF 4.10 writer
+//!
+//! Writes one data group with a single channel group: a float64 time master
+//! channel followed by float64 value channels, as loaded by CANape, vSignalyzer
+//! and asammdf. Only sorted, uncompressed data is produced. Session properties
+//! go into the file history comment as `<common_properties>`.
+
+use anyhow::Result;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A value channel: name and physical unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mdf4Channel {
+    pub name: String,
+    pub unit: String,
+}
+
+impl Mdf4Channel {
+    pub fn new(name: &str, unit: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            unit: unit.to_string(),
+        }
+    }
+}
+
+const HD_OFFSET: u64 = 64;
+const HD_LEN: u64 = 24 + 6 * 8 + 32;
+
+/// Write `records` (time in seconds, then one value per channel) to an MDF4 file
+pub fn write_mdf4<P: AsRef<Path>>(
+    path: P,
+    channels: &[Mdf4Channel],
+    records: &[(f64, Vec<f64>)],
+    properties: &[(String, String)],
+) -> Result<()> {
+    let mut buf = Vec::new();
+    write_id_block(&mut buf);
+    buf.resize((HD_OFFSET + HD_LEN) as usize, 0);
+
+    let record_size = 8 * (channels.len() as u32 + 1);
+
+    // Channels are linked as a list, so write them back to front
+    let mut next_cn = 0u64;
+    for (index, channel) in channels.iter().enumerate().rev() {
+        let name = write_text(&mut buf, b"##TX", &channel.name);
+        let unit = write_text(&mut buf, b"##TX", &channel.unit);
+        next_cn = write_cn(&mut buf, next_cn, name, unit, false, 8 * (index as u32 + 1));
+    }
+    let time_name = write_text(&mut buf, b"##TX", "time");
+    let time_unit = write_text(&mut buf, b"##TX", "s");
+    let first_cn = write_cn(&mut buf, next_cn, time_name, time_unit, true, 0);
+
+    let acq_name = write_text(&mut buf, b"##TX", "livelybot recorder");
+    let cg = write_cg(&mut buf, first_cn, acq_name, records.len() as u64, record_size);
+
+    let mut data = Vec::with_capacity(records.len() * record_size as usize);
+    for (time, values) in records {
+        data.extend_from_slice(&time.to_le_bytes());
+        for i in 0..channels.len() {
+            let value = values.get(i).copied().unwrap_or(f64::NAN);
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    let dt = write_block(&mut buf, b"##DT", &[], &data);
+
+    let dg = write_block(&mut buf, b"##DG", &[0, cg, dt, 0], &[0u8; 8]);
+
+    let common_properties: String = properties
+        .iter()
+        .map(|(name, value)| format!("<e name=\"{}\">{}</e>", xml_escape(name), xml_escape(value)))
+        .collect();
+    let fh_comment = write_text(
+        &mut buf,
+        b"##MD",
+        &format!(
+            "<FHcomment><TX>created</TX><tool_id>livelybot-motor-control</tool_id>\
+             <tool_vendor>LivelyBot</tool_vendor><tool_version>{}</tool_version>\
+             <common_properties>{}</common_properties></FHcomment>",
+            env!("CARGO_PKG_VERSION"),
+            common_properties
+        ),
+    );
+    let now_ns = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut fh_data = Vec::new();
+    fh_data.extend_from_slice(&now_ns.to_le_bytes());
+    fh_data.extend_from_slice(&[0u8; 8]); // tz/dst offsets, flags, reserved
+    let fh = write_block(&mut buf, b"##FH", &[0, fh_comment], &fh_data);
+
+    // Header block at its fixed position
+    let mut hd = Vec::new();
+    hd.extend_from_slice(&now_ns.to_le_bytes());
+    hd.extend_from_slice(&[0u8; 8]); // tz/dst offsets, time flags/class, flags, reserved
+    hd.extend_from_slice(&0f64.to_le_bytes()); // start angle
+    hd.extend_from_slice(&0f64.to_le_bytes()); // start distance
+    let hd_block = encode_block(b"##HD", &[dg, fh, 0, 0, 0, 0], &hd);
+    buf[HD_OFFSET as usize..(HD_OFFSET + HD_LEN) as usize].copy_from_slice(&hd_block);
+
+    File::create(path)?.write_all(&buf)?;
+    Ok(())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_id_block(buf: &mut Vec<u8>) {
+    buf.extend_from_slice(b"MDF     ");
+    buf.extend_from_slice(b"4.10    ");
+    buf.extend_from_slice(b"livelybt");
+    buf.extend_from_slice(&[0u8; 4]);
+    buf.extend_from_slice(&410u16.to_le_bytes());
+    buf.extend_from_slice(&[0u8; 30]);
+    buf.extend_from_slice(&[0u8; 4]); // unfinalized flags
+}
+
+fn encode_block(id: &[u8; 4], links: &[u64], data: &[u8]) -> Vec<u8> {
+    let length = 24 + 8 * links.len() as u64 + data.len() as u64;
+    let mut block = Vec::with_capacity(length as usize);
+    block.extend_from_slice(id);
+    block.extend_from_slice(&[0u8; 4]);
+    block.extend_from_slice(&length.to_le_bytes());
+    block.extend_from_slice(&(links.len() as u64).to_le_bytes());
+    for link in links {
+        block.extend_from_slice(&link.to_le_bytes());
+    }
+    block.extend_from_slice(data);
+    block
+}
+
+/// Append a block at the next 8-byte aligned offset and return that offset
+fn write_block(buf: &mut Vec<u8>, id: &[u8; 4], links: &[u64], data: &[u8]) -> u64 {
+    buf.resize(buf.len().next_multiple_of(8), 0);
+    let offset = buf.len() as u64;
+    buf.extend_from_slice(&encode_block(id, links, data));
+    offset
+}
+
+/// TX / MD block: zero terminated UTF-8, padded to 8 bytes
+fn write_text(buf: &mut Vec<u8>, id: &[u8; 4], text: &str) -> u64 {
+    let mut data = text.as_bytes().to_vec();
+    data.push(0);
+    data.resize(data.len().next_multiple_of(8), 0);
+    write_block(buf, id, &[], &data)
+}
+
+fn write_cn(buf: &mut Vec<u8>, next: u64, name: u64, unit: u64, master: bool, byte_offset: u32) -> u64 {
+    let mut data = Vec::new();
+    data.push(if master { 2 } else { 0 }); // cn_type: master / fixed length
+    data.push(if master { 1 } else { 0 }); // cn_sync_type: time
+    data.push(4); // cn_data_type: IEEE 754 float, little endian
+    data.push(0); // cn_bit_offset
+    data.extend_from_slice(&byte_offset.to_le_bytes());
+    data.extend_from_slice(&64u32.to_le_bytes()); // cn_bit_count
+    data.extend_from_slice(&0u32.to_le_bytes()); // cn_flags
+    data.extend_from_slice(&0u32.to_le_bytes()); // cn_inval_bit_pos
+    data.push(0); // cn_precision
+    data.push(0); // reserved
+    data.extend_from_slice(&0u16.to_le_bytes()); // cn_attachment_count
+    data.extend_from_slice(&[0u8; 6 * 8]); // value range and limits
+    write_block(buf, b"##CN", &[next, 0, name, 0, 0, 0, unit, 0], &data)
+}
+
+fn write_cg(buf: &mut Vec<u8>, first_cn: u64, acq_name: u64, cycles: u64, record_size: u32) -> u64 {
+    let mut data = Vec::new();
+    data.extend_from_slice(&0u64.to_le_bytes()); // cg_record_id
+    data.extend_from_slice(&cycles.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes()); // cg_flags
+    data.extend_from_slice(&0u16.to_le_bytes()); // cg_path_separator
+    data.extend_from_slice(&[0u8; 4]);
+    data.extend_from_slice(&record_size.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes()); // cg_inval_bytes
+    write_block(buf, b"##CG", &[0, first_cn, acq_name, 0, 0, 0], &data)
+}
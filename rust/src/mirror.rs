@@ -0,0 +1,150 @@
+//! Master/slave setpoint mirroring
+//!
+//! Streams the measured position of a master motor as the setpoint of a slave
+//! (`slave = scale * master + offset`), for symmetric limb mirroring and
+//! teleoperation. In bilateral mode the master is in turn pulled towards the
+//! slave's measured position with a low torque limit, so the operator feels
+//! when the slave is blocked. The delay from reading the master to commanding
+//! the slave is measured every cycle.
+
+use crate::{ControlLoop, LivelyMotorController};
+use anyhow::Result;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+/// Mapping from a master joint to a slave joint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MirrorLink {
+    pub master_id: u8,
+    pub slave_id: u8,
+    /// -1.0 mirrors a limb on the opposite side
+    pub scale: f64,
+    pub offset_deg: f64,
+    pub max_vel_rps: f64,
+    pub max_torque_nm: f64,
+    /// Torque limit pulling the master towards the slave, `None` = unilateral
+    pub feedback_torque_nm: Option<f64>,
+}
+
+impl MirrorLink {
+    pub fn new(master_id: u8, slave_id: u8) -> Self {
+        Self {
+            master_id,
+            slave_id,
+            scale: 1.0,
+            offset_deg: 0.0,
+            max_vel_rps: 5.0,
+            max_torque_nm: 3.0,
+            feedback_torque_nm: None,
+        }
+    }
+
+    pub fn scaled(mut self, scale: f64, offset_deg: f64) -> Self {
+        self.scale = scale;
+        self.offset_deg = offset_deg;
+        self
+    }
+
+    /// Enable force feedback to the master with the given torque limit
+    pub fn bilateral(mut self, feedback_torque_nm: f64) -> Self {
+        self.feedback_torque_nm = Some(feedback_torque_nm);
+        self
+    }
+
+    /// Slave setpoint for a master angle
+    pub fn slave_angle(&self, master_deg: f64) -> f64 {
+        self.scale * master_deg + self.offset_deg
+    }
+
+    /// Master angle corresponding to a slave angle
+    pub fn master_angle(&self, slave_deg: f64) -> f64 {
+        if self.scale == 0.0 {
+            return 0.0;
+        }
+        (slave_deg - self.offset_deg) / self.scale
+    }
+}
+
+/// Loop delay statistics (master read → slave command sent)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MirrorStats {
+    pub cycles: u64,
+    pub last_delay: Duration,
+    pub max_delay: Duration,
+    total_delay: Duration,
+}
+
+impl MirrorStats {
+    pub fn mean_delay(&self) -> Duration {
+        if self.cycles == 0 {
+            return Duration::ZERO;
+        }
+        self.total_delay / self.cycles as u32
+    }
+
+    fn record(&mut self, delay: Duration) {
+        self.cycles += 1;
+        self.last_delay = delay;
+        self.max_delay = self.max_delay.max(delay);
+        self.total_delay += delay;
+    }
+}
+
+/// Runs a set of mirror links
+pub struct Mirror<'a> {
+    controller: &'a LivelyMotorController,
+    links: Vec<MirrorLink>,
+    stats: MirrorStats,
+}
+
+impl<'a> Mirror<'a> {
+    pub fn new(controller: &'a LivelyMotorController, links: &[MirrorLink]) -> Self {
+        Self {
+            controller,
+            links: links.to_vec(),
+            stats: MirrorStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> MirrorStats {
+        self.stats
+    }
+
+    /// Run one mirroring cycle for every link
+    pub fn step(&mut self) -> Result<()> {
+        for link in &self.links {
+            let read_at = Instant::now();
+            let master = self.controller.read_motor_state(link.master_id)?;
+
+            self.controller.send_angle_command_to(
+                link.slave_id,
+                crate::degrees_to_position(link.slave_angle(master.position_deg)),
+                crate::rps_to_velocity(link.max_vel_rps),
+                crate::nm_to_torque(link.max_torque_nm),
+            )?;
+            self.stats.record(read_at.elapsed());
+
+            if let Some(feedback_nm) = link.feedback_torque_nm {
+                let slave = self.controller.read_motor_state(link.slave_id)?;
+                self.controller.send_angle_command_to(
+                    link.master_id,
+                    crate::degrees_to_position(link.master_angle(slave.position_deg)),
+                    crate::rps_to_velocity(link.max_vel_rps),
+                    crate::nm_to_torque(feedback_nm),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Mirror at `rate_hz` until `running` is cleared; `observe` sees the stats
+    pub fn run<F>(&mut self, rate_hz: f64, running: &AtomicBool, mut observe: F) -> Result<()>
+    where
+        F: FnMut(&MirrorStats) -> Result<bool>,
+    {
+        ControlLoop::new(rate_hz).run(running, |_| {
+            self.step()?;
+            observe(&self.stats)
+        })
+    }
+}
@@ -0,0 +1,125 @@
+//! MIT-style impedance command
+//!
+//! One frame carries the target position and velocity, the stiffness and
+//! damping gains and a feed-forward torque, so a whole-body controller can
+//! change gains every cycle without register writes. The frame goes to
+//! `(motor_id << 8) | 0xB0` ([`MIT_STREAM_ID`](crate::protocol::MIT_STREAM_ID),
+//! always addressed) and packs the values as unsigned bit fields, most
+//! significant bit first, as in the MIT mini cheetah protocol:
+//!
+//! | bits    | field            |
+//! |---------|------------------|
+//! | 0..16   | position         |
+//! | 16..28  | velocity         |
+//! | 28..40  | kp               |
+//! | 40..52  | kd               |
+//! | 52..64  | feed-forward torque |
+//!
+//! Each value is clamped to its [`MitRanges`] range and mapped linearly onto
+//! its field: 0 is the minimum, all ones the maximum. Firmware that does not
+//! implement the frame ignores it.
+
+use crate::protocol::TruncatedFrame;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Field widths in bits, in frame order
+const BITS: [u32; 5] = [16, 12, 12, 12, 12];
+
+/// Value ranges the firmware maps the bit fields onto
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MitRanges {
+    /// Position range is ±this
+    pub position_deg: f64,
+    /// Velocity range is ±this
+    pub velocity_rps: f64,
+    /// Kp range is 0..this, in the units of the Kp register
+    pub kp: f64,
+    /// Kd range is 0..this, in the units of the Kd register
+    pub kd: f64,
+    /// Torque range is ±this
+    pub torque_nm: f64,
+}
+
+impl Default for MitRanges {
+    fn default() -> Self {
+        Self {
+            position_deg: 720.0,
+            velocity_rps: 8.0,
+            kp: 500.0,
+            kd: 5.0,
+            torque_nm: 20.0,
+        }
+    }
+}
+
+impl MitRanges {
+    /// `(min, max)` of every field, in frame order
+    fn bounds(&self) -> [(f64, f64); 5] {
+        [
+            (-self.position_deg, self.position_deg),
+            (-self.velocity_rps, self.velocity_rps),
+            (0.0, self.kp),
+            (0.0, self.kd),
+            (-self.torque_nm, self.torque_nm),
+        ]
+    }
+}
+
+/// Setpoint of one MIT frame
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct MitCommand {
+    pub position_deg: f64,
+    pub velocity_rps: f64,
+    pub kp: f64,
+    pub kd: f64,
+    /// Added to the output of the position/velocity loop
+    pub torque_nm: f64,
+}
+
+impl MitCommand {
+    fn values(&self) -> [f64; 5] {
+        [self.position_deg, self.velocity_rps, self.kp, self.kd, self.torque_nm]
+    }
+
+    /// Pack into a frame payload
+    pub fn encode(&self, ranges: &MitRanges) -> [u8; 8] {
+        let mut packed = 0u64;
+        for ((value, (min, max)), bits) in self.values().into_iter().zip(ranges.bounds()).zip(BITS) {
+            packed = (packed << bits) | to_field(value, min, max, bits);
+        }
+        packed.to_be_bytes()
+    }
+
+    /// Unpack a frame payload; values are quantized to the field resolution
+    pub fn decode(data: &[u8], ranges: &MitRanges) -> Result<Self> {
+        TruncatedFrame::check("MIT command", data, 8)?;
+        let mut packed = u64::from_be_bytes(data[..8].try_into()?);
+        let mut values = [0.0; 5];
+        for i in (0..5).rev() {
+            let (min, max) = ranges.bounds()[i];
+            values[i] = from_field(packed & ((1 << BITS[i]) - 1), min, max, BITS[i]);
+            packed >>= BITS[i];
+        }
+        let [position_deg, velocity_rps, kp, kd, torque_nm] = values;
+        Ok(Self {
+            position_deg,
+            velocity_rps,
+            kp,
+            kd,
+            torque_nm,
+        })
+    }
+}
+
+/// Clamp `value` to `min..=max` and map it onto a `bits`-wide field
+fn to_field(value: f64, min: f64, max: f64, bits: u32) -> u64 {
+    let full = ((1u64 << bits) - 1) as f64;
+    let x = if max > min { (value.clamp(min, max) - min) / (max - min) } else { 0.0 };
+    (x * full).round() as u64
+}
+
+fn from_field(field: u64, min: f64, max: f64, bits: u32) -> f64 {
+    let full = ((1u64 << bits) - 1) as f64;
+    min + field as f64 / full * (max - min)
+}
@@ -0,0 +1,594 @@
+//! Simulated motors
+//!
+//! [`MockTransport`] answers on a [`CanBus`](crate::CanBus) like a bus of
+//! real motors, so controllers, groups and tools can be tested in CI without
+//! a CAN interface:
+//!
+//! ```no_run
+//! use livelybot_motor_control::{LivelyMotorController, MockTransport, SimMotor};
+//!
+//! let mock = MockTransport::new().with_motor(1, SimMotor::default());
+//! let controller = LivelyMotorController::with_transport("mock", mock.clone());
+//! assert!(controller.ping_motor(1).unwrap().is_online);
+//! ```
+//!
+//! Each motor answers pings, info queries and register reads, applies
+//! register writes and follows stream commands through simple second-order
+//! dynamics: a rigid inertia with viscous damping, driven by a critically
+//! damped position loop of the configured bandwidth (angle stream), a
+//! velocity loop with the commanded acceleration limit (velocity stream) or
+//! the impedance law of an MIT command. The Kp and Kd registers are stored
+//! and read back but do not change the loop. Time advances with the wall
+//! clock in 1 ms steps whenever the bus sends or receives.
+
+use crate::layout;
+use crate::protocol::{self, EncodingPolicy, Register, ValueType};
+use crate::query::{InfoQuery, QUERY_REPLY};
+use crate::report::{ReportField, ReportMask};
+use crate::telemetry_recorder::SentCommand;
+use crate::transport::{CanTransport, RawFrame};
+use crate::{MitRanges, MotorState, Scaling};
+use std::collections::{BTreeMap, VecDeque};
+use std::f64::consts::TAU;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Integration step
+const STEP: Duration = Duration::from_millis(1);
+
+/// Longest stretch of time integrated at once; a transport left idle longer
+/// skips the rest
+const MAX_CATCH_UP: Duration = Duration::from_secs(10);
+
+/// Physical and identity parameters of a simulated motor
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimMotor {
+    /// Name returned by the ping (3 ASCII characters)
+    pub name: String,
+    /// Hardware version returned by the ping (4 ASCII characters)
+    pub hardware_version: String,
+    pub serial_number: u32,
+    /// Rotor plus load inertia, kg·m²
+    pub inertia: f64,
+    /// Viscous damping, Nm·s/rad
+    pub damping: f64,
+    /// Natural frequency of the position and velocity loops, rad/s
+    pub bandwidth: f64,
+    /// Torque per Q-axis amp, Nm/A
+    pub torque_constant: f64,
+    pub voltage: f64,
+    pub temperature_c: f64,
+    /// Scaling reported in the scale registers, `None` for firmware without them
+    pub scaling: Option<Scaling>,
+}
+
+impl Default for SimMotor {
+    fn default() -> Self {
+        Self {
+            name: "SIM".to_string(),
+            hardware_version: "1.00".to_string(),
+            serial_number: 0,
+            inertia: 0.01,
+            damping: 0.05,
+            bandwidth: 30.0,
+            torque_constant: 0.1,
+            voltage: 24.0,
+            temperature_c: 35.0,
+            scaling: Some(Scaling::EXPECTED),
+        }
+    }
+}
+
+impl SimMotor {
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_serial_number(mut self, serial_number: u32) -> Self {
+        self.serial_number = serial_number;
+        self
+    }
+
+    pub fn with_inertia(mut self, inertia: f64) -> Self {
+        self.inertia = inertia;
+        self
+    }
+
+    pub fn with_damping(mut self, damping: f64) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    pub fn with_bandwidth(mut self, bandwidth: f64) -> Self {
+        self.bandwidth = bandwidth;
+        self
+    }
+
+    pub fn with_scaling(mut self, scaling: Option<Scaling>) -> Self {
+        self.scaling = scaling;
+        self
+    }
+}
+
+/// A CAN transport backed by simulated motors instead of a bus
+///
+/// Clones share the same motors, so a test can keep one to inspect the true
+/// state or inject faults while a controller owns another.
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    shared: Arc<(Mutex<Sim>, Condvar)>,
+}
+
+impl MockTransport {
+    /// A bus without motors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a motor answering on `motor_id`
+    pub fn with_motor(self, motor_id: u8, motor: SimMotor) -> Self {
+        self.add_motor(motor_id, motor);
+        self
+    }
+
+    /// Connect a motor, replacing any already on `motor_id`
+    pub fn add_motor(&self, motor_id: u8, motor: SimMotor) {
+        let mut sim = self.shared.0.lock().unwrap();
+        let now = sim.advance();
+        sim.motors.insert(motor_id, MotorSim::new(motor, now));
+    }
+
+    /// Disconnect a motor, e.g. to test dropouts
+    pub fn remove_motor(&self, motor_id: u8) -> Option<SimMotor> {
+        self.shared.0.lock().unwrap().motors.remove(&motor_id).map(|m| m.config)
+    }
+
+    /// True state of a motor, relative to its zero
+    pub fn state(&self, motor_id: u8) -> Option<MotorState> {
+        let mut sim = self.shared.0.lock().unwrap();
+        sim.advance();
+        sim.motors.get(&motor_id).map(|m| m.state(motor_id))
+    }
+
+    /// Raise a fault: the motor goes limp, reports `code` in its fault
+    /// register and logs it; 0 clears the active fault
+    pub fn set_fault(&self, motor_id: u8, code: u8) {
+        let mut sim = self.shared.0.lock().unwrap();
+        let now = sim.advance();
+        if let Some(motor) = sim.motors.get_mut(&motor_id) {
+            motor.fault = code;
+            if code != 0 {
+                let uptime = now.duration_since(motor.started).as_secs() as u32;
+                motor.error_log.insert(0, (code, uptime));
+            }
+        }
+    }
+
+    /// Take the bus down, e.g. an unplugged adapter: sending, receiving and
+    /// reopening fail until it is brought back up
+    pub fn set_link_down(&self, down: bool) {
+        self.shared.0.lock().unwrap().link_down = down;
+    }
+
+    /// Apply a load torque (Nm) to a motor, e.g. gravity or a push
+    pub fn set_load(&self, motor_id: u8, torque_nm: f64) {
+        let mut sim = self.shared.0.lock().unwrap();
+        sim.advance();
+        if let Some(motor) = sim.motors.get_mut(&motor_id) {
+            motor.load = torque_nm;
+        }
+    }
+}
+
+impl CanTransport for MockTransport {
+    fn send(&self, frame: &RawFrame) -> io::Result<()> {
+        let (lock, wake) = &*self.shared;
+        let mut sim = lock.lock().unwrap();
+        sim.check_link()?;
+        sim.advance();
+        sim.receive(frame.id, &frame.data);
+        if !sim.outbox.is_empty() {
+            wake.notify_all();
+        }
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        let (lock, wake) = &*self.shared;
+        let deadline = Instant::now() + timeout;
+        let mut sim = lock.lock().unwrap();
+        loop {
+            sim.check_link()?;
+            let now = sim.advance();
+            sim.push_feedback(now);
+            if let Some(frame) = sim.outbox.pop_front() {
+                return Ok(Some(frame));
+            }
+            if now >= deadline {
+                return Ok(None);
+            }
+            let wait = sim.next_push().map_or(deadline, |push| push.min(deadline)).max(now + STEP);
+            sim = wake.wait_timeout(sim, wait - now).unwrap().0;
+        }
+    }
+
+    fn reopen(&self) -> io::Result<Box<dyn CanTransport>> {
+        self.shared.0.lock().unwrap().check_link()?;
+        Ok(Box::new(self.clone()))
+    }
+}
+
+/// The simulated bus
+struct Sim {
+    motors: BTreeMap<u8, MotorSim>,
+    /// Frames sent by the motors, not yet received
+    outbox: VecDeque<RawFrame>,
+    /// Time integrated up to
+    now: Instant,
+    encoding: EncodingPolicy,
+    mit_ranges: MitRanges,
+    link_down: bool,
+}
+
+impl Default for Sim {
+    fn default() -> Self {
+        Self {
+            motors: BTreeMap::new(),
+            outbox: VecDeque::new(),
+            now: Instant::now(),
+            encoding: EncodingPolicy::default(),
+            mit_ranges: MitRanges::default(),
+            link_down: false,
+        }
+    }
+}
+
+impl Sim {
+    fn check_link(&self) -> io::Result<()> {
+        if self.link_down {
+            return Err(io::Error::new(io::ErrorKind::NetworkDown, "mock bus is down"));
+        }
+        Ok(())
+    }
+
+    /// Integrate every motor up to the wall clock, returns the new time
+    fn advance(&mut self) -> Instant {
+        let now = Instant::now();
+        if now.duration_since(self.now) > MAX_CATCH_UP {
+            self.now = now - MAX_CATCH_UP;
+        }
+        while self.now + STEP <= now {
+            self.now += STEP;
+            self.motors.values_mut().for_each(|m| m.step(STEP.as_secs_f64()));
+        }
+        self.now
+    }
+
+    fn receive(&mut self, id: u32, data: &[u8]) {
+        if let Some(command) = SentCommand::decode(id, data, &self.mit_ranges) {
+            for (&motor_id, motor) in &mut self.motors {
+                if command.motor_id == 0 || command.motor_id == motor_id {
+                    motor.follow(&command);
+                }
+            }
+            return;
+        }
+
+        let layout = self.encoding.layout();
+        let (Some(&opcode), Some(&register)) =
+            (data.get(layout.header_offset("opcode")), data.get(layout.header_offset("register")))
+        else {
+            return;
+        };
+        let ty = ValueType::from_opcode(opcode);
+        let count = opcode & 0x03;
+        let offset = layout.register_values_offset();
+        match opcode & 0xF0 {
+            protocol::OP_READ if id & protocol::REPLY_FLAG != 0 => {
+                let motor_id = (id & 0x7F) as u8;
+                let Some(motor) = self.motors.get(&motor_id) else {
+                    return;
+                };
+                let reply = if register == Register::Mode.addr() && count == 1 && ty == ValueType::Int8 {
+                    motor.query(data.get(offset).copied().unwrap_or(self.encoding.padding), data.get(offset + 1))
+                } else {
+                    let values: Option<Vec<f64>> =
+                        (0..count).map(|i| motor.register(motor_id, register.wrapping_add(i), ty)).collect();
+                    values.map(|values| self.encoding.reply(register, ty, &values))
+                };
+                if let Some(reply) = reply {
+                    self.outbox.push_back(reply_frame(motor_id, &reply));
+                }
+            }
+            protocol::OP_WRITE if id < 0x80 => {
+                let motor_id = id as u8;
+                let values: Vec<f64> = (0..count as usize)
+                    .map_while(|i| {
+                        let bytes = data.get(offset + i * ty.size()..offset + (i + 1) * ty.size())?;
+                        layout::read_value(bytes, ty, layout.endianness)
+                    })
+                    .collect();
+                for (i, value) in values.into_iter().enumerate() {
+                    self.write(motor_id, register.wrapping_add(i as u8), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn write(&mut self, motor_id: u8, register: u8, value: f64) {
+        let Some(motor) = self.motors.get_mut(&motor_id) else {
+            return;
+        };
+        match register {
+            r if r == Register::Mode.addr() => {
+                motor.mode = value as u8;
+                motor.command = Command::Hold(motor.position);
+            }
+            r if r == Register::TorqueLimit.addr() => motor.torque_limit = value,
+            r if r == Register::Kp.addr() => motor.kp = value,
+            r if r == Register::Kd.addr() => motor.kd = value,
+            r if r == Register::ReportContent.addr() => motor.report = value as u8,
+            r if r == Register::FeedbackPeriod.addr() => {
+                motor.feedback_period = value as i16;
+                motor.next_push = self.now;
+            }
+            r if r == Register::ClearErrorLog.addr() => motor.error_log.clear(),
+            r if r == Register::SetZero.addr() => motor.zero = motor.position,
+            r if r == Register::MotorId.addr() => {
+                let new_id = value as u8;
+                if (1..=127).contains(&new_id) {
+                    let motor = self.motors.remove(&motor_id).unwrap();
+                    self.motors.insert(new_id, motor);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_feedback(&mut self, now: Instant) {
+        for (&motor_id, motor) in &mut self.motors {
+            if motor.feedback_period <= 0 || motor.next_push > now {
+                continue;
+            }
+            let period = Duration::from_micros(motor.feedback_period as u64 * 100);
+            motor.next_push = (motor.next_push + period).max(now);
+            let fields = ReportMask::from_bits(motor.report).unwrap_or(ReportMask::STATE).fields();
+            let values: Vec<f64> = fields.iter().map(|&field| motor.report_value(motor_id, field)).collect();
+            let first = fields.first().map_or(Register::Position, |f| f.register());
+            let reply = self.encoding.reply(first.addr(), ValueType::Int16, &values);
+            self.outbox.push_back(reply_frame(motor_id, &reply));
+        }
+    }
+
+    /// When the next pushed state is due
+    fn next_push(&self) -> Option<Instant> {
+        self.motors.values().filter(|m| m.feedback_period > 0).map(|m| m.next_push).min()
+    }
+}
+
+/// `value` clamped to ±`limit`, unchanged for a limit of 0
+fn cap(value: f64, limit: f64) -> f64 {
+    if limit > 0.0 { value.clamp(-limit, limit) } else { value }
+}
+
+/// Frame sent by a motor, from `motor_id << 8`
+fn reply_frame(motor_id: u8, data: &[u8]) -> RawFrame {
+    RawFrame::extended((motor_id as u32) << 8, data)
+}
+
+/// What a motor's loop follows
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    /// Position at the time it was enabled, rad
+    Hold(f64),
+    /// Target rad, speed cap rad/s and torque cap Nm (0 = no cap)
+    Angle { position: f64, max_velocity: f64, max_torque: f64 },
+    /// Target rad/s, acceleration limit rad/s² (0 = none) and optional target position
+    Velocity { velocity: f64, acceleration: f64, position: Option<f64> },
+    /// Impedance law, gains in Nm/rad and Nm·s/rad
+    Mit { position: f64, velocity: f64, kp: f64, kd: f64, torque: f64 },
+}
+
+struct MotorSim {
+    config: SimMotor,
+    /// Absolute position rad, velocity rad/s and torque Nm
+    position: f64,
+    velocity: f64,
+    torque: f64,
+    acceleration: f64,
+    /// Position set as zero, rad
+    zero: f64,
+    /// External load torque, Nm
+    load: f64,
+    /// Velocity setpoint ramped at the acceleration limit, rad/s
+    ramped_velocity: f64,
+    mode: u8,
+    command: Command,
+    torque_limit: f64,
+    kp: f64,
+    kd: f64,
+    report: u8,
+    feedback_period: i16,
+    next_push: Instant,
+    fault: u8,
+    /// Code and uptime (s) of logged faults, most recent first
+    error_log: Vec<(u8, u32)>,
+    started: Instant,
+}
+
+impl MotorSim {
+    fn new(config: SimMotor, now: Instant) -> Self {
+        Self {
+            config,
+            position: 0.0,
+            velocity: 0.0,
+            torque: 0.0,
+            acceleration: 0.0,
+            zero: 0.0,
+            load: 0.0,
+            ramped_velocity: 0.0,
+            mode: 0,
+            command: Command::Hold(0.0),
+            torque_limit: 0.0,
+            kp: 0.0,
+            kd: 0.0,
+            report: ReportMask::STATE.bits(),
+            feedback_period: 0,
+            next_push: now,
+            fault: 0,
+            error_log: Vec::new(),
+            started: now,
+        }
+    }
+
+    fn follow(&mut self, command: &SentCommand) {
+        let rad = |deg: f64| deg.to_radians();
+        let target = command.position_deg.map(|deg| self.zero + rad(deg));
+        self.command = match command.frame {
+            "angle_stream" => Command::Angle {
+                position: target.unwrap_or(self.position),
+                max_velocity: command.velocity_rps.unwrap_or(0.0).abs() * TAU,
+                max_torque: command.torque_nm.unwrap_or(0.0).abs(),
+            },
+            "velocity_stream" => Command::Velocity {
+                velocity: command.velocity_rps.unwrap_or(0.0) * TAU,
+                acceleration: command.acceleration_rps2.unwrap_or(0.0).abs() * TAU,
+                position: target,
+            },
+            _ => Command::Mit {
+                position: target.unwrap_or(self.position),
+                velocity: command.velocity_rps.unwrap_or(0.0) * TAU,
+                kp: command.kp.unwrap_or(0.0),
+                kd: command.kd.unwrap_or(0.0),
+                torque: command.torque_nm.unwrap_or(0.0),
+            },
+        };
+    }
+
+    fn step(&mut self, dt: f64) {
+        let SimMotor { inertia, damping, bandwidth, .. } = self.config;
+        let (position_now, velocity_now) = (self.position, self.velocity);
+        // Critically damped position loop as a cascade: velocity setpoint from
+        // the position error, acceleration from the velocity error
+        let velocity_loop =
+            |velocity: f64| inertia * 2.0 * bandwidth * (velocity - velocity_now) + damping * velocity_now;
+        let position_loop = |position: f64, max_velocity: f64| {
+            velocity_loop(cap(bandwidth / 2.0 * (position - position_now), max_velocity))
+        };
+
+        let torque = if self.mode == 0 || self.fault != 0 {
+            0.0
+        } else {
+            match self.command {
+                Command::Hold(position) => position_loop(position, 0.0),
+                Command::Angle { position, max_velocity, max_torque } => {
+                    cap(position_loop(position, max_velocity), max_torque)
+                }
+                Command::Velocity { velocity, acceleration, position } => {
+                    let step = if acceleration > 0.0 { acceleration * dt } else { f64::INFINITY };
+                    self.ramped_velocity += (velocity - self.ramped_velocity).clamp(-step, step);
+                    match position {
+                        Some(position) => position_loop(position, self.ramped_velocity.abs()),
+                        None => velocity_loop(self.ramped_velocity),
+                    }
+                }
+                Command::Mit { position, velocity, kp, kd, torque } => {
+                    kp * (position - position_now) + kd * (velocity - velocity_now) + torque
+                }
+            }
+        };
+        self.torque = cap(torque, self.torque_limit);
+        if !matches!(self.command, Command::Velocity { .. }) {
+            self.ramped_velocity = self.velocity;
+        }
+
+        self.acceleration = (self.torque + self.load - damping * self.velocity) / inertia;
+        self.velocity += self.acceleration * dt;
+        self.position += self.velocity * dt;
+    }
+
+    fn state(&self, motor_id: u8) -> MotorState {
+        MotorState {
+            motor_id,
+            position_deg: (self.position - self.zero).to_degrees(),
+            velocity_rps: self.velocity / TAU,
+            torque_nm: self.torque,
+            acceleration_rps2: Some(self.acceleration / TAU),
+        }
+    }
+
+    /// Value of a register in the requested type, `None` if unknown
+    fn register(&self, motor_id: u8, register: u8, ty: ValueType) -> Option<f64> {
+        let state = self.state(motor_id);
+        let integer = ty != ValueType::Float;
+        let value = match Register::ALL.into_iter().find(|r| r.addr() == register)? {
+            Register::Mode => self.mode as f64,
+            Register::Position => crate::degrees_to_position(state.position_deg) as f64,
+            Register::Velocity => crate::rps_to_velocity(state.velocity_rps) as f64,
+            Register::Torque => crate::nm_to_torque(state.torque_nm) as f64,
+            Register::QCurrent => self.torque / self.config.torque_constant,
+            Register::Voltage => self.config.voltage,
+            Register::Temperature if integer => self.report_value(motor_id, ReportField::Temperature),
+            Register::Temperature => self.config.temperature_c,
+            Register::Fault => self.fault as f64,
+            Register::TorqueLimit => self.torque_limit,
+            Register::Kp => self.kp,
+            Register::Kd => self.kd,
+            Register::ReportContent => self.report as f64,
+            Register::FeedbackPeriod => self.feedback_period as f64,
+            Register::GpioInput => 0.0,
+            Register::MotorId => motor_id as f64,
+            Register::ScalePosition => self.config.scaling?.position,
+            Register::ScaleVelocity => self.config.scaling?.velocity,
+            Register::ScaleTorque => self.config.scaling?.torque,
+            Register::ClearErrorLog | Register::SaveConfig | Register::SetZero => return None,
+        };
+        Some(value)
+    }
+
+    /// int16 value of a field in a pushed reply
+    fn report_value(&self, motor_id: u8, field: ReportField) -> f64 {
+        match field {
+            ReportField::Temperature => (self.config.temperature_c * 10.0).round(),
+            _ => self.register(motor_id, field.register().addr(), ValueType::Int16).unwrap_or(0.0),
+        }
+    }
+
+    /// Reply to an info query, `None` for unknown selectors
+    fn query(&self, selector: u8, index: Option<&u8>) -> Option<[u8; 8]> {
+        let mut data = [protocol::PADDING; 8];
+        data[0] = QUERY_REPLY;
+        let uptime = self.started.elapsed().as_secs() as u32;
+        match selector {
+            s if s == InfoQuery::Identity.selector() => {
+                let ascii = |text: &str, len: usize| format!("{:<len$.len$}", text).into_bytes();
+                data[1..4].copy_from_slice(&ascii(&self.config.name, 3));
+                data[4..8].copy_from_slice(&ascii(&self.config.hardware_version, 4));
+            }
+            s if s == InfoQuery::SerialNumber.selector() => {
+                data[1] = s;
+                data[2..6].copy_from_slice(&self.config.serial_number.to_le_bytes());
+            }
+            s if s == InfoQuery::ErrorLog.selector() => {
+                let index = *index?;
+                let (code, at) = self.error_log.get(index as usize).copied().unwrap_or((0, 0));
+                data[1..4].copy_from_slice(&[s, index, code]);
+                data[4..8].copy_from_slice(&at.to_le_bytes());
+            }
+            s if s == InfoQuery::Uptime.selector() => {
+                data[1] = s;
+                data[2..6].copy_from_slice(&uptime.to_le_bytes());
+            }
+            s if s == InfoQuery::Temperatures.selector() => {
+                let tenths = ((self.config.temperature_c * 10.0).round() as i16).to_le_bytes();
+                data[1] = s;
+                (0..3).for_each(|i| data[2 + 2 * i..4 + 2 * i].copy_from_slice(&tenths));
+            }
+            _ => return None,
+        }
+        Some(data)
+    }
+}
@@ -0,0 +1,179 @@
+//! Handles to motions running in the background
+//!
+//! [`Trajectory::start`](crate::Trajectory::start) and
+//! [`ParkRunner::start`](crate::ParkRunner::start) execute on their own thread
+//! and return a [`MotionHandle`], so higher-level software can pause, resume or
+//! preempt a motion while it runs and learn how it ended, either blocking
+//! ([`MotionHandle::wait`]) or from async code ([`MotionHandle::finished`]).
+//!
+//! Pausing holds the joints where they are and stops the motion's clock;
+//! resuming continues from the same point. Dropping a handle cancels its motion.
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tokio::sync::oneshot;
+
+/// How a motion ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum MotionOutcome {
+    Completed,
+    Cancelled,
+    Failed(String),
+}
+
+impl MotionOutcome {
+    pub fn is_completed(&self) -> bool {
+        matches!(self, MotionOutcome::Completed)
+    }
+}
+
+/// Flags a running motion checks every cycle
+#[derive(Debug)]
+pub struct MotionSignals {
+    running: AtomicBool,
+    paused: AtomicBool,
+}
+
+impl Default for MotionSignals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MotionSignals {
+    pub fn new() -> Self {
+        Self {
+            running: AtomicBool::new(true),
+            paused: AtomicBool::new(false),
+        }
+    }
+
+    /// Cleared on cancel; pass where a motion takes a `running` flag
+    pub fn running(&self) -> &AtomicBool {
+        &self.running
+    }
+
+    /// Set while paused
+    pub fn paused(&self) -> &AtomicBool {
+        &self.paused
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        !self.running.load(Ordering::SeqCst)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+}
+
+/// Control over a motion running on its own thread
+pub struct MotionHandle {
+    signals: Arc<MotionSignals>,
+    done: Option<oneshot::Receiver<MotionOutcome>>,
+    outcome: Option<MotionOutcome>,
+}
+
+impl MotionHandle {
+    /// Run `motion` on a new thread
+    ///
+    /// The outcome is `Cancelled` if the motion returns after a cancel,
+    /// `Failed` if it returns an error or panics.
+    pub fn spawn<F>(motion: F) -> Self
+    where
+        F: FnOnce(&MotionSignals) -> Result<()> + Send + 'static,
+    {
+        let signals = Arc::new(MotionSignals::new());
+        let (tx, rx) = oneshot::channel();
+        {
+            let signals = Arc::clone(&signals);
+            thread::spawn(move || {
+                let outcome = match motion(&signals) {
+                    Ok(()) if signals.is_cancelled() => MotionOutcome::Cancelled,
+                    Ok(()) => MotionOutcome::Completed,
+                    Err(e) => MotionOutcome::Failed(format!("{:#}", e)),
+                };
+                let _ = tx.send(outcome);
+            });
+        }
+
+        Self {
+            signals,
+            done: Some(rx),
+            outcome: None,
+        }
+    }
+
+    /// Stop the motion at its next cycle
+    pub fn cancel(&self) {
+        self.signals.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Hold the joints where they are until [`resume`](Self::resume)
+    pub fn pause(&self) {
+        self.signals.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.signals.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.signals.is_paused()
+    }
+
+    /// Outcome if the motion has ended, without blocking
+    pub fn outcome(&mut self) -> Option<&MotionOutcome> {
+        if self.outcome.is_none() {
+            let received = match self.done.as_mut()?.try_recv() {
+                Ok(outcome) => outcome,
+                Err(oneshot::error::TryRecvError::Empty) => return None,
+                Err(oneshot::error::TryRecvError::Closed) => panicked(),
+            };
+            self.done = None;
+            self.outcome = Some(received);
+        }
+        self.outcome.as_ref()
+    }
+
+    pub fn is_finished(&mut self) -> bool {
+        self.outcome().is_some()
+    }
+
+    /// Block until the motion ends
+    ///
+    /// Must not be called from async code; await [`finished`](Self::finished) there.
+    pub fn wait(mut self) -> MotionOutcome {
+        if let Some(outcome) = self.outcome.take() {
+            return outcome;
+        }
+        match self.done.take() {
+            Some(rx) => rx.blocking_recv().unwrap_or_else(|_| panicked()),
+            None => panicked(),
+        }
+    }
+
+    /// Wait for the motion to end without blocking the runtime
+    pub async fn finished(mut self) -> MotionOutcome {
+        if let Some(outcome) = self.outcome.take() {
+            return outcome;
+        }
+        match self.done.take() {
+            Some(rx) => rx.await.unwrap_or_else(|_| panicked()),
+            None => panicked(),
+        }
+    }
+}
+
+impl Drop for MotionHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Outcome of a motion thread that dropped its sender without sending
+fn panicked() -> MotionOutcome {
+    MotionOutcome::Failed("motion thread panicked".to_string())
+}
@@ -0,0 +1,164 @@
+//! Motor usage odometer
+//!
+//! Accumulates per motor the revolutions turned, time enabled, mechanical
+//! energy delivered and overload events, and persists them to a JSON file
+//! keyed by serial number, so the totals follow a motor when it is moved to
+//! another joint or robot. The firmware does not report a serial number; it
+//! is assigned with [`Odometer::track`], e.g. from the label on the housing.
+
+use crate::events::{EnvelopeEventKind, Event, EventKind};
+use crate::MotorState;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Samples further apart than this are not integrated (e.g. after a pause)
+const MAX_SAMPLE_GAP_S: f64 = 1.0;
+
+/// Cumulative usage of one motor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MotorUsage {
+    /// Output shaft revolutions, in either direction
+    pub revolutions: f64,
+    /// Seconds spent enabled
+    pub enabled_s: f64,
+    /// Mechanical energy delivered, |torque × speed| integrated (J)
+    pub energy_j: f64,
+    /// Times the peak torque budget was exhausted or the overload torque exceeded
+    pub overload_events: u64,
+}
+
+impl MotorUsage {
+    pub fn enabled_hours(&self) -> f64 {
+        self.enabled_s / 3600.0
+    }
+}
+
+struct Tracked {
+    serial: String,
+    last_sample: Option<Instant>,
+    overloaded: bool,
+}
+
+/// Per-motor usage counters backed by a file
+pub struct Odometer {
+    path: PathBuf,
+    usage: BTreeMap<String, MotorUsage>,
+    motors: HashMap<u8, Tracked>,
+    overload_nm: Option<f64>,
+}
+
+impl Odometer {
+    /// Open the usage file at `path`; a missing file starts all counters at zero
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let usage = if path.exists() {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Cannot read usage file {}: {}", path.display(), e))?;
+            serde_json::from_str(&text)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            usage,
+            motors: HashMap::new(),
+            overload_nm: None,
+        })
+    }
+
+    /// Also count an overload event each time |torque| rises above `torque_nm`
+    pub fn with_overload_torque(mut self, torque_nm: f64) -> Self {
+        self.overload_nm = Some(torque_nm);
+        self
+    }
+
+    /// Record the usage of `motor_id` under `serial` from now on
+    pub fn track(&mut self, motor_id: u8, serial: &str) {
+        self.usage.entry(serial.to_string()).or_default();
+        self.motors.insert(
+            motor_id,
+            Tracked {
+                serial: serial.to_string(),
+                last_sample: None,
+                overloaded: false,
+            },
+        );
+    }
+
+    /// Serial number assigned to `motor_id`
+    pub fn serial(&self, motor_id: u8) -> Option<&str> {
+        self.motors.get(&motor_id).map(|t| t.serial.as_str())
+    }
+
+    /// Totals of `motor_id`, including previous sessions
+    pub fn usage(&self, motor_id: u8) -> Option<MotorUsage> {
+        self.usage.get(&self.motors.get(&motor_id)?.serial).copied()
+    }
+
+    /// Totals of a motor by serial number, tracked or not
+    pub fn usage_by_serial(&self, serial: &str) -> Option<MotorUsage> {
+        self.usage.get(serial).copied()
+    }
+
+    /// Account for a feedback sample taken at `now`; returns false if the motor is not tracked
+    ///
+    /// Revolutions and energy are integrated from velocity and torque between
+    /// consecutive samples, so the totals are as good as the sample rate.
+    pub fn record(&mut self, state: &MotorState, enabled: bool, now: Instant) -> bool {
+        let Some(tracked) = self.motors.get_mut(&state.motor_id) else {
+            return false;
+        };
+        let usage = self.usage.entry(tracked.serial.clone()).or_default();
+
+        let dt = tracked
+            .last_sample
+            .map(|t| now.saturating_duration_since(t).as_secs_f64())
+            .filter(|&dt| dt <= MAX_SAMPLE_GAP_S)
+            .unwrap_or(0.0);
+        tracked.last_sample = Some(now);
+
+        usage.revolutions += state.velocity_rps.abs() * dt;
+        usage.energy_j += (state.torque_nm * state.velocity_rps * 2.0 * PI).abs() * dt;
+        if enabled {
+            usage.enabled_s += dt;
+        }
+
+        if let Some(limit) = self.overload_nm {
+            let overloaded = state.torque_nm.abs() > limit;
+            if overloaded && !tracked.overloaded {
+                usage.overload_events += 1;
+            }
+            tracked.overloaded = overloaded;
+        }
+        true
+    }
+
+    /// Count torque envelope `Limited` events as overloads
+    pub fn observe(&mut self, event: &Event) {
+        if let EventKind::TorqueEnvelope {
+            motor_id,
+            kind: EnvelopeEventKind::Limited,
+            ..
+        } = event.kind
+        {
+            if let Some(tracked) = self.motors.get(&motor_id) {
+                self.usage.entry(tracked.serial.clone()).or_default().overload_events += 1;
+            }
+        }
+    }
+
+    /// Write all counters back to the usage file
+    pub fn save(&self) -> Result<()> {
+        // Write to a temporary file first so a crash can't truncate the totals
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.usage)?)
+            .map_err(|e| anyhow!("Cannot write usage file {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &self.path)
+            .map_err(|e| anyhow!("Cannot write usage file {}: {}", self.path.display(), e))?;
+        Ok(())
+    }
+}
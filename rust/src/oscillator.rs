@@ -0,0 +1,208 @@
+//! Phase-synchronized sine oscillation of several joints
+//!
+//! An [`Oscillator`] drives every joint of an [`OscillatorConfig`] with its own
+//! center, amplitude, frequency and phase, all evaluated on one shared clock so
+//! the phase relations hold for the whole run: e.g. hips of a biped half a
+//! period apart and each knee a quarter period behind its hip, a simple
+//! central pattern generator. The amplitudes ramp in at the start and out at
+//! the end, so the joints start and stop at their centers.
+//!
+//! Configs are JSON:
+//!
+//! ```json
+//! { "rate_hz": 200, "duration_s": 20, "ramp_s": 2,
+//!   "joints": [
+//!     { "motor_id": 1, "amplitude_deg": 20, "frequency_hz": 0.8 },
+//!     { "motor_id": 2, "amplitude_deg": 20, "frequency_hz": 0.8, "phase_deg": 180 },
+//!     { "motor_id": 3, "center_deg": 30, "amplitude_deg": 15, "frequency_hz": 0.8, "phase_deg": -90 } ] }
+//! ```
+
+use crate::{ControlLoop, CycleInfo, LivelyMotorController};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+/// Oscillation of one joint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JointOscillation {
+    pub motor_id: u8,
+    #[serde(default)]
+    pub center_deg: f64,
+    pub amplitude_deg: f64,
+    pub frequency_hz: f64,
+    /// Phase lead over a plain sine started at time 0
+    #[serde(default)]
+    pub phase_deg: f64,
+}
+
+impl JointOscillation {
+    pub fn new(motor_id: u8, amplitude_deg: f64, frequency_hz: f64) -> Self {
+        Self {
+            motor_id,
+            center_deg: 0.0,
+            amplitude_deg,
+            frequency_hz,
+            phase_deg: 0.0,
+        }
+    }
+
+    pub fn with_center(mut self, center_deg: f64) -> Self {
+        self.center_deg = center_deg;
+        self
+    }
+
+    pub fn with_phase(mut self, phase_deg: f64) -> Self {
+        self.phase_deg = phase_deg;
+        self
+    }
+
+    /// Angle at `t` with the amplitude scaled by `gain`
+    fn angle_at(&self, t: f64, gain: f64) -> f64 {
+        let phase = 2.0 * PI * self.frequency_hz * t + self.phase_deg.to_radians();
+        self.center_deg + gain * self.amplitude_deg * phase.sin()
+    }
+}
+
+/// Joints, timing and limits of an oscillation run, loaded from JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OscillatorConfig {
+    pub joints: Vec<JointOscillation>,
+    #[serde(default = "default_rate")]
+    pub rate_hz: f64,
+    pub duration_s: f64,
+    /// Time over which the amplitudes ramp in and out
+    #[serde(default)]
+    pub ramp_s: f64,
+    #[serde(default = "default_max_vel")]
+    pub max_vel_rps: f64,
+    #[serde(default = "default_max_torque")]
+    pub max_torque_nm: f64,
+}
+
+fn default_rate() -> f64 {
+    200.0
+}
+
+fn default_max_vel() -> f64 {
+    2.0
+}
+
+fn default_max_torque() -> f64 {
+    3.0
+}
+
+impl OscillatorConfig {
+    /// Config at 200 Hz without ramps, 2.0 r/s and 3.0 Nm limits
+    pub fn new(joints: Vec<JointOscillation>, duration_s: f64) -> Self {
+        Self {
+            joints,
+            rate_hz: default_rate(),
+            duration_s,
+            ramp_s: 0.0,
+            max_vel_rps: default_max_vel(),
+            max_torque_nm: default_max_torque(),
+        }
+    }
+
+    pub fn with_ramp(mut self, ramp_s: f64) -> Self {
+        self.ramp_s = ramp_s;
+        self
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read oscillator config {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Motor IDs in config order
+    pub fn motor_ids(&self) -> Vec<u8> {
+        self.joints.iter().map(|j| j.motor_id).collect()
+    }
+}
+
+/// Streams a phase-synchronized oscillation to several joints
+pub struct Oscillator {
+    config: OscillatorConfig,
+}
+
+impl Oscillator {
+    pub fn new(config: OscillatorConfig) -> Result<Self> {
+        if config.rate_hz <= 0.0 || config.duration_s <= 0.0 {
+            return Err(anyhow!("rate and duration must be positive"));
+        }
+        if config.ramp_s < 0.0 || 2.0 * config.ramp_s > config.duration_s {
+            return Err(anyhow!("ramp of {} s does not fit twice into {} s", config.ramp_s, config.duration_s));
+        }
+        for (i, joint) in config.joints.iter().enumerate() {
+            if joint.frequency_hz < 0.0 || joint.amplitude_deg < 0.0 {
+                return Err(anyhow!("motor {}: amplitude and frequency must not be negative", joint.motor_id));
+            }
+            if config.joints[..i].iter().any(|j| j.motor_id == joint.motor_id) {
+                return Err(anyhow!("motor {} is listed twice", joint.motor_id));
+            }
+        }
+        Ok(Self { config })
+    }
+
+    pub fn config(&self) -> &OscillatorConfig {
+        &self.config
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.config.duration_s)
+    }
+
+    /// Amplitude scale at `t`: rises from 0 to 1 over the ramp, falls back to 0
+    /// over the last ramp, 0 after the end
+    pub fn envelope(&self, t: Duration) -> f64 {
+        let (t, end, ramp) = (t.as_secs_f64(), self.config.duration_s, self.config.ramp_s);
+        if t >= end {
+            return 0.0;
+        }
+        if ramp <= 0.0 {
+            return 1.0;
+        }
+        let x = (t.min(end - t) / ramp).min(1.0);
+        // Smoothstep: no velocity jump at either end of the ramp
+        x * x * (3.0 - 2.0 * x)
+    }
+
+    /// `(motor ID, angle)` of every joint at `t`
+    pub fn targets(&self, t: Duration) -> Vec<(u8, f64)> {
+        let gain = self.envelope(t);
+        let t = t.as_secs_f64();
+        self.config
+            .joints
+            .iter()
+            .map(|joint| (joint.motor_id, joint.angle_at(t, gain)))
+            .collect()
+    }
+
+    /// Stream the oscillation until it ends or `running` is cleared; the
+    /// joints must be enabled. `observe` sees each cycle's targets.
+    pub fn run<F>(&self, controller: &LivelyMotorController, running: &AtomicBool, mut observe: F) -> Result<()>
+    where
+        F: FnMut(&CycleInfo, &[(u8, f64)]) -> Result<()>,
+    {
+        let duration = self.duration();
+        ControlLoop::new(self.config.rate_hz).run(running, |info| {
+            let elapsed = info.elapsed.min(duration);
+            let targets = self.targets(elapsed);
+            for &(motor_id, angle_deg) in &targets {
+                controller.send_angle_command_to(
+                    motor_id,
+                    crate::degrees_to_position(angle_deg),
+                    crate::rps_to_velocity(self.config.max_vel_rps),
+                    crate::nm_to_torque(self.config.max_torque_nm),
+                )?;
+            }
+            observe(info, &targets)?;
+            Ok(info.elapsed < duration)
+        })
+    }
+}
@@ -0,0 +1,304 @@
+//! Park / stow poses
+//!
+//! A park pose is reached in ordered stages (e.g. fold the arms before bending
+//! the knees). Each stage moves its joints together at a limited velocity.
+//! Before anything moves, the whole staged motion is simulated from the
+//! measured pose and checked against simple joint-range rules, so a stage order
+//! that would make the robot hit itself is rejected instead of executed.
+
+use crate::{ControlLoop, JointLimits, LivelyMotorController, MotionHandle};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Rate at which stage setpoints are streamed and progress is checked
+const PARK_RATE_HZ: f64 = 50.0;
+
+/// Time step of the collision check simulation
+const CHECK_STEP_S: f64 = 0.01;
+
+/// Joints moved together, with their target angles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParkStage {
+    pub name: String,
+    /// Motor ID → target angle in degrees
+    pub targets: BTreeMap<u8, f64>,
+}
+
+/// A named pose reached through ordered stages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParkPose {
+    pub name: String,
+    pub stages: Vec<ParkStage>,
+}
+
+impl ParkPose {
+    /// Every motor moved by any stage
+    pub fn motor_ids(&self) -> Vec<u8> {
+        let mut ids: Vec<u8> = self.stages.iter().flat_map(|s| s.targets.keys().copied()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+/// While `joint` is within `when`, `other` must stay within `allowed`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeRule {
+    pub name: String,
+    pub joint: u8,
+    pub when: JointLimits,
+    pub other: u8,
+    pub allowed: JointLimits,
+}
+
+impl RangeRule {
+    /// Whether `pose` (motor ID → angle) breaks the rule
+    pub fn is_violated(&self, pose: &BTreeMap<u8, f64>) -> bool {
+        match (pose.get(&self.joint), pose.get(&self.other)) {
+            (Some(&a), Some(&b)) => self.when.contains(a) && !self.allowed.contains(b),
+            _ => false,
+        }
+    }
+}
+
+fn default_max_velocity() -> f64 {
+    0.5
+}
+
+fn default_max_torque() -> f64 {
+    3.0
+}
+
+fn default_tolerance() -> f64 {
+    2.0
+}
+
+fn default_stage_timeout() -> f64 {
+    10.0
+}
+
+/// Park poses and self-collision rules, loaded from JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParkConfig {
+    #[serde(default = "default_max_velocity")]
+    pub max_vel_rps: f64,
+    #[serde(default = "default_max_torque")]
+    pub max_torque_nm: f64,
+    /// A stage is done when every joint is this close to its target
+    #[serde(default = "default_tolerance")]
+    pub tolerance_deg: f64,
+    #[serde(default = "default_stage_timeout")]
+    pub stage_timeout_s: f64,
+    #[serde(default)]
+    pub rules: Vec<RangeRule>,
+    pub poses: Vec<ParkPose>,
+}
+
+impl ParkConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read park config {}: {}", path.display(), e))?;
+        Self::from_json(&text)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        let config: Self = serde_json::from_str(text)?;
+        if config.max_vel_rps <= 0.0 {
+            return Err(anyhow!("max_vel_rps must be positive"));
+        }
+        for pose in &config.poses {
+            if pose.stages.is_empty() {
+                return Err(anyhow!("park pose '{}' has no stages", pose.name));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn pose(&self, name: &str) -> Result<&ParkPose> {
+        self.poses
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or(anyhow!("no park pose named '{}'", name))
+    }
+
+    /// Simulate moving through `pose`'s stages from `start` and check every rule
+    ///
+    /// Each joint is assumed to move straight to its target at `max_vel_rps`,
+    /// all joints of a stage starting together.
+    pub fn check_motion(&self, pose: &ParkPose, start: &BTreeMap<u8, f64>) -> Result<()> {
+        let mut current = start.clone();
+        self.check_rules(&current, &format!("start of '{}'", pose.name))?;
+
+        let speed_deg = self.max_vel_rps * 360.0;
+        for stage in &pose.stages {
+            let from: BTreeMap<u8, f64> = stage
+                .targets
+                .keys()
+                .map(|id| {
+                    current
+                        .get(id)
+                        .map(|&a| (*id, a))
+                        .ok_or(anyhow!("motor {} has no measured start position", id))
+                })
+                .collect::<Result<_>>()?;
+
+            let longest = stage
+                .targets
+                .iter()
+                .map(|(id, target)| (target - from[id]).abs())
+                .fold(0.0, f64::max);
+            let steps = ((longest / speed_deg) / CHECK_STEP_S).ceil().max(1.0) as usize;
+
+            for step in 1..=steps {
+                let travel = longest * step as f64 / steps as f64;
+                for (id, &target) in &stage.targets {
+                    let delta = target - from[id];
+                    current.insert(*id, from[id] + delta.signum() * delta.abs().min(travel));
+                }
+                self.check_rules(&current, &format!("stage '{}'", stage.name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rules(&self, pose: &BTreeMap<u8, f64>, context: &str) -> Result<()> {
+        match self.rules.iter().find(|r| r.is_violated(pose)) {
+            Some(rule) => Err(anyhow!(
+                "{} violates rule '{}' (motor {} at {:.1}°, motor {} at {:.1}°)",
+                context,
+                rule.name,
+                rule.joint,
+                pose[&rule.joint],
+                rule.other,
+                pose[&rule.other]
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Moves a robot into its park poses
+pub struct ParkRunner<'a> {
+    controller: &'a LivelyMotorController,
+    config: ParkConfig,
+}
+
+impl<'a> ParkRunner<'a> {
+    pub fn new(controller: &'a LivelyMotorController, config: ParkConfig) -> Self {
+        Self { controller, config }
+    }
+
+    pub fn config(&self) -> &ParkConfig {
+        &self.config
+    }
+
+    /// Measure the joints, check the staged motion and execute it
+    ///
+    /// `on_stage` is called with each stage before it starts.
+    pub fn park<F>(&self, name: &str, running: &AtomicBool, on_stage: F) -> Result<()>
+    where
+        F: FnMut(&ParkStage),
+    {
+        self.park_pausable(name, running, &AtomicBool::new(false), on_stage)
+    }
+
+    /// Park on a new thread; the returned handle pauses, resumes or cancels it
+    ///
+    /// The pose is checked to exist before the thread starts. While paused,
+    /// the joints hold the angles measured when the pause began and the stage
+    /// timeout stops counting.
+    pub fn start(controller: Arc<LivelyMotorController>, config: ParkConfig, name: &str) -> Result<MotionHandle> {
+        config.pose(name)?;
+        let name = name.to_string();
+        Ok(MotionHandle::spawn(move |signals| {
+            ParkRunner::new(&controller, config).park_pausable(&name, signals.running(), signals.paused(), |_| {})
+        }))
+    }
+
+    fn park_pausable<F>(&self, name: &str, running: &AtomicBool, paused: &AtomicBool, mut on_stage: F) -> Result<()>
+    where
+        F: FnMut(&ParkStage),
+    {
+        let pose = self.config.pose(name)?;
+
+        // Joints only named by rules are measured too, so their rules apply
+        let mut measured = pose.motor_ids();
+        measured.extend(self.config.rules.iter().flat_map(|r| [r.joint, r.other]));
+        measured.sort_unstable();
+        measured.dedup();
+
+        let start = measured
+            .into_iter()
+            .map(|id| Ok((id, self.controller.read_motor_state(id)?.position_deg)))
+            .collect::<Result<BTreeMap<u8, f64>>>()?;
+        self.config.check_motion(pose, &start)?;
+
+        let enabled = self.controller.enabled_motors();
+        for id in pose.motor_ids() {
+            if !enabled.contains(&id) {
+                self.controller.enable_motor(id)?;
+            }
+        }
+
+        for stage in &pose.stages {
+            on_stage(stage);
+            self.run_stage(stage, running, paused)?;
+        }
+        Ok(())
+    }
+
+    /// Stream a stage's targets until every joint is within tolerance
+    fn run_stage(&self, stage: &ParkStage, running: &AtomicBool, paused: &AtomicBool) -> Result<()> {
+        let timeout = Duration::from_secs_f64(self.config.stage_timeout_s);
+        // Start of the current pause with the angles held during it, and the
+        // time spent in earlier pauses
+        let mut hold: Option<(Duration, BTreeMap<u8, f64>)> = None;
+        let mut paused_for = Duration::ZERO;
+        ControlLoop::new(PARK_RATE_HZ).run(running, |info| {
+            if paused.load(Ordering::SeqCst) {
+                if hold.is_none() {
+                    let measured = stage
+                        .targets
+                        .keys()
+                        .map(|&id| Ok((id, self.controller.read_motor_state(id)?.position_deg)))
+                        .collect::<Result<_>>()?;
+                    hold = Some((info.elapsed, measured));
+                }
+                for (&id, &angle) in hold.iter().flat_map(|(_, angles)| angles) {
+                    self.send_target(id, angle)?;
+                }
+                return Ok(true);
+            }
+            if let Some((since, _)) = hold.take() {
+                paused_for += info.elapsed - since;
+            }
+
+            let mut settled = true;
+            for (&id, &target) in &stage.targets {
+                self.send_target(id, target)?;
+                let measured = self.controller.read_motor_state(id)?.position_deg;
+                settled &= (measured - target).abs() <= self.config.tolerance_deg;
+            }
+
+            if !settled && info.elapsed - paused_for > timeout {
+                return Err(anyhow!("park stage '{}' did not settle within {:?}", stage.name, timeout));
+            }
+            Ok(!settled)
+        })
+    }
+
+    fn send_target(&self, motor_id: u8, angle_deg: f64) -> Result<()> {
+        Ok(self.controller.send_angle_command_to(
+            motor_id,
+            crate::degrees_to_position(angle_deg),
+            crate::rps_to_velocity(self.config.max_vel_rps),
+            crate::nm_to_torque(self.config.max_torque_nm),
+        )?)
+    }
+}
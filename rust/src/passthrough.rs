@@ -0,0 +1,207 @@
+//! Third-party frame pass-through
+//!
+//! Sensors such as an IMU often sit on the motor bus. Decoders registered with
+//! a [`PassThrough`] turn their frames into [`DecodedSample`]s, which are
+//! published on the [`EventBus`] and, through the fusion hook, paired with the
+//! motor feedback in the [`StateCache`] at the sample's receive time, e.g. for
+//! a balance controller. Frames of custom commands registered with
+//! [`plugins`](crate::plugins) are decoded as well.
+
+use crate::events::{EventBus, EventKind};
+use crate::{BusSubscription, CachedState, CanBus, Endianness, ScaledField, StateCache};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use socketcan::{CanFrame, EmbeddedFrame};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Values decoded from one third-party frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedSample {
+    /// Name of the decoder that produced the sample
+    pub source: String,
+    pub can_id: u32,
+    /// When the frame was received
+    pub timestamp: Instant,
+    pub values: Vec<(String, f64)>,
+}
+
+impl DecodedSample {
+    /// Value called `name`
+    pub fn value(&self, name: &str) -> Option<f64> {
+        self.values.iter().find(|(n, _)| n == name).map(|&(_, v)| v)
+    }
+}
+
+/// Decodes the frames of one third-party node
+pub trait FrameDecoder: Send {
+    fn name(&self) -> &str;
+
+    /// Named values of the frame, `None` if it is not this node's frame
+    fn decode(&mut self, can_id: u32, data: &[u8]) -> Option<Vec<(String, f64)>>;
+}
+
+/// A named field of a [`FieldDecoder`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedField {
+    pub name: String,
+    #[serde(flatten)]
+    pub field: ScaledField,
+}
+
+/// Decoder described by data: fixed-position scaled fields at one CAN ID
+///
+/// ```json
+/// { "name": "imu", "id": 1280, "fields": [
+///     { "name": "pitch_deg", "offset": 0, "size": 2, "signed": true, "scale": 0.01 },
+///     { "name": "roll_deg",  "offset": 2, "size": 2, "signed": true, "scale": 0.01 } ] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDecoder {
+    pub name: String,
+    pub id: u32,
+    #[serde(default)]
+    pub endianness: Endianness,
+    pub fields: Vec<NamedField>,
+}
+
+impl FieldDecoder {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read frame decoder {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+impl FrameDecoder for FieldDecoder {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn decode(&mut self, can_id: u32, data: &[u8]) -> Option<Vec<(String, f64)>> {
+        if can_id != self.id {
+            return None;
+        }
+        self.fields
+            .iter()
+            .map(|f| Some((f.name.clone(), f.field.decode(data, self.endianness)?)))
+            .collect()
+    }
+}
+
+/// A decoded sample with the motor feedback known when it arrived
+#[derive(Debug, Clone)]
+pub struct AlignedSample {
+    pub sample: DecodedSample,
+    /// Latest cached state of each fusion motor that has one
+    pub motors: Vec<CachedState>,
+}
+
+impl AlignedSample {
+    /// Feedback of `motor_id` and how much older (positive) or newer
+    /// (negative) than the sample it is, in seconds
+    pub fn motor(&self, motor_id: u8) -> Option<(&CachedState, f64)> {
+        let cached = self.motors.iter().find(|c| c.state.motor_id == motor_id)?;
+        let skew = if self.sample.timestamp >= cached.timestamp {
+            (self.sample.timestamp - cached.timestamp).as_secs_f64()
+        } else {
+            -(cached.timestamp - self.sample.timestamp).as_secs_f64()
+        };
+        Some((cached, skew))
+    }
+}
+
+type FusionHook = Box<dyn FnMut(&AlignedSample) + Send>;
+
+/// Runs registered decoders over the frames of the shared bus
+pub struct PassThrough {
+    subscription: BusSubscription,
+    decoders: Vec<Box<dyn FrameDecoder>>,
+    events: Arc<EventBus>,
+    fusion: Option<(Arc<StateCache>, Vec<u8>, FusionHook)>,
+}
+
+impl PassThrough {
+    /// Publish decoded samples on `events`
+    pub fn new(bus: &Arc<CanBus>, events: Arc<EventBus>) -> Self {
+        Self {
+            subscription: bus.subscribe(),
+            decoders: Vec::new(),
+            events,
+            fusion: None,
+        }
+    }
+
+    /// Add a decoder; the first decoder accepting a frame handles it
+    pub fn register<D: FrameDecoder + 'static>(&mut self, decoder: D) -> &mut Self {
+        self.decoders.push(Box::new(decoder));
+        self
+    }
+
+    /// Call `hook` with every sample and the cached feedback of `motor_ids`
+    pub fn with_fusion<F>(mut self, cache: Arc<StateCache>, motor_ids: &[u8], hook: F) -> Self
+    where
+        F: FnMut(&AlignedSample) + Send + 'static,
+    {
+        self.fusion = Some((cache, motor_ids.to_vec(), Box::new(hook)));
+        self
+    }
+
+    /// Names of the registered decoders
+    pub fn decoders(&self) -> Vec<&str> {
+        self.decoders.iter().map(|d| d.name()).collect()
+    }
+
+    /// Decode frames for up to `timeout`, returns the number of samples produced
+    pub fn poll(&mut self, timeout: Duration) -> Result<usize> {
+        let deadline = Instant::now() + timeout;
+        let mut produced = 0;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let Some(frame) = self.subscription.recv_timeout(remaining)? else {
+                break;
+            };
+            if let Some(sample) = self.decode(&frame) {
+                self.deliver(sample);
+                produced += 1;
+            }
+        }
+        Ok(produced)
+    }
+
+    fn decode(&mut self, frame: &CanFrame) -> Option<DecodedSample> {
+        let timestamp = Instant::now();
+        let can_id = crate::raw_id(frame);
+        let sample = self.decoders.iter_mut().find_map(|decoder| {
+            let values = decoder.decode(can_id, frame.data())?;
+            Some(DecodedSample {
+                source: decoder.name().to_string(),
+                can_id,
+                timestamp,
+                values,
+            })
+        });
+        // Custom firmware commands from the plugin registry
+        sample.or_else(|| {
+            let decoded = crate::plugins::decode(frame.data()).filter(|_| crate::shaping::is_motor_frame(frame))?;
+            Some(DecodedSample {
+                source: decoded.plugin,
+                can_id,
+                timestamp,
+                values: decoded.values,
+            })
+        })
+    }
+
+    fn deliver(&mut self, sample: DecodedSample) {
+        if let Some((cache, motor_ids, hook)) = &mut self.fusion {
+            let motors = motor_ids.iter().filter_map(|&id| cache.get(id)).collect();
+            hook(&AlignedSample {
+                sample: sample.clone(),
+                motors,
+            });
+        }
+        self.events.publish(EventKind::Decoded(sample));
+    }
+}
@@ -0,0 +1,165 @@
+//! Custom command frames
+//!
+//! Lab firmware often adds commands beyond the register protocol. A
+//! [`CommandCodec`] registered here, keyed by its command byte (the first
+//! payload byte), lets the controller send such frames and the sniffer and
+//! the [`PassThrough`](crate::PassThrough) demultiplexer decode them, without
+//! forking the crate. Registration is process-wide, so a downstream crate only
+//! has to call [`register`] once at startup.
+
+use crate::protocol::PADDING;
+use crate::{Endianness, NamedField};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Command bytes taken by the register protocol (`0x0_` write, `0x1_` read, `0x2_` reply)
+pub const BUILTIN_COMMANDS: RangeInclusive<u8> = 0x00..=0x2F;
+
+/// Encoder and decoder of one custom command
+pub trait CommandCodec: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Command byte, first byte of the payload
+    fn command(&self) -> u8;
+
+    /// Payload for `values`, starting with the command byte
+    fn encode(&self, values: &[f64]) -> Result<Vec<u8>>;
+
+    /// Named values of a payload starting with the command byte
+    fn decode(&self, data: &[u8]) -> Option<Vec<(String, f64)>>;
+}
+
+/// A decoded custom command
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedCommand {
+    /// Name of the codec
+    pub plugin: String,
+    pub command: u8,
+    pub values: Vec<(String, f64)>,
+}
+
+/// Codec described by data: scaled fields after the command byte
+///
+/// ```json
+/// { "name": "cogging_table", "command": 48, "fields": [
+///     { "name": "index", "offset": 1, "size": 1, "scale": 1.0 },
+///     { "name": "offset_nm", "offset": 2, "size": 2, "signed": true, "scale": 0.001 } ] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldCommand {
+    pub name: String,
+    pub command: u8,
+    #[serde(default)]
+    pub endianness: Endianness,
+    pub fields: Vec<NamedField>,
+}
+
+impl FieldCommand {
+    /// Load a list of commands from JSON
+    pub fn load_all<P: AsRef<Path>>(path: P) -> Result<Vec<Self>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read command plugins {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+impl CommandCodec for FieldCommand {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn command(&self) -> u8 {
+        self.command
+    }
+
+    fn encode(&self, values: &[f64]) -> Result<Vec<u8>> {
+        if values.len() != self.fields.len() {
+            return Err(anyhow!(
+                "command '{}' takes {} values, got {}",
+                self.name,
+                self.fields.len(),
+                values.len()
+            ));
+        }
+        let mut data = vec![PADDING; 8];
+        data[0] = self.command;
+        for (f, &value) in self.fields.iter().zip(values) {
+            f.field
+                .encode(value, &mut data, self.endianness)
+                .map_err(|e| anyhow!("command '{}', field '{}': {}", self.name, f.name, e))?;
+        }
+        Ok(data)
+    }
+
+    fn decode(&self, data: &[u8]) -> Option<Vec<(String, f64)>> {
+        if data.first() != Some(&self.command) {
+            return None;
+        }
+        self.fields
+            .iter()
+            .map(|f| Some((f.name.clone(), f.field.decode(data, self.endianness)?)))
+            .collect()
+    }
+}
+
+static REGISTRY: RwLock<BTreeMap<u8, Arc<dyn CommandCodec>>> = RwLock::new(BTreeMap::new());
+
+/// Register a codec; fails if its command byte is built in or already taken
+pub fn register<C: CommandCodec + 'static>(codec: C) -> Result<()> {
+    let command = codec.command();
+    if BUILTIN_COMMANDS.contains(&command) {
+        return Err(anyhow!(
+            "command 0x{:02X} of '{}' is reserved by the register protocol",
+            command,
+            codec.name()
+        ));
+    }
+
+    let mut registry = REGISTRY.write().unwrap();
+    if let Some(existing) = registry.get(&command) {
+        return Err(anyhow!(
+            "command 0x{:02X} of '{}' is already registered by '{}'",
+            command,
+            codec.name(),
+            existing.name()
+        ));
+    }
+    registry.insert(command, Arc::new(codec));
+    Ok(())
+}
+
+/// Remove the codec of `command`, returns false if there was none
+pub fn unregister(command: u8) -> bool {
+    REGISTRY.write().unwrap().remove(&command).is_some()
+}
+
+/// Codec registered for `command`
+pub fn lookup(command: u8) -> Option<Arc<dyn CommandCodec>> {
+    REGISTRY.read().unwrap().get(&command).cloned()
+}
+
+/// Command bytes and names of all registered codecs
+pub fn registered() -> Vec<(u8, String)> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(&command, codec)| (command, codec.name().to_string()))
+        .collect()
+}
+
+/// Decode a payload with the codec of its first byte
+pub fn decode(data: &[u8]) -> Option<DecodedCommand> {
+    let command = *data.first()?;
+    let codec = lookup(command)?;
+    Some(DecodedCommand {
+        plugin: codec.name().to_string(),
+        command,
+        values: codec.decode(data)?,
+    })
+}
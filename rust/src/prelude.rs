@@ -0,0 +1,38 @@
+//! The stable API
+//!
+//! ```no_run
+//! use livelybot_motor_control::prelude::*;
+//! ```
+//!
+//! Everything exported here follows semver: a breaking change to one of
+//! these items only comes with a new major version. Robot code that sticks to
+//! the prelude does not break when socketcan, the frame layout or internal
+//! modules change. The items deliberately contain no socketcan types; frames
+//! cross the API as [`RawFrame`] and other buses plug in as a
+//! [`CanTransport`].
+//!
+//! The rest of the crate stays public for tools and experiments, but may
+//! change in minor releases, in particular anything taking or returning
+//! socketcan frames ([`CanBus::send`](crate::CanBus::send),
+//! [`BusSubscription`](crate::BusSubscription),
+//! [`read_frame_with_timeout`](crate::LivelyMotorController::read_frame_with_timeout))
+//! and the protocol internals in [`protocol`](crate::protocol) and
+//! [`layout`](crate::layout).
+
+pub use crate::calibration::Calibration;
+pub use crate::enable::{ControlMode, EnableConfig};
+pub use crate::error::{FaultCode, MotorError};
+pub use crate::float::{Float, Real};
+pub use crate::group::MotorGroup;
+pub use crate::limits::{LimitMode, Limits};
+pub use crate::mock::{MockTransport, SimMotor};
+pub use crate::protocol::{Register, ValueType};
+pub use crate::report::{ReportField, ReportMask};
+pub use crate::telemetry::{MotorState, MotorTelemetry};
+pub use crate::trajectory::{Trajectory, Waypoint};
+pub use crate::transport::{CanTransport, RawFrame, SocketTransport};
+pub use crate::{
+    acceleration_to_rps2, degrees_to_position, nm_to_torque, position_to_degrees, rps2_to_acceleration,
+    rps_to_velocity, torque_to_nm, velocity_to_rps, LivelyMotorController, MotorInfo, FACTOR_ACC, FACTOR_POS,
+    FACTOR_TQE, FACTOR_VEL, MAGIC_POS,
+};
@@ -0,0 +1,257 @@
+//! Composable motion primitives
+//!
+//! A [`Primitive`] describes a motion (`MoveTo`, `Hold`, `Oscillate`, `Relax`)
+//! or a composition of motions (`Sequence`, `Repeat`). [`PrimitiveRunner`]
+//! executes a primitive tree on a [`ControlLoop`], streaming an angle setpoint
+//! to one motor every cycle.
+
+use crate::{ControlLoop, CycleInfo, LivelyMotorController};
+use anyhow::Result;
+use std::f64::consts::PI;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+/// Parameters of a sinusoidal oscillation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OscillateParams {
+    pub center_deg: f64,
+    pub amplitude_deg: f64,
+    pub frequency_hz: f64,
+    pub duration: Duration,
+}
+
+/// A motion primitive
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive {
+    /// Command `angle_deg` and keep commanding it for `settle`
+    MoveTo { angle_deg: f64, settle: Duration },
+    /// Keep the previous target for a while
+    Hold(Duration),
+    /// Sinusoid around a center angle
+    Oscillate(OscillateParams),
+    /// Disable the motor output; the next setpoint re-enables it
+    Relax,
+    /// Run primitives one after another
+    Sequence(Vec<Primitive>),
+    /// Run a primitive `times` times
+    Repeat { times: u32, body: Box<Primitive> },
+}
+
+impl Primitive {
+    pub fn move_to(angle_deg: f64, settle: Duration) -> Self {
+        Primitive::MoveTo { angle_deg, settle }
+    }
+
+    pub fn hold(duration: Duration) -> Self {
+        Primitive::Hold(duration)
+    }
+
+    pub fn oscillate(params: OscillateParams) -> Self {
+        Primitive::Oscillate(params)
+    }
+
+    pub fn relax() -> Self {
+        Primitive::Relax
+    }
+
+    pub fn sequence(steps: Vec<Primitive>) -> Self {
+        Primitive::Sequence(steps)
+    }
+
+    pub fn repeat(times: u32, body: Primitive) -> Self {
+        Primitive::Repeat {
+            times,
+            body: Box::new(body),
+        }
+    }
+
+    /// Append `next` to this primitive
+    pub fn then(self, next: Primitive) -> Self {
+        match self {
+            Primitive::Sequence(mut steps) => {
+                steps.push(next);
+                Primitive::Sequence(steps)
+            }
+            first => Primitive::Sequence(vec![first, next]),
+        }
+    }
+
+    /// Leaf primitives in execution order
+    pub fn steps(&self) -> Vec<&Primitive> {
+        let mut steps = Vec::new();
+        self.flatten_into(&mut steps);
+        steps
+    }
+
+    /// Total duration of the primitive
+    pub fn duration(&self) -> Duration {
+        self.steps().iter().map(|step| step.step_duration()).sum()
+    }
+
+    fn flatten_into<'a>(&'a self, steps: &mut Vec<&'a Primitive>) {
+        match self {
+            Primitive::Sequence(children) => {
+                for child in children {
+                    child.flatten_into(steps);
+                }
+            }
+            Primitive::Repeat { times, body } => {
+                for _ in 0..*times {
+                    body.flatten_into(steps);
+                }
+            }
+            leaf => steps.push(leaf),
+        }
+    }
+
+    fn step_duration(&self) -> Duration {
+        match self {
+            Primitive::MoveTo { settle, .. } => *settle,
+            Primitive::Hold(duration) => *duration,
+            Primitive::Oscillate(params) => params.duration,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Target of a leaf `t` into it, `None` for `Relax`
+    fn target_at(&self, t: Duration, previous: Option<f64>) -> Option<f64> {
+        match self {
+            Primitive::MoveTo { angle_deg, .. } => Some(*angle_deg),
+            Primitive::Hold(_) => previous,
+            Primitive::Oscillate(p) => {
+                let phase = 2.0 * PI * p.frequency_hz * t.as_secs_f64();
+                Some(p.center_deg + p.amplitude_deg * phase.sin())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Progress reported to the observer once per cycle
+#[derive(Debug, Clone, Copy)]
+pub struct PrimitiveStatus {
+    /// Index of the running leaf step
+    pub step: usize,
+    /// Number of leaf steps
+    pub steps: usize,
+    /// True on the first cycle of a step
+    pub step_started: bool,
+    /// Time spent in the current step
+    pub step_elapsed: Duration,
+    /// Planned duration of the current step
+    pub step_duration: Duration,
+    /// Commanded angle, `None` while relaxed
+    pub target_deg: Option<f64>,
+    pub cycle: CycleInfo,
+}
+
+/// Executes primitives on one motor
+pub struct PrimitiveRunner<'a> {
+    controller: &'a LivelyMotorController,
+    motor_id: u8,
+    rate_hz: f64,
+    max_vel_rps: f64,
+    max_torque_nm: f64,
+    relaxed: bool,
+}
+
+impl<'a> PrimitiveRunner<'a> {
+    /// Runner at 100 Hz with 2.0 r/s and 3.0 Nm limits; the motor must be enabled
+    pub fn new(controller: &'a LivelyMotorController, motor_id: u8) -> Self {
+        Self {
+            controller,
+            motor_id,
+            rate_hz: 100.0,
+            max_vel_rps: 2.0,
+            max_torque_nm: 3.0,
+            relaxed: false,
+        }
+    }
+
+    /// Setpoint streaming rate
+    pub fn with_rate(mut self, rate_hz: f64) -> Self {
+        self.rate_hz = rate_hz;
+        self
+    }
+
+    /// Velocity and torque limits sent with every setpoint
+    pub fn with_limits(mut self, max_vel_rps: f64, max_torque_nm: f64) -> Self {
+        self.max_vel_rps = max_vel_rps;
+        self.max_torque_nm = max_torque_nm;
+        self
+    }
+
+    /// Execute `primitive` until done or `running` is cleared
+    pub fn run<F>(&mut self, primitive: &Primitive, running: &AtomicBool, mut observe: F) -> Result<()>
+    where
+        F: FnMut(&PrimitiveStatus) -> Result<()>,
+    {
+        let steps = primitive.steps();
+        if steps.is_empty() {
+            return Ok(());
+        }
+
+        let mut index = 0;
+        let mut step_start = Duration::ZERO;
+        let mut step_started = true;
+        let mut target = None;
+        self.enter(steps[0], &mut target)?;
+
+        let mut control = ControlLoop::new(self.rate_hz);
+        control.run(running, |info| {
+            // Advance past finished (and zero-length) steps
+            while info.elapsed - step_start >= steps[index].step_duration() {
+                index += 1;
+                if index >= steps.len() {
+                    return Ok(false);
+                }
+                step_start = info.elapsed;
+                step_started = true;
+                self.enter(steps[index], &mut target)?;
+            }
+
+            let step = steps[index];
+            let step_elapsed = info.elapsed - step_start;
+            target = step.target_at(step_elapsed, target);
+
+            if let Some(angle_deg) = target {
+                self.send(angle_deg)?;
+            }
+
+            observe(&PrimitiveStatus {
+                step: index,
+                steps: steps.len(),
+                step_started,
+                step_elapsed,
+                step_duration: step.step_duration(),
+                target_deg: target,
+                cycle: *info,
+            })?;
+            step_started = false;
+            Ok(true)
+        })
+    }
+
+    /// Actions taken once when a step begins
+    fn enter(&mut self, step: &Primitive, target: &mut Option<f64>) -> Result<()> {
+        if let Primitive::Relax = step {
+            self.controller.disable_motor(self.motor_id)?;
+            self.relaxed = true;
+            *target = None;
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, angle_deg: f64) -> Result<()> {
+        if self.relaxed {
+            self.controller.enable_motor(self.motor_id)?;
+            self.relaxed = false;
+        }
+        Ok(self.controller.send_angle_command_to(
+            self.motor_id,
+            crate::degrees_to_position(angle_deg),
+            crate::rps_to_velocity(self.max_vel_rps),
+            crate::nm_to_torque(self.max_torque_nm),
+        )?)
+    }
+}
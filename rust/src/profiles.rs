@@ -0,0 +1,100 @@
+//! Named gain / limit profiles
+//!
+//! A [`ProfileSet`] holds several named parameter sets (e.g. `soft`, `normal`,
+//! `performance`) with [`MotorSettings`] per joint, imported from JSON or CSV.
+//! Switching profile at runtime rewrites the gains of every joint it covers, so
+//! a demo near people can drop to low stiffness without a restart.
+
+use crate::{LivelyMotorController, MotorSettings};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Settings per motor ID
+pub type Profile = BTreeMap<u8, MotorSettings>;
+
+/// Named profiles, loaded from JSON (`{"profiles": {"soft": {"1": {...}}}}`)
+/// or CSV (`profile,motor_id,kp,kd,torque_limit`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSet {
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ProfileSet {
+    /// Import a JSON (`.json`) or CSV (any other extension) file
+    pub fn import<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read profiles {}: {}", path.display(), e))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&text),
+            _ => Self::from_csv(&text),
+        }
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// Parse one row per profile and motor
+    pub fn from_csv(text: &str) -> Result<Self> {
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or(anyhow!("empty profile CSV"))?
+            .split(',')
+            .map(str::trim)
+            .collect();
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|h| *h == name)
+                .ok_or(anyhow!("profile CSV has no '{}' column", name))
+        };
+        let (profile, motor_id) = (column("profile")?, column("motor_id")?);
+        let (kp, kd, torque_limit) = (column("kp")?, column("kd")?, column("torque_limit")?);
+
+        let mut set = Self::default();
+        for (i, line) in lines.enumerate() {
+            let row: Vec<&str> = line.split(',').map(str::trim).collect();
+            let cell = |c: usize| row.get(c).copied().ok_or(anyhow!("CSV row {}: missing column {}", i + 1, c));
+            let float = |c: usize| -> Result<f32> {
+                cell(c)?.parse().map_err(|e| anyhow!("CSV row {}: {}", i + 1, e))
+            };
+
+            let id: u8 = cell(motor_id)?.parse().map_err(|e| anyhow!("CSV row {}: {}", i + 1, e))?;
+            let settings = MotorSettings {
+                kp: float(kp)?,
+                kd: float(kd)?,
+                torque_limit: float(torque_limit)?,
+            };
+            set.profiles.entry(cell(profile)?.to_string()).or_default().insert(id, settings);
+        }
+        Ok(set)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles
+            .get(name)
+            .ok_or(anyhow!("unknown profile '{}' (available: {})", name, self.names().join(", ")))
+    }
+
+    /// Write profile `name` to the given motors; motors the profile does not
+    /// cover keep their current settings. Returns the motors updated.
+    pub fn apply(&self, controller: &LivelyMotorController, name: &str, motor_ids: &[u8]) -> Result<Vec<u8>> {
+        let profile = self.profile(name)?;
+        let mut applied = Vec::new();
+        for &id in motor_ids {
+            if let Some(settings) = profile.get(&id) {
+                settings.write(controller, id)?;
+                applied.push(id);
+            }
+        }
+        Ok(applied)
+    }
+}
@@ -0,0 +1,758 @@
+//! Register-level protocol definitions
+//!
+//! The motor firmware speaks a register multiplex protocol: each payload starts
+//! with an opcode byte (`0x0_` write, `0x1_` read, `0x2_` reply) whose low bits
+//! select the value type and register count, followed by the start register.
+//! Unused bytes are filled with `0x50` (NOP) unless an [`EncodingPolicy`] says
+//! otherwise. Field offsets and byte order come from the policy's
+//! [`ProtocolLayout`].
+
+use crate::layout::{self, Endianness, ProtocolLayout};
+use crate::query::InfoQuery;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// Padding / NOP byte used to fill unused payload bytes
+pub const PADDING: u8 = 0x50;
+
+/// Arbitration ID flag asking the motor to send a reply
+pub const REPLY_FLAG: u32 = 0x8000;
+
+/// Arbitration ID of the angle stream command to every motor
+pub const ANGLE_STREAM_ID: u32 = 0x0090;
+
+/// Arbitration ID of the velocity + acceleration stream command to every motor
+pub const VELOCITY_STREAM_ID: u32 = 0x00AD;
+
+/// Arbitration ID of the MIT impedance command (see [`crate::mit`]); only
+/// sent addressed
+pub const MIT_STREAM_ID: u32 = 0x00B0;
+
+/// Arbitration ID of a stream command (`ANGLE_STREAM_ID`, `VELOCITY_STREAM_ID`
+/// or `MIT_STREAM_ID`) addressed to one motor
+///
+/// The motor ID goes in the second byte, so motor 0 is the unaddressed ID that
+/// every motor listens to.
+pub fn stream_id(stream: u32, motor_id: u8) -> u32 {
+    ((motor_id as u32 & 0x7F) << 8) | stream
+}
+
+/// Stream and addressed motor (0 = every motor) of a stream command ID
+pub fn stream_target(id: u32) -> Option<(u32, u8)> {
+    let stream = id & 0xFF;
+    let addressed = matches!(stream, ANGLE_STREAM_ID | VELOCITY_STREAM_ID | MIT_STREAM_ID) && id >> 8 < 0x80;
+    addressed.then_some((stream, (id >> 8) as u8))
+}
+
+// Opcode groups (high nibble)
+pub const OP_WRITE: u8 = 0x00;
+pub const OP_READ: u8 = 0x10;
+pub const OP_REPLY: u8 = 0x20;
+
+/// Value type encoded in bits 2..3 of an opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    Int8 = 0x00,
+    Int16 = 0x04,
+    Int32 = 0x08,
+    Float = 0x0C,
+}
+
+impl ValueType {
+    /// Size of one value in bytes
+    pub fn size(self) -> usize {
+        match self {
+            ValueType::Int8 => 1,
+            ValueType::Int16 => 2,
+            ValueType::Int32 | ValueType::Float => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueType::Int8 => "int8",
+            ValueType::Int16 => "int16",
+            ValueType::Int32 => "int32",
+            ValueType::Float => "float",
+        }
+    }
+
+    pub(crate) fn from_opcode(op: u8) -> Self {
+        match op & 0x0C {
+            0x00 => ValueType::Int8,
+            0x04 => ValueType::Int16,
+            0x08 => ValueType::Int32,
+            _ => ValueType::Float,
+        }
+    }
+}
+
+/// Known motor registers
+///
+/// 0x00–0x24 are the registers the reference Python and C++ tools use.
+/// 0x59–0x62 are not used by those tools and no firmware documentation for
+/// them is available: their addresses and meaning are unverified, see
+/// [`Register::is_verified`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Register {
+    /// Control mode (0x00 stop, 0x0A position, 0x0B velocity, 0x0C torque)
+    Mode = 0x00,
+    /// Measured position (int16 counts, FACTOR_POS per turn)
+    Position = 0x01,
+    /// Measured velocity (int16 counts, FACTOR_VEL per r/s)
+    Velocity = 0x02,
+    /// Measured torque (int16 counts, FACTOR_TQE per Nm)
+    Torque = 0x03,
+    /// Measured Q-axis (torque producing) current (float, A)
+    QCurrent = 0x04,
+    /// Supply voltage (float, V)
+    Voltage = 0x0D,
+    /// Controller temperature (float, °C)
+    Temperature = 0x0E,
+    /// Active fault code (int8, 0 = no fault)
+    Fault = 0x0F,
+    /// Torque limit (float, Nm)
+    TorqueLimit = 0x22,
+    /// Position loop Kp (float)
+    Kp = 0x23,
+    /// Position loop Kd (float)
+    Kd = 0x24,
+    /// Unverified: fields of automatically sent state replies (int8 bitmask, see
+    /// [`ReportMask`](crate::ReportMask))
+    ReportContent = 0x59,
+    /// Unverified: automatic state feedback period (int16, 0.1 ms; 0 = only on request)
+    FeedbackPeriod = 0x5A,
+    /// Unverified: writing 1 clears the fault history read with the error log query
+    ClearErrorLog = 0x5B,
+    /// Unverified: auxiliary digital input states (int8 bitmask, bit n = input n)
+    GpioInput = 0x5C,
+    /// Unverified: writing 1 stores the current configuration (ID, zero, gains, limits) in flash
+    SaveConfig = 0x5D,
+    /// Unverified: writing 1 sets the current position as zero
+    SetZero = 0x5E,
+    /// Unverified: CAN ID of the motor (int8, applied immediately)
+    MotorId = 0x5F,
+    /// Unverified: position counts per turn (float, newer firmware only)
+    ScalePosition = 0x60,
+    /// Unverified: velocity counts per r/s (float, newer firmware only)
+    ScaleVelocity = 0x61,
+    /// Unverified: torque counts per Nm (float, newer firmware only)
+    ScaleTorque = 0x62,
+}
+
+impl Register {
+    /// All known registers, in address order
+    pub const ALL: [Register; 21] = [
+        Register::Mode,
+        Register::Position,
+        Register::Velocity,
+        Register::Torque,
+        Register::QCurrent,
+        Register::Voltage,
+        Register::Temperature,
+        Register::Fault,
+        Register::TorqueLimit,
+        Register::Kp,
+        Register::Kd,
+        Register::ReportContent,
+        Register::FeedbackPeriod,
+        Register::ClearErrorLog,
+        Register::GpioInput,
+        Register::SaveConfig,
+        Register::SetZero,
+        Register::MotorId,
+        Register::ScalePosition,
+        Register::ScaleVelocity,
+        Register::ScaleTorque,
+    ];
+
+    pub fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// Machine-readable description of the register
+    /// Whether the register is used by the reference tools; the others
+    /// (0x59–0x62) are assumptions that no firmware documentation confirms
+    pub fn is_verified(self) -> bool {
+        !matches!(
+            self,
+            Register::ReportContent
+                | Register::FeedbackPeriod
+                | Register::ClearErrorLog
+                | Register::GpioInput
+                | Register::SaveConfig
+                | Register::SetZero
+                | Register::MotorId
+                | Register::ScalePosition
+                | Register::ScaleVelocity
+                | Register::ScaleTorque
+        )
+    }
+
+    pub fn info(self) -> RegisterInfo {
+        let (name, value_type, access, unit, description) = match self {
+            Register::Mode => ("mode", ValueType::Int8, Access::ReadWrite, "",
+                "Control mode (0x00 stop, 0x0A position, 0x0B velocity, 0x0C torque)"),
+            Register::Position => ("position", ValueType::Int16, Access::Read, "1/10000 turn",
+                "Measured position"),
+            Register::Velocity => ("velocity", ValueType::Int16, Access::Read, "1/4000 r/s",
+                "Measured velocity"),
+            Register::Torque => ("torque", ValueType::Int16, Access::Read, "1/200 Nm",
+                "Measured torque"),
+            Register::QCurrent => ("q_current", ValueType::Float, Access::Read, "A",
+                "Measured Q-axis current"),
+            Register::Voltage => ("voltage", ValueType::Float, Access::Read, "V", "Supply voltage"),
+            Register::Temperature => ("temperature", ValueType::Float, Access::Read, "°C",
+                "Controller temperature"),
+            Register::Fault => ("fault", ValueType::Int8, Access::Read, "",
+                "Active fault code, 0 = no fault"),
+            Register::TorqueLimit => ("torque_limit", ValueType::Float, Access::ReadWrite, "Nm",
+                "Torque limit"),
+            Register::Kp => ("kp", ValueType::Float, Access::ReadWrite, "", "Position loop Kp"),
+            Register::Kd => ("kd", ValueType::Float, Access::ReadWrite, "", "Position loop Kd"),
+            Register::ReportContent => ("report_content", ValueType::Int8, Access::ReadWrite, "",
+                "Fields of automatically sent replies: bit 0 position, 1 velocity, 2 torque, 3 temperature"),
+            Register::FeedbackPeriod => ("feedback_period", ValueType::Int16, Access::ReadWrite, "0.1 ms",
+                "Period of automatically sent position/velocity/torque replies, 0 = only on request"),
+            Register::ClearErrorLog => ("clear_error_log", ValueType::Int8, Access::Write, "",
+                "Writing 1 clears the fault history"),
+            Register::GpioInput => ("gpio_input", ValueType::Int8, Access::Read, "",
+                "Auxiliary digital inputs, bit n = input n"),
+            Register::SaveConfig => ("save_config", ValueType::Int8, Access::Write, "",
+                "Writing 1 stores the current configuration in flash"),
+            Register::SetZero => ("set_zero", ValueType::Int8, Access::Write, "",
+                "Writing 1 sets the current position as zero"),
+            Register::MotorId => ("motor_id", ValueType::Int8, Access::ReadWrite, "",
+                "CAN ID of the motor, applied immediately"),
+            Register::ScalePosition => ("scale_position", ValueType::Float, Access::Read, "counts/turn",
+                "Scaling of position values, FACTOR_POS expected"),
+            Register::ScaleVelocity => ("scale_velocity", ValueType::Float, Access::Read, "counts/(r/s)",
+                "Scaling of velocity values, FACTOR_VEL expected"),
+            Register::ScaleTorque => ("scale_torque", ValueType::Float, Access::Read, "counts/Nm",
+                "Scaling of torque values, FACTOR_TQE expected"),
+        };
+
+        RegisterInfo {
+            name,
+            address: self.addr(),
+            value_type: value_type.name(),
+            access,
+            unit,
+            description,
+            verified: self.is_verified(),
+        }
+    }
+}
+
+/// Register access direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// Description of a register, as dumped by `motor_protocol --dump`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegisterInfo {
+    pub name: &'static str,
+    pub address: u8,
+    pub value_type: &'static str,
+    pub access: Access,
+    pub unit: &'static str,
+    pub description: &'static str,
+    /// See [`Register::is_verified`]
+    pub verified: bool,
+}
+
+/// A field of a command frame payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub offset: usize,
+    pub value_type: &'static str,
+    /// Counts per unit (physical value = raw / scale)
+    pub scale: f64,
+    pub unit: &'static str,
+}
+
+/// Description of a command frame
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameInfo {
+    pub name: &'static str,
+    /// Arbitration ID; for addressed frames the motor ID is OR-ed in
+    pub arbitration_id: u32,
+    pub addressed: bool,
+    pub description: &'static str,
+    pub fields: Vec<FieldInfo>,
+}
+
+/// A named opcode or type code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Code {
+    pub name: &'static str,
+    pub value: u8,
+}
+
+/// Full protocol description: constants, frames and registers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProtocolDescription {
+    pub version: &'static str,
+    pub padding: u8,
+    pub reply_flag: u32,
+    pub opcodes: Vec<Code>,
+    pub value_types: Vec<Code>,
+    pub frames: Vec<FrameInfo>,
+    pub registers: Vec<RegisterInfo>,
+}
+
+/// Describe every implemented frame and register
+pub fn describe() -> ProtocolDescription {
+    let layout = ProtocolLayout::default();
+    let field = |frame: &layout::FrameLayout, name: &'static str, scale, unit| {
+        let field = frame.field(name).expect("default layout has every field");
+        FieldInfo {
+            name,
+            offset: field.offset,
+            value_type: field.value_type.name(),
+            scale,
+            unit,
+        }
+    };
+
+    let frames = vec![
+        FrameInfo {
+            name: "ping",
+            arbitration_id: REPLY_FLAG,
+            addressed: true,
+            description: "Read one int8 from register 0x00; any reply means the motor is online",
+            fields: vec![],
+        },
+        FrameInfo {
+            name: "info_query",
+            arbitration_id: REPLY_FLAG,
+            addressed: true,
+            description: "Ping with a sub-query selector (0x01 serial, 0x02 error log, 0x03 uptime, \
+                0x04 temperatures) and error log index after the header; answered with 0x51, selector, values",
+            fields: vec![],
+        },
+        FrameInfo {
+            name: "register_write",
+            arbitration_id: 0,
+            addressed: true,
+            description: "Opcode 0x0_ (type | count), start register, values",
+            fields: vec![],
+        },
+        FrameInfo {
+            name: "register_read",
+            arbitration_id: REPLY_FLAG,
+            addressed: true,
+            description: "Opcode 0x1_ (type | count), start register; answered with opcode 0x2_",
+            fields: vec![],
+        },
+        FrameInfo {
+            name: "angle_stream",
+            arbitration_id: ANGLE_STREAM_ID,
+            addressed: false,
+            description: "Position setpoint with velocity and torque limits; (id << 8) | 0x90 for one motor",
+            fields: vec![
+                field(&layout.angle_stream, "position", crate::FACTOR_POS, "turn"),
+                field(&layout.angle_stream, "max_velocity", crate::FACTOR_VEL, "r/s"),
+                field(&layout.angle_stream, "max_torque", crate::FACTOR_TQE, "Nm"),
+            ],
+        },
+        FrameInfo {
+            name: "velocity_stream",
+            arbitration_id: VELOCITY_STREAM_ID,
+            addressed: false,
+            description: "Velocity setpoint with acceleration; position -32768 = unlimited; \
+                (id << 8) | 0xAD for one motor",
+            fields: vec![
+                field(&layout.velocity_stream, "position", crate::FACTOR_POS, "turn"),
+                field(&layout.velocity_stream, "velocity", crate::FACTOR_VEL, "r/s"),
+                field(&layout.velocity_stream, "acceleration", crate::FACTOR_ACC, "r/s^2"),
+            ],
+        },
+        FrameInfo {
+            name: "mit_stream",
+            arbitration_id: MIT_STREAM_ID,
+            addressed: false,
+            description: "Sent to (id << 8) | 0xB0 only; big-endian bit fields position:16 velocity:12 \
+                kp:12 kd:12 torque:12, each mapped linearly onto its range",
+            fields: vec![],
+        },
+    ];
+
+    ProtocolDescription {
+        version: env!("CARGO_PKG_VERSION"),
+        padding: PADDING,
+        reply_flag: REPLY_FLAG,
+        opcodes: vec![
+            Code { name: "write", value: OP_WRITE },
+            Code { name: "read", value: OP_READ },
+            Code { name: "reply", value: OP_REPLY },
+        ],
+        value_types: [ValueType::Int8, ValueType::Int16, ValueType::Int32, ValueType::Float]
+            .iter()
+            .map(|&t| Code { name: t.name(), value: t as u8 })
+            .collect(),
+        frames,
+        registers: Register::ALL.iter().map(|r| r.info()).collect(),
+    }
+}
+
+/// How payloads are filled and checked
+///
+/// Unused bytes are filled with `padding` (0x50 on current firmware). In strict
+/// mode every outgoing frame is checked to be exactly 8 bytes with all bytes
+/// outside the encoded fields equal to `padding`. Offsets and byte order of the
+/// fields come from `layout`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingPolicy {
+    pub padding: u8,
+    pub strict: bool,
+    layout: Arc<ProtocolLayout>,
+}
+
+impl Default for EncodingPolicy {
+    fn default() -> Self {
+        Self::new(PADDING, false)
+    }
+}
+
+impl EncodingPolicy {
+    pub fn new(padding: u8, strict: bool) -> Self {
+        Self {
+            padding,
+            strict,
+            layout: Arc::new(ProtocolLayout::default()),
+        }
+    }
+
+    /// Use a different frame layout, e.g. one loaded with [`ProtocolLayout::load`]
+    pub fn with_layout(mut self, layout: ProtocolLayout) -> Result<Self> {
+        layout.validate()?;
+        self.layout = Arc::new(layout);
+        Ok(self)
+    }
+
+    pub fn layout(&self) -> &ProtocolLayout {
+        &self.layout
+    }
+
+    /// 8-byte payload starting with `fields`, the rest filled with padding
+    pub fn payload(&self, fields: &[u8]) -> [u8; 8] {
+        let mut data = [self.padding; 8];
+        let len = fields.len().min(8);
+        data[..len].copy_from_slice(&fields[..len]);
+        data
+    }
+
+    /// Register frame: header followed by `values` of type `ty`
+    fn register_frame(&self, opcode: u8, register: u8, ty: ValueType, values: &[f64]) -> [u8; 8] {
+        let layout = &self.layout;
+        let mut data = layout.register_header.encode(
+            layout.endianness,
+            self.padding,
+            &[opcode as f64, register as f64],
+        );
+        let mut offset = layout.register_values_offset();
+        for &value in values {
+            let Some(out) = data.get_mut(offset..offset + ty.size()) else {
+                break;
+            };
+            layout::put(out, ty, value, layout.endianness);
+            offset += ty.size();
+        }
+        data
+    }
+
+    /// Payload writing one int8 value to `reg`
+    pub fn write_int8(&self, reg: Register, value: i8) -> [u8; 8] {
+        self.register_frame(OP_WRITE | ValueType::Int8 as u8 | 0x01, reg.addr(), ValueType::Int8, &[value as f64])
+    }
+
+    /// Payload writing one int16 value to `reg`
+    pub fn write_int16(&self, reg: Register, value: i16) -> [u8; 8] {
+        self.register_frame(OP_WRITE | ValueType::Int16 as u8 | 0x01, reg.addr(), ValueType::Int16, &[value as f64])
+    }
+
+    /// Payload writing one float value to `reg`
+    pub fn write_float(&self, reg: Register, value: f32) -> [u8; 8] {
+        self.register_frame(OP_WRITE | ValueType::Float as u8 | 0x01, reg.addr(), ValueType::Float, &[value as f64])
+    }
+
+    /// Payload reading `count` (1..=3) registers of `ty` starting at `reg`
+    pub fn read(&self, reg: Register, ty: ValueType, count: u8) -> [u8; 8] {
+        self.register_frame(OP_READ | ty as u8 | (count & 0x03), reg.addr(), ty, &[])
+    }
+
+    /// Payload of a motor's reply with `values` (1..=3) of `ty` starting at
+    /// `register`, as built by simulators such as [`MockTransport`](crate::MockTransport)
+    pub fn reply(&self, register: u8, ty: ValueType, values: &[f64]) -> [u8; 8] {
+        let count = values.len().min(3) as u8;
+        self.register_frame(OP_REPLY | ty as u8 | count, register, ty, &values[..count as usize])
+    }
+
+    /// Payload of an info query (see [`crate::query`]); `index` is only sent
+    /// with the error log query
+    pub fn query(&self, query: InfoQuery, index: u8) -> [u8; 8] {
+        let mut data = self.read(Register::Mode, ValueType::Int8, 1);
+        let offset = self.layout.register_values_offset();
+        if query != InfoQuery::Identity {
+            data[offset] = query.selector();
+        }
+        if query == InfoQuery::ErrorLog {
+            data[offset + 1] = index;
+        }
+        data
+    }
+
+    /// Payload of a 0x90 angle stream command
+    pub fn angle_stream(&self, position: i16, max_vel: i16, max_tqe: i16) -> [u8; 8] {
+        self.layout.angle_stream.encode(
+            self.layout.endianness,
+            self.padding,
+            &[position as f64, max_vel as f64, max_tqe as f64],
+        )
+    }
+
+    /// Payload of a 0xAD velocity stream command
+    pub fn velocity_stream(&self, position: i16, velocity: i16, acceleration: i16) -> [u8; 8] {
+        self.layout.velocity_stream.encode(
+            self.layout.endianness,
+            self.padding,
+            &[position as f64, velocity as f64, acceleration as f64],
+        )
+    }
+
+    /// Check length and reserved bytes of an outgoing frame
+    pub fn validate(&self, id: u32, data: &[u8]) -> Result<()> {
+        if data.len() != 8 {
+            return Err(anyhow!("payload must be 8 bytes, got {}", data.len()));
+        }
+
+        // Bytes covered by fields; everything else must be padding
+        let mut used = [false; 8];
+        let mut cover = |start: usize, end: usize| used[start..end.min(8)].iter_mut().for_each(|u| *u = true);
+
+        let stream = match stream_target(id) {
+            Some((ANGLE_STREAM_ID, _)) => Some(&self.layout.angle_stream),
+            Some((VELOCITY_STREAM_ID, _)) => Some(&self.layout.velocity_stream),
+            // Bit fields cover all 8 bytes
+            Some((MIT_STREAM_ID, _)) => return Ok(()),
+            _ => None,
+        };
+        if let Some(frame) = stream {
+            frame.fields.iter().for_each(|f| cover(f.offset, f.end()));
+        } else if crate::plugins::lookup(data[0]).is_some() {
+            // Custom commands define their own layout
+            return Ok(());
+        } else {
+            let opcode = data[self.layout.header_offset("opcode")];
+            let count = (opcode & 0x03) as usize;
+            if count == 0 {
+                return Err(anyhow!("opcode 0x{:02X} has a register count of 0", opcode));
+            }
+            let register = data[self.layout.header_offset("register")];
+            let values = match opcode & 0xF0 {
+                OP_WRITE | OP_REPLY => count * ValueType::from_opcode(opcode).size(),
+                // Info queries carry a selector and an index after the header
+                OP_READ if opcode == OP_READ | 0x01 && register == Register::Mode.addr() => 2,
+                OP_READ => 0,
+                _ => return Err(anyhow!("unknown opcode 0x{:02X}", opcode)),
+            };
+            let start = self.layout.register_values_offset();
+            if start + values > data.len() {
+                return Err(anyhow!("opcode 0x{:02X} needs {} bytes", opcode, start + values));
+            }
+            self.layout.register_header.fields.iter().for_each(|f| cover(f.offset, f.end()));
+            cover(start, start + values);
+        }
+
+        if let Some(i) = (0..8).find(|&i| !used[i] && data[i] != self.padding) {
+            return Err(anyhow!(
+                "reserved byte {} is 0x{:02X}, expected padding 0x{:02X}",
+                i,
+                data[i],
+                self.padding
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse a register reply payload (`0x2_` opcode)
+    pub fn parse_reply(&self, data: &[u8]) -> Result<RegisterReply> {
+        let layout = &self.layout;
+        let header = |name| data.get(layout.header_offset(name)).copied();
+        let opcode = header("opcode").ok_or(anyhow!("Not a register reply"))?;
+        if opcode & 0xF0 != OP_REPLY {
+            return Err(anyhow!("Not a register reply"));
+        }
+        let value_type = ValueType::from_opcode(opcode);
+        let count = (opcode & 0x03) as usize;
+        let start = layout.register_values_offset();
+        TruncatedFrame::check("register reply", data, start + count * value_type.size())?;
+        let register = header("register").ok_or(anyhow!("Not a register reply"))?;
+        let raw = data[start..start + count * value_type.size()].to_vec();
+
+        Ok(RegisterReply {
+            register,
+            value_type,
+            endianness: layout.endianness,
+            raw,
+        })
+    }
+}
+
+/// A received frame shorter than its content requires (DLC < 8 or a short reply)
+///
+/// Returned inside the `anyhow::Error` of the parsers, so callers can tell a
+/// malformed frame from one that is simply not what they wait for:
+/// `error.is::<TruncatedFrame>()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncatedFrame {
+    pub kind: &'static str,
+    /// Bytes received
+    pub len: usize,
+    /// Bytes the content needs
+    pub needed: usize,
+}
+
+impl TruncatedFrame {
+    /// `Ok` if `data` holds at least `needed` bytes
+    pub fn check(kind: &'static str, data: &[u8], needed: usize) -> Result<()> {
+        if data.len() < needed {
+            return Err(TruncatedFrame { kind, len: data.len(), needed }.into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TruncatedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} truncated: {} of {} bytes", self.kind, self.len, self.needed)
+    }
+}
+
+impl std::error::Error for TruncatedFrame {}
+
+/// Build a payload writing one int8 value to `reg`
+pub fn encode_write_int8(reg: Register, value: i8) -> [u8; 8] {
+    EncodingPolicy::default().write_int8(reg, value)
+}
+
+/// Build a payload writing one float value to `reg`
+pub fn encode_write_float(reg: Register, value: f32) -> [u8; 8] {
+    EncodingPolicy::default().write_float(reg, value)
+}
+
+/// Build a payload reading `count` (1..=3) registers of `ty` starting at `reg`
+pub fn encode_read(reg: Register, ty: ValueType, count: u8) -> [u8; 8] {
+    EncodingPolicy::default().read(reg, ty, count)
+}
+
+/// A decoded register reply
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterReply {
+    pub register: u8,
+    pub value_type: ValueType,
+    pub endianness: Endianness,
+    pub raw: Vec<u8>,
+}
+
+impl RegisterReply {
+    /// Return the `index`-th value as an integer (sign extended)
+    pub fn int(&self, index: usize) -> Option<i32> {
+        self.value(index).map(|v| v as i32)
+    }
+
+    /// Return the `index`-th value as a float
+    pub fn float(&self, index: usize) -> Option<f32> {
+        self.value(index).map(|v| v as f32)
+    }
+
+    fn value(&self, index: usize) -> Option<f64> {
+        let size = self.value_type.size();
+        let bytes = self.raw.get(index * size..(index + 1) * size)?;
+        layout::read_value(bytes, self.value_type, self.endianness)
+    }
+}
+
+/// Parse a register reply payload (`0x2_` opcode) with the default layout
+pub fn parse_reply(data: &[u8]) -> Result<RegisterReply> {
+    EncodingPolicy::default().parse_reply(data)
+}
+
+/// One-line description of a frame for sniffers, including registered
+/// custom commands
+pub fn describe_frame(id: u32, data: &[u8]) -> String {
+    let stream = |name: &str, labels: [&str; 3]| {
+        let value = |i: usize| data.get(2 * i..2 * i + 2).map(|b| i16::from_le_bytes([b[0], b[1]]));
+        let fields: Vec<String> = labels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, label)| Some(format!("{}={}", label, value(i)?)))
+            .collect();
+        format!("{} {}", name, fields.join(" "))
+    };
+    let motor = id & 0x7F;
+
+    if let Some((kind, target)) = stream_target(id) {
+        let described = match kind {
+            ANGLE_STREAM_ID => stream("angle stream", ["pos", "vel", "tqe"]),
+            VELOCITY_STREAM_ID => stream("velocity stream", ["pos", "vel", "acc"]),
+            _ => match crate::mit::MitCommand::decode(data, &Default::default()) {
+                Ok(c) => format!(
+                    "mit pos={:.1} vel={:.2} kp={:.1} kd={:.2} tqe={:.2}",
+                    c.position_deg, c.velocity_rps, c.kp, c.kd, c.torque_nm
+                ),
+                Err(e) => format!("mit {}", e),
+            },
+        };
+        return match target {
+            0 => described,
+            _ => format!("motor {} {}", target, described),
+        };
+    }
+    if id > 0xFFFF {
+        return "foreign".to_string();
+    }
+
+    if let Some(decoded) = crate::plugins::decode(data) {
+        let values: Vec<String> = decoded.values.iter().map(|(n, v)| format!("{}={}", n, v)).collect();
+        return format!("motor {} {} {}", motor, decoded.plugin, values.join(" "));
+    }
+
+    let Some(&opcode) = data.first() else {
+        return format!("motor {} empty", motor);
+    };
+    let register = data.get(1).copied().unwrap_or(0);
+    let info_query = |byte: Option<&u8>| {
+        InfoQuery::ALL
+            .iter()
+            .find(|q| q.selector() == *byte.unwrap_or(&PADDING))
+            .map_or("identity", |q| q.name())
+    };
+    if opcode == crate::query::QUERY_REPLY {
+        return format!("motor {} {} reply", motor, info_query(data.get(1)));
+    }
+    if opcode == OP_READ | 0x01 && register == Register::Mode.addr() {
+        return format!("motor {} query {}", motor, info_query(data.get(2)));
+    }
+    let name = Register::ALL
+        .iter()
+        .find(|r| r.addr() == register)
+        .map_or_else(|| format!("0x{:02X}", register), |r| r.info().name.to_string());
+    match opcode & 0xF0 {
+        OP_WRITE => format!("motor {} write {}", motor, name),
+        OP_READ => format!("motor {} read {} x{}", motor, name, opcode & 0x03),
+        OP_REPLY => format!("motor {} reply {}", motor, name),
+        _ => format!("motor {} unknown command 0x{:02X}", motor, opcode),
+    }
+}
@@ -0,0 +1,148 @@
+//! Python bindings (feature `python`)
+//!
+//! Exports the [`sdk_compat`](crate::sdk_compat) types under the class names
+//! of the official SDK, so `import livelybot_motor_control as
+//! livelybot_hardware_sdk` is usually the only change a script needs. Build
+//! with `maturin build --release` from the `rust/` directory.
+
+use crate::sdk_compat::{LivelyRobot, Motor, MotorBack};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use std::sync::Arc;
+
+fn to_py_err(e: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{:#}", e))
+}
+
+/// SDK `motor_back_t`
+#[pyclass(name = "motor_back_t", module = "livelybot_motor_control", frozen)]
+#[derive(Clone, Copy)]
+struct PyMotorBack(MotorBack);
+
+#[pymethods]
+impl PyMotorBack {
+    #[getter]
+    fn time(&self) -> f64 {
+        self.0.time
+    }
+
+    #[getter(ID)]
+    fn id(&self) -> u8 {
+        self.0.id
+    }
+
+    #[getter]
+    fn fault(&self) -> u8 {
+        self.0.fault
+    }
+
+    #[getter]
+    fn position(&self) -> f64 {
+        self.0.position
+    }
+
+    #[getter]
+    fn velocity(&self) -> f64 {
+        self.0.velocity
+    }
+
+    #[getter]
+    fn torque(&self) -> f64 {
+        self.0.torque
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "motor_back_t(ID={}, position={:.4}, velocity={:.4}, torque={:.3})",
+            self.0.id, self.0.position, self.0.velocity, self.0.torque
+        )
+    }
+}
+
+/// SDK `motor`
+#[pyclass(name = "motor", module = "livelybot_motor_control", frozen)]
+struct PyMotor(Arc<Motor>);
+
+#[pymethods]
+impl PyMotor {
+    fn get_motor_id(&self) -> u8 {
+        self.0.get_motor_id()
+    }
+
+    fn get_current_motor_state(&self, py: Python<'_>) -> PyResult<PyMotorBack> {
+        py.allow_threads(|| self.0.get_current_motor_state())
+            .map(PyMotorBack)
+            .map_err(to_py_err)
+    }
+
+    fn position(&self, py: Python<'_>, position: f64) -> PyResult<()> {
+        py.allow_threads(|| self.0.position(position)).map_err(to_py_err)
+    }
+
+    fn velocity(&self, py: Python<'_>, velocity: f64) -> PyResult<()> {
+        py.allow_threads(|| self.0.velocity(velocity)).map_err(to_py_err)
+    }
+
+    #[allow(non_snake_case)]
+    fn pos_vel_MAXtqe(&self, py: Python<'_>, position: f64, velocity: f64, torque_max: f64) -> PyResult<()> {
+        py.allow_threads(|| self.0.pos_vel_MAXtqe(position, velocity, torque_max))
+            .map_err(to_py_err)
+    }
+
+    fn pos_vel_tqe_kp_kd(
+        &self,
+        py: Python<'_>,
+        position: f64,
+        velocity: f64,
+        torque: f64,
+        kp: f32,
+        kd: f32,
+    ) -> PyResult<()> {
+        py.allow_threads(|| self.0.pos_vel_tqe_kp_kd(position, velocity, torque, kp, kd))
+            .map_err(to_py_err)
+    }
+}
+
+/// SDK `lively_robot`
+#[pyclass(name = "lively_robot", module = "livelybot_motor_control", frozen)]
+struct PyLivelyRobot(LivelyRobot);
+
+#[pymethods]
+impl PyLivelyRobot {
+    #[new]
+    #[pyo3(signature = (motor_ids, channel = "can0", bitrate = 1_000_000))]
+    fn new(py: Python<'_>, motor_ids: Vec<u8>, channel: &str, bitrate: u32) -> PyResult<Self> {
+        py.allow_threads(|| LivelyRobot::new(channel, bitrate, &motor_ids))
+            .map(PyLivelyRobot)
+            .map_err(to_py_err)
+    }
+
+    #[getter(Motors)]
+    fn motors(&self) -> Vec<PyMotor> {
+        self.0.motors().iter().map(|m| PyMotor(Arc::clone(m))).collect()
+    }
+
+    fn motor_send_2(&self) -> PyResult<()> {
+        self.0.motor_send_2().map_err(to_py_err)
+    }
+
+    fn set_stop(&self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.0.set_stop()).map_err(to_py_err)
+    }
+
+    fn set_reset(&self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.0.set_reset()).map_err(to_py_err)
+    }
+
+    fn set_reset_zero(&self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.0.set_reset_zero()).map_err(to_py_err)
+    }
+}
+
+#[pymodule]
+fn livelybot_motor_control(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMotorBack>()?;
+    m.add_class::<PyMotor>()?;
+    m.add_class::<PyLivelyRobot>()?;
+    Ok(())
+}
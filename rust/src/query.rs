@@ -0,0 +1,197 @@
+//! Info query family
+//!
+//! The ping is an int8 read of register 0x00 (`0x11 0x00`, rest padding) that
+//! the firmware answers with an identity frame starting with `0x51` instead of
+//! a register reply. The byte after the register selects other sub-queries of
+//! the same family; padding (`0x50`) there is the plain identity query. Replies
+//! to sub-queries start with `0x51` followed by the echoed selector, so they
+//! can be told apart from the identity reply and from each other.
+//!
+//! | selector | query          | reply after `0x51 sel`                        |
+//! |----------|----------------|-----------------------------------------------|
+//! | `0x50`   | identity       | (no echo) name: 3 × ASCII, hw version: 4 × ASCII |
+//! | `0x01`   | serial number  | u32                                           |
+//! | `0x02`   | error log      | index: u8, fault code: u8, uptime at fault: u32 s |
+//! | `0x03`   | uptime         | u32 s                                         |
+//! | `0x04`   | temperatures   | motor, driver, MCU: i16 0.1 °C                |
+//!
+//! The index of an error log query goes in the byte after the selector. All
+//! values are little endian. Firmware that does not implement a sub-query does
+//! not answer it.
+
+use crate::protocol::TruncatedFrame;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// First byte of every reply of the family
+pub const QUERY_REPLY: u8 = 0x51;
+
+/// Error log entries kept by the firmware
+pub const ERROR_LOG_LEN: u8 = 16;
+
+/// Sub-query selected by the byte after the register
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InfoQuery {
+    /// Name and hardware version (the ping)
+    Identity = 0x50,
+    SerialNumber = 0x01,
+    /// One error log entry, by index (0 = most recent)
+    ErrorLog = 0x02,
+    Uptime = 0x03,
+    Temperatures = 0x04,
+}
+
+impl InfoQuery {
+    pub const ALL: [InfoQuery; 5] = [
+        InfoQuery::Identity,
+        InfoQuery::SerialNumber,
+        InfoQuery::ErrorLog,
+        InfoQuery::Uptime,
+        InfoQuery::Temperatures,
+    ];
+
+    pub fn selector(self) -> u8 {
+        self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InfoQuery::Identity => "identity",
+            InfoQuery::SerialNumber => "serial_number",
+            InfoQuery::ErrorLog => "error_log",
+            InfoQuery::Uptime => "uptime",
+            InfoQuery::Temperatures => "temperatures",
+        }
+    }
+
+    /// Whether `data` is the reply to this query (and, for the error log, to `index`)
+    pub fn matches(self, data: &[u8], index: u8) -> bool {
+        if data.first() != Some(&QUERY_REPLY) {
+            return false;
+        }
+        match self {
+            // The identity reply carries ASCII where the others echo their selector
+            InfoQuery::Identity => !InfoQuery::ALL[1..].iter().any(|q| data.get(1) == Some(&q.selector())),
+            InfoQuery::ErrorLog => data.get(1) == Some(&self.selector()) && data.get(2) == Some(&index),
+            _ => data.get(1) == Some(&self.selector()),
+        }
+    }
+}
+
+/// Identity reply of the ping
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    pub hardware_version: String,
+}
+
+impl Identity {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if !InfoQuery::Identity.matches(data, 0) {
+            return Err(anyhow!("not an identity reply"));
+        }
+        TruncatedFrame::check("identity reply", data, 4)?;
+        Ok(Self {
+            name: ascii(&data[1..4]),
+            hardware_version: data.get(4..8).map(ascii).unwrap_or_default(),
+        })
+    }
+}
+
+/// One entry of the firmware error log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorLogEntry {
+    /// 0 = most recent
+    pub index: u8,
+    /// Fault code as in the fault register
+    pub code: u8,
+    /// Uptime of the motor when the fault occurred
+    pub uptime_s: u32,
+}
+
+impl ErrorLogEntry {
+    /// Time since the fault, given the motor's current uptime; `None` if the
+    /// fault happened before the last power cycle
+    pub fn age(&self, uptime: Duration) -> Option<Duration> {
+        uptime.checked_sub(Duration::from_secs(self.uptime_s as u64))
+    }
+
+    /// Parse an entry; `None` for an empty slot (code 0)
+    pub fn parse(data: &[u8]) -> Result<Option<Self>> {
+        if data.first() != Some(&QUERY_REPLY) || data.get(1) != Some(&InfoQuery::ErrorLog.selector()) {
+            return Err(anyhow!("not an error log reply"));
+        }
+        TruncatedFrame::check("error log reply", data, 4)?;
+        let (index, code) = (data[2], data[3]);
+        if code == 0 {
+            return Ok(None);
+        }
+        TruncatedFrame::check("error log reply", data, 8)?;
+        Ok(Some(Self {
+            index,
+            code,
+            uptime_s: u32_at(data, 4),
+        }))
+    }
+}
+
+/// Temperatures read at one instant
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TemperatureSnapshot {
+    /// Winding temperature in °C
+    pub motor_c: f64,
+    /// Power stage temperature in °C
+    pub driver_c: f64,
+    pub mcu_c: f64,
+}
+
+impl TemperatureSnapshot {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        expect(InfoQuery::Temperatures, data)?;
+        TruncatedFrame::check("temperature reply", data, 8)?;
+        let tenths = |offset: usize| i16::from_le_bytes([data[offset], data[offset + 1]]) as f64 / 10.0;
+        Ok(Self {
+            motor_c: tenths(2),
+            driver_c: tenths(4),
+            mcu_c: tenths(6),
+        })
+    }
+
+    /// Highest of the three temperatures
+    pub fn max_c(&self) -> f64 {
+        self.motor_c.max(self.driver_c).max(self.mcu_c)
+    }
+}
+
+/// Parse a serial number reply
+pub fn parse_serial_number(data: &[u8]) -> Result<u32> {
+    expect(InfoQuery::SerialNumber, data)?;
+    TruncatedFrame::check("serial number reply", data, 6)?;
+    Ok(u32_at(data, 2))
+}
+
+/// Parse an uptime reply
+pub fn parse_uptime(data: &[u8]) -> Result<Duration> {
+    expect(InfoQuery::Uptime, data)?;
+    TruncatedFrame::check("uptime reply", data, 6)?;
+    Ok(Duration::from_secs(u32_at(data, 2) as u64))
+}
+
+fn expect(query: InfoQuery, data: &[u8]) -> Result<()> {
+    if query.matches(data, 0) {
+        Ok(())
+    } else {
+        Err(anyhow!("not a {} reply", query.name()))
+    }
+}
+
+/// Little-endian u32 at `offset`; the caller checked the length
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn ascii(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string()
+}
@@ -0,0 +1,411 @@
+//! Target vs actual recorder
+//!
+//! Records, per control cycle, the commanded target and the measured state of
+//! each joint on a common time base. The latest row can be watched live while
+//! recording; the full run exports to CSV or ASAM MDF4. Session metadata and
+//! annotations added mid-run travel with the file, and survive reloading a CSV
+//! recording with [`Recorder::read_csv`].
+//!
+//! A recorder keeps every row in memory unless given a limit with
+//! [`Recorder::with_max_rows`]; a recorder left running for days should get
+//! one, so it keeps the most recent rows instead of growing without bound.
+
+use crate::mdf4::{self, Mdf4Channel};
+use crate::{ConfigFingerprint, MotorState, SensorFrame};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Values recorded per joint, in channel order
+const JOINT_CHANNELS: &[(&str, &str)] = &[
+    ("target_deg", "deg"),
+    ("actual_deg", "deg"),
+    ("actual_rps", "r/s"),
+    ("actual_nm", "Nm"),
+];
+
+/// Column prefix of external sensor channels
+const SENSOR_PREFIX: &str = "sensor.";
+
+/// Target and measured state of one joint in one cycle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointSample {
+    pub motor_id: u8,
+    pub target_deg: f64,
+    pub actual: Option<MotorState>,
+}
+
+/// One recorded cycle: time since start and one value per channel (NaN if missing)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordRow {
+    pub time_s: f64,
+    pub values: Vec<f64>,
+}
+
+/// Who recorded what, with which code
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robot: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    /// Commit of the control code that produced the recording
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// Hash of the effective configuration ([`ConfigFingerprint`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Wall-clock start of the recording (Unix seconds)
+    #[serde(default)]
+    pub started_unix_s: f64,
+    /// Any other key/value pairs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+}
+
+impl SessionMetadata {
+    pub fn with_robot(mut self, robot: &str) -> Self {
+        self.robot = Some(robot.to_string());
+        self
+    }
+
+    pub fn with_operator(mut self, operator: &str) -> Self {
+        self.operator = Some(operator.to_string());
+        self
+    }
+
+    pub fn with_notes(mut self, notes: &str) -> Self {
+        self.notes = Some(notes.to_string());
+        self
+    }
+
+    /// Record `HEAD` of the git checkout containing `dir` (with `-dirty` if modified)
+    pub fn with_git_commit_of<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(dir.as_ref())
+                .args(args)
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        };
+        self.git_commit = git(&["rev-parse", "HEAD"]).map(|commit| {
+            match git(&["status", "--porcelain"]) {
+                Some(status) if !status.is_empty() => format!("{}-dirty", commit),
+                _ => commit,
+            }
+        });
+        self
+    }
+
+    /// Record the hash of the configuration the run used
+    pub fn with_config(mut self, config: &ConfigFingerprint) -> Self {
+        self.config_hash = Some(config.hash());
+        self
+    }
+
+    pub fn with_extra(mut self, key: &str, value: &str) -> Self {
+        self.extra.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// All fields as key/value pairs, for formats without structured metadata
+    pub fn properties(&self) -> Vec<(String, String)> {
+        let mut properties = Vec::new();
+        let fields = [
+            ("robot", &self.robot),
+            ("operator", &self.operator),
+            ("git_commit", &self.git_commit),
+            ("config_hash", &self.config_hash),
+            ("notes", &self.notes),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                properties.push((key.to_string(), value.clone()));
+            }
+        }
+        properties.push(("started_unix_s".to_string(), format!("{:.3}", self.started_unix_s)));
+        properties.extend(self.extra.iter().map(|(k, v)| (k.clone(), v.clone())));
+        properties
+    }
+}
+
+/// A note attached to a point in the recording ("fell here")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Time since the start of the recording
+    pub time_s: f64,
+    pub text: String,
+}
+
+/// Records target vs actual for a fixed set of joints
+pub struct Recorder {
+    motor_ids: Vec<u8>,
+    /// External sensors recorded after the joints (`sensor.<name>`)
+    sensors: Vec<String>,
+    start: Instant,
+    rows: Vec<RecordRow>,
+    /// Most rows kept in memory, `None` for all
+    max_rows: Option<usize>,
+    /// Oldest rows discarded to stay within `max_rows`
+    dropped_rows: u64,
+    metadata: SessionMetadata,
+    annotations: Vec<Annotation>,
+}
+
+impl Recorder {
+    pub fn new(motor_ids: &[u8]) -> Self {
+        let started_unix_s = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        Self {
+            motor_ids: motor_ids.to_vec(),
+            sensors: Vec::new(),
+            start: Instant::now(),
+            rows: Vec::new(),
+            max_rows: None,
+            dropped_rows: 0,
+            metadata: SessionMetadata {
+                started_unix_s,
+                ..Default::default()
+            },
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Attach session metadata; the start time is kept unless `metadata` sets one
+    pub fn with_metadata(mut self, metadata: SessionMetadata) -> Self {
+        let started_unix_s = self.metadata.started_unix_s;
+        self.metadata = metadata;
+        if self.metadata.started_unix_s == 0.0 {
+            self.metadata.started_unix_s = started_unix_s;
+        }
+        self
+    }
+
+    /// Also record these external sensors, see [`record_with_sensors`](Self::record_with_sensors)
+    pub fn with_sensors(mut self, names: &[&str]) -> Self {
+        self.sensors = names.iter().map(|n| n.to_string()).collect();
+        self
+    }
+
+    /// Keep at most `max_rows` rows, discarding the oldest ones
+    ///
+    /// Rows are discarded in batches of an eighth of the limit, so a full
+    /// recorder holds between 7/8 of `max_rows` and `max_rows` rows.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows.max(1));
+        self
+    }
+
+    /// Oldest rows discarded so far because of [`with_max_rows`](Self::with_max_rows)
+    pub fn dropped_rows(&self) -> u64 {
+        self.dropped_rows
+    }
+
+    pub fn metadata(&self) -> &SessionMetadata {
+        &self.metadata
+    }
+
+    /// Annotate the current moment of the run
+    pub fn annotate(&mut self, text: &str) {
+        self.annotations.push(Annotation {
+            time_s: self.start.elapsed().as_secs_f64(),
+            text: text.to_string(),
+        });
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// Channel names and units, in value order (`m<id>.<quantity>`, then `sensor.<name>`)
+    pub fn channels(&self) -> Vec<(String, &'static str)> {
+        self.motor_ids
+            .iter()
+            .flat_map(|id| {
+                JOINT_CHANNELS
+                    .iter()
+                    .map(move |(name, unit)| (format!("m{}.{}", id, name), *unit))
+            })
+            .chain(self.sensors.iter().map(|name| (format!("{}{}", SENSOR_PREFIX, name), "")))
+            .collect()
+    }
+
+    /// Record one cycle; joints not in `samples` are stored as NaN
+    pub fn record(&mut self, samples: &[JointSample]) {
+        self.record_with_sensors(samples, &SensorFrame::default());
+    }
+
+    /// Record one cycle together with the sensor values of the same cycle
+    ///
+    /// Sensors missing from `sensors` or without a reading are stored as NaN.
+    pub fn record_with_sensors(&mut self, samples: &[JointSample], sensors: &SensorFrame) {
+        let joint_values = self.motor_ids.len() * JOINT_CHANNELS.len();
+        let mut values = vec![f64::NAN; joint_values + self.sensors.len()];
+
+        for sample in samples {
+            let Some(index) = self.motor_ids.iter().position(|&id| id == sample.motor_id) else {
+                continue;
+            };
+            let base = index * JOINT_CHANNELS.len();
+            values[base] = sample.target_deg;
+            if let Some(state) = sample.actual {
+                values[base + 1] = state.position_deg;
+                values[base + 2] = state.velocity_rps;
+                values[base + 3] = state.torque_nm;
+            }
+        }
+        for (value, name) in values[joint_values..].iter_mut().zip(&self.sensors) {
+            *value = sensors.get(name).unwrap_or(f64::NAN);
+        }
+
+        if let Some(max_rows) = self.max_rows {
+            if self.rows.len() >= max_rows {
+                // Shift the buffer once per batch, not once per row
+                let excess = (self.rows.len() + 1 - max_rows).max(max_rows / 8);
+                self.rows.drain(..excess);
+                self.dropped_rows += excess as u64;
+            }
+        }
+        self.rows.push(RecordRow {
+            time_s: self.start.elapsed().as_secs_f64(),
+            values,
+        });
+    }
+
+    /// Most recent row, for live watching
+    pub fn latest(&self) -> Option<&RecordRow> {
+        self.rows.last()
+    }
+
+    pub fn rows(&self) -> &[RecordRow] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Export as CSV with a `time_s` column followed by all channels
+    ///
+    /// Metadata and annotations come first, as `# metadata: {json}` and
+    /// `# annotation: {json}` comment lines.
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+
+        writeln!(out, "# metadata: {}", serde_json::to_string(&self.metadata)?)?;
+        for annotation in &self.annotations {
+            writeln!(out, "# annotation: {}", serde_json::to_string(annotation)?)?;
+        }
+
+        let header: Vec<String> = self.channels().into_iter().map(|(name, _)| name).collect();
+        writeln!(out, "time_s,{}", header.join(","))?;
+
+        for row in &self.rows {
+            write!(out, "{:.6}", row.time_s)?;
+            for value in &row.values {
+                if value.is_nan() {
+                    write!(out, ",")?;
+                } else {
+                    write!(out, ",{}", value)?;
+                }
+            }
+            writeln!(out)?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Load a recording written by [`write_csv`](Self::write_csv), including its
+    /// metadata and annotations, e.g. to trim it and write it back
+    pub fn read_csv<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read recording {}: {}", path.display(), e))?;
+
+        let mut recorder = Self::new(&[]);
+        recorder.metadata = SessionMetadata::default();
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+
+        let header = loop {
+            let line = lines.next().ok_or(anyhow!("recording {} has no header", path.display()))?;
+            if let Some(json) = line.strip_prefix("# metadata: ") {
+                recorder.metadata = serde_json::from_str(json)?;
+            } else if let Some(json) = line.strip_prefix("# annotation: ") {
+                recorder.annotations.push(serde_json::from_str(json)?);
+            } else if !line.starts_with('#') {
+                break line;
+            }
+        };
+
+        // Motor IDs from the `m<id>.target_deg` columns
+        let columns: Vec<&str> = header.split(',').skip(1).collect();
+        recorder.motor_ids = columns
+            .iter()
+            .filter_map(|c| c.strip_prefix('m')?.strip_suffix(".target_deg")?.parse().ok())
+            .collect();
+        recorder.sensors = columns
+            .iter()
+            .filter_map(|c| c.strip_prefix(SENSOR_PREFIX))
+            .map(str::to_string)
+            .collect();
+        if recorder.channels().len() != columns.len() {
+            return Err(anyhow!("recording {} has unexpected columns", path.display()));
+        }
+
+        for (i, line) in lines.enumerate() {
+            let cells: Vec<&str> = line.split(',').collect();
+            if cells.len() != columns.len() + 1 {
+                return Err(anyhow!("recording row {}: expected {} cells, found {}", i + 1, columns.len() + 1, cells.len()));
+            }
+            let number = |cell: &str| -> Result<f64> {
+                if cell.is_empty() {
+                    return Ok(f64::NAN);
+                }
+                cell.parse().map_err(|e| anyhow!("recording row {}: {}", i + 1, e))
+            };
+            recorder.rows.push(RecordRow {
+                time_s: number(cells[0])?,
+                values: cells[1..].iter().map(|c| number(c)).collect::<Result<_>>()?,
+            });
+        }
+        Ok(recorder)
+    }
+
+    /// Export as ASAM MDF4 (CANape / vSignalyzer / asammdf)
+    pub fn write_mdf4<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let channels: Vec<Mdf4Channel> = self
+            .channels()
+            .iter()
+            .map(|(name, unit)| Mdf4Channel::new(name, unit))
+            .collect();
+        let records: Vec<(f64, Vec<f64>)> = self
+            .rows
+            .iter()
+            .map(|row| (row.time_s, row.values.clone()))
+            .collect();
+
+        let mut properties = self.metadata.properties();
+        for annotation in &self.annotations {
+            properties.push((format!("annotation@{:.3}s", annotation.time_s), annotation.text.clone()));
+        }
+
+        mdf4::write_mdf4(path, &channels, &records, &properties)
+    }
+}
@@ -0,0 +1,426 @@
+//! Multi-host coordination over a UDP bridge
+//!
+//! Each robot runs a [`BridgeAgent`] that owns its CAN bus and accepts JSON
+//! messages over UDP. A [`Coordinator`] estimates every agent's clock offset
+//! (NTP-style ping/pong), then sends each robot its trajectory together with a
+//! common start time expressed in that robot's clock, so several robots can
+//! execute a choreographed routine together.
+
+use crate::tuning::{self, TunableParam};
+use crate::{AccessPolicy, Float, LivelyMotorController, Trajectory, Waypoint};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Default UDP port of the bridge agent
+pub const DEFAULT_BRIDGE_PORT: u16 = 7400;
+
+/// Largest datagram accepted by the agent
+const MAX_DATAGRAM: usize = 64 * 1024;
+
+/// Trajectory as sent over the bridge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteTrajectory {
+    pub motor_ids: Vec<u8>,
+    /// `(time in seconds, positions in degrees)` per waypoint
+    pub waypoints: Vec<(f64, Vec<f64>)>,
+}
+
+impl<T: Float> From<&Trajectory<T>> for RemoteTrajectory {
+    fn from(trajectory: &Trajectory<T>) -> Self {
+        Self {
+            motor_ids: trajectory.motor_ids.clone(),
+            waypoints: trajectory
+                .waypoints
+                .iter()
+                .map(|w| (w.time.as_secs_f64(), w.positions_deg.iter().map(|p| p.to_f64()).collect()))
+                .collect(),
+        }
+    }
+}
+
+impl<T: Float> From<RemoteTrajectory> for Trajectory<T> {
+    fn from(remote: RemoteTrajectory) -> Self {
+        Self {
+            motor_ids: remote.motor_ids,
+            waypoints: remote
+                .waypoints
+                .into_iter()
+                .map(|(t, positions_deg)| Waypoint {
+                    time: Duration::from_secs_f64(t.max(0.0)),
+                    positions_deg: positions_deg.into_iter().map(T::from_f64).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Messages exchanged between coordinator and agents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeMessage {
+    Ping { seq: u64, sent_us: u64 },
+    Pong { seq: u64, sent_us: u64, agent_us: u64 },
+    /// Play `trajectory` when the agent's clock reaches `start_us`
+    Run {
+        routine: String,
+        start_us: u64,
+        rate_hz: f64,
+        max_vel_rps: f64,
+        max_tqe_nm: f64,
+        trajectory: RemoteTrajectory,
+    },
+    Ack { routine: String, ok: bool, error: Option<String> },
+    /// Abort the running routine
+    Stop,
+    /// Ask for a motor's state, answered with `State`
+    ReadState { motor_id: u8 },
+    State { motor_id: u8, position_deg: f64, velocity_rps: f64, torque_nm: f64 },
+    /// Write a motor parameter, answered with an `Ack` after reading it back
+    SetParam { motor_id: u8, param: TunableParam, value: f32 },
+}
+
+impl BridgeMessage {
+    /// Name used as the `routine` of the `Ack` answering a request other than `Run`
+    fn kind(&self) -> &'static str {
+        match self {
+            BridgeMessage::Ping { .. } => "ping",
+            BridgeMessage::Pong { .. } => "pong",
+            BridgeMessage::Run { .. } => "run",
+            BridgeMessage::Ack { .. } => "ack",
+            BridgeMessage::Stop => "stop",
+            BridgeMessage::ReadState { .. } => "read_state",
+            BridgeMessage::State { .. } => "state",
+            BridgeMessage::SetParam { .. } => "set_param",
+        }
+    }
+}
+
+/// A message as sent over the bridge, with the sender's access token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgeRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub message: BridgeMessage,
+}
+
+/// Wall clock in microseconds since the Unix epoch
+pub fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+fn send(socket: &UdpSocket, to: SocketAddr, message: &BridgeMessage) -> Result<()> {
+    send_with_token(socket, to, None, message)
+}
+
+fn send_with_token(socket: &UdpSocket, to: SocketAddr, token: Option<&str>, message: &BridgeMessage) -> Result<()> {
+    let request = BridgeRequest {
+        token: token.map(str::to_string),
+        message: message.clone(),
+    };
+    socket.send_to(&serde_json::to_vec(&request)?, to)?;
+    Ok(())
+}
+
+fn recv(socket: &UdpSocket) -> Result<Option<(BridgeMessage, SocketAddr)>> {
+    Ok(recv_request(socket)?.map(|(request, from)| (request.message, from)))
+}
+
+fn recv_request(socket: &UdpSocket) -> Result<Option<(BridgeRequest, SocketAddr)>> {
+    let mut buf = vec![0u8; MAX_DATAGRAM];
+    match socket.recv_from(&mut buf) {
+        Ok((len, from)) => Ok(Some((serde_json::from_slice(&buf[..len])?, from))),
+        Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Robot-side end of the bridge
+pub struct BridgeAgent<'a> {
+    controller: &'a LivelyMotorController,
+    socket: UdpSocket,
+    policy: AccessPolicy,
+}
+
+impl<'a> BridgeAgent<'a> {
+    pub fn bind<A: ToSocketAddrs>(controller: &'a LivelyMotorController, addr: A) -> Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(Duration::from_millis(50)))?;
+        Ok(Self {
+            controller,
+            socket,
+            policy: AccessPolicy::open(),
+        })
+    }
+
+    /// Check every request against `policy` (default: [`AccessPolicy::open`])
+    pub fn with_policy(mut self, policy: AccessPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Serve requests until `running` is cleared
+    pub fn serve(&self, running: &AtomicBool) -> Result<()> {
+        while running.load(Ordering::SeqCst) {
+            let Some((request, from)) = recv_request(&self.socket)? else {
+                continue;
+            };
+            let Some(message) = self.authorize(request, from)? else {
+                continue;
+            };
+
+            match message {
+                BridgeMessage::Ping { seq, sent_us } => {
+                    send(&self.socket, from, &BridgeMessage::Pong { seq, sent_us, agent_us: unix_micros() })?;
+                }
+                BridgeMessage::Run { routine, start_us, rate_hz, max_vel_rps, max_tqe_nm, trajectory } => {
+                    send(&self.socket, from, &BridgeMessage::Ack { routine: routine.clone(), ok: true, error: None })?;
+                    let trajectory: Trajectory = Trajectory::from(trajectory);
+                    let result = self.run_at(start_us, running, |start, playing| {
+                        trajectory.play_from(start, self.controller, rate_hz, max_vel_rps, max_tqe_nm, playing)
+                    });
+                    let error = result.err().map(|e| format!("{:#}", e));
+                    send(&self.socket, from, &BridgeMessage::Ack { routine, ok: error.is_none(), error })?;
+                }
+                BridgeMessage::ReadState { motor_id } => {
+                    let reply = match self.controller.read_motor_state(motor_id) {
+                        Ok(state) => BridgeMessage::State {
+                            motor_id,
+                            position_deg: state.position_deg,
+                            velocity_rps: state.velocity_rps,
+                            torque_nm: state.torque_nm,
+                        },
+                        Err(e) => BridgeMessage::Ack {
+                            routine: "read_state".to_string(),
+                            ok: false,
+                            error: Some(format!("{:#}", e)),
+                        },
+                    };
+                    send(&self.socket, from, &reply)?;
+                }
+                BridgeMessage::SetParam { motor_id, param, value } => {
+                    let error = match tuning::write_verified(self.controller, motor_id, param, value) {
+                        Ok(result) if result.verified() => None,
+                        Ok(result) => Some(format!("{} of motor {} reads back {}", param.name(), motor_id, result.read_back)),
+                        Err(e) => Some(format!("{:#}", e)),
+                    };
+                    let routine = "set_param".to_string();
+                    send(&self.socket, from, &BridgeMessage::Ack { routine, ok: error.is_none(), error })?;
+                }
+                BridgeMessage::Stop
+                | BridgeMessage::Pong { .. }
+                | BridgeMessage::Ack { .. }
+                | BridgeMessage::State { .. } => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The request's message if its token allows it, otherwise answer with a
+    /// failed `Ack`
+    fn authorize(&self, request: BridgeRequest, from: SocketAddr) -> Result<Option<BridgeMessage>> {
+        let BridgeRequest { token, message } = request;
+        match self.policy.authorize(token.as_deref(), &message) {
+            Ok(_) => Ok(Some(message)),
+            Err(e) => {
+                let routine = match &message {
+                    BridgeMessage::Run { routine, .. } => routine.clone(),
+                    other => other.kind().to_string(),
+                };
+                send(&self.socket, from, &BridgeMessage::Ack { routine, ok: false, error: Some(format!("{:#}", e)) })?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Wait for `start_us`, then run `play` from that instant while listening for `Stop`
+    fn run_at<F>(&self, start_us: u64, running: &AtomicBool, play: F) -> Result<()>
+    where
+        F: FnOnce(Instant, &AtomicBool) -> Result<()> + Send,
+    {
+        let playing = AtomicBool::new(true);
+        let wait = Duration::from_micros(start_us.saturating_sub(unix_micros()));
+        let start = Instant::now() + wait;
+
+        thread::scope(|scope| {
+            let player = scope.spawn(|| {
+                while Instant::now() < start {
+                    if !playing.load(Ordering::SeqCst) {
+                        return Ok(());
+                    }
+                    thread::sleep((start - Instant::now()).min(Duration::from_millis(1)));
+                }
+                play(start, &playing)
+            });
+
+            while !player.is_finished() {
+                if !running.load(Ordering::SeqCst) {
+                    playing.store(false, Ordering::SeqCst);
+                }
+                if let Some((request, from)) = recv_request(&self.socket)? {
+                    if let Some(BridgeMessage::Stop) = self.authorize(request, from)? {
+                        playing.store(false, Ordering::SeqCst);
+                    }
+                }
+            }
+            player.join().map_err(|_| anyhow!("trajectory player panicked"))?
+        })
+    }
+}
+
+/// Clock offset of one agent relative to the coordinator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSync {
+    pub agent: SocketAddr,
+    /// Agent clock minus coordinator clock, in microseconds
+    pub offset_us: i64,
+    /// Round trip time of the best sample
+    pub rtt: Duration,
+}
+
+/// Coordinator-side end of the bridge
+pub struct Coordinator {
+    socket: UdpSocket,
+    agents: Vec<SocketAddr>,
+    token: Option<String>,
+}
+
+impl Coordinator {
+    pub fn new(agents: &[SocketAddr]) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+        Ok(Self {
+            socket,
+            agents: agents.to_vec(),
+            token: None,
+        })
+    }
+
+    /// Send `token` with every request, for agents with an [`AccessPolicy`]
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    fn send(&self, agent: SocketAddr, message: &BridgeMessage) -> Result<()> {
+        send_with_token(&self.socket, agent, self.token.as_deref(), message)
+    }
+
+    /// Estimate every agent's clock offset from `samples` ping/pongs, keeping
+    /// the sample with the lowest round trip time
+    pub fn sync_clocks(&self, samples: u32) -> Result<Vec<ClockSync>> {
+        let mut results = Vec::with_capacity(self.agents.len());
+        for (index, &agent) in self.agents.iter().enumerate() {
+            let mut best: Option<ClockSync> = None;
+            for i in 0..samples {
+                let seq = (index as u64) << 32 | i as u64;
+                self.send(agent, &BridgeMessage::Ping { seq, sent_us: unix_micros() })?;
+
+                while let Some((message, _)) = recv(&self.socket)? {
+                    let BridgeMessage::Pong { seq: got, sent_us, agent_us } = message else {
+                        continue;
+                    };
+                    if got != seq {
+                        continue;
+                    }
+                    let now = unix_micros();
+                    let rtt_us = now.saturating_sub(sent_us);
+                    let sample = ClockSync {
+                        agent,
+                        offset_us: agent_us as i64 - (sent_us + rtt_us / 2) as i64,
+                        rtt: Duration::from_micros(rtt_us),
+                    };
+                    if best.is_none_or(|b| sample.rtt < b.rtt) {
+                        best = Some(sample);
+                    }
+                    break;
+                }
+            }
+            results.push(best.ok_or(anyhow!("agent {} did not answer", agent))?);
+        }
+        Ok(results)
+    }
+
+    /// Start one trajectory per agent at the same instant, `lead` from now
+    pub fn run_synchronized(
+        &self,
+        routine: &str,
+        trajectories: &[(SocketAddr, Trajectory)],
+        lead: Duration,
+        rate_hz: f64,
+        max_vel_rps: f64,
+        max_tqe_nm: f64,
+    ) -> Result<()> {
+        let clocks = self.sync_clocks(8)?;
+        let start_us = unix_micros() + lead.as_micros() as u64;
+
+        for (agent, trajectory) in trajectories {
+            let clock = clocks
+                .iter()
+                .find(|c| c.agent == *agent)
+                .ok_or(anyhow!("agent {} is not part of this coordinator", agent))?;
+            let message = BridgeMessage::Run {
+                routine: routine.to_string(),
+                start_us: (start_us as i64 + clock.offset_us).max(0) as u64,
+                rate_hz,
+                max_vel_rps,
+                max_tqe_nm,
+                trajectory: RemoteTrajectory::from(trajectory),
+            };
+            self.send(*agent, &message)?;
+        }
+
+        let mut pending: Vec<SocketAddr> = trajectories.iter().map(|(a, _)| *a).collect();
+        let deadline = Instant::now() + lead;
+        while !pending.is_empty() && Instant::now() < deadline {
+            if let Some((BridgeMessage::Ack { ok, error, .. }, from)) = recv(&self.socket)? {
+                if !ok {
+                    return Err(anyhow!("agent {} rejected the routine: {}", from, error.unwrap_or_default()));
+                }
+                pending.retain(|a| *a != from);
+            }
+        }
+        if !pending.is_empty() {
+            self.stop()?;
+            return Err(anyhow!("no acknowledgement from {:?}, routine stopped", pending));
+        }
+        Ok(())
+    }
+
+    /// Wait until every agent reports the end of the routine
+    pub fn wait_finished(&self, timeout: Duration) -> Result<()> {
+        let mut pending = self.agents.clone();
+        let deadline = Instant::now() + timeout;
+        while !pending.is_empty() && Instant::now() < deadline {
+            if let Some((BridgeMessage::Ack { ok, error, .. }, from)) = recv(&self.socket)? {
+                if !ok {
+                    return Err(anyhow!("agent {} failed: {}", from, error.unwrap_or_default()));
+                }
+                pending.retain(|a| *a != from);
+            }
+        }
+        if !pending.is_empty() {
+            return Err(anyhow!("agents {:?} did not finish in time", pending));
+        }
+        Ok(())
+    }
+
+    /// Abort the routine on every agent
+    pub fn stop(&self) -> Result<()> {
+        for &agent in &self.agents {
+            self.send(agent, &BridgeMessage::Stop)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+//! Content of automatically sent state replies
+//!
+//! A pushing motor ([`PushFeedback`](crate::PushFeedback)) sends position,
+//! velocity and torque by default. Its report content register selects other
+//! fields instead, e.g. only position for a joint whose velocity nobody reads,
+//! or temperature to watch a motor heat up without polling it. The selected
+//! fields are sent as int16 values in bit order, starting at the register of
+//! the first one; at most three fit in a reply.
+//!
+//! The controller remembers the mask it set (or read) for each motor and
+//! decodes that motor's pushed replies with it.
+
+use crate::protocol::{RegisterReply, ValueType};
+use crate::{MotorState, Register};
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::ops::BitOr;
+
+/// Values that fit in one reply
+const MAX_FIELDS: usize = 3;
+
+/// Temperature counts per °C in a report
+const FACTOR_TEMP: f64 = 10.0;
+
+/// A field a motor can report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportField {
+    Position,
+    Velocity,
+    Torque,
+    Temperature,
+}
+
+impl ReportField {
+    /// All fields, in bit (and payload) order
+    pub const ALL: [ReportField; 4] = [
+        ReportField::Position,
+        ReportField::Velocity,
+        ReportField::Torque,
+        ReportField::Temperature,
+    ];
+
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Register the field is read from, and the first register of a reply starting with it
+    pub fn register(self) -> Register {
+        match self {
+            ReportField::Position => Register::Position,
+            ReportField::Velocity => Register::Velocity,
+            ReportField::Torque => Register::Torque,
+            ReportField::Temperature => Register::Temperature,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReportField::Position => "position",
+            ReportField::Velocity => "velocity",
+            ReportField::Torque => "torque",
+            ReportField::Temperature => "temperature",
+        }
+    }
+}
+
+/// Set of fields a motor reports, as written to [`Register::ReportContent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReportMask(u8);
+
+impl ReportMask {
+    pub const POSITION: ReportMask = ReportMask(1 << 0);
+    pub const VELOCITY: ReportMask = ReportMask(1 << 1);
+    pub const TORQUE: ReportMask = ReportMask(1 << 2);
+    pub const TEMPERATURE: ReportMask = ReportMask(1 << 3);
+    /// Position, velocity and torque: what motors report out of the box
+    pub const STATE: ReportMask = ReportMask(0b0111);
+
+    /// Mask from register bits; unknown bits, no field or too many fields are refused
+    pub fn from_bits(bits: u8) -> Result<Self> {
+        let known = ReportField::ALL.iter().fold(0, |acc, f| acc | f.bit());
+        if bits & !known != 0 {
+            return Err(anyhow!("unknown report content bits 0x{:02X}", bits & !known));
+        }
+        let mask = ReportMask(bits);
+        mask.validate()?;
+        Ok(mask)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, field: ReportField) -> bool {
+        self.0 & field.bit() != 0
+    }
+
+    /// Selected fields in payload order
+    pub fn fields(self) -> Vec<ReportField> {
+        ReportField::ALL.into_iter().filter(|f| self.contains(*f)).collect()
+    }
+
+    pub fn validate(self) -> Result<()> {
+        let count = self.fields().len();
+        if count == 0 {
+            return Err(anyhow!("report content must select at least one field"));
+        }
+        if count > MAX_FIELDS {
+            return Err(anyhow!("report content selects {} fields, a reply holds {}", count, MAX_FIELDS));
+        }
+        Ok(())
+    }
+
+    /// Whether `reply` is a report with this content
+    pub fn matches(self, reply: &RegisterReply) -> bool {
+        let fields = self.fields();
+        fields.first().is_some_and(|first| reply.register == first.register().addr())
+            && reply.value_type == ValueType::Int16
+            && reply.raw.len() == fields.len() * ValueType::Int16.size()
+    }
+
+    /// Decode a report with this content
+    pub fn decode(self, motor_id: u8, reply: &RegisterReply) -> Result<Report> {
+        if !self.matches(reply) {
+            return Err(anyhow!("reply of motor {} does not have report content {}", motor_id, self));
+        }
+        let mut report = Report {
+            motor_id,
+            ..Default::default()
+        };
+        for (i, field) in self.fields().into_iter().enumerate() {
+            let raw = reply
+                .int(i)
+                .ok_or_else(|| anyhow!("report of motor {} truncated", motor_id))? as i16;
+            match field {
+                ReportField::Position => report.position_deg = Some(crate::position_to_degrees(raw)),
+                ReportField::Velocity => report.velocity_rps = Some(crate::velocity_to_rps(raw)),
+                ReportField::Torque => report.torque_nm = Some(crate::torque_to_nm(raw)),
+                ReportField::Temperature => report.temperature_c = Some(raw as f64 / FACTOR_TEMP),
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl Default for ReportMask {
+    fn default() -> Self {
+        Self::STATE
+    }
+}
+
+impl BitOr for ReportMask {
+    type Output = ReportMask;
+
+    fn bitor(self, rhs: ReportMask) -> ReportMask {
+        ReportMask(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for ReportMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.fields().into_iter().map(ReportField::name).collect();
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+/// Fields of one pushed reply; unselected fields are `None`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Report {
+    pub motor_id: u8,
+    pub position_deg: Option<f64>,
+    pub velocity_rps: Option<f64>,
+    pub torque_nm: Option<f64>,
+    pub temperature_c: Option<f64>,
+}
+
+impl Report {
+    /// `previous` with the reported fields replaced
+    pub fn merge_into(&self, previous: Option<&MotorState>) -> MotorState {
+        let mut state = previous.copied().unwrap_or(MotorState {
+            motor_id: self.motor_id,
+            ..Default::default()
+        });
+        state.position_deg = self.position_deg.unwrap_or(state.position_deg);
+        state.velocity_rps = self.velocity_rps.unwrap_or(state.velocity_rps);
+        state.torque_nm = self.torque_nm.unwrap_or(state.torque_nm);
+        state
+    }
+}
@@ -0,0 +1,214 @@
+//! Compile-time robot descriptions
+//!
+//! The [`robot!`](crate::robot!) macro turns a list of joint names and motor IDs
+//! into a struct with one typed accessor per joint, so hot code calls
+//! `robot.left_knee().set_angle(..)` instead of looking joints up by string.
+//! Every robot can [`freeze`] in its current pose, e.g. when the planner
+//! commanding it has crashed.
+
+use crate::{LivelyMotorController, MotorState, Register};
+use anyhow::{Result, anyhow};
+
+#[doc(hidden)]
+pub use anyhow::{anyhow as macro_anyhow, Result as MacroResult};
+
+/// Gains and limits a joint holds a position with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoldStiffness {
+    pub kp: f32,
+    pub kd: f32,
+    pub max_vel_rps: f64,
+    pub max_torque_nm: f64,
+}
+
+impl Default for HoldStiffness {
+    fn default() -> Self {
+        Self {
+            kp: 1.0,
+            kd: 0.1,
+            max_vel_rps: 1.0,
+            max_torque_nm: 3.0,
+        }
+    }
+}
+
+/// Handle to one joint of a robot
+#[derive(Clone, Copy)]
+pub struct Joint<'a> {
+    controller: &'a LivelyMotorController,
+    motor_id: u8,
+    name: &'static str,
+}
+
+impl<'a> Joint<'a> {
+    pub fn new(controller: &'a LivelyMotorController, motor_id: u8, name: &'static str) -> Self {
+        Self {
+            controller,
+            motor_id,
+            name,
+        }
+    }
+
+    pub fn motor_id(&self) -> u8 {
+        self.motor_id
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Enable the joint in position mode
+    pub fn enable(&self) -> Result<()> {
+        Ok(self.controller.enable_motor(self.motor_id)?)
+    }
+
+    pub fn disable(&self) -> Result<()> {
+        Ok(self.controller.disable_motor(self.motor_id)?)
+    }
+
+    /// Send an angle setpoint (0x90 stream)
+    pub fn set_angle(&self, angle_deg: f64, max_vel_rps: f64, max_tqe_nm: f64) -> Result<()> {
+        Ok(self.controller.send_angle_command_to(
+            self.motor_id,
+            crate::degrees_to_position(angle_deg),
+            crate::rps_to_velocity(max_vel_rps),
+            crate::nm_to_torque(max_tqe_nm),
+        )?)
+    }
+
+    /// Send a velocity setpoint (0xAD stream)
+    pub fn set_velocity(&self, velocity_rps: f64, acceleration_rps2: f64) -> Result<()> {
+        Ok(self.controller.send_velocity_command_to(
+            self.motor_id,
+            crate::MAGIC_POS,
+            crate::rps_to_velocity(velocity_rps),
+            crate::rps2_to_acceleration(acceleration_rps2),
+        )?)
+    }
+
+    pub fn read_state(&self) -> Result<MotorState> {
+        Ok(self.controller.read_motor_state(self.motor_id)?)
+    }
+
+    /// Hold `angle_deg` with the given stiffness, enabling the joint if needed
+    pub fn hold(&self, angle_deg: f64, stiffness: HoldStiffness) -> Result<()> {
+        if !self.controller.enabled_motors().contains(&self.motor_id) {
+            self.enable()?;
+        }
+        self.controller.write_register_float(self.motor_id, Register::Kp, stiffness.kp)?;
+        self.controller.write_register_float(self.motor_id, Register::Kd, stiffness.kd)?;
+        self.set_angle(angle_deg, stiffness.max_vel_rps, stiffness.max_torque_nm)
+    }
+}
+
+/// Read every joint's position, then make each joint hold it
+///
+/// All positions are read before any joint is commanded, so the captured pose
+/// is from one instant. A joint that cannot be read or commanded does not stop
+/// the others from being frozen; the error names every failed joint.
+pub fn freeze(joints: &[Joint], stiffness: HoldStiffness) -> Result<Vec<MotorState>> {
+    let states: Vec<Result<MotorState>> = joints.iter().map(Joint::read_state).collect();
+
+    let mut frozen = Vec::with_capacity(joints.len());
+    let mut failed = Vec::new();
+    for (joint, state) in joints.iter().zip(states) {
+        match state.and_then(|s| joint.hold(s.position_deg, stiffness).map(|_| s)) {
+            Ok(state) => frozen.push(state),
+            Err(e) => failed.push(format!("{} (motor {}): {:#}", joint.name(), joint.motor_id(), e)),
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow!("could not freeze {}", failed.join("; ")));
+    }
+    Ok(frozen)
+}
+
+/// Declare a robot with typed joint accessors
+///
+/// ```no_run
+/// use livelybot_motor_control::{robot, LivelyMotorController};
+///
+/// robot! {
+///     pub struct Leg {
+///         hip: 1,
+///         knee: 2,
+///     }
+/// }
+///
+/// let controller = LivelyMotorController::new("can0", 1_000_000)?;
+/// let leg = Leg::new(&controller);
+/// leg.knee().set_angle(30.0, 2.0, 3.0)?;
+///
+/// // Planner crashed: hold the current pose instead of collapsing
+/// leg.freeze()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[macro_export]
+macro_rules! robot {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($joint:ident : $id:expr),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name<'a> {
+            controller: &'a $crate::LivelyMotorController,
+            profiles: ::std::option::Option<&'a $crate::ProfileSet>,
+        }
+
+        #[allow(dead_code)]
+        impl<'a> $name<'a> {
+            /// Joint names and motor IDs in declaration order
+            pub const JOINTS: &'static [(&'static str, u8)] = &[$((stringify!($joint), $id)),*];
+
+            pub fn new(controller: &'a $crate::LivelyMotorController) -> Self {
+                Self { controller, profiles: None }
+            }
+
+            /// Gain / limit profiles selectable with `apply_profile`
+            pub fn with_profiles(mut self, profiles: &'a $crate::ProfileSet) -> Self {
+                self.profiles = Some(profiles);
+                self
+            }
+
+            /// Switch every joint covered by profile `name` to its settings
+            pub fn apply_profile(&self, name: &str) -> $crate::robot::MacroResult<()> {
+                let profiles = self
+                    .profiles
+                    .ok_or($crate::robot::macro_anyhow!("no profiles configured for {}", stringify!($name)))?;
+                let ids: ::std::vec::Vec<u8> = Self::JOINTS.iter().map(|&(_, id)| id).collect();
+                profiles.apply(self.controller, name, &ids)?;
+                Ok(())
+            }
+
+            /// All joints in declaration order
+            pub fn joints(&self) -> ::std::vec::Vec<$crate::robot::Joint<'a>> {
+                Self::JOINTS
+                    .iter()
+                    .map(|&(name, id)| $crate::robot::Joint::new(self.controller, id, name))
+                    .collect()
+            }
+
+            /// Hold every joint at its measured position with default stiffness
+            pub fn freeze(&self) -> $crate::robot::MacroResult<::std::vec::Vec<$crate::MotorState>> {
+                self.freeze_with($crate::robot::HoldStiffness::default())
+            }
+
+            /// Hold every joint at its measured position
+            pub fn freeze_with(
+                &self,
+                stiffness: $crate::robot::HoldStiffness,
+            ) -> $crate::robot::MacroResult<::std::vec::Vec<$crate::MotorState>> {
+                $crate::robot::freeze(&self.joints(), stiffness)
+            }
+
+            $(
+                pub fn $joint(&self) -> $crate::robot::Joint<'a> {
+                    $crate::robot::Joint::new(self.controller, $id, stringify!($joint))
+                }
+            )*
+        }
+    };
+}
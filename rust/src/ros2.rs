@@ -0,0 +1,341 @@
+//! ROS 2 bridge over rosbridge (feature `ros2`)
+//!
+//! Exposes every joint of a [`JointMapping`] to ROS 2 through a rosbridge
+//! server (`ros2 launch rosbridge_server rosbridge_websocket_launch.xml`):
+//!
+//! - `<namespace>/joint_commands` (`sensor_msgs/msg/JointState`) is
+//!   subscribed: `position` is the target in radians, `velocity` and `effort`
+//!   (optional, per joint) limit speed in rad/s and torque in Nm
+//! - `<namespace>/joint_states` (`sensor_msgs/msg/JointState`) is published at
+//!   the feedback rate
+//! - `<namespace>/enable` (`std_srvs/srv/SetBool`) enables or disables all joints
+//!
+//! rclrs is not used: it and the message crates are generated inside a sourced
+//! ROS 2 workspace and are not on crates.io, so depending on them would break
+//! every build of this crate outside one. rosbridge only needs a WebSocket.
+
+use crate::remote::unix_micros;
+use crate::{degrees_to_position, nm_to_torque, rps_to_velocity, JointMap, JointMapping, LivelyMotorController};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::f64::consts::TAU;
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+/// Default URL of rosbridge_server
+pub const DEFAULT_ROSBRIDGE_URL: &str = "ws://localhost:9090";
+
+/// `builtin_interfaces/msg/Time`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosTime {
+    pub sec: i32,
+    pub nanosec: u32,
+}
+
+impl RosTime {
+    /// Wall clock, as ROS 2 stamps messages by default
+    pub fn now() -> Self {
+        let micros = unix_micros();
+        Self {
+            sec: (micros / 1_000_000) as i32,
+            nanosec: (micros % 1_000_000) as u32 * 1000,
+        }
+    }
+}
+
+/// `std_msgs/msg/Header`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    #[serde(default)]
+    pub stamp: RosTime,
+    #[serde(default)]
+    pub frame_id: String,
+}
+
+/// `sensor_msgs/msg/JointState`, in radians, rad/s and Nm
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JointState {
+    #[serde(default)]
+    pub header: Header,
+    pub name: Vec<String>,
+    #[serde(default)]
+    pub position: Vec<f64>,
+    #[serde(default)]
+    pub velocity: Vec<f64>,
+    #[serde(default)]
+    pub effort: Vec<f64>,
+}
+
+/// Publishes the joints of a controller to ROS 2 and takes their commands
+pub struct Ros2Bridge<'a> {
+    controller: &'a LivelyMotorController,
+    mapping: JointMapping,
+    namespace: String,
+    rate_hz: f64,
+    max_vel_rps: f64,
+    max_tqe_nm: f64,
+    rejected: u64,
+    last_error: Option<String>,
+}
+
+impl<'a> Ros2Bridge<'a> {
+    pub fn new(controller: &'a LivelyMotorController, mapping: JointMapping) -> Self {
+        Self {
+            controller,
+            mapping,
+            namespace: "/livelybot".to_string(),
+            rate_hz: 50.0,
+            max_vel_rps: 2.0,
+            max_tqe_nm: 3.0,
+            rejected: 0,
+            last_error: None,
+        }
+    }
+
+    /// Prefix of the topic and service names (default `/livelybot`)
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Rate of `joint_states` in Hz (default 50)
+    pub fn with_rate(mut self, rate_hz: f64) -> Self {
+        self.rate_hz = rate_hz;
+        self
+    }
+
+    /// Velocity (r/s) and torque (Nm) limits of commands without `velocity` or `effort`
+    pub fn with_limits(mut self, max_vel_rps: f64, max_tqe_nm: f64) -> Self {
+        self.max_vel_rps = max_vel_rps;
+        self.max_tqe_nm = max_tqe_nm;
+        self
+    }
+
+    /// Full name of a topic or service of the bridge
+    pub fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.namespace, name)
+    }
+
+    /// Messages and service calls that could not be parsed or applied
+    pub fn rejected_commands(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Why the last message, service call or feedback read failed
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// rosbridge operations announcing the topics and the service
+    pub fn setup_messages(&self) -> Vec<Value> {
+        vec![
+            json!({"op": "advertise", "topic": self.topic("joint_states"), "type": "sensor_msgs/msg/JointState"}),
+            json!({
+                "op": "subscribe",
+                "topic": self.topic("joint_commands"),
+                "type": "sensor_msgs/msg/JointState",
+                "queue_length": 1,
+            }),
+            json!({"op": "advertise_service", "service": self.topic("enable"), "type": "std_srvs/srv/SetBool"}),
+        ]
+    }
+
+    /// Handle one rosbridge operation, returns the reply to send if any
+    ///
+    /// A command that can't be parsed or applied and a malformed service
+    /// call are counted in [`rejected_commands`](Self::rejected_commands);
+    /// the service call still gets a failed response.
+    pub fn handle(&mut self, message: &Value) -> Option<Value> {
+        match message["op"].as_str() {
+            Some("publish") if message["topic"] == self.topic("joint_commands") => {
+                let applied = serde_json::from_value::<JointState>(message["msg"].clone())
+                    .map_err(anyhow::Error::from)
+                    .and_then(|command| self.apply_command(&command));
+                if let Err(e) = applied {
+                    self.reject(e);
+                }
+                None
+            }
+            Some("call_service") if message["service"] == self.topic("enable") => {
+                let result = match message["args"]["data"].as_bool() {
+                    Some(enable) => self.set_enabled(enable).map(|()| enable),
+                    None => Err(anyhow!("SetBool request without data")),
+                };
+                let (success, text) = match result {
+                    Ok(enable) => (true, if enable { "enabled" } else { "disabled" }.to_string()),
+                    Err(e) => {
+                        let text = format!("{:#}", e);
+                        self.reject(e);
+                        (false, text)
+                    }
+                };
+                let mut reply = json!({
+                    "op": "service_response",
+                    "service": self.topic("enable"),
+                    "values": {"success": success, "message": text},
+                    "result": true,
+                });
+                if let Some(id) = message.get("id") {
+                    reply["id"] = id.clone();
+                }
+                Some(reply)
+            }
+            _ => None,
+        }
+    }
+
+    fn reject(&mut self, error: anyhow::Error) {
+        self.rejected += 1;
+        self.last_error = Some(format!("{:#}", error));
+    }
+
+    /// Send the angle commands of a `JointState`, returns the number of joints commanded
+    ///
+    /// Nothing is sent unless every named joint is mapped, enabled and has a position.
+    pub fn apply_command(&self, command: &JointState) -> Result<usize> {
+        let n = command.name.len();
+        if command.position.len() != n {
+            return Err(anyhow!("{} joint names but {} positions", n, command.position.len()));
+        }
+        for (field, values) in [("velocity", &command.velocity), ("effort", &command.effort)] {
+            if !values.is_empty() && values.len() != n {
+                return Err(anyhow!("{} joint names but {} {} values", n, values.len(), field));
+            }
+        }
+
+        let enabled = self.controller.enabled_motors();
+        let joints = command
+            .name
+            .iter()
+            .map(|name| {
+                let joint = self.joint(name)?;
+                if !enabled.contains(&joint.motor_id) {
+                    return Err(anyhow!("joint '{}' (motor {}) is not enabled", name, joint.motor_id));
+                }
+                Ok(joint)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (i, joint) in joints.iter().enumerate() {
+            let max_vel_rps = command.velocity.get(i).map_or(self.max_vel_rps, |v| v.abs() / TAU);
+            let max_tqe_nm = command.effort.get(i).map_or(self.max_tqe_nm, |e| e.abs());
+            self.controller.send_angle_command_to(
+                joint.motor_id,
+                degrees_to_position(joint.to_motor_deg(command.position[i])),
+                rps_to_velocity(max_vel_rps),
+                nm_to_torque(max_tqe_nm),
+            )?;
+        }
+        Ok(joints.len())
+    }
+
+    /// Measured state of every joint
+    pub fn feedback(&self) -> Result<JointState> {
+        let mut message = JointState {
+            header: Header {
+                stamp: RosTime::now(),
+                frame_id: String::new(),
+            },
+            ..Default::default()
+        };
+        for joint in &self.mapping.joints {
+            let state = self.controller.read_motor_state(joint.motor_id)?;
+            let sign = if joint.reversed { -1.0 } else { 1.0 };
+            message.name.push(joint.name.clone());
+            message.position.push(joint.to_joint_rad(state.position_deg));
+            message.velocity.push(sign * state.velocity_rps * TAU);
+            message.effort.push(sign * state.torque_nm);
+        }
+        Ok(message)
+    }
+
+    /// Enable or disable the motors of all joints
+    pub fn set_enabled(&self, enable: bool) -> Result<()> {
+        for motor_id in self.mapping.motor_ids() {
+            if enable {
+                self.controller.enable_motor(motor_id)?;
+            } else {
+                self.controller.disable_motor(motor_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Connect to rosbridge at `url` and serve until `running` is cleared
+    ///
+    /// A message that is not JSON or can't be handled is counted in
+    /// [`rejected_commands`](Self::rejected_commands) and
+    /// [`last_error`](Self::last_error), a failed feedback read is kept in
+    /// `last_error`; neither stops the bridge. Losing the connection returns
+    /// an error.
+    pub fn serve(&mut self, url: &str, running: &AtomicBool) -> Result<()> {
+        let (mut socket, _) =
+            tungstenite::connect(url).map_err(|e| anyhow!("Cannot connect to rosbridge at {}: {}", url, e))?;
+        for message in self.setup_messages() {
+            socket.send(Message::text(message.to_string()))?;
+        }
+
+        let period = Duration::from_secs_f64(1.0 / self.rate_hz);
+        let mut next_feedback = Instant::now();
+        while running.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if now >= next_feedback {
+                match self.feedback() {
+                    Ok(state) => {
+                        let publish = json!({"op": "publish", "topic": self.topic("joint_states"), "msg": state});
+                        socket.send(Message::text(publish.to_string()))?;
+                    }
+                    Err(e) => self.last_error = Some(format!("{:#}", e)),
+                }
+                next_feedback += period;
+                if next_feedback < now {
+                    next_feedback = now + period;
+                }
+                continue;
+            }
+
+            set_read_timeout(&socket, (next_feedback - now).max(Duration::from_millis(1)))?;
+            let text = match socket.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => return Err(anyhow!("rosbridge at {} closed the connection", url)),
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let message = match serde_json::from_str(&text) {
+                Ok(message) => message,
+                Err(e) => {
+                    self.reject(anyhow!("malformed rosbridge message: {}", e));
+                    continue;
+                }
+            };
+            if let Some(reply) = self.handle(&message) {
+                socket.send(Message::text(reply.to_string()))?;
+            }
+        }
+        socket.close(None).ok();
+        Ok(())
+    }
+
+    fn joint(&self, name: &str) -> Result<&JointMap> {
+        self.mapping
+            .joints
+            .iter()
+            .find(|j| j.name == name)
+            .ok_or(anyhow!("joint '{}' is not in the mapping", name))
+    }
+}
+
+fn set_read_timeout(socket: &WebSocket<MaybeTlsStream<TcpStream>>, timeout: Duration) -> Result<()> {
+    if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+        stream.set_read_timeout(Some(timeout))?;
+    }
+    Ok(())
+}
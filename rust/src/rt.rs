@@ -0,0 +1,64 @@
+//! Real-time priority and CPU affinity of the calling thread (Linux)
+//!
+//! Under the default scheduler a 1 kHz loop loses whole periods whenever a
+//! compiler or logger gets the CPU. [`set_realtime_priority`] moves the calling
+//! thread to `SCHED_FIFO`, where it preempts every normal thread, and
+//! [`pin_to_core`] keeps it on one CPU, ideally one reserved with `isolcpus`.
+//! [`ControlLoop`](crate::ControlLoop) applies both when asked to.
+//!
+//! `SCHED_FIFO` needs root, `CAP_SYS_NICE` or an `rtprio` limit, e.g. in
+//! `/etc/security/limits.conf`:
+//!
+//! ```text
+//! @robot  -  rtprio  90
+//! ```
+
+use anyhow::{Result, anyhow};
+use std::io;
+
+/// Run the calling thread under `SCHED_FIFO` at `prio` (1 = lowest, 99 = highest)
+pub fn set_realtime_priority(prio: i32) -> Result<()> {
+    // SAFETY: plain queries of the scheduler's priority range
+    let (min, max) = unsafe {
+        (
+            libc::sched_get_priority_min(libc::SCHED_FIFO),
+            libc::sched_get_priority_max(libc::SCHED_FIFO),
+        )
+    };
+    if !(min..=max).contains(&prio) {
+        return Err(anyhow!("SCHED_FIFO priority {} out of range {}..={}", prio, min, max));
+    }
+
+    let param = libc::sched_param { sched_priority: prio };
+    // SAFETY: pthread_self is the calling thread, param is a valid sched_param
+    let ret = unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+    if ret != 0 {
+        let hint = if ret == libc::EPERM { " (needs root, CAP_SYS_NICE or an rtprio limit)" } else { "" };
+        return Err(anyhow!(
+            "Cannot set SCHED_FIFO priority {}: {}{}",
+            prio,
+            io::Error::from_raw_os_error(ret),
+            hint
+        ));
+    }
+    Ok(())
+}
+
+/// Restrict the calling thread to CPU `core`
+pub fn pin_to_core(core: usize) -> Result<()> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(anyhow!("CPU {} out of range", core));
+    }
+    // SAFETY: cpu_set_t is plain data; the set is initialized before use and
+    // sched_setaffinity gets its exact size
+    let ret = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if ret != 0 {
+        return Err(anyhow!("Cannot pin thread to CPU {}: {}", core, io::Error::last_os_error()));
+    }
+    Ok(())
+}
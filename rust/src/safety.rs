@@ -0,0 +1,338 @@
+//! Host-side safety layer
+//!
+//! Enforces per-motor torque duty envelopes: torque above the continuous rating
+//! is allowed only for a limited time (the peak budget). Once the budget is used
+//! up, commands are clamped to the continuous torque until it recovers.
+//! Optionally, all torque limits are scaled down while the battery sags.
+//! Motor faults are answered according to a [`FaultPolicy`].
+
+use crate::events::{EnvelopeEventKind, EventBus, EventKind};
+use crate::{BatteryState, FaultAction, FaultClass, FaultPolicy, LivelyMotorController, Register};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Continuous / peak torque rating of a motor model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TorqueEnvelope {
+    /// Torque that can be held indefinitely (Nm)
+    pub continuous_nm: f64,
+    /// Absolute maximum torque (Nm)
+    pub peak_nm: f64,
+    /// How long torque above `continuous_nm` may be sustained
+    pub peak_duration: Duration,
+}
+
+/// Nominal envelopes of known motor models, keyed by the name reported on ping.
+/// Values are conservative datasheet figures; override them for your hardware.
+const MODEL_ENVELOPES: &[(&str, f64, f64, f64)] = &[
+    // (model, continuous Nm, peak Nm, peak duration s)
+    ("4538", 1.5, 4.5, 2.0),
+    ("5046", 2.5, 7.0, 2.0),
+    ("5047", 3.0, 8.0, 2.0),
+    ("6056", 8.0, 20.0, 2.0),
+];
+
+impl TorqueEnvelope {
+    pub fn new(continuous_nm: f64, peak_nm: f64, peak_duration: Duration) -> Self {
+        Self {
+            continuous_nm,
+            peak_nm: peak_nm.max(continuous_nm),
+            peak_duration,
+        }
+    }
+
+    /// Envelope of a known motor model
+    pub fn for_model(model: &str) -> Option<Self> {
+        MODEL_ENVELOPES
+            .iter()
+            .find(|(name, ..)| model.trim().contains(name))
+            .map(|&(_, cont, peak, secs)| Self::new(cont, peak, Duration::from_secs_f64(secs)))
+    }
+}
+
+/// Tracks time spent above the continuous torque for one motor
+#[derive(Debug, Clone)]
+pub struct DutyTracker {
+    envelope: TorqueEnvelope,
+    /// Seconds of peak budget used
+    used: f64,
+    limited: bool,
+    warned: bool,
+    peak_clamping: bool,
+    last_update: Option<Instant>,
+}
+
+impl DutyTracker {
+    /// Budget fraction above which an `Approaching` event is raised
+    pub const WARN_FRACTION: f64 = 0.8;
+    /// Budget fraction below which a limited motor gets peak torque back
+    pub const RECOVER_FRACTION: f64 = 0.5;
+    /// Budget recovery speed relative to consumption
+    pub const RECOVERY_RATE: f64 = 0.5;
+
+    pub fn new(envelope: TorqueEnvelope) -> Self {
+        Self {
+            envelope,
+            used: 0.0,
+            limited: false,
+            warned: false,
+            peak_clamping: false,
+            last_update: None,
+        }
+    }
+
+    pub fn envelope(&self) -> TorqueEnvelope {
+        self.envelope
+    }
+
+    /// Fraction of the peak budget used (0..=1)
+    pub fn budget_used(&self) -> f64 {
+        let budget = self.envelope.peak_duration.as_secs_f64();
+        if budget <= 0.0 {
+            return if self.used > 0.0 { 1.0 } else { 0.0 };
+        }
+        (self.used / budget).clamp(0.0, 1.0)
+    }
+
+    /// Whether commands are currently clamped to the continuous torque
+    pub fn is_limited(&self) -> bool {
+        self.limited
+    }
+
+    /// Account for a command of `torque_nm` issued at `now` and return the
+    /// allowed torque (same sign) along with any envelope transitions
+    pub fn update(&mut self, torque_nm: f64, now: Instant) -> (f64, Vec<EnvelopeEventKind>) {
+        let dt = self
+            .last_update
+            .map(|t| now.saturating_duration_since(t).as_secs_f64())
+            .unwrap_or(0.0);
+        self.last_update = Some(now);
+
+        let mut events = Vec::new();
+        let requested = torque_nm.abs();
+        let env = self.envelope;
+
+        let clamping = requested > env.peak_nm;
+        if clamping && !self.peak_clamping {
+            events.push(EnvelopeEventKind::PeakClamped);
+        }
+        self.peak_clamping = clamping;
+
+        let mut allowed = requested.min(env.peak_nm);
+        if self.limited {
+            allowed = allowed.min(env.continuous_nm);
+        }
+
+        let budget = env.peak_duration.as_secs_f64();
+        if allowed > env.continuous_nm {
+            self.used = (self.used + dt).min(budget);
+        } else {
+            self.used = (self.used - dt * Self::RECOVERY_RATE).max(0.0);
+        }
+
+        let fraction = self.budget_used();
+        if !self.limited && fraction >= 1.0 {
+            self.limited = true;
+            events.push(EnvelopeEventKind::Limited);
+        } else if self.limited && fraction <= Self::RECOVER_FRACTION {
+            self.limited = false;
+            self.warned = false;
+            events.push(EnvelopeEventKind::Recovered);
+        }
+
+        if !self.warned && !self.limited && fraction >= Self::WARN_FRACTION {
+            self.warned = true;
+            events.push(EnvelopeEventKind::Approaching);
+        } else if self.warned && fraction < Self::WARN_FRACTION {
+            self.warned = false;
+        }
+
+        (allowed.copysign(torque_nm), events)
+    }
+}
+
+/// Torque derating over battery voltage
+///
+/// Full torque at or above `nominal_v`, falling linearly to `min_scale` of it
+/// at `cutoff_v` and below.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryDerating {
+    pub nominal_v: f64,
+    pub cutoff_v: f64,
+    pub min_scale: f64,
+}
+
+impl BatteryDerating {
+    pub fn new(nominal_v: f64, cutoff_v: f64, min_scale: f64) -> Self {
+        Self {
+            nominal_v,
+            cutoff_v: cutoff_v.min(nominal_v),
+            min_scale: min_scale.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Torque scale at `voltage_v`
+    pub fn scale(&self, voltage_v: f64) -> f64 {
+        let span = self.nominal_v - self.cutoff_v;
+        if span <= 0.0 {
+            return if voltage_v >= self.nominal_v { 1.0 } else { self.min_scale };
+        }
+        let t = ((voltage_v - self.cutoff_v) / span).clamp(0.0, 1.0);
+        self.min_scale + (1.0 - self.min_scale) * t
+    }
+}
+
+/// Safety layer applied to host-side commands of several motors
+pub struct SafetyMonitor {
+    trackers: Mutex<HashMap<u8, DutyTracker>>,
+    events: Option<Arc<EventBus>>,
+    derating: Option<BatteryDerating>,
+    /// Current battery torque scale
+    torque_scale: Mutex<f64>,
+    fault_policy: FaultPolicy,
+}
+
+impl SafetyMonitor {
+    pub fn new() -> Self {
+        Self {
+            trackers: Mutex::new(HashMap::new()),
+            events: None,
+            derating: None,
+            torque_scale: Mutex::new(1.0),
+            fault_policy: FaultPolicy::default(),
+        }
+    }
+
+    /// Answer faults according to `policy` instead of stopping on every fault
+    pub fn with_fault_policy(mut self, policy: FaultPolicy) -> Self {
+        self.fault_policy = policy;
+        self
+    }
+
+    pub fn fault_policy(&self) -> &FaultPolicy {
+        &self.fault_policy
+    }
+
+    /// Scale torque limits down with battery voltage (see [`update_battery`](Self::update_battery))
+    pub fn with_battery_derating(mut self, derating: BatteryDerating) -> Self {
+        self.derating = Some(derating);
+        self
+    }
+
+    /// Feed a BMS reading; returns the torque scale now applied
+    pub fn update_battery(&self, battery: &BatteryState) -> f64 {
+        let Some(derating) = self.derating else {
+            return 1.0;
+        };
+
+        let scale = derating.scale(battery.voltage_v);
+        let previous = std::mem::replace(&mut *self.torque_scale.lock().unwrap(), scale);
+        // Report entering, leaving and 10 % steps of derating
+        let step = |s: f64| (s * 10.0).floor();
+        if let Some(bus) = &self.events {
+            if (previous < 1.0) != (scale < 1.0) || step(previous) != step(scale) {
+                bus.publish(EventKind::BatteryDerating {
+                    voltage_v: battery.voltage_v,
+                    torque_scale: scale,
+                });
+            }
+        }
+        scale
+    }
+
+    /// Torque scale from the last battery reading (1.0 without derating)
+    pub fn torque_scale(&self) -> f64 {
+        *self.torque_scale.lock().unwrap()
+    }
+
+    /// Publish envelope transitions on `bus`
+    pub fn with_events(mut self, bus: Arc<EventBus>) -> Self {
+        self.events = Some(bus);
+        self
+    }
+
+    /// Set the torque envelope of a motor
+    pub fn set_envelope(&self, motor_id: u8, envelope: TorqueEnvelope) {
+        self.trackers
+            .lock()
+            .unwrap()
+            .insert(motor_id, DutyTracker::new(envelope));
+    }
+
+    /// Fraction of a motor's peak budget used, `None` without envelope
+    pub fn budget_used(&self, motor_id: u8) -> Option<f64> {
+        self.trackers.lock().unwrap().get(&motor_id).map(|t| t.budget_used())
+    }
+
+    /// Carry out the policy's response to a fault of `motor_id`
+    ///
+    /// `Brake` zeroes the stiffness and sets the brake damping, `Stop`
+    /// brings every enabled motor to rest with the policy's stop mode and
+    /// disables it. `Hold` leaves the motor as it is; the
+    /// caller stops forwarding setpoints (see [`ManagedMotor::check_fault`](crate::ManagedMotor::check_fault)).
+    pub fn respond_to_fault(
+        &self,
+        controller: &LivelyMotorController,
+        motor_id: u8,
+        class: FaultClass,
+        code: Option<u8>,
+    ) -> Result<FaultAction> {
+        let action = self.fault_policy.action(motor_id, class);
+        if action != FaultAction::Ignore {
+            if let Some(bus) = &self.events {
+                bus.publish(EventKind::MotorFault {
+                    motor_id,
+                    code,
+                    class,
+                    action,
+                });
+            }
+        }
+
+        match action {
+            FaultAction::Ignore | FaultAction::Warn | FaultAction::Hold => {}
+            FaultAction::Brake => {
+                controller.write_register_float(motor_id, Register::Kp, 0.0)?;
+                controller.write_register_float(motor_id, Register::Kd, self.fault_policy.brake_kd)?;
+            }
+            FaultAction::Stop => {
+                controller.emergency_stop(&controller.enabled_motors(), self.fault_policy.stop_mode)?;
+            }
+        }
+        Ok(action)
+    }
+
+    /// Limit a torque command for `motor_id`; motors without envelope are only
+    /// scaled by battery derating
+    pub fn limit_torque(&self, motor_id: u8, torque_nm: f64) -> f64 {
+        let scale = self.torque_scale();
+        let mut trackers = self.trackers.lock().unwrap();
+        let Some(tracker) = trackers.get_mut(&motor_id) else {
+            return torque_nm * scale;
+        };
+
+        let (allowed, transitions) = tracker.update(torque_nm, Instant::now());
+        let budget_used = tracker.budget_used();
+        drop(trackers);
+
+        if let Some(bus) = &self.events {
+            for kind in transitions {
+                bus.publish(EventKind::TorqueEnvelope {
+                    motor_id,
+                    kind,
+                    budget_used,
+                });
+            }
+        }
+
+        allowed * scale
+    }
+}
+
+impl Default for SafetyMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,57 @@
+//! Conversion constant check
+//!
+//! The int16 position, velocity and torque values of the protocol are scaled
+//! with [`FACTOR_POS`], [`FACTOR_VEL`] and [`FACTOR_TQE`]. Newer firmware
+//! reports the scaling it uses in three float registers; a motor scaling
+//! differently would silently move twice as far or push twice as hard.
+//! [`LivelyMotorController::check_scaling`](crate::LivelyMotorController::check_scaling)
+//! reads them once at startup and fails with
+//! [`MotorError::ScalingMismatch`](crate::MotorError::ScalingMismatch) on any
+//! difference. Firmware without the registers does not answer and passes.
+
+use crate::{FACTOR_POS, FACTOR_TQE, FACTOR_VEL};
+use serde::{Deserialize, Serialize};
+
+/// Relative difference tolerated between reported and expected constants,
+/// for the float rounding of the registers
+const TOLERANCE: f64 = 1e-4;
+
+/// Counts per turn, per r/s and per Nm of a motor's int16 values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Scaling {
+    pub position: f64,
+    pub velocity: f64,
+    pub torque: f64,
+}
+
+impl Scaling {
+    /// The scaling the crate encodes and decodes with
+    pub const EXPECTED: Scaling = Scaling {
+        position: FACTOR_POS,
+        velocity: FACTOR_VEL,
+        torque: FACTOR_TQE,
+    };
+
+    /// Constants differing from `expected`, as `name reported X, expected Y`
+    pub fn mismatches(&self, expected: &Scaling) -> Vec<String> {
+        [
+            ("position", self.position, expected.position),
+            ("velocity", self.velocity, expected.velocity),
+            ("torque", self.torque, expected.torque),
+        ]
+        .into_iter()
+        .filter(|&(_, reported, expected)| {
+            // NaN never matches
+            let matches = (reported - expected).abs() <= expected.abs() * TOLERANCE;
+            !matches
+        })
+        .map(|(name, reported, expected)| format!("{} scaling {} counts, expected {}", name, reported, expected))
+        .collect()
+    }
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Self::EXPECTED
+    }
+}
@@ -0,0 +1,180 @@
+//! Compatibility layer for the official `livelybot_hardware_sdk` API
+//!
+//! Mirrors the class and method names of the SDK's Python bindings
+//! (`lively_robot`, `motor`, `motor_back_t`) so scripts written against the
+//! SDK can switch to this crate with few edits; with the `python` feature the
+//! same types are exported to Python under those names. Units follow the
+//! SDK: radians, rad/s and Nm.
+//!
+//! Commands are sent when called rather than buffered, so `motor_send_2` only
+//! exists for source compatibility.
+
+use crate::{LivelyMotorController, Register};
+use anyhow::{Result, anyhow};
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Position value that selects pure velocity control in the 0xAD stream
+const VELOCITY_MODE_POSITION: i16 = i16::MIN;
+
+/// Feedback of one motor (SDK `motor_back_t`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotorBack {
+    /// Seconds since the robot was created
+    pub time: f64,
+    pub id: u8,
+    /// Active fault code, 0 = no fault
+    pub fault: u8,
+    /// rad
+    pub position: f64,
+    /// rad/s
+    pub velocity: f64,
+    /// Nm
+    pub torque: f64,
+}
+
+/// One motor of a [`LivelyRobot`] (SDK `motor`)
+pub struct Motor {
+    controller: Arc<LivelyMotorController>,
+    motor_id: u8,
+    start: Instant,
+    /// Gains last written by `pos_vel_tqe_kp_kd`
+    gains: Mutex<Option<(f32, f32)>>,
+}
+
+impl Motor {
+    pub fn get_motor_id(&self) -> u8 {
+        self.motor_id
+    }
+
+    /// Read the motor's feedback
+    pub fn get_current_motor_state(&self) -> Result<MotorBack> {
+        let state = self.controller.read_motor_state(self.motor_id)?;
+        Ok(MotorBack {
+            time: self.start.elapsed().as_secs_f64(),
+            id: self.motor_id,
+            fault: self.controller.read_fault(self.motor_id).unwrap_or(0),
+            position: state.position_deg.to_radians(),
+            velocity: state.velocity_rps * 2.0 * PI,
+            torque: state.torque_nm,
+        })
+    }
+
+    /// Move to `position` with the default 2.0 r/s and 3.0 Nm limits
+    pub fn position(&self, position: f64) -> Result<()> {
+        self.pos_vel_MAXtqe(position, 2.0 * 2.0 * PI, 3.0)
+    }
+
+    /// Run at `velocity` with the 5 r/s² default acceleration
+    pub fn velocity(&self, velocity: f64) -> Result<()> {
+        Ok(self.controller.send_velocity_command_to(
+            self.motor_id,
+            VELOCITY_MODE_POSITION,
+            crate::rps_to_velocity(velocity / (2.0 * PI)),
+            crate::rps2_to_acceleration(5.0),
+        )?)
+    }
+
+    /// Move to `position` limited to `velocity` and `torque_max`
+    #[allow(non_snake_case)]
+    pub fn pos_vel_MAXtqe(&self, position: f64, velocity: f64, torque_max: f64) -> Result<()> {
+        Ok(self.controller.send_angle_command_to(
+            self.motor_id,
+            crate::degrees_to_position(position.to_degrees()),
+            crate::rps_to_velocity(velocity.abs() / (2.0 * PI)),
+            crate::nm_to_torque(torque_max.abs()),
+        )?)
+    }
+
+    /// Impedance command: `position` with stiffness `kp` and damping `kd`
+    ///
+    /// Sent as gain writes plus an angle stream, which every firmware accepts:
+    /// `torque` bounds the output instead of adding feed-forward, and
+    /// `velocity` limits the approach speed (at least 1 r/s). See
+    /// [`LivelyMotorController::send_mit_command`] for a true MIT frame.
+    pub fn pos_vel_tqe_kp_kd(&self, position: f64, velocity: f64, torque: f64, kp: f32, kd: f32) -> Result<()> {
+        {
+            let mut gains = self.gains.lock().unwrap();
+            if *gains != Some((kp, kd)) {
+                self.controller.write_register_float(self.motor_id, Register::Kp, kp)?;
+                self.controller.write_register_float(self.motor_id, Register::Kd, kd)?;
+                *gains = Some((kp, kd));
+            }
+        }
+        let velocity = velocity.abs().max(2.0 * PI);
+        self.pos_vel_MAXtqe(position, velocity, torque)
+    }
+}
+
+/// A set of motors on one CAN channel (SDK `lively_robot`)
+pub struct LivelyRobot {
+    controller: Arc<LivelyMotorController>,
+    motors: Vec<Arc<Motor>>,
+}
+
+impl LivelyRobot {
+    /// Open `channel` and enable `motor_ids` in position mode
+    pub fn new(channel: &str, bitrate: u32, motor_ids: &[u8]) -> Result<Self> {
+        Self::with_controller(Arc::new(LivelyMotorController::new(channel, bitrate)?), motor_ids)
+    }
+
+    /// Use an existing controller
+    pub fn with_controller(controller: Arc<LivelyMotorController>, motor_ids: &[u8]) -> Result<Self> {
+        if motor_ids.is_empty() {
+            return Err(anyhow!("lively_robot needs at least one motor"));
+        }
+        let start = Instant::now();
+        let mut motors = Vec::new();
+        for &motor_id in motor_ids {
+            controller.enable_motor(motor_id)?;
+            motors.push(Arc::new(Motor {
+                controller: Arc::clone(&controller),
+                motor_id,
+                start,
+                gains: Mutex::new(None),
+            }));
+        }
+        Ok(Self { controller, motors })
+    }
+
+    /// The robot's motors, in the order given (SDK `Motors`)
+    pub fn motors(&self) -> &[Arc<Motor>] {
+        &self.motors
+    }
+
+    pub fn controller(&self) -> &Arc<LivelyMotorController> {
+        &self.controller
+    }
+
+    /// Commands are sent immediately; kept for source compatibility
+    pub fn motor_send_2(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Disable all motors
+    pub fn set_stop(&self) -> Result<()> {
+        for motor in &self.motors {
+            self.controller.disable_motor(motor.motor_id)?;
+        }
+        Ok(())
+    }
+
+    /// Disable and re-enable all motors, clearing their command state
+    pub fn set_reset(&self) -> Result<()> {
+        self.set_stop()?;
+        for motor in &self.motors {
+            self.controller.enable_motor(motor.motor_id)?;
+            *motor.gains.lock().unwrap() = None;
+        }
+        Ok(())
+    }
+
+    /// Set the current position of every motor as its zero
+    pub fn set_reset_zero(&self) -> Result<()> {
+        for motor in &self.motors {
+            self.controller.set_zero(motor.motor_id)?;
+        }
+        Ok(())
+    }
+}
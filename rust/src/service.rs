@@ -0,0 +1,198 @@
+//! Running the motor layer as a system service
+//!
+//! [`ServiceConfig`] describes the robot a `motord` daemon serves: the bus,
+//! the motors it watches, how it parks them on shutdown and which clients may
+//! command it. [`SystemdNotifier`] speaks the systemd notify protocol
+//! (`sd_notify`): readiness, status text, watchdog keep-alives and stopping,
+//! sent as datagrams to `$NOTIFY_SOCKET`.
+//! Outside systemd the variable is unset and every notification is a no-op.
+
+use crate::AccessPolicy;
+use crate::config::{self, RobotConfig};
+use crate::remote::DEFAULT_BRIDGE_PORT;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr as UnixAddr, UnixDatagram};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Robot served by the daemon, loaded from JSON, TOML or YAML
+///
+/// A robot description ([`RobotConfig`]) may be used as the config: its bus
+/// and the joints on it stand in for `interface`, `bitrate` and `motor_ids`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    #[serde(default = "default_interface")]
+    pub interface: String,
+    #[serde(default = "default_bitrate")]
+    pub bitrate: u32,
+    pub motor_ids: Vec<u8>,
+    /// UDP address of the bridge interface ([`BridgeAgent`](crate::BridgeAgent))
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Period of the motor watchdog's ping round
+    #[serde(default = "default_watchdog_ms")]
+    pub watchdog_ms: u64,
+    /// A motor silent for this long trips the watchdog, which disables all motors
+    #[serde(default = "default_motor_timeout_ms")]
+    pub motor_timeout_ms: u64,
+    /// Park config to run on shutdown, relative to this config's directory
+    #[serde(default)]
+    pub park_config: Option<PathBuf>,
+    #[serde(default = "default_park_pose")]
+    pub park_pose: String,
+    /// Bridge client tokens; without any, anonymous clients are observers
+    #[serde(default)]
+    pub access: AccessPolicy,
+    /// UDP address (e.g. a multicast group) the bus traffic of the motors is
+    /// mirrored to, see [`TelemetryRecorder::mirror_udp`](crate::TelemetryRecorder::mirror_udp)
+    #[serde(default)]
+    pub mirror: Option<String>,
+    /// Robot description the config was loaded from
+    #[serde(skip)]
+    pub robot: Option<RobotConfig>,
+}
+
+fn default_interface() -> String {
+    "can0".to_string()
+}
+
+fn default_bitrate() -> u32 {
+    1_000_000
+}
+
+fn default_listen() -> String {
+    format!("0.0.0.0:{}", DEFAULT_BRIDGE_PORT)
+}
+
+fn default_watchdog_ms() -> u64 {
+    200
+}
+
+fn default_motor_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_park_pose() -> String {
+    "park".to_string()
+}
+
+impl ServiceConfig {
+    /// Load a config; a relative `park_config` is resolved against the config's directory
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut value = config::load_value(path)?;
+        let robot = config::is_robot_description(&value).then(|| RobotConfig::load(path)).transpose()?;
+        if let (Some(robot), Some(fields)) = (&robot, value.as_object_mut()) {
+            let interface = robot.bus.interface.clone();
+            fields.entry("motor_ids").or_insert_with(|| robot.motor_ids_on(&interface).into());
+            fields.entry("interface").or_insert_with(|| interface.into());
+            fields.entry("bitrate").or_insert_with(|| robot.bus.bitrate.into());
+        }
+        let mut config: Self =
+            serde_json::from_value(value).map_err(|e| anyhow!("Invalid service config {}: {}", path.display(), e))?;
+        config.robot = robot;
+        if let (Some(park), Some(dir)) = (&config.park_config, path.parent()) {
+            if park.is_relative() {
+                config.park_config = Some(dir.join(park));
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.motor_ids.is_empty() {
+            return Err(anyhow!("service config lists no motors"));
+        }
+        if self.watchdog_ms == 0 || self.motor_timeout_ms < self.watchdog_ms {
+            return Err(anyhow!(
+                "motor timeout of {} ms must be at least the watchdog period of {} ms",
+                self.motor_timeout_ms,
+                self.watchdog_ms
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn watchdog_period(&self) -> Duration {
+        Duration::from_millis(self.watchdog_ms)
+    }
+
+    pub fn motor_timeout(&self) -> Duration {
+        Duration::from_millis(self.motor_timeout_ms)
+    }
+}
+
+/// Sends service state to systemd
+pub struct SystemdNotifier {
+    socket: Option<(UnixDatagram, UnixAddr)>,
+    watchdog: Option<Duration>,
+}
+
+impl SystemdNotifier {
+    /// Notifier for `$NOTIFY_SOCKET` and `$WATCHDOG_USEC`, inactive if unset
+    pub fn from_env() -> Result<Self> {
+        let socket = match std::env::var_os("NOTIFY_SOCKET") {
+            Some(path) => Some((UnixDatagram::unbound()?, notify_addr(Path::new(&path))?)),
+            None => None,
+        };
+        // WATCHDOG_PID, if set, names the process that must send keep-alives
+        let for_us = std::env::var("WATCHDOG_PID")
+            .ok()
+            .is_none_or(|pid| pid.parse() == Ok(std::process::id()));
+        let watchdog = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|&usec: &u64| usec > 0 && for_us)
+            .map(Duration::from_micros);
+        Ok(Self { socket, watchdog })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.socket.is_some()
+    }
+
+    /// Watchdog timeout configured in the unit (`WatchdogSec=`)
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        self.watchdog
+    }
+
+    /// Send raw `KEY=VALUE` lines
+    pub fn notify(&self, state: &str) -> Result<()> {
+        if let Some((socket, addr)) = &self.socket {
+            socket
+                .send_to_addr(state.as_bytes(), addr)
+                .map_err(|e| anyhow!("Cannot notify systemd: {}", e))?;
+        }
+        Ok(())
+    }
+
+    pub fn ready(&self, status: &str) -> Result<()> {
+        self.notify(&format!("READY=1\nSTATUS={}", status))
+    }
+
+    pub fn status(&self, status: &str) -> Result<()> {
+        self.notify(&format!("STATUS={}", status))
+    }
+
+    /// Watchdog keep-alive; send at least every half timeout
+    pub fn watchdog(&self) -> Result<()> {
+        self.notify("WATCHDOG=1")
+    }
+
+    pub fn stopping(&self, status: &str) -> Result<()> {
+        self.notify(&format!("STOPPING=1\nSTATUS={}", status))
+    }
+}
+
+/// Address of a notify socket path; a leading `@` names an abstract socket
+fn notify_addr(path: &Path) -> Result<UnixAddr> {
+    let bytes = path.as_os_str().as_bytes();
+    match bytes.strip_prefix(b"@") {
+        Some(name) => Ok(UnixAddr::from_abstract_name(name)?),
+        None => Ok(UnixAddr::from_pathname(path)?),
+    }
+}
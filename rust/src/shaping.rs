@@ -0,0 +1,193 @@
+//! Bus load shaping for shared CAN buses
+//!
+//! Other nodes (IMU, battery BMS) often share the motor bus. Reserving a
+//! fraction of the bandwidth with [`CanBus::reserve_bandwidth`](crate::CanBus::reserve_bandwidth)
+//! spaces motor frames so they never take more than the rest, and
+//! [`BusMonitor::load_report`](crate::BusMonitor::load_report) measures how much
+//! the foreign nodes actually use.
+
+use anyhow::{Result, anyhow};
+use socketcan::{CanFrame, EmbeddedFrame};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Worst-case bits a frame occupies on the wire, including bit stuffing and
+/// the interframe space
+pub fn frame_bits(frame: &CanFrame) -> u32 {
+    let data_bits = 8 * frame.dlc() as u32;
+    // Stuffable bits: SOF through CRC; fixed bits: CRC delimiter, ACK, EOF, IFS
+    let (stuffable, fixed) = if frame.is_extended() {
+        (54 + data_bits, 13)
+    } else {
+        (34 + data_bits, 13)
+    };
+    stuffable + (stuffable - 1) / 4 + fixed
+}
+
+/// Whether a frame belongs to the motor protocol
+///
+/// Motor commands, replies and the 0x90 / 0xAD streams all use extended IDs
+/// up to 16 bits; anything else is traffic of another node.
+pub fn is_motor_frame(frame: &CanFrame) -> bool {
+    frame.is_extended() && crate::raw_id(frame) <= 0xFFFF
+}
+
+/// Whether a frame is a motor query: a frame asking for a reply that is not a
+/// setpoint stream (state and register reads, info queries, pings)
+pub fn is_query(frame: &CanFrame) -> bool {
+    let id = crate::raw_id(frame);
+    is_motor_frame(frame) && id & crate::protocol::REPLY_FLAG != 0 && crate::protocol::stream_target(id).is_none()
+}
+
+/// Spaces motor queries while the bus is degraded, see
+/// [`CanBus::throttle_queries`](crate::CanBus::throttle_queries)
+#[derive(Debug)]
+pub(crate) struct QueryThrottle {
+    spacing: Duration,
+    telemetry_scale: f64,
+    next_slot: Option<Instant>,
+}
+
+impl QueryThrottle {
+    pub(crate) fn new() -> Self {
+        Self {
+            spacing: Duration::ZERO,
+            telemetry_scale: 1.0,
+            next_slot: None,
+        }
+    }
+
+    pub(crate) fn spacing(&self) -> Duration {
+        self.spacing
+    }
+
+    pub(crate) fn telemetry_scale(&self) -> f64 {
+        self.telemetry_scale
+    }
+
+    pub(crate) fn set(&mut self, spacing: Duration, telemetry_scale: f64) -> Result<()> {
+        if !telemetry_scale.is_finite() || telemetry_scale <= 0.0 || telemetry_scale > 1.0 {
+            return Err(anyhow!("telemetry scale must be in (0, 1], got {}", telemetry_scale));
+        }
+        self.spacing = spacing;
+        self.telemetry_scale = telemetry_scale;
+        self.next_slot = None;
+        Ok(())
+    }
+
+    /// How long to wait before sending a query, booking its slot
+    pub(crate) fn delay(&mut self, now: Instant) -> Duration {
+        if self.spacing.is_zero() {
+            return Duration::ZERO;
+        }
+        let start = self.next_slot.map_or(now, |next| next.max(now));
+        self.next_slot = Some(start + self.spacing);
+        start - now
+    }
+}
+
+/// Spaces transmitted frames so they stay within a share of the bitrate
+#[derive(Debug)]
+pub(crate) struct LoadShaper {
+    reserved: f64,
+    next_slot: Option<Instant>,
+}
+
+impl LoadShaper {
+    pub(crate) fn new() -> Self {
+        Self {
+            reserved: 0.0,
+            next_slot: None,
+        }
+    }
+
+    pub(crate) fn reserved(&self) -> f64 {
+        self.reserved
+    }
+
+    pub(crate) fn set_reserved(&mut self, fraction: f64) -> Result<()> {
+        if !(0.0..1.0).contains(&fraction) {
+            return Err(anyhow!("reserved bandwidth must be in [0, 1), got {}", fraction));
+        }
+        self.reserved = fraction;
+        self.next_slot = None;
+        Ok(())
+    }
+
+    /// How long to wait before sending `frame`, booking its slot
+    pub(crate) fn delay(&mut self, frame: &CanFrame, bitrate: u32, now: Instant) -> Duration {
+        if self.reserved == 0.0 || bitrate == 0 {
+            return Duration::ZERO;
+        }
+
+        let share = bitrate as f64 * (1.0 - self.reserved);
+        let slot = Duration::from_secs_f64(frame_bits(frame) as f64 / share);
+        let start = self.next_slot.map_or(now, |next| next.max(now));
+        self.next_slot = Some(start + slot);
+        start - now
+    }
+}
+
+/// Measured bus usage split into motor and foreign traffic
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusLoadReport {
+    /// Observation time
+    pub elapsed: Duration,
+    /// Fraction of the bitrate used by motor frames
+    pub motor_load: f64,
+    /// Fraction of the bitrate used by other nodes
+    pub foreign_load: f64,
+    /// Foreign frames per second, per arbitration ID
+    pub foreign_rates: HashMap<u32, f64>,
+    /// Foreign frames not in `foreign_rates` because too many IDs were seen
+    /// (see [`MAX_TRACKED_IDS`](crate::bus::MAX_TRACKED_IDS))
+    pub untracked_foreign_frames: u64,
+    /// Fraction reserved for foreign traffic on this bus
+    pub reserved: f64,
+}
+
+impl BusLoadReport {
+    /// Whether the foreign nodes stay within the reservation
+    pub fn foreign_fits(&self) -> bool {
+        self.foreign_load <= self.reserved
+    }
+}
+
+/// Bit counters behind a [`BusLoadReport`]
+#[derive(Debug, Default)]
+pub(crate) struct LoadCounter {
+    motor_bits: u64,
+    foreign_bits: u64,
+    foreign_frames: HashMap<u32, u64>,
+    untracked_foreign_frames: u64,
+}
+
+impl LoadCounter {
+    pub(crate) fn record(&mut self, frame: &CanFrame) {
+        if is_motor_frame(frame) {
+            self.motor_bits += frame_bits(frame) as u64;
+        } else {
+            self.foreign_bits += frame_bits(frame) as u64;
+            if !crate::bus::count_id(&mut self.foreign_frames, crate::raw_id(frame)) {
+                self.untracked_foreign_frames += 1;
+            }
+        }
+    }
+
+    pub(crate) fn report(&self, elapsed: Duration, bitrate: u32, reserved: f64) -> BusLoadReport {
+        let seconds = elapsed.as_secs_f64().max(1e-9);
+        let capacity = bitrate.max(1) as f64 * seconds;
+        BusLoadReport {
+            elapsed,
+            motor_load: self.motor_bits as f64 / capacity,
+            foreign_load: self.foreign_bits as f64 / capacity,
+            foreign_rates: self
+                .foreign_frames
+                .iter()
+                .map(|(&id, &count)| (id, count as f64 / seconds))
+                .collect(),
+            untracked_foreign_frames: self.untracked_foreign_frames,
+            reserved,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Group state snapshots that survive a process restart
+//!
+//! A [`GroupSnapshot`] records what a [`MotorGroup`](crate::MotorGroup) was
+//! doing: which members were enabled and how, the last setpoint sent to each,
+//! the active gain profile and the last measured angles. A control process
+//! that crashed and restarted loads it and calls
+//! [`MotorGroup::restore`](crate::MotorGroup::restore), which checks that every
+//! joint is still where it was told to be before holding the same pose again,
+//! instead of coming up with zero targets.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Last setpoint sent to a member
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GroupSetpoint {
+    Angle { angle_deg: f64, max_vel_rps: f64, max_tqe_nm: f64 },
+    Velocity { velocity_rps: f64, acceleration_rps2: f64 },
+}
+
+/// Mode a member was enabled in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnabledMode {
+    Position,
+    Velocity,
+}
+
+/// State of one member
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JointSnapshot {
+    pub motor_id: u8,
+    /// `None` if the member was disabled
+    pub enabled: Option<EnabledMode>,
+    pub setpoint: Option<GroupSetpoint>,
+    /// Last measured angle, if the motor was read
+    pub position_deg: Option<f64>,
+}
+
+/// State of a motor group, saved as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupSnapshot {
+    /// Wall clock of the snapshot, microseconds since the Unix epoch
+    pub saved_us: u64,
+    /// Gain profile last applied to the group
+    pub profile: Option<String>,
+    /// In group order
+    pub joints: Vec<JointSnapshot>,
+}
+
+impl GroupSnapshot {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read group snapshot {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Write the snapshot, replacing the previous one only once it is complete
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        // Write to a temporary file first so a crash can't leave half a snapshot
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Cannot write group snapshot {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, path).map_err(|e| anyhow!("Cannot write group snapshot {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    pub fn joint(&self, motor_id: u8) -> Option<&JointSnapshot> {
+        self.joints.iter().find(|j| j.motor_id == motor_id)
+    }
+
+    /// Age of the snapshot by the wall clock
+    pub fn age_us(&self) -> u64 {
+        crate::remote::unix_micros().saturating_sub(self.saved_us)
+    }
+}
@@ -0,0 +1,145 @@
+//! Lock-free latest-state cache
+//!
+//! Holds the most recent [`MotorState`] of every motor ID in a seqlock built
+//! from atomics. Publishing never waits for readers, so a real-time TX thread
+//! updating the cache cannot be blocked by a telemetry or UI thread reading it.
+//! Readers retry until they observe a consistent snapshot and never see a torn
+//! state (e.g. the position of one sample with the torque of another).
+//!
+//! Publishing never waits for other writers either. A writer that finds the
+//! slot in the middle of another writer's update drops its state instead of
+//! spinning, so a real-time thread can't be stalled by a preempted
+//! lower-priority writer (e.g. the background receiver). The other update is
+//! of the same moment, and the next feedback frame refreshes the slot.
+
+use crate::MotorState;
+use std::hint;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A cached state and when it was published
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedState {
+    pub state: MotorState,
+    pub timestamp: Instant,
+}
+
+impl CachedState {
+    pub fn age(&self) -> Duration {
+        self.timestamp.elapsed()
+    }
+}
+
+/// Seqlock protecting one motor's state; the sequence is odd while a write is
+/// in progress and 0 until the first write
+#[derive(Default)]
+struct Slot {
+    seq: AtomicU64,
+    position: AtomicU64,
+    velocity: AtomicU64,
+    torque: AtomicU64,
+    /// NaN when the state carries no acceleration estimate
+    acceleration: AtomicU64,
+    /// Microseconds since the cache epoch
+    stamp_us: AtomicU64,
+}
+
+impl Slot {
+    /// Store a state unless another writer holds the slot; returns whether it was stored
+    fn try_write(&self, state: &MotorState, stamp_us: u64) -> bool {
+        let seq = self.seq.load(Ordering::Relaxed);
+        if seq & 1 == 1
+            || self
+                .seq
+                .compare_exchange(seq, seq + 1, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return false;
+        }
+        fence(Ordering::Release);
+
+        self.position.store(state.position_deg.to_bits(), Ordering::Relaxed);
+        self.velocity.store(state.velocity_rps.to_bits(), Ordering::Relaxed);
+        self.torque.store(state.torque_nm.to_bits(), Ordering::Relaxed);
+        self.acceleration
+            .store(state.acceleration_rps2.unwrap_or(f64::NAN).to_bits(), Ordering::Relaxed);
+        self.stamp_us.store(stamp_us, Ordering::Relaxed);
+
+        self.seq.store(seq + 2, Ordering::Release);
+        true
+    }
+
+    fn read(&self) -> Option<([f64; 4], u64)> {
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before == 0 {
+                return None;
+            }
+            if before & 1 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+
+            let values = (
+                [&self.position, &self.velocity, &self.torque, &self.acceleration]
+                    .map(|value| f64::from_bits(value.load(Ordering::Relaxed))),
+                self.stamp_us.load(Ordering::Relaxed),
+            );
+
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == before {
+                return Some(values);
+            }
+        }
+    }
+}
+
+/// Latest state of every motor ID, readable without locks
+pub struct StateCache {
+    epoch: Instant,
+    slots: Box<[Slot]>,
+}
+
+impl StateCache {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            slots: (0..=u8::MAX).map(|_| Slot::default()).collect(),
+        }
+    }
+
+    /// Store `state` as the latest state of its motor
+    ///
+    /// Returns false, without waiting, if another thread was publishing the
+    /// same motor's state at that moment; `state` is dropped in that case.
+    pub fn publish(&self, state: &MotorState) -> bool {
+        let stamp_us = self.epoch.elapsed().as_micros() as u64;
+        self.slots[state.motor_id as usize].try_write(state, stamp_us)
+    }
+
+    /// Latest state of a motor, `None` if it was never published
+    pub fn get(&self, motor_id: u8) -> Option<CachedState> {
+        let ([position_deg, velocity_rps, torque_nm, acceleration], stamp_us) = self.slots[motor_id as usize].read()?;
+        Some(CachedState {
+            state: MotorState {
+                motor_id,
+                position_deg,
+                velocity_rps,
+                torque_nm,
+                acceleration_rps2: Some(acceleration).filter(|a| !a.is_nan()),
+            },
+            timestamp: self.epoch + Duration::from_micros(stamp_us),
+        })
+    }
+
+    /// Latest states of all motors that were published at least once
+    pub fn snapshot(&self) -> Vec<CachedState> {
+        (0..=u8::MAX).filter_map(|id| self.get(id)).collect()
+    }
+}
+
+impl Default for StateCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
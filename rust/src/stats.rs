@@ -0,0 +1,150 @@
+//! Timing statistics of control loops
+//!
+//! [`DeadlineStats`] counts the cycles of a [`ControlLoop`](crate::ControlLoop)
+//! run with a TX deadline: a cycle that could not send in time is skipped and
+//! counted here, instead of the loop drifting later unnoticed.
+//! [`JitterStats`] measures the period every loop actually achieved and how
+//! late its cycles woke up.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Deadline accounting of a control loop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeadlineStats {
+    /// Cycles whose step ran
+    pub cycles: u64,
+    /// Cycles not run because they started past their deadline
+    pub skipped: u64,
+    /// Cycles whose step was still running at the deadline
+    pub overruns: u64,
+    /// Longest time a step ran past the deadline
+    pub max_overrun: Duration,
+    /// Cycle number of the latest miss (for skipped cycles, of the cycle run next)
+    pub last_miss: Option<u64>,
+}
+
+impl DeadlineStats {
+    /// Cycles that missed their deadline, skipped or overrun
+    pub fn missed(&self) -> u64 {
+        self.skipped + self.overruns
+    }
+
+    /// Fraction of the cycles due so far that missed their deadline
+    pub fn miss_ratio(&self) -> f64 {
+        let due = self.cycles + self.skipped;
+        if due == 0 {
+            return 0.0;
+        }
+        self.missed() as f64 / due as f64
+    }
+
+    pub(crate) fn record_skipped(&mut self, slots: u64, cycle: u64) {
+        if slots > 0 {
+            self.skipped += slots;
+            self.last_miss = Some(cycle);
+        }
+    }
+
+    pub(crate) fn record_cycle(&mut self, cycle: u64, overrun: Duration) {
+        self.cycles += 1;
+        if !overrun.is_zero() {
+            self.overruns += 1;
+            self.max_overrun = self.max_overrun.max(overrun);
+            self.last_miss = Some(cycle);
+        }
+    }
+}
+
+/// Achieved timing of a control loop
+///
+/// The period is measured between the starts of consecutive cycles; cycles
+/// following skipped ones are not counted. Latency is how long after its
+/// scheduled instant a cycle started.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct JitterStats {
+    /// Period the loop was configured with
+    pub nominal_period: Duration,
+    /// Periods measured
+    pub periods: u64,
+    pub min_period: Duration,
+    pub max_period: Duration,
+    /// Longest wake-up latency
+    pub max_latency: Duration,
+    /// Sum of the periods and of their squares, in seconds
+    period_sum: f64,
+    period_square_sum: f64,
+    latency_sum: f64,
+    latencies: u64,
+}
+
+impl JitterStats {
+    pub(crate) fn new(nominal_period: Duration) -> Self {
+        Self {
+            nominal_period,
+            ..Default::default()
+        }
+    }
+
+    pub fn mean_period(&self) -> Option<Duration> {
+        (self.periods > 0).then(|| Duration::from_secs_f64(self.period_sum / self.periods as f64))
+    }
+
+    /// Standard deviation of the period
+    pub fn jitter(&self) -> Option<Duration> {
+        if self.periods == 0 {
+            return None;
+        }
+        let mean = self.period_sum / self.periods as f64;
+        let variance = self.period_square_sum / self.periods as f64 - mean * mean;
+        Some(Duration::from_secs_f64(variance.max(0.0).sqrt()))
+    }
+
+    /// Largest deviation of a period from the nominal one
+    pub fn max_jitter(&self) -> Option<Duration> {
+        let late = self.max_period.saturating_sub(self.nominal_period);
+        let early = self.nominal_period.saturating_sub(self.min_period);
+        (self.periods > 0).then(|| late.max(early))
+    }
+
+    pub fn mean_latency(&self) -> Option<Duration> {
+        (self.latencies > 0).then(|| Duration::from_secs_f64(self.latency_sum / self.latencies as f64))
+    }
+
+    pub(crate) fn record_latency(&mut self, latency: Duration) {
+        self.latencies += 1;
+        self.latency_sum += latency.as_secs_f64();
+        self.max_latency = self.max_latency.max(latency);
+    }
+
+    pub(crate) fn record_period(&mut self, period: Duration) {
+        if self.periods == 0 {
+            self.min_period = period;
+        }
+        self.periods += 1;
+        self.min_period = self.min_period.min(period);
+        self.max_period = self.max_period.max(period);
+        self.period_sum += period.as_secs_f64();
+        self.period_square_sum += period.as_secs_f64() * period.as_secs_f64();
+    }
+}
+
+impl fmt::Display for JitterStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let us = |d: Option<Duration>| d.map_or(0.0, |d| d.as_secs_f64() * 1e6);
+        write!(
+            f,
+            "period {:.1} µs (nominal {:.1}, min {:.1}, max {:.1}), jitter {:.1} µs rms / {:.1} µs max, \
+             wake-up latency {:.1} µs mean / {:.1} µs max over {} cycles",
+            us(self.mean_period()),
+            us(Some(self.nominal_period)),
+            us(Some(self.min_period)),
+            us(Some(self.max_period)),
+            us(self.jitter()),
+            us(self.max_jitter()),
+            us(self.mean_latency()),
+            us(Some(self.max_latency)),
+            self.latencies,
+        )
+    }
+}
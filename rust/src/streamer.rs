@@ -0,0 +1,352 @@
+//! Cyclic multi-joint command streaming
+//!
+//! A [`GroupStreamer`] holds the latest setpoint of every joint and sends them
+//! all once per control cycle, each joint with the command frame declared for it
+//! in the [`StreamerConfig`]: the 0x90 angle stream for arms, the 0xAD velocity
+//! stream for wheels, or MIT-style impedance (angle stream plus Kp/Kd) for legs.
+//!
+//! Joints may run slower than the streamer (e.g. arms at 250 Hz while legs run
+//! at 1 kHz): such a joint is sent every n-th cycle, and the slow joints of a
+//! rate are spread over the n cycles so each cycle carries a similar number of
+//! frames instead of bursting every n-th cycle.
+//!
+//! With a TX deadline ([`GroupStreamer::with_tx_deadline`]) no frame goes out
+//! after the deadline of its cycle: the rest of the cycle is skipped, the
+//! joints hold their previous setpoint, and the new setpoints are sent in the
+//! next cycle that makes its deadline.
+
+use crate::{ControlLoop, CycleInfo, DeadlineStats, LivelyMotorController, Register};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+/// Command frame used for a joint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandFrame {
+    /// 0x90 position stream
+    AngleStream,
+    /// 0xAD velocity + acceleration stream
+    VelocityStream,
+    /// Position stream with per-setpoint Kp/Kd
+    Mit,
+}
+
+/// Per-joint streaming configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JointStreamConfig {
+    pub motor_id: u8,
+    pub frame: CommandFrame,
+    #[serde(default = "default_max_vel")]
+    pub max_vel_rps: f64,
+    #[serde(default = "default_max_torque")]
+    pub max_torque_nm: f64,
+    #[serde(default = "default_acceleration")]
+    pub acceleration_rps2: f64,
+    /// Gains used by `Mit` joints for plain angle setpoints
+    #[serde(default = "default_kp")]
+    pub kp: f32,
+    #[serde(default = "default_kd")]
+    pub kd: f32,
+    /// Send rate, `None` to send every cycle of the streamer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_hz: Option<f64>,
+}
+
+fn default_max_vel() -> f64 {
+    2.0
+}
+
+fn default_max_torque() -> f64 {
+    3.0
+}
+
+fn default_acceleration() -> f64 {
+    5.0
+}
+
+fn default_kp() -> f32 {
+    1.0
+}
+
+fn default_kd() -> f32 {
+    0.1
+}
+
+impl JointStreamConfig {
+    pub fn new(motor_id: u8, frame: CommandFrame) -> Self {
+        Self {
+            motor_id,
+            frame,
+            max_vel_rps: default_max_vel(),
+            max_torque_nm: default_max_torque(),
+            acceleration_rps2: default_acceleration(),
+            kp: default_kp(),
+            kd: default_kd(),
+            rate_hz: None,
+        }
+    }
+
+    /// Send this joint at `rate_hz` instead of every cycle
+    pub fn with_rate(mut self, rate_hz: f64) -> Self {
+        self.rate_hz = Some(rate_hz);
+        self
+    }
+}
+
+/// Frame selection for a group of joints, loaded from JSON (`{"joints": [...]}`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamerConfig {
+    pub joints: Vec<JointStreamConfig>,
+}
+
+impl StreamerConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read streamer config {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+/// Setpoint of one joint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Setpoint {
+    /// Target angle (`AngleStream` and `Mit` joints)
+    Angle(f64),
+    /// Target velocity in r/s (`VelocityStream` joints)
+    Velocity(f64),
+    /// Target angle with stiffness and damping (`Mit` joints)
+    Impedance { position_deg: f64, kp: f32, kd: f32 },
+}
+
+struct JointSlot {
+    config: JointStreamConfig,
+    setpoint: Option<Setpoint>,
+    /// Gains last written to the motor
+    gains: Option<(f32, f32)>,
+    /// Sent on cycles where `cycle % every == phase`
+    every: u64,
+    phase: u64,
+}
+
+/// Streams the latest setpoint of each joint every cycle
+pub struct GroupStreamer<'a> {
+    controller: &'a LivelyMotorController,
+    joints: Vec<JointSlot>,
+    tx_deadline: Option<Duration>,
+    stats: DeadlineStats,
+}
+
+impl<'a> GroupStreamer<'a> {
+    pub fn new(controller: &'a LivelyMotorController, config: &StreamerConfig) -> Self {
+        Self {
+            controller,
+            joints: config
+                .joints
+                .iter()
+                .map(|c| JointSlot {
+                    config: c.clone(),
+                    setpoint: None,
+                    gains: None,
+                    every: 1,
+                    phase: 0,
+                })
+                .collect(),
+            tx_deadline: None,
+            stats: DeadlineStats::default(),
+        }
+    }
+
+    /// Send each cycle's frames within `budget` of its start or not at all, in [`run`](Self::run)
+    pub fn with_tx_deadline(mut self, budget: Duration) -> Self {
+        self.tx_deadline = Some(budget);
+        self
+    }
+
+    /// Deadline accounting of the last [`run`](Self::run)
+    pub fn deadline_stats(&self) -> DeadlineStats {
+        self.stats
+    }
+
+    /// Frame type configured for a joint
+    pub fn frame(&self, motor_id: u8) -> Option<CommandFrame> {
+        self.joints
+            .iter()
+            .find(|j| j.config.motor_id == motor_id)
+            .map(|j| j.config.frame)
+    }
+
+    /// Set the setpoint sent for `motor_id` from the next cycle on
+    pub fn set(&mut self, motor_id: u8, setpoint: Setpoint) -> Result<()> {
+        let slot = self
+            .joints
+            .iter_mut()
+            .find(|j| j.config.motor_id == motor_id)
+            .ok_or(anyhow!("motor {} is not part of the streamer", motor_id))?;
+
+        let compatible = matches!(
+            (slot.config.frame, setpoint),
+            (CommandFrame::AngleStream, Setpoint::Angle(_))
+                | (CommandFrame::VelocityStream, Setpoint::Velocity(_))
+                | (CommandFrame::Mit, Setpoint::Angle(_) | Setpoint::Impedance { .. })
+        );
+        if !compatible {
+            return Err(anyhow!(
+                "motor {}: {:?} setpoint not supported by {:?} frames",
+                motor_id,
+                setpoint,
+                slot.config.frame
+            ));
+        }
+
+        slot.setpoint = Some(setpoint);
+        Ok(())
+    }
+
+    /// Stop sending to a joint until it gets a new setpoint
+    pub fn clear(&mut self, motor_id: u8) {
+        if let Some(slot) = self.joints.iter_mut().find(|j| j.config.motor_id == motor_id) {
+            slot.setpoint = None;
+        }
+    }
+
+    /// Assign each joint its slice of a streamer running at `rate_hz`
+    ///
+    /// A joint is sent every `round(rate_hz / joint rate)` cycles; returns an
+    /// error if a joint asks for more than `rate_hz`.
+    pub fn plan(&mut self, rate_hz: f64) -> Result<()> {
+        let mut assigned: BTreeMap<u64, u64> = BTreeMap::new();
+        for slot in &mut self.joints {
+            let every = match slot.config.rate_hz {
+                None => 1,
+                Some(joint_hz) if joint_hz > 0.0 && joint_hz <= rate_hz * (1.0 + 1e-9) => {
+                    (rate_hz / joint_hz).round().max(1.0) as u64
+                }
+                Some(joint_hz) => {
+                    return Err(anyhow!(
+                        "motor {}: rate {} Hz outside (0, {}] Hz of the streamer",
+                        slot.config.motor_id,
+                        joint_hz,
+                        rate_hz
+                    ))
+                }
+            };
+            // Round-robin over the cycles of the period, per rate
+            let count = assigned.entry(every).or_insert(0);
+            slot.every = every;
+            slot.phase = *count % every;
+            *count += 1;
+        }
+        Ok(())
+    }
+
+    /// Rate a joint is actually sent at by a streamer running at `rate_hz`, after [`plan`](Self::plan)
+    pub fn joint_rate(&self, motor_id: u8, rate_hz: f64) -> Option<f64> {
+        self.joints
+            .iter()
+            .find(|j| j.config.motor_id == motor_id)
+            .map(|j| rate_hz / j.every as f64)
+    }
+
+    /// Send the current setpoint of every joint once
+    pub fn send_cycle(&mut self) -> Result<()> {
+        self.send_where(|_| true, None)
+    }
+
+    /// Send the joints whose slice includes `cycle`
+    pub fn send_slice(&mut self, cycle: u64) -> Result<()> {
+        self.send_where(|slot| cycle % slot.every == slot.phase, None)
+    }
+
+    /// Send the joints due in the cycle of `info`, stopping at its TX deadline
+    fn send_before_deadline(&mut self, info: &CycleInfo) -> Result<()> {
+        self.send_where(|slot| info.cycle % slot.every == slot.phase, Some(info))
+    }
+
+    fn send_where(&mut self, due: impl Fn(&JointSlot) -> bool, deadline: Option<&CycleInfo>) -> Result<()> {
+        let controller = self.controller;
+        for slot in &mut self.joints {
+            if !due(slot) {
+                continue;
+            }
+            if deadline.is_some_and(CycleInfo::missed_deadline) {
+                // A late frame is worse than none: the motor keeps the previous setpoint
+                break;
+            }
+            let Some(setpoint) = slot.setpoint else {
+                continue;
+            };
+            let c = &slot.config;
+
+            match setpoint {
+                Setpoint::Velocity(velocity_rps) => controller.send_velocity_command_to(
+                    c.motor_id,
+                    crate::MAGIC_POS,
+                    crate::rps_to_velocity(velocity_rps),
+                    crate::rps2_to_acceleration(c.acceleration_rps2),
+                )?,
+                Setpoint::Angle(angle_deg) => {
+                    if c.frame == CommandFrame::Mit {
+                        write_gains(controller, c.motor_id, &mut slot.gains, (c.kp, c.kd))?;
+                    }
+                    send_angle(controller, c, angle_deg)?;
+                }
+                Setpoint::Impedance { position_deg, kp, kd } => {
+                    write_gains(controller, c.motor_id, &mut slot.gains, (kp, kd))?;
+                    send_angle(controller, c, position_deg)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run at `rate_hz`: `update` sets new setpoints, then the joints due in the
+    /// cycle are sent (all of them, unless some have a lower `rate_hz`)
+    pub fn run<F>(&mut self, rate_hz: f64, running: &AtomicBool, mut update: F) -> Result<()>
+    where
+        F: FnMut(&CycleInfo, &mut Self) -> Result<bool>,
+    {
+        self.plan(rate_hz)?;
+        let mut control = ControlLoop::new(rate_hz);
+        if let Some(budget) = self.tx_deadline {
+            control = control.with_tx_deadline(budget);
+        }
+        let result = control.run(running, |info| {
+            if !update(info, self)? {
+                return Ok(false);
+            }
+            self.send_before_deadline(info)?;
+            Ok(true)
+        });
+        self.stats = control.stats();
+        result
+    }
+}
+
+fn send_angle(controller: &LivelyMotorController, config: &JointStreamConfig, angle_deg: f64) -> Result<()> {
+    Ok(controller.send_angle_command_to(
+        config.motor_id,
+        crate::degrees_to_position(angle_deg),
+        crate::rps_to_velocity(config.max_vel_rps),
+        crate::nm_to_torque(config.max_torque_nm),
+    )?)
+}
+
+/// Write Kp/Kd only when they differ from what the motor already has
+fn write_gains(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    current: &mut Option<(f32, f32)>,
+    gains: (f32, f32),
+) -> Result<()> {
+    if *current != Some(gains) {
+        controller.write_register_float(motor_id, Register::Kp, gains.0)?;
+        controller.write_register_float(motor_id, Register::Kd, gains.1)?;
+        *current = Some(gains);
+    }
+    Ok(())
+}
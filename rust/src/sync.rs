@@ -0,0 +1,118 @@
+//! External sync trigger and position latching
+//!
+//! Waits for a sync pulse (an actuator GPIO edge or a designated CAN frame) and
+//! latches the positions of a set of motors at the trigger instant, so joint data
+//! can be aligned with motion-capture recordings.
+
+use crate::LivelyMotorController;
+use anyhow::Result;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Where the sync pulse comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncSource {
+    /// Rising edge on an auxiliary input of a motor
+    Gpio { motor_id: u8, pin: u8 },
+    /// Any frame with this arbitration ID (standard or extended)
+    CanFrame { id: u32 },
+}
+
+/// Position of one motor captured after a trigger
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatchedPosition {
+    pub motor_id: u8,
+    pub position_deg: f64,
+    /// Time between the trigger and the motor's reply
+    pub delay: Duration,
+}
+
+/// All positions latched for one trigger
+#[derive(Debug, Clone)]
+pub struct LatchedSample {
+    /// Trigger counter, starting at 0
+    pub index: u64,
+    /// Monotonic trigger instant
+    pub timestamp: Instant,
+    /// Wall-clock trigger time, for matching against external recordings
+    pub system_time: SystemTime,
+    /// Motors that answered; missing motors are omitted
+    pub positions: Vec<LatchedPosition>,
+}
+
+/// Latches motor positions on external sync pulses
+pub struct SyncLatch<'a> {
+    controller: &'a LivelyMotorController,
+    source: SyncSource,
+    motor_ids: Vec<u8>,
+    last_level: bool,
+    count: u64,
+}
+
+impl<'a> SyncLatch<'a> {
+    pub fn new(controller: &'a LivelyMotorController, source: SyncSource, motor_ids: &[u8]) -> Self {
+        Self {
+            controller,
+            source,
+            motor_ids: motor_ids.to_vec(),
+            last_level: false,
+            count: 0,
+        }
+    }
+
+    /// Number of triggers latched so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Block until the next trigger (or `timeout`), then latch all positions
+    pub fn wait_for_trigger(&mut self, timeout: Duration) -> Result<Option<LatchedSample>> {
+        let start = Instant::now();
+
+        while start.elapsed() < timeout {
+            let triggered = match self.source {
+                SyncSource::Gpio { motor_id, pin } => {
+                    let level = self.controller.read_gpio(motor_id)?.is_high(pin);
+                    let rising = level && !self.last_level;
+                    self.last_level = level;
+                    rising
+                }
+                SyncSource::CanFrame { id } => match self.controller.read_frame_with_timeout(1)? {
+                    Some(frame) => crate::raw_id(&frame) == id,
+                    None => false,
+                },
+            };
+
+            if triggered {
+                return self.latch().map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Latch the current positions immediately (software trigger)
+    pub fn latch(&mut self) -> Result<LatchedSample> {
+        let timestamp = Instant::now();
+        let system_time = SystemTime::now();
+
+        let mut positions = Vec::with_capacity(self.motor_ids.len());
+        for &motor_id in &self.motor_ids {
+            if let Ok(state) = self.controller.read_motor_state(motor_id) {
+                positions.push(LatchedPosition {
+                    motor_id,
+                    position_deg: state.position_deg,
+                    delay: timestamp.elapsed(),
+                });
+            }
+        }
+
+        let sample = LatchedSample {
+            index: self.count,
+            timestamp,
+            system_time,
+            positions,
+        };
+        self.count += 1;
+        Ok(sample)
+    }
+}
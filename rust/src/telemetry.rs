@@ -0,0 +1,89 @@
+//! Telemetry snapshots
+//!
+//! Aggregates the values read back from a motor at one instant.
+
+use std::time::Instant;
+
+/// Auxiliary digital input states reported by the actuator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GpioState {
+    pub bits: u8,
+}
+
+impl GpioState {
+    /// Whether input `pin` (0..8) is high
+    pub fn is_high(&self, pin: u8) -> bool {
+        pin < 8 && self.bits & (1 << pin) != 0
+    }
+
+    /// Whether any input is high (e.g. any limit switch triggered)
+    pub fn any_high(&self) -> bool {
+        self.bits != 0
+    }
+}
+
+/// Measured motor state in engineering units
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MotorState {
+    pub motor_id: u8,
+    /// Position in degrees
+    pub position_deg: f64,
+    /// Velocity in r/s
+    pub velocity_rps: f64,
+    /// Torque in Nm
+    pub torque_nm: f64,
+    /// Acceleration in r/s² estimated from recent velocity feedback, `None`
+    /// until enough readings were taken (see [`crate::feedback`])
+    pub acceleration_rps2: Option<f64>,
+}
+
+/// Battery pack state reported by a BMS on the bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryState {
+    pub timestamp: Instant,
+    /// Pack voltage in V
+    pub voltage_v: f64,
+    /// Pack current in A (positive = discharge)
+    pub current_a: f64,
+    /// State of charge in %, if the BMS reports it
+    pub soc_pct: Option<f64>,
+}
+
+/// One telemetry sample of a motor
+#[derive(Debug, Clone)]
+pub struct MotorTelemetry {
+    pub motor_id: u8,
+    pub timestamp: Instant,
+    /// Measured state, `None` if the motor did not answer the state query
+    pub state: Option<MotorState>,
+    /// Input states, `None` if the motor did not answer the GPIO query
+    pub gpio: Option<GpioState>,
+}
+
+/// End-effector force estimated from joint torques
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EndEffectorForce {
+    pub fx_n: f64,
+    pub fy_n: f64,
+    /// Moment about the z axis, only for chains with a wrist
+    pub mz_nm: Option<f64>,
+}
+
+impl EndEffectorForce {
+    /// Magnitude of the planar force
+    pub fn magnitude(&self) -> f64 {
+        self.fx_n.hypot(self.fy_n)
+    }
+}
+
+/// One telemetry sample of a kinematic chain
+#[derive(Debug, Clone)]
+pub struct ChainTelemetry {
+    pub timestamp: Instant,
+    /// Joint states in chain order
+    pub joints: Vec<MotorState>,
+    /// `None` when the arm is singular
+    pub force: Option<EndEffectorForce>,
+    /// Force magnitude exceeded the contact threshold
+    pub contact: bool,
+}
@@ -0,0 +1,510 @@
+//! Bus-level telemetry logging to CSV or MCAP, or mirroring over UDP
+//!
+//! Unlike [`Recorder`](crate::Recorder), which stores what the control loop
+//! hands it, a [`TelemetryRecorder`] taps the bus itself: every state reply of
+//! the recorded motors (position, velocity, torque, temperature, decoded with
+//! each motor's [`ReportMask`]) and every stream command this process sends to
+//! them is logged with its time as it happens, by a background thread writing
+//! straight to the file.
+//!
+//! CSV files have a `time_s` column followed by `m<id>.<quantity>` columns;
+//! each row holds the latest value of every column, so commands and states
+//! line up row by row in PlotJuggler. MCAP files carry JSON messages on
+//! `/motor/<id>/state` and `/motor/<id>/command` for Foxglove. A UDP mirror
+//! ([`TelemetryRecorder::mirror_udp`]) sends the same messages as datagrams,
+//! so another machine can record or plot them while the robot keeps no log.
+//!
+//! States are timestamped when the recorder takes them off its queue; start
+//! the bus receiver ([`CanBus::start_receiver`](crate::CanBus::start_receiver))
+//! so that happens as they arrive.
+
+use crate::bus::BusSubscription;
+use crate::mcap::McapWriter;
+use crate::protocol::{self, EncodingPolicy, RegisterReply, ValueType};
+use crate::report::{Report, ReportMask};
+use crate::{LivelyMotorController, MitCommand, MitRanges, Register};
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use socketcan::{CanFrame, EmbeddedFrame};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Longest wait for a received frame before sent ones are checked again
+const POLL_SLICE: Duration = Duration::from_millis(2);
+
+/// Columns per motor in CSV files
+const CSV_COLUMNS: [&str; 7] = [
+    "position_deg",
+    "velocity_rps",
+    "torque_nm",
+    "temperature_c",
+    "cmd_position_deg",
+    "cmd_velocity_rps",
+    "cmd_torque_nm",
+];
+
+const STATE_SCHEMA: &str = r#"{"type":"object","properties":{"motor_id":{"type":"integer"},
+"position_deg":{"type":["number","null"]},"velocity_rps":{"type":["number","null"]},
+"torque_nm":{"type":["number","null"]},"temperature_c":{"type":["number","null"]}}}"#;
+
+const COMMAND_SCHEMA: &str = r#"{"type":"object","properties":{"motor_id":{"type":"integer"},
+"frame":{"type":"string"},"position_deg":{"type":["number","null"]},"velocity_rps":{"type":["number","null"]},
+"torque_nm":{"type":["number","null"]},"acceleration_rps2":{"type":["number","null"]},
+"kp":{"type":["number","null"]},"kd":{"type":["number","null"]}}}"#;
+
+/// File format of a telemetry log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    Csv,
+    Mcap,
+}
+
+impl TelemetryFormat {
+    /// Format named by the file extension (`.csv` or `.mcap`)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("csv") => Ok(TelemetryFormat::Csv),
+            Some("mcap") => Ok(TelemetryFormat::Mcap),
+            _ => Err(anyhow!("cannot tell telemetry format of {}, use .csv or .mcap", path.display())),
+        }
+    }
+}
+
+/// A stream command seen on its way out
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SentCommand {
+    /// Addressed motor, 0 for every motor
+    pub motor_id: u8,
+    /// `angle_stream`, `velocity_stream` or `mit`
+    pub frame: &'static str,
+    pub position_deg: Option<f64>,
+    /// Target velocity; the speed cap of angle stream commands
+    pub velocity_rps: Option<f64>,
+    /// Feed-forward torque of MIT commands; the torque cap of angle stream commands
+    pub torque_nm: Option<f64>,
+    pub acceleration_rps2: Option<f64>,
+    pub kp: Option<f64>,
+    pub kd: Option<f64>,
+}
+
+impl SentCommand {
+    /// Decode a stream command frame, `None` for any other frame
+    pub fn decode(id: u32, data: &[u8], mit_ranges: &MitRanges) -> Option<SentCommand> {
+        let (stream, motor_id) = protocol::stream_target(id)?;
+        let value = |i: usize| data.get(2 * i..2 * i + 2).map(|b| i16::from_le_bytes([b[0], b[1]]));
+        let command = SentCommand {
+            motor_id,
+            frame: "",
+            position_deg: None,
+            velocity_rps: None,
+            torque_nm: None,
+            acceleration_rps2: None,
+            kp: None,
+            kd: None,
+        };
+        match stream {
+            protocol::ANGLE_STREAM_ID => Some(SentCommand {
+                frame: "angle_stream",
+                position_deg: Some(crate::position_to_degrees(value(0)?)),
+                velocity_rps: Some(crate::velocity_to_rps(value(1)?)),
+                torque_nm: Some(crate::torque_to_nm(value(2)?)),
+                ..command
+            }),
+            protocol::VELOCITY_STREAM_ID => {
+                let position = value(0)?;
+                Some(SentCommand {
+                    frame: "velocity_stream",
+                    position_deg: (position != crate::MAGIC_POS).then(|| crate::position_to_degrees(position)),
+                    velocity_rps: Some(crate::velocity_to_rps(value(1)?)),
+                    acceleration_rps2: Some(crate::acceleration_to_rps2(value(2)?)),
+                    ..command
+                })
+            }
+            _ => {
+                let mit = MitCommand::decode(data, mit_ranges).ok()?;
+                Some(SentCommand {
+                    frame: "mit",
+                    position_deg: Some(mit.position_deg),
+                    velocity_rps: Some(mit.velocity_rps),
+                    torque_nm: Some(mit.torque_nm),
+                    kp: Some(mit.kp),
+                    kd: Some(mit.kd),
+                    ..command
+                })
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    states: AtomicU64,
+    commands: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Sorted, deduplicated motor IDs; at least one
+fn recorded_ids(motor_ids: &[u8]) -> Result<Vec<u8>> {
+    if motor_ids.is_empty() {
+        return Err(anyhow!("no motors to record"));
+    }
+    let mut motor_ids = motor_ids.to_vec();
+    motor_ids.sort_unstable();
+    motor_ids.dedup();
+    Ok(motor_ids)
+}
+
+/// Logs the bus traffic of a set of motors to a file on a background thread
+pub struct TelemetryRecorder {
+    running: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+    error: Arc<Mutex<Option<String>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl TelemetryRecorder {
+    /// Log `motor_ids` to `path`, in the format of its extension
+    pub fn start<P: AsRef<Path>>(controller: &LivelyMotorController, path: P, motor_ids: &[u8]) -> Result<Self> {
+        let format = TelemetryFormat::from_path(&path)?;
+        Self::start_with_format(controller, path, format, motor_ids)
+    }
+
+    pub fn start_with_format<P: AsRef<Path>>(
+        controller: &LivelyMotorController,
+        path: P,
+        format: TelemetryFormat,
+        motor_ids: &[u8],
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let motor_ids = recorded_ids(motor_ids)?;
+        let file = File::create(path).map_err(|e| anyhow!("Cannot create {}: {}", path.display(), e))?;
+        let sink = match format {
+            TelemetryFormat::Csv => Sink::csv(BufWriter::new(file), &motor_ids)?,
+            TelemetryFormat::Mcap => Sink::mcap(BufWriter::new(file), &motor_ids)?,
+        };
+        Ok(Self::spawn(controller, sink, motor_ids, Arc::default()))
+    }
+
+    /// Forward the traffic of `motor_ids` as JSON datagrams to `target`,
+    /// e.g. a multicast group, instead of writing a file
+    ///
+    /// Each datagram holds one message: `{"seq", "timestamp", "topic",
+    /// "message"}`, with the topics and messages of MCAP files and the Unix
+    /// time in seconds. Datagrams that cannot be sent are dropped and
+    /// counted ([`datagrams_dropped`](Self::datagrams_dropped)); multicast
+    /// goes out with a TTL of 1, to the local network only.
+    pub fn mirror_udp<A: ToSocketAddrs>(
+        controller: &LivelyMotorController,
+        target: A,
+        motor_ids: &[u8],
+    ) -> Result<Self> {
+        let motor_ids = recorded_ids(motor_ids)?;
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("mirror address resolves to nothing"))?;
+        let local: SocketAddr =
+            if target.is_ipv4() { (Ipv4Addr::UNSPECIFIED, 0).into() } else { (Ipv6Addr::UNSPECIFIED, 0).into() };
+        let socket = UdpSocket::bind(local)?;
+        let counters = Arc::new(Counters::default());
+        let sink = Sink::Udp {
+            socket,
+            target,
+            sequence: 0,
+            counters: Arc::clone(&counters),
+        };
+        Ok(Self::spawn(controller, sink, motor_ids, counters))
+    }
+
+    fn spawn(controller: &LivelyMotorController, sink: Sink, motor_ids: Vec<u8>, counters: Arc<Counters>) -> Self {
+        let tap = Tap {
+            received: controller.bus().subscribe(),
+            sent: controller.bus().subscribe_sent(),
+            encoding: controller.encoding().clone(),
+            mit_ranges: *controller.mit_ranges(),
+            report_masks: controller.report_masks(),
+            motor_ids,
+        };
+        let running = Arc::new(AtomicBool::new(true));
+        let error = Arc::new(Mutex::new(None));
+        let worker = {
+            let (running, counters, error) = (Arc::clone(&running), Arc::clone(&counters), Arc::clone(&error));
+            thread::spawn(move || {
+                if let Err(e) = tap.run(sink, &running, &counters) {
+                    *error.lock().unwrap() = Some(format!("{:#}", e));
+                }
+            })
+        };
+        Self {
+            running,
+            counters,
+            error,
+            worker: Some(worker),
+        }
+    }
+
+    /// State replies logged so far
+    pub fn states_logged(&self) -> u64 {
+        self.counters.states.load(Ordering::Relaxed)
+    }
+
+    /// Commands logged so far
+    pub fn commands_logged(&self) -> u64 {
+        self.counters.commands.load(Ordering::Relaxed)
+    }
+
+    /// Datagrams a UDP mirror could not send
+    pub fn datagrams_dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Why logging stopped early, if it did
+    pub fn last_error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Stop logging and complete the file
+    pub fn stop(&mut self) -> Result<()> {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        match self.last_error() {
+            Some(e) => Err(anyhow!("telemetry recording failed: {}", e)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for TelemetryRecorder {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// What the recorder thread reads
+struct Tap {
+    received: BusSubscription,
+    sent: BusSubscription,
+    encoding: EncodingPolicy,
+    mit_ranges: MitRanges,
+    report_masks: Arc<Mutex<BTreeMap<u8, ReportMask>>>,
+    motor_ids: Vec<u8>,
+}
+
+impl Tap {
+    fn run(&self, mut sink: Sink, running: &AtomicBool, counters: &Counters) -> Result<()> {
+        let start = Instant::now();
+        while running.load(Ordering::SeqCst) {
+            while let Some(frame) = self.sent.try_recv() {
+                for command in self.commands(&frame) {
+                    sink.command(start.elapsed(), &command)?;
+                    counters.commands.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            let Some(frame) = self.received.recv_timeout(POLL_SLICE)? else {
+                continue;
+            };
+            if let Some(report) = self.state(&frame) {
+                sink.state(start.elapsed(), &report)?;
+                counters.states.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        sink.finish()
+    }
+
+    /// A command to every motor is logged for each recorded one
+    fn commands(&self, frame: &CanFrame) -> Vec<SentCommand> {
+        let Some(command) = SentCommand::decode(crate::raw_id(frame), frame.data(), &self.mit_ranges) else {
+            return Vec::new();
+        };
+        if command.motor_id == 0 {
+            self.motor_ids.iter().map(|&motor_id| SentCommand { motor_id, ..command }).collect()
+        } else if self.motor_ids.contains(&command.motor_id) {
+            vec![command]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn state(&self, frame: &CanFrame) -> Option<Report> {
+        let motor_id = crate::reply_source(frame).filter(|id| self.motor_ids.contains(id))?;
+        let reply = self.encoding.parse_reply(frame.data()).ok()?;
+        decode_state(motor_id, &reply, self.report_masks.lock().unwrap().get(&motor_id).copied())
+    }
+}
+
+/// A pushed report in the motor's content, a `read_motor_state` reply, or a
+/// temperature register read
+fn decode_state(motor_id: u8, reply: &RegisterReply, mask: Option<ReportMask>) -> Option<Report> {
+    for mask in [mask.unwrap_or_default(), ReportMask::STATE] {
+        if mask.matches(reply) {
+            return mask.decode(motor_id, reply).ok();
+        }
+    }
+    let is_temperature = reply.register == Register::Temperature.addr() && reply.value_type == ValueType::Float;
+    is_temperature.then(|| Report {
+        motor_id,
+        temperature_c: reply.float(0).map(f64::from),
+        ..Default::default()
+    })
+}
+
+enum Sink {
+    Csv {
+        out: BufWriter<File>,
+        motor_ids: Vec<u8>,
+        /// Latest value of every column after `time_s`
+        row: Vec<Option<f64>>,
+    },
+    Mcap {
+        writer: McapWriter<BufWriter<File>>,
+        /// `(state, command)` channel per motor
+        channels: BTreeMap<u8, (u16, u16)>,
+    },
+    Udp {
+        socket: UdpSocket,
+        target: SocketAddr,
+        sequence: u64,
+        counters: Arc<Counters>,
+    },
+}
+
+impl Sink {
+    fn csv(mut out: BufWriter<File>, motor_ids: &[u8]) -> Result<Self> {
+        let header: Vec<String> = motor_ids
+            .iter()
+            .flat_map(|id| CSV_COLUMNS.iter().map(move |column| format!("m{}.{}", id, column)))
+            .collect();
+        writeln!(out, "time_s,{}", header.join(","))?;
+        Ok(Sink::Csv {
+            out,
+            motor_ids: motor_ids.to_vec(),
+            row: vec![None; header.len()],
+        })
+    }
+
+    fn mcap(out: BufWriter<File>, motor_ids: &[u8]) -> Result<Self> {
+        let mut writer = McapWriter::new(out, "")?;
+        let state_schema = writer.add_schema("livelybot.MotorState", "jsonschema", STATE_SCHEMA.as_bytes())?;
+        let command_schema = writer.add_schema("livelybot.MotorCommand", "jsonschema", COMMAND_SCHEMA.as_bytes())?;
+        let mut channels = BTreeMap::new();
+        for &id in motor_ids {
+            let state = writer.add_channel(state_schema, &format!("/motor/{}/state", id), "json")?;
+            let command = writer.add_channel(command_schema, &format!("/motor/{}/command", id), "json")?;
+            channels.insert(id, (state, command));
+        }
+        Ok(Sink::Mcap { writer, channels })
+    }
+
+    fn state(&mut self, time: Duration, report: &Report) -> Result<()> {
+        match self {
+            Sink::Csv { out, motor_ids, row } => {
+                let values = [report.position_deg, report.velocity_rps, report.torque_nm, report.temperature_c];
+                update_row(row, motor_ids, report.motor_id, 0, &values);
+                write_row(out, time, row)
+            }
+            Sink::Mcap { writer, channels } => {
+                let (channel, _) = channels[&report.motor_id];
+                writer.write_message(channel, unix_nanos(), state_message(report).to_string().as_bytes())
+            }
+            Sink::Udp { .. } => {
+                self.send_datagram(&format!("/motor/{}/state", report.motor_id), state_message(report));
+                Ok(())
+            }
+        }
+    }
+
+    fn command(&mut self, time: Duration, command: &SentCommand) -> Result<()> {
+        match self {
+            Sink::Csv { out, motor_ids, row } => {
+                let values = [command.position_deg, command.velocity_rps, command.torque_nm];
+                update_row(row, motor_ids, command.motor_id, 4, &values);
+                write_row(out, time, row)
+            }
+            Sink::Mcap { writer, channels } => {
+                let (_, channel) = channels[&command.motor_id];
+                writer.write_message(channel, unix_nanos(), &serde_json::to_vec(command)?)
+            }
+            Sink::Udp { .. } => {
+                self.send_datagram(&format!("/motor/{}/command", command.motor_id), serde_json::to_value(command)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Best effort: a mirror must not stop over a busy or missing network
+    fn send_datagram(&mut self, topic: &str, message: serde_json::Value) {
+        let Sink::Udp { socket, target, sequence, counters } = self else {
+            return;
+        };
+        let datagram = serde_json::json!({
+            "seq": *sequence,
+            "timestamp": unix_nanos() as f64 / 1e9,
+            "topic": topic,
+            "message": message,
+        });
+        *sequence += 1;
+        if socket.send_to(datagram.to_string().as_bytes(), *target).is_err() {
+            counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            Sink::Csv { mut out, .. } => Ok(out.flush()?),
+            Sink::Mcap { writer, .. } => writer.finish().map(drop),
+            Sink::Udp { .. } => Ok(()),
+        }
+    }
+}
+
+fn state_message(report: &Report) -> serde_json::Value {
+    serde_json::json!({
+        "motor_id": report.motor_id,
+        "position_deg": report.position_deg,
+        "velocity_rps": report.velocity_rps,
+        "torque_nm": report.torque_nm,
+        "temperature_c": report.temperature_c,
+    })
+}
+
+/// Overwrite the known values of `motor_id`'s columns from `offset` on
+fn update_row(row: &mut [Option<f64>], motor_ids: &[u8], motor_id: u8, offset: usize, values: &[Option<f64>]) {
+    let Some(index) = motor_ids.iter().position(|&id| id == motor_id) else {
+        return;
+    };
+    let base = index * CSV_COLUMNS.len() + offset;
+    for (cell, value) in row[base..base + values.len()].iter_mut().zip(values) {
+        if value.is_some() {
+            *cell = *value;
+        }
+    }
+}
+
+fn write_row(out: &mut BufWriter<File>, time: Duration, row: &[Option<f64>]) -> Result<()> {
+    write!(out, "{:.6}", time.as_secs_f64())?;
+    for value in row {
+        match value {
+            Some(value) => write!(out, ",{}", value)?,
+            None => write!(out, ",")?,
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,331 @@
+//! Joint trajectories and JointTrajectory import
+//!
+//! Converts `trajectory_msgs/JointTrajectory` exports (e.g. planned by MoveIt)
+//! into [`Trajectory`] waypoints in motor order and motor degrees, using a
+//! [`JointMapping`] from joint names to motor IDs. Two export formats are read:
+//!
+//! - JSON, as printed by `ros2 topic echo --json` or a bag-to-JSON dump: an object
+//!   with `joint_names` and `points[].positions` / `points[].time_from_start`
+//!   (`sec`/`nanosec` or ROS 1 `secs`/`nsecs`)
+//! - CSV with one row per point: `time_from_start` in seconds, then one position
+//!   column per joint named `<joint>` or `<joint>.position`
+//!
+//! Positions in the export are radians.
+//!
+//! Waypoints and sampling are generic over the [`Float`] type; `Trajectory`
+//! without a parameter is in [`Real`], `f64` unless the `f32` feature is on.
+
+use crate::{ControlLoop, Float, LivelyMotorController, MotionHandle, Real};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Mapping of one trajectory joint to a motor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JointMap {
+    pub name: String,
+    pub motor_id: u8,
+    /// Motor turns opposite to the joint's positive direction
+    #[serde(default)]
+    pub reversed: bool,
+    /// Motor angle at joint zero
+    #[serde(default)]
+    pub offset_deg: f64,
+}
+
+impl JointMap {
+    /// Motor angle for a joint angle in radians
+    pub fn to_motor_deg(&self, joint_rad: f64) -> f64 {
+        let sign = if self.reversed { -1.0 } else { 1.0 };
+        sign * joint_rad.to_degrees() + self.offset_deg
+    }
+
+    /// Joint angle in radians for a motor angle
+    pub fn to_joint_rad(&self, motor_deg: f64) -> f64 {
+        let sign = if self.reversed { -1.0 } else { 1.0 };
+        (sign * (motor_deg - self.offset_deg)).to_radians()
+    }
+}
+
+/// Joint name → motor mapping, loaded from JSON (`{"joints": [...]}`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JointMapping {
+    pub joints: Vec<JointMap>,
+}
+
+impl JointMapping {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read joint mapping {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Motor IDs in mapping order
+    pub fn motor_ids(&self) -> Vec<u8> {
+        self.joints.iter().map(|j| j.motor_id).collect()
+    }
+}
+
+/// One trajectory point, positions in motor order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waypoint<T = Real> {
+    pub time: Duration,
+    pub positions_deg: Vec<T>,
+}
+
+/// Timed waypoints for a set of motors
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory<T = Real> {
+    pub motor_ids: Vec<u8>,
+    pub waypoints: Vec<Waypoint<T>>,
+}
+
+impl<T: Float> Trajectory<T> {
+    /// Import a JSON (`.json`) or CSV (any other extension) export
+    pub fn import<P: AsRef<Path>>(path: P, mapping: &JointMapping) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read trajectory {}: {}", path.display(), e))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&text, mapping),
+            _ => Self::from_csv(&text, mapping),
+        }
+    }
+
+    /// Parse a JSON JointTrajectory message
+    pub fn from_json(text: &str, mapping: &JointMapping) -> Result<Self> {
+        let msg: Value = serde_json::from_str(text)?;
+        let names: Vec<String> = msg["joint_names"]
+            .as_array()
+            .ok_or(anyhow!("JointTrajectory has no joint_names"))?
+            .iter()
+            .map(|n| n.as_str().unwrap_or_default().to_string())
+            .collect();
+        let columns = map_columns(&names, mapping)?;
+
+        let points = msg["points"].as_array().ok_or(anyhow!("JointTrajectory has no points"))?;
+        let mut rows = Vec::with_capacity(points.len());
+        for (i, point) in points.iter().enumerate() {
+            let positions: Vec<f64> = point["positions"]
+                .as_array()
+                .ok_or(anyhow!("point {} has no positions", i))?
+                .iter()
+                .map(|v| v.as_f64().ok_or(anyhow!("point {}: non-numeric position", i)))
+                .collect::<Result<_>>()?;
+            rows.push((json_duration(&point["time_from_start"]), positions));
+        }
+
+        Self::from_rows(mapping, &columns, rows)
+    }
+
+    /// Parse a per-point CSV export
+    pub fn from_csv(text: &str, mapping: &JointMapping) -> Result<Self> {
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or(anyhow!("empty trajectory CSV"))?
+            .split(',')
+            .map(str::trim)
+            .collect();
+        if header.first() != Some(&"time_from_start") {
+            return Err(anyhow!("first CSV column must be time_from_start"));
+        }
+
+        let names: Vec<String> = header[1..]
+            .iter()
+            .map(|h| h.strip_suffix(".position").unwrap_or(h).to_string())
+            .collect();
+        let columns = map_columns(&names, mapping)?;
+
+        let mut rows = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let values: Vec<f64> = line
+                .split(',')
+                .map(|v| v.trim().parse::<f64>().map_err(|e| anyhow!("CSV row {}: {}", i + 1, e)))
+                .collect::<Result<_>>()?;
+            let (time, positions) = values.split_first().ok_or(anyhow!("CSV row {} is empty", i + 1))?;
+            rows.push((Duration::from_secs_f64(time.max(0.0)), positions.to_vec()));
+        }
+
+        Self::from_rows(mapping, &columns, rows)
+    }
+
+    fn from_rows(mapping: &JointMapping, columns: &[usize], rows: Vec<(Duration, Vec<f64>)>) -> Result<Self> {
+        let mut waypoints = Vec::with_capacity(rows.len());
+        for (i, (time, positions)) in rows.into_iter().enumerate() {
+            let positions_deg = mapping
+                .joints
+                .iter()
+                .zip(columns)
+                .map(|(joint, &col)| {
+                    positions
+                        .get(col)
+                        .map(|&rad| T::from_f64(joint.to_motor_deg(rad)))
+                        .ok_or(anyhow!("point {} is missing joint {}", i, joint.name))
+                })
+                .collect::<Result<_>>()?;
+            waypoints.push(Waypoint { time, positions_deg });
+        }
+
+        if waypoints.windows(2).any(|w| w[1].time < w[0].time) {
+            return Err(anyhow!("trajectory times are not increasing"));
+        }
+
+        Ok(Self {
+            motor_ids: mapping.motor_ids(),
+            waypoints,
+        })
+    }
+
+    /// Time of the last waypoint
+    pub fn duration(&self) -> Duration {
+        self.waypoints.last().map(|w| w.time).unwrap_or_default()
+    }
+
+    /// Positions at `t`, linearly interpolated and held at the ends
+    pub fn sample(&self, t: Duration) -> Option<Vec<T>> {
+        let first = self.waypoints.first()?;
+        if t <= first.time {
+            return Some(first.positions_deg.clone());
+        }
+
+        for pair in self.waypoints.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if t <= b.time {
+                let span = T::from_f64((b.time - a.time).as_secs_f64());
+                let s = if span > T::ZERO { T::from_f64((t - a.time).as_secs_f64()) / span } else { T::ONE };
+                return Some(
+                    a.positions_deg
+                        .iter()
+                        .zip(&b.positions_deg)
+                        .map(|(&pa, &pb)| pa + (pb - pa) * s)
+                        .collect(),
+                );
+            }
+        }
+
+        self.waypoints.last().map(|w| w.positions_deg.clone())
+    }
+
+    /// Stream the trajectory at `rate_hz` until it ends or `running` is cleared
+    pub fn play(
+        &self,
+        controller: &LivelyMotorController,
+        rate_hz: f64,
+        max_vel_rps: f64,
+        max_tqe_nm: f64,
+        running: &AtomicBool,
+    ) -> Result<()> {
+        self.play_from(Instant::now(), controller, rate_hz, max_vel_rps, max_tqe_nm, running)
+    }
+
+    /// Like [`play`](Self::play), with time 0 of the trajectory at `start`
+    ///
+    /// Setpoints follow the monotonic clock from `start`, so playback started
+    /// late or slowed by overruns catches up instead of lagging behind music
+    /// or other robots sharing the same start.
+    pub fn play_from(
+        &self,
+        start: Instant,
+        controller: &LivelyMotorController,
+        rate_hz: f64,
+        max_vel_rps: f64,
+        max_tqe_nm: f64,
+        running: &AtomicBool,
+    ) -> Result<()> {
+        let never_paused = AtomicBool::new(false);
+        self.play_pausable(start, controller, rate_hz, max_vel_rps, max_tqe_nm, running, &never_paused)
+    }
+
+    /// Play on a new thread; the returned handle pauses, resumes or cancels it
+    ///
+    /// While paused, the trajectory's clock stops and the last setpoints are
+    /// held.
+    pub fn start(
+        &self,
+        controller: Arc<LivelyMotorController>,
+        rate_hz: f64,
+        max_vel_rps: f64,
+        max_tqe_nm: f64,
+    ) -> MotionHandle {
+        let trajectory = self.clone();
+        MotionHandle::spawn(move |signals| {
+            trajectory.play_pausable(
+                Instant::now(),
+                &controller,
+                rate_hz,
+                max_vel_rps,
+                max_tqe_nm,
+                signals.running(),
+                signals.paused(),
+            )
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn play_pausable(
+        &self,
+        start: Instant,
+        controller: &LivelyMotorController,
+        rate_hz: f64,
+        max_vel_rps: f64,
+        max_tqe_nm: f64,
+        running: &AtomicBool,
+        paused: &AtomicBool,
+    ) -> Result<()> {
+        let duration = self.duration();
+        // Time spent paused before the current pause, and the start of that pause
+        let mut paused_for = Duration::ZERO;
+        let mut paused_at = None;
+        ControlLoop::new(rate_hz).with_start(start).run(running, |info| {
+            if paused.load(Ordering::SeqCst) {
+                paused_at.get_or_insert(info.elapsed);
+            } else if let Some(at) = paused_at.take() {
+                paused_for += info.elapsed - at;
+            }
+            let t = paused_at.unwrap_or(info.elapsed) - paused_for;
+
+            let Some(positions) = self.sample(t) else {
+                return Ok(false);
+            };
+            for (&motor_id, angle_deg) in self.motor_ids.iter().zip(positions) {
+                controller.send_angle_command_to(
+                    motor_id,
+                    crate::conversions::degrees_to_position(angle_deg),
+                    crate::rps_to_velocity(max_vel_rps),
+                    crate::nm_to_torque(max_tqe_nm),
+                )?;
+            }
+            Ok(t < duration)
+        })
+    }
+}
+
+/// Column index in the export of each mapped joint
+fn map_columns(names: &[String], mapping: &JointMapping) -> Result<Vec<usize>> {
+    mapping
+        .joints
+        .iter()
+        .map(|joint| {
+            names
+                .iter()
+                .position(|n| *n == joint.name)
+                .ok_or(anyhow!("joint {} not found in trajectory", joint.name))
+        })
+        .collect()
+}
+
+/// `time_from_start` as `{sec, nanosec}`, `{secs, nsecs}` or plain seconds
+fn json_duration(value: &Value) -> Duration {
+    if let Some(secs) = value.as_f64() {
+        return Duration::from_secs_f64(secs.max(0.0));
+    }
+    let secs = value["sec"].as_u64().or(value["secs"].as_u64()).unwrap_or(0);
+    let nanos = value["nanosec"].as_u64().or(value["nsecs"].as_u64()).unwrap_or(0);
+    Duration::from_secs(secs) + Duration::from_nanos(nanos)
+}
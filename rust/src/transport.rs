@@ -0,0 +1,129 @@
+//! CAN transports
+//!
+//! A [`CanBus`](crate::CanBus) moves frames through a [`CanTransport`]: a
+//! SocketCAN socket for real hardware, a replayed candump log
+//! ([`CanBus::replay`](crate::CanBus::replay)) or the simulated motors of
+//! [`MockTransport`](crate::MockTransport). Everything built on the bus
+//! (controllers, groups, streamers, recorders) works the same on each.
+//!
+//! Transports exchange the crate's own [`RawFrame`], so implementing one does
+//! not tie downstream code to the socketcan version this crate builds on.
+
+use anyhow::{Result, anyhow};
+use socketcan::{CanFrame, CanId, CanSocket, EmbeddedFrame, Socket, StandardId};
+use std::io;
+use std::time::Duration;
+
+/// An arbitrary CAN frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawFrame {
+    pub id: u32,
+    /// 29-bit identifier instead of 11-bit
+    pub extended: bool,
+    /// Remote transmission request; `data` only gives the requested length
+    pub remote: bool,
+    pub data: Vec<u8>,
+}
+
+impl RawFrame {
+    pub fn standard(id: u16, data: &[u8]) -> Self {
+        Self {
+            id: id as u32,
+            extended: false,
+            remote: false,
+            data: data.to_vec(),
+        }
+    }
+
+    pub fn extended(id: u32, data: &[u8]) -> Self {
+        Self {
+            id,
+            extended: true,
+            remote: false,
+            data: data.to_vec(),
+        }
+    }
+
+    /// Copy of a socketcan frame
+    pub fn from_frame(frame: &CanFrame) -> Self {
+        Self {
+            id: crate::raw_id(frame),
+            extended: frame.is_extended(),
+            remote: frame.is_remote_frame(),
+            data: if frame.is_remote_frame() { vec![0; frame.dlc()] } else { frame.data().to_vec() },
+        }
+    }
+
+    /// Build the socketcan frame, checking the ID range and payload length
+    pub fn to_frame(&self) -> Result<CanFrame> {
+        let can_id = if self.extended {
+            CanId::extended(self.id)
+        } else {
+            u16::try_from(self.id).ok().and_then(StandardId::new).map(CanId::Standard)
+        }
+        .ok_or(anyhow!("Invalid CAN ID 0x{:X}", self.id))?;
+        let frame = if self.remote {
+            CanFrame::new_remote(can_id, self.data.len())
+        } else {
+            CanFrame::new(can_id, &self.data)
+        };
+        frame.ok_or(anyhow!("Failed to create CAN frame 0x{:X} ({} bytes)", self.id, self.data.len()))
+    }
+}
+
+/// Sends and receives raw CAN frames
+///
+/// Calls come from several threads; a [`CanBus`](crate::CanBus) serializes
+/// receiving, but sends may overlap with a receive in progress.
+pub trait CanTransport: Send + Sync {
+    /// Transmit a frame
+    fn send(&self, frame: &RawFrame) -> io::Result<()>;
+
+    /// Next received frame, waiting at most `timeout`; `None` if none arrived
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>>;
+
+    /// A fresh connection to the same bus, e.g. after the interface went down
+    fn reopen(&self) -> io::Result<Box<dyn CanTransport>> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "this transport cannot be reopened"))
+    }
+
+    /// Whether no frame will ever arrive again, e.g. at the end of a replayed log
+    fn finished(&self) -> bool {
+        false
+    }
+}
+
+/// A SocketCAN raw socket
+pub struct SocketTransport {
+    socket: CanSocket,
+    channel: String,
+}
+
+impl SocketTransport {
+    pub fn open(channel: &str) -> io::Result<Self> {
+        Ok(Self {
+            socket: CanSocket::open(channel)?,
+            channel: channel.to_string(),
+        })
+    }
+}
+
+impl CanTransport for SocketTransport {
+    fn send(&self, frame: &RawFrame) -> io::Result<()> {
+        let frame = frame.to_frame().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        self.socket.write_frame(&frame)
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        self.socket.set_read_timeout(timeout.max(Duration::from_millis(1)))?;
+        match self.socket.read_frame() {
+            Ok(frame) => Ok(Some(RawFrame::from_frame(&frame))),
+            Err(e) if matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn reopen(&self) -> io::Result<Box<dyn CanTransport>> {
+        Ok(Box::new(Self::open(&self.channel)?))
+    }
+}
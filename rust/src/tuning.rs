@@ -0,0 +1,100 @@
+//! Live gain tuning with read-back
+//!
+//! Parameters are changed in bounded steps and read back from the motor after
+//! every write, so a tuning UI always shows what the motor actually uses.
+
+use crate::{LivelyMotorController, Register};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// Relative mismatch above which a read-back counts as failed
+const READBACK_TOLERANCE: f32 = 1e-3;
+
+/// A parameter that can be tuned while running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunableParam {
+    Kp,
+    Kd,
+    TorqueLimit,
+}
+
+impl TunableParam {
+    pub const ALL: [TunableParam; 3] = [TunableParam::Kp, TunableParam::Kd, TunableParam::TorqueLimit];
+
+    pub fn register(self) -> Register {
+        match self {
+            TunableParam::Kp => Register::Kp,
+            TunableParam::Kd => Register::Kd,
+            TunableParam::TorqueLimit => Register::TorqueLimit,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TunableParam::Kp => "Kp",
+            TunableParam::Kd => "Kd",
+            TunableParam::TorqueLimit => "torque limit",
+        }
+    }
+}
+
+/// Step size and allowed range of a parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TuneBounds {
+    pub step: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl TuneBounds {
+    /// Conservative defaults; the torque limit is capped at `max_torque_nm`
+    pub fn default_for(param: TunableParam, max_torque_nm: f32) -> Self {
+        match param {
+            TunableParam::Kp => Self { step: 0.05, min: 0.0, max: 10.0 },
+            TunableParam::Kd => Self { step: 0.01, min: 0.0, max: 2.0 },
+            TunableParam::TorqueLimit => Self { step: 0.1, min: 0.0, max: max_torque_nm },
+        }
+    }
+
+    /// `value` moved by `steps` steps, clamped to the range
+    pub fn nudge(&self, value: f32, steps: i32) -> f32 {
+        (value + self.step * steps as f32).clamp(self.min, self.max)
+    }
+}
+
+/// Outcome of a verified write
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TuneResult {
+    pub requested: f32,
+    pub read_back: f32,
+}
+
+impl TuneResult {
+    pub fn verified(&self) -> bool {
+        (self.read_back - self.requested).abs() <= READBACK_TOLERANCE * self.requested.abs().max(1.0)
+    }
+}
+
+/// Read the current value of a parameter
+pub fn read_param(controller: &LivelyMotorController, motor_id: u8, param: TunableParam) -> Result<f32> {
+    Ok(controller.read_register_float(motor_id, param.register())?)
+}
+
+/// Write `value` and read it back
+pub fn write_verified(
+    controller: &LivelyMotorController,
+    motor_id: u8,
+    param: TunableParam,
+    value: f32,
+) -> Result<TuneResult> {
+    if !value.is_finite() {
+        return Err(anyhow!("{} of motor {} must be finite", param.name(), motor_id));
+    }
+    controller.write_register_float(motor_id, param.register(), value)?;
+    let read_back = read_param(controller, motor_id, param)?;
+    Ok(TuneResult {
+        requested: value,
+        read_back,
+    })
+}
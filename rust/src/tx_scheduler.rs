@@ -0,0 +1,160 @@
+//! Periodic raw frames for other devices on the bus
+//!
+//! LED drivers, fans and similar nodes often need a frame every few hundred
+//! milliseconds. A [`TxScheduler`] sends them from a background thread through
+//! the shared [`CanBus`], so they obey the same bandwidth shaping and never
+//! need a second socket competing with the controller.
+
+use crate::CanBus;
+use anyhow::{Result, anyhow};
+use socketcan::{CanFrame, EmbeddedFrame};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub use crate::transport::RawFrame;
+
+/// Handle of a scheduled frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScheduledFrameId(u64);
+
+struct Entry {
+    id: ScheduledFrameId,
+    frame: CanFrame,
+    period: Duration,
+    next: Instant,
+}
+
+#[derive(Default)]
+struct State {
+    entries: Vec<Entry>,
+    next_id: u64,
+    stop: bool,
+    errors: u64,
+    last_error: Option<String>,
+}
+
+/// Sends scheduled raw frames on their periods from a background thread
+pub struct TxScheduler {
+    bus: Arc<CanBus>,
+    shared: Arc<(Mutex<State>, Condvar)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl TxScheduler {
+    pub fn new(bus: &Arc<CanBus>) -> Self {
+        let shared = Arc::new((Mutex::new(State::default()), Condvar::new()));
+        let worker = {
+            let bus = Arc::clone(bus);
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || run(&bus, &shared))
+        };
+
+        Self {
+            bus: Arc::clone(bus),
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Send `frame` every `period`, the first time right away
+    pub fn schedule(&self, frame: &RawFrame, period: Duration) -> Result<ScheduledFrameId> {
+        if period.is_zero() {
+            return Err(anyhow!("period of frame 0x{:X} must be positive", frame.id));
+        }
+        let frame = frame.to_frame()?;
+
+        let (lock, wake) = &*self.shared;
+        let mut state = lock.lock().unwrap();
+        let id = ScheduledFrameId(state.next_id);
+        state.next_id += 1;
+        state.entries.push(Entry {
+            id,
+            frame,
+            period,
+            next: Instant::now(),
+        });
+        wake.notify_one();
+        Ok(id)
+    }
+
+    /// Replace the payload of a scheduled frame from its next transmission on
+    pub fn update(&self, id: ScheduledFrameId, data: &[u8]) -> Result<()> {
+        let mut state = self.shared.0.lock().unwrap();
+        let entry = state
+            .entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or(anyhow!("no scheduled frame {:?}", id))?;
+        entry.frame = CanFrame::new(entry.frame.id(), data)
+            .ok_or(anyhow!("Failed to create CAN frame ({} bytes)", data.len()))?;
+        Ok(())
+    }
+
+    /// Stop sending a frame, returns false if it was not scheduled
+    pub fn cancel(&self, id: ScheduledFrameId) -> bool {
+        let mut state = self.shared.0.lock().unwrap();
+        let before = state.entries.len();
+        state.entries.retain(|e| e.id != id);
+        state.entries.len() != before
+    }
+
+    /// Send a frame once, through the same bus
+    pub fn send_now(&self, frame: &RawFrame) -> Result<()> {
+        Ok(self.bus.send(&frame.to_frame()?)?)
+    }
+
+    /// Failed transmissions so far and the last error message
+    pub fn errors(&self) -> (u64, Option<String>) {
+        let state = self.shared.0.lock().unwrap();
+        (state.errors, state.last_error.clone())
+    }
+}
+
+impl Drop for TxScheduler {
+    fn drop(&mut self) {
+        let (lock, wake) = &*self.shared;
+        lock.lock().unwrap().stop = true;
+        wake.notify_one();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run(bus: &CanBus, shared: &(Mutex<State>, Condvar)) {
+    let (lock, wake) = shared;
+    let mut state = lock.lock().unwrap();
+    while !state.stop {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        for entry in state.entries.iter_mut().filter(|e| e.next <= now) {
+            due.push(entry.frame);
+            // Keep the cadence; skip missed periods instead of bursting
+            entry.next += entry.period;
+            if entry.next <= now {
+                entry.next = now + entry.period;
+            }
+        }
+
+        if !due.is_empty() {
+            drop(state);
+            let failures: Vec<String> = due
+                .iter()
+                .filter_map(|frame| bus.send(frame).err().map(|e| format!("{:#}", e)))
+                .collect();
+            state = lock.lock().unwrap();
+            state.errors += failures.len() as u64;
+            if let Some(last) = failures.into_iter().last() {
+                state.last_error = Some(last);
+            }
+            continue;
+        }
+
+        let next = state.entries.iter().map(|e| e.next).min();
+        state = match next {
+            Some(next) => wake.wait_timeout(state, next.saturating_duration_since(now)).unwrap().0,
+            None => wake.wait(state).unwrap(),
+        };
+    }
+}
@@ -0,0 +1,272 @@
+//! Control-loss watchdog
+//!
+//! A motor keeps executing its last stream command until told otherwise, so a
+//! control process that crashes or hangs mid-motion leaves the robot moving. A
+//! [`Watchdog`] has to be fed by the control loop at least once per timeout;
+//! when it is not, its own thread sends a zero-velocity command and a disable
+//! to every registered motor. The frames go straight to the shared
+//! [`CanBus`], so a trip does not depend on the (possibly stuck) controller.
+//! With a braking [`StopMode`] ([`Watchdog::with_stop_mode`]) the disable
+//! follows once the zero-velocity command has brought the motors to rest.
+//!
+//! A tripped watchdog stays tripped until [`Watchdog::reset`]: feeding it
+//! again does not bring the motors back, they have to be enabled again.
+
+use crate::events::{EventBus, EventKind};
+use crate::estop;
+use crate::protocol::{self, EncodingPolicy, Register, VELOCITY_STREAM_ID};
+use crate::{Calibration, CanBus, LivelyMotorController, StateCache, StopMode};
+use anyhow::{Result, anyhow};
+use socketcan::{CanFrame, CanId, EmbeddedFrame};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Deceleration of the zero-velocity command sent on a trip with
+/// [`StopMode::ZeroTorque`]
+const STOP_ACCELERATION_RPS2: f64 = 20.0;
+
+#[derive(Default)]
+struct State {
+    motor_ids: BTreeSet<u8>,
+    last_fed: Option<Instant>,
+    tripped: bool,
+    stop_mode: StopMode,
+    /// Motors whose zero-velocity frame could not be sent yet
+    unstopped: BTreeSet<u8>,
+    /// Motors braking or whose disable could not be sent yet
+    undisabled: BTreeSet<u8>,
+    /// When the braking motors are at rest
+    disable_at: Option<Instant>,
+    trips: u64,
+    send_errors: u64,
+    last_error: Option<String>,
+    stop: bool,
+}
+
+struct Shared {
+    bus: Arc<CanBus>,
+    encoding: EncodingPolicy,
+    /// Velocities the braking deceleration is computed from
+    states: Arc<StateCache>,
+    calibrations: Arc<Mutex<BTreeMap<u8, Calibration>>>,
+    timeout: Duration,
+    events: Option<Arc<EventBus>>,
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+/// Stops all registered motors when it is not fed in time
+pub struct Watchdog {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Start watching with `timeout` between feeds; the timer starts now
+    pub fn start(controller: &LivelyMotorController, timeout: Duration) -> Result<Self> {
+        Self::start_with_events(controller, timeout, None)
+    }
+
+    /// Like [`start`](Self::start), also publishing [`EventKind::WatchdogTripped`] on `events`
+    pub fn start_with_events(
+        controller: &LivelyMotorController,
+        timeout: Duration,
+        events: Option<Arc<EventBus>>,
+    ) -> Result<Self> {
+        if timeout.is_zero() {
+            return Err(anyhow!("watchdog timeout must be positive"));
+        }
+        let shared = Arc::new(Shared {
+            bus: Arc::clone(controller.bus()),
+            encoding: controller.encoding().clone(),
+            states: Arc::clone(controller.state_cache()),
+            calibrations: Arc::clone(controller.calibrations()),
+            timeout,
+            events,
+            state: Mutex::new(State {
+                last_fed: Some(Instant::now()),
+                ..Default::default()
+            }),
+            wake: Condvar::new(),
+        });
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || watch(&shared))
+        };
+        Ok(Self {
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    /// Stop the motors as `mode` says when the watchdog trips, instead of
+    /// disabling them at once
+    pub fn with_stop_mode(self, mode: StopMode) -> Self {
+        self.shared.state.lock().unwrap().stop_mode = mode;
+        self
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.shared.timeout
+    }
+
+    pub fn stop_mode(&self) -> StopMode {
+        self.shared.state.lock().unwrap().stop_mode
+    }
+
+    /// Stop `motor_id` too when the watchdog trips
+    pub fn register(&self, motor_id: u8) {
+        self.shared.state.lock().unwrap().motor_ids.insert(motor_id);
+    }
+
+    pub fn unregister(&self, motor_id: u8) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.motor_ids.remove(&motor_id);
+        state.unstopped.remove(&motor_id);
+        state.undisabled.remove(&motor_id);
+    }
+
+    /// Registered motors, in ID order
+    pub fn motor_ids(&self) -> Vec<u8> {
+        self.shared.state.lock().unwrap().motor_ids.iter().copied().collect()
+    }
+
+    /// Restart the timer; has no effect once tripped
+    pub fn feed(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.tripped {
+            state.last_fed = Some(Instant::now());
+        }
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.shared.state.lock().unwrap().tripped
+    }
+
+    /// Fail if the watchdog tripped, for commands that must not go out after a trip
+    pub fn check(&self) -> Result<()> {
+        if self.is_tripped() {
+            return Err(anyhow!("watchdog tripped: motors were stopped, reset it and enable them again"));
+        }
+        Ok(())
+    }
+
+    /// Re-arm after a trip, starting the timer from now
+    pub fn reset(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.tripped = false;
+        state.unstopped.clear();
+        state.undisabled.clear();
+        state.disable_at = None;
+        state.last_fed = Some(Instant::now());
+        self.shared.wake.notify_all();
+    }
+
+    /// Times the watchdog tripped since it started
+    pub fn trips(&self) -> u64 {
+        self.shared.state.lock().unwrap().trips
+    }
+
+    /// Stop frames that failed to send; they are retried every timeout
+    pub fn send_errors(&self) -> u64 {
+        self.shared.state.lock().unwrap().send_errors
+    }
+
+    /// Why the last stop frame failed to send
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.state.lock().unwrap().last_error.clone()
+    }
+}
+
+impl Drop for Watchdog {
+    /// Stop watching; the motors are left as they are
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stop = true;
+        self.shared.wake.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn watch(shared: &Shared) {
+    let mut state = shared.state.lock().unwrap();
+    while !state.stop {
+        let now = Instant::now();
+        if !state.tripped {
+            let deadline = state.last_fed.unwrap_or(now) + shared.timeout;
+            if now < deadline {
+                state = shared.wake.wait_timeout(state, deadline - now).unwrap().0;
+                continue;
+            }
+            state.tripped = true;
+            state.trips += 1;
+            state.unstopped = state.motor_ids.clone();
+            if let Some(events) = &shared.events {
+                events.publish(EventKind::WatchdogTripped {
+                    motor_ids: state.motor_ids.iter().copied().collect(),
+                });
+            }
+        }
+
+        // Frames lost on a stalled bus are retried until they go out
+        let unstopped = std::mem::take(&mut state.unstopped);
+        for motor_id in unstopped {
+            match stop_motor(shared, state.stop_mode, motor_id) {
+                Ok(braking) => {
+                    let at = Instant::now() + braking;
+                    state.disable_at = Some(state.disable_at.map_or(at, |t| t.max(at)));
+                    state.undisabled.insert(motor_id);
+                }
+                Err(e) => {
+                    state.send_errors += 1;
+                    state.last_error = Some(format!("cannot stop motor {}: {:#}", motor_id, e));
+                    state.unstopped.insert(motor_id);
+                }
+            }
+        }
+        if state.disable_at.is_some_and(|at| Instant::now() >= at) {
+            let undisabled = std::mem::take(&mut state.undisabled);
+            for motor_id in undisabled {
+                if let Err(e) = send(shared, motor_id as u32, &shared.encoding.write_int8(Register::Mode, 0x00)) {
+                    state.send_errors += 1;
+                    state.last_error = Some(format!("cannot disable motor {}: {:#}", motor_id, e));
+                    state.undisabled.insert(motor_id);
+                }
+            }
+        }
+        // Wake up for the end of the braking, else retry every timeout
+        let wait = match state.disable_at {
+            Some(at) if !state.undisabled.is_empty() => at.saturating_duration_since(Instant::now()),
+            _ => Duration::ZERO,
+        };
+        let wait = if wait.is_zero() { shared.timeout } else { wait.min(shared.timeout) };
+        state = shared.wake.wait_timeout(state, wait).unwrap().0;
+    }
+}
+
+/// Send the zero-velocity command of `mode`, returning how long the motor
+/// brakes before it can be disabled
+///
+/// Zero velocity is sent even for [`StopMode::ZeroTorque`], so a motor whose
+/// disable gets lost still comes to rest.
+fn stop_motor(shared: &Shared, mode: StopMode, motor_id: u8) -> Result<Duration> {
+    let velocity = estop::motor_velocity(&shared.states, &shared.calibrations.lock().unwrap(), motor_id);
+    let (acceleration_rps2, braking) = match mode.braking(velocity) {
+        Some(braking) => (braking.acceleration_rps2, braking.duration),
+        None => (STOP_ACCELERATION_RPS2, Duration::ZERO),
+    };
+    let zero = shared
+        .encoding
+        .velocity_stream(crate::MAGIC_POS, 0, crate::rps2_to_acceleration(acceleration_rps2));
+    send(shared, protocol::stream_id(VELOCITY_STREAM_ID, motor_id), &zero)?;
+    Ok(braking)
+}
+
+fn send(shared: &Shared, id: u32, data: &[u8]) -> Result<()> {
+    let can_id = CanId::extended(id).ok_or(anyhow!("Invalid CAN ID 0x{:X}", id))?;
+    let frame = CanFrame::new(can_id, data).ok_or(anyhow!("Failed to create CAN frame 0x{:X}", id))?;
+    Ok(shared.bus.send(&frame)?)
+}
@@ -0,0 +1,237 @@
+//! Wheels and differential-drive odometry
+//!
+//! A [`Wheel`] runs a motor in continuous velocity mode (0xAD stream with no
+//! position limit) and integrates its encoder into an unwrapped angle and a
+//! travelled distance. The position register wraps every 65536 counts, so
+//! consecutive readings are unwrapped by taking the shortest step between them.
+//! [`DifferentialDrive`] converts body twists (v, ω) to wheel speeds and
+//! integrates wheel distances into a planar pose.
+
+use crate::{FACTOR_POS, LivelyMotorController};
+use anyhow::Result;
+use std::f64::consts::PI;
+use std::time::Instant;
+
+/// Degrees covered by one full wrap of the int16 position register
+pub const POSITION_WRAP_DEG: f64 = 65536.0 / FACTOR_POS * 360.0;
+
+/// Turns wrapped int16 position readings into a continuous angle
+#[derive(Debug, Clone, Default)]
+pub struct AngleUnwrapper {
+    last_raw_deg: Option<f64>,
+    angle_deg: f64,
+}
+
+impl AngleUnwrapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a raw reading, returns the unwrapped angle
+    pub fn update(&mut self, raw_deg: f64) -> f64 {
+        match self.last_raw_deg {
+            None => self.angle_deg = raw_deg,
+            Some(last) => {
+                let mut delta = raw_deg - last;
+                if delta > POSITION_WRAP_DEG / 2.0 {
+                    delta -= POSITION_WRAP_DEG;
+                } else if delta < -POSITION_WRAP_DEG / 2.0 {
+                    delta += POSITION_WRAP_DEG;
+                }
+                self.angle_deg += delta;
+            }
+        }
+        self.last_raw_deg = Some(raw_deg);
+        self.angle_deg
+    }
+
+    /// Unwrapped angle so far
+    pub fn angle_deg(&self) -> f64 {
+        self.angle_deg
+    }
+}
+
+/// Odometry of one wheel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelOdometry {
+    /// Unwrapped wheel angle since the first reading
+    pub angle_deg: f64,
+    /// Signed distance rolled since the first reading
+    pub distance_m: f64,
+    /// Ground speed from the measured velocity
+    pub speed_mps: f64,
+}
+
+/// A motor driving a wheel
+pub struct Wheel<'a> {
+    controller: &'a LivelyMotorController,
+    motor_id: u8,
+    radius_m: f64,
+    /// Wheel turns forward for negative motor velocity (mirrored mounting)
+    reversed: bool,
+    acceleration_rps2: f64,
+    unwrapper: AngleUnwrapper,
+    start_deg: Option<f64>,
+    last_update: Option<Instant>,
+}
+
+impl<'a> Wheel<'a> {
+    pub fn new(controller: &'a LivelyMotorController, motor_id: u8, radius_m: f64) -> Self {
+        Self {
+            controller,
+            motor_id,
+            radius_m,
+            reversed: false,
+            acceleration_rps2: 5.0,
+            unwrapper: AngleUnwrapper::new(),
+            start_deg: None,
+            last_update: None,
+        }
+    }
+
+    pub fn reversed(mut self, reversed: bool) -> Self {
+        self.reversed = reversed;
+        self
+    }
+
+    /// Acceleration used for velocity commands
+    pub fn with_acceleration(mut self, acceleration_rps2: f64) -> Self {
+        self.acceleration_rps2 = acceleration_rps2;
+        self
+    }
+
+    pub fn motor_id(&self) -> u8 {
+        self.motor_id
+    }
+
+    pub fn radius_m(&self) -> f64 {
+        self.radius_m
+    }
+
+    /// Enable the motor for continuous velocity control
+    pub fn enable(&self) -> Result<()> {
+        Ok(self.controller.enable_velocity_mode(self.motor_id)?)
+    }
+
+    pub fn disable(&self) -> Result<()> {
+        Ok(self.controller.disable_motor(self.motor_id)?)
+    }
+
+    /// Command a wheel speed in r/s (positive = forward)
+    pub fn set_rps(&self, wheel_rps: f64) -> Result<()> {
+        let motor_rps = if self.reversed { -wheel_rps } else { wheel_rps };
+        Ok(self.controller.send_velocity_command_to(
+            self.motor_id,
+            crate::MAGIC_POS,
+            crate::rps_to_velocity(motor_rps),
+            crate::rps2_to_acceleration(self.acceleration_rps2),
+        )?)
+    }
+
+    /// Command a ground speed in m/s
+    pub fn set_speed(&self, speed_mps: f64) -> Result<()> {
+        self.set_rps(speed_mps / (2.0 * PI * self.radius_m))
+    }
+
+    /// Read the encoder and update the odometry; call at least every half wrap
+    pub fn update(&mut self) -> Result<WheelOdometry> {
+        let state = self.controller.read_motor_state(self.motor_id)?;
+        let sign = if self.reversed { -1.0 } else { 1.0 };
+
+        let angle = self.unwrapper.update(state.position_deg);
+        let start = *self.start_deg.get_or_insert(angle);
+        self.last_update = Some(Instant::now());
+
+        let angle_deg = sign * (angle - start);
+        Ok(WheelOdometry {
+            angle_deg,
+            distance_m: angle_deg.to_radians() * self.radius_m,
+            speed_mps: sign * state.velocity_rps * 2.0 * PI * self.radius_m,
+        })
+    }
+
+    /// Restart distance counting from the current position
+    pub fn reset_odometry(&mut self) {
+        self.unwrapper = AngleUnwrapper::new();
+        self.start_deg = None;
+    }
+
+    /// Time of the last successful `update`
+    pub fn last_update(&self) -> Option<Instant> {
+        self.last_update
+    }
+}
+
+/// Planar pose of a mobile base
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BasePose {
+    pub x_m: f64,
+    pub y_m: f64,
+    pub theta_rad: f64,
+}
+
+/// Differential-drive kinematics and odometry
+#[derive(Debug, Clone)]
+pub struct DifferentialDrive {
+    pub wheel_radius_m: f64,
+    /// Distance between the wheel contact points
+    pub track_width_m: f64,
+    pose: BasePose,
+    last_distances: Option<(f64, f64)>,
+}
+
+impl DifferentialDrive {
+    pub fn new(wheel_radius_m: f64, track_width_m: f64) -> Self {
+        Self {
+            wheel_radius_m,
+            track_width_m,
+            pose: BasePose::default(),
+            last_distances: None,
+        }
+    }
+
+    /// Left/right wheel speeds in r/s for a body twist (v m/s, ω rad/s)
+    pub fn wheel_speeds(&self, v_mps: f64, omega_rad_s: f64) -> (f64, f64) {
+        let half_track = self.track_width_m / 2.0;
+        let circumference = 2.0 * PI * self.wheel_radius_m;
+        (
+            (v_mps - omega_rad_s * half_track) / circumference,
+            (v_mps + omega_rad_s * half_track) / circumference,
+        )
+    }
+
+    /// Body twist (v m/s, ω rad/s) from left/right wheel speeds in r/s
+    pub fn body_twist(&self, left_rps: f64, right_rps: f64) -> (f64, f64) {
+        let circumference = 2.0 * PI * self.wheel_radius_m;
+        let (left, right) = (left_rps * circumference, right_rps * circumference);
+        ((left + right) / 2.0, (right - left) / self.track_width_m)
+    }
+
+    /// Integrate cumulative left/right wheel distances into the pose
+    pub fn update(&mut self, left_distance_m: f64, right_distance_m: f64) -> BasePose {
+        if let Some((last_left, last_right)) = self.last_distances {
+            let dl = left_distance_m - last_left;
+            let dr = right_distance_m - last_right;
+            let ds = (dl + dr) / 2.0;
+            let dtheta = (dr - dl) / self.track_width_m;
+
+            // Midpoint integration
+            let heading = self.pose.theta_rad + dtheta / 2.0;
+            self.pose.x_m += ds * heading.cos();
+            self.pose.y_m += ds * heading.sin();
+            self.pose.theta_rad = (self.pose.theta_rad + dtheta + PI).rem_euclid(2.0 * PI) - PI;
+        }
+        self.last_distances = Some((left_distance_m, right_distance_m));
+        self.pose
+    }
+
+    pub fn pose(&self) -> BasePose {
+        self.pose
+    }
+
+    /// Reset the pose; the next `update` only sets the distance reference
+    pub fn reset(&mut self, pose: BasePose) {
+        self.pose = pose;
+        self.last_distances = None;
+    }
+}
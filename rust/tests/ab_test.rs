@@ -0,0 +1,140 @@
+//! A/B comparison of two simulated actuators
+
+use livelybot_motor_control::protocol::Register;
+use livelybot_motor_control::{
+    AbComparison, AbReport, AbTest, LivelyMotorController, MockTransport, OscillateParams, Primitive, RawFrame,
+    SimMotor,
+};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+const EXCITATION: Duration = Duration::from_millis(300);
+
+fn test() -> AbTest {
+    AbTest::new(Primitive::oscillate(OscillateParams {
+        center_deg: 0.0,
+        amplitude_deg: 20.0,
+        frequency_hz: 2.0,
+        duration: EXCITATION,
+    }))
+}
+
+/// A stiff actuator and a soft replacement that lags behind
+fn stiff() -> SimMotor {
+    SimMotor {
+        bandwidth: 60.0,
+        ..SimMotor::default()
+    }
+}
+
+fn soft() -> SimMotor {
+    SimMotor {
+        bandwidth: 8.0,
+        temperature_c: 41.0,
+        ..SimMotor::default()
+    }
+}
+
+/// Motor 1 stiff, motor 2 soft, on one bus
+fn setup() -> LivelyMotorController {
+    LivelyMotorController::with_transport("mock", MockTransport::new().with_motor(1, stiff()).with_motor(2, soft()))
+}
+
+fn report(motor_id: u8, rms_error_deg: f64, temperatures: (Option<f64>, Option<f64>)) -> AbReport {
+    AbReport {
+        channel: "can0".to_string(),
+        motor_id,
+        samples: 30,
+        completed: true,
+        rms_error_deg,
+        max_error_deg: 2.0 * rms_error_deg,
+        temperature_start_c: temperatures.0,
+        temperature_end_c: temperatures.1,
+        mean_current_a: Some(1.5),
+        peak_current_a: None,
+    }
+}
+
+#[test]
+fn metrics_compare_b_against_a_in_a_fixed_order() {
+    let comparison = AbComparison {
+        a: report(1, 0.5, (Some(30.0), Some(32.5))),
+        b: report(2, 1.25, (Some(30.0), None)),
+    };
+    let metrics = comparison.metrics();
+    let names: Vec<&str> = metrics.iter().map(|m| m.name).collect();
+    assert_eq!(names, ["rms_error_deg", "max_error_deg", "temperature_rise_c", "mean_current_a", "peak_current_a"]);
+    let deltas: Vec<Option<f64>> = metrics.iter().map(|m| m.delta()).collect();
+    assert_eq!(deltas, [Some(0.75), Some(1.5), None, Some(0.0), None]);
+    assert_eq!((metrics[2].a, metrics[2].b), (Some(2.5), None));
+
+    let json = serde_json::to_string(&comparison).unwrap();
+    assert_eq!(serde_json::from_str::<AbComparison>(&json).unwrap(), comparison);
+}
+
+#[test]
+fn sequential_runs_keep_each_motor_on_its_arm() {
+    let controller = setup();
+    let running = AtomicBool::new(true);
+    let cooldown = Duration::from_millis(100);
+
+    let sent = controller.bus().subscribe_sent();
+    let start = Instant::now();
+    let comparison = test().compare(&controller, 1, 2, cooldown, &running).unwrap();
+    assert!(start.elapsed() >= 2 * EXCITATION + cooldown, "{:?}", start.elapsed());
+
+    // B is switched off before A is enabled, and enabled only after A is off again
+    let modes: Vec<(u8, u8)> = std::iter::from_fn(|| sent.try_recv())
+        .map(|f| RawFrame::from_frame(&f))
+        .filter(|f| f.id < 0x80 && f.data[..2] == [0x01, Register::Mode as u8])
+        .map(|f| (f.id as u8, f.data[2]))
+        .collect();
+    assert_eq!(modes, [(2, 0x00), (1, 0x0A), (1, 0x00), (2, 0x0A), (2, 0x00)]);
+
+    let (a, b) = (&comparison.a, &comparison.b);
+    assert_eq!((a.motor_id, b.motor_id), (1, 2));
+    assert_eq!((a.channel.as_str(), b.channel.as_str()), ("mock", "mock"));
+    assert!(a.completed && b.completed);
+    assert!(a.samples > 10 && b.samples > 10, "{} {}", a.samples, b.samples);
+    assert!(b.rms_error_deg > 1.5 * a.rms_error_deg, "{:?}", comparison);
+    assert!(a.max_error_deg >= a.rms_error_deg && b.max_error_deg >= b.rms_error_deg);
+    assert_eq!((a.temperature_start_c, b.temperature_start_c), (Some(35.0), Some(41.0)));
+    assert_eq!(a.temperature_rise_c(), Some(0.0));
+    assert!(b.peak_current_a.unwrap() >= b.mean_current_a.unwrap());
+
+    // Swapping the arms swaps the reports
+    let swapped = test().compare(&setup(), 2, 1, Duration::ZERO, &running).unwrap();
+    assert_eq!((swapped.a.motor_id, swapped.b.motor_id), (2, 1));
+    assert!(swapped.a.rms_error_deg > 1.5 * swapped.b.rms_error_deg, "{:?}", swapped);
+}
+
+#[test]
+fn parallel_runs_use_one_bus_per_arm() {
+    let controller_a = LivelyMotorController::with_transport("can0", MockTransport::new().with_motor(1, stiff()));
+    let controller_b = LivelyMotorController::with_transport("can1", MockTransport::new().with_motor(1, soft()));
+
+    let start = Instant::now();
+    let comparison = test()
+        .compare_parallel((&controller_a, 1), (&controller_b, 1), &AtomicBool::new(true))
+        .unwrap();
+    assert!(start.elapsed() < 2 * EXCITATION, "{:?}", start.elapsed());
+
+    assert_eq!((comparison.a.channel.as_str(), comparison.b.channel.as_str()), ("can0", "can1"));
+    assert_eq!(comparison.b.temperature_start_c, Some(41.0));
+    assert!(comparison.b.rms_error_deg > 1.5 * comparison.a.rms_error_deg, "{:?}", comparison);
+}
+
+#[test]
+fn an_interrupted_run_is_reported_incomplete() {
+    let start = Instant::now();
+    let comparison = test()
+        .compare(&setup(), 1, 2, Duration::from_secs(10), &AtomicBool::new(false))
+        .unwrap();
+    // No excitation and no cooldown
+    assert!(start.elapsed() < EXCITATION, "{:?}", start.elapsed());
+    for report in [&comparison.a, &comparison.b] {
+        assert!(!report.completed);
+        assert_eq!((report.samples, report.rms_error_deg), (0, 0.0));
+    }
+}
+
@@ -0,0 +1,59 @@
+//! Throttling queries while the bus error counters rise
+
+use livelybot_motor_control::{
+    AdaptiveRate, AdaptiveRateConfig, EventBus, EventKind, LivelyMotorController, MockTransport, SimMotor,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn errors_throttle_queries_but_not_setpoints() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    let bus = Arc::clone(controller.bus());
+    let events = Arc::new(EventBus::new());
+    let subscription = events.subscribe();
+    let config = AdaptiveRateConfig::default()
+        .with_degrade_errors(3)
+        .with_recover_checks(2)
+        .with_spacing_per_level(Duration::from_millis(20));
+    let mut adaptive = AdaptiveRate::new(&bus, config, Some(Arc::clone(&events))).unwrap();
+
+    adaptive.check().unwrap();
+    assert_eq!(adaptive.level(), 0);
+    (0..5).for_each(|_| bus.note_malformed());
+    assert_eq!(adaptive.check().unwrap().malformed, 5);
+    assert_eq!(adaptive.level(), 1);
+    assert_eq!(bus.query_spacing(), Duration::from_millis(20));
+    assert_eq!(bus.telemetry_period(Duration::from_millis(100)), Duration::from_millis(200));
+    match subscription.try_recv().unwrap().kind {
+        EventKind::BusDegradation { level, errors, telemetry_scale, .. } => {
+            assert_eq!((level, errors.malformed, telemetry_scale), (1, 5, 0.5));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    let start = Instant::now();
+    for _ in 0..3 {
+        controller.read_motor_state(1).unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(40));
+
+    controller.enable_motor(1).unwrap();
+    let start = Instant::now();
+    for _ in 0..10 {
+        controller.send_angle_command_to(1, 0, 100, 100).unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(100));
+
+    // Two clean checks step back down
+    adaptive.check().unwrap();
+    assert_eq!(adaptive.level(), 1);
+    adaptive.check().unwrap();
+    assert_eq!(adaptive.level(), 0);
+    assert_eq!(bus.query_spacing(), Duration::ZERO);
+    match subscription.try_recv().unwrap().kind {
+        EventKind::BusDegradation { level, .. } => assert_eq!(level, 0),
+        other => panic!("unexpected event {:?}", other),
+    }
+}
@@ -0,0 +1,43 @@
+//! Authority levels of bridge clients
+
+use livelybot_motor_control::tuning::TunableParam;
+use livelybot_motor_control::{AccessPolicy, Authority, BridgeMessage, BridgeRequest};
+
+#[test]
+fn each_level_gets_its_operations_only() {
+    let policy = AccessPolicy::default()
+        .with_token("dashboard", Authority::Observer)
+        .with_token("pilot", Authority::Operator)
+        .with_token("tuner", Authority::Engineer);
+    let read = BridgeMessage::ReadState { motor_id: 1 };
+    let write = BridgeMessage::SetParam { motor_id: 1, param: TunableParam::Kp, value: 2.0 };
+
+    assert!(policy.authorize(Some("dashboard"), &read).is_ok());
+    assert!(policy.authorize(Some("dashboard"), &BridgeMessage::Stop).is_err());
+    assert!(policy.authorize(Some("pilot"), &BridgeMessage::Stop).is_ok());
+    assert!(policy.authorize(Some("pilot"), &write).is_err());
+    assert_eq!(policy.authorize(Some("tuner"), &write).unwrap(), Authority::Engineer);
+
+    // Anonymous clients are observers; a wrong token is not downgraded to anonymous
+    assert!(policy.authorize(None, &read).is_ok());
+    assert!(policy.authorize(None, &write).is_err());
+    assert!(policy.authorize(Some("tuner2"), &read).is_err());
+}
+
+#[test]
+fn token_travels_next_to_the_message() {
+    let request = BridgeRequest {
+        token: Some("tuner".to_string()),
+        message: BridgeMessage::SetParam { motor_id: 3, param: TunableParam::TorqueLimit, value: 1.5 },
+    };
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(
+        json,
+        r#"{"token":"tuner","type":"set_param","motor_id":3,"param":"torque_limit","value":1.5}"#
+    );
+    assert_eq!(serde_json::from_str::<BridgeRequest>(&json).unwrap(), request);
+
+    // Requests from clients without tokens still parse
+    let plain: BridgeRequest = serde_json::from_str(r#"{"type":"stop"}"#).unwrap();
+    assert_eq!(plain, BridgeRequest { token: None, message: BridgeMessage::Stop });
+}
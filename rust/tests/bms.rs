@@ -0,0 +1,145 @@
+//! BMS frames decoded from a recorded bus, and battery derating on top
+
+use livelybot_motor_control::{
+    BatteryDerating, BatteryState, BmsFormat, BmsMonitor, CanBus, CandumpLog, EventBus, EventKind, RawFrame,
+    SafetyMonitor,
+};
+use socketcan::EmbeddedFrame;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A pack sagging under load, then recovering while charging, recorded with
+/// a motor reply and a truncated status frame in between; the first frame
+/// leaves the monitor time to subscribe
+const SAG: &str = "\
+(1700000000.000000) can0 123#00
+(1700000000.100000) can0 18904001#01F4000075AD0320
+(1700000000.105000) can0 00000300#2D230000C03F
+(1700000000.110000) can0 18904001#01D8000075AD031B
+(1700000000.120000) can0 18904001#01D0000075AD0316
+(1700000000.125000) can0 18904001#01D0
+(1700000000.130000) can0 18904001#01CE000075AD0316
+(1700000000.140000) can0 18904001#01B4000075AD0311
+(1700000000.150000) can0 18904001#017C000075AD030C
+(1700000000.160000) can0 18904001#0172000075AD030C
+(1700000000.170000) can0 18904001#01EA000074CC030C
+";
+
+fn decode(format: &BmsFormat, frame: RawFrame) -> Option<BatteryState> {
+    format.decode(&frame.to_frame().unwrap())
+}
+
+#[test]
+fn daly_status_frames_are_decoded() {
+    let daly = BmsFormat::known("daly").unwrap();
+    let state = decode(&daly, RawFrame::extended(0x1890_4001, &[0x01, 0xF4, 0, 0, 0x75, 0xAD, 0x03, 0x20])).unwrap();
+    assert!((state.voltage_v - 50.0).abs() < 1e-9, "{:?}", state);
+    assert!((state.current_a - 12.5).abs() < 1e-9, "{:?}", state);
+    assert!((state.soc_pct.unwrap() - 80.0).abs() < 1e-9, "{:?}", state);
+
+    // Charging reads as a negative current
+    let charging = decode(&daly, RawFrame::extended(0x1890_4001, &[0x01, 0xEA, 0, 0, 0x74, 0xCC, 0x03, 0x0C])).unwrap();
+    assert!((charging.current_a + 10.0).abs() < 1e-9, "{:?}", charging);
+
+    assert!(decode(&daly, RawFrame::extended(0x1890_4002, &[0; 8])).is_none());
+    assert!(decode(&daly, RawFrame::extended(0x1890_4001, &[0x01, 0xF4, 0, 0, 0x75, 0xAD])).is_none());
+    assert_eq!(
+        BmsFormat::known("jbd").unwrap_err().to_string(),
+        "unknown BMS format 'jbd' (known: daly)"
+    );
+}
+
+#[test]
+fn custom_formats_load_from_json() {
+    let path = std::env::temp_dir().join(format!("livelybot-bms-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{
+            "name": "pack",
+            "id": 1280,
+            "voltage": { "offset": 0, "size": 2, "scale": 0.01 },
+            "current": { "offset": 2, "size": 2, "signed": true, "scale": 0.1 }
+        }"#,
+    )
+    .unwrap();
+    let format = BmsFormat::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((format.request_id, format.soc), (None, None));
+
+    // Little endian by default: 48.00 V, -2.5 A
+    let state = decode(&format, RawFrame::standard(0x500, &[0xC0, 0x12, 0xE7, 0xFF])).unwrap();
+    assert!((state.voltage_v - 48.0).abs() < 1e-9, "{:?}", state);
+    assert!((state.current_a + 2.5).abs() < 1e-9, "{:?}", state);
+    assert_eq!(state.soc_pct, None);
+
+    let error = BmsFormat::load(&path).unwrap_err().to_string();
+    assert!(error.starts_with(&format!("Cannot read BMS format {}: ", path.display())), "{}", error);
+}
+
+#[test]
+fn the_monitor_picks_the_bms_out_of_a_recorded_bus() {
+    let bus = CanBus::replay_log(CandumpLog::parse(SAG).unwrap(), 1_000_000);
+    let mut monitor = BmsMonitor::new(&bus, BmsFormat::daly());
+
+    let mut states = Vec::new();
+    while let Some(state) = monitor.poll(Duration::from_millis(500)).unwrap() {
+        states.push(state);
+    }
+    assert!(bus.replay_finished());
+    let voltages: Vec<f64> = states.iter().map(|s| (s.voltage_v * 10.0).round() / 10.0).collect();
+    assert_eq!(voltages, [50.0, 47.2, 46.4, 46.2, 43.6, 38.0, 37.0, 49.0]);
+    let soc: Vec<f64> = states.iter().map(|s| (s.soc_pct.unwrap() * 10.0).round() / 10.0).collect();
+    assert_eq!(soc, [80.0, 79.5, 79.0, 79.0, 78.5, 78.0, 78.0, 78.0]);
+    assert!(states.windows(2).all(|s| s[0].timestamp <= s[1].timestamp));
+    // The truncated status frame is counted, the motor reply ignored
+    assert_eq!(bus.malformed_frames(), 1);
+    assert_eq!(monitor.latest(), states.last().copied());
+
+    // Daly only answers polls
+    let sent = bus.subscribe_sent();
+    monitor.request().unwrap();
+    let request = sent.try_recv().unwrap();
+    assert_eq!(RawFrame::from_frame(&request), RawFrame::extended(0x1890_0140, &[0; 8]));
+    assert!(request.is_extended());
+    let broadcasting = BmsFormat {
+        request_id: None,
+        ..BmsFormat::daly()
+    };
+    BmsMonitor::new(&bus, broadcasting).request().unwrap();
+    assert!(sent.try_recv().is_none());
+}
+
+#[test]
+fn derating_thresholds_fire_as_the_pack_sags() {
+    let bus = CanBus::replay_log(CandumpLog::parse(SAG).unwrap(), 1_000_000);
+    let mut monitor = BmsMonitor::new(&bus, BmsFormat::daly());
+    let events = Arc::new(EventBus::new());
+    let received = events.subscribe();
+    // Full torque from 48 V, down to 20 % at 40 V
+    let safety = SafetyMonitor::new()
+        .with_battery_derating(BatteryDerating::new(48.0, 40.0, 0.2))
+        .with_events(Arc::clone(&events));
+
+    let mut scales = Vec::new();
+    while let Some(state) = monitor.poll(Duration::from_millis(500)).unwrap() {
+        scales.push((safety.update_battery(&state) * 100.0).round());
+    }
+    assert_eq!(scales, [100.0, 92.0, 84.0, 82.0, 56.0, 20.0, 20.0, 100.0]);
+    assert_eq!(safety.torque_scale(), 1.0);
+
+    // Entering derating, every 10 % step and leaving it; not every reading
+    let fired: Vec<(f64, f64)> = received
+        .try_iter()
+        .map(|event| match event.kind {
+            EventKind::BatteryDerating { voltage_v, torque_scale } => {
+                ((voltage_v * 10.0).round() / 10.0, (torque_scale * 100.0).round())
+            }
+            other => panic!("{:?}", other),
+        })
+        .collect();
+    assert_eq!(fired, [(47.2, 92.0), (46.4, 84.0), (43.6, 56.0), (38.0, 20.0), (49.0, 100.0)]);
+
+    // Without derating the battery changes nothing
+    let plain = SafetyMonitor::new();
+    assert_eq!(plain.update_battery(&monitor.latest().unwrap()), 1.0);
+}
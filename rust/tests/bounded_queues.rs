@@ -0,0 +1,42 @@
+//! Internal queues stay bounded when nobody drains them
+
+use livelybot_motor_control::events::EVENT_QUEUE_LEN;
+use livelybot_motor_control::recorder::{JointSample, Recorder};
+use livelybot_motor_control::{EventBus, EventKind};
+
+#[test]
+fn stalled_event_subscriber_loses_new_events() {
+    let events = EventBus::new();
+    let stalled = events.subscribe();
+    for _ in 0..EVENT_QUEUE_LEN + 10 {
+        events.publish(EventKind::BatteryDerating {
+            voltage_v: 44.0,
+            torque_scale: 0.8,
+        });
+    }
+    assert_eq!(events.dropped(), 10);
+    assert_eq!(stalled.try_iter().count(), EVENT_QUEUE_LEN);
+
+    // A subscriber that went away is forgotten, not counted
+    drop(stalled);
+    events.publish(EventKind::BatteryDerating {
+        voltage_v: 44.0,
+        torque_scale: 0.8,
+    });
+    assert_eq!(events.dropped(), 10);
+}
+
+#[test]
+fn recorder_keeps_the_most_recent_rows() {
+    let mut recorder = Recorder::new(&[1]).with_max_rows(80);
+    for i in 0..1000 {
+        recorder.record(&[JointSample {
+            motor_id: 1,
+            target_deg: i as f64,
+            actual: None,
+        }]);
+    }
+    assert!(recorder.len() <= 80 && recorder.len() >= 70);
+    assert_eq!(recorder.dropped_rows() + recorder.len() as u64, 1000);
+    assert_eq!(recorder.latest().unwrap().values[0], 999.0);
+}
@@ -0,0 +1,80 @@
+//! Per-channel bus ownership lock
+
+use livelybot_motor_control::BusLock;
+use std::sync::mpsc;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A channel name no other test or process uses
+fn channel(name: &str) -> String {
+    format!("test-{}-{}", std::process::id(), name)
+}
+
+#[test]
+fn only_one_of_two_contending_threads_gets_the_lock() {
+    let channel = channel("contended");
+    let barrier = Barrier::new(2);
+    let (release_tx, release) = mpsc::channel::<()>();
+    let release = Mutex::new(release);
+
+    let results: Vec<Result<(), String>> = thread::scope(|scope| {
+        let contenders: Vec<_> = (0..2)
+            .map(|_| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    let lock = BusLock::acquire(&channel).map_err(|e| e.to_string())?;
+                    assert_eq!(BusLock::owner(&channel), Some(std::process::id()));
+                    // Hold the lock until the other thread has tried
+                    release.lock().unwrap().recv_timeout(Duration::from_secs(5)).ok();
+                    drop(lock);
+                    Ok(())
+                })
+            })
+            .collect();
+        // The loser returns without waiting; then let the winner go
+        while !contenders.iter().any(|c| c.is_finished()) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        release_tx.send(()).unwrap();
+        contenders.into_iter().map(|c| c.join().unwrap()).collect()
+    });
+
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1, "{:?}", results);
+    let error = results.into_iter().find_map(Result::err).unwrap();
+    let expected = format!("CAN channel {} is already in use by PID {}", channel, std::process::id());
+    assert!(error.starts_with(&expected), "{}", error);
+    assert!(error.ends_with("use --force to override"), "{}", error);
+
+    // Released by the winner's drop
+    assert_eq!(BusLock::owner(&channel), None);
+    BusLock::acquire(&channel).unwrap();
+}
+
+#[test]
+fn a_held_lock_is_handed_over_when_dropped() {
+    let channel = channel("handover");
+    let lock = BusLock::acquire(&channel).unwrap();
+    assert_eq!(lock.path(), BusLock::path_for(&channel));
+
+    let waiter = thread::scope(|scope| {
+        let waiter = scope.spawn(|| {
+            assert!(BusLock::acquire_or_force(&channel, false).is_err());
+            // Forcing continues without the lock
+            assert!(BusLock::acquire_or_force(&channel, true).unwrap().is_none());
+            loop {
+                if let Ok(lock) = BusLock::acquire(&channel) {
+                    return lock;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+        });
+        thread::sleep(Duration::from_millis(50));
+        drop(lock);
+        waiter.join().unwrap()
+    });
+
+    assert_eq!(BusLock::owner(&channel), Some(std::process::id()));
+    drop(waiter);
+    assert_eq!(BusLock::owner(&channel), None);
+}
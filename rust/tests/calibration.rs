@@ -0,0 +1,74 @@
+//! Joint zero offset, direction and gear ratio applied to commands and feedback
+
+use livelybot_motor_control::cli::ToolConfig;
+use livelybot_motor_control::{
+    degrees_to_position, nm_to_torque, rps_to_velocity, Calibration, LivelyMotorController, MockTransport, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+/// Wait until the simulated motor has come to rest at `motor_deg`
+fn settle(mock: &MockTransport, motor_id: u8, motor_deg: f64) {
+    let settled = || {
+        let state = mock.state(motor_id).unwrap();
+        (state.position_deg - motor_deg).abs() < 0.2 && state.velocity_rps.abs() < 0.002
+    };
+    for _ in 0..300 {
+        if settled() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("motor {} did not settle at {}: {:?}", motor_id, motor_deg, mock.state(motor_id));
+}
+
+#[test]
+fn commands_and_feedback_are_in_joint_angles() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default()).with_motor(2, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    controller.set_calibration(1, Calibration::new(12.5, true)).unwrap();
+    controller.enable_motor(1).unwrap();
+
+    controller.send_angle_command_to(1, degrees_to_position(30.0), rps_to_velocity(5.0), nm_to_torque(3.0)).unwrap();
+    settle(&mock, 1, -17.5);
+    let state = controller.read_motor_state(1).unwrap();
+    assert!((state.position_deg - 30.0).abs() < 0.2, "{:?}", state);
+
+    // A broadcast can't honour per-motor calibrations
+    assert!(controller.send_angle_command(0, 0, 0).is_err());
+    controller.clear_calibration(1);
+    assert!(controller.send_angle_command(0, 0, 0).is_ok());
+}
+
+#[test]
+fn geared_joints_are_commanded_at_the_output_shaft() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    controller.set_calibration(1, Calibration::new(0.0, false).with_gear_ratio(4.0)).unwrap();
+    controller.enable_motor(1).unwrap();
+
+    controller.send_angle_command_to(1, degrees_to_position(10.0), rps_to_velocity(2.0), nm_to_torque(12.0)).unwrap();
+    settle(&mock, 1, 40.0);
+    let state = controller.read_motor_state(1).unwrap();
+    assert!((state.position_deg - 10.0).abs() < 0.1, "{:?}", state);
+
+    let calibration = controller.calibration(1).unwrap();
+    assert_eq!(calibration.to_motor_velocity(1.5), 6.0);
+    assert_eq!(calibration.to_joint_torque(0.5), 2.0);
+    assert_eq!(calibration.to_motor_gain(16.0), 1.0);
+    assert!(controller.set_calibration(1, Calibration::default().with_gear_ratio(0.0)).is_err());
+}
+
+#[test]
+fn calibration_is_read_from_the_tool_config() {
+    let path = std::env::temp_dir().join(format!("livelybot-calibration-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "calibration": { "2": { "zero_offset_deg": -90, "inverted": true }, "3": { "gear_ratio": 2.5 } } }"#).unwrap();
+    let config = ToolConfig::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(config.calibration[&2], Calibration::new(-90.0, true));
+    assert_eq!(config.calibration[&3], Calibration::default().with_gear_ratio(2.5));
+
+    let calibration = config.calibration[&2];
+    assert_eq!(calibration.to_motor_deg(10.0), -100.0);
+    assert_eq!(calibration.to_joint_deg(-100.0), 10.0);
+}
@@ -0,0 +1,66 @@
+//! candump log parsing and replay
+
+use livelybot_motor_control::{CandumpLog, LivelyMotorController, Register};
+use socketcan::EmbeddedFrame;
+use std::time::{Duration, Instant};
+
+#[test]
+fn log_and_screen_formats_are_parsed() {
+    let log = CandumpLog::parse(
+        "# captured on the test stand\n\
+         (1700000000.000100) can0 00000300#2D230000C03F\n\
+         (1700000000.250100) can0 123#R\n\
+         \n\
+         (1700000000.500100)  can1  7FF   [3]  01 02 03\n",
+    )
+    .unwrap();
+    assert_eq!(log.records.len(), 3);
+    assert_eq!(log.interface(), Some("can0"));
+    assert_eq!(log.duration(), Duration::from_millis(500));
+
+    let reply = &log.records[0].frame;
+    assert!(reply.is_extended());
+    assert_eq!(reply.data(), &[0x2D, 0x23, 0x00, 0x00, 0xC0, 0x3F]);
+    assert!(log.records[1].frame.is_remote_frame());
+    assert_eq!(log.records[2].frame.data(), &[1, 2, 3]);
+
+    // Written back in candump -l format and read again unchanged
+    let text = log.to_text();
+    assert!(text.starts_with("(1700000000.000100) can0 00000300#2D230000C03F\n"));
+    assert_eq!(CandumpLog::parse(&text).unwrap().to_text(), text);
+
+    assert_eq!(log.on_interface("can1").records.len(), 1);
+    assert!(CandumpLog::parse("(1.0) can0 123##1AABB").is_err());
+    assert!(CandumpLog::parse("can0 123#00").is_err());
+}
+
+#[test]
+fn controller_replays_with_original_timing() {
+    // Kp reply of motor 3 right away, a second one 200 ms later
+    let path = std::env::temp_dir().join(format!("livelybot-replay-{}.log", std::process::id()));
+    std::fs::write(
+        &path,
+        "(1700000000.000000) can0 00000300#2D230000C03F\n\
+         (1700000000.200000) can0 00000300#2D2300002040\n",
+    )
+    .unwrap();
+    let start = Instant::now();
+    let controller = LivelyMotorController::replay(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(controller.bus().is_replay());
+    assert_eq!(controller.channel(), "can0");
+
+    assert_eq!(controller.read_register_float(3, Register::Kp).unwrap(), 1.5);
+    assert!(!controller.bus().replay_finished());
+
+    let rx = controller.bus().subscribe();
+    let frame = loop {
+        if let Some(frame) = rx.recv_timeout(Duration::from_millis(50)).unwrap() {
+            break frame;
+        }
+        assert!(start.elapsed() < Duration::from_secs(2), "second frame never replayed");
+    };
+    assert!(start.elapsed() >= Duration::from_millis(190));
+    assert_eq!(&frame.data()[2..], &[0x00, 0x00, 0x20, 0x40]);
+    assert!(controller.bus().replay_finished());
+}
@@ -0,0 +1,29 @@
+//! Firmware feature detection results
+
+use livelybot_motor_control::{Capabilities, ErrorContext, Feature, MotorError};
+use std::collections::BTreeSet;
+
+#[test]
+fn missing_features_are_reported_as_unsupported() {
+    let capabilities = Capabilities {
+        motor_id: 3,
+        firmware: "v1.2".to_string(),
+        features: BTreeSet::from([Feature::SerialNumber, Feature::Uptime, Feature::Gpio]),
+    };
+    assert!(capabilities.supports(Feature::Gpio));
+    assert_eq!(
+        capabilities.missing(),
+        vec![Feature::ErrorLog, Feature::Temperatures, Feature::FeedbackPeriod, Feature::ReportContent]
+    );
+
+    let json = serde_json::to_string(&capabilities).unwrap();
+    assert!(json.contains(r#""features":["serial_number","uptime","gpio"]"#));
+
+    let error = MotorError::Unsupported {
+        motor_id: 3,
+        feature: Feature::ErrorLog,
+        context: ErrorContext::default(),
+    };
+    assert_eq!(error.motor_id(), Some(3));
+    assert_eq!(error.to_string(), "the firmware of motor 3 does not support error_log");
+}
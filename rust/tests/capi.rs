@@ -0,0 +1,21 @@
+//! C API error reporting (feature `capi`)
+#![cfg(feature = "capi")]
+
+use livelybot_motor_control::capi::*;
+use std::ffi::CStr;
+use std::ptr;
+
+#[test]
+fn failures_return_null_or_minus_one_with_a_message() {
+    unsafe {
+        let controller = lmc_new(c"lmc_missing0".as_ptr(), 1_000_000);
+        assert!(controller.is_null());
+        assert!(!CStr::from_ptr(lmc_last_error()).to_bytes().is_empty());
+
+        assert_eq!(lmc_enable(ptr::null(), 1), -1);
+        assert_eq!(CStr::from_ptr(lmc_last_error()).to_str().unwrap(), "controller is null");
+        let mut state = LmcMotorState::default();
+        assert_eq!(lmc_read_state(ptr::null(), 1, &mut state), -1);
+        lmc_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,33 @@
+//! Tool configuration shared by the command line tools
+
+use livelybot_motor_control::cli::{BusArgs, ToolConfig};
+use livelybot_motor_control::LimitMode;
+
+#[test]
+fn flags_override_the_config_file() {
+    let path = std::env::temp_dir().join(format!("livelybot-tool-config-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{ "interface": "can1", "reserve_bandwidth": 0.2,
+             "limits": { "3": { "min_pos_deg": -90, "max_pos_deg": 90, "mode": "reject" } } }"#,
+    )
+    .unwrap();
+
+    let file = ToolConfig::load(&path).unwrap();
+    assert_eq!((file.interface.as_str(), file.bitrate), ("can1", 1_000_000));
+    assert_eq!(file.limits[&3].max_pos_deg, Some(90.0));
+    assert_eq!(file.limits[&3].mode, LimitMode::Reject);
+
+    let bus = BusArgs {
+        interface: Some("vcan0".to_string()),
+        config: Some(path.clone()),
+        ..Default::default()
+    };
+    let config = bus.config().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(config.interface, "vcan0");
+    assert_eq!(config.reserve_bandwidth, 0.2);
+    assert_eq!(config.limits, file.limits);
+
+    assert_eq!(BusArgs::default().config().unwrap(), ToolConfig::default());
+}
@@ -0,0 +1,91 @@
+//! Robot description files in TOML and YAML
+
+use livelybot_motor_control::cli::ToolConfig;
+use livelybot_motor_control::tuning::{self, TunableParam};
+use livelybot_motor_control::{LivelyMotorController, MockTransport, RobotConfig, SimMotor};
+
+const TOML: &str = r#"
+[bus]
+interface = "can0"
+bitrate = 1000000
+
+[[joints]]
+name = "hip"
+motor_id = 1
+kp = 2.5
+kd = 0.3
+torque_limit = 4.0
+limits = { min_pos_deg = -45.0, max_pos_deg = 45.0 }
+
+[[joints]]
+name = "knee"
+motor_id = 2
+gear_ratio = 2.0
+reversed = true
+zero_offset_deg = 10.0
+
+[[joints]]
+name = "ankle"
+motor_id = 1
+interface = "can1"
+"#;
+
+const YAML: &str = r#"
+bus:
+  interface: can0
+  bitrate: 1000000
+joints:
+  - { name: hip, motor_id: 1, kp: 2.5, kd: 0.3, torque_limit: 4.0, limits: { min_pos_deg: -45, max_pos_deg: 45 } }
+  - { name: knee, motor_id: 2, gear_ratio: 2.0, reversed: true, zero_offset_deg: 10.0 }
+  - { name: ankle, motor_id: 1, interface: can1 }
+"#;
+
+fn write(extension: &str, text: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("livelybot-robot-{}.{}", std::process::id(), extension));
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+#[test]
+fn toml_and_yaml_describe_the_same_robot() {
+    let (toml, yaml) = (write("toml", TOML), write("yaml", YAML));
+    let robot = RobotConfig::load(&toml).unwrap();
+    assert_eq!(RobotConfig::load(&yaml).unwrap(), robot);
+    let tool = ToolConfig::load(&yaml).unwrap();
+    std::fs::remove_file(&toml).unwrap();
+    std::fs::remove_file(&yaml).unwrap();
+
+    assert_eq!(robot.channels(), vec!["can0", "can1"]);
+    assert_eq!(robot.motor_ids_on("can0"), vec![1, 2]);
+    let knee = robot.joint("knee").unwrap();
+    assert_eq!(knee.to_motor_deg(30.0), -50.0);
+    assert_eq!(knee.to_joint_deg(-50.0), 30.0);
+
+    assert_eq!(tool.interface, "can0");
+    assert_eq!(tool.limits[&1].max_pos_deg, Some(45.0));
+    assert_eq!(tool.limits_on("can1"), Default::default());
+
+    let mut duplicate = robot.clone();
+    duplicate.joints[2].interface = None;
+    assert!(duplicate.validate().is_err());
+}
+
+#[test]
+fn group_enables_each_joint_with_its_gains() {
+    let path = write("yml", YAML);
+    let robot = RobotConfig::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mock = MockTransport::new().with_motor(1, SimMotor::default()).with_motor(2, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("can0", mock);
+    let group = robot.group(&controller).unwrap();
+    assert_eq!(group.motor_ids(), &[1, 2]);
+    assert_eq!(controller.limits(1).unwrap().min_pos_deg, Some(-45.0));
+    assert!(controller.limits(2).is_none());
+    assert_eq!(controller.calibration(2).unwrap().gear_ratio, 2.0);
+
+    group.enable_all().unwrap();
+    assert_eq!(tuning::read_param(&controller, 1, TunableParam::Kp).unwrap(), 2.5);
+    assert_eq!(tuning::read_param(&controller, 1, TunableParam::TorqueLimit).unwrap(), 4.0);
+    assert_eq!(tuning::read_param(&controller, 2, TunableParam::Kp).unwrap(), 1.0);
+}
@@ -0,0 +1,91 @@
+//! Fingerprints of the effective configuration, embedded in recordings
+
+use livelybot_motor_control::audit::AuditParameters;
+use livelybot_motor_control::protocol::{Register, ValueType};
+use livelybot_motor_control::{
+    ConfigFingerprint, LivelyMotorController, MockTransport, Recorder, SessionMetadata, SimMotor,
+};
+use serde_json::json;
+
+fn setup() -> LivelyMotorController {
+    LivelyMotorController::with_transport("mock", MockTransport::new().with_motor(1, SimMotor::default()))
+}
+
+/// Gains as read back from the motor, not as configured
+fn gains(controller: &LivelyMotorController, motor_id: u8) -> AuditParameters {
+    let read = |reg| controller.read_registers(motor_id, reg, ValueType::Float, 1).unwrap().float(0).unwrap();
+    AuditParameters {
+        kp: read(Register::Kp),
+        kd: read(Register::Kd),
+        torque_limit: read(Register::TorqueLimit),
+    }
+}
+
+fn fingerprint(controller: &LivelyMotorController) -> ConfigFingerprint {
+    ConfigFingerprint::new()
+        .with("bus", &("can0", 1_000_000, 0.0))
+        .unwrap()
+        .with("gains", &gains(controller, 1))
+        .unwrap()
+}
+
+#[test]
+fn equal_configurations_hash_alike() {
+    let controller = setup();
+    let config = fingerprint(&controller);
+    assert_eq!(config.parts(), ["bus", "gains"]);
+    let hash = config.hash();
+    assert_eq!(hash.len(), 16);
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit()), "{}", hash);
+
+    // Read again, and from another motor set up the same way
+    assert_eq!(fingerprint(&controller).hash(), hash);
+    assert_eq!(fingerprint(&setup()).hash(), hash);
+
+    // Neither the part order nor the key order of a part matters
+    let reordered = ConfigFingerprint::new()
+        .with("gains", &gains(&controller, 1))
+        .unwrap()
+        .with("bus", &("can0", 1_000_000, 0.0))
+        .unwrap();
+    assert_eq!(reordered, config);
+    assert_eq!(reordered.hash(), hash);
+    let sine = |value: serde_json::Value| ConfigFingerprint::new().with("sine", &value).unwrap().hash();
+    assert_eq!(
+        sine(json!({ "amplitude": 10.0, "frequency": 0.5 })),
+        sine(serde_json::from_str(r#"{ "frequency": 0.5, "amplitude": 10.0 }"#).unwrap())
+    );
+}
+
+#[test]
+fn one_register_changes_the_hash() {
+    let controller = setup();
+    let before = fingerprint(&controller);
+    controller.write_register_float(1, Register::Kd, gains(&controller, 1).kd + 0.01).unwrap();
+    let after = fingerprint(&controller);
+    assert_ne!(after.hash(), before.hash());
+    assert_ne!(after.canonical_json(), before.canonical_json());
+
+    // A part added under an existing name replaces it
+    let mut replaced = after.clone();
+    replaced.add("gains", &gains(&setup(), 1)).unwrap();
+    assert_eq!(replaced.hash(), before.hash());
+    let mut extended = before.clone();
+    extended.add("motor_id", &1).unwrap();
+    assert_ne!(extended.hash(), before.hash());
+}
+
+#[test]
+fn recordings_carry_the_hash() {
+    let config = fingerprint(&setup());
+    let metadata = SessionMetadata::default().with_robot("biped-3").with_config(&config);
+    assert_eq!(metadata.config_hash.as_deref(), Some(config.hash().as_str()));
+    assert!(metadata.properties().contains(&("config_hash".to_string(), config.hash())));
+
+    let recorder = Recorder::new(&[1]).with_metadata(metadata);
+    let path = std::env::temp_dir().join(format!("livelybot-config-hash-{}.csv", std::process::id()));
+    recorder.write_csv(&path).unwrap();
+    let loaded = Recorder::read_csv(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.metadata().config_hash, Some(config.hash()));
+}
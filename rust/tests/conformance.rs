@@ -0,0 +1,149 @@
+//! Golden frame vectors taken from the reference Python and C++ tools
+//! (`tests/vectors/conformance.json`); the crate's encoders must produce the
+//! same arbitration IDs and bytes
+//!
+//! The tools truncate fractional counts towards zero, the crate rounds them to
+//! the nearest count; vectors marked `rounded` hold the rounded bytes.
+
+use livelybot_motor_control::protocol::{ANGLE_STREAM_ID, REPLY_FLAG, VELOCITY_STREAM_ID};
+use livelybot_motor_control::{
+    degrees_to_position, nm_to_torque, rps2_to_acceleration, rps_to_velocity, EncodingPolicy, Register, ValueType,
+    FACTOR_ACC, FACTOR_POS, FACTOR_TQE, FACTOR_VEL,
+};
+use serde::Deserialize;
+
+/// Position value that selects pure velocity control in the 0xAD stream
+const VELOCITY_MODE_POSITION: i16 = i16::MIN;
+
+#[derive(Deserialize)]
+struct Vector {
+    name: String,
+    source: String,
+    frame: Frame,
+    id: String,
+    data: String,
+    /// `data` differs from the tools' by rounding instead of truncating
+    #[serde(default)]
+    rounded: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Frame {
+    WriteInt8 { motor_id: u8, register: String, value: i8 },
+    WriteFloat { motor_id: u8, register: String, value: f32 },
+    Read { motor_id: u8, register: String, value_type: String, count: u8 },
+    AngleStream { angle_deg: f64, max_vel_rps: f64, max_torque_nm: f64 },
+    VelocityStream { velocity_rps: f64, acceleration_rps2: f64 },
+}
+
+fn vectors() -> Vec<Vector> {
+    serde_json::from_str(include_str!("vectors/conformance.json")).unwrap()
+}
+
+fn register(name: &str) -> Register {
+    Register::ALL
+        .into_iter()
+        .find(|r| r.info().name == name)
+        .unwrap_or_else(|| panic!("unknown register {}", name))
+}
+
+fn value_type(name: &str) -> ValueType {
+    [ValueType::Int8, ValueType::Int16, ValueType::Int32, ValueType::Float]
+        .into_iter()
+        .find(|t| t.name() == name)
+        .unwrap_or_else(|| panic!("unknown value type {}", name))
+}
+
+fn parse_hex(text: &str) -> Vec<u8> {
+    text.split_whitespace().map(|b| u8::from_str_radix(b, 16).unwrap()).collect()
+}
+
+fn encode(policy: &EncodingPolicy, frame: &Frame) -> (u32, [u8; 8]) {
+    match frame {
+        Frame::WriteInt8 { motor_id, register: reg, value } => {
+            (*motor_id as u32, policy.write_int8(register(reg), *value))
+        }
+        Frame::WriteFloat { motor_id, register: reg, value } => {
+            (*motor_id as u32, policy.write_float(register(reg), *value))
+        }
+        Frame::Read { motor_id, register: reg, value_type: ty, count } => {
+            (REPLY_FLAG | *motor_id as u32, policy.read(register(reg), value_type(ty), *count))
+        }
+        Frame::AngleStream { angle_deg, max_vel_rps, max_torque_nm } => (
+            ANGLE_STREAM_ID,
+            policy.angle_stream(
+                degrees_to_position(*angle_deg),
+                rps_to_velocity(*max_vel_rps),
+                nm_to_torque(*max_torque_nm),
+            ),
+        ),
+        Frame::VelocityStream { velocity_rps, acceleration_rps2 } => (
+            VELOCITY_STREAM_ID,
+            policy.velocity_stream(
+                VELOCITY_MODE_POSITION,
+                rps_to_velocity(*velocity_rps),
+                rps2_to_acceleration(*acceleration_rps2),
+            ),
+        ),
+    }
+}
+
+#[test]
+fn vectors_cover_every_frame_kind() {
+    let vectors = vectors();
+    assert!(vectors.iter().any(|v| matches!(v.frame, Frame::WriteInt8 { .. })));
+    assert!(vectors.iter().any(|v| matches!(v.frame, Frame::WriteFloat { .. })));
+    assert!(vectors.iter().any(|v| matches!(v.frame, Frame::Read { .. })));
+    assert!(vectors.iter().any(|v| matches!(v.frame, Frame::AngleStream { .. })));
+    assert!(vectors.iter().any(|v| matches!(v.frame, Frame::VelocityStream { .. })));
+}
+
+/// Whether `value` lands between two counts, where rounding and truncation differ
+fn is_fractional(value: f64, factor: f64) -> bool {
+    (value * factor).fract() != 0.0
+}
+
+#[test]
+fn stream_vectors_include_fractional_counts() {
+    let vectors = vectors();
+    assert!(vectors.iter().any(|v| matches!(
+        v.frame,
+        Frame::AngleStream { angle_deg, max_vel_rps, max_torque_nm }
+            if is_fractional(angle_deg / 360.0, FACTOR_POS) && is_fractional(max_vel_rps, FACTOR_VEL)
+                && is_fractional(max_torque_nm, FACTOR_TQE) && angle_deg < 0.0
+    )));
+    assert!(vectors.iter().any(|v| matches!(
+        v.frame,
+        Frame::VelocityStream { velocity_rps, acceleration_rps2 }
+            if is_fractional(velocity_rps, FACTOR_VEL) && is_fractional(acceleration_rps2, FACTOR_ACC)
+                && velocity_rps < 0.0
+    )));
+    // Only the unit encoders of the streams round
+    assert!(vectors
+        .iter()
+        .filter(|v| v.rounded)
+        .all(|v| matches!(v.frame, Frame::AngleStream { .. } | Frame::VelocityStream { .. })));
+}
+
+#[test]
+fn encoders_match_reference_bytes() {
+    let policy = EncodingPolicy::default();
+    for vector in vectors() {
+        let (id, data) = encode(&policy, &vector.frame);
+        let expected_id = u32::from_str_radix(vector.id.trim_start_matches("0x"), 16).unwrap();
+        assert_eq!(id, expected_id, "{} ({}): arbitration ID", vector.name, vector.source);
+        assert_eq!(data.to_vec(), parse_hex(&vector.data), "{} ({}): payload", vector.name, vector.source);
+    }
+}
+
+#[test]
+fn reference_frames_pass_strict_validation() {
+    let policy = EncodingPolicy::new(0x50, true);
+    for vector in vectors() {
+        let (id, data) = encode(&policy, &vector.frame);
+        policy
+            .validate(id, &data)
+            .unwrap_or_else(|e| panic!("{} ({}): {}", vector.name, vector.source, e));
+    }
+}
@@ -0,0 +1,106 @@
+//! Console parsing and dispatch into a running control loop
+
+use livelybot_motor_control::{Console, ConsoleCommand, ConsoleInput, ControlLoop};
+use std::io::Cursor;
+use std::sync::atomic::AtomicBool;
+
+fn parse(line: &str) -> Option<ConsoleCommand> {
+    ConsoleCommand::parse(line).unwrap()
+}
+
+fn error(line: &str) -> String {
+    ConsoleCommand::parse(line).unwrap_err().to_string()
+}
+
+#[test]
+fn lines_parse_into_commands() {
+    assert_eq!(parse("90"), Some(ConsoleCommand::Target(90.0)));
+    assert_eq!(parse("  -2.5\n"), Some(ConsoleCommand::Target(-2.5)));
+    assert_eq!(
+        parse("KP 2.0"),
+        Some(ConsoleCommand::Set {
+            name: "kp".to_string(),
+            value: 2.0
+        })
+    );
+    for (words, command) in [
+        (["q", "quit", "exit"], ConsoleCommand::Quit),
+        (["p", "pause", "PAUSE"], ConsoleCommand::Pause),
+        (["r", "resume", "Resume"], ConsoleCommand::Resume),
+    ] {
+        for word in words {
+            assert_eq!(parse(word), Some(command.clone()), "{}", word);
+        }
+    }
+    assert_eq!(parse(""), None);
+    assert_eq!(parse(" \t\r\n"), None);
+
+    assert_eq!(error("fast"), "unknown command 'fast'");
+    assert_eq!(error("kp stiff"), "'stiff' is not a number");
+    assert_eq!(error("kp 2 3"), "unexpected '3'");
+    assert_eq!(error("q now"), "'now' is not a number");
+}
+
+/// What a loop driven by the console did
+#[derive(Debug, Default, PartialEq)]
+struct Dispatched {
+    targets: Vec<f64>,
+    kp: f64,
+    ignored: Vec<f64>,
+    errors: Vec<String>,
+    cycles: u64,
+}
+
+/// Dispatch console inputs once per cycle, the way the interactive tools do
+fn drive(console: &Console) -> Dispatched {
+    let mut dispatched = Dispatched::default();
+    let mut paused = false;
+    ControlLoop::new(200.0)
+        .run(&AtomicBool::new(true), |info| {
+            dispatched.cycles = info.cycle + 1;
+            for input in console.poll() {
+                match input {
+                    Ok(ConsoleCommand::Quit) => return Ok(false),
+                    Ok(ConsoleCommand::Target(target)) if paused => dispatched.ignored.push(target),
+                    Ok(ConsoleCommand::Target(target)) => dispatched.targets.push(target),
+                    Ok(ConsoleCommand::Set { name, value }) if name == "kp" => dispatched.kp = value,
+                    Ok(ConsoleCommand::Set { name, .. }) => dispatched.errors.push(format!("unknown parameter {}", name)),
+                    Ok(ConsoleCommand::Pause) => paused = true,
+                    Ok(ConsoleCommand::Resume) => paused = false,
+                    Err(e) => dispatched.errors.push(e),
+                }
+            }
+            // Safety net should the quit never arrive
+            Ok(info.cycle < 1000)
+        })
+        .unwrap();
+    dispatched
+}
+
+#[test]
+fn a_script_drives_the_loop_until_it_quits() {
+    let script = "90\nkp 2.0\n\npause\n45\nresume\n-30\nfast\nkd 0.1\nq\n-90\n";
+    let dispatched = drive(&Console::reader(Cursor::new(script)));
+
+    assert_eq!(dispatched.targets, [90.0, -30.0]);
+    assert_eq!(dispatched.ignored, [45.0]);
+    assert_eq!(dispatched.kp, 2.0);
+    assert_eq!(dispatched.errors, ["unknown command 'fast'", "unknown parameter kd"]);
+    // Everything was read in the first cycles, not at the safety net
+    assert!(dispatched.cycles < 100, "{}", dispatched.cycles);
+}
+
+#[test]
+fn the_end_of_the_input_quits() {
+    let dispatched = drive(&Console::reader(Cursor::new("10\n20")));
+    assert_eq!(dispatched.targets, [10.0, 20.0]);
+    assert!(dispatched.cycles < 100, "{}", dispatched.cycles);
+
+    let (tx, console) = Console::channel();
+    tx.send(Ok(ConsoleCommand::Target(5.0))).unwrap();
+    tx.send(Err("typo".to_string())).unwrap();
+    assert_eq!(console.poll(), [Ok(ConsoleCommand::Target(5.0)), Err("typo".to_string())]);
+    assert_eq!(console.poll(), Vec::<ConsoleInput>::new());
+    drop(tx);
+    assert_eq!(console.poll(), [Ok(ConsoleCommand::Quit)]);
+}
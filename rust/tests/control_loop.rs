@@ -0,0 +1,95 @@
+//! Timeline, achieved period and jitter of the control loop
+
+use livelybot_motor_control::ControlLoop;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn achieved_period_is_measured() {
+    let running = AtomicBool::new(true);
+    let mut control = ControlLoop::new(500.0);
+    let start = Instant::now();
+    control.run(&running, |info| Ok(info.cycle < 99)).unwrap();
+    let elapsed = start.elapsed();
+
+    let jitter = control.jitter();
+    assert_eq!(jitter.nominal_period, Duration::from_millis(2));
+    // Waking more than a period late (a loaded test machine) skips a slot, and
+    // the period across the gap isn't sampled
+    assert!(jitter.periods <= 99 && jitter.periods >= 90, "{}", jitter);
+    // The timeline is absolute: 100 cycles end 99 periods after the start
+    assert!(elapsed >= Duration::from_millis(198), "{:?}", elapsed);
+    let mean = jitter.mean_period().unwrap();
+    assert!(mean > Duration::from_micros(1800) && mean < Duration::from_micros(2500), "{}", jitter);
+    assert!(jitter.min_period <= mean && mean <= jitter.max_period, "{}", jitter);
+    assert!(jitter.jitter().unwrap() <= jitter.max_jitter().unwrap(), "{}", jitter);
+}
+
+#[test]
+fn slow_steps_do_not_shift_the_timeline() {
+    let running = AtomicBool::new(true);
+    let mut control = ControlLoop::with_period(Duration::from_millis(10));
+    control
+        .run(&running, |info| {
+            // Busy for most of the period; the wake-up must not drift by it
+            thread::sleep(Duration::from_millis(6));
+            Ok(info.cycle < 9)
+        })
+        .unwrap();
+
+    let jitter = control.jitter();
+    assert_eq!(jitter.periods, 9);
+    let mean = jitter.mean_period().unwrap();
+    assert!(mean < Duration::from_millis(13), "{}", jitter);
+    assert!(jitter.mean_latency().unwrap() < Duration::from_millis(5), "{}", jitter);
+}
+
+#[test]
+fn the_first_cycle_is_aligned_to_the_start_instant() {
+    let running = AtomicBool::new(true);
+    let period = Duration::from_millis(5);
+    let start = Instant::now() + Duration::from_millis(50);
+    let mut control = ControlLoop::with_period(period).with_start(start);
+    let mut cycles = Vec::new();
+    control
+        .run(&running, |info| {
+            cycles.push((info.cycle, info.elapsed, info.deadline, Instant::now()));
+            Ok(info.cycle < 4)
+        })
+        .unwrap();
+
+    let (cycle, elapsed, deadline, woke) = cycles[0];
+    assert_eq!((cycle, elapsed, deadline), (0, Duration::ZERO, start));
+    assert!(woke >= start, "woke {:?} before the start", start - woke);
+    // Every later cycle is scheduled on the same timeline; a loaded machine
+    // may skip slots but never shifts them
+    for &(cycle, elapsed, deadline, woke) in &cycles {
+        assert_eq!(deadline, start + elapsed);
+        assert_eq!(elapsed.as_nanos() % period.as_nanos(), 0, "{:?}", elapsed);
+        assert!(elapsed >= period * cycle as u32);
+        assert!(woke >= deadline);
+    }
+}
+
+#[test]
+fn a_start_in_the_past_skips_the_cycles_that_are_over() {
+    let running = AtomicBool::new(true);
+    let period = Duration::from_millis(10);
+    let start = Instant::now() - Duration::from_millis(55);
+    let mut control = ControlLoop::with_period(period).with_start(start);
+    let mut first = None;
+    control
+        .run(&running, |info| {
+            first.get_or_insert(*info);
+            Ok(false)
+        })
+        .unwrap();
+
+    // The cycle in progress at the call runs at once, at its slot on the timeline
+    let first = first.unwrap();
+    assert_eq!(first.cycle, 0);
+    assert!(first.elapsed >= Duration::from_millis(50), "{:?}", first.elapsed);
+    assert_eq!(first.elapsed.as_nanos() % period.as_nanos(), 0, "{:?}", first.elapsed);
+    assert_eq!(first.deadline, start + first.elapsed);
+}
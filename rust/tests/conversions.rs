@@ -0,0 +1,106 @@
+//! Round-trip checks between the unit encoders and the feedback decoders
+
+use livelybot_motor_control::{
+    acceleration_to_rps2, conversions, degrees_to_position, nm_to_torque, position_to_degrees,
+    rps2_to_acceleration, rps_to_velocity, torque_to_nm, velocity_to_rps, Float, JointMapping, Real, Trajectory,
+    FACTOR_ACC, FACTOR_POS, FACTOR_TQE, FACTOR_VEL,
+};
+use std::time::Duration;
+
+/// Encoding rounds to the nearest count, so a decoded count always encodes
+/// back to itself
+fn assert_count_kept(encoded: i16, counts: i16) {
+    assert_eq!(encoded, counts, "{} encoded back as {}", counts, encoded);
+}
+
+#[test]
+fn counts_round_trip_exactly() {
+    for counts in (i16::MIN..=i16::MAX).step_by(7) {
+        assert_count_kept(degrees_to_position(position_to_degrees(counts)), counts);
+        assert_count_kept(rps_to_velocity(velocity_to_rps(counts)), counts);
+        assert_count_kept(rps2_to_acceleration(acceleration_to_rps2(counts)), counts);
+        assert_count_kept(nm_to_torque(torque_to_nm(counts)), counts);
+    }
+}
+
+/// Half a count, plus the precision of `Real` for values that land on a half
+fn assert_within_half_a_count(decoded: f64, original: f64, count: f64) {
+    let tolerance = count / 2.0 + original.abs() * Real::EPSILON.to_f64();
+    assert!((decoded - original).abs() <= tolerance, "{} decoded as {}", original, decoded);
+}
+
+#[test]
+fn units_round_trip_within_half_a_count() {
+    // Sweep each quantity across its representable range
+    let max = i16::MAX as f64;
+    for i in -999..=999 {
+        let f = i as f64 / 1000.0;
+
+        let deg = f * max / FACTOR_POS * 360.0;
+        let vel = f * max / FACTOR_VEL;
+        let acc = f * max / FACTOR_ACC;
+        let tqe = f * max / FACTOR_TQE;
+
+        assert_within_half_a_count(position_to_degrees(degrees_to_position(deg)), deg, 360.0 / FACTOR_POS);
+        assert_within_half_a_count(velocity_to_rps(rps_to_velocity(vel)), vel, 1.0 / FACTOR_VEL);
+        assert_within_half_a_count(acceleration_to_rps2(rps2_to_acceleration(acc)), acc, 1.0 / FACTOR_ACC);
+        assert_within_half_a_count(torque_to_nm(nm_to_torque(tqe)), tqe, 1.0 / FACTOR_TQE);
+    }
+}
+
+#[test]
+fn fractional_counts_round_to_nearest() {
+    assert_eq!(degrees_to_position(10.0), 278);
+    assert_eq!(degrees_to_position(-10.0), -278);
+    assert_eq!(rps_to_velocity(0.3), 1200);
+    assert_eq!(rps_to_velocity(-0.0003), -1);
+    assert_eq!(rps_to_velocity(0.0001), 0);
+    // Halves round away from zero
+    assert_eq!(rps2_to_acceleration(1.2345), 1235);
+    assert_eq!(nm_to_torque(0.123), 25);
+    assert_eq!(nm_to_torque(-0.123), -25);
+    assert_eq!(conversions::degrees_to_position(10.0f32), 278);
+}
+
+#[test]
+fn encoders_saturate_and_decoders_cover_full_range() {
+    assert_eq!(degrees_to_position(1.0e6), i16::MAX);
+    assert_eq!(nm_to_torque(-1.0e6), i16::MIN);
+    // Decoded in `Real`, so only as exact as f32 with the `f32` feature
+    let close = |decoded: f64, expected: f64| (decoded - expected).abs() <= expected.abs() * Real::EPSILON.to_f64();
+    assert!(close(position_to_degrees(i16::MAX), i16::MAX as f64 / FACTOR_POS * 360.0));
+    assert!(close(torque_to_nm(i16::MIN), i16::MIN as f64 / FACTOR_TQE));
+}
+
+#[test]
+fn f32_conversions_round_trip_exactly() {
+    for counts in (i16::MIN..=i16::MAX).step_by(7) {
+        assert_count_kept(conversions::degrees_to_position(conversions::position_to_degrees::<f32>(counts)), counts);
+        assert_count_kept(conversions::rps_to_velocity(conversions::velocity_to_rps::<f32>(counts)), counts);
+        assert_count_kept(conversions::rps2_to_acceleration(conversions::acceleration_to_rps2::<f32>(counts)), counts);
+        assert_count_kept(conversions::nm_to_torque(conversions::torque_to_nm::<f32>(counts)), counts);
+    }
+}
+
+#[test]
+fn f32_and_f64_encode_alike() {
+    for i in -999..=999 {
+        let deg = i as f64 * 1.17;
+        assert!((conversions::degrees_to_position(deg as f32) as i32 - degrees_to_position(deg) as i32).abs() <= 1);
+    }
+    assert_eq!(conversions::degrees_to_position(f32::MAX), i16::MAX);
+    assert_eq!(conversions::nm_to_torque(-1.0e6f32), i16::MIN);
+}
+
+#[test]
+fn trajectories_sample_in_either_float_type() {
+    let mapping: JointMapping = serde_json::from_str(r#"{"joints": [{"name": "hip", "motor_id": 1}]}"#).unwrap();
+    let csv = "time_from_start,hip\n0.0,0.0\n1.0,1.0\n";
+    let single = Trajectory::<f32>::from_csv(csv, &mapping).unwrap();
+    let double = Trajectory::<f64>::from_csv(csv, &mapping).unwrap();
+
+    let t = Duration::from_millis(250);
+    let expected = 0.25f64.to_degrees();
+    assert!((single.sample(t).unwrap()[0].to_f64() - expected).abs() < 1e-4);
+    assert!((double.sample(t).unwrap()[0] - expected).abs() < 1e-9);
+}
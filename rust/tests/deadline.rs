@@ -0,0 +1,36 @@
+//! Deadline-miss accounting of the control loop
+
+use livelybot_motor_control::ControlLoop;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn late_cycles_are_skipped_and_counted() {
+    let running = AtomicBool::new(true);
+    let mut control = ControlLoop::with_period(Duration::from_millis(20)).with_tx_deadline(Duration::from_millis(5));
+    let mut late_seen = 0;
+    control
+        .run(&running, |info| {
+            match info.cycle {
+                // Runs past its own deadline
+                2 => thread::sleep(Duration::from_millis(10)),
+                // Runs past the next two cycles as well
+                4 => thread::sleep(Duration::from_millis(55)),
+                _ => {}
+            }
+            if info.missed_deadline() {
+                late_seen += 1;
+            }
+            Ok(info.cycle < 7)
+        })
+        .unwrap();
+
+    let stats = control.stats();
+    assert_eq!(stats.cycles, 8);
+    assert!(late_seen <= stats.overruns);
+    assert!(stats.overruns >= 2, "{:?}", stats);
+    assert!(stats.skipped >= 2, "{:?}", stats);
+    assert!(stats.max_overrun >= Duration::from_millis(5), "{:?}", stats);
+    assert_eq!(stats.missed(), stats.skipped + stats.overruns);
+}
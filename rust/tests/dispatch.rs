@@ -0,0 +1,123 @@
+//! Per-motor frame dispatch
+
+use livelybot_motor_control::bus::SUBSCRIBER_QUEUE_LEN;
+use livelybot_motor_control::protocol::{stream_id, ANGLE_STREAM_ID};
+use livelybot_motor_control::{CanBus, CanTransport, FrameDispatcher, RawFrame};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const WAIT: Duration = Duration::from_secs(2);
+
+/// A transport receiving the frames a test puts on it
+#[derive(Clone, Default)]
+struct Inject(Arc<Mutex<VecDeque<RawFrame>>>);
+
+impl Inject {
+    fn reply(&self, motor_id: u8, tag: u8) {
+        let frame = RawFrame::extended((motor_id as u32) << 8, &[0x24, 0x01, tag, 0, 0, 0, 0, 0]);
+        self.0.lock().unwrap().push_back(frame);
+    }
+
+    fn stream_command(&self, motor_id: u8) {
+        let frame = RawFrame::standard(stream_id(ANGLE_STREAM_ID, motor_id) as u16, &[0; 8]);
+        self.0.lock().unwrap().push_back(frame);
+    }
+}
+
+impl CanTransport for Inject {
+    fn send(&self, _frame: &RawFrame) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        let frame = self.0.lock().unwrap().pop_front();
+        if frame.is_none() {
+            thread::sleep(timeout.min(Duration::from_millis(1)));
+        }
+        Ok(frame)
+    }
+}
+
+fn setup() -> (Inject, FrameDispatcher) {
+    let inject = Inject::default();
+    let bus = CanBus::with_transport(inject.clone(), "inject", 1_000_000);
+    let dispatcher = FrameDispatcher::start(&bus);
+    (inject, dispatcher)
+}
+
+fn tag(frame: &socketcan::CanFrame) -> (u32, u8) {
+    let raw = RawFrame::from_frame(frame);
+    (raw.id, raw.data[2])
+}
+
+#[test]
+fn frames_are_routed_by_the_sending_motor() {
+    let (inject, dispatcher) = setup();
+    let motor1 = dispatcher.motor_channel(1);
+    let motor2 = dispatcher.motor_channel(2);
+    let (callback_tx, callback) = mpsc::channel();
+    dispatcher.on_motor(1, move |frame| callback_tx.send(tag(frame)).unwrap());
+    let (other_tx, other) = mpsc::channel();
+    dispatcher.on_other(move |frame| other_tx.send(RawFrame::from_frame(frame).id).unwrap());
+
+    inject.reply(1, 10);
+    inject.reply(2, 20);
+    // Nobody listens to motor 3
+    inject.reply(3, 30);
+    inject.stream_command(4);
+    inject.reply(1, 11);
+
+    assert_eq!(tag(&motor1.recv_timeout(WAIT).unwrap()), (0x100, 10));
+    assert_eq!(tag(&motor1.recv_timeout(WAIT).unwrap()), (0x100, 11));
+    assert_eq!(tag(&motor2.recv_timeout(WAIT).unwrap()), (0x200, 20));
+    assert_eq!(callback.recv_timeout(WAIT).unwrap(), (0x100, 10));
+    assert_eq!(callback.recv_timeout(WAIT).unwrap(), (0x100, 11));
+    assert_eq!(other.recv_timeout(WAIT).unwrap(), 0x0490);
+
+    // Each frame went to its own motor's routes only
+    thread::sleep(Duration::from_millis(50));
+    assert!(motor1.try_recv().is_err());
+    assert!(motor2.try_recv().is_err());
+    assert!(callback.try_recv().is_err());
+    assert!(other.try_recv().is_err());
+    assert_eq!(dispatcher.dropped(), 0);
+}
+
+#[test]
+fn full_channels_drop_and_dropped_receivers_unregister() {
+    let (inject, dispatcher) = setup();
+    let full = dispatcher.motor_channel(1);
+    let unregistered = dispatcher.motor_channel(1);
+    drop(unregistered);
+    let (seen_tx, seen) = mpsc::channel();
+    dispatcher.on_motor(1, move |frame| seen_tx.send(tag(frame)).unwrap());
+
+    // In batches, so the bus's own subscriber queue never overflows
+    let total = SUBSCRIBER_QUEUE_LEN + 5;
+    for batch in (0..total).collect::<Vec<_>>().chunks(16) {
+        batch.iter().for_each(|&i| inject.reply(1, i as u8));
+        for _ in batch {
+            seen.recv_timeout(WAIT).unwrap();
+        }
+    }
+
+    // Only the channel nobody reads counts drops; the dropped receiver is gone
+    assert_eq!(dispatcher.dropped(), 5);
+    assert_eq!(full.try_iter().count(), SUBSCRIBER_QUEUE_LEN);
+}
+
+#[test]
+fn a_stopped_dispatcher_routes_nothing() {
+    let (inject, mut dispatcher) = setup();
+    let motor1 = dispatcher.motor_channel(1);
+    inject.reply(1, 1);
+    assert_eq!(tag(&motor1.recv_timeout(WAIT).unwrap()), (0x100, 1));
+
+    dispatcher.stop();
+    inject.reply(1, 2);
+    assert!(motor1.recv_timeout(Duration::from_millis(100)).is_err());
+}
@@ -0,0 +1,30 @@
+//! Gains and limits written on enable
+
+use livelybot_motor_control::{ControlMode, EnableConfig};
+use std::time::Duration;
+
+#[test]
+fn defaults_are_the_historic_gains() {
+    let position = EnableConfig::default();
+    assert_eq!(position.mode, ControlMode::Position);
+    assert_eq!((position.kp, position.kd, position.torque_limit_nm), (1.0, 0.1, None));
+    assert_eq!(position.gain_steps(), vec![(1.0, 0.1)]);
+
+    let velocity = EnableConfig::velocity();
+    assert_eq!(velocity.mode, ControlMode::Velocity);
+    assert_eq!((velocity.kp, velocity.kd, velocity.torque_limit_nm), (2.0, 0.2, Some(3.0)));
+}
+
+#[test]
+fn ramp_rises_to_the_target_gains() {
+    let config = EnableConfig::position()
+        .with_gains(4.0, 0.4)
+        .with_ramp(Duration::from_millis(80));
+    let steps = config.gain_steps();
+    assert_eq!(steps.len(), 4);
+    assert_eq!(steps[0], (1.0, 0.1));
+    assert_eq!(*steps.last().unwrap(), (4.0, 0.4));
+
+    assert!(EnableConfig::position().with_gains(-1.0, 0.1).validate().is_err());
+    assert!(EnableConfig::velocity().with_torque_limit(0.0).validate().is_err());
+}
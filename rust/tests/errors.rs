@@ -0,0 +1,123 @@
+//! Matching failure kinds returned by the controller and through anyhow
+//! context chains
+
+use anyhow::{anyhow, Context};
+use livelybot_motor_control::bus::SUBSCRIBER_QUEUE_LEN;
+use livelybot_motor_control::{
+    ErrorContext, FaultClass, FaultCode, LivelyMotorController, MockTransport, MotorError, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+fn timeout(motor_id: u8) -> MotorError {
+    MotorError::Timeout {
+        motor_id,
+        context: ErrorContext::default(),
+    }
+}
+
+#[test]
+fn motor_error_is_found_below_any_context() {
+    let result: anyhow::Result<()> = Err(timeout(3).into());
+    let error = result
+        .context("timeout reading register 0x01 from motor 3 on can0")
+        .context("reading feedback of motor 3 on can0")
+        .unwrap_err();
+
+    assert!(matches!(MotorError::of(&error), Some(MotorError::Timeout { motor_id: 3, .. })));
+    assert_eq!(MotorError::of(&error).and_then(MotorError::motor_id), Some(3));
+    assert!(format!("{:#}", error).ends_with("motor 3 did not answer in time"));
+    assert!(MotorError::of(&anyhow::anyhow!("unrelated")).is_none());
+}
+
+#[test]
+fn fault_codes_name_their_class() {
+    let fault = MotorError::MotorFault {
+        motor_id: 2,
+        code: FaultCode(38),
+        context: ErrorContext::default(),
+    };
+    assert_eq!(FaultCode(38).class(), FaultClass::OverTemperature);
+    assert_eq!(fault.to_string(), format!("motor 2 reports fault 38 ({})", FaultClass::OverTemperature));
+
+    let io = MotorError::CanIo(std::io::Error::from(std::io::ErrorKind::NotFound));
+    assert!(std::error::Error::source(&io).is_some());
+    assert_eq!(io.motor_id(), None);
+}
+
+#[test]
+fn controller_returns_the_failure_kind() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    let error = controller.read_motor_state(9).unwrap_err();
+    assert!(matches!(error, MotorError::Timeout { motor_id: 9, .. }));
+    assert_eq!(error.to_string(), "reading feedback of motor 9 on mock: motor 9 did not answer in time");
+
+    // The kind keeps every operation it happened in
+    let error = controller.check_fault(9).unwrap_err();
+    assert!(matches!(error, MotorError::Timeout { motor_id: 9, .. }));
+    assert_eq!(error.to_string(), "read fault of motor 9 on mock: motor 9 did not answer in time");
+    let context: Vec<&str> = error.error_context().unwrap().iter().collect();
+    assert_eq!(context, ["read fault of motor 9 on mock"]);
+
+    mock.set_fault(1, 38);
+    match controller.check_fault(1) {
+        Err(MotorError::MotorFault { motor_id: 1, code, .. }) => assert_eq!(code.class(), FaultClass::OverTemperature),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // Failures without a kind keep their message
+    let error = controller.set_motor_id(1, 0).unwrap_err();
+    assert!(matches!(error, MotorError::Other(_)));
+    assert_eq!(error.to_string(), "Invalid motor ID 0 (valid: 1-127)");
+    assert_eq!(error.motor_id(), None);
+}
+
+#[test]
+fn timeouts_explain_dropped_frames() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    // A subscriber nobody drains loses the frames sent while motor 9 is awaited
+    let _stalled = controller.bus().subscribe_sent();
+    let error = thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(5));
+            for _ in 0..SUBSCRIBER_QUEUE_LEN + 10 {
+                controller.send_frame(0x7F, &[0x01]).unwrap();
+            }
+        });
+        controller.read_motor_state(9).unwrap_err()
+    });
+
+    assert!(matches!(error, MotorError::Timeout { motor_id: 9, .. }));
+    let message = error.to_string();
+    assert!(message.starts_with("reading feedback of motor 9 on mock: reading register 0x01"), "{}", message);
+    assert!(message.contains("received frames were dropped since the last timeout"), "{}", message);
+    assert!(message.ends_with("the reply may have been lost: motor 9 did not answer in time"), "{}", message);
+    // The losses are reported once
+    assert_eq!(controller.bus().new_rx_drops().total(), 0);
+}
+
+#[test]
+fn conversions_keep_the_kind() {
+    let io: MotorError = std::io::Error::from(std::io::ErrorKind::BrokenPipe).into();
+    assert!(matches!(io, MotorError::CanIo(ref e) if e.kind() == std::io::ErrorKind::BrokenPipe));
+
+    // A kind wrapped in anyhow context comes back out with that context;
+    // anything else is `Other`
+    let wrapped = anyhow::Error::new(timeout(2)).context("reading motor 2").context("homing");
+    let error = MotorError::from(wrapped);
+    assert!(matches!(error, MotorError::Timeout { motor_id: 2, .. }));
+    assert_eq!(error.to_string(), "homing: reading motor 2: motor 2 did not answer in time");
+    let other = MotorError::from(anyhow!("file missing").context("loading limits"));
+    assert_eq!(format!("{:#}", other), "loading limits: file missing");
+
+    // `of` looks through `Other` for the kind below it
+    let nested: anyhow::Error = MotorError::from(
+        anyhow::Error::new(timeout(5))
+            .context("ping")
+            .context(anyhow!("outer")),
+    )
+    .into();
+    assert!(matches!(MotorError::of(&nested), Some(MotorError::Timeout { motor_id: 5, .. })));
+}
@@ -0,0 +1,44 @@
+//! Emergency stop modes
+
+use livelybot_motor_control::{
+    rps2_to_acceleration, rps_to_velocity, FaultPolicy, LivelyMotorController, MockTransport, SimMotor, StopMode,
+    MAGIC_POS,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn braking_follows_the_mode() {
+    assert_eq!(StopMode::ZeroTorque.braking(Some(2.0)), None);
+
+    let ramped = StopMode::Ramped { ramp_ms: 50 }.braking(Some(-1.0)).unwrap();
+    assert!((ramped.acceleration_rps2 - 20.0).abs() < 1e-9);
+    assert!((ramped.duration.as_secs_f64() - 0.05).abs() < 1e-6);
+
+    // Unknown velocity: the fastest encodable one still stops in time
+    let hardest = StopMode::MaxBraking.braking(None).unwrap();
+    assert!((hardest.acceleration_rps2 - 32.767).abs() < 1e-9);
+    assert!(hardest.duration < Duration::from_millis(251));
+
+    let policy: FaultPolicy = serde_json::from_str(r#"{ "stop_mode": { "mode": "ramped", "ramp_ms": 80 } }"#).unwrap();
+    assert_eq!(policy.stop_mode, StopMode::Ramped { ramp_ms: 80 });
+    assert_eq!(FaultPolicy::default().stop_mode, StopMode::ZeroTorque);
+}
+
+#[test]
+fn ramped_stop_brings_the_motor_to_rest_before_disabling() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    controller.enable_velocity_mode(1).unwrap();
+    controller
+        .send_velocity_command_to(1, MAGIC_POS, rps_to_velocity(2.0), rps2_to_acceleration(30.0))
+        .unwrap();
+    thread::sleep(Duration::from_millis(300));
+    assert!((controller.read_motor_state(1).unwrap().velocity_rps - 2.0).abs() < 0.2);
+
+    let start = Instant::now();
+    controller.emergency_stop(&[1], StopMode::Ramped { ramp_ms: 200 }).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(180));
+    assert!(mock.state(1).unwrap().velocity_rps.abs() < 0.2);
+    assert!(controller.enabled_motors().is_empty());
+}
@@ -0,0 +1,36 @@
+//! Parameter sweep on a simulated motor
+
+use livelybot_motor_control::{
+    LivelyMotorController, MockTransport, OscillateParams, Primitive, Register, SimMotor, Sweep, SweepParam,
+};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+#[test]
+fn torque_limit_sweep_compares_tracking() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    controller.write_register_float(1, Register::TorqueLimit, 4.0).unwrap();
+
+    let excitation = Primitive::oscillate(OscillateParams {
+        center_deg: 0.0,
+        amplitude_deg: 30.0,
+        frequency_hz: 1.0,
+        duration: Duration::from_millis(500),
+    });
+    let sweep = Sweep::new(SweepParam::TorqueLimit, &[0.02, 2.0], excitation).with_rest(Duration::ZERO);
+    let mut seen = Vec::new();
+    let report = sweep.run(&controller, 1, &AtomicBool::new(true), |p| seen.push(p.value)).unwrap();
+
+    assert_eq!(seen, vec![0.02, 2.0]);
+    let [weak, strong] = [&report.points[0].report, &report.points[1].report];
+    assert!(weak.samples > 0 && strong.samples > 0);
+    assert!(weak.rms_error_deg > 2.0 * strong.rms_error_deg);
+    assert_eq!(report.best().unwrap().value, 2.0);
+    assert!(report.table().lines().nth(2).unwrap().starts_with("* 2 "));
+    assert!(report.to_csv().starts_with("torque_limit,rms_error_deg,"));
+
+    // The swept register is restored
+    assert_eq!(controller.read_register_float(1, Register::TorqueLimit).unwrap(), 4.0);
+    assert!(Sweep::new(SweepParam::CommandRate, &[0.0], Primitive::relax()).validate().is_err());
+}
@@ -0,0 +1,90 @@
+//! Catching a falling disabled joint on a simulated motor
+
+use livelybot_motor_control::protocol::{Register, ValueType};
+use livelybot_motor_control::{
+    EnableConfig, EventBus, EventKind, FallCatchConfig, FallCatcher, LimitMode, Limits, LivelyMotorController,
+    MockTransport, SimMotor,
+};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn falling_disabled_joint_is_caught_and_released() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default()).with_motor(2, SimMotor::default());
+    let controller = Arc::new(LivelyMotorController::with_transport("mock", mock.clone()));
+    let events = Arc::new(EventBus::new());
+    let subscription = events.subscribe();
+    let config = FallCatchConfig::new(0.5);
+    let catcher =
+        FallCatcher::start_with_events(Arc::clone(&controller), &[1, 2], config, Some(Arc::clone(&events))).unwrap();
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(catcher.caught().is_empty());
+
+    // Gravity takes over motor 1 while it is disabled
+    mock.set_load(1, 0.5);
+    for _ in 0..100 {
+        if !catcher.caught().is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(catcher.caught(), vec![1]);
+    assert!(controller.enabled_motors().contains(&1));
+    // Enabled in position mode with the damping-only gains
+    let compliant = EnableConfig::compliant();
+    assert_eq!(register(&controller, Register::Mode), 0x0A as f64);
+    assert_eq!(register(&controller, Register::Kp), compliant.kp as f64);
+    assert_eq!(register(&controller, Register::Kd), compliant.kd as f64);
+    match subscription.recv_timeout(Duration::from_secs(1)).unwrap().kind {
+        EventKind::FallCaught { motor_id, velocity_rps } => assert!(motor_id == 1 && velocity_rps.abs() >= 0.5),
+        other => panic!("unexpected event {:?}", other),
+    }
+    thread::sleep(Duration::from_millis(300));
+    assert!(mock.state(1).unwrap().velocity_rps.abs() < 0.5);
+
+    // Supported again: released motors are disabled and watched again
+    mock.set_load(1, 0.0);
+    catcher.release(1).unwrap();
+    assert!(catcher.caught().is_empty() && controller.enabled_motors().is_empty());
+    assert_eq!(register(&controller, Register::Mode), 0.0);
+    assert!(catcher.release(2).is_err());
+    assert_eq!(catcher.catches(), 1);
+    assert_eq!((catcher.errors(), catcher.poll_errors(), catcher.last_error()), (0, 0, None));
+}
+
+/// Register of motor 1 as the simulated motor holds it
+fn register(controller: &LivelyMotorController, reg: Register) -> f64 {
+    // A one-value int8 read of the mode register is an info query
+    let ty = if reg == Register::Mode { ValueType::Int16 } else { ValueType::Float };
+    let reply = controller.read_registers(1, reg, ty, 1).unwrap();
+    reply.float(0).map(f64::from).or(reply.int(0).map(|v| v as f64)).unwrap()
+}
+
+#[test]
+fn failed_catch_is_published() {
+    let mock = MockTransport::new().with_motor(2, SimMotor::default());
+    let controller = Arc::new(LivelyMotorController::with_transport("mock", mock.clone()));
+    // The catch asks for more torque than the limits allow
+    let limits = Limits::position(-180.0, 180.0).with_max_torque(1.0).with_mode(LimitMode::Reject);
+    controller.set_limits(2, limits).unwrap();
+    let events = Arc::new(EventBus::new());
+    let subscription = events.subscribe();
+    let config = FallCatchConfig::new(0.5).with_catch(EnableConfig::compliant().with_torque_limit(3.0));
+    let catcher =
+        FallCatcher::start_with_events(Arc::clone(&controller), &[2], config, Some(Arc::clone(&events))).unwrap();
+
+    mock.set_load(2, 0.5);
+    match subscription.recv_timeout(Duration::from_secs(2)).unwrap().kind {
+        EventKind::FallCatchFailed { motor_id, velocity_rps, reason } => {
+            assert!(motor_id == 2 && velocity_rps.abs() >= 0.5);
+            assert!(reason.contains("limit exceeded"), "{}", reason);
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+    assert!(catcher.errors() >= 1);
+    assert!(catcher.last_error().unwrap().starts_with("cannot catch motor 2: "));
+    assert!(catcher.caught().is_empty() && controller.enabled_motors().is_empty());
+    mock.set_load(2, 0.0);
+}
@@ -0,0 +1,125 @@
+//! Per-joint fault responses
+
+use livelybot_motor_control::{
+    FaultAction, FaultClass, FaultPolicy, LivelyMotorController, ManagedMotor, MockTransport, MotorLifecycle,
+    MotorSettings, Register, SafetyMonitor, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+const POLICY: &str = r#"{
+    "default": { "over_temperature": "brake", "under_voltage": "warn", "encoder": "stop" },
+    "joints": { "3": { "encoder": "hold", "limit": "ignore" } },
+    "brake_kd": 0.5
+}"#;
+
+#[test]
+fn fault_codes_are_classified() {
+    assert_eq!(FaultClass::from_code(0), None);
+    assert_eq!(FaultClass::from_code(33), Some(FaultClass::Driver));
+    assert_eq!(FaultClass::from_code(43), Some(FaultClass::Encoder));
+    assert_eq!(FaultClass::from_code(38), Some(FaultClass::OverTemperature));
+    assert_eq!(FaultClass::from_code(40), Some(FaultClass::UnderVoltage));
+    assert_eq!(FaultClass::from_code(41), Some(FaultClass::Configuration));
+    assert_eq!(FaultClass::from_code(200), Some(FaultClass::Other));
+    assert_eq!(FaultClass::OverTemperature.to_string(), "over_temperature");
+}
+
+#[test]
+fn joint_overrides_win_over_defaults() {
+    let policy: FaultPolicy = serde_json::from_str(POLICY).unwrap();
+    assert_eq!(policy.action(1, FaultClass::Encoder), FaultAction::Stop);
+    assert_eq!(policy.action(3, FaultClass::Encoder), FaultAction::Hold);
+    assert_eq!(policy.action(3, FaultClass::OverTemperature), FaultAction::Brake, "no override for this class");
+    assert_eq!(policy.action(3, FaultClass::Limit), FaultAction::Ignore);
+    assert_eq!(policy.action(1, FaultClass::Limit), FaultPolicy::FALLBACK);
+    assert_eq!(FaultPolicy::default().action(1, FaultClass::UnderVoltage), FaultAction::Stop);
+    assert!(serde_json::from_str::<FaultPolicy>(r#"{"default": {"encoder": "explode"}}"#).is_err());
+}
+
+fn enabled_motor<'a>(controller: &'a LivelyMotorController, safety: &'a SafetyMonitor, id: u8) -> ManagedMotor<'a> {
+    let mut motor = ManagedMotor::new(controller, id).with_safety(safety);
+    motor.discover().unwrap();
+    motor.configure(MotorSettings::default()).unwrap();
+    motor.enable().unwrap();
+    motor
+}
+
+fn setup() -> (MockTransport, LivelyMotorController, SafetyMonitor) {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(3, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    let safety = SafetyMonitor::new().with_fault_policy(serde_json::from_str(POLICY).unwrap());
+    (mock, controller, safety)
+}
+
+#[test]
+fn hold_replaces_setpoints_until_resumed() {
+    let (mock, controller, safety) = setup();
+    let mut motor = enabled_motor(&controller, &safety, 3);
+    assert_eq!(motor.check_fault().unwrap(), FaultAction::Ignore);
+
+    mock.set_fault(3, 43);
+    assert_eq!(motor.check_fault().unwrap(), FaultAction::Hold);
+    assert_eq!(motor.fault_response(), Some(FaultAction::Hold));
+    assert_eq!(motor.state(), MotorLifecycle::Enabled);
+    // The setpoint is replaced by the angle held at the fault
+    for _ in 0..20 {
+        motor.set_angle(90.0, 1.0, 1.0).unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(mock.state(3).unwrap().position_deg.abs() < 1.0, "{:?}", mock.state(3));
+
+    mock.set_fault(3, 0);
+    motor.resume().unwrap();
+    assert_eq!(motor.fault_response(), None);
+}
+
+#[test]
+fn brake_rejects_setpoints_and_drops_stiffness() {
+    let (mock, controller, safety) = setup();
+    let mut motor = enabled_motor(&controller, &safety, 1);
+
+    mock.set_fault(1, 38);
+    assert_eq!(motor.check_fault().unwrap(), FaultAction::Brake);
+    assert_eq!(controller.read_register_float(1, Register::Kp).unwrap(), 0.0);
+    assert_eq!(controller.read_register_float(1, Register::Kd).unwrap(), 0.5);
+    let err = motor.set_angle(10.0, 1.0, 1.0).unwrap_err();
+    assert!(err.to_string().contains("suspended by fault response 'brake'"), "{}", err);
+
+    mock.set_fault(1, 0);
+    motor.resume().unwrap();
+    motor.set_angle(10.0, 1.0, 1.0).unwrap();
+}
+
+#[test]
+fn stop_faults_the_motor_and_disables_the_bus() {
+    let (mock, controller, safety) = setup();
+    let mut motor = enabled_motor(&controller, &safety, 1);
+    let _other = enabled_motor(&controller, &safety, 3);
+
+    mock.set_fault(1, 43);
+    assert_eq!(motor.check_fault().unwrap(), FaultAction::Stop);
+    assert_eq!(motor.state(), MotorLifecycle::Faulted);
+    assert!(motor.fault_reason().unwrap().contains("encoder fault 0x2B"));
+    assert!(controller.enabled_motors().is_empty());
+
+    // Without a monitor every fault stops
+    mock.set_fault(1, 40);
+    let mut bare = ManagedMotor::new(&controller, 1);
+    bare.discover().unwrap();
+    assert_eq!(bare.check_fault().unwrap(), FaultAction::Stop);
+}
+
+#[test]
+fn a_silent_motor_is_a_comm_loss() {
+    let (mock, controller, _) = setup();
+    let safety = SafetyMonitor::new().with_fault_policy(
+        serde_json::from_str(r#"{"default": {"comm_loss": "warn"}}"#).unwrap(),
+    );
+    let mut motor = enabled_motor(&controller, &safety, 1);
+    mock.remove_motor(1);
+    assert_eq!(motor.check_fault().unwrap(), FaultAction::Warn);
+    assert_eq!(motor.state(), MotorLifecycle::Enabled);
+}
@@ -0,0 +1,81 @@
+//! End-effector force estimation through chain Jacobians
+
+use livelybot_motor_control::{
+    degrees_to_position, nm_to_torque, rps_to_velocity, Chain, ForceEstimator, LivelyMotorController, MockTransport,
+    Planar2Link, Planar3Link, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn joint_torques_map_back_to_the_applied_force() {
+    // Arm bent at the elbow, pushed up at the tip: only the shoulder feels it
+    let arm = Planar2Link::new(0.3, 0.2);
+    let force = Chain::TwoLink(arm).estimate(&[0.0, 90.0], &[3.0, 0.0]).unwrap();
+    assert!(close(force.fx_n, 0.0) && close(force.fy_n, 10.0) && force.mz_nm.is_none(), "{:?}", force);
+    assert!(close(force.magnitude(), 10.0));
+
+    // τ = Jᵀ W for an arbitrary wrench, then back
+    let arm = Planar3Link::new(0.3, 0.2, 0.1);
+    let joints = [20.0, 45.0, -30.0];
+    let wrench = [2.0, -4.0, 0.5];
+    let j = arm.jacobian(joints);
+    let torques: Vec<f64> = (0..3).map(|col| (0..3).map(|row| j[row][col] * wrench[row]).sum()).collect();
+    let force = Chain::ThreeLink(arm).estimate(&joints, &torques).unwrap();
+    assert!(close(force.fx_n, 2.0) && close(force.fy_n, -4.0) && close(force.mz_nm.unwrap(), 0.5), "{:?}", force);
+}
+
+#[test]
+fn singular_poses_and_wrong_joint_counts_are_errors() {
+    let chain = Chain::TwoLink(Planar2Link::new(0.3, 0.2));
+    let err = chain.estimate(&[0.0, 0.0], &[1.0, 1.0]).unwrap_err();
+    assert!(err.to_string().contains("singular"), "{}", err);
+    assert!(chain.estimate(&[0.0, 90.0, 0.0], &[1.0, 1.0]).is_err());
+
+    let mock = MockTransport::new();
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    let err = ForceEstimator::new(&controller, chain, &[1]).err().unwrap();
+    assert!(err.to_string().contains("2 joints but 1 motors"), "{}", err);
+}
+
+#[test]
+fn contact_is_flagged_when_a_load_pushes_the_tip() {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(2, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    for id in [1, 2] {
+        controller.enable_motor(id).unwrap();
+    }
+    let hold = |id: u8, angle: f64| {
+        controller
+            .send_angle_command_to(id, degrees_to_position(angle), rps_to_velocity(2.0), nm_to_torque(8.0))
+            .unwrap()
+    };
+    hold(1, 0.0);
+    hold(2, 90.0);
+    thread::sleep(Duration::from_millis(500));
+
+    let mut estimator = ForceEstimator::new(&controller, Chain::TwoLink(Planar2Link::new(0.3, 0.2)), &[1, 2])
+        .unwrap()
+        .with_contact_threshold(5.0);
+    estimator.calibrate_bias().unwrap();
+    let at_rest = estimator.sample().unwrap();
+    assert!(!at_rest.contact, "{:?}", at_rest.force);
+    assert_eq!(at_rest.joints.len(), 2);
+
+    // 3 Nm against the shoulder is 10 N at the tip, held by the motor
+    mock.set_load(1, -3.0);
+    hold(1, 0.0);
+    hold(2, 90.0);
+    thread::sleep(Duration::from_millis(500));
+    let pushed = estimator.sample().unwrap();
+    let force = pushed.force.unwrap();
+    assert!(pushed.contact, "{:?}", force);
+    // The position loop gives a little under load, so the pose is no longer exact
+    assert!((force.fy_n - 10.0).abs() < 1.5, "{:?}", force);
+}
@@ -0,0 +1,238 @@
+//! Frame layout descriptors: validation, and that the default layout encodes
+//! the same bytes as current firmware expects
+
+use livelybot_motor_control::protocol::{self, ANGLE_STREAM_ID, VELOCITY_STREAM_ID};
+use livelybot_motor_control::query;
+use livelybot_motor_control::{
+    EncodingPolicy, Endianness, ErrorLogEntry, FieldLayout, FrameLayout, Identity, InfoQuery, MitCommand, MitRanges,
+    ProtocolLayout, Register, TemperatureSnapshot, ValueType,
+};
+
+#[test]
+fn default_layout_is_valid() {
+    ProtocolLayout::default().validate().unwrap();
+}
+
+#[test]
+fn default_layout_matches_firmware_bytes() {
+    let policy = EncodingPolicy::default();
+
+    assert_eq!(
+        policy.write_int8(Register::Mode, 0x0A),
+        [0x01, 0x00, 0x0A, 0x50, 0x50, 0x50, 0x50, 0x50]
+    );
+
+    assert_eq!(
+        policy.write_int16(Register::FeedbackPeriod, 10),
+        [0x05, 0x5A, 0x0A, 0x00, 0x50, 0x50, 0x50, 0x50]
+    );
+
+    let mut float = [0x0D, 0x23, 0, 0, 0, 0, 0x50, 0x50];
+    float[2..6].copy_from_slice(&1.5f32.to_le_bytes());
+    assert_eq!(policy.write_float(Register::Kp, 1.5), float);
+
+    assert_eq!(
+        policy.read(Register::Position, ValueType::Int16, 3),
+        [0x17, 0x01, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50]
+    );
+
+    assert_eq!(
+        policy.angle_stream(0x0102, -2, 0x0304),
+        [0x02, 0x01, 0xFE, 0xFF, 0x04, 0x03, 0x50, 0x50]
+    );
+    assert_eq!(
+        policy.velocity_stream(i16::MIN, 4000, 1000),
+        [0x00, 0x80, 0xA0, 0x0F, 0xE8, 0x03, 0x50, 0x50]
+    );
+}
+
+#[test]
+fn default_layout_frames_pass_strict_validation() {
+    let policy = EncodingPolicy::new(0x50, true);
+    policy.validate(1, &policy.write_int8(Register::Mode, 0x0A)).unwrap();
+    policy.validate(1, &policy.write_float(Register::Kd, 0.1)).unwrap();
+    policy.validate(0x8001, &policy.read(Register::Position, ValueType::Int16, 3)).unwrap();
+    policy.validate(ANGLE_STREAM_ID, &policy.angle_stream(1, 2, 3)).unwrap();
+    policy.validate(VELOCITY_STREAM_ID, &policy.velocity_stream(1, 2, 3)).unwrap();
+    policy.validate(0x8001, &policy.query(InfoQuery::ErrorLog, 3)).unwrap();
+}
+
+#[test]
+fn stream_commands_can_be_addressed() {
+    assert_eq!(protocol::stream_id(ANGLE_STREAM_ID, 0), ANGLE_STREAM_ID);
+    assert_eq!(protocol::stream_id(ANGLE_STREAM_ID, 1), 0x0190);
+    assert_eq!(protocol::stream_id(VELOCITY_STREAM_ID, 12), 0x0CAD);
+    assert_eq!(protocol::stream_target(0x0CAD), Some((VELOCITY_STREAM_ID, 12)));
+    assert_eq!(protocol::stream_target(VELOCITY_STREAM_ID), Some((VELOCITY_STREAM_ID, 0)));
+    // Register requests and replies are not streams
+    assert_eq!(protocol::stream_target(0x8001), None);
+    assert_eq!(protocol::stream_target(0x0100), None);
+
+    let policy = EncodingPolicy::new(0x50, true);
+    policy.validate(0x0190, &policy.angle_stream(1, 2, 3)).unwrap();
+    policy.validate(0x0CAD, &policy.velocity_stream(1, 2, 3)).unwrap();
+
+    let frame = policy.angle_stream(100, 2, 3);
+    assert_eq!(protocol::describe_frame(0x0190, &frame), "motor 1 angle stream pos=100 vel=2 tqe=3");
+    assert_eq!(protocol::describe_frame(ANGLE_STREAM_ID, &frame), "angle stream pos=100 vel=2 tqe=3");
+}
+
+#[test]
+fn mit_commands_pack_into_bit_fields() {
+    let ranges = MitRanges::default();
+    let full = MitCommand { position_deg: 720.0, velocity_rps: 8.0, kp: 500.0, kd: 5.0, torque_nm: 20.0 };
+    assert_eq!(full.encode(&ranges), [0xFF; 8]);
+    let empty = MitCommand { position_deg: -720.0, velocity_rps: -8.0, kp: 0.0, kd: 0.0, torque_nm: -20.0 };
+    assert_eq!(empty.encode(&ranges), [0x00; 8]);
+    // Out of range values are clamped, not wrapped
+    let beyond = MitCommand { position_deg: 1000.0, kp: -3.0, ..full };
+    assert_eq!(beyond.encode(&ranges), MitCommand { kp: 0.0, ..full }.encode(&ranges));
+
+    // Position in the top 16 bits, then 12 bits each
+    let data = MitCommand { position_deg: 0.0, velocity_rps: 8.0, kp: 0.0, kd: 5.0, torque_nm: -20.0 }.encode(&ranges);
+    assert_eq!(data, [0x80, 0x00, 0xFF, 0xF0, 0x00, 0xFF, 0xF0, 0x00]);
+
+    let command = MitCommand { position_deg: 12.5, velocity_rps: -1.25, kp: 40.0, kd: 0.8, torque_nm: 1.5 };
+    let decoded = MitCommand::decode(&command.encode(&ranges), &ranges).unwrap();
+    assert!((decoded.position_deg - 12.5).abs() <= 1440.0 / 65535.0);
+    assert!((decoded.velocity_rps + 1.25).abs() <= 16.0 / 4095.0);
+    assert!((decoded.kp - 40.0).abs() <= 500.0 / 4095.0);
+    assert!((decoded.kd - 0.8).abs() <= 5.0 / 4095.0);
+    assert!((decoded.torque_nm - 1.5).abs() <= 40.0 / 4095.0);
+
+    let id = protocol::stream_id(protocol::MIT_STREAM_ID, 3);
+    assert_eq!(id, 0x03B0);
+    EncodingPolicy::new(0x50, true).validate(id, &data).unwrap();
+    assert!(protocol::describe_frame(id, &data).starts_with("motor 3 mit pos=0.0"));
+}
+
+#[test]
+fn info_queries_extend_the_ping() {
+    let policy = EncodingPolicy::default();
+    assert_eq!(policy.query(InfoQuery::Identity, 0), policy.read(Register::Mode, ValueType::Int8, 1));
+    assert_eq!(
+        policy.query(InfoQuery::ErrorLog, 2),
+        [0x11, 0x00, 0x02, 0x02, 0x50, 0x50, 0x50, 0x50]
+    );
+
+    let identity = Identity::parse(&[0x51, b'H', b'T', b'5', b'V', b'2', b'.', b'1']).unwrap();
+    assert_eq!((identity.name.as_str(), identity.hardware_version.as_str()), ("HT5", "V2.1"));
+    assert!(!InfoQuery::Uptime.matches(b"\x51HT5V2.1", 0));
+
+    assert_eq!(query::parse_uptime(&[0x51, 0x03, 0x10, 0x0E, 0, 0]).unwrap().as_secs(), 3600);
+    let entry = ErrorLogEntry::parse(&[0x51, 0x02, 0x01, 0x07, 0x2C, 0x01, 0, 0]).unwrap().unwrap();
+    assert_eq!((entry.index, entry.code, entry.uptime_s), (1, 7, 300));
+    assert_eq!(ErrorLogEntry::parse(&[0x51, 0x02, 0x02, 0x00, 0, 0, 0, 0]).unwrap(), None);
+    let temps = TemperatureSnapshot::parse(&[0x51, 0x04, 0xC2, 0x01, 0x5E, 0x01, 0xF6, 0xFF]).unwrap();
+    assert_eq!((temps.motor_c, temps.driver_c, temps.mcu_c), (45.0, 35.0, -1.0));
+}
+
+#[test]
+fn overlapping_fields_are_rejected() {
+    let frame = FrameLayout::new(
+        "bad",
+        vec![
+            FieldLayout::new("a", 0, ValueType::Int16),
+            FieldLayout::new("b", 1, ValueType::Int16),
+        ],
+    );
+    assert!(frame.validate().unwrap_err().to_string().contains("overlap"));
+}
+
+#[test]
+fn fields_past_the_payload_are_rejected() {
+    let frame = FrameLayout::new("bad", vec![FieldLayout::new("a", 6, ValueType::Float)]);
+    assert!(frame.validate().is_err());
+
+    let frame = FrameLayout::new("ok", vec![FieldLayout::new("a", 4, ValueType::Float)]);
+    assert!(frame.validate().is_ok());
+}
+
+#[test]
+fn duplicate_field_names_are_rejected() {
+    let frame = FrameLayout::new(
+        "bad",
+        vec![
+            FieldLayout::new("a", 0, ValueType::Int8),
+            FieldLayout::new("a", 1, ValueType::Int8),
+        ],
+    );
+    assert!(frame.validate().unwrap_err().to_string().contains("duplicate"));
+}
+
+#[test]
+fn missing_or_renamed_fields_are_rejected() {
+    let mut layout = ProtocolLayout::default();
+    layout.angle_stream.fields.pop();
+    assert!(layout.validate().is_err());
+
+    let mut layout = ProtocolLayout::default();
+    layout.velocity_stream.fields[1].name = "speed".to_string();
+    assert!(layout.validate().is_err());
+
+    let mut layout = ProtocolLayout::default();
+    layout.register_header.fields[0].value_type = ValueType::Int16;
+    assert!(layout.validate().is_err());
+    assert!(EncodingPolicy::default().with_layout(layout).is_err());
+}
+
+#[test]
+fn layout_round_trips_through_json() {
+    let layout = ProtocolLayout::default();
+    let json = serde_json::to_string(&layout).unwrap();
+    assert_eq!(ProtocolLayout::from_json(&json).unwrap(), layout);
+}
+
+#[test]
+fn shifted_layout_is_supported_with_data_only() {
+    // A hypothetical firmware with the register byte first and big-endian fields
+    let json = r#"{
+        "endianness": "big",
+        "register_header": { "name": "register_header", "fields": [
+            { "name": "opcode", "offset": 1, "value_type": "int8" },
+            { "name": "register", "offset": 0, "value_type": "int8" }
+        ] },
+        "angle_stream": { "name": "angle_stream", "fields": [
+            { "name": "position", "offset": 2, "value_type": "int16" },
+            { "name": "max_velocity", "offset": 4, "value_type": "int16" },
+            { "name": "max_torque", "offset": 6, "value_type": "int16" }
+        ] },
+        "velocity_stream": { "name": "velocity_stream", "fields": [
+            { "name": "position", "offset": 0, "value_type": "int16" },
+            { "name": "velocity", "offset": 2, "value_type": "int16" },
+            { "name": "acceleration", "offset": 4, "value_type": "int16" }
+        ] }
+    }"#;
+    let policy = EncodingPolicy::new(0x50, true)
+        .with_layout(ProtocolLayout::from_json(json).unwrap())
+        .unwrap();
+
+    let mut float = [0x23, 0x0D, 0, 0, 0, 0, 0x50, 0x50];
+    float[2..6].copy_from_slice(&1.5f32.to_be_bytes());
+    assert_eq!(policy.write_float(Register::Kp, 1.5), float);
+
+    let stream = policy.angle_stream(0x0102, 0x0304, 0x0506);
+    assert_eq!(stream, [0x50, 0x50, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+    policy.validate(ANGLE_STREAM_ID, &stream).unwrap();
+
+    let reply = policy.parse_reply(&[0x01, 0x25, 0xFF, 0x38, 0x50, 0x50, 0x50, 0x50]).unwrap();
+    assert_eq!(reply.register, 0x01);
+    assert_eq!(reply.value_type, ValueType::Int16);
+    assert_eq!(reply.int(0), Some(-200));
+    assert_eq!(policy.layout().endianness, Endianness::Big);
+}
+
+#[test]
+fn default_reply_parsing_is_little_endian() {
+    let reply = protocol::parse_reply(&[0x25, 0x01, 0x38, 0xFF, 0x50, 0x50, 0x50, 0x50]).unwrap();
+    assert_eq!(reply.register, 0x01);
+    assert_eq!(reply.int(0), Some(-200));
+    assert_eq!(reply.float(0), Some(-200.0));
+}
+
+#[test]
+fn registers_the_reference_tools_do_not_use_are_unverified() {
+    let unverified: Vec<u8> = Register::ALL.into_iter().filter(|r| !r.is_verified()).map(Register::addr).collect();
+    assert_eq!(unverified, (0x59..=0x62).collect::<Vec<u8>>());
+    assert!(!Register::SetZero.info().verified && Register::Kp.info().verified);
+}
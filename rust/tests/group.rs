@@ -0,0 +1,40 @@
+//! Several group members commanded at once
+
+use livelybot_motor_control::{GroupSetpoint, LivelyMotorController, MockTransport, MotorGroup, RawFrame, SimMotor};
+use std::time::Duration;
+
+fn sent_frames(controller: &LivelyMotorController, send: impl FnOnce()) -> Vec<u32> {
+    let sent = controller.bus().subscribe_sent();
+    send();
+    let mut ids = Vec::new();
+    while let Some(frame) = sent.recv_timeout(Duration::from_millis(20)).unwrap() {
+        ids.push(RawFrame::from_frame(&frame).id);
+    }
+    ids
+}
+
+#[test]
+fn common_targets_of_the_whole_bus_are_broadcast() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default()).with_motor(2, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+
+    let group = MotorGroup::new(&controller, &[1, 2]).unwrap();
+    let ids = sent_frames(&controller, || {
+        assert_eq!(group.send_group_positions(&[(1, 10.0), (2, 10.0)], 1.0, 2.0).unwrap(), 2);
+    });
+    assert_eq!(ids, vec![0x190, 0x290]);
+
+    let group = MotorGroup::new(&controller, &[1, 2]).unwrap().with_broadcast();
+    let ids = sent_frames(&controller, || {
+        assert_eq!(group.send_group_positions(&[(1, 10.0), (2, 10.0)], 1.0, 2.0).unwrap(), 1);
+        assert_eq!(group.send_group_positions(&[(1, 10.0), (2, 20.0)], 1.0, 2.0).unwrap(), 2);
+    });
+    assert_eq!(ids, vec![0x090, 0x190, 0x290]);
+    assert!(matches!(group.setpoint(2), Some(GroupSetpoint::Angle { angle_deg, .. }) if angle_deg == 20.0));
+
+    // Nothing is sent when one target is not a member
+    let ids = sent_frames(&controller, || {
+        assert!(group.send_group_positions(&[(1, 0.0), (3, 0.0)], 1.0, 2.0).is_err());
+    });
+    assert!(ids.is_empty());
+}
@@ -0,0 +1,62 @@
+//! Setpoint shaping by virtual walls
+
+use livelybot_motor_control::{HapticBoundary, LivelyMotorController, MockTransport, SimMotor, VirtualWall};
+
+#[test]
+fn targets_beyond_a_free_wall_are_clamped() {
+    let wall = VirtualWall::upper(90.0, 10.0, 4.0);
+    let command = wall.shape(120.0, 50.0, 1.0);
+    assert_eq!((command.angle_deg, command.max_torque_nm, command.engagement), (90.0, 1.0, 0.0));
+
+    // Targets on the allowed side pass through
+    assert_eq!(wall.shape(60.0, 50.0, 1.0).angle_deg, 60.0);
+}
+
+#[test]
+fn stiffness_ramps_up_towards_the_wall() {
+    let wall = VirtualWall::upper(90.0, 10.0, 4.0);
+    assert_eq!(wall.engagement(79.0), 0.0);
+    assert_eq!(wall.engagement(85.0), 0.5);
+    assert_eq!(wall.engagement(90.0), 1.0);
+    assert_eq!(wall.engagement(95.0), 1.0);
+
+    // In the zone a target into the wall is pulled back to the zone edge
+    let command = wall.shape(120.0, 85.0, 1.0);
+    assert_eq!((command.angle_deg, command.max_torque_nm), (80.0, 2.5));
+    // A target already clear of the zone is kept
+    assert_eq!(wall.shape(70.0, 85.0, 1.0).angle_deg, 70.0);
+    assert_eq!(wall.shape(120.0, 95.0, 1.0).max_torque_nm, 4.0);
+    // The wall never lowers a torque limit above its own
+    assert_eq!(wall.shape(120.0, 95.0, 5.0).max_torque_nm, 5.0);
+}
+
+#[test]
+fn lower_walls_mirror_upper_walls() {
+    let wall = VirtualWall::lower(-30.0, 5.0, 3.0);
+    assert_eq!(wall.shape(-60.0, 0.0, 1.0).angle_deg, -30.0);
+    assert_eq!(wall.engagement(-27.5), 0.5);
+    assert_eq!(wall.shape(-60.0, -32.0, 1.0).angle_deg, -25.0);
+    assert_eq!(wall.shape(-60.0, -32.0, 1.0).max_torque_nm, 3.0);
+}
+
+#[test]
+fn boundary_keeps_the_joint_in_the_envelope() {
+    let boundary = HapticBoundary::new(-30.0, 90.0, 10.0, 4.0);
+    assert_eq!(boundary.shape(200.0, 0.0, 1.0).angle_deg, 90.0);
+    assert_eq!(boundary.shape(-200.0, 0.0, 1.0).angle_deg, -30.0);
+
+    // Only the upper wall is engaged near the top of the range
+    let command = boundary.shape(0.0, 85.0, 1.0);
+    assert_eq!((command.angle_deg, command.max_torque_nm, command.engagement), (0.0, 2.5, 0.5));
+}
+
+#[test]
+fn send_angle_sends_the_shaped_setpoint() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    let boundary = HapticBoundary::new(-30.0, 90.0, 10.0, 4.0);
+
+    let command = boundary.send_angle(&controller, 1, 200.0, 1.0, 1.0).unwrap();
+    assert_eq!((command.angle_deg, command.engagement), (90.0, 0.0));
+    assert!(boundary.send_angle(&controller, 2, 0.0, 1.0, 1.0).is_err(), "motor 2 is not on the bus");
+}
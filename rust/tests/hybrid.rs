@@ -0,0 +1,83 @@
+//! Position / torque crossfade
+
+use livelybot_motor_control::hybrid::TORQUE_MODE_LEAD_DEG;
+use livelybot_motor_control::{
+    HybridBlend, HybridGains, HybridTarget, LivelyMotorController, MockTransport, Register, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+fn setup() -> (MockTransport, LivelyMotorController) {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    controller.enable_motor(1).unwrap();
+    (mock, controller)
+}
+
+#[test]
+fn ends_of_the_blend() {
+    let (_mock, controller) = setup();
+    let gains = HybridGains::default();
+    let mut blend = HybridBlend::new(&controller, 1, 30.0, 4.0);
+
+    // Position control ignores the measured angle
+    let command = blend.command(10.0);
+    assert_eq!(command.torque_weight, 0.0);
+    assert_eq!((command.angle_deg, command.max_torque_nm, command.kd), (30.0, 4.0, gains.kd_position));
+
+    // Torque control leads the joint in the torque's direction
+    blend.blend_to(HybridTarget::Torque { torque_nm: -2.0 }, Duration::ZERO);
+    assert!(blend.is_settled());
+    let command = blend.command(10.0);
+    assert_eq!(command.torque_weight, 1.0);
+    assert_eq!((command.angle_deg, command.max_torque_nm), (10.0 - TORQUE_MODE_LEAD_DEG, 2.0));
+    assert!((command.kd - gains.kd_torque).abs() < 1e-6, "{}", command.kd);
+}
+
+#[test]
+fn crossfade_interpolates_over_its_duration() {
+    let (_mock, controller) = setup();
+    let mut blend = HybridBlend::new(&controller, 1, 0.0, 4.0);
+    blend.blend_to(HybridTarget::Torque { torque_nm: 1.0 }, Duration::from_millis(200));
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(!blend.is_settled());
+    let middle = blend.command(0.0);
+    assert!(middle.torque_weight > 0.2 && middle.torque_weight < 0.9, "{:?}", middle);
+    assert!(middle.max_torque_nm < 4.0 && middle.max_torque_nm > 1.0, "{:?}", middle);
+
+    // Releasing halfway starts from the current blend, not from full torque
+    blend.blend_to(HybridTarget::Position { angle_deg: 5.0, max_torque_nm: 4.0 }, Duration::from_millis(200));
+    let weight = blend.torque_weight();
+    assert!(weight > 0.2 && weight < 1.0, "{}", weight);
+    thread::sleep(Duration::from_millis(250));
+    assert!(blend.is_settled());
+    assert_eq!(blend.command(0.0).angle_deg, 5.0);
+}
+
+#[test]
+fn step_writes_gains_and_streams_the_blend() {
+    let (mock, controller) = setup();
+    let gains = HybridGains {
+        kp: 2.0,
+        kd_position: 0.5,
+        kd_torque: 0.25,
+    };
+    let mut blend = HybridBlend::new(&controller, 1, 20.0, 4.0).with_gains(gains).with_max_velocity(5.0);
+
+    let command = blend.step().unwrap();
+    assert_eq!(command.angle_deg, 20.0);
+    assert_eq!(controller.read_register_float(1, Register::Kp).unwrap(), 2.0);
+    assert_eq!(controller.read_register_float(1, Register::Kd).unwrap(), 0.5);
+
+    blend.blend_to(HybridTarget::Torque { torque_nm: 0.5 }, Duration::ZERO);
+    blend.step().unwrap();
+    assert_eq!(controller.read_register_float(1, Register::Kd).unwrap(), 0.25);
+
+    // Pushing in the positive direction moves the joint that way
+    for _ in 0..20 {
+        blend.step().unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(mock.state(1).unwrap().velocity_rps > 0.0);
+}
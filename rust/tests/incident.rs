@@ -0,0 +1,59 @@
+//! Flight recorder incidents cover every joint around a safety event
+
+use livelybot_motor_control::{
+    EventBus, EventKind, FaultAction, FaultClass, FlightRecorder, FlightRecorderConfig, Incident, MotorState,
+    StateCache,
+};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn state(motor_id: u8, position_deg: f64) -> MotorState {
+    MotorState {
+        motor_id,
+        position_deg,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn safety_event_writes_all_joints_before_and_after_it() {
+    let dir = std::env::temp_dir().join(format!("incidents-{}", std::process::id()));
+    let cache = Arc::new(StateCache::new());
+    let events = EventBus::new();
+    let config = FlightRecorderConfig::new(&dir)
+        .with_window(Duration::from_millis(200), Duration::from_millis(100))
+        .with_sample_period(Duration::from_millis(5));
+    let mut recorder = FlightRecorder::start(Arc::clone(&cache), &events, config).unwrap();
+
+    // Not a safety event: nothing is written
+    events.publish(EventKind::BatteryDerating {
+        voltage_v: 44.0,
+        torque_scale: 0.9,
+    });
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(400) {
+        cache.publish(&state(1, 10.0));
+        cache.publish(&state(2, 20.0));
+        thread::sleep(Duration::from_millis(2));
+    }
+    events.publish(EventKind::MotorFault {
+        motor_id: 2,
+        code: Some(3),
+        class: FaultClass::Driver,
+        action: FaultAction::Stop,
+    });
+    thread::sleep(Duration::from_millis(250));
+    recorder.stop();
+
+    let written = recorder.incidents();
+    assert_eq!(written.len(), 1, "{:?}", recorder.last_error());
+    let incident = Incident::load(&written[0]).unwrap();
+    assert!(incident.trigger.contains("MotorFault"));
+    let first = incident.samples.first().unwrap();
+    let last = incident.samples.last().unwrap();
+    assert!(first.time_s < -0.15 && first.time_s >= -0.2);
+    assert!(last.time_s >= 0.1);
+    assert!(incident.samples.iter().all(|s| s.joints.len() == 2));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
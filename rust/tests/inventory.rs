@@ -0,0 +1,41 @@
+//! Joint inventory against simulated motors
+
+use livelybot_motor_control::{
+    JointInventory, JointMap, JointMapping, JointStatus, LivelyMotorController, MockTransport, SimMotor,
+};
+
+#[test]
+fn swapped_motors_are_detected_and_remapped() {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default().with_serial_number(1001))
+        .with_motor(2, SimMotor::default().with_serial_number(1002));
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    let joint = |name: &str, motor_id| JointMap { name: name.into(), motor_id, reversed: false, offset_deg: 0.0 };
+    let mapping = JointMapping { joints: vec![joint("left_knee", 1), joint("right_knee", 2)] };
+
+    let inventory = JointInventory::record(&controller, &mapping).unwrap();
+    assert_eq!(inventory.joints[1].serial_number, 1002);
+    assert!(inventory.check(&[&controller]).unwrap().is_ok());
+
+    // Plugged back in the wrong way round
+    let left = mock.remove_motor(1).unwrap();
+    let right = mock.remove_motor(2).unwrap();
+    mock.add_motor(1, right);
+    mock.add_motor(2, left);
+
+    let report = inventory.check(&[&controller]).unwrap();
+    assert_eq!(report.joints[0].status, JointStatus::Moved { channel: "mock".into(), motor_id: 2 });
+    assert_eq!(report.joints[0].occupant.as_deref(), Some("right_knee"));
+    assert!(report.ensure_ok().is_err());
+    let remapped = report.remap(&mapping).unwrap();
+    assert_eq!((remapped.joints[0].motor_id, remapped.joints[1].motor_id), (2, 1));
+    assert!(report.remapped_inventory().check(&[&controller]).unwrap().is_ok());
+
+    // A repaired actuator with a new serial is not remapped
+    mock.remove_motor(2);
+    mock.add_motor(2, SimMotor::default().with_serial_number(2001));
+    let report = inventory.check(&[&controller]).unwrap();
+    assert_eq!(report.joints[0].status, JointStatus::Missing);
+    assert_eq!(report.joints[1].status, JointStatus::Moved { channel: "mock".into(), motor_id: 1 });
+    assert!(report.remap(&mapping).is_err());
+}
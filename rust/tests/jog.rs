@@ -0,0 +1,69 @@
+//! Torque-limited jog against a simulated motor
+
+use livelybot_motor_control::jog::JOG_MAX_TORQUE;
+use livelybot_motor_control::{JogDirection, JogStatus, LivelyMotorController, MockTransport, Register, SimMotor};
+use std::thread;
+use std::time::Duration;
+
+fn setup() -> (MockTransport, LivelyMotorController) {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    (mock, controller)
+}
+
+#[test]
+fn jog_moves_while_held_and_clamps_the_cap() {
+    let (mock, controller) = setup();
+    let mut jog = controller.jog(1, JogDirection::Negative, 5.0, 10.0).unwrap();
+    let cap = controller.read_register_float(1, Register::TorqueLimit).unwrap();
+    assert_eq!(cap as f64, JOG_MAX_TORQUE);
+
+    for _ in 0..25 {
+        jog.hold();
+        assert!(matches!(jog.step().unwrap(), JogStatus::Moving { .. }));
+        thread::sleep(Duration::from_millis(20));
+    }
+    let state = mock.state(1).unwrap();
+    assert!(state.velocity_rps < -0.5, "{:?}", state);
+    assert!(state.velocity_rps >= -1.0 - 1e-3, "speed not clamped: {:?}", state);
+}
+
+#[test]
+fn releasing_the_key_stops_the_jog() {
+    let (_mock, controller) = setup();
+    let mut jog = controller.jog(1, JogDirection::Positive, 0.5, 1.0).unwrap();
+    jog.set_deadman(Duration::from_millis(50));
+    jog.hold();
+    assert!(matches!(jog.step().unwrap(), JogStatus::Moving { .. }));
+
+    thread::sleep(Duration::from_millis(80));
+    assert!(jog.is_released());
+    assert_eq!(jog.step().unwrap(), JogStatus::Released);
+    assert!(jog.is_stopped());
+    assert_eq!(jog.step().unwrap(), JogStatus::Stopped);
+    assert!(!controller.enabled_motors().contains(&1));
+}
+
+#[test]
+fn measured_torque_above_the_cap_stops_the_jog() {
+    let (mock, controller) = setup();
+    let mut jog = controller.jog(1, JogDirection::Positive, 1.0, 0.2).unwrap();
+    // A motor that lost its torque limit: only the host-side check is left
+    controller.write_register_float(1, Register::TorqueLimit, 3.0).unwrap();
+    mock.set_load(1, -5.0);
+
+    let mut status = JogStatus::Stopped;
+    for _ in 0..50 {
+        jog.hold();
+        status = jog.step().unwrap();
+        if !matches!(status, JogStatus::Moving { .. }) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    match status {
+        JogStatus::Overload { torque_nm } => assert!(torque_nm.abs() > 0.2),
+        other => panic!("expected overload, got {:?}", other),
+    }
+    assert!(jog.is_stopped());
+}
@@ -0,0 +1,62 @@
+//! Planar forward and inverse kinematics
+
+use livelybot_motor_control::{Elbow, JointLimits, Planar2Link, Planar3Link, Point2, Pose2};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn forward_at_known_angles() {
+    let arm = Planar2Link::new(0.3, 0.2);
+    let stretched = arm.forward([0.0, 0.0]);
+    assert!(close(stretched.x, 0.5) && close(stretched.y, 0.0));
+    let bent = arm.forward([90.0, -90.0]);
+    assert!(close(bent.x, 0.2) && close(bent.y, 0.3), "{:?}", bent);
+
+    let wrist = Planar3Link::new(0.3, 0.2, 0.1).forward([90.0, 90.0, 90.0]);
+    assert!(close(wrist.x, -0.2) && close(wrist.y, 0.2) && close(wrist.phi_deg, -90.0), "{:?}", wrist);
+}
+
+#[test]
+fn inverse_then_forward_returns_the_point() {
+    let arm = Planar2Link::new(0.3, 0.2);
+    for &(x, y) in &[(0.4, 0.1), (-0.2, 0.3), (0.15, -0.35), (0.0, 0.45)] {
+        let target = Point2 { x, y };
+        let up = arm.inverse(target, Elbow::Up).unwrap();
+        let down = arm.inverse(target, Elbow::Down).unwrap();
+        assert!(up[1] > 0.0 && down[1] < 0.0, "{:?} {:?}", up, down);
+        for joints in [up, down] {
+            let reached = arm.forward(joints);
+            assert!(close(reached.x, x) && close(reached.y, y), "{:?} -> {:?}", target, reached);
+        }
+    }
+
+    let arm = Planar3Link::new(0.3, 0.2, 0.1);
+    let target = Pose2 { x: 0.35, y: 0.2, phi_deg: -30.0 };
+    for elbow in [Elbow::Up, Elbow::Down] {
+        let reached = arm.forward(arm.inverse(target, elbow).unwrap());
+        assert!(close(reached.x, target.x) && close(reached.y, target.y), "{:?}", reached);
+        assert!(close(reached.phi_deg, target.phi_deg), "{:?}", reached);
+    }
+}
+
+#[test]
+fn unreachable_targets_are_rejected() {
+    let arm = Planar2Link::new(0.3, 0.2);
+    let err = arm.inverse(Point2 { x: 0.6, y: 0.0 }, Elbow::Up).unwrap_err();
+    assert!(err.to_string().contains("out of reach"), "{}", err);
+    assert!(arm.inverse(Point2 { x: 0.05, y: 0.0 }, Elbow::Up).is_err(), "inside the inner radius");
+}
+
+#[test]
+fn joint_limits_select_the_elbow() {
+    let arm = Planar2Link::new(0.3, 0.2).with_limits([JointLimits::default(), JointLimits::new(-150.0, 0.0)]);
+    let target = Point2 { x: 0.3, y: 0.2 };
+
+    let err = arm.inverse(target, Elbow::Up).unwrap_err();
+    assert!(err.to_string().contains("joint 2"), "{}", err);
+    let joints = arm.inverse_any(target, Elbow::Up).unwrap();
+    assert!(joints[1] < 0.0);
+    assert_eq!(joints, arm.inverse(target, Elbow::Down).unwrap());
+}
@@ -0,0 +1,80 @@
+//! Motor lifecycle transitions, on their own and driven against simulated motors
+
+use livelybot_motor_control::{
+    LivelyMotorController, ManagedMotor, MockTransport, MotorLifecycle, MotorSettings, SimMotor,
+};
+use MotorLifecycle::*;
+
+const STATES: [MotorLifecycle; 5] = [Offline, Discovered, Configured, Enabled, Faulted];
+
+#[test]
+fn transition_table() {
+    let allowed = [
+        (Offline, Discovered),
+        (Discovered, Offline),
+        (Discovered, Configured),
+        (Configured, Configured),
+        (Configured, Enabled),
+        (Configured, Offline),
+        (Enabled, Configured),
+        (Enabled, Offline),
+        (Faulted, Offline),
+    ];
+    for from in STATES {
+        for to in STATES {
+            let expected = to == Faulted || allowed.contains(&(from, to));
+            assert_eq!(from.can_transition_to(to), expected, "{} -> {}", from, to);
+        }
+    }
+}
+
+#[test]
+fn motor_walks_the_lifecycle() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    let mut motor = ManagedMotor::new(&controller, 1);
+
+    assert!(motor.set_angle(10.0, 1.0, 1.0).is_err());
+    assert!(motor.enable().is_err(), "enabling an undiscovered motor");
+    assert_eq!(motor.discover().unwrap(), Discovered);
+    motor.configure(MotorSettings::default()).unwrap();
+    motor.enable().unwrap();
+    assert_eq!(motor.state(), Enabled);
+    motor.set_angle(10.0, 1.0, 1.0).unwrap();
+
+    // Still answering: rediscovery keeps the motor enabled
+    assert_eq!(motor.discover().unwrap(), Enabled);
+    motor.disable().unwrap();
+    assert_eq!(motor.state(), Configured);
+}
+
+#[test]
+fn enabled_motor_dropping_off_the_bus_goes_offline() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    let mut motor = ManagedMotor::new(&controller, 1);
+    motor.discover().unwrap();
+    motor.configure(MotorSettings::default()).unwrap();
+    motor.enable().unwrap();
+
+    mock.remove_motor(1);
+    assert_eq!(motor.discover().unwrap(), Offline);
+    assert!(motor.set_angle(0.0, 1.0, 1.0).is_err());
+    assert!(motor.enable().is_err(), "must be rediscovered and configured");
+}
+
+#[test]
+fn faulted_motor_needs_a_reset() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    let mut motor = ManagedMotor::new(&controller, 1);
+    motor.discover().unwrap();
+    motor.fault("test").unwrap();
+    assert_eq!(motor.fault_reason(), Some("test"));
+
+    assert_eq!(motor.discover().unwrap(), Faulted);
+    assert!(motor.configure(MotorSettings::default()).is_err());
+    motor.reset().unwrap();
+    assert_eq!((motor.state(), motor.fault_reason()), (Offline, None));
+    assert_eq!(motor.discover().unwrap(), Discovered);
+}
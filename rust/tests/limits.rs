@@ -0,0 +1,103 @@
+//! Software joint limits
+
+use livelybot_motor_control::{
+    LimitMode, Limits, LivelyMotorController, MockTransport, MotorError, RawFrame, SimMotor, MAGIC_POS,
+};
+use std::time::Duration;
+
+/// int16 fields of the one stream frame `send` sends
+fn sent_fields(controller: &LivelyMotorController, send: impl FnOnce()) -> [i16; 3] {
+    let sent = controller.bus().subscribe_sent();
+    send();
+    let frame = RawFrame::from_frame(&sent.recv_timeout(Duration::from_millis(20)).unwrap().unwrap());
+    [0, 2, 4].map(|i| i16::from_le_bytes([frame.data[i], frame.data[i + 1]]))
+}
+
+#[test]
+fn out_of_range_targets_are_clamped_or_rejected() {
+    let clamp = Limits::position(-90.0, 45.0).with_max_velocity(2.0).with_max_torque(5.0);
+    clamp.validate().unwrap();
+    assert_eq!(clamp.position_deg(1, 60.0).unwrap(), 45.0);
+    assert_eq!(clamp.position_deg(1, -10.0).unwrap(), -10.0);
+    assert_eq!(clamp.velocity_rps(1, -3.0).unwrap(), -2.0);
+    assert_eq!(clamp.torque_nm(1, 7.5).unwrap(), 5.0);
+
+    // Already at the upper end stop: only motion back into range is allowed
+    assert_eq!(clamp.velocity_at(1, 1.0, Some(46.0)).unwrap(), 0.0);
+    assert_eq!(clamp.velocity_at(1, -1.0, Some(46.0)).unwrap(), -1.0);
+    assert_eq!(clamp.velocity_at(1, 1.0, None).unwrap(), 1.0);
+
+    let reject = clamp.with_mode(LimitMode::Reject);
+    let error = reject.position_deg(4, 60.0).unwrap_err();
+    match MotorError::of(&error) {
+        Some(MotorError::LimitExceeded { motor_id, reason, .. }) => {
+            assert_eq!(*motor_id, 4);
+            assert!(reason.contains("position 60°"), "{}", reason);
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert!(reject.velocity_at(4, 1.0, Some(50.0)).is_err());
+}
+
+#[test]
+fn broadcast_limits_are_the_intersection() {
+    let a = Limits::position(-90.0, 45.0).with_max_velocity(2.0);
+    let b = Limits::position(-30.0, 90.0).with_max_torque(4.0).with_mode(LimitMode::Reject);
+    let both = a.intersect(&b);
+    assert_eq!(both.min_pos_deg, Some(-30.0));
+    assert_eq!(both.max_pos_deg, Some(45.0));
+    assert_eq!(both.max_vel_rps, Some(2.0));
+    assert_eq!(both.max_torque_nm, Some(4.0));
+    assert_eq!(both.mode, LimitMode::Reject);
+
+    assert!(Limits::position(10.0, -10.0).validate().is_err());
+    assert!(Limits::default().with_max_velocity(f64::NAN).validate().is_err());
+}
+
+#[test]
+fn non_finite_targets_are_rejected_in_both_modes() {
+    let clamp = Limits::position(-90.0, 45.0).with_max_velocity(2.0).with_max_torque(5.0);
+    for limits in [clamp, clamp.with_mode(LimitMode::Reject), Limits::default()] {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            for error in [
+                limits.position_deg(3, value).unwrap_err(),
+                limits.velocity_rps(3, value).unwrap_err(),
+                limits.torque_nm(3, value).unwrap_err(),
+                limits.velocity_at(3, value, Some(0.0)).unwrap_err(),
+            ] {
+                match MotorError::of(&error) {
+                    Some(MotorError::LimitExceeded { motor_id: 3, reason, .. }) => {
+                        assert!(reason.contains("not a finite number"), "{}", reason)
+                    }
+                    other => panic!("unexpected error {:?}", other),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn raw_fields_within_the_limits_pass_unchanged() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    controller
+        .set_limits(1, Limits::position(-90.0, 45.0).with_max_velocity(2.0).with_max_torque(5.0))
+        .unwrap();
+
+    for (angle, max_vel, max_tqe) in [(1, 1, 1), (-2499, 7999, 999), (1249, -3, 17)] {
+        let fields = sent_fields(&controller, || controller.send_angle_command_to(1, angle, max_vel, max_tqe).unwrap());
+        assert_eq!(fields, [angle, max_vel, max_tqe]);
+    }
+    let fields = sent_fields(&controller, || controller.send_velocity_command_to(1, 100, -7, 50).unwrap());
+    assert_eq!(fields, [100, -7, 50]);
+    let fields = sent_fields(&controller, || controller.send_velocity_command_to(1, MAGIC_POS, 5, 50).unwrap());
+    assert_eq!(fields, [MAGIC_POS, 5, 50]);
+
+    // Only the limited fields are re-encoded: 45°, 2 r/s and 5 Nm
+    let fields = sent_fields(&controller, || controller.send_angle_command_to(1, 5000, 9000, 17).unwrap());
+    assert_eq!(fields, [1250, 8000, 17]);
+    let fields = sent_fields(&controller, || controller.send_angle_command_to(1, 3, -9000, 1200).unwrap());
+    assert_eq!(fields, [3, -8000, 1000]);
+    let fields = sent_fields(&controller, || controller.send_velocity_command_to(1, 100, 10000, 50).unwrap());
+    assert_eq!(fields, [100, 8000, 50]);
+}
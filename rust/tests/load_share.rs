@@ -0,0 +1,74 @@
+//! Torque distribution across motors sharing a load
+
+use livelybot_motor_control::{LoadShare, ThermalDerating, TorqueEnvelope};
+use std::time::{Duration, Instant};
+
+fn envelope() -> TorqueEnvelope {
+    TorqueEnvelope::new(1.0, 3.0, Duration::from_secs(1))
+}
+
+fn torques(share: &mut LoadShare, torque_nm: f64, now: Instant) -> Vec<f64> {
+    share.distribute(torque_nm, now).commands.iter().map(|c| c.torque_nm).collect()
+}
+
+#[test]
+fn thermal_derating_is_linear() {
+    let derating = ThermalDerating::default();
+    assert_eq!(derating.scale(25.0), 1.0);
+    assert_eq!(derating.scale(60.0), 1.0);
+    assert_eq!(derating.scale(72.5), 0.5);
+    assert_eq!(derating.scale(90.0), 0.0);
+
+    // A start above the maximum is a hard cut-off
+    let cutoff = ThermalDerating::new(90.0, 80.0);
+    assert_eq!((cutoff.scale(79.0), cutoff.scale(80.0)), (1.0, 0.0));
+}
+
+#[test]
+fn equal_motors_share_equally() {
+    let mut share = LoadShare::new(&[(1, envelope()), (2, envelope())]);
+    assert_eq!(share.capacities(), vec![(1, 3.0), (2, 3.0)]);
+
+    let split = share.distribute(-4.0, Instant::now());
+    let commands: Vec<_> = split.commands.iter().map(|c| (c.motor_id, c.weight, c.torque_nm)).collect();
+    assert_eq!(commands, [(1, 0.5, -2.0), (2, 0.5, -2.0)]);
+    assert_eq!((split.delivered_nm, split.saturated), (-4.0, false));
+}
+
+#[test]
+fn a_hot_motor_hands_load_to_its_partner() {
+    let mut share = LoadShare::new(&[(1, envelope()), (2, envelope())]);
+    assert!(share.set_temperature(1, 72.5));
+    assert!(!share.set_temperature(9, 20.0));
+    assert_eq!(share.capacities(), vec![(1, 1.5), (2, 3.0)]);
+
+    let split = torques(&mut share, 3.0, Instant::now());
+    assert!((split[0] - 1.0).abs() < 1e-12 && (split[1] - 2.0).abs() < 1e-12, "{:?}", split);
+}
+
+#[test]
+fn efforts_beyond_the_group_saturate() {
+    let mut share = LoadShare::new(&[(1, envelope()), (2, envelope())]);
+    let split = share.distribute(10.0, Instant::now());
+    assert_eq!((split.delivered_nm, split.saturated), (6.0, true));
+}
+
+#[test]
+fn used_peak_budget_shrinks_capacity() {
+    let mut share = LoadShare::new(&[(1, envelope()), (2, envelope())]);
+    let start = Instant::now();
+    // 2 Nm per motor is above continuous, so the 1 s budget drains
+    for tick in 0..4u32 {
+        torques(&mut share, 4.0, start + Duration::from_millis(125) * tick);
+    }
+    let capacities = share.capacities();
+    assert_eq!(capacities, vec![(1, 2.25), (2, 2.25)], "3/8 of the budget used");
+
+    for tick in 4..10u32 {
+        torques(&mut share, 4.0, start + Duration::from_millis(125) * tick);
+    }
+    // Used up: only the continuous torque is left
+    assert_eq!(share.capacities(), vec![(1, 1.0), (2, 1.0)]);
+    let split = share.distribute(4.0, start + Duration::from_millis(1250));
+    assert_eq!((split.delivered_nm, split.saturated), (2.0, true));
+}
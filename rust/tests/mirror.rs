@@ -0,0 +1,84 @@
+//! Master/slave mirroring on simulated motors
+
+use livelybot_motor_control::protocol::{stream_target, ANGLE_STREAM_ID};
+use livelybot_motor_control::{
+    degrees_to_position, nm_to_torque, LivelyMotorController, Mirror, MirrorLink, MockTransport, RawFrame, SimMotor,
+};
+use std::sync::atomic::AtomicBool;
+
+fn setup() -> (MockTransport, LivelyMotorController) {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(2, SimMotor::default())
+        .with_motor(3, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    for motor_id in 1..=3 {
+        controller.enable_motor(motor_id).unwrap();
+    }
+    (mock, controller)
+}
+
+#[test]
+fn links_map_angles_both_ways() {
+    let reversed = MirrorLink::new(1, 2).scaled(-1.0, 10.0);
+    assert_eq!(reversed.slave_angle(40.0), -30.0);
+    assert_eq!(reversed.master_angle(-30.0), 40.0);
+    let geared = MirrorLink::new(1, 2).scaled(0.5, 0.0);
+    assert_eq!(geared.slave_angle(40.0), 20.0);
+    assert_eq!(geared.master_angle(20.0), 40.0);
+    assert_eq!(MirrorLink::new(1, 2).scaled(0.0, 5.0).master_angle(5.0), 0.0);
+}
+
+#[test]
+fn slaves_follow_the_master_including_a_reversed_joint() {
+    let (mock, controller) = setup();
+    let links = [MirrorLink::new(1, 2), MirrorLink::new(1, 3).scaled(-1.0, 10.0)];
+    let mut mirror = Mirror::new(&controller, &links);
+    let running = AtomicBool::new(true);
+
+    // The operator moves the master to 40° while the slaves mirror it
+    mirror
+        .run(100.0, &running, |stats| {
+            controller.send_angle_command_to(1, degrees_to_position(40.0), 0, 0)?;
+            Ok(stats.cycles < 300)
+        })
+        .unwrap();
+
+    let master = mock.state(1).unwrap().position_deg;
+    assert!((master - 40.0).abs() < 1.0, "{}", master);
+    assert!((mock.state(2).unwrap().position_deg - master).abs() < 1.5, "{:?}", mock.state(2));
+    assert!((mock.state(3).unwrap().position_deg - (10.0 - master)).abs() < 1.5, "{:?}", mock.state(3));
+
+    // One delay sample per link and cycle: 150 cycles
+    let stats = mirror.stats();
+    assert_eq!(stats.cycles, 300);
+    assert!(stats.last_delay <= stats.max_delay);
+    assert!(stats.mean_delay() <= stats.max_delay && stats.mean_delay() > std::time::Duration::ZERO);
+}
+
+#[test]
+fn bilateral_links_pull_the_master_with_the_feedback_torque() {
+    let (_mock, controller) = setup();
+    let link = MirrorLink::new(1, 3).scaled(-1.0, 10.0).bilateral(0.5);
+    let mut mirror = Mirror::new(&controller, &[link]);
+
+    let sent = controller.bus().subscribe_sent();
+    mirror.step().unwrap();
+    let commands: Vec<(u8, [i16; 3])> = std::iter::from_fn(|| sent.try_recv())
+        .map(|f| RawFrame::from_frame(&f))
+        .filter_map(|f| {
+            let (stream, motor_id) = stream_target(f.id)?;
+            let field = |i: usize| i16::from_le_bytes([f.data[i], f.data[i + 1]]);
+            (stream == ANGLE_STREAM_ID).then(|| (motor_id, [field(0), field(2), field(4)]))
+        })
+        .collect();
+
+    // The slave first, at the link's limits, then the master at the feedback torque
+    assert_eq!(commands.iter().map(|c| c.0).collect::<Vec<_>>(), [3, 1]);
+    assert_eq!(commands[0].1[2], nm_to_torque(link.max_torque_nm));
+    assert_eq!(commands[1].1[2], nm_to_torque(0.5));
+    // Both at rest at 0°: the slave is sent to 10° and the master pulled to
+    // where the slave, still at rest, maps to
+    assert_eq!(commands[0].1[0], degrees_to_position(10.0));
+    assert!((commands[1].1[0] - degrees_to_position(10.0)).abs() <= 1, "{:?}", commands);
+}
@@ -0,0 +1,63 @@
+//! MIT impedance frames
+
+use livelybot_motor_control::{
+    LivelyMotorController, MitCommand, MitRanges, MockTransport, MotorError, RawFrame, SimMotor,
+};
+use std::time::Duration;
+
+fn sent_frames(controller: &LivelyMotorController, send: impl FnOnce()) -> Vec<RawFrame> {
+    let sent = controller.bus().subscribe_sent();
+    send();
+    let mut frames = Vec::new();
+    while let Some(frame) = sent.recv_timeout(Duration::from_millis(20)).unwrap() {
+        frames.push(RawFrame::from_frame(&frame));
+    }
+    frames
+}
+
+#[test]
+fn commands_round_trip_through_the_bit_fields() {
+    let ranges = MitRanges::default();
+    let command = MitCommand {
+        position_deg: 90.0,
+        velocity_rps: -1.0,
+        kp: 100.0,
+        kd: 1.0,
+        torque_nm: 2.5,
+    };
+    let decoded = MitCommand::decode(&command.encode(&ranges), &ranges).unwrap();
+    assert!((decoded.position_deg - 90.0).abs() < 0.03, "{:?}", decoded);
+    assert!((decoded.torque_nm - 2.5).abs() < 0.01, "{:?}", decoded);
+
+    // Out of range values are clamped to the field ends
+    let far = MitCommand { position_deg: 1.0e6, ..command };
+    assert_eq!(MitCommand::decode(&far.encode(&ranges), &ranges).unwrap().position_deg, 720.0);
+}
+
+#[test]
+fn non_finite_values_send_nothing() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    controller.enable_motor(1).unwrap();
+
+    let frames = sent_frames(&controller, || {
+        controller.send_mit_command(1, 10.0, 0.0, 20.0, 0.5, 0.0).unwrap();
+    });
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].id, 0x1B0);
+
+    let bad = [
+        [f64::NAN, 0.0, 20.0, 0.5, 0.0],
+        [10.0, f64::INFINITY, 20.0, 0.5, 0.0],
+        [10.0, 0.0, f64::NAN, 0.5, 0.0],
+        [10.0, 0.0, 20.0, f64::NEG_INFINITY, 0.0],
+        [10.0, 0.0, 20.0, 0.5, f64::NAN],
+    ];
+    for [position, velocity, kp, kd, torque] in bad {
+        let frames = sent_frames(&controller, || {
+            let err = controller.send_mit_command(1, position, velocity, kp, kd, torque).unwrap_err();
+            assert!(matches!(err, MotorError::LimitExceeded { motor_id: 1, .. }), "{}", err);
+        });
+        assert!(frames.is_empty(), "{:?}", frames);
+    }
+}
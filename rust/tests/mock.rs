@@ -0,0 +1,53 @@
+//! Controller against simulated motors
+
+use livelybot_motor_control::{
+    degrees_to_position, nm_to_torque, rps_to_velocity, LivelyMotorController, MockTransport, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn simulated_motors_answer_pings_and_register_reads() {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default().with_name("ABC"))
+        .with_motor(3, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+
+    let info = controller.ping_motor(1).unwrap();
+    assert!(info.is_online);
+    assert_eq!((info.name.as_str(), info.hardware_version.as_str()), ("ABC", "1.00"));
+    assert!(!controller.ping_motor(2).unwrap().is_online);
+    let motors = controller.scan_range(1, 4).unwrap();
+    let online: Vec<u8> = motors.iter().filter(|m| m.is_online).map(|m| m.motor_id).collect();
+    assert_eq!(online, vec![1, 3]);
+
+    controller.set_feedback_period(3, 100.0).unwrap();
+    assert_eq!(controller.feedback_rate(3).unwrap(), 100.0);
+
+    mock.set_fault(1, 7);
+    assert_eq!(controller.read_fault(1).unwrap(), 7);
+    assert_eq!(controller.read_error_log(1).unwrap()[0].code, 7);
+}
+
+#[test]
+fn enabled_motor_follows_angle_commands() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+
+    controller.enable_motor(1).unwrap();
+    controller
+        .send_angle_command_to(1, degrees_to_position(90.0), rps_to_velocity(2.0), nm_to_torque(5.0))
+        .unwrap();
+    thread::sleep(Duration::from_millis(600));
+
+    let state = controller.read_motor_state(1).unwrap();
+    assert!((state.position_deg - 90.0).abs() < 1.0, "at {} degrees", state.position_deg);
+    assert!(state.velocity_rps.abs() < 0.05);
+    assert!((mock.state(1).unwrap().position_deg - state.position_deg).abs() < 1.0);
+
+    // Limp once disabled: the damping alone slows a pushed joint
+    controller.disable_motor(1).unwrap();
+    mock.set_load(1, 0.5);
+    thread::sleep(Duration::from_millis(100));
+    assert!(mock.state(1).unwrap().position_deg > 91.0);
+}
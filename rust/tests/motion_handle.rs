@@ -0,0 +1,49 @@
+//! Motion handles: pause, cancel and outcome reporting of background motions
+
+use anyhow::anyhow;
+use livelybot_motor_control::{MotionHandle, MotionOutcome};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn completed_and_failed_motions_report_their_outcome() {
+    assert_eq!(MotionHandle::spawn(|_| Ok(())).wait(), MotionOutcome::Completed);
+    assert_eq!(
+        MotionHandle::spawn(|_| Err(anyhow!("stage did not settle"))).wait(),
+        MotionOutcome::Failed("stage did not settle".to_string())
+    );
+    assert!(matches!(MotionHandle::spawn(|_| panic!("boom")).wait(), MotionOutcome::Failed(_)));
+}
+
+#[test]
+fn paused_motion_stops_advancing_until_resumed_or_cancelled() {
+    let steps = Arc::new(AtomicU64::new(0));
+    let mut handle = {
+        let steps = Arc::clone(&steps);
+        MotionHandle::spawn(move |signals| {
+            while !signals.is_cancelled() {
+                if !signals.is_paused() {
+                    steps.fetch_add(1, Ordering::SeqCst);
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            Ok(())
+        })
+    };
+
+    handle.pause();
+    thread::sleep(Duration::from_millis(20));
+    let held = steps.load(Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(steps.load(Ordering::SeqCst), held);
+    assert!(!handle.is_finished());
+
+    handle.resume();
+    thread::sleep(Duration::from_millis(20));
+    assert!(steps.load(Ordering::SeqCst) > held);
+
+    handle.cancel();
+    assert_eq!(handle.wait(), MotionOutcome::Cancelled);
+}
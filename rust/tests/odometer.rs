@@ -0,0 +1,99 @@
+//! Usage odometer counters and their persistence
+
+use livelybot_motor_control::events::{EnvelopeEventKind, Event, EventKind};
+use livelybot_motor_control::{MotorState, Odometer};
+use std::f64::consts::PI;
+use std::time::{Duration, Instant};
+
+fn state(motor_id: u8, velocity_rps: f64, torque_nm: f64) -> MotorState {
+    MotorState {
+        motor_id,
+        position_deg: 0.0,
+        velocity_rps,
+        torque_nm,
+        acceleration_rps2: None,
+    }
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("livelybot-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn samples_integrate_revolutions_energy_and_time() {
+    let path = temp_path("usage-integrate.json");
+    let mut odometer = Odometer::open(&path).unwrap();
+    assert!(!odometer.record(&state(1, 1.0, 1.0), true, Instant::now()), "untracked motor");
+    odometer.track(1, "SN-001");
+    assert_eq!(odometer.serial(1), Some("SN-001"));
+
+    let start = Instant::now();
+    let step = Duration::from_millis(250);
+    for i in 0..4u32 {
+        // Reverse direction halfway: revolutions count both ways
+        let velocity = if i < 2 { 2.0 } else { -2.0 };
+        assert!(odometer.record(&state(1, velocity, 1.5), i < 3, start + step * i));
+    }
+    let usage = odometer.usage(1).unwrap();
+    assert_eq!(usage.revolutions, 1.5);
+    assert!((usage.energy_j - 4.5 * PI).abs() < 1e-9, "{:?}", usage);
+    assert_eq!(usage.enabled_s, 0.5, "the last interval was disabled");
+
+    // A gap longer than a second is not integrated
+    odometer.record(&state(1, 2.0, 1.5), true, start + Duration::from_secs(5));
+    assert_eq!(odometer.usage(1).unwrap().revolutions, 1.5);
+}
+
+#[test]
+fn overloads_count_rising_edges_and_limited_events() {
+    let path = temp_path("usage-overload.json");
+    let mut odometer = Odometer::open(&path).unwrap().with_overload_torque(3.0);
+    odometer.track(2, "SN-002");
+
+    let now = Instant::now();
+    for torque in [1.0, 3.5, 4.0, 1.0, -3.5] {
+        odometer.record(&state(2, 0.0, torque), true, now);
+    }
+    assert_eq!(odometer.usage(2).unwrap().overload_events, 2);
+
+    let limited = |motor_id, kind| Event {
+        timestamp: now,
+        kind: EventKind::TorqueEnvelope {
+            motor_id,
+            kind,
+            budget_used: 1.0,
+        },
+    };
+    odometer.observe(&limited(2, EnvelopeEventKind::Limited));
+    odometer.observe(&limited(2, EnvelopeEventKind::Approaching));
+    odometer.observe(&limited(9, EnvelopeEventKind::Limited));
+    assert_eq!(odometer.usage(2).unwrap().overload_events, 3);
+}
+
+#[test]
+fn totals_follow_the_serial_number_across_sessions() {
+    let path = temp_path("usage-persist.json");
+    let start = Instant::now();
+    {
+        let mut odometer = Odometer::open(&path).unwrap();
+        odometer.track(1, "SN-003");
+        odometer.record(&state(1, 1.0, 0.0), true, start);
+        odometer.record(&state(1, 1.0, 0.0), true, start + Duration::from_millis(500));
+        odometer.save().unwrap();
+    }
+
+    // The motor moved to another joint
+    let mut odometer = Odometer::open(&path).unwrap();
+    assert_eq!(odometer.usage_by_serial("SN-003").unwrap().revolutions, 0.5);
+    odometer.track(4, "SN-003");
+    odometer.record(&state(4, 1.0, 0.0), true, start);
+    odometer.record(&state(4, 1.0, 0.0), true, start + Duration::from_millis(500));
+    let usage = odometer.usage(4).unwrap();
+    assert_eq!((usage.revolutions, usage.enabled_s), (1.0, 1.0));
+    assert_eq!(usage.enabled_hours(), 1.0 / 3600.0);
+    std::fs::remove_file(&path).ok();
+
+    std::fs::write(&path, "not json").unwrap();
+    assert!(Odometer::open(&path).is_err());
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,188 @@
+//! Phase-synchronized multi-joint oscillation, checked against sampled targets
+
+use livelybot_motor_control::protocol::{stream_target, ANGLE_STREAM_ID};
+use livelybot_motor_control::{
+    position_to_degrees, JointOscillation, LivelyMotorController, MockTransport, Oscillator, OscillatorConfig,
+    RawFrame, SimMotor,
+};
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Hips half a period apart, a knee a quarter period behind its hip, and a
+/// faster joint to tell the frequencies apart
+const GAIT: &str = r#"{ "duration_s": 10, "ramp_s": 0,
+  "joints": [
+    { "motor_id": 1, "amplitude_deg": 20, "frequency_hz": 0.8 },
+    { "motor_id": 2, "amplitude_deg": 20, "frequency_hz": 0.8, "phase_deg": 180 },
+    { "motor_id": 3, "center_deg": 30, "amplitude_deg": 15, "frequency_hz": 0.8, "phase_deg": -90 },
+    { "motor_id": 4, "amplitude_deg": 5, "frequency_hz": 2.0, "phase_deg": 45 } ] }"#;
+
+fn gait() -> Oscillator {
+    Oscillator::new(serde_json::from_str(GAIT).unwrap()).unwrap()
+}
+
+fn close(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() < tolerance
+}
+
+/// Angles of the joint at `index`, sampled every millisecond over `[from, to)`
+fn samples(oscillator: &Oscillator, index: usize, from: f64, to: f64) -> Vec<(f64, f64)> {
+    let count = ((to - from) * 1000.0).round() as usize;
+    (0..count)
+        .map(|i| {
+            let t = from + i as f64 / 1000.0;
+            (t, oscillator.targets(Duration::from_secs_f64(t))[index].1)
+        })
+        .collect()
+}
+
+/// Upward crossings of the center per second
+fn frequency(samples: &[(f64, f64)], center: f64) -> f64 {
+    let crossings = samples.windows(2).filter(|w| w[0].1 < center && w[1].1 >= center).count();
+    let span = samples.last().unwrap().0 - samples[0].0 + 0.001;
+    crossings as f64 / span
+}
+
+/// Center, amplitude and phase (degrees) of the component at `frequency_hz`,
+/// projected over whole periods
+fn fit(samples: &[(f64, f64)], frequency_hz: f64) -> (f64, f64, f64) {
+    let n = samples.len() as f64;
+    let center = samples.iter().map(|s| s.1).sum::<f64>() / n;
+    let (mut sin, mut cos) = (0.0, 0.0);
+    for &(t, angle) in samples {
+        let phase = 2.0 * PI * frequency_hz * t;
+        sin += (angle - center) * phase.sin();
+        cos += (angle - center) * phase.cos();
+    }
+    (center, 2.0 * sin.hypot(cos) / n, cos.atan2(sin).to_degrees())
+}
+
+#[test]
+fn sampled_targets_match_each_joints_sine() {
+    let oscillator = gait();
+    assert_eq!(oscillator.config().motor_ids(), [1, 2, 3, 4]);
+    // (center, amplitude, frequency, phase) as configured
+    let expected = [(0.0, 20.0, 0.8, 0.0), (0.0, 20.0, 0.8, 180.0), (30.0, 15.0, 0.8, -90.0), (0.0, 5.0, 2.0, 45.0)];
+    for (index, &(center, amplitude, frequency_hz, phase)) in expected.iter().enumerate() {
+        // Four periods of the slow joints, ten of the fast one
+        let samples = samples(&oscillator, index, 0.1, 5.1);
+        let (low, high) = samples.iter().fold((f64::MAX, f64::MIN), |(l, h), s| (l.min(s.1), h.max(s.1)));
+        assert!(close(low, center - amplitude, 0.01), "{}: {}", index, low);
+        assert!(close(high, center + amplitude, 0.01), "{}: {}", index, high);
+        assert!(close(frequency(&samples, center), frequency_hz, 1e-9), "{}", index);
+
+        let (fit_center, fit_amplitude, fit_phase) = fit(&samples, frequency_hz);
+        assert!(close(fit_center, center, 1e-6), "{}: {}", index, fit_center);
+        assert!(close(fit_amplitude, amplitude, 1e-6), "{}: {}", index, fit_amplitude);
+        let wrapped = (fit_phase - phase + 540.0).rem_euclid(360.0) - 180.0;
+        assert!(close(wrapped, 0.0, 1e-6), "{}: {}", index, fit_phase);
+    }
+
+    // One clock for all joints: a quarter period in, hip 1 is at its peak,
+    // hip 2 at its trough and the knee back at its center
+    let quarter = oscillator.targets(Duration::from_secs_f64(0.3125));
+    let angles: Vec<f64> = quarter.iter().map(|t| t.1).collect();
+    assert_eq!(quarter.iter().map(|t| t.0).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    assert!(close(angles[0], 20.0, 1e-9) && close(angles[1], -20.0, 1e-9), "{:?}", angles);
+    assert!(close(angles[2], 30.0, 1e-9), "{:?}", angles);
+    let start = oscillator.targets(Duration::ZERO);
+    assert!(close(start[2].1, 15.0, 1e-9) && close(start[3].1, 5.0 * 0.5f64.sqrt(), 1e-9), "{:?}", start);
+}
+
+#[test]
+fn amplitudes_ramp_in_and_out() {
+    let config = OscillatorConfig::new(vec![JointOscillation::new(1, 10.0, 1.0).with_center(5.0)], 4.0).with_ramp(1.0);
+    let oscillator = Oscillator::new(config).unwrap();
+    let envelope = |t: f64| oscillator.envelope(Duration::from_secs_f64(t));
+    assert_eq!(envelope(0.0), 0.0);
+    assert!(close(envelope(0.5), 0.5, 1e-9) && close(envelope(3.5), 0.5, 1e-9));
+    assert!(close(envelope(0.25), 0.15625, 1e-9));
+    assert_eq!((envelope(1.0), envelope(2.0), envelope(3.0)), (1.0, 1.0, 1.0));
+    assert_eq!((envelope(4.0), envelope(9.0)), (0.0, 0.0));
+
+    // Starts and stops at the center; the first peak is scaled down by the ramp
+    assert_eq!(oscillator.targets(Duration::ZERO), [(1, 5.0)]);
+    assert!(close(oscillator.targets(Duration::from_secs_f64(0.25))[0].1, 5.0 + 10.0 * 0.15625, 1e-9));
+    assert!(close(oscillator.targets(Duration::from_secs_f64(2.25))[0].1, 15.0, 1e-9));
+    assert_eq!(oscillator.targets(Duration::from_secs(4)), [(1, 5.0)]);
+}
+
+#[test]
+fn configs_load_and_are_validated() {
+    let path = std::env::temp_dir().join(format!("livelybot-oscillator-{}.json", std::process::id()));
+    std::fs::write(&path, GAIT).unwrap();
+    let config = OscillatorConfig::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((config.rate_hz, config.max_vel_rps, config.max_torque_nm), (200.0, 2.0, 3.0));
+    assert_eq!(config.joints[2], JointOscillation::new(3, 15.0, 0.8).with_center(30.0).with_phase(-90.0));
+    let error = OscillatorConfig::load(&path).unwrap_err().to_string();
+    assert!(error.starts_with(&format!("Cannot read oscillator config {}: ", path.display())), "{}", error);
+
+    let rejected = |config: OscillatorConfig| Oscillator::new(config).err().unwrap().to_string();
+    let joint = JointOscillation::new(1, 10.0, 1.0);
+    assert_eq!(rejected(OscillatorConfig::new(vec![joint.clone()], 0.0)), "rate and duration must be positive");
+    assert_eq!(
+        rejected(OscillatorConfig::new(vec![joint.clone()], 2.0).with_ramp(1.5)),
+        "ramp of 1.5 s does not fit twice into 2 s"
+    );
+    assert_eq!(
+        rejected(OscillatorConfig::new(vec![JointOscillation::new(2, -1.0, 1.0)], 2.0)),
+        "motor 2: amplitude and frequency must not be negative"
+    );
+    assert_eq!(
+        rejected(OscillatorConfig::new(vec![joint.clone(), joint.with_phase(90.0)], 2.0)),
+        "motor 1 is listed twice"
+    );
+}
+
+#[test]
+fn a_run_streams_every_joint_each_cycle() {
+    let controller = LivelyMotorController::with_transport(
+        "mock",
+        MockTransport::new().with_motor(1, SimMotor::default()).with_motor(2, SimMotor::default()),
+    );
+    let joints = vec![JointOscillation::new(2, 20.0, 2.0), JointOscillation::new(1, 20.0, 2.0).with_phase(180.0)];
+    let oscillator = Oscillator::new(OscillatorConfig::new(joints, 0.3)).unwrap();
+
+    let sent = controller.bus().subscribe_sent();
+    let mut observed = Vec::new();
+    oscillator
+        .run(&controller, &AtomicBool::new(true), |_, targets| {
+            observed.push(targets.to_vec());
+            Ok(())
+        })
+        .unwrap();
+    assert!((40..=65).contains(&observed.len()), "{}", observed.len());
+
+    // Each cycle's targets went out in config order, to the motor they belong to
+    let streamed: Vec<(u8, f64)> = std::iter::from_fn(|| sent.try_recv())
+        .map(|f| RawFrame::from_frame(&f))
+        .map(|f| {
+            let (stream, motor_id) = stream_target(f.id).unwrap();
+            assert_eq!(stream, ANGLE_STREAM_ID);
+            (motor_id, position_to_degrees(i16::from_le_bytes([f.data[0], f.data[1]])))
+        })
+        .collect();
+    let targets: Vec<(u8, f64)> = observed.concat();
+    assert_eq!(streamed.len(), targets.len());
+    for (frame, target) in streamed.iter().zip(&targets) {
+        assert_eq!(frame.0, target.0);
+        assert!(close(frame.1, target.1, 0.05), "{:?} {:?}", frame, target);
+    }
+    // The hips mirror each other
+    assert!(observed.iter().all(|t| close(t[0].1, -t[1].1, 1e-9)));
+    assert!(observed.iter().any(|t| t[0].1 > 15.0));
+
+    // Stopped before the first cycle
+    let running = AtomicBool::new(true);
+    running.store(false, Ordering::Relaxed);
+    let mut cycles = 0;
+    oscillator
+        .run(&controller, &running, |_, _| {
+            cycles += 1;
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(cycles, 0);
+}
@@ -0,0 +1,108 @@
+//! Staged park poses and their joint-range check
+
+use livelybot_motor_control::{
+    degrees_to_position, nm_to_torque, rps_to_velocity, LivelyMotorController, MockTransport, ParkConfig, ParkRunner,
+    SimMotor,
+};
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use std::time::Duration;
+
+/// Motor 1 is an arm, motor 2 a knee: the arm must be folded before the knee bends
+const CONFIG: &str = r#"{
+    "max_vel_rps": 2.0,
+    "stage_timeout_s": 3.0,
+    "rules": [{
+        "name": "arm clear of the thigh",
+        "joint": 2, "when": {"min_deg": 30.0, "max_deg": 180.0},
+        "other": 1, "allowed": {"min_deg": -10.0, "max_deg": 10.0}
+    }],
+    "poses": [
+        {"name": "stow", "stages": [
+            {"name": "fold arm", "targets": {"1": 0.0}},
+            {"name": "bend knee", "targets": {"2": 90.0}}
+        ]},
+        {"name": "knee first", "stages": [
+            {"name": "bend knee", "targets": {"2": 90.0}},
+            {"name": "fold arm", "targets": {"1": 0.0}}
+        ]}
+    ]
+}"#;
+
+fn start(arm_deg: f64, knee_deg: f64) -> BTreeMap<u8, f64> {
+    BTreeMap::from([(1, arm_deg), (2, knee_deg)])
+}
+
+#[test]
+fn stage_order_is_checked_before_moving() {
+    let config = ParkConfig::from_json(CONFIG).unwrap();
+    assert_eq!(config.pose("stow").unwrap().motor_ids(), vec![1, 2]);
+    assert!(config.pose("sit").unwrap_err().to_string().contains("no park pose named 'sit'"));
+
+    config.check_motion(config.pose("stow").unwrap(), &start(45.0, 0.0)).unwrap();
+    let err = config.check_motion(config.pose("knee first").unwrap(), &start(45.0, 0.0)).unwrap_err();
+    assert!(err.to_string().contains("stage 'bend knee' violates rule 'arm clear of the thigh'"), "{}", err);
+
+    // With the arm already folded either order is fine
+    config.check_motion(config.pose("knee first").unwrap(), &start(0.0, 0.0)).unwrap();
+    // A start pose that already breaks a rule is reported as such
+    let err = config.check_motion(config.pose("stow").unwrap(), &start(45.0, 60.0)).unwrap_err();
+    assert!(err.to_string().contains("start of 'stow'"), "{}", err);
+    let err = config.check_motion(config.pose("stow").unwrap(), &BTreeMap::from([(2, 0.0)])).unwrap_err();
+    assert!(err.to_string().contains("motor 1 has no measured start position"), "{}", err);
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let err = ParkConfig::from_json(r#"{"max_vel_rps": 0.0, "poses": []}"#).unwrap_err();
+    assert!(err.to_string().contains("max_vel_rps"), "{}", err);
+    let err = ParkConfig::from_json(r#"{"poses": [{"name": "empty", "stages": []}]}"#).unwrap_err();
+    assert!(err.to_string().contains("'empty' has no stages"), "{}", err);
+    assert!(ParkConfig::from_json(r#"{"rules": []}"#).is_err(), "poses are required");
+}
+
+fn setup() -> (MockTransport, LivelyMotorController) {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(2, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    // Raise the arm so the stage order matters
+    controller.enable_motor(1).unwrap();
+    for _ in 0..50 {
+        controller
+            .send_angle_command_to(1, degrees_to_position(45.0), rps_to_velocity(2.0), nm_to_torque(3.0))
+            .unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    (mock, controller)
+}
+
+#[test]
+fn runner_moves_the_stages_in_order() {
+    let (mock, controller) = setup();
+    let runner = ParkRunner::new(&controller, ParkConfig::from_json(CONFIG).unwrap());
+
+    let mut stages = Vec::new();
+    runner
+        .park("stow", &AtomicBool::new(true), |stage| stages.push(stage.name.clone()))
+        .unwrap();
+    assert_eq!(stages, ["fold arm", "bend knee"]);
+    assert!(mock.state(1).unwrap().position_deg.abs() < 2.0);
+    assert!((mock.state(2).unwrap().position_deg - 90.0).abs() < 2.0);
+}
+
+#[test]
+fn runner_refuses_an_unsafe_order() {
+    let (mock, controller) = setup();
+    let runner = ParkRunner::new(&controller, ParkConfig::from_json(CONFIG).unwrap());
+
+    let mut stages = Vec::new();
+    let err = runner
+        .park("knee first", &AtomicBool::new(true), |stage| stages.push(stage.name.clone()))
+        .unwrap_err();
+    assert!(err.to_string().contains("violates rule"), "{}", err);
+    assert!(stages.is_empty());
+    assert!(!controller.enabled_motors().contains(&2), "nothing was enabled");
+    assert!(mock.state(2).unwrap().position_deg.abs() < 0.5);
+}
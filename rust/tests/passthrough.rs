@@ -0,0 +1,185 @@
+//! Third-party frames decoded off the motor bus, with the fusion hook
+
+use livelybot_motor_control::plugins;
+use livelybot_motor_control::{
+    AlignedSample, CachedState, CanBus, CanTransport, DecodedSample, Endianness, Event, EventBus, EventKind, FieldCommand,
+    FieldDecoder, FrameDecoder, MotorState, PassThrough, RawFrame, StateCache,
+};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const IMU: &str = r#"{ "name": "imu", "id": 1280, "fields": [
+    { "name": "pitch_deg", "offset": 0, "size": 2, "signed": true, "scale": 0.01 },
+    { "name": "roll_deg",  "offset": 2, "size": 2, "signed": true, "scale": 0.01 } ] }"#;
+
+/// A transport receiving the frames a test puts on it
+#[derive(Clone, Default)]
+struct Inject(Arc<Mutex<VecDeque<RawFrame>>>);
+
+impl Inject {
+    fn put(&self, frame: RawFrame) {
+        self.0.lock().unwrap().push_back(frame);
+    }
+}
+
+impl CanTransport for Inject {
+    fn send(&self, _frame: &RawFrame) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        let frame = self.0.lock().unwrap().pop_front();
+        if frame.is_none() {
+            thread::sleep(timeout.min(Duration::from_millis(1)));
+        }
+        Ok(frame)
+    }
+}
+
+/// Counts the frames it is asked about, decodes none
+struct Counting(Arc<Mutex<Vec<u32>>>);
+
+impl FrameDecoder for Counting {
+    fn name(&self) -> &str {
+        "counting"
+    }
+
+    fn decode(&mut self, can_id: u32, _data: &[u8]) -> Option<Vec<(String, f64)>> {
+        self.0.lock().unwrap().push(can_id);
+        None
+    }
+}
+
+fn imu() -> FieldDecoder {
+    serde_json::from_str(IMU).unwrap()
+}
+
+fn decoded(events: &Receiver<Event>) -> Vec<DecodedSample> {
+    events
+        .try_iter()
+        .filter_map(|event| match event.kind {
+            EventKind::Decoded(sample) => Some(sample),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn sensor_frames_become_samples_on_the_event_bus() {
+    let inject = Inject::default();
+    let bus = CanBus::with_transport(inject.clone(), "inject", 1_000_000);
+    let events = Arc::new(EventBus::new());
+    let received = events.subscribe();
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let mut passthrough = PassThrough::new(&bus, Arc::clone(&events));
+    passthrough.register(imu()).register(Counting(Arc::clone(&asked)));
+    assert_eq!(passthrough.decoders(), ["imu", "counting"]);
+
+    inject.put(RawFrame::standard(0x500, &[0x2C, 0x01, 0x38, 0xFF]));
+    // Motor traffic and a truncated IMU frame produce nothing
+    inject.put(RawFrame::extended(0x0100, &[0x24, 0x01, 0, 0, 0, 0, 0, 0]));
+    inject.put(RawFrame::standard(0x500, &[0x2C, 0x01]));
+    inject.put(RawFrame::standard(0x500, &[0x00, 0x00, 0xC8, 0x00]));
+    assert_eq!(passthrough.poll(Duration::from_millis(100)).unwrap(), 2);
+
+    let samples = decoded(&received);
+    assert_eq!(samples.len(), 2);
+    assert_eq!((samples[0].source.as_str(), samples[0].can_id), ("imu", 0x500));
+    assert_eq!(samples[0].value("pitch_deg"), Some(3.0));
+    assert_eq!(samples[0].value("roll_deg"), Some(-2.0));
+    assert_eq!(samples[0].value("yaw_deg"), None);
+    assert_eq!(samples[1].values, [("pitch_deg".to_string(), 0.0), ("roll_deg".to_string(), 2.0)]);
+    assert!(samples[0].timestamp <= samples[1].timestamp);
+    // The first decoder took the IMU frames; the next one only saw the rest
+    assert_eq!(*asked.lock().unwrap(), [0x0100, 0x500]);
+}
+
+#[test]
+fn custom_commands_are_decoded_both_ways() {
+    plugins::register(FieldCommand {
+        name: "cogging_table".to_string(),
+        command: 0x30,
+        endianness: Endianness::Little,
+        fields: serde_json::from_str(
+            r#"[{ "name": "index", "offset": 1, "size": 1, "scale": 1.0 },
+                { "name": "offset_nm", "offset": 2, "size": 2, "signed": true, "scale": 0.001 }]"#,
+        )
+        .unwrap(),
+    })
+    .unwrap();
+
+    let inject = Inject::default();
+    let bus = CanBus::with_transport(inject.clone(), "inject", 1_000_000);
+    let events = Arc::new(EventBus::new());
+    let received = events.subscribe();
+    let mut passthrough = PassThrough::new(&bus, Arc::clone(&events));
+    passthrough.register(imu());
+
+    // Host to motor 1, then motor 1's reply
+    inject.put(RawFrame::extended(0x0001, &[0x30, 5, 0xF4, 0x01, 0x50, 0x50, 0x50, 0x50]));
+    inject.put(RawFrame::extended(0x0100, &[0x30, 5, 0x0C, 0xFE, 0x50, 0x50, 0x50, 0x50]));
+    // Not a motor frame: left to the decoders
+    inject.put(RawFrame::standard(0x123, &[0x30, 5, 0, 0]));
+    assert_eq!(passthrough.poll(Duration::from_millis(100)).unwrap(), 2);
+    plugins::unregister(0x30);
+
+    let samples = decoded(&received);
+    let decoded: Vec<(u32, Option<f64>, Option<f64>)> = samples
+        .iter()
+        .map(|s| (s.can_id, s.value("index"), s.value("offset_nm")))
+        .collect();
+    assert!(samples.iter().all(|s| s.source == "cogging_table"));
+    assert_eq!(decoded, [(0x0001, Some(5.0), Some(0.5)), (0x0100, Some(5.0), Some(-0.5))]);
+}
+
+#[test]
+fn the_fusion_hook_pairs_samples_with_motor_feedback() {
+    let inject = Inject::default();
+    let bus = CanBus::with_transport(inject.clone(), "inject", 1_000_000);
+    let cache = Arc::new(StateCache::new());
+    let feedback = MotorState {
+        motor_id: 1,
+        position_deg: 12.5,
+        velocity_rps: 0.25,
+        torque_nm: 0.5,
+        acceleration_rps2: None,
+    };
+    cache.publish(&feedback);
+
+    let aligned = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&aligned);
+    let mut passthrough = PassThrough::new(&bus, Arc::new(EventBus::new())).with_fusion(
+        Arc::clone(&cache),
+        &[1, 2],
+        move |sample: &AlignedSample| collected.lock().unwrap().push(sample.clone()),
+    );
+    passthrough.register(imu());
+
+    thread::sleep(Duration::from_millis(20));
+    inject.put(RawFrame::standard(0x500, &[0x2C, 0x01, 0x38, 0xFF]));
+    assert_eq!(passthrough.poll(Duration::from_millis(100)).unwrap(), 1);
+
+    let aligned = aligned.lock().unwrap();
+    assert_eq!(aligned.len(), 1);
+    assert_eq!(aligned[0].sample.value("pitch_deg"), Some(3.0));
+    // Motor 2 never reported
+    assert_eq!(aligned[0].motors.len(), 1);
+    let (cached, skew) = aligned[0].motor(1).unwrap();
+    assert_eq!(cached.state.position_deg, 12.5);
+    assert!((0.02..0.5).contains(&skew), "{}", skew);
+    assert!(aligned[0].motor(2).is_none());
+
+    // Feedback newer than the sample reads as a negative skew
+    let later = AlignedSample {
+        sample: aligned[0].sample.clone(),
+        motors: vec![CachedState {
+            state: feedback,
+            timestamp: aligned[0].sample.timestamp + Duration::from_millis(5),
+        }],
+    };
+    assert!((later.motor(1).unwrap().1 + 0.005).abs() < 1e-9);
+}
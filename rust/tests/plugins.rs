@@ -0,0 +1,155 @@
+//! Custom command plugins: registration, sending and decoding
+//!
+//! The registry is process-wide, so every test uses its own command bytes.
+
+use anyhow::{anyhow, Result};
+use livelybot_motor_control::plugins::{self, BUILTIN_COMMANDS};
+use livelybot_motor_control::protocol::describe_frame;
+use livelybot_motor_control::{CommandCodec, FieldCommand, LivelyMotorController, MockTransport, RawFrame, SimMotor};
+use std::sync::{Arc, Mutex};
+
+const COGGING: &str = r#"[
+    { "name": "cogging_table", "command": 48, "fields": [
+        { "name": "index", "offset": 1, "size": 1, "scale": 1.0 },
+        { "name": "offset_nm", "offset": 2, "size": 2, "signed": true, "scale": 0.001 } ] },
+    { "name": "led", "command": 49, "fields": [
+        { "name": "rgb", "offset": 1, "size": 4, "scale": 1.0 } ] }
+]"#;
+
+/// A codec logging every call into a shared journal
+struct Journaled {
+    name: &'static str,
+    command: u8,
+    journal: Arc<Mutex<Vec<String>>>,
+}
+
+impl CommandCodec for Journaled {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn command(&self) -> u8 {
+        self.command
+    }
+
+    fn encode(&self, values: &[f64]) -> Result<Vec<u8>> {
+        self.journal.lock().unwrap().push(format!("{} encode {:?}", self.name, values));
+        let &[value] = values else {
+            return Err(anyhow!("takes one value"));
+        };
+        Ok(vec![self.command, value as u8, 0, 0, 0, 0, 0, 0])
+    }
+
+    fn decode(&self, data: &[u8]) -> Option<Vec<(String, f64)>> {
+        self.journal.lock().unwrap().push(format!("{} decode {:?}", self.name, data.get(1)));
+        Some(vec![("value".to_string(), *data.get(1)? as f64)])
+    }
+}
+
+fn setup() -> LivelyMotorController {
+    LivelyMotorController::with_transport("mock", MockTransport::new().with_motor(1, SimMotor::default()))
+}
+
+#[test]
+fn field_commands_load_and_round_trip() {
+    let path = std::env::temp_dir().join(format!("livelybot-plugins-{}.json", std::process::id()));
+    std::fs::write(&path, COGGING).unwrap();
+    let commands = FieldCommand::load_all(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let names: Vec<(u8, &str)> = commands.iter().map(|c| (c.command, c.name.as_str())).collect();
+    assert_eq!(names, [(48, "cogging_table"), (49, "led")]);
+
+    let cogging = &commands[0];
+    let data = cogging.encode(&[5.0, -0.5]).unwrap();
+    assert_eq!(data, [0x30, 5, 0x0C, 0xFE, 0x50, 0x50, 0x50, 0x50]);
+    assert_eq!(
+        cogging.decode(&data).unwrap(),
+        [("index".to_string(), 5.0), ("offset_nm".to_string(), -0.5)]
+    );
+    assert_eq!(cogging.decode(&[0x31, 5, 0, 0]), None);
+    assert_eq!(cogging.decode(&[0x30, 5]), None);
+    assert_eq!(
+        cogging.encode(&[5.0]).unwrap_err().to_string(),
+        "command 'cogging_table' takes 2 values, got 1"
+    );
+
+    let error = FieldCommand::load_all(&path).unwrap_err().to_string();
+    assert!(error.starts_with(&format!("Cannot read command plugins {}: ", path.display())), "{}", error);
+}
+
+#[test]
+fn registration_guards_the_command_bytes() {
+    let codec = |name: &'static str, command: u8| Journaled {
+        name,
+        command,
+        journal: Arc::default(),
+    };
+    assert_eq!(
+        plugins::register(codec("clash", *BUILTIN_COMMANDS.end())).unwrap_err().to_string(),
+        "command 0x2F of 'clash' is reserved by the register protocol"
+    );
+
+    plugins::register(codec("first", 0x40)).unwrap();
+    assert_eq!(
+        plugins::register(codec("second", 0x40)).unwrap_err().to_string(),
+        "command 0x40 of 'second' is already registered by 'first'"
+    );
+    assert_eq!(plugins::lookup(0x40).unwrap().name(), "first");
+    assert!(plugins::registered().contains(&(0x40, "first".to_string())));
+
+    assert!(plugins::unregister(0x40));
+    assert!(!plugins::unregister(0x40));
+    assert!(plugins::lookup(0x40).is_none());
+    // Free again after unregistering
+    plugins::register(codec("second", 0x40)).unwrap();
+    assert!(plugins::unregister(0x40));
+}
+
+#[test]
+fn hooks_run_in_order_from_send_to_decode() {
+    let journal = Arc::new(Mutex::new(Vec::new()));
+    for (name, command) in [("brake", 0x51), ("horn", 0x50)] {
+        plugins::register(Journaled {
+            name,
+            command,
+            journal: Arc::clone(&journal),
+        })
+        .unwrap();
+    }
+    // Listed by command byte, whatever the registration order
+    let ours: Vec<(u8, String)> =
+        plugins::registered().into_iter().filter(|(c, _)| (0x50..=0x51).contains(c)).collect();
+    assert_eq!(ours, [(0x50, "horn".to_string()), (0x51, "brake".to_string())]);
+
+    let controller = setup();
+    let sent = controller.bus().subscribe_sent();
+    controller.send_command(1, 0x51, &[7.0]).unwrap();
+    controller.send_command(1, 0x50, &[3.0]).unwrap();
+    let error = controller.send_command(1, 0x51, &[]).unwrap_err();
+    assert_eq!(format!("{:#}", error), "sending brake to motor 1: takes one value");
+    assert_eq!(
+        controller.send_command(1, 0x52, &[1.0]).unwrap_err().to_string(),
+        "no plugin registered for command 0x52"
+    );
+
+    // Each frame goes to the motor and is decoded by the codec of its command byte
+    let described: Vec<String> = std::iter::from_fn(|| sent.try_recv())
+        .map(|f| RawFrame::from_frame(&f))
+        .inspect(|f| assert_eq!(f.id, 1))
+        .map(|f| describe_frame(f.id, &f.data))
+        .collect();
+    assert_eq!(described, ["motor 1 brake value=7", "motor 1 horn value=3"]);
+    plugins::unregister(0x50);
+    plugins::unregister(0x51);
+
+    assert_eq!(
+        *journal.lock().unwrap(),
+        [
+            "brake encode [7.0]",
+            "horn encode [3.0]",
+            "brake encode []",
+            "brake decode Some(7)",
+            "horn decode Some(3)",
+        ]
+    );
+}
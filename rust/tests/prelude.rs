@@ -0,0 +1,41 @@
+//! Downstream code written against the prelude alone
+
+use livelybot_motor_control::prelude::*;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A transport that keeps what it is given and never receives
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<RawFrame>>>);
+
+impl CanTransport for Capture {
+    fn send(&self, frame: &RawFrame) -> io::Result<()> {
+        self.0.lock().unwrap().push(frame.clone());
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        std::thread::sleep(timeout);
+        Ok(None)
+    }
+}
+
+#[test]
+fn custom_transports_see_raw_frames() {
+    let capture = Capture::default();
+    let controller = LivelyMotorController::with_transport("capture", capture.clone());
+    controller.disable_motor(5).unwrap();
+    controller.send_angle_command_to(5, degrees_to_position(10.0), 0, 0).unwrap();
+
+    let sent = capture.0.lock().unwrap().clone();
+    assert_eq!(sent.len(), 2);
+    assert_eq!((sent[0].id, sent[0].extended), (5, true));
+    assert_eq!(&sent[0].data[..3], &[0x01, Register::Mode as u8, 0x00]);
+    assert_eq!(sent[1].id, 0x0590);
+    assert!(controller.read_raw_frame(20).unwrap().is_none());
+
+    // Remote frames keep their requested length through socketcan and back
+    let remote = RawFrame { remote: true, ..RawFrame::standard(0x123, &[0; 4]) };
+    assert_eq!(RawFrame::from_frame(&remote.to_frame().unwrap()), remote);
+}
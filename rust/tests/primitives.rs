@@ -0,0 +1,121 @@
+//! Motion primitives executed on a simulated motor
+
+use livelybot_motor_control::protocol::{stream_target, Register, ANGLE_STREAM_ID};
+use livelybot_motor_control::{
+    degrees_to_position, LivelyMotorController, MockTransport, OscillateParams, Primitive, PrimitiveRunner,
+    PrimitiveStatus, RawFrame, SimMotor,
+};
+use std::f64::consts::PI;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// What the runner sent to the motor
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sent {
+    Angle(i16),
+    Mode(u8),
+}
+
+fn decode(frame: &RawFrame) -> Option<Sent> {
+    if stream_target(frame.id) == Some((ANGLE_STREAM_ID, 1)) {
+        return Some(Sent::Angle(i16::from_le_bytes([frame.data[0], frame.data[1]])));
+    }
+    let mode_write = frame.id == 1 && frame.data[..2] == [0x01, Register::Mode as u8];
+    mode_write.then(|| Sent::Mode(frame.data[2]))
+}
+
+fn oscillation(duration: Duration) -> OscillateParams {
+    OscillateParams {
+        center_deg: 5.0,
+        amplitude_deg: 10.0,
+        frequency_hz: 5.0,
+        duration,
+    }
+}
+
+#[test]
+fn trees_flatten_into_leaf_steps() {
+    let wave = Primitive::oscillate(oscillation(ms(200)));
+    let tree = Primitive::move_to(30.0, ms(100))
+        .then(Primitive::repeat(2, Primitive::sequence(vec![wave.clone(), Primitive::hold(ms(50))])))
+        .then(Primitive::relax());
+
+    let steps = tree.steps();
+    assert_eq!(steps.len(), 6);
+    assert_eq!(steps[0], &Primitive::move_to(30.0, ms(100)));
+    assert_eq!((steps[1], steps[3]), (&wave, &wave));
+    assert_eq!((steps[2], steps[4]), (&Primitive::hold(ms(50)), &Primitive::hold(ms(50))));
+    assert_eq!(steps[5], &Primitive::Relax);
+    assert_eq!(tree.duration(), ms(100 + 2 * 250));
+    assert!(Primitive::repeat(0, wave).steps().is_empty());
+}
+
+#[test]
+fn each_primitive_commands_its_setpoints() {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    controller.enable_motor(1).unwrap();
+
+    let program = Primitive::move_to(30.0, ms(150))
+        .then(Primitive::hold(ms(100)))
+        .then(Primitive::oscillate(oscillation(ms(200))))
+        .then(Primitive::relax())
+        .then(Primitive::move_to(-15.0, ms(100)));
+    let mut statuses: Vec<PrimitiveStatus> = Vec::new();
+    let sent = controller.bus().subscribe_sent();
+    PrimitiveRunner::new(&controller, 1)
+        .run(&program, &AtomicBool::new(true), |status| {
+            statuses.push(*status);
+            Ok(())
+        })
+        .unwrap();
+    let sent: Vec<Sent> = std::iter::from_fn(|| sent.try_recv())
+        .filter_map(|f| decode(&RawFrame::from_frame(&f)))
+        .collect();
+
+    // Exactly one setpoint per observed cycle, the one reported
+    let angles: Vec<i16> = sent.iter().filter_map(|s| if let Sent::Angle(a) = s { Some(*a) } else { None }).collect();
+    let reported: Vec<i16> = statuses.iter().map(|s| degrees_to_position(s.target_deg.unwrap())).collect();
+    assert_eq!(angles, reported);
+
+    // Relax is instantaneous: it is never observed, but disables the motor
+    // before the next move re-enables it
+    let mut steps: Vec<usize> = statuses.iter().map(|s| s.step).collect();
+    steps.dedup();
+    assert_eq!(steps, [0, 1, 2, 4]);
+    assert!(statuses.iter().all(|s| s.steps == 5));
+    let modes: Vec<(usize, u8)> = sent
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| if let Sent::Mode(m) = s { Some((i, *m)) } else { None })
+        .collect();
+    assert_eq!(modes.iter().map(|m| m.1).collect::<Vec<_>>(), [0x00, 0x0A]);
+    let back = Sent::Angle(degrees_to_position(-15.0));
+    let first_back = sent.iter().position(|s| *s == back).unwrap();
+    assert_eq!(modes[1].0, modes[0].0 + 1);
+    assert_eq!(first_back, modes[1].0 + 1);
+    assert!(sent[first_back..].iter().all(|s| *s == back));
+
+    for status in &statuses {
+        let target = status.target_deg.unwrap();
+        let t = status.step_elapsed.as_secs_f64();
+        let expected = match status.step {
+            // Hold keeps the target it started with
+            0 | 1 => 30.0,
+            2 => 5.0 + 10.0 * (2.0 * PI * 5.0 * t).sin(),
+            _ => -15.0,
+        };
+        assert!((target - expected).abs() < 1e-9, "{:?}", status);
+        assert!(status.step_elapsed < status.step_duration, "{:?}", status);
+    }
+    // Each step starts once, at its first cycle
+    let started: Vec<usize> = statuses.iter().filter(|s| s.step_started).map(|s| s.step).collect();
+    assert_eq!(started, [0, 1, 2, 4]);
+    let oscillating = statuses.iter().filter(|s| s.step == 2).count();
+    // 200 ms at 100 Hz, fewer if a loaded machine skips cycles
+    assert!((10..=21).contains(&oscillating), "{}", oscillating);
+}
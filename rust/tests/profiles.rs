@@ -0,0 +1,103 @@
+//! Named gain / limit profiles: loading, selection and switching at runtime
+
+use livelybot_motor_control::protocol::{Register, ValueType};
+use livelybot_motor_control::{LivelyMotorController, MockTransport, MotorSettings, ProfileSet, SimMotor};
+
+const JSON: &str = r#"{
+  "profiles": {
+    "soft": {
+      "1": { "kp": 0.5, "kd": 0.05, "torque_limit": 1.0 },
+      "2": { "kp": 0.8, "kd": 0.1, "torque_limit": 1.5 }
+    },
+    "performance": {
+      "1": { "kp": 6.0, "kd": 0.4, "torque_limit": 8.0 }
+    }
+  }
+}"#;
+
+const CSV: &str = "profile, motor_id, kp, kd, torque_limit
+soft, 1, 0.5, 0.05, 1.0
+
+soft, 2, 0.8, 0.1, 1.5
+performance, 1, 6.0, 0.4, 8.0
+";
+
+fn settings(kp: f32, kd: f32, torque_limit: f32) -> MotorSettings {
+    MotorSettings { kp, kd, torque_limit }
+}
+
+fn register(controller: &LivelyMotorController, motor_id: u8, reg: Register) -> f32 {
+    controller.read_registers(motor_id, reg, ValueType::Float, 1).unwrap().float(0).unwrap()
+}
+
+#[test]
+fn json_and_csv_load_the_same_profiles() {
+    let set = ProfileSet::from_json(JSON).unwrap();
+    assert_eq!(ProfileSet::from_csv(CSV).unwrap(), set);
+
+    let json = std::env::temp_dir().join(format!("livelybot-profiles-{}.json", std::process::id()));
+    let csv = json.with_extension("csv");
+    std::fs::write(&json, JSON).unwrap();
+    std::fs::write(&csv, CSV).unwrap();
+    let imported = (ProfileSet::import(&json), ProfileSet::import(&csv));
+    std::fs::remove_file(&json).unwrap();
+    std::fs::remove_file(&csv).unwrap();
+    assert_eq!(imported.0.unwrap(), set);
+    assert_eq!(imported.1.unwrap(), set);
+
+    let error = ProfileSet::import(&json).unwrap_err().to_string();
+    assert!(error.starts_with(&format!("Cannot read profiles {}: ", json.display())), "{}", error);
+}
+
+#[test]
+fn malformed_csv_names_the_problem() {
+    let error = |text: &str| ProfileSet::from_csv(text).unwrap_err().to_string();
+    assert_eq!(error(""), "empty profile CSV");
+    assert_eq!(error("profile,motor_id,kp,kd\n"), "profile CSV has no 'torque_limit' column");
+    assert!(error("profile,motor_id,kp,kd,torque_limit\nsoft,1,stiff,0.1,1.0").starts_with("CSV row 1: "));
+    assert!(error("profile,motor_id,kp,kd,torque_limit\nsoft,1,0.5,0.1,1.0\nsoft,300,0.5,0.1,1.0").starts_with("CSV row 2: "));
+    assert_eq!(error("profile,motor_id,kp,kd,torque_limit\nsoft,1,0.5"), "CSV row 1: missing column 3");
+}
+
+#[test]
+fn profiles_are_selected_by_name() {
+    let set = ProfileSet::from_json(JSON).unwrap();
+    assert_eq!(set.names(), ["performance", "soft"]);
+    assert_eq!(set.profile("performance").unwrap()[&1], settings(6.0, 0.4, 8.0));
+    assert_eq!(set.profile("soft").unwrap().len(), 2);
+    assert_eq!(
+        set.profile("normal").unwrap_err().to_string(),
+        "unknown profile 'normal' (available: performance, soft)"
+    );
+}
+
+#[test]
+fn switching_profile_rewrites_the_covered_joints() {
+    let mock = (1..=3).fold(MockTransport::new(), |mock, id| mock.with_motor(id, SimMotor::default()));
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    let set = ProfileSet::from_json(JSON).unwrap();
+    let gains = |motor_id: u8| {
+        settings(
+            register(&controller, motor_id, Register::Kp),
+            register(&controller, motor_id, Register::Kd),
+            register(&controller, motor_id, Register::TorqueLimit),
+        )
+    };
+    let initial = gains(3);
+
+    assert_eq!(set.apply(&controller, "soft", &[1, 2, 3]).unwrap(), [1, 2]);
+    assert_eq!(gains(1), settings(0.5, 0.05, 1.0));
+    assert_eq!(gains(2), settings(0.8, 0.1, 1.5));
+    assert_eq!(gains(3), initial);
+
+    // Motors the profile does not cover keep what the previous one wrote
+    assert_eq!(set.apply(&controller, "performance", &[1, 2, 3]).unwrap(), [1]);
+    assert_eq!(gains(1), settings(6.0, 0.4, 8.0));
+    assert_eq!(gains(2), settings(0.8, 0.1, 1.5));
+
+    // Only the motors asked for
+    assert_eq!(set.apply(&controller, "soft", &[2]).unwrap(), [2]);
+    assert_eq!(gains(1), settings(6.0, 0.4, 8.0));
+    assert!(set.apply(&controller, "normal", &[1]).is_err());
+    assert_eq!(gains(1), settings(6.0, 0.4, 8.0));
+}
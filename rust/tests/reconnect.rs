@@ -0,0 +1,68 @@
+//! Reconnecting a controller after the bus went down
+
+use livelybot_motor_control::protocol::{Register, ValueType};
+use livelybot_motor_control::{
+    degrees_to_position, EnableConfig, LimitMode, Limits, LivelyMotorController, MockTransport, MotorError, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+/// Register of a motor as the simulated motor holds it
+fn register(controller: &LivelyMotorController, motor_id: u8, reg: Register) -> f64 {
+    // A one-value int8 read of the mode register is an info query
+    let ty = if reg == Register::Mode { ValueType::Int16 } else { ValueType::Float };
+    let reply = controller.read_registers(motor_id, reg, ty, 1).unwrap();
+    reply.float(0).map(f64::from).or(reply.int(0).map(|v| v as f64)).unwrap()
+}
+
+#[test]
+fn reconnecting_restores_enabled_motors_and_keeps_limits() {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(2, SimMotor::default());
+    let mut controller = LivelyMotorController::with_transport("mock", mock.clone());
+    let limits = Limits::position(-30.0, 30.0).with_mode(LimitMode::Clamp);
+    controller.set_limits(1, limits).unwrap();
+    controller.enable_with(1, &EnableConfig::position().with_gains(2.5, 0.2)).unwrap();
+
+    mock.set_link_down(true);
+    let error = controller.send_angle_command_to(1, degrees_to_position(10.0), 0, 0).unwrap_err();
+    assert!(matches!(error, MotorError::CanIo(_)), "{:?}", error);
+    assert!(controller.reconnect("mock", 1_000_000).is_err());
+
+    // The adapter comes back, the motors were power cycled meanwhile
+    mock.set_link_down(false);
+    mock.add_motor(1, SimMotor::default());
+    assert_eq!(register(&controller, 1, Register::Mode), 0.0);
+    controller.reconnect("mock", 1_000_000).unwrap();
+
+    assert_eq!(controller.enabled_motors(), [1]);
+    assert_eq!(register(&controller, 1, Register::Mode), 0x0A as f64);
+    assert_eq!(register(&controller, 1, Register::Kp), 2.5);
+    assert_eq!(register(&controller, 2, Register::Mode), 0.0);
+    assert_eq!(controller.limits(1), Some(limits));
+
+    // Commands on the new bus are still held to the limits
+    for _ in 0..100 {
+        controller.send_angle_command_to(1, degrees_to_position(90.0), 0, 0).unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    let position = mock.state(1).unwrap().position_deg;
+    assert!((position - 30.0).abs() < 2.0, "{}", position);
+}
+
+#[test]
+fn motors_missing_after_reconnecting_are_reported() {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(3, SimMotor::default());
+    let mut controller = LivelyMotorController::with_transport("mock", mock.clone());
+    controller.enable_motor(1).unwrap();
+    controller.enable_motor(3).unwrap();
+
+    mock.remove_motor(3);
+    let error = controller.reconnect("mock", 1_000_000).unwrap_err();
+    assert!(error.to_string().contains("motors [3] did not answer on mock after reconnecting"), "{}", error);
+    // Motor 1 is back in position mode regardless
+    assert_eq!(register(&controller, 1, Register::Mode), 0x0A as f64);
+}
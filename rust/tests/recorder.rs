@@ -0,0 +1,146 @@
+//! Target vs actual recording and its CSV / MDF4 exports
+
+use livelybot_motor_control::mdf4::{write_mdf4, Mdf4Channel};
+use livelybot_motor_control::{JointSample, MotorState, Recorder, SessionMetadata};
+
+fn state(motor_id: u8, position_deg: f64) -> MotorState {
+    MotorState {
+        motor_id,
+        position_deg,
+        velocity_rps: 0.5,
+        torque_nm: -1.25,
+        acceleration_rps2: None,
+    }
+}
+
+fn sample(motor_id: u8, target_deg: f64, actual: Option<MotorState>) -> JointSample {
+    JointSample {
+        motor_id,
+        target_deg,
+        actual,
+    }
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("livelybot-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn rows_follow_the_channel_order() {
+    let mut recorder = Recorder::new(&[1, 2]).with_sensors(&["imu_pitch"]);
+    let names: Vec<String> = recorder.channels().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(
+        names,
+        [
+            "m1.target_deg", "m1.actual_deg", "m1.actual_rps", "m1.actual_nm",
+            "m2.target_deg", "m2.actual_deg", "m2.actual_rps", "m2.actual_nm",
+            "sensor.imu_pitch",
+        ]
+    );
+
+    // Motor 2 without feedback, motor 9 not recorded, no sensor reading
+    recorder.record(&[sample(2, 45.0, None), sample(1, 10.0, Some(state(1, 9.5))), sample(9, 1.0, None)]);
+    let row = recorder.latest().unwrap();
+    assert_eq!(&row.values[..5], &[10.0, 9.5, 0.5, -1.25, 45.0]);
+    assert!(row.values[5..].iter().all(|v| v.is_nan()));
+
+    recorder.record(&[]);
+    assert_eq!(recorder.len(), 2);
+    assert!(recorder.rows()[1].time_s >= recorder.rows()[0].time_s);
+}
+
+#[test]
+fn max_rows_discards_the_oldest_in_batches() {
+    let mut recorder = Recorder::new(&[1]).with_max_rows(16);
+    for i in 0..20 {
+        recorder.record(&[sample(1, i as f64, None)]);
+    }
+    // Full at 16 rows: the 17th drops 2 (16 / 8), and so on
+    assert_eq!(recorder.dropped_rows() + recorder.len() as u64, 20);
+    assert!(recorder.len() <= 16 && recorder.len() >= 14);
+    assert_eq!(recorder.rows().last().unwrap().values[0], 19.0);
+    assert_eq!(recorder.rows()[0].values[0], recorder.dropped_rows() as f64);
+}
+
+#[test]
+fn csv_round_trips_with_metadata_and_annotations() {
+    let metadata = SessionMetadata::default()
+        .with_robot("biped-3")
+        .with_operator("lab")
+        .with_extra("gait", "walk");
+    let mut recorder = Recorder::new(&[3]).with_sensors(&["load"]).with_metadata(metadata);
+    recorder.record(&[sample(3, 20.0, Some(state(3, 19.0)))]);
+    recorder.annotate("fell here");
+    recorder.record(&[sample(3, 21.0, None)]);
+
+    let path = temp_path("recording.csv");
+    recorder.write_csv(&path).unwrap();
+    let loaded = Recorder::read_csv(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(loaded.channels(), recorder.channels());
+    assert_eq!(loaded.metadata(), recorder.metadata());
+    assert_eq!(loaded.annotations()[0].text, "fell here");
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(&loaded.rows()[0].values[..4], &[20.0, 19.0, 0.5, -1.25]);
+    assert_eq!(loaded.rows()[1].values[0], 21.0);
+    assert!(loaded.rows()[1].values[1..].iter().all(|v| v.is_nan()));
+}
+
+#[test]
+fn malformed_csv_is_rejected() {
+    let path = temp_path("bad-recording.csv");
+    std::fs::write(&path, "time_s,m1.target_deg,m1.actual_deg,m1.actual_rps,m1.actual_nm\n0.0,1,2,3\n").unwrap();
+    let short_row = Recorder::read_csv(&path);
+    std::fs::write(&path, "time_s,m1.target_deg,bogus\n").unwrap();
+    let bad_columns = Recorder::read_csv(&path);
+    std::fs::remove_file(&path).ok();
+
+    assert!(short_row.err().unwrap().to_string().contains("expected 5 cells, found 4"));
+    assert!(bad_columns.err().unwrap().to_string().contains("unexpected columns"));
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn f64_at(bytes: &[u8], at: usize) -> f64 {
+    f64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+#[test]
+fn mdf4_layout() {
+    let path = temp_path("recording.mf4");
+    let channels = [Mdf4Channel::new("m1.actual_deg", "deg"), Mdf4Channel::new("m1.actual_nm", "Nm")];
+    let records = [(0.0, vec![1.0, 2.0]), (0.01, vec![3.0])];
+    let properties = [("robot".to_string(), "a<b".to_string())];
+    write_mdf4(&path, &channels, &records, &properties).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    // Identification block, then the header block at 64
+    assert_eq!(&bytes[..16], b"MDF     4.10    ");
+    assert_eq!(u16::from_le_bytes([bytes[28], bytes[29]]), 410);
+    assert_eq!(&bytes[64..68], b"##HD");
+
+    // Header -> data group -> data block
+    let dg = u64_at(&bytes, 64 + 24) as usize;
+    assert_eq!(&bytes[dg..dg + 4], b"##DG");
+    let dt = u64_at(&bytes, dg + 24 + 16) as usize;
+    assert_eq!(&bytes[dt..dt + 4], b"##DT");
+    assert_eq!(u64_at(&bytes, dt + 8), 24 + 2 * 24);
+
+    // Records of time + two values; missing values are NaN
+    let data = dt + 24;
+    assert_eq!(
+        [f64_at(&bytes, data), f64_at(&bytes, data + 8), f64_at(&bytes, data + 16), f64_at(&bytes, data + 24)],
+        [0.0, 1.0, 2.0, 0.01]
+    );
+    assert_eq!(f64_at(&bytes, data + 32), 3.0);
+    assert!(f64_at(&bytes, data + 40).is_nan());
+
+    let text = String::from_utf8_lossy(&bytes);
+    for needle in ["m1.actual_deg", "Nm", "<e name=\"robot\">a&lt;b</e>"] {
+        assert!(text.contains(needle), "missing {}", needle);
+    }
+}
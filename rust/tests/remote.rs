@@ -0,0 +1,197 @@
+//! Multi-host coordination over the UDP bridge, on loopback
+
+use livelybot_motor_control::authority::{AccessPolicy, Authority};
+use livelybot_motor_control::remote::RemoteTrajectory;
+use livelybot_motor_control::trajectory::Waypoint;
+use livelybot_motor_control::{
+    BridgeAgent, BridgeMessage, BridgeRequest, Coordinator, LivelyMotorController, MockTransport, Real, SimMotor,
+    Trajectory,
+};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn setup() -> (MockTransport, LivelyMotorController) {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    controller.enable_motor(1).unwrap();
+    (mock, controller)
+}
+
+/// Motor 1 from 0° to `target` in `duration`
+fn ramp(target: Real, duration: Duration) -> Trajectory {
+    Trajectory {
+        motor_ids: vec![1],
+        waypoints: vec![
+            Waypoint { time: Duration::ZERO, positions_deg: vec![0.0] },
+            Waypoint { time: duration, positions_deg: vec![target] },
+        ],
+    }
+}
+
+/// Clears the flag when dropped, also when a test assertion fails
+struct StopOnDrop<'a>(&'a AtomicBool);
+
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Serve `agents` until `f` returns
+fn serving<R>(agents: &[BridgeAgent], f: impl FnOnce(Vec<SocketAddr>) -> R) -> R {
+    let running = AtomicBool::new(true);
+    let addrs = agents.iter().map(|a| a.local_addr().unwrap()).collect();
+    thread::scope(|scope| {
+        let served: Vec<_> = agents.iter().map(|agent| scope.spawn(|| agent.serve(&running))).collect();
+        let result = {
+            let _stop = StopOnDrop(&running);
+            f(addrs)
+        };
+        for served in served {
+            served.join().unwrap().unwrap();
+        }
+        result
+    })
+}
+
+#[test]
+fn trajectories_survive_the_wire_format() {
+    let trajectory = ramp(30.0, Duration::from_millis(250));
+    let remote = RemoteTrajectory::from(&trajectory);
+    assert_eq!(remote.waypoints, [(0.0, vec![0.0]), (0.25, vec![30.0])]);
+
+    let request = BridgeRequest {
+        token: None,
+        message: BridgeMessage::Run {
+            routine: "wave".to_string(),
+            start_us: 1,
+            rate_hz: 100.0,
+            max_vel_rps: 1.0,
+            max_tqe_nm: 2.0,
+            trajectory: remote,
+        },
+    };
+    let json = serde_json::to_string(&request).unwrap();
+    assert!(json.starts_with(r#"{"type":"run","#), "{}", json);
+    let back: BridgeRequest = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, request);
+    let BridgeMessage::Run { trajectory: remote, .. } = back.message else { unreachable!() };
+    assert_eq!(Trajectory::from(remote), trajectory);
+}
+
+#[test]
+fn agents_on_the_same_host_share_the_clock() {
+    let (_mock_a, controller_a) = setup();
+    let (_mock_b, controller_b) = setup();
+    let agents = [
+        BridgeAgent::bind(&controller_a, "127.0.0.1:0").unwrap(),
+        BridgeAgent::bind(&controller_b, "127.0.0.1:0").unwrap(),
+    ];
+
+    let clocks = serving(&agents, |addrs| {
+        let clocks = Coordinator::new(&addrs).unwrap().sync_clocks(4).unwrap();
+        assert_eq!(clocks.iter().map(|c| c.agent).collect::<Vec<_>>(), addrs);
+        clocks
+    });
+    for clock in &clocks {
+        assert!(clock.offset_us.abs() < 5_000, "{:?}", clock);
+        assert!(clock.rtt < Duration::from_millis(50), "{:?}", clock);
+    }
+}
+
+#[test]
+fn a_synchronized_routine_runs_on_every_agent() {
+    let (mock_a, controller_a) = setup();
+    let (mock_b, controller_b) = setup();
+    let agents = [
+        BridgeAgent::bind(&controller_a, "127.0.0.1:0").unwrap(),
+        BridgeAgent::bind(&controller_b, "127.0.0.1:0").unwrap(),
+    ];
+
+    serving(&agents, |addrs| {
+        let coordinator = Coordinator::new(&addrs).unwrap();
+        let duration = Duration::from_millis(300);
+        let trajectories = [(addrs[0], ramp(30.0, duration)), (addrs[1], ramp(-20.0, duration))];
+        let lead = Duration::from_millis(200);
+        let start = Instant::now();
+        coordinator.run_synchronized("wave", &trajectories, lead, 100.0, 5.0, 2.0).unwrap();
+        // Nothing moves before the common start
+        assert!(mock_a.state(1).unwrap().position_deg.abs() < 1.0);
+        coordinator.wait_finished(Duration::from_secs(3)).unwrap();
+        assert!(start.elapsed() >= lead + duration, "{:?}", start.elapsed());
+    });
+    // Let the last setpoint settle
+    thread::sleep(Duration::from_millis(300));
+
+    for (mock, target) in [(mock_a, 30.0), (mock_b, -20.0)] {
+        let position = mock.state(1).unwrap().position_deg;
+        assert!((position - target).abs() < 1.5, "{} vs {}", position, target);
+    }
+}
+
+#[test]
+fn stop_aborts_a_running_routine() {
+    let (mock, controller) = setup();
+    let agents = [BridgeAgent::bind(&controller, "127.0.0.1:0").unwrap()];
+
+    serving(&agents, |addrs| {
+        let coordinator = Coordinator::new(&addrs).unwrap();
+        let trajectories = [(addrs[0], ramp(90.0, Duration::from_secs(10)))];
+        coordinator
+            .run_synchronized("slow", &trajectories, Duration::from_millis(100), 100.0, 5.0, 2.0)
+            .unwrap();
+        thread::sleep(Duration::from_millis(300));
+        let stopped = Instant::now();
+        coordinator.stop().unwrap();
+        coordinator.wait_finished(Duration::from_secs(2)).unwrap();
+        assert!(stopped.elapsed() < Duration::from_secs(1), "{:?}", stopped.elapsed());
+    });
+
+    let position = mock.state(1).unwrap().position_deg;
+    assert!(position < 20.0, "{}", position);
+}
+
+#[test]
+fn requests_beyond_the_token_are_rejected() {
+    let (_mock, controller) = setup();
+    let policy = AccessPolicy::default().with_token("pilot", Authority::Operator);
+    let agents = [BridgeAgent::bind(&controller, "127.0.0.1:0").unwrap().with_policy(policy)];
+
+    serving(&agents, |addrs| {
+        let trajectories = [(addrs[0], ramp(10.0, Duration::from_millis(100)))];
+        let lead = Duration::from_millis(200);
+
+        // Observers may ping, not run
+        let anonymous = Coordinator::new(&addrs).unwrap();
+        let error = anonymous
+            .run_synchronized("wave", &trajectories, lead, 100.0, 5.0, 2.0)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with(&format!("agent {} rejected the routine: ", addrs[0])), "{}", error);
+
+        let pilot = Coordinator::new(&addrs).unwrap().with_token("pilot");
+        pilot.run_synchronized("wave", &trajectories, lead, 100.0, 5.0, 2.0).unwrap();
+        pilot.wait_finished(Duration::from_secs(2)).unwrap();
+
+        // Plain reads, straight over a socket, once the last setpoint settled
+        thread::sleep(Duration::from_millis(300));
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let request = BridgeRequest {
+            token: None,
+            message: BridgeMessage::ReadState { motor_id: 1 },
+        };
+        socket.send_to(&serde_json::to_vec(&request).unwrap(), addrs[0]).unwrap();
+        let mut buf = [0; 1024];
+        let (len, _) = socket.recv_from(&mut buf).unwrap();
+        match serde_json::from_slice(&buf[..len]).unwrap() {
+            BridgeMessage::State { motor_id, position_deg, .. } => {
+                assert_eq!(motor_id, 1);
+                assert!((position_deg - 10.0).abs() < 1.5, "{}", position_deg);
+            }
+            other => panic!("{:?}", other),
+        }
+    });
+}
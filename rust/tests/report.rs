@@ -0,0 +1,39 @@
+//! Decoding pushed replies with a selected report content
+
+use livelybot_motor_control::{protocol, MotorState, ReportField, ReportMask};
+
+#[test]
+fn reports_are_decoded_with_the_selected_fields() {
+    let mask = ReportMask::POSITION | ReportMask::TEMPERATURE;
+    assert_eq!(mask.fields(), vec![ReportField::Position, ReportField::Temperature]);
+    assert_eq!(mask.to_string(), "position+temperature");
+
+    // Two int16 values from the position register: 2500 counts, 45.2 °C
+    let reply = protocol::parse_reply(&[0x26, 0x01, 0xC4, 0x09, 0xC4, 0x01, 0x50, 0x50]).unwrap();
+    assert!(mask.matches(&reply));
+    assert!(!ReportMask::STATE.matches(&reply));
+
+    let report = mask.decode(5, &reply).unwrap();
+    assert_eq!(report.position_deg, Some(90.0));
+    assert_eq!(report.temperature_c, Some(45.2));
+    assert_eq!(report.velocity_rps, None);
+
+    // Unreported fields keep their previous values
+    let previous = MotorState {
+        motor_id: 5,
+        position_deg: 10.0,
+        velocity_rps: 1.5,
+        torque_nm: 0.4,
+        acceleration_rps2: None,
+    };
+    let state = report.merge_into(Some(&previous));
+    assert_eq!((state.position_deg, state.velocity_rps, state.torque_nm), (90.0, 1.5, 0.4));
+}
+
+#[test]
+fn masks_a_reply_cannot_hold_are_refused() {
+    assert_eq!(ReportMask::from_bits(0b0111).unwrap(), ReportMask::STATE);
+    assert!(ReportMask::from_bits(0).is_err());
+    assert!(ReportMask::from_bits(0b1111).is_err());
+    assert!(ReportMask::from_bits(0x10).is_err());
+}
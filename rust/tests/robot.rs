@@ -0,0 +1,136 @@
+//! The robot! macro and freezing in the current pose
+
+use livelybot_motor_control::protocol::{stream_target, Register, ValueType, ANGLE_STREAM_ID, REPLY_FLAG};
+use livelybot_motor_control::robot::{freeze, HoldStiffness, Joint};
+use livelybot_motor_control::{
+    degrees_to_position, position_to_degrees, robot, LivelyMotorController, MockTransport, RawFrame, SimMotor,
+};
+use std::thread;
+use std::time::Duration;
+
+robot! {
+    /// A two-joint leg
+    #[derive(Clone, Copy)]
+    pub struct Leg {
+        hip: 1,
+        knee: 2,
+    }
+}
+
+fn setup(motor_ids: &[u8]) -> (MockTransport, LivelyMotorController) {
+    let mock = motor_ids
+        .iter()
+        .fold(MockTransport::new(), |mock, &id| mock.with_motor(id, SimMotor::default()));
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    (mock, controller)
+}
+
+/// Drive motors to `targets_deg` and leave them there
+fn pose(controller: &LivelyMotorController, targets_deg: &[(u8, f64)]) {
+    for &(motor_id, _) in targets_deg {
+        controller.enable_motor(motor_id).unwrap();
+    }
+    for _ in 0..60 {
+        for &(motor_id, target) in targets_deg {
+            controller.send_angle_command_to(motor_id, degrees_to_position(target), 0, 0).unwrap();
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn register(controller: &LivelyMotorController, motor_id: u8, reg: Register) -> f32 {
+    controller.read_registers(motor_id, reg, ValueType::Float, 1).unwrap().float(0).unwrap()
+}
+
+#[test]
+fn freezing_holds_exactly_the_measured_pose() {
+    let (mock, controller) = setup(&[1, 2]);
+    pose(&controller, &[(1, 37.3), (2, -121.9)]);
+    let joints = [Joint::new(&controller, 1, "hip"), Joint::new(&controller, 2, "knee")];
+    let stiffness = HoldStiffness {
+        kp: 4.0,
+        kd: 0.3,
+        ..HoldStiffness::default()
+    };
+
+    let sent = controller.bus().subscribe_sent();
+    let frozen = freeze(&joints, stiffness).unwrap();
+    let frames: Vec<RawFrame> = std::iter::from_fn(|| sent.try_recv()).map(|f| RawFrame::from_frame(&f)).collect();
+
+    assert_eq!(frozen.iter().map(|s| s.motor_id).collect::<Vec<_>>(), [1, 2]);
+    // Both poses are read before either joint is commanded
+    let is_read = |f: &RawFrame| f.id & REPLY_FLAG != 0 && stream_target(f.id).is_none();
+    let first_command = frames.iter().position(|f| stream_target(f.id).is_some()).unwrap();
+    assert_eq!(frames.iter().filter(|f| is_read(f)).count(), 2);
+    assert_eq!(frames[..first_command].iter().filter(|f| is_read(f)).count(), 2);
+
+    // The held count is the measured count: no rounding or drift in between
+    for state in &frozen {
+        let command = frames
+            .iter()
+            .find(|f| stream_target(f.id) == Some((ANGLE_STREAM_ID, state.motor_id)))
+            .unwrap();
+        let held = i16::from_le_bytes([command.data[0], command.data[1]]);
+        assert_eq!(held, degrees_to_position(state.position_deg));
+        assert_eq!(position_to_degrees(held), state.position_deg);
+        assert_eq!(register(&controller, state.motor_id, Register::Kp), 4.0);
+        assert_eq!(register(&controller, state.motor_id, Register::Kd), 0.3);
+    }
+    assert!((frozen[0].position_deg - 37.3).abs() < 1.0, "{:?}", frozen);
+    assert!((frozen[1].position_deg + 121.9).abs() < 1.0, "{:?}", frozen);
+
+    // And the joints stay there
+    thread::sleep(Duration::from_millis(200));
+    for state in &frozen {
+        let now = mock.state(state.motor_id).unwrap().position_deg;
+        assert!((now - state.position_deg).abs() < 0.5, "{} vs {}", now, state.position_deg);
+    }
+}
+
+#[test]
+fn a_failed_joint_does_not_stop_the_others_from_freezing() {
+    let (mock, controller) = setup(&[1]);
+    pose(&controller, &[(1, -45.0)]);
+    let joints = [Joint::new(&controller, 1, "hip"), Joint::new(&controller, 3, "knee")];
+
+    let error = freeze(&joints, HoldStiffness::default()).unwrap_err();
+    let message = error.to_string();
+    assert!(message.starts_with("could not freeze knee (motor 3): "), "{}", message);
+    assert!(!message.contains("hip"), "{}", message);
+
+    // The hip was still frozen where it stood
+    thread::sleep(Duration::from_millis(100));
+    assert!((mock.state(1).unwrap().position_deg + 45.0).abs() < 1.0, "{:?}", mock.state(1));
+    assert_eq!(register(&controller, 1, Register::Kp), 1.0);
+}
+
+#[test]
+fn declared_robots_command_their_joints() {
+    let (mock, controller) = setup(&[1, 2]);
+    let leg = Leg::new(&controller);
+
+    assert_eq!(Leg::JOINTS, [("hip", 1), ("knee", 2)]);
+    let joints: Vec<(&str, u8)> = leg.joints().iter().map(|j| (j.name(), j.motor_id())).collect();
+    assert_eq!(joints, Leg::JOINTS);
+    assert_eq!((leg.knee().name(), leg.knee().motor_id()), ("knee", 2));
+
+    leg.hip().enable().unwrap();
+    leg.knee().enable().unwrap();
+    for _ in 0..60 {
+        leg.hip().set_angle(-20.0, 5.0, 3.0).unwrap();
+        leg.knee().set_angle(45.0, 5.0, 3.0).unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    let knee = leg.knee().read_state().unwrap();
+    assert!((knee.position_deg - 45.0).abs() < 1.0, "{:?}", knee);
+    assert!((mock.state(1).unwrap().position_deg + 20.0).abs() < 1.0, "{:?}", mock.state(1));
+
+    let frozen = leg.freeze().unwrap();
+    assert_eq!(frozen.iter().map(|s| s.motor_id).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(register(&controller, 2, Register::Kp), HoldStiffness::default().kp);
+
+    let error = leg.apply_profile("soft").unwrap_err();
+    assert_eq!(error.to_string(), "no profiles configured for Leg");
+    leg.knee().disable().unwrap();
+    assert_eq!(controller.enabled_motors(), [1]);
+}
@@ -0,0 +1,169 @@
+//! ROS 2 bridge over rosbridge (feature `ros2`)
+#![cfg(feature = "ros2")]
+
+use livelybot_motor_control::ros2::JointState;
+use livelybot_motor_control::{JointMapping, LivelyMotorController, MockTransport, Ros2Bridge, SimMotor};
+use serde_json::{json, Value};
+use std::f64::consts::FRAC_PI_2;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tungstenite::Message;
+
+fn mapping() -> JointMapping {
+    serde_json::from_value(json!({"joints": [
+        {"name": "hip", "motor_id": 1},
+        {"name": "knee", "motor_id": 2, "reversed": true, "offset_deg": 10.0}
+    ]}))
+    .unwrap()
+}
+
+fn setup() -> (MockTransport, LivelyMotorController) {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(2, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    (mock, controller)
+}
+
+fn command(names: &[&str], positions: &[f64]) -> JointState {
+    JointState {
+        name: names.iter().map(|n| n.to_string()).collect(),
+        position: positions.to_vec(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn commands_need_mapped_enabled_joints() {
+    let (mock, controller) = setup();
+    let bridge = Ros2Bridge::new(&controller, mapping());
+
+    let err = bridge.apply_command(&command(&["hip"], &[0.5])).unwrap_err();
+    assert!(err.to_string().contains("joint 'hip' (motor 1) is not enabled"), "{}", err);
+    bridge.set_enabled(true).unwrap();
+    assert_eq!(controller.enabled_motors(), [1, 2]);
+
+    let err = bridge.apply_command(&command(&["hip", "ankle"], &[0.5, 0.0])).unwrap_err();
+    assert!(err.to_string().contains("'ankle' is not in the mapping"), "{}", err);
+    let err = bridge.apply_command(&command(&["hip", "knee"], &[0.5])).unwrap_err();
+    assert!(err.to_string().contains("2 joint names but 1 positions"), "{}", err);
+
+    // The knee is reversed with an offset: joint π/2 is motor -80°
+    for _ in 0..100 {
+        assert_eq!(bridge.apply_command(&command(&["hip", "knee"], &[0.0, FRAC_PI_2])).unwrap(), 2);
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!((mock.state(2).unwrap().position_deg + 80.0).abs() < 2.0, "{:?}", mock.state(2));
+
+    let feedback = bridge.feedback().unwrap();
+    assert_eq!(feedback.name, ["hip", "knee"]);
+    assert!(feedback.position[0].abs() < 0.05, "{:?}", feedback);
+    assert!((feedback.position[1] - FRAC_PI_2).abs() < 0.05, "{:?}", feedback);
+    assert!(feedback.header.stamp.sec > 0);
+}
+
+fn read_json(socket: &mut tungstenite::WebSocket<std::net::TcpStream>) -> Value {
+    loop {
+        if let Message::Text(text) = socket.read().unwrap() {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[test]
+fn serves_topics_and_the_enable_service_through_rosbridge() {
+    let (mock, controller) = setup();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let running = AtomicBool::new(true);
+
+    thread::scope(|scope| {
+        let server = scope.spawn(|| {
+            let mut socket = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+            let setup: Vec<Value> = (0..3).map(|_| read_json(&mut socket)).collect();
+            assert_eq!(setup[0]["op"], "advertise");
+            assert_eq!(setup[1]["topic"], "/robot/joint_commands");
+            assert_eq!(setup[2]["type"], "std_srvs/srv/SetBool");
+
+            let call = json!({"op": "call_service", "id": "c1", "service": "/robot/enable", "args": {"data": true}});
+            socket.send(Message::text(call.to_string())).unwrap();
+            let response = loop {
+                let message = read_json(&mut socket);
+                if message["op"] == "service_response" {
+                    break message;
+                }
+                assert_eq!(message["topic"], "/robot/joint_states");
+            };
+            assert_eq!(response["id"], "c1");
+            assert_eq!(response["values"]["success"], true);
+
+            let target = json!({"name": ["hip"], "position": [FRAC_PI_2], "effort": [3.0]});
+            let deadline = Instant::now() + Duration::from_secs(3);
+            let mut hip = 0.0;
+            while Instant::now() < deadline && (hip - FRAC_PI_2).abs() > 0.05 {
+                let publish = json!({"op": "publish", "topic": "/robot/joint_commands", "msg": target});
+                socket.send(Message::text(publish.to_string())).unwrap();
+                let state = read_json(&mut socket);
+                hip = state["msg"]["position"][0].as_f64().unwrap();
+            }
+            running.store(false, Ordering::SeqCst);
+            // Keep the connection open until the bridge closes it
+            while !matches!(socket.read(), Ok(Message::Close(_)) | Err(_)) {}
+            hip
+        });
+
+        let mut bridge = Ros2Bridge::new(&controller, mapping()).with_namespace("/robot/").with_rate(100.0);
+        bridge.serve(&url, &running).unwrap();
+        assert_eq!(bridge.rejected_commands(), 0, "{:?}", bridge.last_error());
+        let hip = server.join().unwrap();
+        assert!((hip - FRAC_PI_2).abs() < 0.05, "{}", hip);
+    });
+    assert!((mock.state(1).unwrap().position_deg - 90.0).abs() < 3.0, "{:?}", mock.state(1));
+}
+
+#[test]
+fn malformed_messages_are_rejected_without_stopping_the_bridge() {
+    let (_mock, controller) = setup();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let running = AtomicBool::new(true);
+
+    thread::scope(|scope| {
+        let server = scope.spawn(|| {
+            let mut socket = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+            (0..3).for_each(|_| drop(read_json(&mut socket)));
+            let call = |socket: &mut tungstenite::WebSocket<std::net::TcpStream>, args: Value| {
+                let call = json!({"op": "call_service", "id": "c", "service": "/livelybot/enable", "args": args});
+                socket.send(Message::text(call.to_string())).unwrap();
+                loop {
+                    let message = read_json(socket);
+                    if message["op"] == "service_response" {
+                        return message["values"].clone();
+                    }
+                }
+            };
+
+            socket.send(Message::text("{not json")).unwrap();
+            let bad_command = json!({"op": "publish", "topic": "/livelybot/joint_commands", "msg": {"name": 5}});
+            socket.send(Message::text(bad_command.to_string())).unwrap();
+            let without_data = call(&mut socket, json!({}));
+            // The bridge is still serving
+            let enabled = call(&mut socket, json!({"data": true}));
+            running.store(false, Ordering::SeqCst);
+            while !matches!(socket.read(), Ok(Message::Close(_)) | Err(_)) {}
+            (without_data, enabled)
+        });
+
+        let mut bridge = Ros2Bridge::new(&controller, mapping()).with_rate(100.0);
+        bridge.serve(&url, &running).unwrap();
+        let (without_data, enabled) = server.join().unwrap();
+        assert_eq!(without_data["success"], false);
+        assert_eq!(without_data["message"], "SetBool request without data");
+        assert_eq!(enabled["success"], true);
+        assert_eq!(bridge.rejected_commands(), 3, "{:?}", bridge.last_error());
+        assert_eq!(bridge.last_error(), Some("SetBool request without data"));
+    });
+    assert_eq!(controller.enabled_motors(), [1, 2]);
+}
@@ -0,0 +1,53 @@
+//! Real-time scheduling helpers and their fallback in the control loop
+
+use livelybot_motor_control::{rt, ControlLoop};
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn out_of_range_requests_fail() {
+    assert!(rt::set_realtime_priority(0).is_err());
+    assert!(rt::set_realtime_priority(100).is_err());
+    assert!(rt::pin_to_core(usize::MAX).is_err());
+}
+
+#[test]
+fn loop_runs_without_realtime_privileges() {
+    // On a thread of its own, so the test harness thread keeps its scheduling
+    let handle = thread::spawn(|| {
+        let running = AtomicBool::new(true);
+        let mut control = ControlLoop::with_period(Duration::from_millis(5))
+            .with_realtime_priority(80)
+            .with_core(0);
+        control.run(&running, |info| Ok(info.cycle < 19)).unwrap();
+        // Either the scheduling was applied or the loop says why not
+        assert_eq!(control.is_realtime(), control.realtime_errors().is_empty());
+        (control.stats().cycles, control.jitter().periods)
+    });
+    let (cycles, periods) = handle.join().unwrap();
+    assert_eq!(cycles, 20);
+    assert!(periods > 0 && periods <= 19, "{} periods", periods);
+}
+
+#[test]
+fn unrequested_realtime_is_not_reported() {
+    let running = AtomicBool::new(true);
+    let mut control = ControlLoop::with_period(Duration::from_millis(1));
+    control.run(&running, |info| Ok(info.cycle < 2)).unwrap();
+    assert!(!control.is_realtime());
+    assert!(control.realtime_errors().is_empty());
+}
+
+#[test]
+fn unavailable_core_falls_back() {
+    let handle = thread::spawn(|| {
+        let running = AtomicBool::new(true);
+        let mut control = ControlLoop::with_period(Duration::from_millis(1)).with_core(usize::MAX);
+        control.run(&running, |info| Ok(info.cycle < 2)).unwrap();
+        (control.is_realtime(), control.realtime_errors().iter().map(|e| e.to_string()).collect::<Vec<_>>())
+    });
+    let (realtime, errors) = handle.join().unwrap();
+    assert!(!realtime);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+}
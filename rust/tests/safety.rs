@@ -0,0 +1,86 @@
+//! Torque duty envelopes and battery derating
+
+use livelybot_motor_control::events::EnvelopeEventKind;
+use livelybot_motor_control::safety::DutyTracker;
+use livelybot_motor_control::{BatteryDerating, BatteryState, SafetyMonitor, TorqueEnvelope};
+use std::time::{Duration, Instant};
+
+/// Exact in binary, so the budget arithmetic below is exact too
+const STEP: Duration = Duration::from_millis(125);
+
+#[test]
+fn envelope_approaches_limits_and_recovers() {
+    let mut tracker = DutyTracker::new(TorqueEnvelope::new(1.0, 3.0, Duration::from_secs(1)));
+    let start = Instant::now();
+    let mut events = Vec::new();
+    let mut allowed = Vec::new();
+    // 2 Nm is above the continuous rating: the 1 s budget lasts 8 steps, then
+    // recovers at half speed while the command stays at 0.5 Nm
+    for tick in 0..20u32 {
+        let torque = if tick < 10 { -2.0 } else { 0.5 };
+        let (torque, kinds) = tracker.update(torque, start + STEP * tick);
+        allowed.push(torque);
+        events.extend(kinds.into_iter().map(|kind| (tick, kind)));
+    }
+
+    assert_eq!(
+        events,
+        vec![
+            (7, EnvelopeEventKind::Approaching),
+            (8, EnvelopeEventKind::Limited),
+            (16, EnvelopeEventKind::Recovered),
+        ]
+    );
+    assert_eq!(&allowed[..10], &[-2.0, -2.0, -2.0, -2.0, -2.0, -2.0, -2.0, -2.0, -2.0, -1.0]);
+    assert!(!tracker.is_limited());
+    assert_eq!(tracker.budget_used(), 0.3125);
+    assert_eq!(tracker.update(2.0, start + STEP * 20).0, 2.0, "peak torque allowed again");
+}
+
+#[test]
+fn commands_above_peak_are_clamped_once_reported() {
+    let mut tracker = DutyTracker::new(TorqueEnvelope::new(1.0, 3.0, Duration::from_secs(1)));
+    let now = Instant::now();
+    let (allowed, events) = tracker.update(5.0, now);
+    assert_eq!((allowed, events), (3.0, vec![EnvelopeEventKind::PeakClamped]));
+    let (allowed, events) = tracker.update(-5.0, now + STEP);
+    assert_eq!(allowed, -3.0);
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+#[test]
+fn known_models_have_envelopes() {
+    let envelope = TorqueEnvelope::for_model(" 5047 ").unwrap();
+    assert_eq!((envelope.continuous_nm, envelope.peak_nm), (3.0, 8.0));
+    assert!(TorqueEnvelope::for_model("SIM").is_none());
+    assert_eq!(TorqueEnvelope::new(5.0, 2.0, Duration::ZERO).peak_nm, 5.0);
+}
+
+#[test]
+fn derating_is_linear_between_cutoff_and_nominal() {
+    let derating = BatteryDerating::new(24.0, 20.0, 0.4);
+    assert_eq!(derating.scale(25.0), 1.0);
+    assert_eq!(derating.scale(24.0), 1.0);
+    assert!((derating.scale(22.0) - 0.7).abs() < 1e-12);
+    assert_eq!(derating.scale(20.0), 0.4);
+    assert_eq!(derating.scale(12.0), 0.4);
+}
+
+#[test]
+fn monitor_scales_torque_with_the_battery() {
+    let monitor = SafetyMonitor::new().with_battery_derating(BatteryDerating::new(24.0, 20.0, 0.5));
+    assert_eq!(monitor.limit_torque(1, 2.0), 2.0);
+
+    let battery = BatteryState {
+        timestamp: Instant::now(),
+        voltage_v: 20.0,
+        current_a: 3.0,
+        soc_pct: None,
+    };
+    assert_eq!(monitor.update_battery(&battery), 0.5);
+    assert_eq!(monitor.limit_torque(1, 2.0), 1.0);
+
+    monitor.set_envelope(1, TorqueEnvelope::new(1.0, 3.0, Duration::from_secs(1)));
+    assert_eq!(monitor.limit_torque(1, 10.0), 1.5, "peak clamp, then battery scale");
+    assert_eq!(monitor.budget_used(2), None);
+}
@@ -0,0 +1,26 @@
+//! Conversion constants checked against the scaling motors report
+
+use livelybot_motor_control::{LivelyMotorController, MockTransport, MotorError, Scaling, SimMotor};
+
+#[test]
+fn mismatched_scaling_is_refused() {
+    let doubled = Scaling {
+        position: Scaling::EXPECTED.position * 2.0,
+        ..Scaling::EXPECTED
+    };
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(2, SimMotor::default().with_scaling(None))
+        .with_motor(3, SimMotor::default().with_scaling(Some(doubled)));
+    let controller = LivelyMotorController::with_transport("mock", mock);
+
+    assert_eq!(controller.check_scaling(1).unwrap(), Some(Scaling::EXPECTED));
+    assert_eq!(controller.check_scaling(2).unwrap(), None);
+
+    match controller.check_scaling(3).unwrap_err() {
+        MotorError::ScalingMismatch { motor_id: 3, reason, .. } => {
+            assert_eq!(reason, "position scaling 20000 counts, expected 10000")
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
@@ -0,0 +1,94 @@
+//! Periodic tasks of the control loop scheduler
+
+use livelybot_motor_control::{CycleInfo, Scheduler};
+use std::cell::RefCell;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn cycle(cycle: u64) -> CycleInfo {
+    CycleInfo {
+        cycle,
+        elapsed: Duration::ZERO,
+        deadline: Instant::now(),
+        tx_deadline: None,
+    }
+}
+
+#[test]
+fn cycle_tasks_run_every_n_cycles() {
+    let runs = RefCell::new(Vec::new());
+    let mut scheduler = Scheduler::new();
+    scheduler.every_cycles(3, |info| {
+        runs.borrow_mut().push(info.cycle);
+        Ok(())
+    });
+
+    // Cycles 4 and 5 are skipped: cycle 6 runs the task once, cycle 9 is next
+    for n in [0, 1, 2, 3, 6, 7, 8, 9, 10] {
+        scheduler.tick(&cycle(n)).unwrap();
+    }
+    drop(scheduler);
+    assert_eq!(runs.into_inner(), [3, 6, 9]);
+}
+
+#[test]
+fn interval_tasks_run_on_wall_time_without_drift() {
+    let runs = RefCell::new(0);
+    let mut scheduler = Scheduler::new();
+    let start = Instant::now();
+    scheduler.every(Duration::from_millis(100), |_| {
+        *runs.borrow_mut() += 1;
+        Ok(())
+    });
+
+    scheduler.tick(&cycle(0)).unwrap();
+    assert_eq!(*runs.borrow(), 0);
+
+    // Late by one and a half intervals: one catch-up run, not two
+    thread::sleep(Duration::from_millis(250));
+    scheduler.tick(&cycle(1)).unwrap();
+    scheduler.tick(&cycle(2)).unwrap();
+    assert_eq!(*runs.borrow(), 1);
+
+    // The next run stays due at 300 ms, not one interval after the late one
+    thread::sleep((start + Duration::from_millis(310)).saturating_duration_since(Instant::now()));
+    scheduler.tick(&cycle(3)).unwrap();
+    assert_eq!(*runs.borrow(), 2);
+}
+
+#[test]
+fn zero_interval_runs_every_cycle() {
+    let runs = RefCell::new(0);
+    let mut scheduler = Scheduler::new();
+    scheduler.every(Duration::ZERO, |_| {
+        *runs.borrow_mut() += 1;
+        Ok(())
+    });
+    for n in 0..5 {
+        scheduler.tick(&cycle(n)).unwrap();
+    }
+    drop(scheduler);
+    assert_eq!(runs.into_inner(), 4);
+}
+
+#[test]
+fn cancelled_tasks_stop_running() {
+    let runs = RefCell::new(Vec::new());
+    let mut scheduler = Scheduler::new();
+    let first = scheduler.every_cycles(1, |_| {
+        runs.borrow_mut().push("first");
+        Ok(())
+    });
+    scheduler.every_cycles(1, |_| {
+        runs.borrow_mut().push("second");
+        Ok(())
+    });
+    scheduler.tick(&cycle(1)).unwrap();
+
+    assert!(scheduler.cancel(first));
+    assert!(!scheduler.cancel(first));
+    assert_eq!(scheduler.len(), 1);
+    scheduler.tick(&cycle(2)).unwrap();
+    drop(scheduler);
+    assert_eq!(runs.into_inner(), ["first", "second", "second"]);
+}
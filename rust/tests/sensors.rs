@@ -0,0 +1,88 @@
+//! External sensors sampled by the control loop
+
+use anyhow::anyhow;
+use livelybot_motor_control::{ControlLoop, Recorder};
+use std::cell::Cell;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+#[test]
+fn sensors_are_sampled_every_cycle_before_the_step() {
+    let running = AtomicBool::new(true);
+    let reads = Cell::new(0u32);
+    let mut control = ControlLoop::with_period(Duration::from_millis(2));
+    control.add_sensor("load", || {
+        reads.set(reads.get() + 1);
+        Some(reads.get() as f64)
+    });
+    // Only every other cycle has a reading, like a sensor sampling at half the rate
+    control.add_sensor("imu_pitch", || reads.get().is_multiple_of(2).then_some(-1.5));
+
+    let mut seen = Vec::new();
+    control
+        .run_with_sensors(&running, |info, frame| {
+            assert_eq!(frame.names().collect::<Vec<_>>(), ["load", "imu_pitch"]);
+            // Sampled for this cycle, not left over from the previous one
+            assert_eq!(reads.get() as u64, info.cycle + 1);
+            seen.push((frame.get("load"), frame.get("imu_pitch")));
+            Ok(info.cycle < 3)
+        })
+        .unwrap();
+    drop(control);
+
+    assert_eq!(reads.get(), 4);
+    assert_eq!(
+        seen,
+        [(Some(1.0), None), (Some(2.0), Some(-1.5)), (Some(3.0), None), (Some(4.0), Some(-1.5))]
+    );
+}
+
+#[test]
+fn a_sensor_of_the_same_name_is_replaced() {
+    let running = AtomicBool::new(true);
+    let mut control = ControlLoop::with_period(Duration::from_millis(1));
+    control.add_sensor("load", || Some(1.0));
+    control.add_sensor("load", || Some(2.0));
+
+    let mut frames = Vec::new();
+    control
+        .run_with_sensors(&running, |info, frame| {
+            frames.push(frame.clone());
+            Ok(info.cycle < 1)
+        })
+        .unwrap();
+    assert_eq!(frames.len(), 2);
+    assert!(frames.iter().all(|f| f.iter().collect::<Vec<_>>() == [("load", Some(2.0))]), "{:?}", frames);
+}
+
+#[test]
+fn missing_readings_are_recorded_as_nan_and_step_errors_stop_the_loop() {
+    let running = AtomicBool::new(true);
+    let mut recorder = Recorder::new(&[1]).with_sensors(&["load"]);
+    let mut control = ControlLoop::with_period(Duration::from_millis(1));
+    let mut reads = 0;
+    control.add_sensor("load", move || {
+        reads += 1;
+        (reads % 2 == 1).then_some(reads as f64 * 10.0)
+    });
+
+    let err = control
+        .run_with_sensors(&running, |info, frame| {
+            recorder.record_with_sensors(&[], frame);
+            match frame.get("load") {
+                None if info.cycle >= 3 => Err(anyhow!("load cell silent in cycle {}", info.cycle)),
+                _ => Ok(true),
+            }
+        })
+        .unwrap_err();
+    drop(control);
+    assert_eq!(err.to_string(), "load cell silent in cycle 3");
+
+    // The loop stopped on the error: no fifth cycle was recorded
+    let loads: Vec<f64> = recorder.rows().iter().map(|row| *row.values.last().unwrap()).collect();
+    assert_eq!(loads.len(), 4);
+    assert_eq!(loads[0], 10.0);
+    assert!(loads[1].is_nan());
+    assert_eq!(loads[2], 30.0);
+    assert!(loads[3].is_nan());
+}
@@ -0,0 +1,45 @@
+//! Daemon service config and systemd notifications
+
+use livelybot_motor_control::service::{ServiceConfig, SystemdNotifier};
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+#[test]
+fn park_config_is_resolved_next_to_the_service_config() {
+    let dir = std::env::temp_dir().join(format!("motord-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("robot.json");
+    std::fs::write(&path, r#"{ "motor_ids": [1, 2], "park_config": "park.json" }"#).unwrap();
+
+    let config = ServiceConfig::load(&path).unwrap();
+    assert_eq!(config.park_config, Some(dir.join("park.json")));
+    assert_eq!(config.interface, "can0");
+    assert_eq!(config.park_pose, "park");
+
+    std::fs::write(&path, r#"{ "motor_ids": [1], "watchdog_ms": 500, "motor_timeout_ms": 100 }"#).unwrap();
+    assert!(ServiceConfig::load(&path).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn notifications_reach_the_notify_socket() {
+    let path = std::env::temp_dir().join(format!("motord-notify-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let systemd = UnixDatagram::bind(&path).unwrap();
+    systemd.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+
+    std::env::set_var("NOTIFY_SOCKET", &path);
+    std::env::set_var("WATCHDOG_USEC", "2000000");
+    let notifier = SystemdNotifier::from_env().unwrap();
+    assert!(notifier.is_active());
+    assert_eq!(notifier.watchdog_timeout(), Some(Duration::from_secs(2)));
+
+    notifier.ready("serving 2 motors").unwrap();
+    notifier.watchdog().unwrap();
+    let mut buf = [0u8; 256];
+    let n = systemd.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"READY=1\nSTATUS=serving 2 motors");
+    let n = systemd.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"WATCHDOG=1");
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,171 @@
+//! Bus load accounting and shaping on a shared bus
+
+use livelybot_motor_control::protocol::{stream_id, ANGLE_STREAM_ID, REPLY_FLAG};
+use livelybot_motor_control::shaping::{frame_bits, is_motor_frame, is_query};
+use livelybot_motor_control::{BusLoadReport, BusMonitor, CanBus, CanTransport, RawFrame};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BITRATE: u32 = 125_000;
+
+/// A transport receiving the frames a test puts on it
+#[derive(Clone, Default)]
+struct Inject(Arc<Mutex<VecDeque<RawFrame>>>);
+
+impl Inject {
+    fn put(&self, frame: &RawFrame, count: usize) {
+        self.0.lock().unwrap().extend(std::iter::repeat_n(frame.clone(), count));
+    }
+}
+
+impl CanTransport for Inject {
+    fn send(&self, _frame: &RawFrame) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        let frame = self.0.lock().unwrap().pop_front();
+        if frame.is_none() {
+            thread::sleep(timeout.min(Duration::from_millis(1)));
+        }
+        Ok(frame)
+    }
+}
+
+fn bits(frame: &RawFrame) -> u32 {
+    frame_bits(&frame.to_frame().unwrap())
+}
+
+#[test]
+fn frames_cost_their_worst_case_bits() {
+    // Stuffable bits plus one stuff bit per four, then 13 fixed bits
+    assert_eq!(bits(&RawFrame::standard(0x100, &[])), 34 + 8 + 13);
+    assert_eq!(bits(&RawFrame::standard(0x100, &[0; 8])), 98 + 24 + 13);
+    assert_eq!(bits(&RawFrame::extended(0x101, &[])), 54 + 13 + 13);
+    assert_eq!(bits(&RawFrame::extended(0x101, &[0; 8])), 118 + 29 + 13);
+    assert_eq!(bits(&RawFrame::extended(0x101, &[0; 2])), bits(&RawFrame::extended(0x1FFF_FFFF, &[0; 2])));
+}
+
+#[test]
+fn frames_are_told_apart_by_id() {
+    let frame = |raw: RawFrame| raw.to_frame().unwrap();
+    let command = frame(RawFrame::extended(0x0001, &[0; 8]));
+    let read = frame(RawFrame::extended(0x0001 | REPLY_FLAG, &[0; 8]));
+    let stream = frame(RawFrame::extended(stream_id(ANGLE_STREAM_ID, 1), &[0; 8]));
+    let bms = frame(RawFrame::extended(0x18FF_50E5, &[0; 8]));
+    let imu = frame(RawFrame::standard(0x100, &[0; 8]));
+
+    assert!([&command, &read, &stream].iter().all(|f| is_motor_frame(f)));
+    assert!(!is_motor_frame(&bms) && !is_motor_frame(&imu));
+    assert_eq!([&command, &read, &stream, &bms, &imu].map(is_query), [false, true, false, false, false]);
+}
+
+#[test]
+fn load_is_split_between_motors_and_other_nodes() {
+    let inject = Inject::default();
+    let bus = CanBus::with_transport(inject.clone(), "inject", BITRATE);
+    bus.reserve_bandwidth(0.25).unwrap();
+    let mut monitor = BusMonitor::new(&bus);
+
+    let motor = RawFrame::extended(0x0101, &[0; 8]);
+    let imu = RawFrame::standard(0x100, &[0; 8]);
+    let heartbeat = RawFrame::standard(0x200, &[0; 2]);
+    let bms = RawFrame::extended(0x18FF_50E5, &[0; 8]);
+    inject.put(&motor, 20);
+    inject.put(&imu, 10);
+    inject.put(&heartbeat, 5);
+    inject.put(&bms, 3);
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while monitor.total_frames() < 38 && Instant::now() < deadline {
+        monitor.poll(Duration::from_millis(10)).unwrap();
+    }
+    assert_eq!(monitor.total_frames(), 38);
+
+    let report = monitor.load_report();
+    let capacity = BITRATE as f64 * report.elapsed.as_secs_f64();
+    let foreign_bits = 10 * bits(&imu) + 5 * bits(&heartbeat) + 3 * bits(&bms);
+    assert!((report.motor_load * capacity - 20.0 * bits(&motor) as f64).abs() < 1e-6, "{:?}", report);
+    assert!((report.foreign_load * capacity - foreign_bits as f64).abs() < 1e-6, "{:?}", report);
+
+    let counts: HashMap<u32, f64> = report
+        .foreign_rates
+        .iter()
+        .map(|(&id, &rate)| (id, (rate * report.elapsed.as_secs_f64()).round()))
+        .collect();
+    assert_eq!(counts, HashMap::from([(0x100, 10.0), (0x200, 5.0), (0x18FF_50E5, 3.0)]));
+    assert_eq!(report.untracked_foreign_frames, 0);
+    assert_eq!(report.reserved, 0.25);
+}
+
+#[test]
+fn foreign_traffic_fits_within_the_reservation() {
+    let report = |foreign_load: f64| BusLoadReport {
+        elapsed: Duration::from_secs(1),
+        motor_load: 0.6,
+        foreign_load,
+        foreign_rates: HashMap::new(),
+        untracked_foreign_frames: 0,
+        reserved: 0.25,
+    };
+    assert!(report(0.1).foreign_fits());
+    assert!(report(0.25).foreign_fits());
+    assert!(!report(0.3).foreign_fits());
+}
+
+#[test]
+fn reserving_bandwidth_spaces_the_motor_frames() {
+    let bus = CanBus::with_transport(Inject::default(), "inject", BITRATE);
+    assert!(bus.reserve_bandwidth(1.0).is_err());
+    assert!(bus.reserve_bandwidth(-0.1).is_err());
+    bus.reserve_bandwidth(0.5).unwrap();
+    assert_eq!(bus.reserved_bandwidth(), 0.5);
+
+    let frame = RawFrame::extended(0x0101, &[0; 8]).to_frame().unwrap();
+    let start = Instant::now();
+    for _ in 0..21 {
+        bus.send(&frame).unwrap();
+    }
+    // 20 slots of 160 bits at half of 125 kbit/s: 51.2 ms
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_micros(51_200), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(200), "{:?}", elapsed);
+
+    bus.reserve_bandwidth(0.0).unwrap();
+    let start = Instant::now();
+    for _ in 0..21 {
+        bus.send(&frame).unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(20), "{:?}", start.elapsed());
+}
+
+#[test]
+fn throttled_queries_never_hold_up_setpoints() {
+    let bus = CanBus::with_transport(Inject::default(), "inject", BITRATE);
+    assert!(bus.throttle_queries(Duration::from_millis(10), 0.0).is_err());
+    bus.throttle_queries(Duration::from_millis(10), 0.5).unwrap();
+    assert_eq!((bus.query_spacing(), bus.telemetry_scale()), (Duration::from_millis(10), 0.5));
+
+    let query = RawFrame::extended(0x0001 | REPLY_FLAG, &[0; 8]).to_frame().unwrap();
+    let setpoint = RawFrame::extended(stream_id(ANGLE_STREAM_ID, 1), &[0; 8]).to_frame().unwrap();
+    let start = Instant::now();
+    for _ in 0..6 {
+        bus.send(&query).unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(50), "{:?}", start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..50 {
+        bus.send(&setpoint).unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(20), "{:?}", start.elapsed());
+
+    bus.throttle_queries(Duration::ZERO, 1.0).unwrap();
+    let start = Instant::now();
+    for _ in 0..6 {
+        bus.send(&query).unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(20), "{:?}", start.elapsed());
+}
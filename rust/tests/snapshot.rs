@@ -0,0 +1,35 @@
+//! Group snapshots written by one process and read by the next
+
+use livelybot_motor_control::{EnabledMode, GroupSetpoint, GroupSnapshot, JointSnapshot};
+
+#[test]
+fn snapshot_round_trips_through_its_file() {
+    let snapshot = GroupSnapshot {
+        saved_us: 1_700_000_000_000_000,
+        profile: Some("soft".to_string()),
+        joints: vec![
+            JointSnapshot {
+                motor_id: 1,
+                enabled: Some(EnabledMode::Position),
+                setpoint: Some(GroupSetpoint::Angle { angle_deg: 42.5, max_vel_rps: 1.0, max_tqe_nm: 3.0 }),
+                position_deg: Some(42.3),
+            },
+            JointSnapshot {
+                motor_id: 2,
+                enabled: None,
+                setpoint: Some(GroupSetpoint::Velocity { velocity_rps: 0.5, acceleration_rps2: 2.0 }),
+                position_deg: None,
+            },
+        ],
+    };
+
+    let path = std::env::temp_dir().join(format!("group-snapshot-{}.json", std::process::id()));
+    snapshot.save(&path).unwrap();
+    assert!(!path.with_extension("tmp").exists());
+    let loaded = GroupSnapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, snapshot);
+    assert_eq!(loaded.joint(2).and_then(|j| j.enabled), None);
+    assert!(GroupSnapshot::load(&path).is_err());
+}
@@ -0,0 +1,111 @@
+//! Stress test of the seqlock state cache: readers racing a writer must never
+//! observe a torn state, and racing writers must not block each other
+
+use livelybot_motor_control::{MotorState, StateCache};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+const WRITES: u64 = 200_000;
+const READERS: usize = 4;
+
+/// Every field of sample `k` encodes `k`, so mixing two samples is detectable
+fn sample(motor_id: u8, k: u64) -> MotorState {
+    MotorState {
+        motor_id,
+        position_deg: k as f64,
+        velocity_rps: -(k as f64),
+        torque_nm: k as f64 * 0.5,
+        acceleration_rps2: Some(k as f64 * 2.0),
+    }
+}
+
+#[test]
+fn unpublished_motor_has_no_state() {
+    let cache = StateCache::new();
+    cache.publish(&sample(3, 7));
+    assert!(cache.get(4).is_none());
+    assert_eq!(cache.get(3).map(|c| c.state), Some(sample(3, 7)));
+    assert_eq!(cache.snapshot().len(), 1);
+}
+
+#[test]
+fn concurrent_reads_are_never_torn() {
+    let cache = Arc::new(StateCache::new());
+    let done = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let cache = cache.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut last = 0.0;
+                let mut reads = 0u64;
+                while !done.load(Ordering::Acquire) {
+                    let Some(cached) = cache.get(1) else {
+                        continue;
+                    };
+                    let state = cached.state;
+                    let k = state.position_deg;
+                    assert_eq!(state, sample(1, k as u64), "torn read");
+                    assert!(k >= last, "state went backwards: {} after {}", k, last);
+                    last = k;
+                    reads += 1;
+                }
+                reads
+            })
+        })
+        .collect();
+
+    let writer = {
+        let cache = cache.clone();
+        thread::spawn(move || {
+            for k in 1..=WRITES {
+                assert!(cache.publish(&sample(1, k)), "a single writer is never skipped");
+            }
+        })
+    };
+
+    writer.join().unwrap();
+    done.store(true, Ordering::Release);
+    for reader in readers {
+        assert!(reader.join().unwrap() > 0);
+    }
+    assert_eq!(cache.get(1).unwrap().state, sample(1, WRITES));
+}
+
+#[test]
+fn concurrent_writers_never_wait_for_each_other() {
+    let cache = Arc::new(StateCache::new());
+    // Each writer returns how many of its states were stored and the last one
+    let writers: Vec<_> = (0..4u64)
+        .map(|w| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                let (mut stored, mut last) = (0u64, None);
+                for k in 0..WRITES / 4 {
+                    if cache.publish(&sample(2, w * WRITES + k)) {
+                        stored += 1;
+                        last = Some(w * WRITES + k);
+                    }
+                }
+                (stored, last)
+            })
+        })
+        .collect();
+
+    for _ in 0..WRITES / 4 {
+        if let Some(cached) = cache.get(2) {
+            let k = cached.state.position_deg as u64;
+            assert_eq!(cached.state, sample(2, k), "torn read");
+        }
+    }
+    let results: Vec<(u64, Option<u64>)> = writers.into_iter().map(|w| w.join().unwrap()).collect();
+
+    // Busy slots drop states instead of blocking, but most writes land, and
+    // the slot holds the last state one of the writers stored
+    let stored: u64 = results.iter().map(|(stored, _)| stored).sum();
+    assert!(stored > WRITES / 8, "only {} of {} states stored", stored, WRITES);
+    let k = cache.get(2).unwrap().state.position_deg as u64;
+    assert!(results.iter().any(|&(_, last)| last == Some(k)), "{} is no writer's last state", k);
+}
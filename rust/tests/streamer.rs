@@ -0,0 +1,148 @@
+//! Cyclic multi-joint streaming
+
+use livelybot_motor_control::protocol::{stream_target, Register, ANGLE_STREAM_ID, VELOCITY_STREAM_ID};
+use livelybot_motor_control::{
+    degrees_to_position, rps_to_velocity, BusSubscription, CommandFrame, GroupStreamer, JointStreamConfig,
+    LivelyMotorController, MockTransport, RawFrame, Setpoint, SimMotor, StreamerConfig,
+};
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+
+fn setup(motor_ids: impl IntoIterator<Item = u8>) -> LivelyMotorController {
+    let mock = motor_ids
+        .into_iter()
+        .fold(MockTransport::new(), |mock, id| mock.with_motor(id, SimMotor::default()));
+    LivelyMotorController::with_transport("mock", mock)
+}
+
+/// Stream and first field of each stream frame sent since the last call, by motor
+fn drain(sent: &BusSubscription) -> BTreeMap<u8, Vec<(u32, i16)>> {
+    let mut streams: BTreeMap<u8, Vec<(u32, i16)>> = BTreeMap::new();
+    while let Some(frame) = sent.try_recv() {
+        let frame = RawFrame::from_frame(&frame);
+        if let Some((stream, motor_id)) = stream_target(frame.id) {
+            let field = if stream == VELOCITY_STREAM_ID { 2 } else { 0 };
+            let value = i16::from_le_bytes([frame.data[field], frame.data[field + 1]]);
+            streams.entry(motor_id).or_default().push((stream, value));
+        }
+    }
+    streams
+}
+
+/// Kp writes since the last call, by motor
+fn kp_writes(sent: &BusSubscription) -> Vec<(u8, f32)> {
+    std::iter::from_fn(|| sent.try_recv())
+        .map(|f| RawFrame::from_frame(&f))
+        .filter(|f| f.id < 0x80 && f.data[1] == Register::Kp as u8)
+        .map(|f| (f.id as u8, f32::from_le_bytes(f.data[2..6].try_into().unwrap())))
+        .collect()
+}
+
+#[test]
+fn every_member_gets_its_setpoint_each_period() {
+    let controller = setup(1..=3);
+    let config = StreamerConfig {
+        joints: vec![
+            JointStreamConfig::new(1, CommandFrame::AngleStream),
+            JointStreamConfig::new(2, CommandFrame::VelocityStream),
+            JointStreamConfig::new(3, CommandFrame::Mit),
+        ],
+    };
+    let mut streamer = GroupStreamer::new(&controller, &config);
+    let sent = controller.bus().subscribe_sent();
+
+    let mut periods = Vec::new();
+    streamer
+        .run(200.0, &AtomicBool::new(true), |info, streamer| {
+            // What the previous cycle sent
+            if info.cycle > 0 {
+                periods.push(drain(&sent));
+            }
+            let target = if info.cycle < 10 { 10.0 } else { -20.0 };
+            streamer.set(1, Setpoint::Angle(target))?;
+            streamer.set(2, Setpoint::Velocity(target / 10.0))?;
+            streamer.set(3, Setpoint::Angle(target * 2.0))?;
+            Ok(info.cycle < 20)
+        })
+        .unwrap();
+
+    assert_eq!(periods.len(), 20);
+    for (cycle, period) in periods.iter().enumerate() {
+        let target = if cycle < 10 { 10.0 } else { -20.0 };
+        let expected = BTreeMap::from([
+            (1, vec![(ANGLE_STREAM_ID, degrees_to_position(target))]),
+            (2, vec![(VELOCITY_STREAM_ID, rps_to_velocity(target / 10.0))]),
+            (3, vec![(ANGLE_STREAM_ID, degrees_to_position(target * 2.0))]),
+        ]);
+        assert_eq!(*period, expected, "cycle {}", cycle);
+    }
+}
+
+#[test]
+fn slower_joints_are_spread_over_the_cycles() {
+    let controller = setup(1..=5);
+    let mut joints = vec![JointStreamConfig::new(1, CommandFrame::AngleStream)];
+    joints.extend((2..=5).map(|id| JointStreamConfig::new(id, CommandFrame::AngleStream).with_rate(100.0)));
+    let mut streamer = GroupStreamer::new(&controller, &StreamerConfig { joints });
+    streamer.plan(400.0).unwrap();
+    assert_eq!(streamer.joint_rate(1, 400.0), Some(400.0));
+    assert_eq!(streamer.joint_rate(4, 400.0), Some(100.0));
+    for id in 1..=5 {
+        streamer.set(id, Setpoint::Angle(id as f64)).unwrap();
+    }
+
+    let sent = controller.bus().subscribe_sent();
+    let mut per_motor = BTreeMap::new();
+    for cycle in 0..8 {
+        streamer.send_slice(cycle).unwrap();
+        let motors: Vec<u8> = drain(&sent).keys().copied().collect();
+        // The fast joint plus exactly one of the slow ones, never a burst
+        assert_eq!(motors.len(), 2, "cycle {}: {:?}", cycle, motors);
+        assert_eq!(motors[0], 1);
+        per_motor.entry(motors[1]).or_insert_with(Vec::new).push(cycle);
+    }
+    assert_eq!(
+        per_motor,
+        BTreeMap::from([(2, vec![0, 4]), (3, vec![1, 5]), (4, vec![2, 6]), (5, vec![3, 7])])
+    );
+
+    let too_fast = StreamerConfig {
+        joints: vec![JointStreamConfig::new(1, CommandFrame::AngleStream).with_rate(500.0)],
+    };
+    assert!(GroupStreamer::new(&controller, &too_fast).plan(400.0).is_err());
+}
+
+#[test]
+fn setpoints_must_fit_the_frame_and_gains_are_written_once() {
+    let controller = setup([1, 2]);
+    let config = StreamerConfig {
+        joints: vec![
+            JointStreamConfig::new(1, CommandFrame::Mit),
+            JointStreamConfig::new(2, CommandFrame::AngleStream),
+        ],
+    };
+    let mut streamer = GroupStreamer::new(&controller, &config);
+    assert_eq!(streamer.frame(1), Some(CommandFrame::Mit));
+    assert!(streamer.set(2, Setpoint::Velocity(1.0)).is_err());
+    assert!(streamer.set(9, Setpoint::Angle(1.0)).is_err());
+
+    let sent = controller.bus().subscribe_sent();
+    let impedance = Setpoint::Impedance {
+        position_deg: 5.0,
+        kp: 3.0,
+        kd: 0.2,
+    };
+    streamer.set(1, impedance).unwrap();
+    streamer.send_cycle().unwrap();
+    streamer.send_cycle().unwrap();
+    assert_eq!(kp_writes(&sent), [(1, 3.0)]);
+    // Back to the configured gains for plain angles
+    streamer.set(1, Setpoint::Angle(5.0)).unwrap();
+    streamer.send_cycle().unwrap();
+    assert_eq!(kp_writes(&sent), [(1, 1.0)]);
+
+    streamer.set(2, Setpoint::Angle(1.0)).unwrap();
+    streamer.clear(1);
+    streamer.send_cycle().unwrap();
+    assert_eq!(drain(&sent).keys().copied().collect::<Vec<_>>(), [2]);
+}
@@ -0,0 +1,122 @@
+//! Latching positions on a sync trigger
+
+use livelybot_motor_control::{
+    degrees_to_position, CanTransport, LivelyMotorController, MockTransport, RawFrame, SimMotor, SyncLatch,
+    SyncSource,
+};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TRIGGER_ID: u32 = 0x7E0;
+
+/// Simulated motors plus frames of other nodes the test puts on the bus
+#[derive(Clone)]
+struct WithTriggers {
+    mock: MockTransport,
+    pending: Arc<Mutex<VecDeque<RawFrame>>>,
+}
+
+impl WithTriggers {
+    fn put(&self, id: u16) {
+        self.pending.lock().unwrap().push_back(RawFrame::standard(id, &[1]));
+    }
+}
+
+impl CanTransport for WithTriggers {
+    fn send(&self, frame: &RawFrame) -> io::Result<()> {
+        self.mock.send(frame)
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        if let Some(frame) = self.pending.lock().unwrap().pop_front() {
+            return Ok(Some(frame));
+        }
+        self.mock.recv(timeout)
+    }
+}
+
+/// Motors 1-3 holding distinct poses
+fn setup() -> (WithTriggers, LivelyMotorController) {
+    let mock = (1..=3).fold(MockTransport::new(), |mock, id| mock.with_motor(id, SimMotor::default()));
+    let bus = WithTriggers {
+        mock,
+        pending: Arc::default(),
+    };
+    let controller = LivelyMotorController::with_transport("mock", bus.clone());
+    for motor_id in 1..=3 {
+        controller.enable_motor(motor_id).unwrap();
+    }
+    for _ in 0..50 {
+        for motor_id in 1..=3 {
+            let target = 20.0 * motor_id as f64;
+            controller.send_angle_command_to(motor_id, degrees_to_position(target), 0, 0).unwrap();
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    (bus, controller)
+}
+
+#[test]
+fn a_trigger_latches_every_member_at_once() {
+    let (bus, controller) = setup();
+    let mut latch = SyncLatch::new(&controller, SyncSource::CanFrame { id: TRIGGER_ID }, &[1, 2, 3]);
+
+    let sample = thread::scope(|scope| {
+        let triggered = scope.spawn(|| {
+            thread::sleep(Duration::from_millis(30));
+            // Other traffic first: it must not release the latch
+            bus.put(TRIGGER_ID as u16 + 1);
+            thread::sleep(Duration::from_millis(30));
+            let at = Instant::now();
+            bus.put(TRIGGER_ID as u16);
+            at
+        });
+        let sample = latch.wait_for_trigger(Duration::from_secs(2)).unwrap().unwrap();
+        (sample, triggered.join().unwrap())
+    });
+    let (sample, triggered_at) = sample;
+
+    assert_eq!(sample.index, 0);
+    assert_eq!(latch.count(), 1);
+    assert!(sample.timestamp >= triggered_at);
+    assert!(sample.timestamp - triggered_at < Duration::from_millis(50));
+    assert_eq!(sample.positions.iter().map(|p| p.motor_id).collect::<Vec<_>>(), [1, 2, 3]);
+    for position in &sample.positions {
+        let target = 20.0 * position.motor_id as f64;
+        assert!((position.position_deg - target).abs() < 1.0, "{:?}", position);
+    }
+    // Read back to back right after the trigger
+    let delays: Vec<Duration> = sample.positions.iter().map(|p| p.delay).collect();
+    assert!(delays.windows(2).all(|d| d[0] <= d[1]), "{:?}", delays);
+    assert!(delays[2] < Duration::from_millis(100), "{:?}", delays);
+}
+
+#[test]
+fn a_timeout_latches_nobody() {
+    let (bus, controller) = setup();
+    let mut latch = SyncLatch::new(&controller, SyncSource::CanFrame { id: TRIGGER_ID }, &[1, 2, 3]);
+
+    bus.put(TRIGGER_ID as u16 + 1);
+    let start = Instant::now();
+    assert!(latch.wait_for_trigger(Duration::from_millis(100)).unwrap().is_none());
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(latch.count(), 0);
+
+    // The next trigger is still the first one
+    bus.put(TRIGGER_ID as u16);
+    assert_eq!(latch.wait_for_trigger(Duration::from_secs(1)).unwrap().unwrap().index, 0);
+}
+
+#[test]
+fn members_that_do_not_answer_are_left_out() {
+    let (_bus, controller) = setup();
+    let mut latch = SyncLatch::new(&controller, SyncSource::CanFrame { id: TRIGGER_ID }, &[1, 9, 3]);
+
+    let first = latch.latch().unwrap();
+    let second = latch.latch().unwrap();
+    assert_eq!(first.positions.iter().map(|p| p.motor_id).collect::<Vec<_>>(), [1, 3]);
+    assert_eq!((first.index, second.index, latch.count()), (0, 1, 2));
+}
@@ -0,0 +1,85 @@
+//! Command decoding and file format of the telemetry recorder
+
+use livelybot_motor_control::mcap::McapWriter;
+use livelybot_motor_control::protocol::{self, ANGLE_STREAM_ID, VELOCITY_STREAM_ID};
+use livelybot_motor_control::{
+    degrees_to_position, nm_to_torque, rps_to_velocity, EncodingPolicy, LivelyMotorController, MitRanges,
+    MockTransport, SentCommand, SimMotor, TelemetryFormat, TelemetryRecorder, MAGIC_POS,
+};
+use std::net::UdpSocket;
+use std::time::Duration;
+
+#[test]
+fn sent_stream_commands_are_decoded() {
+    let policy = EncodingPolicy::default();
+    let angle = policy.angle_stream(degrees_to_position(90.0), rps_to_velocity(2.0), nm_to_torque(3.0));
+    let command = SentCommand::decode(protocol::stream_id(ANGLE_STREAM_ID, 4), &angle, &MitRanges::default()).unwrap();
+    assert_eq!(command.motor_id, 4);
+    assert_eq!(command.frame, "angle_stream");
+    assert_eq!(command.position_deg, Some(90.0));
+    assert_eq!(command.velocity_rps, Some(2.0));
+    assert_eq!(command.torque_nm, Some(3.0));
+
+    // Pure velocity control carries no position; unaddressed frames go to motor 0
+    let velocity = policy.velocity_stream(MAGIC_POS, rps_to_velocity(-1.5), 0);
+    let command = SentCommand::decode(VELOCITY_STREAM_ID, &velocity, &MitRanges::default()).unwrap();
+    assert_eq!((command.motor_id, command.position_deg, command.velocity_rps), (0, None, Some(-1.5)));
+
+    // Register writes are not stream commands
+    assert!(SentCommand::decode(4, &[0x01, 0x00, 0x0A, 0x50, 0x50, 0x50, 0x50, 0x50], &MitRanges::default()).is_none());
+
+    assert_eq!(TelemetryFormat::from_path("run.MCAP").unwrap(), TelemetryFormat::Mcap);
+    assert!(TelemetryFormat::from_path("run.txt").is_err());
+}
+
+#[test]
+fn mcap_records_are_framed() {
+    let mut writer = McapWriter::new(Vec::new(), "").unwrap();
+    let schema = writer.add_schema("s", "jsonschema", b"{}").unwrap();
+    let channel = writer.add_channel(schema, "/motor/1/state", "json").unwrap();
+    writer.write_message(channel, 1_000, br#"{"position_deg":1.0}"#).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    assert_eq!(&bytes[..8], b"\x89MCAP0\r\n");
+    assert_eq!(&bytes[bytes.len() - 8..], b"\x89MCAP0\r\n");
+
+    // Walk the records: header, schema, channel, message, data end, footer
+    let mut opcodes = Vec::new();
+    let mut at = 8;
+    while at < bytes.len() - 8 {
+        let len = u64::from_le_bytes(bytes[at + 1..at + 9].try_into().unwrap()) as usize;
+        opcodes.push(bytes[at]);
+        at += 9 + len;
+    }
+    assert_eq!(at, bytes.len() - 8);
+    assert_eq!(opcodes, vec![0x01, 0x03, 0x04, 0x05, 0x0F, 0x02]);
+}
+
+#[test]
+fn udp_mirror_forwards_commands_and_states() {
+    let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+    listener.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let mock = MockTransport::new().with_motor(1, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock);
+    let mut mirror = TelemetryRecorder::mirror_udp(&controller, listener.local_addr().unwrap(), &[1]).unwrap();
+
+    controller.send_angle_command_to(1, degrees_to_position(90.0), rps_to_velocity(1.0), nm_to_torque(2.0)).unwrap();
+    controller.read_motor_state(1).unwrap();
+
+    let mut topics = Vec::new();
+    let mut buf = [0u8; 2048];
+    while topics.len() < 2 {
+        let len = listener.recv(&mut buf).unwrap();
+        let datagram: serde_json::Value = serde_json::from_slice(&buf[..len]).unwrap();
+        assert_eq!(datagram["seq"], topics.len() as u64);
+        assert!(datagram["timestamp"].as_f64().unwrap() > 0.0);
+        if datagram["topic"] == "/motor/1/command" {
+            assert_eq!(datagram["message"]["position_deg"], 90.0);
+        }
+        topics.push(datagram["topic"].as_str().unwrap().to_string());
+    }
+    topics.sort();
+    assert_eq!(topics, vec!["/motor/1/command", "/motor/1/state"]);
+    mirror.stop().unwrap();
+    assert_eq!(mirror.datagrams_dropped(), 0);
+}
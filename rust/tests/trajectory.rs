@@ -0,0 +1,80 @@
+//! JointTrajectory import and sampling
+
+use livelybot_motor_control::{JointMap, JointMapping, Trajectory};
+use std::time::Duration;
+
+fn mapping() -> JointMapping {
+    JointMapping {
+        joints: vec![
+            JointMap {
+                name: "knee".to_string(),
+                motor_id: 2,
+                reversed: true,
+                offset_deg: 0.0,
+            },
+            JointMap {
+                name: "hip".to_string(),
+                motor_id: 1,
+                reversed: false,
+                offset_deg: 10.0,
+            },
+        ],
+    }
+}
+
+const HALF_PI: f64 = std::f64::consts::FRAC_PI_2;
+
+#[test]
+fn json_points_map_to_motor_degrees() {
+    let json = format!(
+        r#"{{"joint_names": ["hip", "knee"], "points": [
+            {{"positions": [0.0, 0.0], "time_from_start": {{"sec": 0, "nanosec": 0}}}},
+            {{"positions": [{0}, {0}], "time_from_start": {{"secs": 1, "nsecs": 500000000}}}}
+        ]}}"#,
+        HALF_PI
+    );
+    let trajectory = Trajectory::<f64>::from_json(&json, &mapping()).unwrap();
+
+    // Motor order follows the mapping, not the export
+    assert_eq!(trajectory.motor_ids, vec![2, 1]);
+    assert_eq!(trajectory.waypoints[0].positions_deg, vec![0.0, 10.0]);
+    assert_eq!(trajectory.waypoints[1].positions_deg, vec![-90.0, 100.0]);
+    assert_eq!(trajectory.duration(), Duration::from_millis(1500));
+}
+
+#[test]
+fn csv_points_are_interpolated() {
+    let csv = format!("time_from_start,knee.position,hip\n0.0,0,0\n2.0,{0},{0}\n", -HALF_PI);
+    let trajectory = Trajectory::<f64>::from_csv(&csv, &mapping()).unwrap();
+
+    assert_eq!(trajectory.sample(Duration::ZERO).unwrap(), vec![0.0, 10.0]);
+    let middle = trajectory.sample(Duration::from_secs(1)).unwrap();
+    assert!((middle[0] - 45.0).abs() < 1e-9 && (middle[1] + 35.0).abs() < 1e-9, "{:?}", middle);
+    assert_eq!(trajectory.sample(Duration::from_secs(5)).unwrap(), vec![90.0, -80.0]);
+    assert_eq!(Trajectory::<f64>::from_csv("time_from_start,knee,hip\n", &mapping()).unwrap().sample(Duration::ZERO), None);
+}
+
+fn csv_error(csv: &str) -> String {
+    Trajectory::<f64>::from_csv(csv, &mapping()).unwrap_err().to_string()
+}
+
+#[test]
+fn malformed_csv_is_rejected() {
+    assert!(csv_error("").contains("empty trajectory CSV"));
+    assert!(csv_error("t,knee,hip\n0,0,0\n").contains("time_from_start"));
+    assert!(csv_error("time_from_start,knee\n0,0\n").contains("joint hip not found"));
+    assert!(csv_error("time_from_start,knee,hip\n0,0,abc\n").contains("CSV row 1"));
+    assert!(csv_error("time_from_start,knee,hip\n0,0,0\n1,0\n").contains("point 1 is missing joint hip"));
+    assert!(csv_error("time_from_start,knee,hip\n1,0,0\n0.5,0,0\n").contains("not increasing"));
+}
+
+#[test]
+fn malformed_json_is_rejected() {
+    let json_error = |json: &str| Trajectory::<f64>::from_json(json, &mapping()).unwrap_err().to_string();
+    assert!(json_error(r#"{"points": []}"#).contains("no joint_names"));
+    assert!(json_error(r#"{"joint_names": ["hip", "knee"]}"#).contains("no points"));
+    assert!(json_error(r#"{"joint_names": ["hip", "knee"], "points": [{}]}"#).contains("point 0 has no positions"));
+    assert!(json_error(r#"{"joint_names": ["hip", "knee"], "points": [{"positions": [0, "x"]}]}"#)
+        .contains("non-numeric"));
+    assert!(json_error("not json").contains("expected"));
+}
@@ -0,0 +1,65 @@
+//! Feedback parsers given payloads shorter than 8 bytes: every truncation is
+//! reported as a `TruncatedFrame` error (or no value), never mis-sliced
+
+use livelybot_motor_control::query;
+use livelybot_motor_control::{BmsFormat, EncodingPolicy, ErrorLogEntry, Identity, TemperatureSnapshot, TruncatedFrame};
+use socketcan::{CanFrame, EmbeddedFrame, ExtendedId};
+
+fn truncated(result: anyhow::Result<impl std::fmt::Debug>) -> TruncatedFrame {
+    let error = result.expect_err("truncated payload was accepted");
+    *error.downcast_ref::<TruncatedFrame>().unwrap_or_else(|| panic!("not a truncation: {:#}", error))
+}
+
+#[test]
+fn register_replies_need_all_their_values() {
+    let policy = EncodingPolicy::default();
+    // int16 x3 needs 2 header bytes + 6 value bytes
+    let full = [0x27, 0x01, 0x10, 0x00, 0x20, 0x00, 0x30, 0x00];
+    assert_eq!(policy.parse_reply(&full).unwrap().int(2), Some(0x30));
+    for len in 1..full.len() {
+        let error = truncated(policy.parse_reply(&full[..len]));
+        assert_eq!((error.len, error.needed), (len, 8));
+    }
+
+    // A single float fits in 6 bytes
+    let float = [0x2D, 0x23, 0x00, 0x00, 0xC0, 0x3F];
+    assert_eq!(policy.parse_reply(&float).unwrap().float(0), Some(1.5));
+    assert_eq!(truncated(policy.parse_reply(&float[..5])).needed, 6);
+}
+
+#[test]
+fn other_frames_are_not_reported_as_truncated() {
+    let policy = EncodingPolicy::default();
+    for data in [&[][..], &[0x11, 0x00], &[0x51, b'H']] {
+        let error = policy.parse_reply(data).unwrap_err();
+        assert!(!error.is::<TruncatedFrame>(), "{:#}", error);
+    }
+}
+
+#[test]
+fn query_replies_need_their_values() {
+    assert_eq!(truncated(Identity::parse(&[0x51, b'H'])).needed, 4);
+    assert_eq!(Identity::parse(&[0x51, b'H', b'T', b'5']).unwrap().hardware_version, "");
+
+    assert_eq!(truncated(query::parse_serial_number(&[0x51, 0x01, 0x01, 0x02])).needed, 6);
+    assert_eq!(truncated(query::parse_uptime(&[0x51, 0x03, 0x10])).needed, 6);
+    assert_eq!(truncated(TemperatureSnapshot::parse(&[0x51, 0x04, 0xC2, 0x01, 0x5E, 0x01])).needed, 8);
+
+    // An empty slot is complete after its code; an entry needs its timestamp
+    assert_eq!(ErrorLogEntry::parse(&[0x51, 0x02, 0x00, 0x00]).unwrap(), None);
+    assert_eq!(truncated(ErrorLogEntry::parse(&[0x51, 0x02, 0x00])).needed, 4);
+    assert_eq!(truncated(ErrorLogEntry::parse(&[0x51, 0x02, 0x00, 0x07, 0x2C])).needed, 8);
+}
+
+#[test]
+fn short_bms_frames_decode_to_nothing() {
+    let format = BmsFormat::daly();
+    let id = ExtendedId::new(format.id).unwrap();
+    let full = CanFrame::new(id, &[0x01, 0x04, 0x00, 0x00, 0x75, 0x44, 0x03, 0x20]).unwrap();
+    let battery = format.decode(&full).unwrap();
+    assert!((battery.voltage_v - 26.0).abs() < 1e-9);
+    assert_eq!(battery.soc_pct, Some(80.0));
+
+    let short = CanFrame::new(id, &[0x01, 0x04, 0x00, 0x00, 0x75, 0x44]).unwrap();
+    assert!(format.decode(&short).is_none());
+}
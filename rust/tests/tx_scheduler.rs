@@ -0,0 +1,114 @@
+//! Periodic raw frames sent through the shared bus
+
+use livelybot_motor_control::{CanBus, CanTransport, RawFrame, TxScheduler};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A transport that timestamps what it is given and never receives;
+/// frames with ID 0x7FF fail to send
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<(Instant, RawFrame)>>>);
+
+impl Capture {
+    fn sent(&self, id: u32) -> Vec<(Instant, Vec<u8>)> {
+        let sent = self.0.lock().unwrap();
+        sent.iter().filter(|(_, f)| f.id == id).map(|(t, f)| (*t, f.data.clone())).collect()
+    }
+}
+
+impl CanTransport for Capture {
+    fn send(&self, frame: &RawFrame) -> io::Result<()> {
+        if frame.id == 0x7FF {
+            return Err(io::Error::other("no buffer space"));
+        }
+        self.0.lock().unwrap().push((Instant::now(), frame.clone()));
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<RawFrame>> {
+        thread::sleep(timeout);
+        Ok(None)
+    }
+}
+
+#[test]
+fn frames_keep_their_periods_and_go_out_in_schedule_order() {
+    let capture = Capture::default();
+    let bus = CanBus::with_transport(capture.clone(), "capture", 1_000_000);
+    let scheduler = TxScheduler::new(&bus);
+    scheduler.schedule(&RawFrame::standard(0x300, &[1]), Duration::from_millis(20)).unwrap();
+    scheduler.schedule(&RawFrame::standard(0x301, &[2]), Duration::from_millis(50)).unwrap();
+    thread::sleep(Duration::from_millis(215));
+    drop(scheduler);
+
+    // Both are due at once first: the one scheduled first goes first
+    let order: Vec<u32> = capture.0.lock().unwrap().iter().take(2).map(|(_, f)| f.id).collect();
+    assert_eq!(order, [0x300, 0x301]);
+
+    let fast = capture.sent(0x300);
+    let slow = capture.sent(0x301);
+    assert!((10..=12).contains(&fast.len()), "{}", fast.len());
+    assert!((4..=6).contains(&slow.len()), "{}", slow.len());
+    // The cadence holds from the first transmission, without drift
+    let span = fast.last().unwrap().0 - fast[0].0;
+    let periods = (fast.len() - 1) as u32;
+    assert!(span >= Duration::from_millis(20) * periods - Duration::from_millis(2), "{:?}", span);
+    assert!(span <= Duration::from_millis(20) * periods + Duration::from_millis(15), "{:?}", span);
+}
+
+#[test]
+fn frames_obey_the_bus_bandwidth_reservation() {
+    let capture = Capture::default();
+    // 10 kbit/s with half reserved: an 8-byte standard frame (135 bits) takes a 27 ms slot
+    let bus = CanBus::with_transport(capture.clone(), "capture", 10_000);
+    bus.reserve_bandwidth(0.5).unwrap();
+    let scheduler = TxScheduler::new(&bus);
+    for id in [0x310, 0x311, 0x312] {
+        scheduler.schedule(&RawFrame::standard(id, &[0; 8]), Duration::from_secs(10)).unwrap();
+    }
+    thread::sleep(Duration::from_millis(120));
+    scheduler.send_now(&RawFrame::standard(0x313, &[0; 8])).unwrap();
+    drop(scheduler);
+
+    let sent = capture.0.lock().unwrap().clone();
+    assert_eq!(sent.iter().map(|(_, f)| f.id).collect::<Vec<_>>(), [0x310, 0x311, 0x312, 0x313]);
+    for pair in sent.windows(2) {
+        let gap = pair[1].0 - pair[0].0;
+        assert!(gap >= Duration::from_millis(26), "{:?}", gap);
+    }
+}
+
+#[test]
+fn frames_can_be_updated_and_cancelled() {
+    let capture = Capture::default();
+    let bus = CanBus::with_transport(capture.clone(), "capture", 1_000_000);
+    let scheduler = TxScheduler::new(&bus);
+    assert!(scheduler.schedule(&RawFrame::standard(0x320, &[0]), Duration::ZERO).is_err());
+
+    let id = scheduler.schedule(&RawFrame::standard(0x320, &[1, 1]), Duration::from_millis(10)).unwrap();
+    let failing = scheduler.schedule(&RawFrame::standard(0x7FF, &[]), Duration::from_millis(10)).unwrap();
+    thread::sleep(Duration::from_millis(35));
+    scheduler.update(id, &[2, 2, 2]).unwrap();
+    thread::sleep(Duration::from_millis(35));
+    assert!(scheduler.cancel(id));
+    assert!(scheduler.cancel(failing));
+    assert!(!scheduler.cancel(id));
+    assert!(scheduler.update(id, &[3]).is_err());
+    let (errors, last_error) = scheduler.errors();
+    // Let a transmission that was already due when cancelling finish
+    thread::sleep(Duration::from_millis(5));
+    let sent = capture.sent(0x320);
+    thread::sleep(Duration::from_millis(30));
+
+    let payloads: Vec<&[u8]> = sent.iter().map(|(_, d)| d.as_slice()).collect();
+    let updated = payloads.iter().position(|d| *d == [2, 2, 2]).unwrap();
+    assert!(updated >= 3, "{:?}", payloads);
+    assert!(payloads[..updated].iter().all(|d| *d == [1, 1]), "{:?}", payloads);
+    assert!(payloads[updated..].iter().all(|d| *d == [2, 2, 2]), "{:?}", payloads);
+    // Nothing after the cancel
+    assert_eq!(capture.sent(0x320).len(), sent.len());
+    assert!(errors >= 6, "{}", errors);
+    assert!(last_error.unwrap().contains("failed to send frame 0x7FF"));
+}
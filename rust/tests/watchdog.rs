@@ -0,0 +1,108 @@
+//! Control-loss watchdog: trips, feeding, reset and stop frames
+
+use livelybot_motor_control::protocol::Register;
+use livelybot_motor_control::{
+    rps2_to_acceleration, EventBus, EventKind, LivelyMotorController, MockTransport, RawFrame, SimMotor, StopMode,
+    Watchdog, MAGIC_POS,
+};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_millis(40);
+
+fn setup() -> LivelyMotorController {
+    let mock = MockTransport::new().with_motor(1, SimMotor::default()).with_motor(2, SimMotor::default());
+    LivelyMotorController::with_transport("mock", mock)
+}
+
+/// Frames sent until the bus is quiet for `quiet`, with the time since `start`
+fn frames_until_quiet(controller: &LivelyMotorController, quiet: Duration) -> Vec<(Duration, RawFrame)> {
+    let sent = controller.bus().subscribe_sent();
+    let start = Instant::now();
+    let mut frames = Vec::new();
+    while let Some(frame) = sent.recv_timeout(quiet).unwrap() {
+        frames.push((start.elapsed(), RawFrame::from_frame(&frame)));
+    }
+    frames
+}
+
+#[test]
+fn missed_feed_trips_until_reset() {
+    let controller = setup();
+    let events = Arc::new(EventBus::new());
+    let received = events.subscribe();
+    let watchdog = Watchdog::start_with_events(&controller, TIMEOUT, Some(Arc::clone(&events))).unwrap();
+    watchdog.register(1);
+    watchdog.register(2);
+
+    // Fed well within the timeout it stays armed
+    let sent = controller.bus().subscribe_sent();
+    for _ in 0..10 {
+        thread::sleep(TIMEOUT / 4);
+        watchdog.feed();
+    }
+    assert!(!watchdog.is_tripped());
+    assert!(watchdog.check().is_ok());
+    assert!(sent.try_recv().is_none());
+
+    thread::sleep(TIMEOUT * 3);
+    assert!(watchdog.is_tripped());
+    assert_eq!(watchdog.trips(), 1);
+    assert_eq!(watchdog.send_errors(), 0);
+    assert_eq!(watchdog.last_error(), None);
+    assert!(watchdog.check().unwrap_err().to_string().contains("watchdog tripped"));
+    let event = received.try_recv().unwrap();
+    assert!(matches!(event.kind, EventKind::WatchdogTripped { ref motor_ids } if motor_ids == &[1, 2]));
+
+    // Feeding a tripped watchdog does not re-arm it
+    watchdog.feed();
+    assert!(watchdog.is_tripped());
+    watchdog.reset();
+    assert!(!watchdog.is_tripped());
+    assert!(watchdog.check().is_ok());
+    thread::sleep(TIMEOUT / 2);
+    watchdog.feed();
+    assert!(!watchdog.is_tripped());
+    assert_eq!(watchdog.trips(), 1);
+}
+
+/// Stop and disable frames of motor 1 after a trip in `mode`, with the time
+/// from the stop frame to the disable
+fn trip(mode: StopMode) -> (RawFrame, RawFrame, Duration) {
+    let controller = setup();
+    let watchdog = Watchdog::start(&controller, TIMEOUT).unwrap().with_stop_mode(mode);
+    watchdog.register(1);
+    assert_eq!(watchdog.stop_mode(), mode);
+
+    let frames = frames_until_quiet(&controller, Duration::from_millis(400));
+    assert!(watchdog.is_tripped());
+    let [(stopped_at, stop), (disabled_at, disable)] = <[_; 2]>::try_from(frames).unwrap();
+
+    let disable_frame = controller.encoding().write_int8(Register::Mode, 0x00);
+    assert_eq!((disable.id, disable.data.as_slice()), (1, &disable_frame[..]));
+    (stop, disable, disabled_at - stopped_at)
+}
+
+#[test]
+fn zero_torque_stops_and_disables_at_once() {
+    let (stop, _, braking) = trip(StopMode::ZeroTorque);
+    // Zero velocity at the default deceleration of 20 r/s², then the disable
+    let zero = setup().encoding().velocity_stream(MAGIC_POS, 0, rps2_to_acceleration(20.0));
+    assert_eq!((stop.id, stop.data), (0x1AD, zero.to_vec()));
+    assert!(braking < Duration::from_millis(30), "{:?}", braking);
+}
+
+#[test]
+fn braking_modes_disable_once_at_rest() {
+    for mode in [StopMode::MaxBraking, StopMode::Ramped { ramp_ms: 150 }] {
+        let (stop, _, braking) = trip(mode);
+        let expected = mode.braking(None).unwrap();
+        let zero = setup()
+            .encoding()
+            .velocity_stream(MAGIC_POS, 0, rps2_to_acceleration(expected.acceleration_rps2));
+        assert_eq!((stop.id, stop.data), (0x1AD, zero.to_vec()), "{:?}", mode);
+        assert!(braking + Duration::from_millis(5) >= expected.duration, "{:?}: {:?}", mode, braking);
+        assert!(braking < expected.duration + TIMEOUT * 2, "{:?}: {:?}", mode, braking);
+    }
+}
@@ -0,0 +1,94 @@
+//! Wheel odometry and differential-drive kinematics
+
+use livelybot_motor_control::wheel::POSITION_WRAP_DEG;
+use livelybot_motor_control::{
+    AngleUnwrapper, BasePose, DifferentialDrive, LivelyMotorController, MockTransport, SimMotor, Wheel,
+};
+use std::f64::consts::PI;
+use std::thread;
+use std::time::Duration;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn readings_are_unwrapped_across_the_register_wrap() {
+    let half = POSITION_WRAP_DEG / 2.0;
+    let mut unwrapper = AngleUnwrapper::new();
+    assert_eq!(unwrapper.update(half - 100.0), half - 100.0);
+    // Forward over the wrap: the reading jumps to the negative end
+    assert!(close(unwrapper.update(-half + 50.0), half + 50.0));
+    assert!(close(unwrapper.update(-half + 150.0), half + 150.0));
+    // Backwards over the wrap again
+    assert!(close(unwrapper.update(half - 10.0), half - 10.0));
+    assert!(close(unwrapper.angle_deg(), half - 10.0));
+}
+
+#[test]
+fn wheel_speeds_and_twist_are_inverse() {
+    let drive = DifferentialDrive::new(0.05, 0.4);
+    let (left, right) = drive.wheel_speeds(0.5, 1.0);
+    assert!(left < right, "turning left speeds up the right wheel");
+    let (v, omega) = drive.body_twist(left, right);
+    assert!(close(v, 0.5) && close(omega, 1.0), "{} {}", v, omega);
+
+    // Straight at one wheel circumference per second is 1 r/s
+    let (left, right) = drive.wheel_speeds(2.0 * PI * 0.05, 0.0);
+    assert!(close(left, 1.0) && close(right, 1.0));
+}
+
+#[test]
+fn odometry_integrates_straight_lines_and_turns() {
+    let mut drive = DifferentialDrive::new(0.05, 0.4);
+    assert_eq!(drive.update(10.0, 10.0), BasePose::default(), "first update sets the reference");
+    let pose = drive.update(11.0, 11.0);
+    assert!(close(pose.x_m, 1.0) && close(pose.y_m, 0.0) && close(pose.theta_rad, 0.0));
+
+    // Spin in place by a quarter turn: the wheels roll in opposite directions
+    let arc = PI / 2.0 * 0.2;
+    let pose = drive.update(11.0 - arc, 11.0 + arc);
+    assert!(close(pose.x_m, 1.0) && close(pose.y_m, 0.0) && close(pose.theta_rad, PI / 2.0), "{:?}", pose);
+    let pose = drive.update(12.0 - arc, 12.0 + arc);
+    assert!(close(pose.x_m, 1.0) && close(pose.y_m, 1.0), "{:?}", pose);
+
+    // Three more quarter turns wrap the heading back to zero
+    let pose = drive.update(12.0 - 4.0 * arc, 12.0 + 4.0 * arc);
+    assert!(close(pose.theta_rad, 0.0), "{:?}", pose);
+
+    drive.reset(BasePose::default());
+    assert_eq!(drive.update(0.0, 5.0), BasePose::default());
+}
+
+#[test]
+fn wheel_tracks_distance_on_a_simulated_motor() {
+    let mock = MockTransport::new()
+        .with_motor(1, SimMotor::default())
+        .with_motor(2, SimMotor::default());
+    let controller = LivelyMotorController::with_transport("mock", mock.clone());
+    let mut left = Wheel::new(&controller, 1, 0.05);
+    let mut right = Wheel::new(&controller, 2, 0.05).reversed(true);
+    left.enable().unwrap();
+    right.enable().unwrap();
+    assert_eq!(left.update().unwrap().distance_m, 0.0);
+    assert_eq!(right.update().unwrap().distance_m, 0.0);
+    assert!(left.last_update().is_some());
+
+    // Both forward; the mirrored motor turns the other way
+    left.set_speed(0.2).unwrap();
+    right.set_speed(0.2).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    assert!(mock.state(2).unwrap().velocity_rps < 0.0);
+
+    let (l, r) = (left.update().unwrap(), right.update().unwrap());
+    for odometry in [l, r] {
+        assert!(odometry.distance_m > 0.01 && odometry.distance_m < 0.1, "{:?}", odometry);
+        assert!(odometry.speed_mps > 0.1, "{:?}", odometry);
+        assert!(close(odometry.distance_m, odometry.angle_deg.to_radians() * 0.05));
+    }
+
+    left.reset_odometry();
+    assert_eq!(left.update().unwrap().distance_m, 0.0);
+    left.disable().unwrap();
+    right.disable().unwrap();
+}